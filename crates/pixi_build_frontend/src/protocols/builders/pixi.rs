@@ -276,9 +276,34 @@ fn find_pixi_manifest(source_dir: &Path) -> Option<PathBuf> {
 
 #[cfg(test)]
 mod tests {
-    use std::path::PathBuf;
+    use std::path::{Path, PathBuf};
+
+    use pixi_manifest::Manifest;
+    use rattler_conda_types::{ChannelConfig, NamedChannelOrUrl};
 
     use super::ProtocolBuilder;
+    use crate::tool::ToolSpec;
+
+    /// Returns the tool spec of the package described by the given manifest.
+    fn tool_spec(package_dir: &Path, manifest: &str) -> crate::IsolatedToolSpec {
+        let manifest_path = package_dir.join("pixi.toml");
+        let manifest = Manifest::from_str(&manifest_path, manifest).unwrap();
+        let builder = ProtocolBuilder::new(
+            package_dir.to_path_buf(),
+            manifest_path,
+            manifest.workspace,
+            manifest.package.unwrap(),
+        );
+        match builder
+            .get_tool_spec(&ChannelConfig::default_with_root_dir(
+                package_dir.to_path_buf(),
+            ))
+            .unwrap()
+        {
+            ToolSpec::Isolated(spec) => spec,
+            ToolSpec::System(_) => panic!("expected an isolated tool spec"),
+        }
+    }
 
     #[test]
     pub fn discover_basic_pixi_manifest() {
@@ -287,4 +312,60 @@ mod tests {
             .unwrap_or_else(|| panic!("No manifest found at {}", manifest_dir.display()));
         ProtocolBuilder::discover(&manifest_path).unwrap();
     }
+
+    #[test]
+    pub fn packages_resolve_their_own_backend() {
+        let workspace = r#"
+        [workspace]
+        channels = ["conda-forge"]
+        platforms = []
+        preview = ["pixi-build"]
+        "#;
+
+        let python = tool_spec(
+            Path::new("/workspace/python"),
+            &format!(
+                r#"{workspace}
+        [package]
+        name = "python-package"
+        version = "0.1.0"
+
+        [package.build]
+        backend = {{ name = "pixi-build-python", version = "*" }}
+        channels = ["https://prefix.dev/pixi-build-backends", "conda-forge"]
+        "#
+            ),
+        );
+        let cmake = tool_spec(
+            Path::new("/workspace/cmake"),
+            &format!(
+                r#"{workspace}
+        [package]
+        name = "cmake-package"
+        version = "0.1.0"
+
+        [package.build]
+        backend = {{ name = "pixi-build-cmake", version = "0.1.*" }}
+        "#
+            ),
+        );
+
+        assert_eq!(python.command, "pixi-build-python");
+        assert_eq!(
+            python.channels,
+            vec![
+                NamedChannelOrUrl::Url("https://prefix.dev/pixi-build-backends".parse().unwrap()),
+                NamedChannelOrUrl::Name("conda-forge".to_string()),
+            ]
+        );
+
+        // Without channels in the build section, the channels of the workspace
+        // are used.
+        assert_eq!(cmake.command, "pixi-build-cmake");
+        assert_eq!(
+            cmake.channels,
+            vec![NamedChannelOrUrl::Name("conda-forge".to_string())]
+        );
+        assert_ne!(python, cmake);
+    }
 }
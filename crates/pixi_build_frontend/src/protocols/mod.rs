@@ -32,7 +32,7 @@ use pixi_build_types::{
         conda_metadata::{CondaMetadataParams, CondaMetadataResult},
         initialize::{InitializeParams, InitializeResult},
     },
-    BackendCapabilities, FrontendCapabilities,
+    BackendCapabilities, BuildProgress, FrontendCapabilities,
 };
 use stderr::{stderr_null, stderr_stream};
 use thiserror::Error;
@@ -226,7 +226,9 @@ impl JsonRPCBuildProtocol {
             .request(
                 procedures::negotiate_capabilities::METHOD_NAME,
                 RpcParams::from(NegotiateCapabilitiesParams {
-                    capabilities: FrontendCapabilities {},
+                    capabilities: FrontendCapabilities {
+                        build_progress: Some(true),
+                    },
                 }),
             )
             .await
@@ -368,7 +370,10 @@ impl JsonRPCBuildProtocol {
             let send_stderr = async {
                 let mut receiver = receiver;
                 while let Some(line) = receiver.recv().await {
-                    reporter.on_build_output(operation, line);
+                    match BuildProgress::from_line(&line) {
+                        Some(progress) => reporter.on_build_progress(operation, progress),
+                        None => reporter.on_build_output(operation, line),
+                    }
                 }
             };
 
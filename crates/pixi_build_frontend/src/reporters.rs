@@ -1,5 +1,7 @@
 use std::sync::Arc;
 
+use pixi_build_types::BuildProgress;
+
 /// Reporter trait for reporting the progress of metadata operations.
 pub trait CondaMetadataReporter: Send + Sync {
     /// Reports the start of the get_conda_metadata operation.
@@ -37,6 +39,12 @@ pub trait CondaBuildReporter: Send + Sync {
 
     /// Reports output from the build process.
     fn on_build_output(&self, operation: usize, line: String);
+
+    /// Reports structured progress from the build process. By default the
+    /// progress is reported as a line of output.
+    fn on_build_progress(&self, operation: usize, progress: BuildProgress) {
+        self.on_build_output(operation, progress.to_string());
+    }
 }
 
 /// A no-op implementation of the CondaBuildReporter trait.
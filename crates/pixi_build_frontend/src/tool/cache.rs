@@ -8,7 +8,7 @@ use std::{
 use dashmap::{DashMap, Entry};
 use itertools::Itertools;
 use miette::{Context, IntoDiagnostic};
-use rattler_conda_types::{ChannelConfig, Matches, Platform, PrefixRecord};
+use rattler_conda_types::{Channel, ChannelConfig, Matches, Platform, PrefixRecord};
 use rattler_shell::{
     activation::{ActivationVariables, Activator},
     shell::ShellEnum,
@@ -90,6 +90,18 @@ pub(crate) async fn find_spec_records(
     Ok(None)
 }
 
+/// Returns true if the record was installed from one of the given channel
+/// urls.
+fn is_from_channels(record: &PrefixRecord, channel_urls: &[String]) -> bool {
+    let Some(channel) = record.repodata_record.channel.as_deref() else {
+        return false;
+    };
+    let channel = channel.trim_end_matches('/');
+    channel_urls
+        .iter()
+        .any(|url| url.trim_end_matches('/') == channel)
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum ToolCacheError {
     #[error("could not resolve '{path}', {1}", path = .0.as_display())]
@@ -179,7 +191,10 @@ impl ToolCache {
         //
 
         // Let's start by finding already existing matchspec
-        let tool = match self.get_file_system_cached(&spec, cache_dir).await? {
+        let tool = match self
+            .get_file_system_cached(&spec, cache_dir, channel_config)
+            .await?
+        {
             // Let's start by installing tool. If an error occurs we immediately return
             // the error. This will drop the sender and all other waiting tasks will
             // receive an error.
@@ -214,10 +229,16 @@ impl ToolCache {
 
     /// Try to find already existing environment with the same tool spec
     /// in the cache directory.
+    ///
+    /// Only environments of which all the matching records come from the
+    /// channels of the spec are reused. Packages of the same workspace can use
+    /// the same backend from different channels, and should not share their
+    /// environments.
     pub async fn get_file_system_cached(
         &self,
         spec: &IsolatedToolSpec,
         cache_dir: &Path,
+        channel_config: &ChannelConfig,
     ) -> miette::Result<Option<IsolatedTool>> {
         // check if the cache directory exists
         if !cache_dir.exists() {
@@ -235,6 +256,15 @@ impl ToolCache {
             return Ok(None);
         }
 
+        let channel_urls = spec
+            .channels
+            .iter()
+            .cloned()
+            .map(|channel| channel.into_channel(channel_config))
+            .map_ok(|channel: Channel| channel.base_url.to_string())
+            .collect::<Result<Vec<_>, _>>()
+            .into_diagnostic()?;
+
         // verify if we have a similar environment that match our matchspec
         // we need to load all prefix record from all folders in the cache
         // load all package records
@@ -262,10 +292,10 @@ impl ToolCache {
         // Find the first set of records where all specs in the manifest are present
         let matching_record = records_of_records.iter().find(|records| {
             spec.specs.iter().all(|spec| {
-                records
-                    .1
-                    .iter()
-                    .any(|record| spec.matches(&record.repodata_record.package_record))
+                records.1.iter().any(|record| {
+                    spec.matches(&record.repodata_record.package_record)
+                        && is_from_channels(record, &channel_urls)
+                })
             })
         });
 
@@ -628,11 +658,17 @@ mod tests {
         let tool_spec = IsolatedToolSpec {
             specs: vec![MatchSpec::from_str("bat", ParseStrictness::Strict).unwrap()],
             command: "bat".into(),
-            channels: config.default_channels.clone(),
+            channels: vec![NamedChannelOrUrl::Url(
+                "https://conda.anaconda.org/conda-forge/".parse().unwrap(),
+            )],
         };
 
         let tool = tool_cache
-            .get_file_system_cached(&tool_spec, &conda_meta_builder.tmp_dir)
+            .get_file_system_cached(
+                &tool_spec,
+                &conda_meta_builder.tmp_dir,
+                config.global_channel_config(),
+            )
             .await
             .unwrap()
             .unwrap();
@@ -667,7 +703,43 @@ mod tests {
         };
 
         let tool = tool_cache
-            .get_file_system_cached(&tool_spec, &conda_meta_builder.tmp_dir)
+            .get_file_system_cached(
+                &tool_spec,
+                &conda_meta_builder.tmp_dir,
+                config.global_channel_config(),
+            )
+            .await
+            .unwrap();
+
+        assert!(tool.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_skip_other_channels_from_filesystem() {
+        // A package that uses the same backend from another channel should not
+        // reuse the environment.
+        let config = Config::for_tests();
+
+        let tool_cache = ToolCache::new();
+
+        let conda_meta_builder = CondaMetaWriter::new().await;
+
+        conda_meta_builder
+            .write_meta_json(BAT_META_JSON, "bat-somehash", None)
+            .await;
+
+        let tool_spec = IsolatedToolSpec {
+            specs: vec![MatchSpec::from_str("bat", ParseStrictness::Strict).unwrap()],
+            command: "bat".into(),
+            channels: vec![NamedChannelOrUrl::Name("pixi-build-backends".to_string())],
+        };
+
+        let tool = tool_cache
+            .get_file_system_cached(
+                &tool_spec,
+                &conda_meta_builder.tmp_dir,
+                config.global_channel_config(),
+            )
             .await
             .unwrap();
 
@@ -2,6 +2,7 @@ use std::fmt::Debug;
 use std::future::Future;
 use std::path::PathBuf;
 
+use pixi_config::Config;
 use pixi_consts::consts::CACHED_BUILD_TOOL_ENVS_DIR;
 use pixi_progress::await_in_progress;
 use pixi_utils::{AsyncPrefixGuard, EnvironmentHash};
@@ -34,6 +35,7 @@ pub trait ToolInstaller {
 
 pub struct ToolContextBuilder {
     gateway: Option<Gateway>,
+    config: Option<Config>,
     client: ClientWithMiddleware,
     cache_dir: PathBuf,
     cache: ToolCache,
@@ -51,6 +53,7 @@ impl ToolContextBuilder {
     pub fn new() -> Self {
         Self {
             gateway: None,
+            config: None,
             client: ClientWithMiddleware::default(),
             cache_dir: pixi_config::get_cache_dir().expect("we should have a cache dir"),
             cache: ToolCache::default(),
@@ -72,6 +75,13 @@ impl ToolContextBuilder {
         self
     }
 
+    /// Set the configuration with which channels whose sharded repodata
+    /// fails fall back to the full repodata.
+    pub fn with_config(mut self, config: Config) -> Self {
+        self.config = Some(config);
+        self
+    }
+
     /// Set the client for the tool context.
     pub fn with_client(mut self, client: ClientWithMiddleware) -> Self {
         self.client = client;
@@ -104,6 +114,7 @@ impl ToolContextBuilder {
             cache: self.cache,
             platform: self.platform,
             gateway,
+            config: self.config,
         }
     }
 }
@@ -119,6 +130,9 @@ pub struct ToolContext {
     pub cache_dir: PathBuf,
     // The gateway to use for fetching repodata.
     pub gateway: Gateway,
+    /// The configuration with which channels whose sharded repodata fails
+    /// fall back to the full repodata.
+    pub config: Option<Config>,
     // The cache to use for the tools.
     pub cache: ToolCache,
     /// The platform to install tools for. This is usually the current platform
@@ -214,17 +228,34 @@ impl ToolInstaller for ToolContext {
             .collect::<Result<Vec<Channel>, _>>()
             .into_diagnostic()?;
 
-        let repodata = self
-            .gateway
-            .query(
-                channels.clone(),
-                [self.platform, Platform::NoArch],
-                spec.specs.clone(),
-            )
-            .recursive(true)
-            .execute()
-            .await
-            .into_diagnostic()?;
+        let platforms = [self.platform, Platform::NoArch];
+        let query = |gateway: Gateway| {
+            let channels = channels.clone();
+            let specs = spec.specs.clone();
+            async move {
+                gateway
+                    .query(channels, platforms, specs)
+                    .recursive(true)
+                    .execute()
+                    .await
+            }
+        };
+        let repodata = match &self.config {
+            Some(config) => {
+                config
+                    .query_with_sharded_fallback(
+                        &self.client,
+                        self.gateway.clone(),
+                        &channels,
+                        &platforms,
+                        &spec.specs,
+                        query,
+                    )
+                    .await
+            }
+            None => query(self.gateway.clone()).await,
+        }
+        .into_diagnostic()?;
 
         // Determine virtual packages of the current platform
         let virtual_packages = VirtualPackage::detect(&VirtualPackageOverrides::from_env())
@@ -2,7 +2,10 @@ mod cache;
 mod installer;
 mod spec;
 
-use std::{collections::HashMap, path::PathBuf};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
 
 pub use cache::ToolCacheError;
 pub use spec::{IsolatedToolSpec, SystemToolSpec, ToolSpec};
@@ -67,6 +70,11 @@ impl IsolatedTool {
             activation_scripts: activation,
         }
     }
+
+    /// Returns the prefix of the isolated environment.
+    pub fn prefix(&self) -> &Path {
+        &self.prefix
+    }
 }
 
 impl Tool {
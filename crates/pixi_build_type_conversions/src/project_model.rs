@@ -41,6 +41,9 @@ fn to_pixi_spec_v1(
                             Reference::DefaultBranch => pbt::GitReferenceV1::DefaultBranch,
                         }),
                         subdirectory: git_spec.subdirectory.clone(),
+                        depth: git_spec.options.depth.map(|depth| depth.get()),
+                        submodules: git_spec.options.submodules,
+                        lfs: git_spec.options.lfs,
                     })
                 }
                 pixi_spec::SourceSpec::Path(path_source_spec) => {
@@ -225,4 +228,29 @@ mod tests {
     ) {
         snapshot_test!(manifest_path);
     }
+
+    #[test]
+    fn test_git_fetch_options() {
+        let spec = pixi_spec::PixiSpec::Git(pixi_spec::GitSpec {
+            git: "https://github.com/prefix-dev/pixi".parse().unwrap(),
+            rev: None,
+            subdirectory: None,
+            options: pixi_git::git::GitFetchOptions {
+                depth: std::num::NonZeroU32::new(1),
+                submodules: Some(false),
+                lfs: true,
+            },
+            ignore_run_exports: Default::default(),
+        });
+        let converted = super::to_pixi_spec_v1(&spec, &some_channel_config()).unwrap();
+        let pixi_build_types::PackageSpecV1::Source(pixi_build_types::SourcePackageSpecV1::Git(
+            git,
+        )) = converted
+        else {
+            panic!("expected a git source spec, got {converted:?}");
+        };
+        assert_eq!(git.depth, Some(1));
+        assert_eq!(git.submodules, Some(false));
+        assert!(git.lfs);
+    }
 }
@@ -0,0 +1,118 @@
+//! Structured progress that a backend reports while it builds a package.
+
+use std::fmt::{Display, Formatter};
+
+use serde::{Deserialize, Serialize};
+
+/// The prefix of a line on stderr of the backend that contains a
+/// [`BuildProgress`] event serialized as JSON. All other lines are forwarded
+/// to the user verbatim.
+///
+/// Backends should only write these lines if the frontend advertises the
+/// `buildProgress` capability.
+pub const BUILD_PROGRESS_PREFIX: &str = "pixi-build-progress:";
+
+/// A progress event of a build.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildProgress {
+    /// The phase the build is in, e.g. `resolving`, `compiling` or
+    /// `packaging`.
+    pub phase: String,
+
+    /// How much of the phase is done, between 0 and 100. Or `None` if the
+    /// backend doesn't know.
+    pub percentage: Option<f64>,
+
+    /// The file that the backend is currently working on.
+    pub current_file: Option<String>,
+}
+
+impl BuildProgress {
+    /// Parses a progress event from a line on stderr of the backend. Returns
+    /// `None` if the line is regular output.
+    pub fn from_line(line: &str) -> Option<Self> {
+        let json = line.strip_prefix(BUILD_PROGRESS_PREFIX)?;
+        serde_json::from_str(json.trim()).ok()
+    }
+
+    /// Formats the event as a line that can be written to stderr.
+    pub fn to_line(&self) -> String {
+        format!(
+            "{BUILD_PROGRESS_PREFIX} {}",
+            serde_json::to_string(self).expect("serialization to JSON should not fail")
+        )
+    }
+
+    /// Returns the percentage clamped between 0 and 100.
+    pub fn position(&self) -> Option<u64> {
+        self.percentage
+            .map(|percentage| percentage.clamp(0.0, 100.0).round() as u64)
+    }
+}
+
+impl Display for BuildProgress {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.phase)?;
+        if let Some(position) = self.position() {
+            write!(f, " {position}%")?;
+        }
+        if let Some(current_file) = &self.current_file {
+            write!(f, " {current_file}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_line() {
+        let progress = BuildProgress::from_line(
+            r#"pixi-build-progress: {"phase": "compiling", "percentage": 42.4, "currentFile": "src/lib.rs"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            progress,
+            BuildProgress {
+                phase: "compiling".to_string(),
+                percentage: Some(42.4),
+                current_file: Some("src/lib.rs".to_string()),
+            }
+        );
+        assert_eq!(progress.to_string(), "compiling 42% src/lib.rs");
+
+        // The optional fields can be omitted
+        let progress =
+            BuildProgress::from_line(r#"pixi-build-progress:{"phase": "packaging"}"#).unwrap();
+        assert_eq!(progress.to_string(), "packaging");
+
+        // Events round-trip through their line
+        assert_eq!(
+            BuildProgress::from_line(&progress.to_line()),
+            Some(progress)
+        );
+
+        // Regular output and malformed events are not progress
+        assert_eq!(BuildProgress::from_line("compiling src/lib.rs"), None);
+        assert_eq!(BuildProgress::from_line("pixi-build-progress: {"), None);
+        assert_eq!(
+            BuildProgress::from_line(r#" pixi-build-progress: {"phase": "packaging"}"#),
+            None
+        );
+    }
+
+    #[test]
+    fn test_position_is_clamped() {
+        let progress = |percentage| BuildProgress {
+            phase: "compiling".to_string(),
+            percentage: Some(percentage),
+            current_file: None,
+        };
+        assert_eq!(progress(-3.0).position(), Some(0));
+        assert_eq!(progress(99.6).position(), Some(100));
+        assert_eq!(progress(250.0).position(), Some(100));
+    }
+}
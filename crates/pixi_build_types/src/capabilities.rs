@@ -16,5 +16,10 @@ pub struct BackendCapabilities {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 /// Capabilities that the frontend provides.
-pub struct FrontendCapabilities {}
+pub struct FrontendCapabilities {
+    /// Whether the frontend renders the [`crate::BuildProgress`] events that
+    /// the backend writes to stderr.
+    pub build_progress: Option<bool>,
+}
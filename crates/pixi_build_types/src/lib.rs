@@ -1,3 +1,4 @@
+mod build_progress;
 #[deny(missing_docs)]
 mod capabilities;
 mod channel_configuration;
@@ -5,6 +6,7 @@ mod conda_package_metadata;
 pub mod procedures;
 mod project_model;
 
+pub use build_progress::{BuildProgress, BUILD_PROGRESS_PREFIX};
 pub use capabilities::{BackendCapabilities, FrontendCapabilities};
 pub use channel_configuration::ChannelConfiguration;
 pub use conda_package_metadata::CondaPackageMetadata;
@@ -4,6 +4,7 @@ use rattler_conda_types::GenericVirtualPackage;
 use serde::{Deserialize, Serialize};
 use url::Url;
 
+use super::conda_metadata::IgnoreRunExports;
 use crate::{ChannelConfiguration, PlatformAndVirtualPackages};
 
 pub const METHOD_NAME: &str = "conda/build";
@@ -37,6 +38,16 @@ pub struct CondaBuildParams {
     /// The variants that we want to build
     pub variant_configuration: Option<HashMap<String, Vec<String>>>,
 
+    /// Groups of variant keys whose values are zipped together instead of
+    /// combined with every value of the other keys, like `zip_keys` in a
+    /// conda-build variant config. The keys of a group have the same number
+    /// of values.
+    ///
+    /// If this field is not present, every combination of the variants is
+    /// built.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub variant_zip_keys: Option<Vec<Vec<String>>>,
+
     /// A directory that can be used by the backend to store files for
     /// subsequent requests. This directory is unique for each separate source
     /// dependency.
@@ -47,6 +58,12 @@ pub struct CondaBuildParams {
     /// Whether we want to install the package as editable
     // TODO: remove this parameter as soon as we have profiles
     pub editable: bool,
+    /// The run exports of the dependencies of the package that are ignored
+    /// when computing the dependencies of the built package.
+    ///
+    /// If this field is not present, all the run exports are applied.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ignore_run_exports: Option<IgnoreRunExports>,
 }
 
 /// Identifier of an output.
@@ -1,3 +1,4 @@
+use rattler_conda_types::PackageName;
 use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, path::PathBuf};
 use url::Url;
@@ -30,12 +31,43 @@ pub struct CondaMetadataParams {
     /// The variants that we want to build
     pub variant_configuration: Option<HashMap<String, Vec<String>>>,
 
+    /// Groups of variant keys whose values are zipped together instead of
+    /// combined with every value of the other keys, like `zip_keys` in a
+    /// conda-build variant config. The keys of a group have the same number
+    /// of values.
+    ///
+    /// If this field is not present, every combination of the variants is
+    /// built.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub variant_zip_keys: Option<Vec<Vec<String>>>,
+
     /// A directory that can be used by the backend to store files for
     /// subsequent requests. This directory is unique for each separate source
     /// dependency.
     ///
     /// The directory may not yet exist.
     pub work_directory: PathBuf,
+
+    /// The run exports of the dependencies of the package that are ignored
+    /// when computing its dependencies.
+    ///
+    /// If this field is not present, all the run exports are applied.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ignore_run_exports: Option<IgnoreRunExports>,
+}
+
+/// The run exports that the consumer of a source package ignores.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IgnoreRunExports {
+    /// The names of the run exports to ignore, regardless of the package that
+    /// exports them.
+    #[serde(default)]
+    pub by_name: Vec<PackageName>,
+
+    /// The packages of which all the run exports are ignored.
+    #[serde(default)]
+    pub from_package: Vec<PackageName>,
 }
 
 /// Contains the result of the `conda/getMetadata` request.
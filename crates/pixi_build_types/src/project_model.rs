@@ -215,6 +215,20 @@ pub struct GitSpecV1 {
 
     /// The git subdirectory of the package
     pub subdirectory: Option<String>,
+
+    /// Only fetch this many commits of history, instead of the full history
+    /// of the repository.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub depth: Option<u32>,
+
+    /// Whether the submodules of the repository are checked out, defaults to
+    /// `true`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub submodules: Option<bool>,
+
+    /// Whether the Git LFS files of the repository are fetched.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub lfs: bool,
 }
 
 /// A specification of a package from a git repository.
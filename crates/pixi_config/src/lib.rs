@@ -4,19 +4,24 @@ use miette::{miette, Context, IntoDiagnostic};
 use pixi_consts::consts;
 use rattler_conda_types::{
     version_spec::{EqualityOperator, LogicalOperator, RangeOperator},
-    ChannelConfig, NamedChannelOrUrl, Version, VersionBumpType, VersionSpec,
+    ChannelConfig, NamedChannelOrUrl, Platform, Version, VersionBumpType, VersionSpec,
 };
 use rattler_repodata_gateway::{Gateway, SourceConfig};
 use reqwest_middleware::ClientWithMiddleware;
 use serde::{de::IntoDeserializer, Deserialize, Serialize};
 use std::{
     collections::{BTreeSet as Set, HashMap},
+    num::NonZeroUsize,
     path::{Path, PathBuf},
     process::{Command, Stdio},
     str::FromStr,
 };
 use url::Url;
 
+mod sharded_fallback;
+
+pub use sharded_fallback::{ShardedFallback, ShardedFallbackState};
+
 const EXPERIMENTAL: &str = "experimental";
 
 pub fn default_channel_config() -> ChannelConfig {
@@ -173,19 +178,56 @@ impl RepodataConfig {
             per_channel,
         }
     }
+
+    /// Returns the configuration of the channel, which is the configuration
+    /// of the longest matching url merged with the default configuration.
+    pub fn channel(&self, channel: &Url) -> RepodataChannelConfig {
+        self.per_channel
+            .iter()
+            .filter(|(url, _)| url_is_prefix_of(url, channel))
+            .max_by_key(|(url, _)| url.as_str().len())
+            .map_or_else(
+                || self.default.clone(),
+                |(_, config)| config.merge(self.default.clone()),
+            )
+    }
+}
+
+/// Returns true if `url` is `prefix` or a url below it. The paths are
+/// compared by segments, so `https://prefix.dev/conda-forge` is not a prefix
+/// of `https://prefix.dev/conda-forge-nightly`.
+fn url_is_prefix_of(prefix: &Url, url: &Url) -> bool {
+    let segments = |url: &Url| {
+        url.path_segments()
+            .map(|segments| segments.filter(|s| !s.is_empty()).collect_vec())
+            .unwrap_or_default()
+    };
+    prefix.scheme() == url.scheme()
+        && prefix.host_str() == url.host_str()
+        && prefix.port_or_known_default() == url.port_or_known_default()
+        && segments(url).starts_with(&segments(prefix))
 }
 
 #[derive(Parser, Debug, Default, Clone)]
 pub struct ConfigCliActivation {
-    /// Do not use the environment activation cache. (default: true except in experimental mode)
+    /// Do not read the environment activation cache, activate the
+    /// environment again and update the cache.
     #[arg(long)]
     force_activate: bool,
+
+    /// Neither read nor update the environment activation cache for this
+    /// invocation.
+    #[arg(long)]
+    no_activation_cache: bool,
 }
 
 impl ConfigCliActivation {
     pub fn merge_config(self, config: Config) -> Config {
         let mut config = config;
         config.force_activate = Some(self.force_activate);
+        if self.no_activation_cache {
+            config.activation_cache = Some(false);
+        }
         config
     }
 }
@@ -194,6 +236,7 @@ impl From<ConfigCliActivation> for Config {
     fn from(cli: ConfigCliActivation) -> Self {
         Self {
             force_activate: Some(cli.force_activate),
+            activation_cache: cli.no_activation_cache.then_some(false),
             ..Default::default()
         }
     }
@@ -242,8 +285,7 @@ impl From<RepodataChannelConfig> for SourceConfig {
             jlap_enabled: !value.disable_jlap.unwrap_or(false),
             zstd_enabled: !value.disable_zstd.unwrap_or(false),
             bz2_enabled: !value.disable_bzip2.unwrap_or(false),
-            // TODO: Change sharded repodata default to true, when enough testing has been done.
-            sharded_enabled: !value.disable_sharded.unwrap_or(true),
+            sharded_enabled: !value.disable_sharded.unwrap_or(false),
             cache_action: Default::default(),
         }
     }
@@ -275,6 +317,59 @@ pub struct PyPIConfig {
     #[serde(default)]
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub allow_insecure_host: Vec<String>,
+    /// Flat indexes of distributions, also called `--find-links` in pip.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub find_links: Vec<FindLinks>,
+    /// Revalidate all the cached PyPI data, also called `--refresh` in uv.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache_refresh: Option<bool>,
+    /// Revalidate the cached PyPI data of these packages, also called
+    /// `--refresh-package` in uv.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub cache_refresh_packages: Vec<String>,
+}
+
+/// The location of a flat index, either a URL or a path to a directory that
+/// contains the distributions.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq, Hash)]
+#[serde(try_from = "String", into = "String")]
+pub enum FindLinks {
+    Url(Url),
+    Path(PathBuf),
+}
+
+impl FromStr for FindLinks {
+    type Err = url::ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // A windows path like `C:\wheels` would parse as a URL, so only
+        // strings with an explicit `://` are URLs.
+        if s.contains("://") {
+            Url::parse(s).map(FindLinks::Url)
+        } else {
+            Ok(FindLinks::Path(PathBuf::from(s)))
+        }
+    }
+}
+
+impl TryFrom<String> for FindLinks {
+    type Error = url::ParseError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<FindLinks> for String {
+    fn from(value: FindLinks) -> Self {
+        match value {
+            FindLinks::Url(url) => url.to_string(),
+            FindLinks::Path(path) => path.display().to_string(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
@@ -310,11 +405,19 @@ impl Default for DetachedEnvironments {
 #[derive(Default, Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
 #[serde(rename_all = "kebab-case")]
 pub struct ExperimentalConfig {
-    /// The option to opt into the environment activation cache feature.
-    /// This is an experimental feature and may be removed in the future or made default.
+    /// The former option to opt into the environment activation cache, which
+    /// is now on by default. Superseded by `activation-cache`, setting it to
+    /// false still turns the cache off.
     #[serde(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub use_environment_activation_cache: Option<bool>,
+
+    /// The option to record the duration of commands and their phases in a
+    /// local statistics file in the pixi home. The statistics never leave the
+    /// machine.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub local_stats: Option<bool>,
 }
 
 impl ExperimentalConfig {
@@ -323,14 +426,385 @@ impl ExperimentalConfig {
             use_environment_activation_cache: other
                 .use_environment_activation_cache
                 .or(self.use_environment_activation_cache),
+            local_stats: other.local_stats.or(self.local_stats),
         }
     }
     pub fn use_environment_activation_cache(&self) -> bool {
         self.use_environment_activation_cache.unwrap_or(false)
     }
 
+    pub fn local_stats(&self) -> bool {
+        self.local_stats.unwrap_or(false)
+    }
+
+    pub fn is_default(&self) -> bool {
+        self.use_environment_activation_cache.is_none() && self.local_stats.is_none()
+    }
+}
+
+#[derive(Default, Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct BuildConfig {
+    /// Configuration of the cache of the packages that are built from source.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "BuildCacheConfig::is_default")]
+    pub cache: BuildCacheConfig,
+}
+
+impl BuildConfig {
+    /// Merge the given BuildConfig into the current one.
+    pub fn merge(self, other: Self) -> Self {
+        Self {
+            cache: self.cache.merge(other.cache),
+        }
+    }
+
+    pub fn is_default(&self) -> bool {
+        self.cache.is_default()
+    }
+}
+
+#[derive(Default, Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct BuildCacheConfig {
+    /// The url of a remote store that is shared between machines. Packages
+    /// built from source are fetched from it before they are built locally.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remote: Option<Url>,
+
+    /// Whether the packages that are built locally are uploaded to the remote
+    /// store.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upload: Option<bool>,
+}
+
+impl BuildCacheConfig {
+    /// Merge the given BuildCacheConfig into the current one.
+    pub fn merge(self, other: Self) -> Self {
+        Self {
+            remote: other.remote.or(self.remote),
+            upload: other.upload.or(self.upload),
+        }
+    }
+
+    /// Whether the locally built packages are uploaded to the remote store
+    /// (defaults to false).
+    pub fn upload(&self) -> bool {
+        self.upload.unwrap_or(false)
+    }
+
+    pub fn is_default(&self) -> bool {
+        self.remote.is_none() && self.upload.is_none()
+    }
+}
+
+/// Configuration of the minisign signatures of the conda packages that are
+/// installed.
+#[derive(Default, Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct TrustConfig {
+    /// The minisign public keys that are trusted to sign packages.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub public_keys: Vec<String>,
+
+    /// The channels of which every installed package has to be signed by one
+    /// of the trusted keys.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub channels: Vec<Url>,
+}
+
+impl TrustConfig {
+    /// Merge the given TrustConfig into the current one.
+    pub fn merge(self, other: Self) -> Self {
+        Self {
+            public_keys: if other.public_keys.is_empty() {
+                self.public_keys
+            } else {
+                other.public_keys
+            },
+            channels: if other.channels.is_empty() {
+                self.channels
+            } else {
+                other.channels
+            },
+        }
+    }
+
+    /// Returns true if the packages of the given channel url have to be
+    /// signed.
+    pub fn is_trusted_channel(&self, channel: &Url) -> bool {
+        let channel = channel.as_str().trim_end_matches('/');
+        self.channels
+            .iter()
+            .any(|url| url.as_str().trim_end_matches('/') == channel)
+    }
+
+    pub fn is_default(&self) -> bool {
+        self.public_keys.is_empty() && self.channels.is_empty()
+    }
+}
+
+/// The backend that stores the responses of the HTTP requests pixi caches.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq, Copy)]
+#[serde(rename_all = "kebab-case")]
+pub enum HttpCacheBackend {
+    /// Store the responses as files in the cache directory.
+    #[default]
+    Cacache,
+    /// Don't cache the responses.
+    Disabled,
+}
+impl FromStr for HttpCacheBackend {
+    type Err = serde::de::value::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::deserialize(s.into_deserializer())
+    }
+}
+
+#[derive(Default, Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct CacheConfig {
+    /// The backend of the HTTP cache.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub http_backend: Option<HttpCacheBackend>,
+
+    /// Whether the results of conda solves are cached and shared between
+    /// workspaces.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub solves: Option<bool>,
+}
+
+impl CacheConfig {
+    /// Merge the given CacheConfig into the current one.
+    pub fn merge(self, other: Self) -> Self {
+        Self {
+            http_backend: other.http_backend.or(self.http_backend),
+            solves: other.solves.or(self.solves),
+        }
+    }
+
+    /// The backend of the HTTP cache (defaults to cacache).
+    pub fn http_backend(&self) -> HttpCacheBackend {
+        self.http_backend.unwrap_or_default()
+    }
+
+    /// Whether the results of conda solves are cached (defaults to true).
+    pub fn solves(&self) -> bool {
+        self.solves.unwrap_or(true)
+    }
+
     pub fn is_default(&self) -> bool {
-        self.use_environment_activation_cache.is_none()
+        self.http_backend.is_none() && self.solves.is_none()
+    }
+}
+
+/// The release channel of pixi that `pixi self-update` updates from.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReleaseChannel {
+    /// The latest stable release.
+    #[default]
+    Stable,
+    /// The latest release, including pre-releases.
+    Beta,
+}
+
+impl FromStr for ReleaseChannel {
+    type Err = serde::de::value::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::deserialize(s.into_deserializer())
+    }
+}
+
+impl std::fmt::Display for ReleaseChannel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ReleaseChannel::Stable => "stable",
+            ReleaseChannel::Beta => "beta",
+        })
+    }
+}
+
+#[derive(Default, Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct SelfUpdateConfig {
+    /// The release channel that `pixi self-update` updates from.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channel: Option<ReleaseChannel>,
+
+    /// The minisign public keys of which one has to sign the release archive
+    /// before it replaces pixi.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub public_keys: Vec<String>,
+}
+
+impl SelfUpdateConfig {
+    /// Merge the given SelfUpdateConfig into the current one.
+    pub fn merge(self, other: Self) -> Self {
+        Self {
+            channel: other.channel.or(self.channel),
+            public_keys: if other.public_keys.is_empty() {
+                self.public_keys
+            } else {
+                other.public_keys
+            },
+        }
+    }
+
+    /// The release channel (defaults to stable).
+    pub fn channel(&self) -> ReleaseChannel {
+        self.channel.unwrap_or_default()
+    }
+
+    pub fn is_default(&self) -> bool {
+        self.channel.is_none() && self.public_keys.is_empty()
+    }
+}
+
+/// A number of bytes per second, written like `10MB/s` or `512KiB/s`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Bandwidth(u64);
+
+#[derive(Debug, thiserror::Error)]
+#[error("invalid bandwidth '{0}', expected a size per second like '10MB/s'")]
+pub struct ParseBandwidthError(String);
+
+impl Bandwidth {
+    /// The units of a bandwidth, ordered from large to small so the largest
+    /// unit is used when displaying it.
+    const UNITS: [(&'static str, u64); 7] = [
+        ("GiB", 1 << 30),
+        ("GB", 1_000_000_000),
+        ("MiB", 1 << 20),
+        ("MB", 1_000_000),
+        ("KiB", 1 << 10),
+        ("kB", 1_000),
+        ("B", 1),
+    ];
+
+    pub fn from_bytes_per_second(bytes: u64) -> Self {
+        Self(bytes)
+    }
+
+    pub fn bytes_per_second(&self) -> u64 {
+        self.0
+    }
+}
+
+impl FromStr for Bandwidth {
+    type Err = ParseBandwidthError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseBandwidthError(s.to_string());
+        let value = s.trim();
+        let value = value.strip_suffix("/s").unwrap_or(value).trim_end();
+        let split = value
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(value.len());
+        let (number, unit) = value.split_at(split);
+        let number: f64 = number.parse().map_err(|_| err())?;
+        let unit = unit.trim();
+        let multiplier = if unit.is_empty() {
+            1
+        } else {
+            Self::UNITS
+                .iter()
+                .find(|(name, _)| {
+                    name.eq_ignore_ascii_case(unit)
+                        || name[..name.len() - 1].eq_ignore_ascii_case(unit)
+                })
+                .map(|(_, multiplier)| *multiplier)
+                .ok_or_else(err)?
+        };
+        let bytes = (number * multiplier as f64).round();
+        if !bytes.is_finite() || bytes < 1.0 {
+            return Err(err());
+        }
+        Ok(Self(bytes as u64))
+    }
+}
+
+impl std::fmt::Display for Bandwidth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (unit, multiplier) = Self::UNITS
+            .iter()
+            .find(|(_, multiplier)| self.0 % multiplier == 0)
+            .expect("every bandwidth is a multiple of a byte");
+        write!(f, "{}{unit}/s", self.0 / multiplier)
+    }
+}
+
+impl TryFrom<String> for Bandwidth {
+    type Error = ParseBandwidthError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Bandwidth> for String {
+    fn from(value: Bandwidth) -> Self {
+        value.to_string()
+    }
+}
+
+#[derive(Clone, Default, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct NetworkConfig {
+    #[serde(flatten)]
+    pub default: NetworkChannelConfig,
+
+    #[serde(flatten)]
+    pub per_channel: HashMap<Url, NetworkChannelConfig>,
+}
+
+impl NetworkConfig {
+    pub fn is_default(&self) -> bool {
+        self.default.is_empty() && self.per_channel.is_empty()
+    }
+
+    /// Merge the given NetworkConfig into the current one.
+    /// The given config will have higher priority
+    pub fn merge(self, mut other: Self) -> Self {
+        let mut per_channel: HashMap<_, _> = self
+            .per_channel
+            .into_iter()
+            .map(|(url, config)| {
+                let other_config = other.per_channel.remove(&url).unwrap_or_default();
+                (url, config.merge(other_config))
+            })
+            .collect();
+
+        per_channel.extend(other.per_channel);
+
+        Self {
+            default: self.default.merge(other.default),
+            per_channel,
+        }
+    }
+}
+
+#[derive(Clone, Default, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct NetworkChannelConfig {
+    /// The maximum bandwidth of the downloads.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_bandwidth: Option<Bandwidth>,
+}
+
+impl NetworkChannelConfig {
+    pub fn is_empty(&self) -> bool {
+        self.max_bandwidth.is_none()
+    }
+
+    pub fn merge(self, other: Self) -> Self {
+        Self {
+            max_bandwidth: other.max_bandwidth.or(self.max_bandwidth),
+        }
     }
 }
 
@@ -348,6 +822,12 @@ fn default_max_concurrent_downloads() -> usize {
     50
 }
 
+/// The default maximum number of packages that are downloaded, extracted and
+/// linked at the same time while installing an environment.
+fn default_max_concurrent_installs() -> NonZeroUsize {
+    NonZeroUsize::new(10).unwrap()
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
 #[serde(rename_all = "kebab-case")]
 pub struct ConcurrencyConfig {
@@ -360,6 +840,13 @@ pub struct ConcurrencyConfig {
     // Needing to set this default next to the default of the full struct to avoid serde defaulting to 0 of partial struct was omitted.
     #[serde(default = "default_max_concurrent_downloads")]
     pub downloads: usize,
+
+    /// The maximum number of packages that are downloaded, extracted and
+    /// linked at the same time while installing an environment. Zero is
+    /// rejected, because no package could ever be installed.
+    // Needing to set this default next to the default of the full struct to avoid serde defaulting to 0 of partial struct was omitted.
+    #[serde(default = "default_max_concurrent_installs")]
+    pub installs: NonZeroUsize,
 }
 
 impl Default for ConcurrencyConfig {
@@ -367,6 +854,7 @@ impl Default for ConcurrencyConfig {
         Self {
             solves: default_max_concurrent_solves(),
             downloads: default_max_concurrent_downloads(),
+            installs: default_max_concurrent_installs(),
         }
     }
 }
@@ -386,6 +874,11 @@ impl ConcurrencyConfig {
             } else {
                 self.downloads
             },
+            installs: if other.installs != ConcurrencyConfig::default().installs {
+                other.installs
+            } else {
+                self.installs
+            },
         }
     }
 
@@ -412,6 +905,19 @@ impl PyPIConfig {
                 .into_iter()
                 .chain(other.allow_insecure_host)
                 .collect(),
+            find_links: self
+                .find_links
+                .into_iter()
+                .chain(other.find_links)
+                .unique()
+                .collect(),
+            cache_refresh: other.cache_refresh.or(self.cache_refresh),
+            cache_refresh_packages: self
+                .cache_refresh_packages
+                .into_iter()
+                .chain(other.cache_refresh_packages)
+                .unique()
+                .collect(),
         }
     }
 
@@ -432,6 +938,9 @@ impl PyPIConfig {
         self.index_url.is_none()
             && self.extra_index_urls.is_empty()
             && self.keyring_provider.is_none()
+            && self.find_links.is_empty()
+            && self.cache_refresh.is_none()
+            && self.cache_refresh_packages.is_empty()
     }
 }
 
@@ -544,6 +1053,50 @@ impl PinningStrategy {
     }
 }
 
+/// The policy for verifying the sha256 of the packages that are installed
+/// against the lock file.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq, Copy)]
+#[serde(rename_all = "kebab-case")]
+pub enum VerifyHashes {
+    /// Verify every package that is downloaded, a package without a sha256 in
+    /// the lock file is an error.
+    Always,
+    /// Verify the packages that are downloaded and have a sha256 in the lock
+    /// file.
+    LockOnly,
+    /// Don't verify the packages.
+    #[default]
+    Off,
+}
+impl FromStr for VerifyHashes {
+    type Err = serde::de::value::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::deserialize(s.into_deserializer())
+    }
+}
+
+/// The policy for files that are provided by multiple conda packages in the
+/// same environment. Only one of the packages can provide the file, the files
+/// of the other packages are clobbered.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq, Copy)]
+#[serde(rename_all = "kebab-case")]
+pub enum ClobberPolicy {
+    /// Fail the installation if files are clobbered. The files are detected
+    /// after the packages are linked, so they are clobbered nonetheless.
+    Error,
+    /// Warn about the files that are clobbered by the installation.
+    #[default]
+    Warn,
+    /// Clobber the files silently.
+    Allow,
+}
+impl FromStr for ClobberPolicy {
+    type Err = serde::de::value::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::deserialize(s.into_deserializer())
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
 #[serde(rename_all = "kebab-case")]
 pub struct Config {
@@ -580,6 +1133,28 @@ pub struct Config {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub pinning_strategy: Option<PinningStrategy>,
 
+    /// The directory with the packages downloaded by `pixi lock vendor`.
+    /// Packages found in this directory are installed from it instead of
+    /// being downloaded. A relative path is relative to the project root.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vendor_dir: Option<PathBuf>,
+
+    /// The platforms of the project that are locked. The other platforms of
+    /// the project are not solved and removed from the lock file. Defaults to
+    /// all platforms of the project.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lock_platforms: Option<Vec<Platform>>,
+
+    /// Whether the sha256 of the downloaded packages is verified against the
+    /// lock file before they are installed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verify_hashes: Option<VerifyHashes>,
+
+    /// What to do when multiple conda packages of an environment provide the
+    /// same file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clobber_policy: Option<ClobberPolicy>,
+
     #[serde(skip)]
     #[serde(alias = "loaded_from")] // BREAK: remove to stop supporting snake_case alias
     pub loaded_from: Vec<PathBuf>,
@@ -610,6 +1185,12 @@ pub struct Config {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub force_activate: Option<bool>,
 
+    /// Whether the environment variables of the activation of an environment
+    /// are cached, defaults to true.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub activation_cache: Option<bool>,
+
     /// Experimental features that can be enabled.
     #[serde(default)]
     #[serde(skip_serializing_if = "ExperimentalConfig::is_default")]
@@ -619,6 +1200,31 @@ pub struct Config {
     #[serde(default)]
     #[serde(skip_serializing_if = "ConcurrencyConfig::is_default")]
     pub concurrency: ConcurrencyConfig,
+
+    /// Configuration for building packages from source.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "BuildConfig::is_default")]
+    pub build: BuildConfig,
+
+    /// Configuration of the signatures of the installed packages.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "TrustConfig::is_default")]
+    pub trust: TrustConfig,
+
+    /// Configuration of the caches of pixi.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "CacheConfig::is_default")]
+    pub cache: CacheConfig,
+
+    /// Configuration of the network requests, globally and per channel.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "NetworkConfig::is_default")]
+    pub network: NetworkConfig,
+
+    /// Configuration of `pixi self-update`.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "SelfUpdateConfig::is_default")]
+    pub self_update: SelfUpdateConfig,
 }
 
 impl Default for Config {
@@ -635,9 +1241,19 @@ impl Default for Config {
             pypi_config: PyPIConfig::default(),
             detached_environments: None,
             pinning_strategy: None,
+            vendor_dir: None,
+            lock_platforms: None,
+            verify_hashes: None,
+            clobber_policy: None,
             force_activate: None,
+            activation_cache: None,
             experimental: ExperimentalConfig::default(),
             concurrency: ConcurrencyConfig::default(),
+            build: BuildConfig::default(),
+            trust: TrustConfig::default(),
+            cache: CacheConfig::default(),
+            network: NetworkConfig::default(),
+            self_update: SelfUpdateConfig::default(),
         }
     }
 }
@@ -659,6 +1275,7 @@ impl From<ConfigCli> for Config {
                 downloads: cli
                     .concurrent_downloads
                     .unwrap_or(ConcurrencyConfig::default().downloads),
+                installs: ConcurrencyConfig::default().installs,
             },
             ..Default::default()
         }
@@ -673,7 +1290,12 @@ impl From<Config> for rattler_repodata_gateway::ChannelConfig {
 
 impl From<&Config> for rattler_repodata_gateway::ChannelConfig {
     fn from(config: &Config) -> Self {
-        let repodata_config = &config.repodata_config;
+        (&config.repodata_config).into()
+    }
+}
+
+impl From<&RepodataConfig> for rattler_repodata_gateway::ChannelConfig {
+    fn from(repodata_config: &RepodataConfig) -> Self {
         let default = repodata_config.default.clone().into();
 
         let per_channel = repodata_config
@@ -718,9 +1340,6 @@ impl Config {
         // Use conda-forge as the default channel
         config.default_channels = vec![NamedChannelOrUrl::Name("conda-forge".into())];
 
-        // Enable sharded repodata by default.
-        config.repodata_config.default.disable_sharded = Some(false);
-
         config
     }
 
@@ -910,7 +1529,16 @@ impl Config {
             "mirrors",
             "detached-environments",
             "pinning-strategy",
+            "vendor-dir",
+            "lock-platforms",
+            "verify-hashes",
+            "clobber-policy",
+            "activation-cache",
             "max-concurrent-solves",
+            "concurrency",
+            "concurrency.solves",
+            "concurrency.downloads",
+            "concurrency.installs",
             "repodata-config",
             "repodata-config.disable-jlap",
             "repodata-config.disable-bzip2",
@@ -920,7 +1548,25 @@ impl Config {
             "pypi-config.index-url",
             "pypi-config.extra-index-urls",
             "pypi-config.keyring-provider",
+            "pypi-config.find-links",
+            "pypi-config.cache-refresh",
+            "pypi-config.cache-refresh-packages",
             "experimental.use-environment-activation-cache",
+            "experimental.local-stats",
+            "build",
+            "build.cache.remote",
+            "build.cache.upload",
+            "trust",
+            "trust.public-keys",
+            "trust.channels",
+            "cache",
+            "cache.http-backend",
+            "cache.solves",
+            "network",
+            "network.max-bandwidth",
+            "self-update",
+            "self-update.channel",
+            "self-update.public-keys",
         ]
     }
 
@@ -950,10 +1596,20 @@ impl Config {
             pypi_config: self.pypi_config.merge(other.pypi_config),
             detached_environments: other.detached_environments.or(self.detached_environments),
             pinning_strategy: other.pinning_strategy.or(self.pinning_strategy),
+            vendor_dir: other.vendor_dir.or(self.vendor_dir),
+            lock_platforms: other.lock_platforms.or(self.lock_platforms),
+            verify_hashes: other.verify_hashes.or(self.verify_hashes),
+            clobber_policy: other.clobber_policy.or(self.clobber_policy),
             force_activate: other.force_activate,
+            activation_cache: other.activation_cache.or(self.activation_cache),
             experimental: self.experimental.merge(other.experimental),
             // Make other take precedence over self to allow for setting the value through the CLI
             concurrency: self.concurrency.merge(other.concurrency),
+            build: self.build.merge(other.build),
+            trust: self.trust.merge(other.trust),
+            cache: self.cache.merge(other.cache),
+            network: self.network.merge(other.network),
+            self_update: self.self_update.merge(other.self_update),
         }
     }
 
@@ -1008,12 +1664,67 @@ impl Config {
         self.detached_environments.clone().unwrap_or_default()
     }
 
+    /// Retrieve the value for the vendor_dir field.
+    pub fn vendor_dir(&self) -> Option<&PathBuf> {
+        self.vendor_dir.as_ref()
+    }
+
+    /// Retrieve the value for the lock_platforms field.
+    pub fn lock_platforms(&self) -> Option<&[Platform]> {
+        self.lock_platforms.as_deref()
+    }
+
+    /// Retrieve the value for the verify_hashes field (defaults to off).
+    pub fn verify_hashes(&self) -> VerifyHashes {
+        self.verify_hashes.unwrap_or_default()
+    }
+
+    /// Retrieve the value for the clobber_policy field (defaults to warn).
+    pub fn clobber_policy(&self) -> ClobberPolicy {
+        self.clobber_policy.unwrap_or_default()
+    }
+
+    /// Retrieve the configuration for building packages from source.
+    pub fn build(&self) -> &BuildConfig {
+        &self.build
+    }
+
+    pub fn trust(&self) -> &TrustConfig {
+        &self.trust
+    }
+
+    /// Retrieve the value for the cache field.
+    pub fn cache(&self) -> &CacheConfig {
+        &self.cache
+    }
+
+    /// Retrieve the value for the network field.
+    pub fn network(&self) -> &NetworkConfig {
+        &self.network
+    }
+
+    /// Retrieve the configuration of `pixi self-update`.
+    pub fn self_update(&self) -> &SelfUpdateConfig {
+        &self.self_update
+    }
+
     pub fn force_activate(&self) -> bool {
         self.force_activate.unwrap_or(false)
     }
 
-    pub fn experimental_activation_cache_usage(&self) -> bool {
-        self.experimental.use_environment_activation_cache()
+    /// Returns true if the environment variables of activations are cached.
+    /// The cache is on by default, the experimental setting is still honored
+    /// to turn it off.
+    pub fn activation_cache(&self) -> bool {
+        self.activation_cache
+            .or(self.experimental.use_environment_activation_cache)
+            .unwrap_or(true)
+    }
+
+    /// Returns true if the duration of commands should be recorded in the
+    /// local statistics file.
+    pub fn local_stats(&self) -> bool {
+        self.experimental.local_stats()
     }
 
     /// Retrieve the value for the max_concurrent_solves field.
@@ -1026,6 +1737,11 @@ impl Config {
         self.concurrency.downloads
     }
 
+    /// Retrieve the value for the max_concurrent_installs field.
+    pub fn max_concurrent_installs(&self) -> usize {
+        self.concurrency.installs.get()
+    }
+
     /// Modify this config with the given key and value
     ///
     /// # Note
@@ -1077,6 +1793,30 @@ impl Config {
                     .transpose()
                     .into_diagnostic()?
             }
+            "vendor-dir" => {
+                self.vendor_dir = value.map(PathBuf::from);
+            }
+            "lock-platforms" => {
+                self.lock_platforms = value
+                    .map(|v| serde_json::de::from_str(&v))
+                    .transpose()
+                    .into_diagnostic()?;
+            }
+            "verify-hashes" => {
+                self.verify_hashes = value
+                    .map(|v| VerifyHashes::from_str(v.as_str()))
+                    .transpose()
+                    .into_diagnostic()?
+            }
+            "clobber-policy" => {
+                self.clobber_policy = value
+                    .map(|v| ClobberPolicy::from_str(v.as_str()))
+                    .transpose()
+                    .into_diagnostic()?
+            }
+            "activation-cache" => {
+                self.activation_cache = value.map(|v| v.parse()).transpose().into_diagnostic()?;
+            }
             key if key.starts_with("repodata-config") => {
                 if key == "repodata-config" {
                     self.repodata_config = value
@@ -1146,6 +1886,24 @@ impl Config {
                             })
                             .transpose()?;
                     }
+                    "find-links" => {
+                        self.pypi_config.find_links = value
+                            .map(|v| serde_json::de::from_str(&v))
+                            .transpose()
+                            .into_diagnostic()?
+                            .unwrap_or_default();
+                    }
+                    "cache-refresh" => {
+                        self.pypi_config.cache_refresh =
+                            value.map(|v| v.parse()).transpose().into_diagnostic()?;
+                    }
+                    "cache-refresh-packages" => {
+                        self.pypi_config.cache_refresh_packages = value
+                            .map(|v| serde_json::de::from_str(&v))
+                            .transpose()
+                            .into_diagnostic()?
+                            .unwrap_or_default();
+                    }
                     _ => return Err(err),
                 }
             }
@@ -1169,6 +1927,142 @@ impl Config {
                         self.experimental.use_environment_activation_cache =
                             value.map(|v| v.parse()).transpose().into_diagnostic()?;
                     }
+                    "local-stats" => {
+                        self.experimental.local_stats =
+                            value.map(|v| v.parse()).transpose().into_diagnostic()?;
+                    }
+                    _ => return Err(err),
+                }
+            }
+            key if key.starts_with("build") => {
+                if key == "build" {
+                    if let Some(value) = value {
+                        self.build = serde_json::de::from_str(&value).into_diagnostic()?;
+                    } else {
+                        self.build = BuildConfig::default();
+                    }
+                    return Ok(());
+                } else if !key.starts_with("build.") {
+                    return Err(err);
+                }
+
+                let subkey = key.strip_prefix("build.").unwrap();
+                match subkey {
+                    "cache.remote" => {
+                        self.build.cache.remote = value
+                            .map(|v| Url::parse(&v))
+                            .transpose()
+                            .into_diagnostic()?;
+                    }
+                    "cache.upload" => {
+                        self.build.cache.upload =
+                            value.map(|v| v.parse()).transpose().into_diagnostic()?;
+                    }
+                    _ => return Err(err),
+                }
+            }
+            key if key.starts_with("trust") => {
+                if key == "trust" {
+                    if let Some(value) = value {
+                        self.trust = serde_json::de::from_str(&value).into_diagnostic()?;
+                    } else {
+                        self.trust = TrustConfig::default();
+                    }
+                    return Ok(());
+                } else if !key.starts_with("trust.") {
+                    return Err(err);
+                }
+
+                let subkey = key.strip_prefix("trust.").unwrap();
+                match subkey {
+                    "public-keys" => {
+                        self.trust.public_keys = value
+                            .map(|v| serde_json::de::from_str(&v))
+                            .transpose()
+                            .into_diagnostic()?
+                            .unwrap_or_default();
+                    }
+                    "channels" => {
+                        self.trust.channels = value
+                            .map(|v| serde_json::de::from_str(&v))
+                            .transpose()
+                            .into_diagnostic()?
+                            .unwrap_or_default();
+                    }
+                    _ => return Err(err),
+                }
+            }
+            key if key.starts_with("cache") => {
+                if key == "cache" {
+                    if let Some(value) = value {
+                        self.cache = serde_json::de::from_str(&value).into_diagnostic()?;
+                    } else {
+                        self.cache = CacheConfig::default();
+                    }
+                    return Ok(());
+                } else if !key.starts_with("cache.") {
+                    return Err(err);
+                }
+
+                let subkey = key.strip_prefix("cache.").unwrap();
+                match subkey {
+                    "http-backend" => {
+                        self.cache.http_backend =
+                            value.map(|v| v.parse()).transpose().into_diagnostic()?;
+                    }
+                    "solves" => {
+                        self.cache.solves =
+                            value.map(|v| v.parse()).transpose().into_diagnostic()?;
+                    }
+                    _ => return Err(err),
+                }
+            }
+            key if key.starts_with("network") => {
+                if key == "network" {
+                    if let Some(value) = value {
+                        self.network = serde_json::de::from_str(&value).into_diagnostic()?;
+                    } else {
+                        self.network = NetworkConfig::default();
+                    }
+                    return Ok(());
+                } else if !key.starts_with("network.") {
+                    return Err(err);
+                }
+
+                let subkey = key.strip_prefix("network.").unwrap();
+                match subkey {
+                    "max-bandwidth" => {
+                        self.network.default.max_bandwidth =
+                            value.map(|v| v.parse()).transpose().into_diagnostic()?;
+                    }
+                    _ => return Err(err),
+                }
+            }
+            key if key.starts_with("self-update") => {
+                if key == "self-update" {
+                    if let Some(value) = value {
+                        self.self_update = serde_json::de::from_str(&value).into_diagnostic()?;
+                    } else {
+                        self.self_update = SelfUpdateConfig::default();
+                    }
+                    return Ok(());
+                } else if !key.starts_with("self-update.") {
+                    return Err(err);
+                }
+
+                let subkey = key.strip_prefix("self-update.").unwrap();
+                match subkey {
+                    "channel" => {
+                        self.self_update.channel =
+                            value.map(|v| v.parse()).transpose().into_diagnostic()?;
+                    }
+                    "public-keys" => {
+                        self.self_update.public_keys = value
+                            .map(|v| serde_json::de::from_str(&v))
+                            .transpose()
+                            .into_diagnostic()?
+                            .unwrap_or_default();
+                    }
                     _ => return Err(err),
                 }
             }
@@ -1199,6 +2093,13 @@ impl Config {
                             return Err(miette!("'downloads' requires a number value"));
                         }
                     }
+                    "installs" => {
+                        if let Some(value) = value {
+                            self.concurrency.installs = value.parse().into_diagnostic()?;
+                        } else {
+                            return Err(miette!("'installs' requires a number value"));
+                        }
+                    }
                     _ => return Err(err),
                 }
             }
@@ -1233,14 +2134,40 @@ impl Config {
             std::env::current_dir().unwrap_or_else(|_| PathBuf::from("./"))
         });
 
+        let repodata_cache_dir = cache_dir.join(consts::CONDA_REPODATA_CACHE_DIR);
+        let repodata_config = self.repodata_config_with_fallbacks(
+            &ShardedFallbackState::from_cache_dir(&repodata_cache_dir),
+        );
+
         // Construct the gateway
         Gateway::builder()
             .with_client(client)
-            .with_cache_dir(cache_dir.join(consts::CONDA_REPODATA_CACHE_DIR))
-            .with_channel_config(self.into())
+            .with_cache_dir(repodata_cache_dir)
+            .with_channel_config((&repodata_config).into())
             .with_max_concurrent_requests(self.max_concurrent_downloads())
             .finish()
     }
+
+    /// Returns the repodata configuration in which sharded repodata is
+    /// disabled for the channels that fall back to the full repodata. A
+    /// channel for which `disable-sharded` is configured explicitly always
+    /// uses the configured value.
+    pub fn repodata_config_with_fallbacks(&self, state: &ShardedFallbackState) -> RepodataConfig {
+        let mut repodata_config = self.repodata_config.clone();
+        for channel in state.channels.keys() {
+            let channel_config = self.repodata_config.channel(channel);
+            if channel_config.disable_sharded.is_none() {
+                repodata_config.per_channel.insert(
+                    channel.clone(),
+                    RepodataChannelConfig {
+                        disable_sharded: Some(true),
+                        ..channel_config
+                    },
+                );
+            }
+        }
+        repodata_config
+    }
 }
 
 /// Returns the path to the system-level pixi config file.
@@ -1396,6 +2323,15 @@ UNUSED = "unused"
         // This depends on the system so it's hard to test.
         assert!(config.concurrency.solves > 0);
         assert_eq!(config.concurrency.downloads, 50);
+        assert_eq!(config.max_concurrent_installs(), 10);
+    }
+
+    #[test]
+    fn test_concurrency_installs_zero() {
+        // Installs would wait forever for a free slot.
+        assert!(Config::from_toml("[concurrency]\ninstalls = 0").is_err());
+        let (config, _) = Config::from_toml("[concurrency]\ninstalls = 1").unwrap();
+        assert_eq!(config.max_concurrent_installs(), 1);
     }
 
     #[test]
@@ -1418,8 +2354,13 @@ UNUSED = "unused"
                 Vec::default(),
             )]),
             pinning_strategy: Some(PinningStrategy::NoPin),
+            vendor_dir: Some(PathBuf::from("vendor")),
+            lock_platforms: Some(vec![Platform::Linux64]),
+            verify_hashes: Some(VerifyHashes::Always),
+            clobber_policy: Some(ClobberPolicy::Error),
             experimental: ExperimentalConfig {
                 use_environment_activation_cache: Some(true),
+                local_stats: Some(true),
             },
             loaded_from: Vec::from([PathBuf::from_str("test").unwrap()]),
             force_activate: Some(true),
@@ -1430,6 +2371,9 @@ UNUSED = "unused"
                 ]),
                 index_url: Some(Url::parse("https://conda.anaconda.org/conda-forge").unwrap()),
                 keyring_provider: Some(KeyringProvider::Subprocess),
+                find_links: Vec::from([FindLinks::Path(PathBuf::from("wheels"))]),
+                cache_refresh: Some(true),
+                cache_refresh_packages: Vec::from(["numpy".to_string()]),
             },
             repodata_config: RepodataConfig {
                 default: RepodataChannelConfig {
@@ -1443,6 +2387,39 @@ UNUSED = "unused"
                     RepodataChannelConfig::default(),
                 )]),
             },
+            build: BuildConfig {
+                cache: BuildCacheConfig {
+                    remote: Some(Url::parse("https://cache.example.com/builds/").unwrap()),
+                    upload: Some(true),
+                },
+            },
+            trust: TrustConfig {
+                public_keys: vec![
+                    "RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3".to_string()
+                ],
+                channels: vec![Url::parse("https://conda.example.com/internal").unwrap()],
+            },
+            cache: CacheConfig {
+                http_backend: Some(HttpCacheBackend::Disabled),
+                solves: Some(false),
+            },
+            network: NetworkConfig {
+                default: NetworkChannelConfig {
+                    max_bandwidth: Some(Bandwidth::from_bytes_per_second(10_000_000)),
+                },
+                per_channel: HashMap::from([(
+                    Url::parse("https://conda.anaconda.org/conda-forge").unwrap(),
+                    NetworkChannelConfig {
+                        max_bandwidth: Some(Bandwidth::from_bytes_per_second(1 << 20)),
+                    },
+                )]),
+            },
+            self_update: SelfUpdateConfig {
+                channel: Some(ReleaseChannel::Beta),
+                public_keys: vec![
+                    "RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3".to_string()
+                ],
+            },
         };
         let original_other = other.clone();
         config = config.merge_config(other);
@@ -1666,6 +2643,160 @@ UNUSED = "unused"
             Some(KeyringProvider::Subprocess)
         );
 
+        config
+            .set(
+                "pypi-config.find-links",
+                Some(r#"["./wheels", "https://example.com/wheels/"]"#.to_string()),
+            )
+            .unwrap();
+        assert_eq!(
+            config.pypi_config().find_links,
+            vec![
+                FindLinks::Path(PathBuf::from("./wheels")),
+                FindLinks::Url(Url::parse("https://example.com/wheels/").unwrap())
+            ]
+        );
+
+        // The find links are saved, also when they are the only PyPI setting.
+        let mut find_links_only = Config::default();
+        find_links_only.pypi_config.find_links = config.pypi_config().find_links.clone();
+        let (saved, _) =
+            Config::from_toml(&toml_edit::ser::to_string_pretty(&find_links_only).unwrap())
+                .unwrap();
+        assert_eq!(
+            saved.pypi_config().find_links,
+            config.pypi_config().find_links
+        );
+
+        config
+            .set(
+                "build.cache.remote",
+                Some("https://cache.example.com/builds/".to_string()),
+            )
+            .unwrap();
+        config
+            .set("build.cache.upload", Some("true".to_string()))
+            .unwrap();
+        assert_eq!(
+            config.build().cache.remote,
+            Some(Url::parse("https://cache.example.com/builds/").unwrap())
+        );
+        assert!(config.build().cache.upload());
+
+        config
+            .set(
+                "trust.channels",
+                Some(r#"["https://conda.example.com/internal"]"#.to_string()),
+            )
+            .unwrap();
+        assert!(config
+            .trust()
+            .is_trusted_channel(&Url::parse("https://conda.example.com/internal/").unwrap()));
+        assert!(!config
+            .trust()
+            .is_trusted_channel(&Url::parse("https://conda.anaconda.org/conda-forge/").unwrap()));
+
+        config
+            .set("cache.http-backend", Some("disabled".to_string()))
+            .unwrap();
+        assert_eq!(config.cache().http_backend(), HttpCacheBackend::Disabled);
+        assert!(config
+            .set("cache.http-backend", Some("memory".to_string()))
+            .is_err());
+        assert!(config.cache().solves());
+        config
+            .set("cache.solves", Some("false".to_string()))
+            .unwrap();
+        assert!(!config.cache().solves());
+
+        config
+            .set("network.max-bandwidth", Some("10MB/s".to_string()))
+            .unwrap();
+        assert_eq!(
+            config.network().default.max_bandwidth,
+            Some(Bandwidth::from_bytes_per_second(10_000_000))
+        );
+        assert!(config
+            .set("network.max-bandwidth", Some("fast".to_string()))
+            .is_err());
+
+        config
+            .set("self-update.channel", Some("beta".to_string()))
+            .unwrap();
+        assert_eq!(config.self_update().channel(), ReleaseChannel::Beta);
+        assert!(config
+            .set("self-update.channel", Some("alpha".to_string()))
+            .is_err());
+
+        config
+            .set("pypi-config.cache-refresh", Some("true".to_string()))
+            .unwrap();
+        assert_eq!(config.pypi_config().cache_refresh, Some(true));
+
+        config
+            .set(
+                "pypi-config.cache-refresh-packages",
+                Some(r#"["numpy"]"#.to_string()),
+            )
+            .unwrap();
+        assert_eq!(
+            config.pypi_config().cache_refresh_packages,
+            vec!["numpy".to_string()]
+        );
+
+        config
+            .set("vendor-dir", Some("./vendor".to_string()))
+            .unwrap();
+        assert_eq!(config.vendor_dir(), Some(&PathBuf::from("./vendor")));
+
+        config
+            .set(
+                "lock-platforms",
+                Some(r#"["linux-64", "osx-arm64"]"#.to_string()),
+            )
+            .unwrap();
+        assert_eq!(
+            config.lock_platforms(),
+            Some([Platform::Linux64, Platform::OsxArm64].as_slice())
+        );
+
+        config
+            .set("verify-hashes", Some("lock-only".to_string()))
+            .unwrap();
+        assert_eq!(config.verify_hashes(), VerifyHashes::LockOnly);
+        assert!(config
+            .set("verify-hashes", Some("sometimes".to_string()))
+            .is_err());
+
+        assert_eq!(config.clobber_policy(), ClobberPolicy::Warn);
+        config
+            .set("clobber-policy", Some("error".to_string()))
+            .unwrap();
+        assert_eq!(config.clobber_policy(), ClobberPolicy::Error);
+        assert!(config
+            .set("clobber-policy", Some("ignore".to_string()))
+            .is_err());
+
+        // The activation cache is on by default, the experimental setting can
+        // still turn it off.
+        assert!(config.activation_cache());
+        config
+            .set(
+                "experimental.use-environment-activation-cache",
+                Some("false".to_string()),
+            )
+            .unwrap();
+        assert!(!config.activation_cache());
+        config
+            .set("activation-cache", Some("true".to_string()))
+            .unwrap();
+        assert!(config.activation_cache());
+        let cli = Config::from(ConfigCliActivation {
+            no_activation_cache: true,
+            ..Default::default()
+        });
+        assert!(!config.clone().merge_config(cli).activation_cache());
+
         config.set("change-ps1", None).unwrap();
         assert_eq!(config.change_ps1, None);
 
@@ -1687,6 +2818,16 @@ UNUSED = "unused"
 
         assert_eq!(config.max_concurrent_downloads(), 1);
 
+        config
+            .set("concurrency.installs", Some("4".to_string()))
+            .unwrap();
+        assert_eq!(config.max_concurrent_installs(), 4);
+        config.set("concurrency.installs", None).unwrap_err();
+        config
+            .set("concurrency.installs", Some("0".to_string()))
+            .unwrap_err();
+        assert_eq!(config.max_concurrent_installs(), 4);
+
         config.set("unknown-key", None).unwrap_err();
     }
 
@@ -1813,4 +2954,127 @@ UNUSED = "unused"
         assert_eq!(anaconda_config.disable_zstd, Some(false));
         assert_eq!(anaconda_config.disable_sharded, None);
     }
+
+    #[test]
+    fn test_repodata_config_with_fallbacks() {
+        let toml = r#"
+            [repodata-config."https://prefix.dev/conda-forge"]
+            disable-sharded = false
+
+            [repodata-config."https://conda.anaconda.org/conda-forge"]
+            disable-zstd = true
+        "#;
+        let (config, _) = Config::from_toml(toml).unwrap();
+        let prefix = Url::parse("https://prefix.dev/conda-forge/").unwrap();
+        let anaconda = Url::parse("https://conda.anaconda.org/conda-forge/").unwrap();
+        let internal = Url::parse("https://conda.example.com/internal/").unwrap();
+
+        let mut state = ShardedFallbackState::default();
+        for channel in [&prefix, &anaconda, &internal] {
+            state.channels.insert(
+                channel.clone(),
+                ShardedFallback {
+                    error: "not found".to_string(),
+                    pixi_version: consts::PIXI_VERSION.to_string(),
+                },
+            );
+        }
+        let repodata_config = config.repodata_config_with_fallbacks(&state);
+
+        // The explicitly configured value is kept.
+        assert_eq!(
+            repodata_config.channel(&prefix).disable_sharded,
+            Some(false)
+        );
+
+        // The other channels fall back to the full repodata, and keep the rest
+        // of their configuration.
+        let anaconda_config = repodata_config.channel(&anaconda);
+        assert_eq!(anaconda_config.disable_sharded, Some(true));
+        assert_eq!(anaconda_config.disable_zstd, Some(true));
+        assert_eq!(
+            repodata_config.channel(&internal).disable_sharded,
+            Some(true)
+        );
+        assert_eq!(
+            config.repodata_config.channel(&internal).disable_sharded,
+            None
+        );
+    }
+
+    #[test]
+    fn test_repodata_config_channel_prefix() {
+        let toml = r#"
+            [repodata-config."https://prefix.dev/conda-forge"]
+            disable-sharded = true
+        "#;
+        let (config, _) = Config::from_toml(toml).unwrap();
+        let channel = |url: &str| {
+            config
+                .repodata_config
+                .channel(&Url::parse(url).unwrap())
+                .disable_sharded
+        };
+
+        assert_eq!(channel("https://prefix.dev/conda-forge"), Some(true));
+        assert_eq!(channel("https://prefix.dev/conda-forge/"), Some(true));
+        assert_eq!(
+            channel("https://prefix.dev/conda-forge/label/dev/"),
+            Some(true)
+        );
+        // Only whole path segments match.
+        assert_eq!(channel("https://prefix.dev/conda-forge-nightly/"), None);
+        assert_eq!(channel("https://prefix.dev.example.com/conda-forge/"), None);
+        assert_eq!(channel("http://prefix.dev/conda-forge/"), None);
+    }
+
+    #[test]
+    fn test_network_config() {
+        let toml = r#"
+            [network]
+            max-bandwidth = "10MB/s"
+
+            [network."https://conda.anaconda.org/conda-forge"]
+            max-bandwidth = "512 KiB/s"
+        "#;
+        let (config, _) = Config::from_toml(toml).unwrap();
+        let network = config.network();
+        assert_eq!(
+            network.default.max_bandwidth,
+            Some(Bandwidth::from_bytes_per_second(10_000_000))
+        );
+        assert_eq!(
+            network
+                .per_channel
+                .get(&Url::from_str("https://conda.anaconda.org/conda-forge").unwrap())
+                .unwrap()
+                .max_bandwidth,
+            Some(Bandwidth::from_bytes_per_second(512 * 1024))
+        );
+    }
+
+    #[rstest]
+    #[case("10MB/s", 10_000_000)]
+    #[case("10mb", 10_000_000)]
+    #[case("1.5 GB/s", 1_500_000_000)]
+    #[case("512KiB/s", 512 * 1024)]
+    #[case("100k/s", 100_000)]
+    #[case("2048", 2048)]
+    fn test_parse_bandwidth(#[case] input: &str, #[case] bytes: u64) {
+        let bandwidth: Bandwidth = input.parse().unwrap();
+        assert_eq!(bandwidth.bytes_per_second(), bytes);
+        assert_eq!(
+            bandwidth.to_string().parse::<Bandwidth>().unwrap(),
+            bandwidth
+        );
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("fast")]
+    #[case("0MB/s")]
+    #[case("10 TB/s")]
+    fn test_parse_invalid_bandwidth(#[case] input: &str) {
+        assert!(input.parse::<Bandwidth>().is_err());
+    }
 }
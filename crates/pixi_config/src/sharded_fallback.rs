@@ -0,0 +1,251 @@
+//! Sharded repodata is used for all channels by default. Channels for which
+//! the sharded repodata can't be fetched, while the full repodata can, are
+//! remembered in a small state file in the repodata cache. The full repodata
+//! is used for these channels from then on, without any configuration.
+
+use std::{collections::BTreeMap, future::Future, io::Write, path::Path};
+
+use pixi_consts::consts;
+use rattler_conda_types::{Channel, MatchSpec, Platform};
+use rattler_repodata_gateway::Gateway;
+use reqwest_middleware::ClientWithMiddleware;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{get_cache_dir, Config};
+
+/// A channel that falls back to the full repodata.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct ShardedFallback {
+    /// The error that occurred while fetching the sharded repodata.
+    pub error: String,
+
+    /// The version of pixi that recorded the failure.
+    pub pixi_version: String,
+}
+
+/// The channels that fall back to the full repodata, stored in the
+/// [`consts::SHARDED_REPODATA_FALLBACK_FILE`] in the repodata cache.
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ShardedFallbackState {
+    #[serde(default)]
+    pub channels: BTreeMap<Url, ShardedFallback>,
+}
+
+impl ShardedFallbackState {
+    /// Reads the state from the repodata cache directory. A missing or
+    /// invalid state file results in an empty state.
+    ///
+    /// Failures recorded by another version of pixi are ignored, so every new
+    /// version tries the sharded repodata of these channels again.
+    pub fn from_cache_dir(repodata_cache_dir: &Path) -> Self {
+        let path = repodata_cache_dir.join(consts::SHARDED_REPODATA_FALLBACK_FILE);
+        let Ok(contents) = fs_err::read_to_string(&path) else {
+            return Self::default();
+        };
+        let mut state: Self = serde_json::from_str(&contents).unwrap_or_else(|err| {
+            tracing::debug!("ignoring invalid state file {}: {err}", path.display());
+            Self::default()
+        });
+        state
+            .channels
+            .retain(|_, fallback| fallback.pixi_version == consts::PIXI_VERSION);
+        state
+    }
+
+    /// Returns true if the channel falls back to the full repodata.
+    pub fn contains(&self, channel: &Url) -> bool {
+        self.channels.contains_key(channel)
+    }
+
+    /// Remembers that the sharded repodata of the channel can't be used and
+    /// writes the state to the repodata cache directory.
+    ///
+    /// The state file is updated under a lock, so failures recorded by
+    /// concurrent solves are not lost, and replaced atomically, so readers
+    /// never see a partially written file.
+    pub fn record(repodata_cache_dir: &Path, channel: Url, error: String) -> std::io::Result<()> {
+        fs_err::create_dir_all(repodata_cache_dir)?;
+        let lock_file = std::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(repodata_cache_dir.join(consts::SHARDED_REPODATA_FALLBACK_LOCK_FILE))?;
+        let mut lock = fd_lock::RwLock::new(lock_file);
+        let _guard = lock.write()?;
+
+        let mut state = Self::from_cache_dir(repodata_cache_dir);
+        state.channels.insert(
+            channel,
+            ShardedFallback {
+                error,
+                pixi_version: consts::PIXI_VERSION.to_string(),
+            },
+        );
+        let mut file = tempfile::NamedTempFile::new_in(repodata_cache_dir)?;
+        file.write_all(serde_json::to_string_pretty(&state)?.as_bytes())?;
+        file.persist(repodata_cache_dir.join(consts::SHARDED_REPODATA_FALLBACK_FILE))
+            .map_err(|err| err.error)?;
+        Ok(())
+    }
+}
+
+impl Config {
+    /// Runs a repodata query with the given gateway. If the query fails, the
+    /// channels whose sharded repodata can't be fetched while their full
+    /// repodata can are recorded in the [`ShardedFallbackState`], and the query
+    /// is run once more with a new gateway that uses the full repodata of these
+    /// channels.
+    pub async fn query_with_sharded_fallback<T, E, F, Fut>(
+        &self,
+        client: &ClientWithMiddleware,
+        gateway: Gateway,
+        channels: &[Channel],
+        platforms: &[Platform],
+        specs: &[MatchSpec],
+        query: F,
+    ) -> Result<T, E>
+    where
+        F: Fn(Gateway) -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        match query(gateway).await {
+            Ok(result) => Ok(result),
+            Err(err) => {
+                if self
+                    .record_sharded_fallbacks(client, channels, platforms, specs)
+                    .await
+                {
+                    query(self.gateway(client.clone())).await
+                } else {
+                    Err(err)
+                }
+            }
+        }
+    }
+
+    /// Finds the channels whose sharded repodata can't be fetched while their
+    /// full repodata can, and remembers them so the full repodata is used for
+    /// these channels from now on. Returns true if a channel was added to the
+    /// [`ShardedFallbackState`], in which case fetching the repodata with a
+    /// new gateway is worth another try.
+    pub async fn record_sharded_fallbacks(
+        &self,
+        client: &ClientWithMiddleware,
+        channels: &[Channel],
+        platforms: &[Platform],
+        specs: &[MatchSpec],
+    ) -> bool {
+        let Ok(cache_dir) = get_cache_dir() else {
+            return false;
+        };
+        let repodata_cache_dir = cache_dir.join(consts::CONDA_REPODATA_CACHE_DIR);
+        let repodata_config = self.repodata_config_with_fallbacks(
+            &ShardedFallbackState::from_cache_dir(&repodata_cache_dir),
+        );
+
+        // A gateway that never uses sharded repodata, to find out whether the
+        // full repodata of a channel is available.
+        let mut full_config = self.clone();
+        full_config.repodata_config.default.disable_sharded = Some(true);
+        for channel_config in full_config.repodata_config.per_channel.values_mut() {
+            channel_config.disable_sharded = Some(true);
+        }
+        let sharded_gateway = self.gateway(client.clone());
+        let full_gateway = full_config.gateway(client.clone());
+
+        let mut recorded = false;
+        for channel in channels {
+            let url = channel.base_url.url();
+            if repodata_config.channel(url).disable_sharded == Some(true) {
+                continue;
+            }
+            let query = |gateway: &Gateway| {
+                gateway.query(
+                    [channel.clone()],
+                    platforms.iter().copied(),
+                    specs.iter().cloned(),
+                )
+            };
+            let Err(err) = query(&sharded_gateway).await else {
+                continue;
+            };
+            if query(&full_gateway).await.is_err() {
+                // The channel itself can't be reached, falling back won't help.
+                continue;
+            }
+
+            tracing::warn!(
+                "failed to fetch the sharded repodata of '{url}', using the full repodata of this channel from now on: {err}"
+            );
+            match ShardedFallbackState::record(&repodata_cache_dir, url.clone(), err.to_string()) {
+                Ok(()) => recorded = true,
+                Err(err) => {
+                    tracing::debug!("failed to record the sharded repodata fallback: {err}")
+                }
+            }
+        }
+        recorded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_fallback() {
+        let temp = tempfile::tempdir().unwrap();
+        let channel = Url::parse("https://conda.example.com/internal/").unwrap();
+        assert!(!ShardedFallbackState::from_cache_dir(temp.path()).contains(&channel));
+
+        ShardedFallbackState::record(temp.path(), channel.clone(), "not found".into()).unwrap();
+        let state = ShardedFallbackState::from_cache_dir(temp.path());
+        assert!(state.contains(&channel));
+        assert_eq!(state.channels[&channel].error, "not found");
+
+        // Failures recorded by another version of pixi are retried.
+        let mut outdated = state.clone();
+        outdated.channels.get_mut(&channel).unwrap().pixi_version = "0.0.1".into();
+        fs_err::write(
+            temp.path().join(consts::SHARDED_REPODATA_FALLBACK_FILE),
+            serde_json::to_string(&outdated).unwrap(),
+        )
+        .unwrap();
+        assert!(!ShardedFallbackState::from_cache_dir(temp.path()).contains(&channel));
+    }
+
+    #[test]
+    fn test_record_concurrently() {
+        let temp = tempfile::tempdir().unwrap();
+        let channels = (0..8)
+            .map(|i| Url::parse(&format!("https://conda.example.com/channel-{i}/")).unwrap())
+            .collect::<Vec<_>>();
+
+        std::thread::scope(|scope| {
+            for channel in &channels {
+                scope.spawn(|| {
+                    ShardedFallbackState::record(temp.path(), channel.clone(), "not found".into())
+                        .unwrap()
+                });
+            }
+        });
+
+        // None of the failures is lost, and no temporary files are left behind.
+        let state = ShardedFallbackState::from_cache_dir(temp.path());
+        assert!(channels.iter().all(|channel| state.contains(channel)));
+        let mut files = fs_err::read_dir(temp.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        files.sort();
+        assert_eq!(
+            files,
+            [
+                consts::SHARDED_REPODATA_FALLBACK_LOCK_FILE,
+                consts::SHARDED_REPODATA_FALLBACK_FILE
+            ]
+        );
+    }
+}
@@ -21,11 +21,14 @@ pub const PIXI_VERSION: &str = match option_env!("PIXI_VERSION") {
 pub const PREFIX_FILE_NAME: &str = "pixi_env_prefix";
 pub const ENVIRONMENTS_DIR: &str = "envs";
 pub const SOLVE_GROUP_ENVIRONMENTS_DIR: &str = "solve-group-envs";
+pub const LOCKS_DIR: &str = "locks";
 pub const PYPI_DEPENDENCIES: &str = "pypi-dependencies";
+pub const R_DEPENDENCIES: &str = "r-dependencies";
 pub const DEPENDENCIES: &str = "dependencies";
 pub const SYSTEM_REQUIREMENTS: &str = "system-requirements";
 pub const TASK_CACHE_DIR: &str = "task-cache-v0";
 pub const ACTIVATION_ENV_CACHE_DIR: &str = "activation-env-v0";
+pub const LOCK_FILE_STATE_FILE: &str = "lock-file-state-v0";
 pub const PIXI_UV_INSTALLER: &str = "uv-pixi";
 pub const CONDA_PACKAGE_CACHE_DIR: &str = rattler_cache::PACKAGE_CACHE_DIR;
 pub const CONDA_REPODATA_CACHE_DIR: &str = rattler_cache::REPODATA_CACHE_DIR;
@@ -33,11 +36,17 @@ pub const CONDA_REPODATA_CACHE_DIR: &str = rattler_cache::REPODATA_CACHE_DIR;
 pub const CONDA_META_DIR: &str = "conda-meta";
 pub const PYPI_CACHE_DIR: &str = "uv-cache";
 pub const CONDA_PYPI_MAPPING_CACHE_DIR: &str = "conda-pypi-mapping";
+pub const HTTP_CACHE_DIR: &str = "http-cache-v0";
 pub const CACHED_ENVS_DIR: &str = "cached-envs-v0";
 // TODO: CACHED_BUILD_ENVS_DIR was deprecated in favor of CACHED_BUILD_ENVS_DIR. This constant will be removed in a future release.
 pub const _CACHED_BUILD_ENVS_DIR: &str = "cached-build-envs-v0";
 pub const CACHED_BUILD_TOOL_ENVS_DIR: &str = "cached-build-tool-envs-v0";
 pub const CACHED_GIT_DIR: &str = "git-cache-v0";
+pub const CACHED_URL_SOURCES_DIR: &str = "url-sources-v0";
+pub const CACHED_GLOBAL_BUILD_DIR: &str = "global-build-v0";
+pub const CACHED_SOLVES_DIR: &str = "solves-v0";
+pub const SHARDED_REPODATA_FALLBACK_FILE: &str = "sharded-fallback-v0.json";
+pub const SHARDED_REPODATA_FALLBACK_LOCK_FILE: &str = ".sharded-fallback.lock";
 
 /// The default config directory for pixi, typically at $XDG_CONFIG_HOME/$PIXI_CONFIG_DIR or $HOME/.config/$PIXI_CONFIG_DIR.
 pub const CONFIG_DIR: &str = match option_env!("PIXI_CONFIG_DIR") {
@@ -80,6 +89,8 @@ pub const CONDA_INSTALLER: &str = "conda";
 pub const ONE_TIME_MESSAGES_DIR: &str = "one-time-messages";
 
 pub const ENVIRONMENT_FILE_NAME: &str = "pixi";
+pub const EXTERNAL_MANAGERS_FILE_NAME: &str = "pixi-external-managers.json";
+pub const STATS_FILE_NAME: &str = "stats.jsonl";
 
 pub static TASK_STYLE: LazyLock<Style> = LazyLock::new(|| Style::new().blue());
 pub static PLATFORM_STYLE: LazyLock<Style> = LazyLock::new(|| Style::new().yellow());
@@ -89,6 +100,8 @@ pub static FEATURE_STYLE: LazyLock<Style> = LazyLock::new(|| Style::new().cyan()
 pub static SOLVE_GROUP_STYLE: LazyLock<Style> = LazyLock::new(|| Style::new().cyan());
 pub static DEFAULT_PYPI_INDEX_URL: LazyLock<Url> =
     LazyLock::new(|| Url::parse("https://pypi.org/simple").unwrap());
+pub static PYTORCH_INDEX_URL: LazyLock<Url> =
+    LazyLock::new(|| Url::parse("https://download.pytorch.org/whl/").unwrap());
 
 pub struct CondaEmoji;
 
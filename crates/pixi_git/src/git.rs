@@ -7,6 +7,7 @@
 ///   * `GitDatabase` and `GitRepository` that represents a local clone of a remote repository's database.
 use std::{
     fmt::Display,
+    num::NonZeroU32,
     path::{Path, PathBuf},
     process::Command,
     str::FromStr,
@@ -24,6 +25,8 @@ use crate::sha::{GitOid, GitSha};
 /// checkout is ready to go. See [`GitCheckout::reset`] for why we need this.
 const CHECKOUT_READY_LOCK: &str = ".ok";
 pub const GIT_DIR: &str = "GIT_DIR";
+/// Prevents Git LFS from downloading the LFS files during a checkout.
+const GIT_LFS_SKIP_SMUDGE: &str = "GIT_LFS_SKIP_SMUDGE";
 
 #[derive(Debug, thiserror::Error)]
 pub enum GitError {
@@ -187,6 +190,68 @@ impl Display for GitReference {
     }
 }
 
+/// Options that control how much of a repository is fetched and checked out.
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[serde(rename_all = "kebab-case")]
+pub struct GitFetchOptions {
+    /// Only fetch this many commits of history, instead of the full history
+    /// of the repository.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub depth: Option<NonZeroU32>,
+
+    /// Whether the submodules of the repository are checked out, defaults to
+    /// `true`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub submodules: Option<bool>,
+
+    /// Whether the Git LFS files of the repository are fetched. Without it
+    /// only the LFS pointer files are checked out.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub lfs: bool,
+}
+
+impl GitFetchOptions {
+    /// Returns true if the submodules of the repository are checked out.
+    pub fn submodules(&self) -> bool {
+        self.submodules.unwrap_or(true)
+    }
+
+    /// Returns the name of the database directory of a repository. A shallow
+    /// database lacks the history that a fetch of the full history expects,
+    /// so databases of different depths are kept apart.
+    pub(crate) fn db_dir_name(&self, ident: &str) -> String {
+        match self.depth {
+            Some(depth) => format!("{ident}-depth-{depth}"),
+            None => ident.to_string(),
+        }
+    }
+
+    /// Returns the name of the directory of a checkout of the given revision.
+    /// Checkouts with and without submodules or LFS files contain different
+    /// files, so they are kept apart.
+    pub(crate) fn checkout_dir_name(&self, short_id: &str) -> String {
+        let mut name = short_id.to_string();
+        if !self.submodules() {
+            name.push_str("-no-submodules");
+        }
+        if self.lfs {
+            name.push_str("-lfs");
+        }
+        name
+    }
+}
+
 /// A remote repository. It gets cloned into a local [`GitDatabase`].
 #[derive(PartialEq, Clone, Debug)]
 pub(crate) struct GitRemote {
@@ -218,12 +283,13 @@ impl GitRemote {
         db: Option<GitDatabase>,
         reference: &GitReference,
         locked_rev: Option<GitOid>,
+        depth: Option<NonZeroU32>,
         client: &ClientWithMiddleware,
     ) -> miette::Result<(GitDatabase, GitOid)> {
         let locked_ref = locked_rev.map(|oid| GitReference::FullCommit(oid.to_string()));
         let reference = locked_ref.as_ref().unwrap_or(reference);
         if let Some(mut db) = db {
-            fetch(&mut db.repo, self.url.as_str(), reference, client)
+            fetch(&mut db.repo, self.url.as_str(), reference, depth, client)
                 .with_context(|| format!("failed to fetch into: {}", into.display()))?;
 
             let resolved_commit_hash = match locked_rev {
@@ -245,7 +311,7 @@ impl GitRemote {
 
         fs_err::create_dir_all(into).into_diagnostic()?;
         let mut repo = GitRepository::init(into)?;
-        fetch(&mut repo, self.url.as_str(), reference, client)
+        fetch(&mut repo, self.url.as_str(), reference, depth, client)
             .with_context(|| format!("failed to clone into: {}", into.display()))?;
         let rev = match locked_rev {
             Some(rev) => rev,
@@ -275,8 +341,15 @@ pub(crate) struct GitDatabase {
 }
 
 impl GitDatabase {
-    /// Checkouts to a revision at `destination` from this database.
-    pub(crate) fn copy_to(&self, rev: GitOid, destination: &Path) -> miette::Result<GitCheckout> {
+    /// Checkouts to a revision at `destination` from this database. The
+    /// `remote` is used to fetch the LFS files, if requested by the `options`.
+    pub(crate) fn copy_to(
+        &self,
+        rev: GitOid,
+        destination: &Path,
+        remote: &Url,
+        options: &GitFetchOptions,
+    ) -> miette::Result<GitCheckout> {
         // If the existing checkout exists, and it is fresh, use it.
         // A non-fresh checkout can happen if the checkout operation was
         // interrupted. In that case, the checkout gets deleted and a new
@@ -287,7 +360,7 @@ impl GitDatabase {
             .filter(GitCheckout::is_fresh)
         {
             Some(co) => co,
-            None => GitCheckout::clone_into(destination, self, rev)?,
+            None => GitCheckout::clone_into(destination, self, rev, remote, options)?,
         };
         Ok(checkout)
     }
@@ -385,7 +458,13 @@ impl GitCheckout {
 
     /// Clone a repo for a `revision` into a local path from a `database`.
     /// This is a filesystem-to-filesystem clone.
-    fn clone_into(into: &Path, database: &GitDatabase, revision: GitOid) -> miette::Result<Self> {
+    fn clone_into(
+        into: &Path,
+        database: &GitDatabase,
+        revision: GitOid,
+        remote: &Url,
+        options: &GitFetchOptions,
+    ) -> miette::Result<Self> {
         tracing::debug!("cloning into {:?} from {:?}", database.repo.path, into);
         let dirname = into.parent().expect("into path must have a parent");
         fs_err::create_dir_all(dirname).into_diagnostic()?;
@@ -411,7 +490,7 @@ impl GitCheckout {
 
         let repo = GitRepository::open(into)?;
         let checkout = GitCheckout::new(revision, repo);
-        checkout.reset()?;
+        checkout.reset(remote, options)?;
         Ok(checkout)
     }
 
@@ -439,36 +518,76 @@ impl GitCheckout {
     /// *doesn't* exist, and then once we're done we create the file.
     ///
     /// [`.ok`]: CHECKOUT_READY_LOCK
-    fn reset(&self) -> miette::Result<()> {
+    fn reset(&self, remote: &Url, options: &GitFetchOptions) -> miette::Result<()> {
         let ok_file = self.repo.path.join(CHECKOUT_READY_LOCK);
         let _ = fs_err::remove_file(&ok_file);
 
         tracing::debug!("reset {} to {}", self.repo.path.display(), self.revision);
 
-        // Perform the hard reset.
+        // Perform the hard reset. The LFS files are never downloaded by the
+        // smudge filter, because the database that is checked out from doesn't
+        // contain them.
         let output = Command::new(GIT.as_ref().into_diagnostic()?)
             .arg("reset")
             .arg("--hard")
             .arg(self.revision.as_str())
+            .env(GIT_LFS_SKIP_SMUDGE, "1")
             .current_dir(&self.repo.path)
             .output();
 
         output.into_diagnostic()?;
 
         // Update submodules (`git submodule update --recursive`).
-        Command::new(GIT.as_ref().into_diagnostic()?)
-            .arg("submodule")
-            .arg("update")
-            .arg("--recursive")
-            .arg("--init")
-            .current_dir(&self.repo.path)
-            .output()
-            .map(drop)
-            .into_diagnostic()?;
+        if options.submodules() {
+            let mut cmd = Command::new(GIT.as_ref().into_diagnostic()?);
+            cmd.arg("submodule")
+                .arg("update")
+                .arg("--recursive")
+                .arg("--init");
+            if let Some(depth) = options.depth {
+                cmd.arg("--depth").arg(depth.to_string());
+            }
+            cmd.env(GIT_LFS_SKIP_SMUDGE, "1")
+                .current_dir(&self.repo.path)
+                .output()
+                .map(drop)
+                .into_diagnostic()?;
+        }
+
+        if options.lfs {
+            self.fetch_lfs(remote)?;
+        }
 
         fs_err::File::create(ok_file).into_diagnostic()?;
         Ok(())
     }
+
+    /// Downloads the LFS files of the checked out revision from the `remote`
+    /// and replaces the LFS pointer files in the working tree with them.
+    fn fetch_lfs(&self, remote: &Url) -> miette::Result<()> {
+        tracing::debug!("fetching lfs files of {} from {remote}", self.revision);
+        let git = GIT.as_ref().into_diagnostic()?;
+        let commands: [&[&str]; 2] = [
+            &["lfs", "fetch", remote.as_str(), self.revision.as_str()],
+            &["lfs", "checkout"],
+        ];
+        for args in commands {
+            let output = Command::new(git)
+                .args(args)
+                .env_remove(GIT_DIR)
+                .current_dir(&self.repo.path)
+                .output()
+                .into_diagnostic()?;
+            if !output.status.success() {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                miette::bail!(
+                    help = "make sure that Git LFS is installed",
+                    "failed to fetch the LFS files of `{remote}`: {stderr}"
+                );
+            }
+        }
+        Ok(())
+    }
 }
 
 /// Attempts to fetch the given git `reference` for a Git repository.
@@ -483,6 +602,7 @@ pub(crate) fn fetch(
     repo: &mut GitRepository,
     remote_url: &str,
     reference: &GitReference,
+    depth: Option<NonZeroU32>,
     client: &ClientWithMiddleware,
 ) -> miette::Result<()> {
     let oid_to_fetch = match github_fast_path(repo, remote_url, reference, client) {
@@ -573,14 +693,19 @@ pub(crate) fn fetch(
         repo.path.display()
     );
     let result = match refspec_strategy {
-        RefspecStrategy::All => fetch_with_cli(repo, remote_url, refspecs.as_slice(), tags),
+        RefspecStrategy::All => fetch_with_cli(repo, remote_url, refspecs.as_slice(), tags, depth),
         RefspecStrategy::First => {
             // Try each refspec
             let mut errors = refspecs
                 .iter()
                 .map_while(|refspec| {
-                    let fetch_result =
-                        fetch_with_cli(repo, remote_url, std::slice::from_ref(refspec), tags);
+                    let fetch_result = fetch_with_cli(
+                        repo,
+                        remote_url,
+                        std::slice::from_ref(refspec),
+                        tags,
+                        depth,
+                    );
 
                     // Stop after the first success and log failures
                     match fetch_result {
@@ -626,12 +751,16 @@ fn fetch_with_cli(
     url: &str,
     refspecs: &[String],
     tags: bool,
+    depth: Option<NonZeroU32>,
 ) -> miette::Result<()> {
     let mut cmd = Command::new(GIT.as_ref().into_diagnostic()?);
     cmd.arg("fetch");
     if tags {
         cmd.arg("--tags");
     }
+    if let Some(depth) = depth {
+        cmd.arg("--depth").arg(depth.to_string());
+    }
     cmd.arg("--force") // handle force pushes
         .arg("--update-head-ok") // see discussion in #2078
         .arg(url)
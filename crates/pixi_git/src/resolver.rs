@@ -13,7 +13,7 @@ use dashmap::DashMap;
 use reqwest_middleware::ClientWithMiddleware;
 
 use crate::{
-    git::GitReference,
+    git::{GitFetchOptions, GitReference},
     sha::GitSha,
     source::{cache_digest, Fetch, GitSource},
     url::RepositoryUrl,
@@ -51,6 +51,7 @@ impl GitResolver {
     pub async fn fetch(
         &self,
         url: &GitUrl,
+        options: GitFetchOptions,
         client: ClientWithMiddleware,
         cache: PathBuf,
         reporter: Option<Arc<dyn Reporter>>,
@@ -81,7 +82,7 @@ impl GitResolver {
         write_guard.begin().await?;
 
         // Fetch the Git repository.
-        let source = GitSource::new(url.as_ref().clone(), client, cache);
+        let source = GitSource::new(url.as_ref().clone(), client, cache).with_options(options);
         let source = if let Some(reporter) = reporter {
             source.with_reporter(reporter)
         } else {
@@ -13,7 +13,7 @@ use tracing::{debug, instrument};
 
 use crate::{
     credentials::GIT_STORE,
-    git::GitRemote,
+    git::{GitFetchOptions, GitRemote},
     sha::{GitOid, GitSha},
     url::RepositoryUrl,
     GitUrl, Reporter,
@@ -29,6 +29,8 @@ pub struct GitSource {
     cache: PathBuf,
     /// The reporter to use for this source.
     reporter: Option<Arc<dyn Reporter>>,
+    /// How much of the repository is fetched and checked out.
+    options: GitFetchOptions,
 }
 
 impl GitSource {
@@ -43,6 +45,7 @@ impl GitSource {
             client: client.into(),
             cache: cache.into(),
             reporter: None,
+            options: GitFetchOptions::default(),
         }
     }
 
@@ -55,6 +58,12 @@ impl GitSource {
         }
     }
 
+    /// Set the [`GitFetchOptions`] to use for the [`GitSource`].
+    #[must_use]
+    pub fn with_options(self, options: GitFetchOptions) -> Self {
+        Self { options, ..self }
+    }
+
     /// Fetch the underlying Git repository at the given revision.
     #[instrument(skip(self), fields(repository = %self.git.repository, rev = ?self.git.precise))]
     pub fn fetch(self) -> miette::Result<Fetch> {
@@ -63,7 +72,7 @@ impl GitSource {
 
         // The path to the repo, within the Git database.
         let ident = cache_digest(&canonical);
-        let db_path = self.cache.join("db").join(&ident);
+        let db_path = self.cache.join("db").join(self.options.db_dir_name(&ident));
 
         // Authenticate the URL, if necessary.
         let remote = if let Some(credentials) = GIT_STORE.get(&canonical) {
@@ -98,6 +107,7 @@ impl GitSource {
                     db,
                     &self.git.reference,
                     locked_rev.map(GitOid::from),
+                    self.options.depth,
                     &self.client,
                 )?;
 
@@ -116,10 +126,15 @@ impl GitSource {
             .cache
             .join("checkouts")
             .join(&ident)
-            .join(short_id.as_str());
+            .join(self.options.checkout_dir_name(&short_id));
 
         debug!(" I will copy from {:?} to {:?}", actual_rev, checkout_path);
-        db.copy_to(actual_rev.into(), &checkout_path)?;
+        db.copy_to(
+            actual_rev.into(),
+            &checkout_path,
+            remote.url(),
+            &self.options,
+        )?;
 
         // Report the checkout operation to the reporter.
         if let Some(task) = task {
@@ -169,3 +184,73 @@ pub fn cache_digest(url: &RepositoryUrl) -> String {
     let hash = hasher.finish();
     format!("{:x}", hash)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{num::NonZeroU32, path::Path, process::Command};
+
+    use ::url::Url;
+
+    use super::*;
+    use crate::git::GitReference;
+
+    fn git(dir: &Path, args: &[&str]) -> String {
+        let output = Command::new("git")
+            .args(["-c", "user.name=pixi", "-c", "user.email=pixi@example.com"])
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "git {args:?} failed: {output:?}");
+        String::from_utf8(output.stdout).unwrap().trim().to_string()
+    }
+
+    fn fetch(repository: &Url, cache: &Path, depth: Option<NonZeroU32>) -> Fetch {
+        GitSource::new(
+            GitUrl::from_reference(repository.clone(), GitReference::DefaultBranch),
+            reqwest::Client::new(),
+            cache,
+        )
+        .with_options(GitFetchOptions {
+            depth,
+            ..GitFetchOptions::default()
+        })
+        .fetch()
+        .unwrap()
+    }
+
+    #[test]
+    fn test_fetch_keeps_shallow_database_apart() {
+        let repo = tempfile::tempdir().unwrap();
+        git(repo.path(), &["init"]);
+        fs_err::write(repo.path().join("README.md"), "first").unwrap();
+        git(repo.path(), &["add", "README.md"]);
+        git(repo.path(), &["commit", "-m", "first"]);
+        let first = git(repo.path(), &["rev-parse", "HEAD"]);
+        fs_err::write(repo.path().join("README.md"), "second").unwrap();
+        git(repo.path(), &["commit", "-am", "second"]);
+        let repository = Url::from_directory_path(repo.path()).unwrap();
+
+        let cache = tempfile::tempdir().unwrap();
+        let ident = cache_digest(&RepositoryUrl::new(&repository));
+        let shallow = fetch(&repository, cache.path(), NonZeroU32::new(1));
+        let full = fetch(&repository, cache.path(), None);
+        assert_eq!(shallow.git().precise(), full.git().precise());
+
+        // A fetch of the full history doesn't reuse the shallow database.
+        let shallow_db = cache.path().join("db").join(format!("{ident}-depth-1"));
+        let full_db = cache.path().join("db").join(&ident);
+        assert_eq!(
+            git(&shallow_db, &["rev-parse", "--is-shallow-repository"]),
+            "true"
+        );
+        assert_eq!(
+            git(&full_db, &["rev-parse", "--is-shallow-repository"]),
+            "false"
+        );
+        git(
+            &full_db,
+            &["cat-file", "-e", &format!("{first}^{{commit}}")],
+        );
+    }
+}
@@ -26,6 +26,16 @@ impl Environments {
         self.environments[index.0].as_ref()
     }
 
+    /// Returns a mutable reference to the environment with the given name or
+    /// `None` if it does not exist.
+    pub(crate) fn find_mut<Q>(&mut self, name: &Q) -> Option<&mut Environment>
+    where
+        Q: ?Sized + Hash + Equivalent<EnvironmentName>,
+    {
+        let index = self.by_name.get(name)?;
+        self.environments[index.0].as_mut()
+    }
+
     /// Returns an iterator over all the environments in the project.
     pub fn iter(&self) -> impl Iterator<Item = &Environment> + '_ {
         self.environments.iter().flat_map(Option::as_ref)
@@ -23,6 +23,8 @@ pub enum DependencyError {
     NoDependency(String),
     #[error("No Pypi dependencies.")]
     NoPyPiDependencies,
+    #[error("No R dependencies.")]
+    NoRDependencies,
     #[error(transparent)]
     Pep508ToPyPiRequirementError(#[from] Box<Pep508ToPyPiRequirementError>),
 }
@@ -225,6 +227,9 @@ impl Diagnostic for FeatureNotEnabled {
     }
 }
 
+/// Keys that are only valid in the `[package]` table.
+const PACKAGE_ONLY_KEYS: &[&str] = &["run-dependencies"];
+
 impl Diagnostic for TomlError {
     fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
         let mut additional_spans = None;
@@ -321,6 +326,11 @@ impl Diagnostic for TomlError {
                 }
                 toml_span::ErrorKind::UnexpectedKeys { expected, keys } => {
                     if let Ok((single, _)) = keys.iter().exactly_one() {
+                        if PACKAGE_ONLY_KEYS.contains(&single.as_str()) {
+                            return Some(Box::new(format!(
+                                "{single} can only be specified for a package, did you mean [package.{single}]?"
+                            )));
+                        }
                         if let Some((_, similar)) = expected
                             .iter()
                             .filter_map(|expected| {
@@ -0,0 +1,40 @@
+use std::path::PathBuf;
+
+use indexmap::IndexMap;
+
+use crate::EnvironmentName;
+
+/// The package manager of another ecosystem, like `npm` or the `Pkg` module of
+/// Julia, that installs packages into an environment after pixi installed the
+/// environment itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExternalManager {
+    /// The command that installs the packages, it runs in the activated
+    /// environment.
+    pub cmd: String,
+
+    /// Glob patterns of the files the installation is based on, like
+    /// `package-lock.json`. The command only runs again when one of these
+    /// files changes.
+    pub inputs: Vec<String>,
+
+    /// The working directory of the command, relative to the workspace root.
+    pub cwd: Option<PathBuf>,
+
+    /// The environments that the command runs in, every environment if not
+    /// set.
+    pub environments: Option<Vec<EnvironmentName>>,
+}
+
+impl ExternalManager {
+    /// Returns true if the command runs in the given environment.
+    pub fn runs_in(&self, environment: &EnvironmentName) -> bool {
+        self.environments
+            .as_ref()
+            .map_or(true, |environments| environments.contains(environment))
+    }
+}
+
+/// The external managers of the workspace, as defined in the
+/// `[external-managers]` table, by name.
+pub type ExternalManagers = IndexMap<String, ExternalManager>;
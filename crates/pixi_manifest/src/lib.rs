@@ -5,15 +5,18 @@ mod dependencies;
 mod environment;
 mod environments;
 mod error;
+mod external_managers;
 mod feature;
 mod features_ext;
 mod has_features_iter;
 mod has_manifest_ref;
 mod manifests;
 mod package;
+mod package_overrides;
 mod preview;
 pub mod pypi;
 pub mod pyproject;
+mod r_mapping;
 mod solve_group;
 mod spec_type;
 mod system_requirements;
@@ -30,6 +33,7 @@ pub use channel::PrioritizedChannel;
 pub use dependencies::{CondaDependencies, Dependencies, PyPiDependencies};
 pub use environment::{Environment, EnvironmentName};
 pub use error::TomlError;
+pub use external_managers::{ExternalManager, ExternalManagers};
 pub use feature::{Feature, FeatureName};
 pub use features_ext::FeaturesExt;
 pub use has_features_iter::HasFeaturesIter;
@@ -37,15 +41,20 @@ pub use has_manifest_ref::HasManifestRef;
 use itertools::Itertools;
 pub use manifests::{Manifest, ManifestKind, ManifestSource, PackageManifest, WorkspaceManifest};
 use miette::Diagnostic;
+pub use package::PackageTests;
+pub use package_overrides::{
+    CondaPackageOverride, PackageOverride, PackageOverrides, PypiCondaMapping, PypiPackageOverride,
+};
 pub use preview::{KnownPreviewFeature, Preview, PreviewFeature};
 pub use pypi::pypi_requirement::PyPiRequirement;
+pub use r_mapping::{RMapping, RMappingSource};
 use rattler_conda_types::Platform;
 pub use spec_type::SpecType;
 pub use system_requirements::{LibCFamilyAndVersion, LibCSystemRequirement, SystemRequirements};
 pub use target::{PackageTarget, TargetSelector, Targets, WorkspaceTarget};
 pub use task::{Task, TaskName};
 use thiserror::Error;
-pub use workspace::{ChannelPriority, Workspace};
+pub use workspace::{ChannelPriority, ExcludeNewer, VariantConfigSource, Workspace};
 
 pub use crate::{
     environments::Environments,
@@ -115,6 +115,7 @@ impl Manifest {
         let (parsed, file_name) = match manifest_kind {
             ManifestKind::Pixi => (
                 TomlManifest::from_toml_str(&contents).and_then(|manifest| {
+                    manifest.check_path_dependencies(root)?;
                     manifest.into_manifests(ExternalWorkspaceProperties::default())
                 }),
                 "pixi.toml",
@@ -122,7 +123,12 @@ impl Manifest {
             ManifestKind::Pyproject => {
                 let manifest = match PyProjectManifest::from_toml_str(&contents)
                     .and_then(|m| m.ensure_pixi())
-                {
+                    .and_then(|m| {
+                        if let Some(pixi) = m.tool.as_ref().and_then(|t| t.pixi.as_ref()) {
+                            pixi.check_path_dependencies(root)?;
+                        }
+                        Ok(m)
+                    }) {
                     Ok(manifest) => match manifest.into_manifests() {
                         Ok(manifests) => Ok(manifests),
                         Err(PyProjectToManifestError::TomlError(err)) => Err(err),
@@ -448,6 +454,39 @@ impl Manifest {
         Ok(any_added)
     }
 
+    /// Add an R package to the manifest, by its name on CRAN
+    pub fn add_r_dependency(
+        &mut self,
+        r_name: &str,
+        spec: &PixiSpec,
+        platforms: &[Platform],
+        feature_name: &FeatureName,
+        overwrite_behavior: DependencyOverwriteBehavior,
+    ) -> miette::Result<bool> {
+        let conda_name = self
+            .workspace
+            .r_mapping
+            .conda_name(r_name)
+            .into_diagnostic()?;
+        let mut any_added = false;
+        for platform in to_options(platforms) {
+            // Add the dependency to the manifest
+            match self
+                .get_or_insert_target_mut(platform, Some(feature_name))
+                .try_add_r_dependency(r_name, &conda_name, spec, overwrite_behavior)
+            {
+                Ok(true) => {
+                    self.source
+                        .add_r_dependency(r_name, spec, platform, feature_name)?;
+                    any_added = true;
+                }
+                Ok(false) => {}
+                Err(e) => return Err(e.into()),
+            };
+        }
+        Ok(any_added)
+    }
+
     /// Removes a dependency based on `SpecType`.
     pub fn remove_dependency(
         &mut self,
@@ -515,6 +554,44 @@ impl Manifest {
         Ok(())
     }
 
+    /// Removes an R dependency.
+    pub fn remove_r_dependency(
+        &mut self,
+        r_name: &str,
+        platforms: &[Platform],
+        feature_name: &FeatureName,
+    ) -> miette::Result<()> {
+        let conda_name = self
+            .workspace
+            .r_mapping
+            .conda_name(r_name)
+            .into_diagnostic()?;
+        for platform in crate::to_options(platforms) {
+            // Remove the dependency from the manifest
+            match self
+                .target_mut(platform, feature_name)
+                .ok_or_else(|| {
+                    Self::handle_target_missing(
+                        platform.as_ref(),
+                        feature_name,
+                        consts::R_DEPENDENCIES,
+                    )
+                })?
+                .remove_r_dependency(r_name, &conda_name)
+            {
+                Ok(_) => (),
+                Err(DependencyError::NoDependency(e)) => {
+                    tracing::warn!("Dependency `{}` doesn't exist", e);
+                }
+                Err(e) => return Err(e.into()),
+            };
+            // Remove the dependency from the TOML document
+            self.source
+                .remove_r_dependency(r_name, platform, feature_name)?;
+        }
+        Ok(())
+    }
+
     /// Handles the target missing error cases
     fn handle_target_missing(
         platform: Option<&Platform>,
@@ -2197,6 +2274,96 @@ bar = "*"
         assert_snapshot!(manifest.source.to_string());
     }
 
+    #[test]
+    fn test_r_dependencies() {
+        let file_contents = r#"
+[project]
+name = "foo"
+channels = []
+platforms = ["linux-64", "win-64"]
+
+[r-dependencies]
+ggplot2 = ">=3.5"
+DESeq2 = "*"
+
+[r-mapping]
+DESeq2 = "bioconductor-deseq2"
+            "#;
+        let mut manifest = Manifest::from_str(Path::new("pixi.toml"), file_contents).unwrap();
+        let run_dependencies = |manifest: &Manifest| {
+            manifest
+                .default_feature()
+                .targets
+                .default()
+                .run_dependencies()
+                .unwrap()
+                .keys()
+                .map(|name| name.as_normalized().to_string())
+                .collect_vec()
+        };
+        assert_eq!(
+            run_dependencies(&manifest),
+            vec!["r-ggplot2", "bioconductor-deseq2"]
+        );
+
+        let spec = PixiSpec::Version(VersionSpec::from_str(">=1.15", Strict).unwrap());
+        manifest
+            .add_r_dependency(
+                "data.table",
+                &spec,
+                &[],
+                &FeatureName::Default,
+                DependencyOverwriteBehavior::Overwrite,
+            )
+            .unwrap();
+        manifest
+            .remove_r_dependency("DESeq2", &[], &FeatureName::Default)
+            .unwrap();
+        assert_eq!(
+            run_dependencies(&manifest),
+            vec!["r-ggplot2", "r-data.table"]
+        );
+
+        assert_snapshot!(manifest.source.to_string());
+    }
+
+    #[test]
+    fn test_r_dependency_with_conda_dependency() {
+        let file_contents = r#"
+[project]
+name = "foo"
+channels = []
+platforms = ["linux-64"]
+
+[dependencies]
+r-ggplot2 = "==3.4.0"
+
+[r-dependencies]
+ggplot2 = ">=3.5"
+            "#;
+        let mut manifest = Manifest::from_str(Path::new("pixi.toml"), file_contents).unwrap();
+        let ggplot2 = PackageName::from_str("r-ggplot2").unwrap();
+        let run_dependency = |manifest: &Manifest| {
+            manifest
+                .default_feature()
+                .targets
+                .default()
+                .run_dependencies()
+                .and_then(|deps| deps.get(&ggplot2))
+                .map(|spec| spec.to_toml_value().to_string())
+        };
+
+        // The conda dependency takes precedence over the R dependency.
+        assert_eq!(run_dependency(&manifest).as_deref(), Some("\"==3.4.0\""));
+
+        // Removing the R dependency keeps the conda dependency.
+        manifest
+            .remove_r_dependency("ggplot2", &[], &FeatureName::Default)
+            .unwrap();
+        assert_eq!(run_dependency(&manifest).as_deref(), Some("\"==3.4.0\""));
+        assert!(manifest.source.to_string().contains("r-ggplot2"));
+    }
+
     #[test]
     fn test_add_environment() {
         let contents = r#"
@@ -2378,6 +2545,76 @@ bar = "*"
         "###);
     }
 
+    #[test]
+    fn test_path_dependencies_outside_workspace() {
+        let workspace = tempdir().unwrap();
+        let manifest_path = workspace.path().join("pixi.toml");
+        let contents = format!(
+            r#"
+            {PROJECT_BOILERPLATE}
+            [dependencies]
+            inside = {{ path = "packages/inside" }}
+            outside = {{ path = "packages/../../outside" }}
+            "#
+        );
+
+        let err = Manifest::from_str(&manifest_path, &contents).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("the path dependency 'outside' refers to 'packages/../../outside'"));
+
+        let contents = contents.replace(
+            "[dependencies]",
+            "allow-external-paths = true\n[dependencies]",
+        );
+        let manifest = Manifest::from_str(&manifest_path, contents).unwrap();
+        assert!(manifest.workspace.workspace.allow_external_paths);
+    }
+
+    #[test]
+    fn test_binary_path_dependencies_outside_workspace() {
+        let workspace = tempdir().unwrap();
+        let manifest_path = workspace.path().join("pixi.toml");
+        let contents = format!(
+            r#"
+            {PROJECT_BOILERPLATE}
+            [dependencies]
+            archive = {{ path = "../packages/archive-1.0-h123_0.conda" }}
+
+            [pypi-dependencies]
+            wheel = {{ path = "../wheels/wheel-1.0-py3-none-any.whl" }}
+            "#
+        );
+        Manifest::from_str(&manifest_path, &contents).unwrap();
+    }
+
+    #[test]
+    fn test_pypi_path_dependencies_outside_workspace() {
+        let workspace = tempdir().unwrap();
+        let manifest_path = workspace.path().join("pixi.toml");
+        let contents = format!(
+            r#"
+            {PROJECT_BOILERPLATE}
+            [pypi-dependencies]
+            inside = {{ path = "packages/inside" }}
+
+            [feature.test.target.linux-64.pypi-dependencies]
+            outside = {{ path = "../outside", editable = true }}
+            "#
+        );
+
+        let err = Manifest::from_str(&manifest_path, &contents).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("the pypi path dependency 'outside' refers to '../outside'"));
+
+        let contents = contents.replace(
+            "[pypi-dependencies]",
+            "allow-external-paths = true\n[pypi-dependencies]",
+        );
+        Manifest::from_str(&manifest_path, contents).unwrap();
+    }
+
     #[rstest]
     fn test_docs_pixi_manifests(
         #[files("../../docs/source_files/pixi_tomls/*.toml")] manifest_path: PathBuf,
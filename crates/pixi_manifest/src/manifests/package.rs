@@ -1,5 +1,8 @@
 use crate::target::PackageTarget;
-use crate::{package::Package, PackageBuild, Targets};
+use crate::{
+    package::{Package, PackageTests},
+    PackageBuild, Targets,
+};
 
 /// Holds the parsed content of the package part of a pixi manifest. This
 /// describes the part related to the package only.
@@ -13,4 +16,7 @@ pub struct PackageManifest {
 
     /// Defines the dependencies of the package
     pub targets: Targets<PackageTarget>,
+
+    /// The tests that are executed after the package is built
+    pub tests: Option<PackageTests>,
 }
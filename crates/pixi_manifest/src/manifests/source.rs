@@ -231,6 +231,28 @@ impl ManifestSource {
         Ok(())
     }
 
+    /// Removes an R dependency from the TOML manifest's pixi table
+    /// for either a 'pyproject.toml' and 'pixi.toml'
+    ///
+    /// If will be a no-op if the dependency is not found
+    pub fn remove_r_dependency(
+        &mut self,
+        r_name: &str,
+        platform: Option<Platform>,
+        feature_name: &FeatureName,
+    ) -> Result<(), TomlError> {
+        let table_name = TableName::new()
+            .with_prefix(self.table_prefix())
+            .with_feature_name(Some(feature_name))
+            .with_platform(platform.as_ref())
+            .with_table(Some(consts::R_DEPENDENCIES));
+
+        self.manifest_mut()
+            .get_or_insert_nested_table(table_name.to_string().as_str())
+            .map(|t| t.remove(r_name))?;
+        Ok(())
+    }
+
     /// Adds a conda dependency to the TOML manifest
     ///
     /// If a dependency with the same name already exists, it will be replaced.
@@ -255,6 +277,29 @@ impl ManifestSource {
         Ok(())
     }
 
+    /// Adds an R dependency to the TOML manifest
+    ///
+    /// If a dependency with the same name already exists, it will be replaced.
+    pub fn add_r_dependency(
+        &mut self,
+        r_name: &str,
+        spec: &PixiSpec,
+        platform: Option<Platform>,
+        feature_name: &FeatureName,
+    ) -> Result<(), TomlError> {
+        let dependency_table = TableName::new()
+            .with_prefix(self.table_prefix())
+            .with_platform(platform.as_ref())
+            .with_feature_name(Some(feature_name))
+            .with_table(Some(consts::R_DEPENDENCIES));
+
+        self.manifest_mut()
+            .get_or_insert_nested_table(dependency_table.to_string().as_str())
+            .map(|t| t.insert(r_name, Item::Value(spec.to_toml_value())))?;
+
+        Ok(())
+    }
+
     /// Adds a pypi dependency to the TOML manifest
     ///
     /// If a pypi dependency with the same name already exists, it will be
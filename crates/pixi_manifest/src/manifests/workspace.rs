@@ -7,7 +7,10 @@ use crate::{
     consts,
     environment::{Environment, EnvironmentName},
     environments::Environments,
+    external_managers::ExternalManagers,
     feature::{Feature, FeatureName},
+    package_overrides::PackageOverrides,
+    r_mapping::RMapping,
     solve_group::SolveGroups,
     toml::ExternalWorkspaceProperties,
     workspace::Workspace,
@@ -29,6 +32,17 @@ pub struct WorkspaceManifest {
 
     /// The solve groups that are part of the project.
     pub solve_groups: SolveGroups,
+
+    /// The overrides that are applied to the packages of every environment.
+    pub package_overrides: PackageOverrides,
+
+    /// The conda packages that provide the R packages of the `[r-dependencies]`
+    /// tables.
+    pub r_mapping: RMapping,
+
+    /// The package managers of other ecosystems that install packages into
+    /// the environments.
+    pub external_managers: ExternalManagers,
 }
 
 impl WorkspaceManifest {
@@ -470,4 +484,60 @@ mod tests {
             .collect::<Vec<_>>();
         assert_debug_snapshot!(resolved_win);
     }
+
+    #[test]
+    fn test_build_variants_zip_keys() {
+        let contents = r#"
+        [workspace]
+        name = "foo"
+        channels = []
+        platforms = []
+        build-variants-zip-keys = [["python", "numpy"]]
+
+        [workspace.build-variants]
+        python = ["3.11.*", "3.12.*"]
+        numpy = ["1.26.*", "2.0.*"]
+        "#;
+        let manifest = WorkspaceManifest::from_toml_str(contents).unwrap();
+        assert_eq!(
+            manifest.workspace.build_variants_zip_keys,
+            vec![vec!["python".to_string(), "numpy".to_string()]]
+        );
+
+        let contents = r#"
+        [workspace]
+        name = "foo"
+        channels = []
+        platforms = []
+        build-variants-zip-keys = [["python", "numpy"], ["numpy", "cuda"]]
+        "#;
+        let err = WorkspaceManifest::from_toml_str(contents).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "the build variant 'numpy' is part of more than one group of `build-variants-zip-keys`"
+        );
+    }
+
+    #[test]
+    fn test_empty_build_variant() {
+        let contents = r#"
+        [workspace]
+        name = "foo"
+        channels = []
+        platforms = []
+
+        [workspace.target.win-64.build-variants]
+        python = ["3.12.*"]
+        numpy = []
+        "#;
+        let err = WorkspaceManifest::from_toml_str(contents).unwrap_err();
+        let crate::TomlError::TomlError(err) = err else {
+            panic!("expected a spanned error, found {err:?}");
+        };
+        assert_eq!(&contents[err.span.start..err.span.end], "[]");
+        assert!(
+            matches!(&err.kind, toml_span::ErrorKind::Custom(message) if message == "the build variant 'numpy' needs at least one value"),
+            "{err:?}"
+        );
+    }
 }
@@ -1,6 +1,8 @@
 use std::path::PathBuf;
 
-use rattler_conda_types::Version;
+use indexmap::IndexMap;
+use pixi_spec::PixiSpec;
+use rattler_conda_types::{PackageName, Version};
 use url::Url;
 
 /// Defines the contents of the `[package]` section of the project manifest.
@@ -36,3 +38,16 @@ pub struct Package {
     /// URL of the project documentation
     pub documentation: Option<Url>,
 }
+
+/// Defines the contents of the `[package.tests]` section of the project
+/// manifest. The tests are executed after the package is built, in a clean
+/// environment that contains the built package and the test dependencies.
+#[derive(Debug, Clone, Default)]
+pub struct PackageTests {
+    /// The commands to execute in the test environment. The tests pass when
+    /// all of them exit successfully.
+    pub commands: Vec<String>,
+
+    /// The dependencies of the test environment, besides the package itself.
+    pub dependencies: IndexMap<PackageName, PixiSpec>,
+}
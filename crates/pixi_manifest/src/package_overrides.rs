@@ -0,0 +1,59 @@
+use indexmap::IndexMap;
+use rattler_conda_types::{PackageName, VersionSpec};
+
+use crate::pypi::PyPiPackageName;
+
+/// Describes how a package is treated when it is pulled in by any of the
+/// packages in the solve, regardless of who depends on it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PackageOverride<N, V> {
+    /// Only versions matching the specifier can be selected.
+    Version(V),
+
+    /// Every requirement on the package is replaced by a requirement on the
+    /// given package.
+    ReplaceWith(N),
+
+    /// The package is never installed, requirements on it are ignored.
+    Exclude,
+}
+
+/// An override of a conda package.
+pub type CondaPackageOverride = PackageOverride<PackageName, VersionSpec>;
+
+/// An override of a PyPI package.
+pub type PypiPackageOverride = PackageOverride<PyPiPackageName, pep440_rs::VersionSpecifiers>;
+
+/// The PyPI distribution that a conda package provides, overriding the
+/// mapping of the conda packages to PyPI packages.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PypiCondaMapping {
+    /// The conda package provides the PyPI distribution.
+    Provides(PyPiPackageName),
+
+    /// The conda package doesn't provide any PyPI distribution, it never
+    /// satisfies a PyPI requirement.
+    NotAPypiPackage,
+}
+
+/// The overrides that are applied to all the packages of the workspace, as
+/// defined in the `[package-overrides]`, `[pypi-package-overrides]` and
+/// `[pypi-conda-mapping]` tables.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PackageOverrides {
+    /// Overrides of conda packages.
+    pub conda: IndexMap<PackageName, CondaPackageOverride>,
+
+    /// Overrides of PyPI packages.
+    pub pypi: IndexMap<PyPiPackageName, PypiPackageOverride>,
+
+    /// Overrides of the PyPI distributions that conda packages provide.
+    pub pypi_conda_mapping: IndexMap<PackageName, PypiCondaMapping>,
+}
+
+impl PackageOverrides {
+    /// Returns true if no overrides are defined.
+    pub fn is_empty(&self) -> bool {
+        self.conda.is_empty() && self.pypi.is_empty() && self.pypi_conda_mapping.is_empty()
+    }
+}
@@ -1,6 +1,7 @@
 use std::{hash::Hash, path::PathBuf};
 
-use indexmap::IndexSet;
+use indexmap::{IndexMap, IndexSet};
+use rattler_conda_types::Platform;
 use serde::Serialize;
 use thiserror::Error;
 use url::Url;
@@ -48,6 +49,210 @@ pub enum FindLinksUrlOrPath {
     Url(Url),
 }
 
+/// Overrides of the PEP 508 markers with which the PyPI dependencies are
+/// resolved for a platform, for instance to set the `platform_machine` when
+/// cross-compiling. Markers that are not overridden are derived from the
+/// platform and the python interpreter.
+#[derive(Debug, Clone, PartialEq, Serialize, Eq, Default)]
+pub struct MarkerEnvironmentOverrides {
+    pub implementation_name: Option<String>,
+    pub implementation_version: Option<String>,
+    pub os_name: Option<String>,
+    pub platform_machine: Option<String>,
+    pub platform_python_implementation: Option<String>,
+    pub platform_release: Option<String>,
+    pub platform_system: Option<String>,
+    pub platform_version: Option<String>,
+    pub python_full_version: Option<String>,
+    pub python_version: Option<String>,
+    pub sys_platform: Option<String>,
+}
+
+impl MarkerEnvironmentOverrides {
+    /// Merges the overrides of two features for the same platform, a marker
+    /// can only be overridden with a single value.
+    fn union(
+        &self,
+        other: &MarkerEnvironmentOverrides,
+        platform: Platform,
+    ) -> Result<MarkerEnvironmentOverrides, PypiOptionsMergeError> {
+        let merge =
+            |marker: &str, first: &Option<String>, second: &Option<String>| match (first, second) {
+                (Some(first), Some(second)) if first != second => {
+                    Err(PypiOptionsMergeError::MultipleMarkerValues {
+                        platform,
+                        marker: marker.to_string(),
+                        first: first.clone(),
+                        second: second.clone(),
+                    })
+                }
+                _ => Ok(first.clone().or_else(|| second.clone())),
+            };
+
+        Ok(MarkerEnvironmentOverrides {
+            implementation_name: merge(
+                "implementation_name",
+                &self.implementation_name,
+                &other.implementation_name,
+            )?,
+            implementation_version: merge(
+                "implementation_version",
+                &self.implementation_version,
+                &other.implementation_version,
+            )?,
+            os_name: merge("os_name", &self.os_name, &other.os_name)?,
+            platform_machine: merge(
+                "platform_machine",
+                &self.platform_machine,
+                &other.platform_machine,
+            )?,
+            platform_python_implementation: merge(
+                "platform_python_implementation",
+                &self.platform_python_implementation,
+                &other.platform_python_implementation,
+            )?,
+            platform_release: merge(
+                "platform_release",
+                &self.platform_release,
+                &other.platform_release,
+            )?,
+            platform_system: merge(
+                "platform_system",
+                &self.platform_system,
+                &other.platform_system,
+            )?,
+            platform_version: merge(
+                "platform_version",
+                &self.platform_version,
+                &other.platform_version,
+            )?,
+            python_full_version: merge(
+                "python_full_version",
+                &self.python_full_version,
+                &other.python_full_version,
+            )?,
+            python_version: merge(
+                "python_version",
+                &self.python_version,
+                &other.python_version,
+            )?,
+            sys_platform: merge("sys_platform", &self.sys_platform, &other.sys_platform)?,
+        })
+    }
+}
+
+/// The accelerator for which the PyTorch wheels are built.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Serialize,
+    strum::Display,
+    strum::EnumString,
+    strum::VariantNames,
+)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum TorchBackend {
+    /// Wheels that only run on the CPU
+    Cpu,
+    /// Wheels built against CUDA 11.8
+    Cu118,
+    /// Wheels built against CUDA 12.1
+    Cu121,
+    /// Wheels built against CUDA 12.4
+    Cu124,
+    /// Wheels built against ROCm 6.2
+    Rocm,
+}
+
+impl TorchBackend {
+    /// Returns the index that contains the PyTorch wheels of the backend.
+    pub fn index_url(&self) -> Url {
+        let name = match self {
+            TorchBackend::Cpu => "cpu",
+            TorchBackend::Cu118 => "cu118",
+            TorchBackend::Cu121 => "cu121",
+            TorchBackend::Cu124 => "cu124",
+            TorchBackend::Rocm => "rocm6.2",
+        };
+        pixi_consts::consts::PYTORCH_INDEX_URL
+            .join(name)
+            .expect("valid pytorch index url")
+    }
+
+    /// Returns true if the backend publishes wheels for the platform.
+    fn supports(&self, platform: Platform) -> bool {
+        match self {
+            TorchBackend::Cpu => true,
+            TorchBackend::Cu118 | TorchBackend::Cu121 | TorchBackend::Cu124 => {
+                matches!(platform, Platform::Linux64 | Platform::Win64)
+            }
+            TorchBackend::Rocm => platform == Platform::Linux64,
+        }
+    }
+}
+
+/// Selects the index from which the PyTorch packages are installed.
+#[derive(Debug, Clone, PartialEq, Serialize, Eq)]
+pub struct TorchOptions {
+    /// The accelerator of the PyTorch wheels
+    pub backend: TorchBackend,
+}
+
+impl TorchOptions {
+    /// The packages that are installed from the PyTorch index.
+    pub const PACKAGES: &'static [&'static str] =
+        &["torch", "torchvision", "torchaudio", "pytorch-triton-rocm"];
+
+    /// Returns the index from which the PyTorch packages are installed on the
+    /// platform, or `None` if they are installed from the regular indexes.
+    ///
+    /// Platforms for which the backend doesn't publish wheels use the CPU
+    /// wheels instead. The wheels for macOS are only published on PyPI.
+    pub fn index_url(&self, platform: Platform) -> Option<Url> {
+        if platform.is_osx() {
+            return None;
+        }
+        if self.backend.supports(platform) {
+            Some(self.backend.index_url())
+        } else {
+            Some(TorchBackend::Cpu.index_url())
+        }
+    }
+}
+
+/// Which installer owns a distribution that is provided by both a conda
+/// package and the PyPI dependencies.
+#[derive(
+    Default,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Serialize,
+    strum::Display,
+    strum::EnumString,
+    strum::VariantNames,
+)]
+#[strum(serialize_all = "kebab-case")]
+#[serde(rename_all = "kebab-case")]
+pub enum OwnershipPolicy {
+    /// The conda package is used, the PyPI requirements have to be satisfied
+    /// by its version
+    #[default]
+    PreferConda,
+    /// The distribution is installed from PyPI over the files of the conda
+    /// package
+    PreferPypi,
+    /// Solving fails when the PyPI dependencies require a distribution that is
+    /// provided by a conda package
+    Error,
+}
+
 /// Specific options for a PyPI registries
 #[derive(Debug, Clone, PartialEq, Serialize, Eq, Default)]
 #[serde(rename_all = "kebab-case")]
@@ -63,6 +268,21 @@ pub struct PypiOptions {
     pub no_build_isolation: Option<Vec<String>>,
     /// The strategy to use when resolving against multiple index URLs.
     pub index_strategy: Option<IndexStrategy>,
+    /// Don't build source distributions
+    pub no_build: Option<bool>,
+    /// Don't build source distributions of these packages
+    pub only_binary: Option<Vec<String>>,
+    /// Don't install pre-built wheels of these packages
+    pub no_binary: Option<Vec<String>>,
+    /// Overrides of the marker environment per platform
+    pub marker_environment: Option<IndexMap<Platform, MarkerEnvironmentOverrides>>,
+    /// The index from which the PyTorch packages are installed
+    pub torch: Option<TorchOptions>,
+    /// Which installer owns the distributions that are provided by both conda
+    /// and PyPI
+    pub ownership_policy: Option<OwnershipPolicy>,
+    /// The ownership policy of specific packages
+    pub package_ownership_policy: Option<IndexMap<pep508_rs::PackageName, OwnershipPolicy>>,
 }
 
 /// Clones and deduplicates two iterators of values
@@ -92,9 +312,58 @@ impl PypiOptions {
             find_links: flat_indexes,
             no_build_isolation,
             index_strategy,
+            no_build: None,
+            only_binary: None,
+            no_binary: None,
+            marker_environment: None,
+            torch: None,
+            ownership_policy: None,
+            package_ownership_policy: None,
         }
     }
 
+    /// Returns the ownership policy of a distribution that is provided by both
+    /// a conda package and the PyPI dependencies.
+    pub fn ownership_policy(&self, name: &pep508_rs::PackageName) -> OwnershipPolicy {
+        self.package_ownership_policy
+            .as_ref()
+            .and_then(|policies| policies.get(name))
+            .copied()
+            .or(self.ownership_policy)
+            .unwrap_or_default()
+    }
+
+    /// Returns the indexes with which the PyPI packages of the platforms are
+    /// resolved. The PyTorch indexes are only included for the platforms on
+    /// which the PyTorch packages are installed from them.
+    #[cfg(feature = "rattler_lock")]
+    pub fn pypi_indexes(
+        &self,
+        platforms: impl IntoIterator<Item = Platform>,
+    ) -> rattler_lock::PypiIndexes {
+        let mut indexes = rattler_lock::PypiIndexes::from(self);
+        if let Some(torch) = &self.torch {
+            let torch_indexes = platforms
+                .into_iter()
+                .filter_map(|platform| torch.index_url(platform))
+                .collect::<std::collections::BTreeSet<_>>();
+            for index in torch_indexes {
+                if !indexes.indexes.contains(&index) {
+                    indexes.indexes.push(index);
+                }
+            }
+        }
+        indexes
+    }
+
+    /// Returns the overrides of the marker environment for the given platform.
+    pub fn marker_environment_overrides(
+        &self,
+        platform: Platform,
+    ) -> Option<&MarkerEnvironmentOverrides> {
+        self.marker_environment.as_ref()?.get(&platform)
+    }
+
     /// Return an iterator over all [`Url`] entries.
     /// In order of:
     /// - `find_links`
@@ -122,6 +391,12 @@ impl PypiOptions {
     ///   provided
     /// - Flat indexes are merged and deduplicated, in the order they are
     ///   provided
+    /// - `no-build` can only be set to one value
+    /// - `only-binary` and `no-binary` packages are merged and deduplicated
+    /// - Marker overrides are merged per platform, a marker can only be
+    ///   overridden once
+    /// - There can only be one PyTorch backend
+    /// - There can only be one ownership policy, and one per package
     pub fn union(&self, other: &PypiOptions) -> Result<PypiOptions, PypiOptionsMergeError> {
         let index = if let Some(other_index) = other.index_url.clone() {
             // Allow only one index
@@ -190,12 +465,114 @@ impl PypiOptions {
             })
             .or_else(|| other.no_build_isolation.clone());
 
+        // Allow only one value for no-build
+        let no_build = match (self.no_build, other.no_build) {
+            (Some(first), Some(second)) if first != second => {
+                return Err(PypiOptionsMergeError::MultipleNoBuild);
+            }
+            (own, other) => own.or(other),
+        };
+
+        // Merge the packages of which only binaries or no binaries are used
+        let only_binary = self
+            .only_binary
+            .as_ref()
+            .map(|only_binary| {
+                clone_and_deduplicate(
+                    only_binary.iter(),
+                    other.only_binary.clone().unwrap_or_default().iter(),
+                )
+            })
+            .or_else(|| other.only_binary.clone());
+        let no_binary = self
+            .no_binary
+            .as_ref()
+            .map(|no_binary| {
+                clone_and_deduplicate(
+                    no_binary.iter(),
+                    other.no_binary.clone().unwrap_or_default().iter(),
+                )
+            })
+            .or_else(|| other.no_binary.clone());
+
+        // Merge the marker overrides of every platform
+        let marker_environment = match (&self.marker_environment, &other.marker_environment) {
+            (Some(own), Some(other)) => {
+                let mut merged = own.clone();
+                for (platform, overrides) in other {
+                    let overrides = match merged.get(platform) {
+                        Some(own_overrides) => own_overrides.union(overrides, *platform)?,
+                        None => overrides.clone(),
+                    };
+                    merged.insert(*platform, overrides);
+                }
+                Some(merged)
+            }
+            (own, other) => own.clone().or_else(|| other.clone()),
+        };
+
+        // Allow only one PyTorch backend
+        let torch = match (&self.torch, &other.torch) {
+            (Some(first), Some(second)) if first != second => {
+                return Err(PypiOptionsMergeError::MultipleTorchBackends {
+                    first: first.backend.to_string(),
+                    second: second.backend.to_string(),
+                });
+            }
+            (own, other) => own.clone().or_else(|| other.clone()),
+        };
+
+        // Allow only one ownership policy
+        let ownership_policy = match (self.ownership_policy, other.ownership_policy) {
+            (Some(first), Some(second)) if first != second => {
+                return Err(PypiOptionsMergeError::MultipleOwnershipPolicies {
+                    first: first.to_string(),
+                    second: second.to_string(),
+                });
+            }
+            (own, other) => own.or(other),
+        };
+
+        // Merge the ownership policies of the packages, a package can only have
+        // one policy
+        let package_ownership_policy = match (
+            &self.package_ownership_policy,
+            &other.package_ownership_policy,
+        ) {
+            (Some(own), Some(other)) => {
+                let mut merged = own.clone();
+                for (name, policy) in other {
+                    match merged.get(name) {
+                        Some(own_policy) if own_policy != policy => {
+                            return Err(PypiOptionsMergeError::MultiplePackageOwnershipPolicies {
+                                package: name.to_string(),
+                                first: own_policy.to_string(),
+                                second: policy.to_string(),
+                            });
+                        }
+                        _ => {
+                            merged.insert(name.clone(), *policy);
+                        }
+                    }
+                }
+                Some(merged)
+            }
+            (own, other) => own.clone().or_else(|| other.clone()),
+        };
+
         Ok(PypiOptions {
             index_url: index,
             extra_index_urls: extra_indexes,
             find_links: flat_indexes,
             no_build_isolation,
             index_strategy,
+            no_build,
+            only_binary,
+            no_binary,
+            marker_environment,
+            torch,
+            ownership_policy,
+            package_ownership_policy,
         })
     }
 }
@@ -257,6 +634,33 @@ pub enum PypiOptionsMergeError {
         "multiple index strategies are not supported, found both {first} and {second} across multiple pypi options"
     )]
     MultipleIndexStrategies { first: String, second: String },
+    #[error("`no-build` is both enabled and disabled across multiple pypi options")]
+    MultipleNoBuild,
+    #[error(
+        "the marker '{marker}' of {platform} is overridden with both '{first}' and '{second}' across multiple pypi options"
+    )]
+    MultipleMarkerValues {
+        platform: Platform,
+        marker: String,
+        first: String,
+        second: String,
+    },
+    #[error(
+        "multiple pytorch backends are not supported, found both {first} and {second} across multiple pypi options"
+    )]
+    MultipleTorchBackends { first: String, second: String },
+    #[error(
+        "multiple ownership policies are not supported, found both {first} and {second} across multiple pypi options"
+    )]
+    MultipleOwnershipPolicies { first: String, second: String },
+    #[error(
+        "the ownership policy of '{package}' is set to both {first} and {second} across multiple pypi options"
+    )]
+    MultiplePackageOwnershipPolicies {
+        package: String,
+        first: String,
+        second: String,
+    },
 }
 
 #[cfg(test)]
@@ -278,6 +682,13 @@ mod tests {
             ]),
             no_build_isolation: Some(vec!["foo".to_string(), "bar".to_string()]),
             index_strategy: None,
+            no_build: None,
+            only_binary: None,
+            no_binary: None,
+            marker_environment: None,
+            torch: None,
+            ownership_policy: None,
+            package_ownership_policy: None,
         };
 
         // Create the second set of options
@@ -290,6 +701,13 @@ mod tests {
             ]),
             no_build_isolation: Some(vec!["foo".to_string()]),
             index_strategy: None,
+            no_build: None,
+            only_binary: None,
+            no_binary: None,
+            marker_environment: None,
+            torch: None,
+            ownership_policy: None,
+            package_ownership_policy: None,
         };
 
         // Merge the two options
@@ -307,6 +725,13 @@ mod tests {
             find_links: None,
             no_build_isolation: None,
             index_strategy: None,
+            no_build: None,
+            only_binary: None,
+            no_binary: None,
+            marker_environment: None,
+            torch: None,
+            ownership_policy: None,
+            package_ownership_policy: None,
         };
 
         // Create the second set of options
@@ -316,6 +741,13 @@ mod tests {
             find_links: None,
             no_build_isolation: None,
             index_strategy: None,
+            no_build: None,
+            only_binary: None,
+            no_binary: None,
+            marker_environment: None,
+            torch: None,
+            ownership_policy: None,
+            package_ownership_policy: None,
         };
 
         // Merge the two options
@@ -333,6 +765,13 @@ mod tests {
             find_links: None,
             no_build_isolation: None,
             index_strategy: Some(IndexStrategy::FirstIndex),
+            no_build: None,
+            only_binary: None,
+            no_binary: None,
+            marker_environment: None,
+            torch: None,
+            ownership_policy: None,
+            package_ownership_policy: None,
         };
 
         // Create the second set of options
@@ -342,6 +781,13 @@ mod tests {
             find_links: None,
             no_build_isolation: None,
             index_strategy: Some(IndexStrategy::UnsafeBestMatch),
+            no_build: None,
+            only_binary: None,
+            no_binary: None,
+            marker_environment: None,
+            torch: None,
+            ownership_policy: None,
+            package_ownership_policy: None,
         };
 
         // Merge the two options
@@ -349,4 +795,200 @@ mod tests {
         let merged_opts = opts.union(&opts2);
         insta::assert_snapshot!(merged_opts.err().unwrap());
     }
+
+    #[test]
+    fn test_merge_no_build() {
+        let no_build = |no_build: Option<bool>| PypiOptions {
+            no_build,
+            only_binary: Some(vec!["numpy".to_string()]),
+            ..Default::default()
+        };
+
+        // An unset no-build takes the value of the other options
+        let merged_opts = no_build(Some(true)).union(&no_build(None)).unwrap();
+        assert_eq!(merged_opts.no_build, Some(true));
+        assert_eq!(merged_opts.only_binary, Some(vec!["numpy".to_string()]));
+
+        // This should error because no-build is both enabled and disabled
+        let merged_opts = no_build(Some(true)).union(&no_build(Some(false)));
+        insta::assert_snapshot!(merged_opts.err().unwrap(), @"`no-build` is both enabled and disabled across multiple pypi options");
+    }
+
+    #[test]
+    fn test_merge_marker_environment() {
+        let linux_aarch64 = |platform_machine: &str| PypiOptions {
+            marker_environment: Some(IndexMap::from([(
+                Platform::LinuxAarch64,
+                MarkerEnvironmentOverrides {
+                    platform_machine: Some(platform_machine.to_string()),
+                    ..Default::default()
+                },
+            )])),
+            ..Default::default()
+        };
+        let osx_arm64 = PypiOptions {
+            marker_environment: Some(IndexMap::from([(
+                Platform::OsxArm64,
+                MarkerEnvironmentOverrides {
+                    platform_release: Some("23.0.0".to_string()),
+                    ..Default::default()
+                },
+            )])),
+            ..Default::default()
+        };
+
+        // The overrides of different platforms are combined
+        let merged_opts = linux_aarch64("aarch64").union(&osx_arm64).unwrap();
+        let overrides = merged_opts
+            .marker_environment_overrides(Platform::LinuxAarch64)
+            .unwrap();
+        assert_eq!(overrides.platform_machine.as_deref(), Some("aarch64"));
+        let overrides = merged_opts
+            .marker_environment_overrides(Platform::OsxArm64)
+            .unwrap();
+        assert_eq!(overrides.platform_release.as_deref(), Some("23.0.0"));
+        assert!(merged_opts
+            .marker_environment_overrides(Platform::Linux64)
+            .is_none());
+
+        // The same value can be set twice, a different value is an error
+        assert!(linux_aarch64("aarch64")
+            .union(&linux_aarch64("aarch64"))
+            .is_ok());
+        let merged_opts = linux_aarch64("aarch64").union(&linux_aarch64("arm64"));
+        insta::assert_snapshot!(merged_opts.err().unwrap(), @"the marker 'platform_machine' of linux-aarch64 is overridden with both 'aarch64' and 'arm64' across multiple pypi options");
+    }
+
+    #[test]
+    fn test_merge_torch() {
+        let torch = |backend: TorchBackend| PypiOptions {
+            torch: Some(TorchOptions { backend }),
+            ..Default::default()
+        };
+
+        let merged_opts = torch(TorchBackend::Cu124)
+            .union(&PypiOptions::default())
+            .unwrap();
+        assert_eq!(
+            merged_opts.torch,
+            Some(TorchOptions {
+                backend: TorchBackend::Cu124
+            })
+        );
+
+        // This should error because there are two backends
+        let merged_opts = torch(TorchBackend::Cu124).union(&torch(TorchBackend::Cpu));
+        insta::assert_snapshot!(merged_opts.err().unwrap(), @"multiple pytorch backends are not supported, found both cu124 and cpu across multiple pypi options");
+    }
+
+    #[test]
+    fn test_merge_ownership_policy() {
+        let numpy = "numpy".parse::<pep508_rs::PackageName>().unwrap();
+        let package_policy = |policy: OwnershipPolicy| PypiOptions {
+            package_ownership_policy: Some(IndexMap::from([(numpy.clone(), policy)])),
+            ..Default::default()
+        };
+
+        // The policy of a package takes precedence over the policy of all
+        // packages
+        let merged_opts = package_policy(OwnershipPolicy::PreferPypi)
+            .union(&PypiOptions {
+                ownership_policy: Some(OwnershipPolicy::Error),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(
+            merged_opts.ownership_policy(&numpy),
+            OwnershipPolicy::PreferPypi
+        );
+        assert_eq!(
+            merged_opts.ownership_policy(&"pillow".parse().unwrap()),
+            OwnershipPolicy::Error
+        );
+        assert_eq!(
+            PypiOptions::default().ownership_policy(&numpy),
+            OwnershipPolicy::PreferConda
+        );
+
+        // This should error because the package has two policies
+        let merged_opts = package_policy(OwnershipPolicy::PreferPypi)
+            .union(&package_policy(OwnershipPolicy::Error));
+        insta::assert_snapshot!(merged_opts.err().unwrap(), @"the ownership policy of 'numpy' is set to both prefer-pypi and error across multiple pypi options");
+    }
+
+    #[test]
+    fn test_torch_index_url() {
+        let torch = |backend: TorchBackend| TorchOptions { backend };
+
+        assert_eq!(
+            torch(TorchBackend::Cu124)
+                .index_url(Platform::Linux64)
+                .unwrap()
+                .as_str(),
+            "https://download.pytorch.org/whl/cu124"
+        );
+        assert_eq!(
+            torch(TorchBackend::Rocm)
+                .index_url(Platform::Linux64)
+                .unwrap()
+                .as_str(),
+            "https://download.pytorch.org/whl/rocm6.2"
+        );
+
+        // Platforms without wheels for the backend use the CPU wheels
+        assert_eq!(
+            torch(TorchBackend::Rocm)
+                .index_url(Platform::Win64)
+                .unwrap()
+                .as_str(),
+            "https://download.pytorch.org/whl/cpu"
+        );
+        assert_eq!(
+            torch(TorchBackend::Cu124)
+                .index_url(Platform::LinuxAarch64)
+                .unwrap()
+                .as_str(),
+            "https://download.pytorch.org/whl/cpu"
+        );
+
+        // macOS only has wheels on PyPI
+        assert_eq!(torch(TorchBackend::Cpu).index_url(Platform::OsxArm64), None);
+    }
+
+    #[cfg(feature = "rattler_lock")]
+    #[test]
+    fn test_torch_pypi_indexes() {
+        let options = PypiOptions {
+            torch: Some(TorchOptions {
+                backend: TorchBackend::Cu124,
+            }),
+            ..Default::default()
+        };
+        let indexes = |platforms: &[Platform]| {
+            options
+                .pypi_indexes(platforms.iter().copied())
+                .indexes
+                .iter()
+                .map(Url::to_string)
+                .collect::<Vec<_>>()
+        };
+
+        // The PyTorch packages of macOS are installed from PyPI
+        assert_eq!(indexes(&[Platform::OsxArm64]), ["https://pypi.org/simple"]);
+        assert_eq!(
+            indexes(&[Platform::OsxArm64, Platform::Linux64, Platform::Win64]),
+            [
+                "https://pypi.org/simple",
+                "https://download.pytorch.org/whl/cu124"
+            ]
+        );
+        assert_eq!(
+            indexes(&[Platform::Linux64, Platform::LinuxAarch64]),
+            [
+                "https://pypi.org/simple",
+                "https://download.pytorch.org/whl/cpu",
+                "https://download.pytorch.org/whl/cu124"
+            ]
+        );
+    }
 }
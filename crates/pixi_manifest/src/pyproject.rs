@@ -15,9 +15,10 @@ use super::{
 };
 use crate::toml::{FromTomlStr, Warning};
 use crate::{
-    error::DependencyError,
+    error::{DependencyError, GenericError},
     manifests::PackageManifest,
-    toml::{ExternalWorkspaceProperties, TomlManifest},
+    toml::{ExternalWorkspaceProperties, TomlEnvironmentList, TomlManifest},
+    utils::PixiSpanned,
     FeatureName,
 };
 
@@ -223,13 +224,33 @@ impl PyProjectManifest {
     {
         // Load the data nested under '[tool.pixi]' as pixi manifest
         let Some(Tool {
-            pixi: Some(pixi),
+            pixi: Some(mut pixi),
             poetry,
         }) = self.tool
         else {
             return Err(PyProjectToManifestError::MissingPixiTable);
         };
 
+        // The references to the dependency groups are resolved below, once the
+        // pixi manifest is converted.
+        let feature_group_refs = pixi
+            .feature
+            .iter_mut()
+            .filter_map(|(name, feature)| {
+                Some((name.clone(), feature.pypi_dependency_groups.take()?))
+            })
+            .collect::<Vec<_>>();
+        let environment_group_refs = pixi
+            .environments
+            .iter_mut()
+            .filter_map(|(name, env)| match env {
+                TomlEnvironmentList::Map(env) => {
+                    Some((name.clone(), env.pypi_dependency_groups.take()?))
+                }
+                TomlEnvironmentList::Seq(_) => None,
+            })
+            .collect::<Vec<_>>();
+
         // Extract the values we are interested in from the pyproject.toml
         let pyproject_toml::PyProjectToml {
             project,
@@ -305,16 +326,16 @@ impl PyProjectManifest {
         }
 
         // Define an iterator over both optional dependencies and dependency groups
+        let dependency_groups = dependency_groups
+            .as_ref()
+            .map(DependencyGroups::resolve)
+            .transpose()?
+            .unwrap_or_default();
         let groups = project
             .optional_dependencies
             .into_iter()
-            .chain(
-                dependency_groups
-                    .as_ref()
-                    .map(DependencyGroups::resolve)
-                    .transpose()?,
-            )
-            .flat_map(|map| map.into_iter());
+            .flatten()
+            .chain(dependency_groups.clone());
 
         // For each group of optional dependency or dependency group,
         // create a feature of the same name if it does not exist,
@@ -346,10 +367,72 @@ impl PyProjectManifest {
             }
         }
 
+        // Add the requirements of the referenced dependency groups to the
+        // features. Dependencies that the feature defines itself take
+        // precedence.
+        for (feature_name, group_names) in feature_group_refs {
+            let target = workspace_manifest
+                .features
+                .get_mut(&feature_name)
+                .expect("the feature was converted")
+                .targets
+                .default_mut();
+            for group in referenced_groups(&dependency_groups, &group_names)? {
+                for requirement in &dependency_groups[group] {
+                    if project_name.as_ref() != Some(&requirement.name) {
+                        target
+                            .try_add_pep508_dependency(
+                                requirement,
+                                None,
+                                DependencyOverwriteBehavior::IgnoreDuplicate,
+                            )
+                            .map_err(|err| {
+                                PyProjectToManifestError::DependencyError(requirement.clone(), err)
+                            })?;
+                    }
+                }
+            }
+        }
+
+        // Every dependency group is a feature of the same name, so an
+        // environment that references a group includes that feature.
+        for (environment_name, group_names) in environment_group_refs {
+            let environment = workspace_manifest
+                .environments
+                .find_mut(&environment_name)
+                .expect("the environment was converted");
+            for group in referenced_groups(&dependency_groups, &group_names)? {
+                if !environment.features.iter().any(|feature| feature == group) {
+                    environment.features.push(group.to_string());
+                }
+            }
+        }
+
         Ok((workspace_manifest, package_manifest, warnings))
     }
 }
 
+/// Returns the names of the referenced dependency groups, or an error
+/// pointing at the references if one of the groups is not defined in the
+/// `[dependency-groups]` table.
+fn referenced_groups<'a>(
+    dependency_groups: &IndexMap<String, Vec<Requirement>>,
+    group_names: &'a PixiSpanned<Vec<String>>,
+) -> Result<&'a [String], TomlError> {
+    if let Some(missing) = group_names
+        .value
+        .iter()
+        .find(|group| !dependency_groups.contains_key(group.as_str()))
+    {
+        return Err(GenericError::new(format!(
+            "the dependency group '{missing}' is not defined in `[dependency-groups]`"
+        ))
+        .with_opt_span(group_names.span.clone())
+        .into());
+    }
+    Ok(&group_names.value)
+}
+
 /// Try to return a NamelessMatchSpec from a pep508_rs::VersionOrUrl
 /// This will only work if it is not URL and the VersionSpecifier can
 /// successfully be interpreted as a NamelessMatchSpec.version
@@ -639,6 +722,60 @@ mod tests {
         assert_snapshot!(manifest.source.to_string());
     }
 
+    #[test]
+    fn test_pypi_dependency_groups() {
+        let pyproject = format!(
+            r#"{PYPROJECT_BOILERPLATE}
+        [dependency-groups]
+        test = ["pytest"]
+        lint = ["ruff"]
+        dev = [{{include-group = "test"}}, "flask-hello-world-pyproject"]
+
+        [tool.pixi.feature.ci]
+        pypi-dependency-groups = ["dev", "lint"]
+        pypi-dependencies = {{ ruff = "==0.5" }}
+
+        [tool.pixi.environments]
+        ci = {{ features = ["ci"] }}
+        test = {{ pypi-dependency-groups = ["test", "lint"] }}
+        "#
+        );
+        let manifest = Manifest::from_str(Path::new("pyproject.toml"), pyproject).unwrap();
+
+        // The feature contains the requirements of the groups, except for the
+        // self-reference and the dependencies it defines itself.
+        let pypi_dependencies = manifest
+            .feature(&FeatureName::Named("ci".to_string()))
+            .unwrap()
+            .targets
+            .default()
+            .pypi_dependencies
+            .clone()
+            .unwrap();
+        assert_eq!(
+            pypi_dependencies
+                .iter()
+                .map(|(name, req)| format!("{} = {req}", name.as_source()))
+                .collect::<Vec<_>>(),
+            vec![r#"ruff = "==0.5""#, r#"pytest = "*""#]
+        );
+
+        let environment = manifest.environment("test").unwrap();
+        assert_eq!(environment.features, vec!["test", "lint"]);
+
+        // A group that doesn't exist is an error.
+        let pyproject = format!(
+            r#"{PYPROJECT_BOILERPLATE}
+        [tool.pixi.environments]
+        test = {{ pypi-dependency-groups = ["test"] }}
+        "#
+        );
+        let err = Manifest::from_str(Path::new("pyproject.toml"), pyproject).unwrap_err();
+        assert!(err
+            .chain()
+            .any(|err| err.to_string().contains("'test' is not defined")));
+    }
+
     #[test]
     fn test_version_url_to_matchspec() {
         fn cmp(v1: &str, v2: &str) {
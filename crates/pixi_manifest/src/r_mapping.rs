@@ -0,0 +1,123 @@
+use std::{fmt::Debug, str::FromStr, sync::Arc};
+
+use indexmap::IndexMap;
+use rattler_conda_types::{InvalidPackageNameError, PackageName};
+
+/// The prefix of the conda packages that conda-forge builds from CRAN.
+const CONDA_FORGE_R_PREFIX: &str = "r-";
+
+/// A source of the conda packages that provide R packages, like the
+/// `[r-mapping]` table of the manifest.
+pub trait RMappingSource: Debug + Send + Sync {
+    /// Returns the conda package that provides the R package, or `None` if
+    /// this source doesn't map the package.
+    fn conda_name(&self, r_name: &str) -> Option<PackageName>;
+}
+
+/// A table of R packages and their conda packages.
+impl RMappingSource for IndexMap<String, PackageName> {
+    fn conda_name(&self, r_name: &str) -> Option<PackageName> {
+        self.get(r_name).cloned()
+    }
+}
+
+/// Maps the names of R packages, as used on CRAN, to the conda packages that
+/// provide them.
+///
+/// The sources are queried in order, the first source that maps a package
+/// wins. Packages that no source maps follow the naming convention of
+/// conda-forge, which packages `ggplot2` as `r-ggplot2`. The `[r-mapping]`
+/// table of the manifest is the first source, for packages that don't follow
+/// that convention, like the packages of Bioconductor.
+#[derive(Debug, Clone, Default)]
+pub struct RMapping {
+    sources: Vec<Arc<dyn RMappingSource>>,
+}
+
+impl RMapping {
+    /// Creates a mapping that is only overridden by the given table.
+    pub fn from_overrides(overrides: IndexMap<String, PackageName>) -> Self {
+        Self::default().with_source(overrides)
+    }
+
+    /// Adds a source that is queried after the sources that were added
+    /// before it.
+    pub fn with_source(mut self, source: impl RMappingSource + 'static) -> Self {
+        self.sources.push(Arc::new(source));
+        self
+    }
+
+    /// Returns the conda package that provides the R package.
+    pub fn conda_name(&self, r_name: &str) -> Result<PackageName, InvalidPackageNameError> {
+        if let Some(name) = self
+            .sources
+            .iter()
+            .find_map(|source| source.conda_name(r_name))
+        {
+            return Ok(name);
+        }
+        PackageName::from_str(&format!("{CONDA_FORGE_R_PREFIX}{}", r_name.to_lowercase()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conda_name() {
+        let mapping = RMapping::from_overrides(IndexMap::from([(
+            "DESeq2".to_string(),
+            PackageName::from_str("bioconductor-deseq2").unwrap(),
+        )]));
+        assert_eq!(
+            mapping.conda_name("ggplot2").unwrap().as_normalized(),
+            "r-ggplot2"
+        );
+        assert_eq!(
+            mapping.conda_name("data.table").unwrap().as_normalized(),
+            "r-data.table"
+        );
+        assert_eq!(
+            mapping.conda_name("DESeq2").unwrap().as_normalized(),
+            "bioconductor-deseq2"
+        );
+        assert!(mapping.conda_name("not a package").is_err());
+    }
+
+    /// Maps the packages of Bioconductor, which are all lowercase in conda.
+    #[derive(Debug)]
+    struct Bioconductor(Vec<&'static str>);
+
+    impl RMappingSource for Bioconductor {
+        fn conda_name(&self, r_name: &str) -> Option<PackageName> {
+            self.0.contains(&r_name).then(|| {
+                PackageName::from_str(&format!("bioconductor-{}", r_name.to_lowercase())).unwrap()
+            })
+        }
+    }
+
+    #[test]
+    fn test_source_precedence() {
+        let mapping = RMapping::from_overrides(IndexMap::from([(
+            "limma".to_string(),
+            PackageName::from_str("my-limma").unwrap(),
+        )]))
+        .with_source(Bioconductor(vec!["limma", "edgeR"]));
+
+        // The sources that were added first win, the convention of
+        // conda-forge is the fallback.
+        assert_eq!(
+            mapping.conda_name("limma").unwrap().as_normalized(),
+            "my-limma"
+        );
+        assert_eq!(
+            mapping.conda_name("edgeR").unwrap().as_normalized(),
+            "bioconductor-edger"
+        );
+        assert_eq!(
+            mapping.conda_name("dplyr").unwrap().as_normalized(),
+            "r-dplyr"
+        );
+    }
+}
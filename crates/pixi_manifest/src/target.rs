@@ -1,6 +1,6 @@
 use std::{borrow::Cow, collections::HashMap, str::FromStr};
 
-use indexmap::{map::Entry, IndexMap};
+use indexmap::{map::Entry, IndexMap, IndexSet};
 use itertools::Either;
 use pixi_spec::PixiSpec;
 use rattler_conda_types::{PackageName, ParsePlatformError, Platform};
@@ -29,6 +29,15 @@ pub struct WorkspaceTarget {
     /// Specific python dependencies
     pub pypi_dependencies: Option<IndexMap<PyPiPackageName, PyPiRequirement>>,
 
+    /// R packages by their name on CRAN. The conda packages that provide them
+    /// are part of the run dependencies.
+    pub r_dependencies: Option<IndexMap<String, PixiSpec>>,
+
+    /// The run dependencies that were added for the R dependencies. A conda
+    /// dependency on the same package takes precedence, so it isn't part of
+    /// this set.
+    pub r_conda_dependencies: IndexSet<PackageName>,
+
     /// Additional information to activate an environment.
     pub activation: Option<Activation>,
 
@@ -139,6 +148,9 @@ impl WorkspaceTarget {
     ///
     /// This will overwrite any existing dependency of the same name
     pub fn add_dependency(&mut self, dep_name: &PackageName, spec: &PixiSpec, spec_type: SpecType) {
+        if spec_type == SpecType::Run {
+            self.r_conda_dependencies.shift_remove(dep_name);
+        }
         self.dependencies
             .entry(spec_type)
             .or_default()
@@ -259,6 +271,79 @@ impl WorkspaceTarget {
         self.add_pypi_dependency(name, requirement);
         Ok(true)
     }
+
+    /// Adds an R dependency to a target, together with the conda package that
+    /// provides it. A conda dependency on the same package takes precedence
+    /// and is kept as is.
+    ///
+    /// This will return an error if the exact same dependency already exist
+    /// This will overwrite any existing dependency of the same name
+    pub fn try_add_r_dependency(
+        &mut self,
+        r_name: &str,
+        conda_name: &PackageName,
+        spec: &PixiSpec,
+        dependency_overwrite_behavior: DependencyOverwriteBehavior,
+    ) -> Result<bool, DependencyError> {
+        if self
+            .r_dependencies
+            .as_ref()
+            .is_some_and(|deps| deps.contains_key(r_name))
+        {
+            match dependency_overwrite_behavior {
+                DependencyOverwriteBehavior::OverwriteIfExplicit if !spec.has_version_spec() => {
+                    return Ok(false)
+                }
+                DependencyOverwriteBehavior::IgnoreDuplicate => return Ok(false),
+                DependencyOverwriteBehavior::Error => {
+                    return Err(DependencyError::Duplicate(r_name.into()));
+                }
+                _ => {}
+            }
+        }
+        self.r_dependencies
+            .get_or_insert_with(Default::default)
+            .insert(r_name.to_string(), spec.clone());
+        self.add_r_conda_dependency(conda_name, spec);
+        Ok(true)
+    }
+
+    /// Adds the conda package that provides an R package to the run
+    /// dependencies, unless there is a conda dependency on it.
+    pub(crate) fn add_r_conda_dependency(&mut self, conda_name: &PackageName, spec: &PixiSpec) {
+        let run_dependencies = self.dependencies.entry(SpecType::Run).or_default();
+        if !run_dependencies.contains_key(conda_name)
+            || self.r_conda_dependencies.contains(conda_name)
+        {
+            run_dependencies.insert(conda_name.clone(), spec.clone());
+            self.r_conda_dependencies.insert(conda_name.clone());
+        }
+    }
+
+    /// Removes an R dependency from this target, together with the conda
+    /// package that provides it, unless there is a conda dependency on that
+    /// package.
+    ///
+    /// it will Err if the dependency is not found
+    pub fn remove_r_dependency(
+        &mut self,
+        r_name: &str,
+        conda_name: &PackageName,
+    ) -> Result<(String, PixiSpec), DependencyError> {
+        let Some(r_dependencies) = self.r_dependencies.as_mut() else {
+            return Err(DependencyError::NoRDependencies);
+        };
+        let (r_name, spec) = r_dependencies
+            .shift_remove_entry(r_name)
+            .ok_or_else(|| DependencyError::NoDependency(r_name.into()))?;
+
+        if self.r_conda_dependencies.shift_remove(conda_name) {
+            if let Some(run_dependencies) = self.dependencies.get_mut(&SpecType::Run) {
+                run_dependencies.shift_remove(conda_name);
+            }
+        }
+        Ok((r_name, spec))
+    }
 }
 
 impl PackageTarget {
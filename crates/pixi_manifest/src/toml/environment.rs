@@ -9,6 +9,10 @@ pub struct TomlEnvironment {
     pub features: Option<PixiSpanned<Vec<String>>>,
     pub solve_group: Option<String>,
     pub no_default_feature: bool,
+
+    /// The names of the `[dependency-groups]` of the `pyproject.toml` that are
+    /// added to the environment.
+    pub pypi_dependency_groups: Option<PixiSpanned<Vec<String>>>,
 }
 
 #[derive(Debug)]
@@ -24,10 +28,13 @@ impl<'de> toml_span::Deserialize<'de> for TomlEnvironment {
         let features = th.optional_s("features").map(PixiSpanned::from);
         let solve_group = th.optional("solve-group");
         let no_default_feature = th.optional("no-default-feature");
+        let pypi_dependency_groups = th
+            .optional_s("pypi-dependency-groups")
+            .map(PixiSpanned::from);
 
         th.finalize(None)?;
 
-        if features.is_none() && solve_group.is_none() {
+        if features.is_none() && solve_group.is_none() && pypi_dependency_groups.is_none() {
             return Err(DeserError::from(toml_span::Error {
                 kind: toml_span::ErrorKind::MissingField("features"),
                 span: value.span,
@@ -39,6 +46,7 @@ impl<'de> toml_span::Deserialize<'de> for TomlEnvironment {
             features,
             solve_group,
             no_default_feature: no_default_feature.unwrap_or_default(),
+            pypi_dependency_groups,
         })
     }
 }
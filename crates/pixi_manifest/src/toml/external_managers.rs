@@ -0,0 +1,104 @@
+use pixi_toml::{TomlFromStr, TomlWith};
+use toml_span::{de_helpers::TableHelper, DeserError, Error, ErrorKind, Value};
+
+use crate::ExternalManager;
+
+impl<'de> toml_span::Deserialize<'de> for ExternalManager {
+    fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+        let mut th = TableHelper::new(value)?;
+
+        let cmd = th.required_s::<String>("cmd")?;
+        let inputs = th.optional::<Vec<String>>("inputs").unwrap_or_default();
+        let cwd = th
+            .optional::<TomlFromStr<_>>("cwd")
+            .map(TomlFromStr::into_inner);
+        let environments = th
+            .optional::<TomlWith<_, Vec<TomlFromStr<_>>>>("environments")
+            .map(TomlWith::into_inner);
+
+        th.finalize(None)?;
+
+        if cmd.value.trim().is_empty() {
+            return Err(Error {
+                kind: ErrorKind::Custom("the command of an external manager can't be empty".into()),
+                span: cmd.span,
+                line_info: None,
+            }
+            .into());
+        }
+
+        Ok(Self {
+            cmd: cmd.value,
+            inputs,
+            cwd,
+            environments,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use insta::assert_snapshot;
+
+    use crate::{
+        toml::{FromTomlStr, TomlManifest},
+        utils::test_utils::format_parse_error,
+        EnvironmentName, WorkspaceManifest,
+    };
+
+    fn expect_parse_failure(pixi_toml: &str) -> String {
+        let parse_error = <TomlManifest as FromTomlStr>::from_toml_str(pixi_toml)
+            .expect_err("parsing should fail");
+        format_parse_error(pixi_toml, parse_error)
+    }
+
+    #[test]
+    fn test_external_managers() {
+        let manifest = WorkspaceManifest::from_toml_str(
+            r#"
+        [workspace]
+        name = "foo"
+        channels = []
+        platforms = []
+
+        [external-managers]
+        npm = { cmd = "npm ci", inputs = ["package.json", "package-lock.json"], cwd = "web" }
+        julia = { cmd = "julia --project -e 'using Pkg; Pkg.instantiate()'", inputs = ["Project.toml", "Manifest.toml"], environments = ["default"] }
+        "#,
+        )
+        .unwrap();
+
+        let npm = &manifest.external_managers["npm"];
+        assert_eq!(npm.cmd, "npm ci");
+        assert_eq!(npm.inputs, vec!["package.json", "package-lock.json"]);
+        assert_eq!(npm.cwd.as_deref(), Some("web".as_ref()));
+        assert!(npm.runs_in(&EnvironmentName::Named("test".to_string())));
+
+        let julia = &manifest.external_managers["julia"];
+        assert!(julia.runs_in(&EnvironmentName::Default));
+        assert!(!julia.runs_in(&EnvironmentName::Named("test".to_string())));
+    }
+
+    #[test]
+    fn test_empty_command() {
+        assert_snapshot!(expect_parse_failure(
+            r#"
+        [workspace]
+        name = "foo"
+        channels = []
+        platforms = []
+
+        [external-managers]
+        npm = { cmd = " " }
+        "#,
+        ), @r###"
+         × the command of an external manager can't be empty
+          ╭─[pixi.toml:8:24]
+        7 │         [external-managers]
+        8 │         npm = { cmd = " " }
+          ·                        ─
+        9 │
+          ╰────
+        "###);
+    }
+}
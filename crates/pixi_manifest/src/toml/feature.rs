@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 
 use indexmap::{IndexMap, IndexSet};
+use pixi_spec::PixiSpec;
 use pixi_toml::{TomlHashMap, TomlIndexMap, TomlIndexSet, TomlWith};
 use rattler_conda_types::Platform;
 use toml_span::{de_helpers::TableHelper, DeserError, Value};
@@ -13,8 +14,8 @@ use crate::{
     },
     utils::{package_map::UniquePackageMap, PixiSpanned},
     workspace::ChannelPriority,
-    Activation, Feature, FeatureName, PyPiRequirement, SystemRequirements, TargetSelector, Targets,
-    Task, TaskName, TomlError,
+    Activation, Feature, FeatureName, PyPiRequirement, RMapping, SystemRequirements,
+    TargetSelector, Targets, Task, TaskName, TomlError,
 };
 
 #[derive(Debug)]
@@ -28,6 +29,7 @@ pub struct TomlFeature {
     pub host_dependencies: Option<PixiSpanned<UniquePackageMap>>,
     pub build_dependencies: Option<PixiSpanned<UniquePackageMap>>,
     pub pypi_dependencies: Option<IndexMap<PyPiPackageName, PyPiRequirement>>,
+    pub r_dependencies: Option<IndexMap<String, PixiSpec>>,
 
     /// Additional information to activate an environment.
     pub activation: Option<Activation>,
@@ -38,6 +40,10 @@ pub struct TomlFeature {
     /// Additional options for PyPi dependencies.
     pub pypi_options: Option<PypiOptions>,
 
+    /// The names of the `[dependency-groups]` of the `pyproject.toml` whose
+    /// requirements are added to the pypi dependencies of the feature.
+    pub pypi_dependency_groups: Option<PixiSpanned<Vec<String>>>,
+
     /// Any warnings we encountered while parsing the feature
     pub warnings: Vec<Warning>,
 }
@@ -47,6 +53,7 @@ impl TomlFeature {
         self,
         name: FeatureName,
         preview: &TomlPreview,
+        r_mapping: &RMapping,
     ) -> Result<WithWarnings<Feature>, TomlError> {
         let WithWarnings {
             value: default_target,
@@ -56,18 +63,19 @@ impl TomlFeature {
             host_dependencies: self.host_dependencies,
             build_dependencies: self.build_dependencies,
             pypi_dependencies: self.pypi_dependencies,
+            r_dependencies: self.r_dependencies,
             activation: self.activation,
             tasks: self.tasks,
             warnings: self.warnings,
         }
-        .into_workspace_target(None, preview)?;
+        .into_workspace_target(None, preview, r_mapping)?;
 
         let mut targets = IndexMap::new();
         for (selector, target) in self.target {
             let WithWarnings {
                 value: target,
                 warnings: mut target_warnings,
-            } = target.into_workspace_target(Some(selector.value.clone()), preview)?;
+            } = target.into_workspace_target(Some(selector.value.clone()), preview, r_mapping)?;
             targets.insert(selector, target);
             warnings.append(&mut target_warnings);
         }
@@ -107,6 +115,9 @@ impl<'de> toml_span::Deserialize<'de> for TomlFeature {
         let pypi_dependencies = th
             .optional::<TomlIndexMap<_, _>>("pypi-dependencies")
             .map(TomlIndexMap::into_inner);
+        let r_dependencies = th
+            .optional::<TomlIndexMap<_, _>>("r-dependencies")
+            .map(TomlIndexMap::into_inner);
         let activation = th.optional("activation");
         let tasks = th
             .optional::<TomlHashMap<_, TomlTask>>("tasks")
@@ -123,6 +134,9 @@ impl<'de> toml_span::Deserialize<'de> for TomlFeature {
             })
             .collect();
         let pypi_options = th.optional("pypi-options");
+        let pypi_dependency_groups = th
+            .optional_s("pypi-dependency-groups")
+            .map(PixiSpanned::from);
         let system_requirements = th.optional("system-requirements").unwrap_or_default();
 
         th.finalize(None)?;
@@ -137,9 +151,11 @@ impl<'de> toml_span::Deserialize<'de> for TomlFeature {
             host_dependencies,
             build_dependencies,
             pypi_dependencies,
+            r_dependencies,
             activation,
             tasks,
             pypi_options,
+            pypi_dependency_groups,
             warnings,
         })
     }
@@ -1,8 +1,10 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, path::Path};
 
 use indexmap::IndexMap;
 use itertools::chain;
-use pixi_toml::{TomlHashMap, TomlIndexMap};
+use pixi_spec::{PathSpec, PixiSpec};
+use pixi_toml::{TomlFromStr, TomlHashMap, TomlIndexMap, TomlWith};
+use rattler_conda_types::PackageName;
 use toml_span::{
     de_helpers::{expected, TableHelper},
     value::ValueInner,
@@ -11,7 +13,7 @@ use toml_span::{
 
 use crate::{
     environment::EnvironmentIdx,
-    error::{FeatureNotEnabled, InvalidNonPackageDependencies},
+    error::{FeatureNotEnabled, GenericError, InvalidNonPackageDependencies},
     manifests::PackageManifest,
     pypi::{pypi_options::PypiOptions, PyPiPackageName},
     toml::{
@@ -20,9 +22,10 @@ use crate::{
         TomlTarget, TomlWorkspace, Warning,
     },
     utils::{package_map::UniquePackageMap, PixiSpanned},
-    Activation, Environment, EnvironmentName, Environments, Feature, FeatureName,
-    KnownPreviewFeature, PyPiRequirement, SolveGroups, SystemRequirements, TargetSelector, Targets,
-    Task, TaskName, TomlError, WorkspaceManifest,
+    Activation, CondaPackageOverride, Environment, EnvironmentName, Environments, ExternalManagers,
+    Feature, FeatureName, KnownPreviewFeature, PackageOverrides, PyPiRequirement, PypiCondaMapping,
+    PypiPackageOverride, RMapping, SolveGroups, SystemRequirements, TargetSelector, Targets, Task,
+    TaskName, TomlError, WorkspaceManifest,
 };
 
 /// Raw representation of a pixi manifest. This is the deserialized form of the
@@ -37,6 +40,7 @@ pub struct TomlManifest {
     pub host_dependencies: Option<PixiSpanned<UniquePackageMap>>,
     pub build_dependencies: Option<PixiSpanned<UniquePackageMap>>,
     pub pypi_dependencies: Option<IndexMap<PyPiPackageName, PyPiRequirement>>,
+    pub r_dependencies: Option<IndexMap<String, PixiSpec>>,
 
     /// Additional information to activate an environment.
     pub activation: Option<Activation>,
@@ -53,6 +57,21 @@ pub struct TomlManifest {
     /// pypi-options
     pub pypi_options: Option<PypiOptions>,
 
+    /// The overrides of conda packages.
+    pub package_overrides: IndexMap<PackageName, CondaPackageOverride>,
+
+    /// The overrides of PyPI packages.
+    pub pypi_package_overrides: IndexMap<PyPiPackageName, PypiPackageOverride>,
+
+    /// The PyPI distributions that conda packages provide.
+    pub pypi_conda_mapping: IndexMap<PackageName, PypiCondaMapping>,
+
+    /// The conda packages that provide R packages.
+    pub r_mapping: IndexMap<String, PackageName>,
+
+    /// The package managers of other ecosystems.
+    pub external_managers: ExternalManagers,
+
     /// Any warnings we encountered while parsing the manifest
     pub warnings: Vec<Warning>,
 }
@@ -121,6 +140,134 @@ impl TomlManifest {
         }
     }
 
+    /// Check that no feature or environment references `[dependency-groups]`,
+    /// these only exist in a `pyproject.toml`, which resolves them before
+    /// converting the manifest.
+    fn check_pypi_dependency_groups(&self) -> Result<(), TomlError> {
+        let environment_groups = self.environments.values().filter_map(|env| match env {
+            TomlEnvironmentList::Map(env) => env.pypi_dependency_groups.as_ref(),
+            TomlEnvironmentList::Seq(_) => None,
+        });
+        let feature_groups = self
+            .feature
+            .values()
+            .filter_map(|feature| feature.pypi_dependency_groups.as_ref());
+        match chain!(feature_groups, environment_groups).next() {
+            Some(groups) => Err(GenericError::new(
+                "`pypi-dependency-groups` can only be used in a `pyproject.toml`",
+            )
+            .with_opt_span(groups.span.clone())
+            .with_help("define the requirements in `pypi-dependencies` instead")
+            .into()),
+            None => Ok(()),
+        }
+    }
+
+    /// Check that path dependencies don't point outside of the workspace,
+    /// unless `allow-external-paths` is set. Relative paths are resolved
+    /// against `root`, the directory that contains the manifest.
+    pub fn check_path_dependencies(&self, root: &Path) -> Result<(), TomlError> {
+        if self
+            .workspace
+            .value
+            .allow_external_paths
+            .unwrap_or_default()
+        {
+            return Ok(());
+        }
+
+        let top_level_dependencies = [
+            &self.dependencies,
+            &self.host_dependencies,
+            &self.build_dependencies,
+        ];
+        let target_dependencies = self
+            .target
+            .values()
+            .flat_map(|t| [&t.dependencies, &t.host_dependencies, &t.build_dependencies]);
+        let feature_dependencies = self.feature.values().flat_map(|f| {
+            let top_level_dependencies =
+                [&f.dependencies, &f.host_dependencies, &f.build_dependencies];
+            let target_dependencies = f
+                .target
+                .values()
+                .flat_map(|t| [&t.dependencies, &t.host_dependencies, &t.build_dependencies]);
+            chain!(top_level_dependencies, target_dependencies)
+        });
+        let package_dependencies = self.package.iter().flat_map(|p| {
+            [
+                &p.value.host_dependencies,
+                &p.value.build_dependencies,
+                &p.value.run_dependencies,
+            ]
+        });
+
+        for map in chain!(
+            top_level_dependencies,
+            target_dependencies,
+            feature_dependencies,
+            package_dependencies
+        )
+        .flatten()
+        {
+            for (name, spec) in &map.value.specs {
+                // Binary archives are only read, they can be stored anywhere.
+                let PixiSpec::Path(path) = spec else {
+                    continue;
+                };
+                if !path.is_binary() && matches!(path.is_within(root), Ok(false)) {
+                    return Err(GenericError::new(format!(
+                        "the path dependency '{}' refers to '{}', which is outside of the workspace",
+                        name.as_source(),
+                        path.path
+                    ))
+                    .with_opt_span(map.value.value_spans.get(name).cloned())
+                    .with_help(
+                        "set `allow-external-paths = true` in the `[workspace]` table to allow path dependencies outside of the workspace",
+                    )
+                    .into());
+                }
+            }
+        }
+
+        let pypi_dependencies = chain!(
+            [&self.pypi_dependencies],
+            self.target.values().map(|t| &t.pypi_dependencies),
+            self.feature.values().flat_map(|f| {
+                chain!(
+                    [&f.pypi_dependencies],
+                    f.target.values().map(|t| &t.pypi_dependencies)
+                )
+            })
+        )
+        .flatten();
+        for map in pypi_dependencies {
+            for (name, requirement) in map {
+                let PyPiRequirement::Path { path, .. } = requirement else {
+                    continue;
+                };
+                // Wheels are only read, they can be stored anywhere.
+                if path.extension().is_some_and(|ext| ext == "whl") {
+                    continue;
+                }
+                let spec = PathSpec::new(path.to_string_lossy().as_ref());
+                if matches!(spec.is_within(root), Ok(false)) {
+                    return Err(GenericError::new(format!(
+                        "the pypi path dependency '{}' refers to '{}', which is outside of the workspace",
+                        name.as_source(),
+                        path.display()
+                    ))
+                    .with_help(
+                        "set `allow-external-paths = true` in the `[workspace]` table to allow path dependencies outside of the workspace",
+                    )
+                    .into());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     /// Converts the raw manifest into a workspace manifest.
     ///
     /// The `name` is used to set the workspace name in the manifest if it is
@@ -130,9 +277,11 @@ impl TomlManifest {
         external: ExternalWorkspaceProperties,
     ) -> Result<(WorkspaceManifest, Option<PackageManifest>, Vec<Warning>), TomlError> {
         self.check_dependency_usage()?;
+        self.check_pypi_dependency_groups()?;
 
         let preview = &self.workspace.value.preview;
         let pixi_build_enabled = self.is_pixi_build_enabled();
+        let r_mapping = RMapping::from_overrides(self.r_mapping);
 
         let WithWarnings {
             value: default_workspace_target,
@@ -142,18 +291,19 @@ impl TomlManifest {
             host_dependencies: self.host_dependencies,
             build_dependencies: self.build_dependencies,
             pypi_dependencies: self.pypi_dependencies,
+            r_dependencies: self.r_dependencies,
             activation: self.activation,
             tasks: self.tasks,
             warnings: self.warnings,
         }
-        .into_workspace_target(None, preview)?;
+        .into_workspace_target(None, preview, &r_mapping)?;
 
         let mut workspace_targets = IndexMap::new();
         for (selector, target) in self.target {
             let WithWarnings {
                 value: workspace_target,
                 warnings: mut target_warnings,
-            } = target.into_workspace_target(Some(selector.value.clone()), preview)?;
+            } = target.into_workspace_target(Some(selector.value.clone()), preview, &r_mapping)?;
             workspace_targets.insert(selector, workspace_target);
             warnings.append(&mut target_warnings);
         }
@@ -192,7 +342,7 @@ impl TomlManifest {
                 let WithWarnings {
                     value: feature,
                     warnings: mut feature_warnings,
-                } = feature.into_feature(name.clone(), preview)?;
+                } = feature.into_feature(name.clone(), preview, &r_mapping)?;
                 warnings.append(&mut feature_warnings);
                 Ok((name, feature))
             })
@@ -296,6 +446,13 @@ impl TomlManifest {
             features,
             environments,
             solve_groups,
+            package_overrides: PackageOverrides {
+                conda: self.package_overrides,
+                pypi: self.pypi_package_overrides,
+                pypi_conda_mapping: self.pypi_conda_mapping,
+            },
+            r_mapping,
+            external_managers: self.external_managers,
         };
 
         Ok((workspace_manifest, package_manifest, warnings))
@@ -324,6 +481,9 @@ impl<'de> toml_span::Deserialize<'de> for TomlManifest {
         let pypi_dependencies = th
             .optional::<TomlIndexMap<_, _>>("pypi-dependencies")
             .map(TomlIndexMap::into_inner);
+        let r_dependencies = th
+            .optional::<TomlIndexMap<_, _>>("r-dependencies")
+            .map(TomlIndexMap::into_inner);
         let activation = th.optional("activation");
         let tasks = th
             .optional::<TomlHashMap<_, TomlTask>>("tasks")
@@ -349,6 +509,26 @@ impl<'de> toml_span::Deserialize<'de> for TomlManifest {
             .unwrap_or_default();
         let pypi_options = th.optional("pypi-options");
         let system_requirements = th.optional("system-requirements").unwrap_or_default();
+        let package_overrides = th
+            .optional::<TomlIndexMap<_, _>>("package-overrides")
+            .map(TomlIndexMap::into_inner)
+            .unwrap_or_default();
+        let pypi_package_overrides = th
+            .optional::<TomlIndexMap<_, _>>("pypi-package-overrides")
+            .map(TomlIndexMap::into_inner)
+            .unwrap_or_default();
+        let pypi_conda_mapping = th
+            .optional::<TomlIndexMap<_, _>>("pypi-conda-mapping")
+            .map(TomlIndexMap::into_inner)
+            .unwrap_or_default();
+        let r_mapping = th
+            .optional::<TomlWith<_, TomlIndexMap<String, TomlFromStr<PackageName>>>>("r-mapping")
+            .map(TomlWith::into_inner)
+            .unwrap_or_default();
+        let external_managers = th
+            .optional::<TomlIndexMap<_, _>>("external-managers")
+            .map(TomlIndexMap::into_inner)
+            .unwrap_or_default();
 
         // Parse the tool section by ignoring it.
         if let Some(mut tool) = th.table.remove("tool") {
@@ -381,11 +561,17 @@ impl<'de> toml_span::Deserialize<'de> for TomlManifest {
             host_dependencies,
             build_dependencies,
             pypi_dependencies,
+            r_dependencies,
             activation,
             tasks,
             feature,
             environments,
             pypi_options,
+            package_overrides,
+            pypi_package_overrides,
+            pypi_conda_mapping,
+            r_mapping,
+            external_managers,
             warnings,
         })
     }
@@ -3,9 +3,11 @@ mod channel;
 mod deprecation;
 mod document;
 mod environment;
+mod external_managers;
 mod feature;
 mod manifest;
 mod package;
+mod package_overrides;
 mod package_target;
 mod platform;
 mod preview;
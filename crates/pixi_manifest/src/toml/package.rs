@@ -9,7 +9,7 @@ use toml_span::{de_helpers::TableHelper, DeserError, Error, ErrorKind, Span, Val
 use url::Url;
 
 use crate::{
-    package::Package,
+    package::{Package, PackageTests},
     target::PackageTarget,
     toml::{
         package_target::TomlPackageTarget, workspace::ExternalWorkspaceProperties, TomlPackageBuild,
@@ -43,10 +43,44 @@ pub struct TomlPackage {
     pub build_dependencies: Option<PixiSpanned<UniquePackageMap>>,
     pub run_dependencies: Option<PixiSpanned<UniquePackageMap>>,
     pub target: IndexMap<PixiSpanned<TargetSelector>, PackageTarget>,
+    pub tests: Option<TomlPackageTests>,
 
     pub span: Span,
 }
 
+/// The TOML representation of the `[package.tests]` section in a pixi
+/// manifest.
+#[derive(Debug)]
+pub struct TomlPackageTests {
+    pub commands: Vec<String>,
+    pub dependencies: Option<PixiSpanned<UniquePackageMap>>,
+}
+
+impl<'de> toml_span::Deserialize<'de> for TomlPackageTests {
+    fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+        let mut th = TableHelper::new(value)?;
+        let commands = th.optional("commands").unwrap_or_default();
+        let dependencies = th.optional("dependencies");
+        th.finalize(None)?;
+        Ok(TomlPackageTests {
+            commands,
+            dependencies,
+        })
+    }
+}
+
+impl TomlPackageTests {
+    pub fn into_package_tests(self) -> PackageTests {
+        PackageTests {
+            commands: self.commands,
+            dependencies: self
+                .dependencies
+                .map(|dependencies| dependencies.value.specs)
+                .unwrap_or_default(),
+        }
+    }
+}
+
 impl<'de> toml_span::Deserialize<'de> for TomlPackage {
     fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
         let mut th = TableHelper::new(value)?;
@@ -81,6 +115,7 @@ impl<'de> toml_span::Deserialize<'de> for TomlPackage {
             .optional::<TomlWith<_, TomlIndexMap<_, TomlPackageTarget>>>("target")
             .map(TomlWith::into_inner)
             .unwrap_or_default();
+        let tests = th.optional("tests");
         th.finalize(None)?;
 
         Ok(TomlPackage {
@@ -99,6 +134,7 @@ impl<'de> toml_span::Deserialize<'de> for TomlPackage {
             run_dependencies,
             build,
             target,
+            tests,
             span: value.span,
         })
     }
@@ -189,6 +225,7 @@ impl TomlPackage {
             },
             build: self.build.into_build_system()?,
             targets: Targets::from_default_and_user_defined(default_package_target, self.target),
+            tests: self.tests.map(TomlPackageTests::into_package_tests),
         })
     }
 }
@@ -217,6 +254,63 @@ mod test {
         ));
     }
 
+    #[test]
+    fn test_package_tests() {
+        let package = TomlPackage::from_toml_str(
+            r#"
+        name = "foo"
+        version = "0.1.0"
+
+        [build]
+        backend = { name = "bla", version = "1.0" }
+
+        [tests]
+        commands = ["foo --version", "pytest tests"]
+        dependencies = { pytest = ">=8" }
+        "#,
+        )
+        .unwrap();
+        let manifest = package
+            .into_manifest(ExternalPackageProperties {
+                name: None,
+                version: None,
+                description: None,
+                authors: None,
+                license: None,
+                license_file: None,
+                readme: None,
+                homepage: None,
+                repository: None,
+                documentation: None,
+            })
+            .unwrap();
+        let tests = manifest.tests.unwrap();
+        assert_eq!(tests.commands, vec!["foo --version", "pytest tests"]);
+        assert_eq!(
+            tests
+                .dependencies
+                .keys()
+                .map(|name| name.as_source())
+                .collect::<Vec<_>>(),
+            vec!["pytest"]
+        );
+    }
+
+    #[test]
+    fn test_invalid_package_tests_key() {
+        assert_snapshot!(expect_parse_failure(
+            r#"
+        name = "bla"
+
+        [build]
+        backend = { name = "bla", version = "1.0" }
+
+        [tests]
+        requires = ["pytest"]
+        "#,
+        ));
+    }
+
     #[test]
     fn test_invalid_extra_key() {
         assert_snapshot!(expect_parse_failure(
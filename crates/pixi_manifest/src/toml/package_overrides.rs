@@ -0,0 +1,297 @@
+use std::{fmt::Display, str::FromStr};
+
+use pixi_toml::TomlFromStr;
+use toml_span::{
+    de_helpers::{expected, TableHelper},
+    value::ValueInner,
+    DeserError, Error, ErrorKind, Value,
+};
+
+use crate::{package_overrides::PackageOverride, pypi::PyPiPackageName, PypiCondaMapping};
+
+impl<'de, N, V> toml_span::Deserialize<'de> for PackageOverride<N, V>
+where
+    N: FromStr,
+    N::Err: Display,
+    V: FromStr,
+    V::Err: Display,
+{
+    fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+        match value.take() {
+            ValueInner::String(str) => {
+                let version = V::from_str(&str).map_err(|e| Error {
+                    kind: ErrorKind::Custom(e.to_string().into()),
+                    span: value.span,
+                    line_info: None,
+                })?;
+                Ok(PackageOverride::Version(version))
+            }
+            ValueInner::Table(table) => {
+                let mut th = TableHelper::from((table, value.span));
+
+                let version = th
+                    .optional::<TomlFromStr<V>>("version")
+                    .map(TomlFromStr::into_inner);
+                let replace_with = th
+                    .optional::<TomlFromStr<N>>("replace-with")
+                    .map(TomlFromStr::into_inner);
+                let exclude = th.optional_s::<bool>("exclude");
+
+                th.finalize(None)?;
+
+                match (version, replace_with, exclude) {
+                    (Some(version), None, None) => Ok(PackageOverride::Version(version)),
+                    (None, Some(name), None) => Ok(PackageOverride::ReplaceWith(name)),
+                    (None, None, Some(exclude)) if exclude.value => Ok(PackageOverride::Exclude),
+                    (None, None, Some(exclude)) => Err(Error {
+                        kind: ErrorKind::Custom(
+                            "'exclude' can only be set to true, remove the override instead".into(),
+                        ),
+                        span: exclude.span,
+                        line_info: None,
+                    }
+                    .into()),
+                    (None, None, None) => Err(Error {
+                        kind: ErrorKind::Custom(
+                            "one of 'version', 'replace-with' or 'exclude' must be defined".into(),
+                        ),
+                        span: value.span,
+                        line_info: None,
+                    }
+                    .into()),
+                    _ => Err(Error {
+                        kind: ErrorKind::Custom(
+                            "only one of 'version', 'replace-with' or 'exclude' can be defined"
+                                .into(),
+                        ),
+                        span: value.span,
+                        line_info: None,
+                    }
+                    .into()),
+                }
+            }
+            inner => Err(expected("a version string or table", inner, value.span).into()),
+        }
+    }
+}
+
+impl<'de> toml_span::Deserialize<'de> for PypiCondaMapping {
+    fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+        match value.take() {
+            ValueInner::String(str) => {
+                let name = str.parse::<PyPiPackageName>().map_err(|e| Error {
+                    kind: ErrorKind::Custom(e.to_string().into()),
+                    span: value.span,
+                    line_info: None,
+                })?;
+                Ok(PypiCondaMapping::Provides(name))
+            }
+            ValueInner::Boolean(false) => Ok(PypiCondaMapping::NotAPypiPackage),
+            ValueInner::Boolean(true) => Err(Error {
+                kind: ErrorKind::Custom(
+                    "expected the name of a PyPI package, or false if the package doesn't provide one".into(),
+                ),
+                span: value.span,
+                line_info: None,
+            }
+            .into()),
+            inner => Err(expected("a PyPI package name or false", inner, value.span).into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use insta::assert_snapshot;
+    use rattler_conda_types::{PackageName, ParseStrictness, VersionSpec};
+
+    use crate::{
+        toml::{FromTomlStr, TomlManifest},
+        utils::test_utils::format_parse_error,
+        CondaPackageOverride, PypiCondaMapping, WorkspaceManifest,
+    };
+
+    fn expect_parse_failure(pixi_toml: &str) -> String {
+        let parse_error = <TomlManifest as FromTomlStr>::from_toml_str(pixi_toml)
+            .expect_err("parsing should fail");
+        format_parse_error(pixi_toml, parse_error)
+    }
+
+    #[test]
+    fn test_package_overrides() {
+        let manifest = WorkspaceManifest::from_toml_str(
+            r#"
+        [workspace]
+        name = "foo"
+        channels = []
+        platforms = []
+
+        [package-overrides]
+        numpy = "<2"
+        pillow = { replace-with = "pillow-simd" }
+        tk = { exclude = true }
+        openssl = { version = ">=3" }
+
+        [pypi-package-overrides]
+        Requests = "==2.31.0"
+        pillow = { replace-with = "pillow-simd" }
+        tqdm = { exclude = true }
+        "#,
+        )
+        .unwrap();
+
+        let conda = &manifest.package_overrides.conda;
+        assert_eq!(
+            conda[&PackageName::new_unchecked("numpy")],
+            CondaPackageOverride::Version(
+                VersionSpec::from_str("<2", ParseStrictness::Strict).unwrap()
+            )
+        );
+        assert_eq!(
+            conda[&PackageName::new_unchecked("pillow")],
+            CondaPackageOverride::ReplaceWith(PackageName::new_unchecked("pillow-simd"))
+        );
+        assert_eq!(
+            conda[&PackageName::new_unchecked("tk")],
+            CondaPackageOverride::Exclude
+        );
+        assert_eq!(
+            conda[&PackageName::new_unchecked("openssl")],
+            CondaPackageOverride::Version(
+                VersionSpec::from_str(">=3", ParseStrictness::Strict).unwrap()
+            )
+        );
+
+        let pypi = manifest
+            .package_overrides
+            .pypi
+            .iter()
+            .map(|(name, value)| format!("{} = {value:?}", name.as_normalized()))
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_snapshot!(pypi, @r###"
+        requests = Version(VersionSpecifiers([VersionSpecifier { operator: Equal, version: "2.31.0" }]))
+        pillow = ReplaceWith(PyPiPackageName { source: "pillow-simd", normalized: PackageName("pillow-simd") })
+        tqdm = Exclude
+        "###);
+    }
+
+    #[test]
+    fn test_multiple_overrides() {
+        assert_snapshot!(expect_parse_failure(
+            r#"
+        [workspace]
+        name = "foo"
+        channels = []
+        platforms = []
+
+        [package-overrides]
+        pillow = { version = "*", replace-with = "pillow-simd" }
+        "#,
+        ), @r###"
+         × only one of 'version', 'replace-with' or 'exclude' can be defined
+          ╭─[pixi.toml:8:18]
+        7 │         [package-overrides]
+        8 │         pillow = { version = "*", replace-with = "pillow-simd" }
+          ·                  ───────────────────────────────────────────────
+        9 │
+          ╰────
+        "###);
+    }
+
+    #[test]
+    fn test_empty_override() {
+        assert_snapshot!(expect_parse_failure(
+            r#"
+        [workspace]
+        name = "foo"
+        channels = []
+        platforms = []
+
+        [package-overrides]
+        pillow = {}
+        "#,
+        ), @r###"
+         × one of 'version', 'replace-with' or 'exclude' must be defined
+          ╭─[pixi.toml:8:18]
+        7 │         [package-overrides]
+        8 │         pillow = {}
+          ·                  ──
+        9 │
+          ╰────
+        "###);
+    }
+
+    #[test]
+    fn test_exclude_false() {
+        assert_snapshot!(expect_parse_failure(
+            r#"
+        [workspace]
+        name = "foo"
+        channels = []
+        platforms = []
+
+        [package-overrides]
+        pillow = { exclude = false }
+        "#,
+        ), @r###"
+         × 'exclude' can only be set to true, remove the override instead
+          ╭─[pixi.toml:8:30]
+        7 │         [package-overrides]
+        8 │         pillow = { exclude = false }
+          ·                              ─────
+        9 │
+          ╰────
+        "###);
+    }
+
+    #[test]
+    fn test_pypi_conda_mapping() {
+        let manifest = WorkspaceManifest::from_toml_str(
+            r#"
+        [workspace]
+        name = "foo"
+        channels = []
+        platforms = []
+
+        [pypi-conda-mapping]
+        pytorch = "torch"
+        python-graphviz = false
+        "#,
+        )
+        .unwrap();
+
+        let mapping = &manifest.package_overrides.pypi_conda_mapping;
+        assert_eq!(
+            mapping[&PackageName::new_unchecked("pytorch")],
+            PypiCondaMapping::Provides("torch".parse().unwrap())
+        );
+        assert_eq!(
+            mapping[&PackageName::new_unchecked("python-graphviz")],
+            PypiCondaMapping::NotAPypiPackage
+        );
+    }
+
+    #[test]
+    fn test_pypi_conda_mapping_true() {
+        assert_snapshot!(expect_parse_failure(
+            r#"
+        [workspace]
+        name = "foo"
+        channels = []
+        platforms = []
+
+        [pypi-conda-mapping]
+        pytorch = true
+        "#,
+        ), @r###"
+         × expected the name of a PyPI package, or false if the package doesn't provide one
+          ╭─[pixi.toml:8:19]
+        7 │         [pypi-conda-mapping]
+        8 │         pytorch = true
+          ·                   ────
+        9 │
+          ╰────
+        "###);
+    }
+}
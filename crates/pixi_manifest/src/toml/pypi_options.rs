@@ -1,6 +1,6 @@
 use std::path::PathBuf;
 
-use pixi_toml::{TomlEnum, TomlFromStr, TomlWith};
+use pixi_toml::{TomlEnum, TomlFromStr, TomlIndexMap, TomlWith};
 use toml_span::{
     de_helpers::{expected, TableHelper},
     value::ValueInner,
@@ -8,7 +8,9 @@ use toml_span::{
 };
 use url::Url;
 
-use crate::pypi::pypi_options::{FindLinksUrlOrPath, PypiOptions};
+use crate::pypi::pypi_options::{
+    FindLinksUrlOrPath, MarkerEnvironmentOverrides, OwnershipPolicy, PypiOptions, TorchOptions,
+};
 
 impl<'de> toml_span::Deserialize<'de> for PypiOptions {
     fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
@@ -25,6 +27,17 @@ impl<'de> toml_span::Deserialize<'de> for PypiOptions {
         let index_strategy = th
             .optional::<TomlEnum<_>>("index-strategy")
             .map(TomlEnum::into_inner);
+        let no_build = th.optional("no-build");
+        let only_binary = th.optional("only-binary");
+        let no_binary = th.optional("no-binary");
+        let marker_environment = th
+            .optional::<TomlIndexMap<_, _>>("marker-environment")
+            .map(TomlIndexMap::into_inner);
+        let torch = th.optional("torch");
+        let ownership_policy = th.optional("ownership-policy");
+        let package_ownership_policy = th
+            .optional::<TomlIndexMap<_, _>>("package-ownership-policy")
+            .map(TomlIndexMap::into_inner);
 
         th.finalize(None)?;
 
@@ -34,6 +47,65 @@ impl<'de> toml_span::Deserialize<'de> for PypiOptions {
             find_links,
             no_build_isolation,
             index_strategy,
+            no_build,
+            only_binary,
+            no_binary,
+            marker_environment,
+            torch,
+            ownership_policy,
+            package_ownership_policy,
+        })
+    }
+}
+
+impl<'de> toml_span::Deserialize<'de> for OwnershipPolicy {
+    fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+        TomlEnum::deserialize(value).map(TomlEnum::into_inner)
+    }
+}
+
+impl<'de> toml_span::Deserialize<'de> for TorchOptions {
+    fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+        let mut th = TableHelper::new(value)?;
+
+        let backend = th.required::<TomlEnum<_>>("backend")?.into_inner();
+
+        th.finalize(None)?;
+
+        Ok(Self { backend })
+    }
+}
+
+impl<'de> toml_span::Deserialize<'de> for MarkerEnvironmentOverrides {
+    fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
+        let mut th = TableHelper::new(value)?;
+
+        let implementation_name = th.optional("implementation_name");
+        let implementation_version = th.optional("implementation_version");
+        let os_name = th.optional("os_name");
+        let platform_machine = th.optional("platform_machine");
+        let platform_python_implementation = th.optional("platform_python_implementation");
+        let platform_release = th.optional("platform_release");
+        let platform_system = th.optional("platform_system");
+        let platform_version = th.optional("platform_version");
+        let python_full_version = th.optional("python_full_version");
+        let python_version = th.optional("python_version");
+        let sys_platform = th.optional("sys_platform");
+
+        th.finalize(None)?;
+
+        Ok(Self {
+            implementation_name,
+            implementation_version,
+            os_name,
+            platform_machine,
+            platform_python_implementation,
+            platform_release,
+            platform_system,
+            platform_version,
+            python_full_version,
+            python_version,
+            sys_platform,
         })
     }
 }
@@ -149,6 +221,13 @@ mod test {
                 ]),
                 no_build_isolation: Some(vec!["pkg1".to_string(), "pkg2".to_string()]),
                 index_strategy: None,
+                no_build: None,
+                only_binary: None,
+                no_binary: None,
+                marker_environment: None,
+                torch: None,
+                ownership_policy: None,
+                package_ownership_policy: None,
             },
         );
     }
@@ -164,6 +243,17 @@ mod test {
         ]
         no-build-isolation = ["sigma"]
         index-strategy = "first-index"
+        no-build = true
+        only-binary = ["numpy"]
+        no-binary = ["pyyaml"]
+        ownership-policy = "prefer-conda"
+        package-ownership-policy = { numpy = "prefer-pypi" }
+
+        [marker-environment.linux-aarch64]
+        platform_machine = "aarch64"
+
+        [torch]
+        backend = "cu124"
         "#;
         let options = PypiOptions::from_toml_str(input).unwrap();
         assert_debug_snapshot!(options);
@@ -228,6 +318,26 @@ mod test {
         )
     }
 
+    #[test]
+    fn test_invalid_torch_backend() {
+        let input = r#"
+        torch = { backend = "cuda" }
+        "#;
+        assert_snapshot!(
+            format_parse_error(input, PypiOptions::from_toml_str(input).unwrap_err()),
+            @r###"
+         × Expected one of 'cpu', 'cu118', 'cu121', 'cu124', 'rocm'
+          ╭─[pixi.toml:2:30]
+        1 │
+        2 │         torch = { backend = "cuda" }
+          ·                              ────
+        3 │
+          ╰────
+         help: Did you mean 'cpu'?
+        "###
+        )
+    }
+
     #[test]
     fn test_missing_url_or_path() {
         let input = "find-links = [{}]";
@@ -10,8 +10,8 @@ use crate::{
     pypi::PyPiPackageName,
     toml::{preview::TomlPreview, task::TomlTask, warning::WithWarnings, Warning},
     utils::{package_map::UniquePackageMap, PixiSpanned},
-    Activation, KnownPreviewFeature, PyPiRequirement, SpecType, TargetSelector, Task, TaskName,
-    TomlError, WorkspaceTarget,
+    Activation, KnownPreviewFeature, PyPiRequirement, RMapping, SpecType, TargetSelector, Task,
+    TaskName, TomlError, WorkspaceTarget,
 };
 
 #[derive(Debug, Default)]
@@ -20,6 +20,7 @@ pub struct TomlTarget {
     pub host_dependencies: Option<PixiSpanned<UniquePackageMap>>,
     pub build_dependencies: Option<PixiSpanned<UniquePackageMap>>,
     pub pypi_dependencies: Option<IndexMap<PyPiPackageName, PyPiRequirement>>,
+    pub r_dependencies: Option<IndexMap<String, PixiSpec>>,
 
     /// Additional information to activate an environment.
     pub activation: Option<Activation>,
@@ -37,6 +38,7 @@ impl TomlTarget {
         self,
         target: Option<TargetSelector>,
         preview: &TomlPreview,
+        r_mapping: &RMapping,
     ) -> Result<WithWarnings<WorkspaceTarget>, TomlError> {
         let pixi_build_enabled = preview.is_enabled(KnownPreviewFeature::PixiBuild);
 
@@ -67,17 +69,38 @@ impl TomlTarget {
             }
         }
 
+        let dependencies = combine_target_dependencies([
+            (SpecType::Run, self.dependencies),
+            (SpecType::Host, self.host_dependencies),
+            (SpecType::Build, self.build_dependencies),
+        ]);
+
+        let mut target = WorkspaceTarget {
+            dependencies,
+            pypi_dependencies: self.pypi_dependencies,
+            r_dependencies: None,
+            r_conda_dependencies: Default::default(),
+            activation: self.activation,
+            tasks: self.tasks,
+        };
+
+        // The R packages are installed from the conda packages that provide them. A
+        // conda dependency on the same package takes precedence.
+        for (r_name, spec) in self.r_dependencies.iter().flatten() {
+            let name = r_mapping.conda_name(r_name).map_err(|e| {
+                TomlError::Generic(
+                    GenericError::new(format!(
+                        "the R package `{r_name}` can't be mapped to a conda package: {e}"
+                    ))
+                    .with_help("Map it to a conda package in the [r-mapping] table"),
+                )
+            })?;
+            target.add_r_conda_dependency(&name, spec);
+        }
+        target.r_dependencies = self.r_dependencies;
+
         Ok(WithWarnings {
-            value: WorkspaceTarget {
-                dependencies: combine_target_dependencies([
-                    (SpecType::Run, self.dependencies),
-                    (SpecType::Host, self.host_dependencies),
-                    (SpecType::Build, self.build_dependencies),
-                ]),
-                pypi_dependencies: self.pypi_dependencies,
-                activation: self.activation,
-                tasks: self.tasks,
-            },
+            value: target,
             warnings: self.warnings,
         })
     }
@@ -103,6 +126,9 @@ impl<'de> toml_span::Deserialize<'de> for TomlTarget {
         let pypi_dependencies = th
             .optional::<TomlIndexMap<_, _>>("pypi-dependencies")
             .map(TomlIndexMap::into_inner);
+        let r_dependencies = th
+            .optional::<TomlIndexMap<_, _>>("r-dependencies")
+            .map(TomlIndexMap::into_inner);
         let activation = th.optional("activation");
         let tasks = th
             .optional::<TomlHashMap<_, TomlTask>>("tasks")
@@ -126,6 +152,7 @@ impl<'de> toml_span::Deserialize<'de> for TomlTarget {
             host_dependencies,
             build_dependencies,
             pypi_dependencies,
+            r_dependencies,
             activation,
             tasks,
             warnings,
@@ -1,16 +1,20 @@
-use std::{collections::HashMap, path::PathBuf};
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+};
 
 use indexmap::{IndexMap, IndexSet};
 use pixi_toml::{TomlFromStr, TomlHashMap, TomlIndexMap, TomlIndexSet, TomlWith};
 use rattler_conda_types::{NamedChannelOrUrl, Platform, Version};
-use toml_span::{de_helpers::TableHelper, DeserError, Error, ErrorKind, Span, Value};
+use toml_span::{de_helpers::TableHelper, DeserError, Error, ErrorKind, Span, Spanned, Value};
 use url::Url;
 
 use crate::{
+    error::GenericError,
     pypi::pypi_options::PypiOptions,
     toml::{platform::TomlPlatform, preview::TomlPreview},
     utils::PixiSpanned,
-    workspace::ChannelPriority,
+    workspace::{ChannelPriority, ExcludeNewer, VariantConfigSource},
     PrioritizedChannel, TargetSelector, Targets, TomlError, Workspace,
 };
 
@@ -31,6 +35,9 @@ pub struct TomlWorkspace {
     pub authors: Option<Vec<String>>,
     pub channels: IndexSet<PrioritizedChannel>,
     pub channel_priority: Option<ChannelPriority>,
+    pub exclude_newer: Option<ExcludeNewer>,
+    pub per_environment_lock_files: Option<bool>,
+    pub allow_external_paths: Option<bool>,
     pub platforms: PixiSpanned<IndexSet<Platform>>,
     pub license: Option<String>,
     pub license_file: Option<PathBuf>,
@@ -43,6 +50,8 @@ pub struct TomlWorkspace {
     pub preview: TomlPreview,
     pub target: IndexMap<PixiSpanned<TargetSelector>, TomlWorkspaceTarget>,
     pub build_variants: Option<HashMap<String, Vec<String>>>,
+    pub build_variants_zip_keys: Option<PixiSpanned<Vec<Vec<String>>>>,
+    pub variant_config: Option<Vec<VariantConfigSource>>,
 
     pub span: Span,
 }
@@ -70,6 +79,16 @@ impl TomlWorkspace {
         self,
         external: ExternalWorkspaceProperties,
     ) -> Result<Workspace, TomlError> {
+        let build_variants_zip_keys = match self.build_variants_zip_keys {
+            Some(zip_keys) => {
+                check_zip_keys(&zip_keys.value).map_err(|message| {
+                    GenericError::new(message).with_opt_span(zip_keys.span.clone())
+                })?;
+                zip_keys.value
+            }
+            None => Vec::new(),
+        };
+
         Ok(Workspace {
             name: self.name.or(external.name).ok_or(Error {
                 kind: ErrorKind::MissingField("name"),
@@ -87,6 +106,9 @@ impl TomlWorkspace {
             documentation: self.documentation.or(external.documentation),
             channels: self.channels,
             channel_priority: self.channel_priority,
+            exclude_newer: self.exclude_newer,
+            per_environment_lock_files: self.per_environment_lock_files.unwrap_or_default(),
+            allow_external_paths: self.allow_external_paths.unwrap_or_default(),
             platforms: self.platforms,
             conda_pypi_map: self.conda_pypi_map,
             pypi_options: self.pypi_options,
@@ -99,6 +121,8 @@ impl TomlWorkspace {
                     .map(|(k, v)| (k, v.build_variants))
                     .collect(),
             ),
+            build_variants_zip_keys,
+            variant_config: self.variant_config.unwrap_or_default(),
         })
     }
 }
@@ -117,6 +141,11 @@ impl<'de> toml_span::Deserialize<'de> for TomlWorkspace {
             .required::<TomlIndexSet<_>>("channels")
             .map(TomlIndexSet::into_inner)?;
         let channel_priority = th.optional("channel-priority");
+        let exclude_newer = th
+            .optional::<TomlFromStr<_>>("exclude-newer")
+            .map(TomlFromStr::into_inner);
+        let per_environment_lock_files = th.optional("per-environment-lock-files");
+        let allow_external_paths = th.optional("allow-external-paths");
         let platforms = th
             .optional::<TomlWith<_, PixiSpanned<TomlIndexSet<TomlPlatform>>>>("platforms")
             .map(TomlWith::into_inner);
@@ -144,9 +173,11 @@ impl<'de> toml_span::Deserialize<'de> for TomlWorkspace {
         let target = th
             .optional::<TomlIndexMap<_, _>>("target")
             .map(TomlIndexMap::into_inner);
-        let build_variants = th
-            .optional::<TomlHashMap<_, _>>("build-variants")
-            .map(TomlHashMap::into_inner);
+        let build_variants = build_variants(&mut th);
+        let build_variants_zip_keys = th.optional("build-variants-zip-keys");
+        let variant_config = th
+            .optional::<TomlWith<_, Vec<TomlFromStr<_>>>>("variant-config")
+            .map(TomlWith::into_inner);
 
         th.finalize(None)?;
 
@@ -157,6 +188,9 @@ impl<'de> toml_span::Deserialize<'de> for TomlWorkspace {
             authors,
             channels,
             channel_priority,
+            exclude_newer,
+            per_environment_lock_files,
+            allow_external_paths,
             platforms: platforms.unwrap_or_default(),
             license,
             license_file,
@@ -169,18 +203,63 @@ impl<'de> toml_span::Deserialize<'de> for TomlWorkspace {
             preview,
             target: target.unwrap_or_default(),
             build_variants,
+            build_variants_zip_keys,
+            variant_config,
             span: value.span,
         })
     }
 }
 
+/// Checks that every group of zipped build variant keys contains at least two
+/// keys, and that no key is part of more than one group.
+fn check_zip_keys(zip_keys: &[Vec<String>]) -> Result<(), String> {
+    let mut seen = HashSet::new();
+    for group in zip_keys {
+        if group.len() < 2 {
+            return Err(format!(
+                "a group of `build-variants-zip-keys` must contain at least two keys, found [{}]",
+                group.join(", ")
+            ));
+        }
+        for key in group {
+            if !seen.insert(key.as_str()) {
+                return Err(format!(
+                    "the build variant '{key}' is part of more than one group of `build-variants-zip-keys`"
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Deserializes the `build-variants` table. Every variant needs at least one
+/// value, otherwise there would be nothing to build.
+fn build_variants(th: &mut TableHelper<'_>) -> Option<HashMap<String, Vec<String>>> {
+    let variants = th
+        .optional::<TomlHashMap<String, Spanned<Vec<String>>>>("build-variants")?
+        .into_inner();
+    let mut result = HashMap::with_capacity(variants.len());
+    for (key, values) in variants {
+        if values.value.is_empty() {
+            th.errors.push(Error {
+                kind: ErrorKind::Custom(
+                    format!("the build variant '{key}' needs at least one value").into(),
+                ),
+                span: values.span,
+                line_info: None,
+            });
+            continue;
+        }
+        result.insert(key, values.value);
+    }
+    Some(result)
+}
+
 impl<'de> toml_span::Deserialize<'de> for TomlWorkspaceTarget {
     fn deserialize(value: &mut Value<'de>) -> Result<Self, DeserError> {
         let mut th = TableHelper::new(value)?;
 
-        let build_variants = th
-            .optional::<TomlHashMap<_, _>>("build-variants")
-            .map(TomlHashMap::into_inner);
+        let build_variants = build_variants(&mut th);
 
         th.finalize(None)?;
 
@@ -133,6 +133,19 @@ impl WorkspaceManifest {
             }
         }
 
+        // Check that the external managers only run in existing environments
+        for (name, manager) in self.external_managers.iter() {
+            for environment in manager.environments.iter().flatten() {
+                if self.environments.find(environment).is_none() {
+                    return Err(miette::miette!(
+                        "the external manager '{}' runs in the environment '{}', which is not defined",
+                        name,
+                        environment.as_str()
+                    ));
+                }
+            }
+        }
+
         // Warn on any unknown preview features
         let preview = self.workspace.preview.unknown_preview_features();
         if !preview.is_empty() {
@@ -1,8 +1,9 @@
-use std::{collections::HashMap, path::PathBuf};
+use std::{collections::HashMap, fmt::Display, path::PathBuf, str::FromStr};
 
+use chrono::{DateTime, NaiveDate, SecondsFormat, Utc};
 use indexmap::IndexSet;
 use pixi_toml::TomlEnum;
-use rattler_conda_types::{NamedChannelOrUrl, Platform, Version};
+use rattler_conda_types::{NamedChannelOrUrl, PackageName, Platform, Version};
 use serde::Deserialize;
 use toml_span::{DeserError, Value};
 use url::Url;
@@ -31,6 +32,15 @@ pub struct Workspace {
     /// Channel priority for the whole project
     pub channel_priority: Option<ChannelPriority>,
 
+    /// Packages published after this point in time are ignored when solving
+    pub exclude_newer: Option<ExcludeNewer>,
+
+    /// Store the lock file of every environment in a separate file
+    pub per_environment_lock_files: bool,
+
+    /// Allow path dependencies that point outside of the workspace
+    pub allow_external_paths: bool,
+
     /// The platforms this project supports
     // TODO: This is actually slightly different from the rattler_conda_types::Platform because it
     //     should not include noarch.
@@ -65,6 +75,13 @@ pub struct Workspace {
 
     /// Build variants
     pub build_variants: Targets<Option<HashMap<String, Vec<String>>>>,
+
+    /// Groups of build variant keys whose values are combined pairwise
+    /// instead of with every value of the other keys
+    pub build_variants_zip_keys: Vec<Vec<String>>,
+
+    /// Variant config files that the build variants are merged with
+    pub variant_config: Vec<VariantConfigSource>,
 }
 
 #[derive(
@@ -112,3 +129,118 @@ impl From<rattler_solve::ChannelPriority> for ChannelPriority {
         }
     }
 }
+
+/// A point in time after which published packages are ignored when solving
+/// the workspace.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct ExcludeNewer(pub DateTime<Utc>);
+
+#[derive(Debug, thiserror::Error)]
+#[error("'{0}' is neither a date (e.g. 2024-06-01) nor a RFC 3339 timestamp (e.g. 2024-06-01T12:00:00Z)")]
+pub struct ParseExcludeNewerError(String);
+
+impl FromStr for ExcludeNewer {
+    type Err = ParseExcludeNewerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(timestamp) = DateTime::parse_from_rfc3339(s) {
+            return Ok(Self(timestamp.with_timezone(&Utc)));
+        }
+
+        // A date means midnight UTC at the start of that day.
+        NaiveDate::parse_from_str(s, "%Y-%m-%d")
+            .ok()
+            .and_then(|date| date.and_hms_opt(0, 0, 0))
+            .map(|date_time| Self(date_time.and_utc()))
+            .ok_or_else(|| ParseExcludeNewerError(s.to_string()))
+    }
+}
+
+impl Display for ExcludeNewer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0.to_rfc3339_opts(SecondsFormat::Secs, true))
+    }
+}
+
+impl From<ExcludeNewer> for DateTime<Utc> {
+    fn from(value: ExcludeNewer) -> Self {
+        value.0
+    }
+}
+
+/// A conda-build style variant config file that build variants are loaded
+/// from.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum VariantConfigSource {
+    /// A file relative to the workspace root, e.g. `./variants.yaml`.
+    Path(PathBuf),
+
+    /// A package that ships a `conda_build_config.yaml` in its root, e.g.
+    /// `conda-forge-pinning`. The package is fetched from the channels of the
+    /// workspace.
+    Package(PackageName),
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("'{0}' is neither a path to a variant config file nor a package name")]
+pub struct ParseVariantConfigSourceError(String);
+
+impl FromStr for VariantConfigSource {
+    type Err = ParseVariantConfigSourceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let is_path = s.contains(['/', '\\']) || s.ends_with(".yaml") || s.ends_with(".yml");
+        if is_path {
+            return Ok(Self::Path(PathBuf::from(s)));
+        }
+        PackageName::from_str(s)
+            .map(Self::Package)
+            .map_err(|_| ParseVariantConfigSourceError(s.to_string()))
+    }
+}
+
+impl Display for VariantConfigSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Path(path) => write!(f, "{}", path.display()),
+            Self::Package(name) => write!(f, "{}", name.as_source()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_variant_config_source() {
+        assert_eq!(
+            VariantConfigSource::from_str("./variants.yaml").unwrap(),
+            VariantConfigSource::Path(PathBuf::from("./variants.yaml"))
+        );
+        assert_eq!(
+            VariantConfigSource::from_str("variants.yml").unwrap(),
+            VariantConfigSource::Path(PathBuf::from("variants.yml"))
+        );
+        assert_eq!(
+            VariantConfigSource::from_str("conda-forge-pinning").unwrap(),
+            VariantConfigSource::Package(PackageName::from_str("conda-forge-pinning").unwrap())
+        );
+        assert!(VariantConfigSource::from_str("not a package").is_err());
+    }
+
+    #[test]
+    fn test_parse_exclude_newer() {
+        assert_eq!(
+            ExcludeNewer::from_str("2024-06-01").unwrap().to_string(),
+            "2024-06-01T00:00:00Z"
+        );
+        assert_eq!(
+            ExcludeNewer::from_str("2024-06-01T12:30:00+02:00")
+                .unwrap()
+                .to_string(),
+            "2024-06-01T10:30:00Z"
+        );
+        assert!(ExcludeNewer::from_str("last tuesday").is_err());
+    }
+}
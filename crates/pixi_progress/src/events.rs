@@ -0,0 +1,150 @@
+//! Machine-readable progress reporting.
+//!
+//! When the progress format is set to [`ProgressFormat::JsonLines`] the
+//! progress bars are hidden and instead every [`ProgressEvent`] is written to
+//! stderr as a single line of JSON. This allows tools that wrap pixi (GUIs,
+//! IDE plugins) to render their own progress.
+
+use std::io::Write;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use serde::Serialize;
+
+/// The format in which progress is reported to the user.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ProgressFormat {
+    /// Render progress bars on stderr.
+    #[default]
+    Bars,
+
+    /// Write newline-delimited JSON events to stderr.
+    JsonLines,
+}
+
+static PROGRESS_FORMAT: AtomicU8 = AtomicU8::new(0);
+
+/// Sets the global format in which progress is reported.
+pub fn set_progress_format(format: ProgressFormat) {
+    let value = match format {
+        ProgressFormat::Bars => 0,
+        ProgressFormat::JsonLines => 1,
+    };
+    PROGRESS_FORMAT.store(value, Ordering::Relaxed);
+}
+
+/// Returns the global format in which progress is reported.
+pub fn progress_format() -> ProgressFormat {
+    match PROGRESS_FORMAT.load(Ordering::Relaxed) {
+        1 => ProgressFormat::JsonLines,
+        _ => ProgressFormat::Bars,
+    }
+}
+
+/// The kind of packages that are being solved.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SolveKind {
+    Conda,
+    Pypi,
+}
+
+/// A single progress event.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum ProgressEvent {
+    /// A long running task started, e.g. "loading all package names".
+    TaskStarted { message: String },
+
+    /// A long running task finished.
+    TaskFinished { message: String, duration_ms: u64 },
+
+    /// Solving an environment for a platform started.
+    SolveStarted {
+        kind: SolveKind,
+        environment: String,
+        platform: String,
+    },
+
+    /// Solving an environment for a platform finished.
+    SolveFinished {
+        kind: SolveKind,
+        environment: String,
+        platform: String,
+        duration_ms: u64,
+    },
+
+    /// Installing packages into a prefix started.
+    InstallStarted {
+        prefix: String,
+        link: usize,
+        unlink: usize,
+    },
+
+    /// Bytes of a package were downloaded.
+    DownloadProgress {
+        package: String,
+        bytes: u64,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        total_bytes: Option<u64>,
+    },
+
+    /// A package was completely downloaded.
+    DownloadFinished { package: String },
+
+    /// A package was linked into a prefix.
+    LinkFinished {
+        package: String,
+        linked: usize,
+        total: usize,
+    },
+
+    /// Installing packages into a prefix finished.
+    InstallFinished { prefix: String, duration_ms: u64 },
+}
+
+/// Emits a progress event if the progress format is
+/// [`ProgressFormat::JsonLines`], otherwise this does nothing.
+///
+/// The event is constructed lazily to avoid allocating when not needed.
+pub fn emit(event: impl FnOnce() -> ProgressEvent) {
+    if progress_format() != ProgressFormat::JsonLines {
+        return;
+    }
+
+    let Ok(line) = serde_json::to_string(&event()) else {
+        return;
+    };
+
+    // Write the line in one go so that concurrent events don't interleave.
+    let mut stderr = std::io::stderr().lock();
+    let _ = writeln!(stderr, "{line}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serialize_event() {
+        let event = ProgressEvent::SolveFinished {
+            kind: SolveKind::Conda,
+            environment: "default".to_string(),
+            platform: "linux-64".to_string(),
+            duration_ms: 42,
+        };
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"event":"solve-finished","kind":"conda","environment":"default","platform":"linux-64","duration_ms":42}"#
+        );
+
+        let event = ProgressEvent::DownloadProgress {
+            package: "python".to_string(),
+            bytes: 10,
+            total_bytes: None,
+        };
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"event":"download-progress","package":"python","bytes":10}"#
+        );
+    }
+}
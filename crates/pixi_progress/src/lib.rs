@@ -4,7 +4,13 @@ use std::borrow::Cow;
 use std::fmt::Write;
 use std::future::Future;
 use std::sync::{Arc, LazyLock};
-use std::time::Duration;
+use std::time::{Duration, Instant};
+
+mod events;
+
+pub use events::{
+    emit, progress_format, set_progress_format, ProgressEvent, ProgressFormat, SolveKind,
+};
 
 /// Returns a global instance of [`indicatif::MultiProgress`].
 ///
@@ -50,12 +56,21 @@ pub fn long_running_progress_style() -> indicatif::ProgressStyle {
 
 /// Displays a spinner with the given message while running the specified function to completion.
 pub fn wrap_in_progress<T, F: FnOnce() -> T>(msg: impl Into<Cow<'static, str>>, func: F) -> T {
+    let msg = msg.into();
+    let start = Instant::now();
+    emit(|| ProgressEvent::TaskStarted {
+        message: msg.trim().to_string(),
+    });
     let pb = global_multi_progress().add(ProgressBar::new_spinner());
     pb.enable_steady_tick(Duration::from_millis(100));
     pb.set_style(long_running_progress_style());
-    pb.set_message(msg);
+    pb.set_message(msg.clone());
     let result = func();
     pb.finish_and_clear();
+    emit(|| ProgressEvent::TaskFinished {
+        message: msg.trim().to_string(),
+        duration_ms: start.elapsed().as_millis() as u64,
+    });
     result
 }
 
@@ -70,6 +85,10 @@ pub async fn await_in_progress<T, F: FnOnce(ProgressBar) -> Fut, Fut: Future<Out
         _ => ("", msg.as_ref()),
     };
 
+    let start = Instant::now();
+    emit(|| ProgressEvent::TaskStarted {
+        message: msg.to_string(),
+    });
     let pb = global_multi_progress().add(ProgressBar::new_spinner());
     pb.enable_steady_tick(Duration::from_millis(100));
     pb.set_style(long_running_progress_style());
@@ -77,6 +96,10 @@ pub async fn await_in_progress<T, F: FnOnce(ProgressBar) -> Fut, Fut: Future<Out
     pb.set_message(msg.to_string());
     let result = future(pb.clone()).await;
     pb.finish_and_clear();
+    emit(|| ProgressEvent::TaskFinished {
+        message: msg.to_string(),
+        duration_ms: start.elapsed().as_millis() as u64,
+    });
     result
 }
 
@@ -7,14 +7,23 @@ use std::{
 
 use miette::IntoDiagnostic;
 use pixi_git::url::{redact_credentials, RepositoryUrl};
-use pixi_git::{sha::GitSha, GitUrl};
+use pixi_git::{git::GitFetchOptions, sha::GitSha, GitUrl};
 use pixi_spec::{GitSpec, PathSourceSpec, Reference, SourceSpec, UrlSourceSpec};
 
-use rattler_digest::{Md5Hash, Sha256Hash};
+use rattler_digest::{
+    parse_digest_from_hex, Blake2b256, Blake2b256Hash, Md5, Md5Hash, Sha256, Sha256Hash,
+};
 use rattler_lock::UrlOrPath;
 use thiserror::Error;
 use typed_path::Utf8TypedPathBuf;
-use url::Url;
+use url::{form_urlencoded, Url};
+
+/// The fragment keys under which the hashes and the mirrors of a url source
+/// are locked.
+const SHA256_KEY: &str = "sha256";
+const MD5_KEY: &str = "md5";
+const BLAKE2B_KEY: &str = "blake2b";
+const MIRROR_KEY: &str = "mirror";
 
 /// Describes an exact revision of a source checkout. This is used to pin a
 /// particular source definition to a revision. A git source spec does not
@@ -140,6 +149,11 @@ pub struct PinnedUrlSpec {
     pub sha256: Sha256Hash,
     /// The md5 hash of the archive.
     pub md5: Option<Md5Hash>,
+    /// The blake2b-256 hash of the archive.
+    pub blake2b: Option<Blake2b256Hash>,
+    /// Alternative URLs of the same archive, tried in order when the archive
+    /// can't be downloaded from `url`.
+    pub mirrors: Vec<Url>,
 }
 
 impl From<PinnedUrlSpec> for PinnedSourceSpec {
@@ -148,6 +162,70 @@ impl From<PinnedUrlSpec> for PinnedSourceSpec {
     }
 }
 
+impl PinnedUrlSpec {
+    /// Construct the lockfile-compatible [`Url`] from [`PinnedUrlSpec`].
+    ///
+    /// The hashes and the mirrors of the archive are stored in the fragment of
+    /// the url, like `https://example.com/foo.tar.gz#sha256=...&mirror=...`.
+    pub fn to_locked_url(&self) -> Url {
+        let mut url = self.url.clone();
+        let mut fragment = form_urlencoded::Serializer::new(String::new());
+        fragment.append_pair(SHA256_KEY, &format!("{:x}", self.sha256));
+        if let Some(md5) = &self.md5 {
+            fragment.append_pair(MD5_KEY, &format!("{:x}", md5));
+        }
+        if let Some(blake2b) = &self.blake2b {
+            fragment.append_pair(BLAKE2B_KEY, &format!("{:x}", blake2b));
+        }
+        for mirror in &self.mirrors {
+            fragment.append_pair(MIRROR_KEY, mirror.as_str());
+        }
+        url.set_fragment(Some(&fragment.finish()));
+        url
+    }
+
+    /// Parses a url source from the lock-file, see [`Self::to_locked_url`].
+    fn from_locked_url(mut locked_url: Url) -> Result<Self, ParseError> {
+        let fragment = locked_url.fragment().unwrap_or_default().to_string();
+        locked_url.set_fragment(None);
+
+        let mut sha256 = None;
+        let mut md5 = None;
+        let mut blake2b = None;
+        let mut mirrors = Vec::new();
+        for (key, value) in form_urlencoded::parse(fragment.as_bytes()) {
+            let invalid_hash = || ParseError::InvalidUrlHash {
+                hash: key.to_string(),
+                value: value.to_string(),
+            };
+            match &*key {
+                SHA256_KEY => {
+                    sha256 = Some(parse_digest_from_hex::<Sha256>(&value).ok_or_else(invalid_hash)?)
+                }
+                MD5_KEY => {
+                    md5 = Some(parse_digest_from_hex::<Md5>(&value).ok_or_else(invalid_hash)?)
+                }
+                BLAKE2B_KEY => {
+                    blake2b =
+                        Some(parse_digest_from_hex::<Blake2b256>(&value).ok_or_else(invalid_hash)?)
+                }
+                MIRROR_KEY => mirrors.push(
+                    Url::parse(&value).map_err(|_| ParseError::InvalidMirror(value.to_string()))?,
+                ),
+                _ => continue,
+            }
+        }
+
+        Ok(Self {
+            sha256: sha256.ok_or_else(|| ParseError::MissingUrlHash(locked_url.clone()))?,
+            url: locked_url,
+            md5,
+            blake2b,
+            mirrors,
+        })
+    }
+}
+
 /// A pinned version of a git checkout.
 #[derive(Clone, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
 pub struct PinnedGitCheckout {
@@ -157,6 +235,8 @@ pub struct PinnedGitCheckout {
     pub subdirectory: Option<String>,
     /// The reference of the git checkout.
     pub reference: Reference,
+    /// How much of the repository was fetched and checked out.
+    pub options: GitFetchOptions,
 }
 
 impl PinnedGitCheckout {
@@ -166,6 +246,7 @@ impl PinnedGitCheckout {
         let url = &locked_url.0;
         let mut reference = None;
         let mut subdirectory = None;
+        let mut options = GitFetchOptions::default();
 
         for (key, val) in url.query_pairs() {
             match &*key {
@@ -201,6 +282,23 @@ impl PinnedGitCheckout {
                         return Err(miette::miette!("multiple subdirectories in URL"));
                     }
                 }
+                "depth" => {
+                    let depth = val
+                        .parse()
+                        .map_err(|_| miette::miette!("invalid depth '{val}' in URL"))?;
+                    options.depth = Some(depth);
+                }
+                "submodules" => {
+                    let submodules = val
+                        .parse()
+                        .map_err(|_| miette::miette!("invalid submodules '{val}' in URL"))?;
+                    options.submodules = Some(submodules);
+                }
+                "lfs" => {
+                    options.lfs = val
+                        .parse()
+                        .map_err(|_| miette::miette!("invalid lfs '{val}' in URL"))?;
+                }
                 _ => continue,
             };
         }
@@ -217,6 +315,7 @@ impl PinnedGitCheckout {
             commit,
             subdirectory,
             reference: reference.expect("reference should be set"),
+            options,
         })
     }
 }
@@ -266,6 +365,20 @@ impl PinnedGitSpec {
             Reference::DefaultBranch => {}
         }
 
+        // Put the options that change the contents of the checkout in the query.
+        let options = &self.source.options;
+        if let Some(depth) = options.depth {
+            url.query_pairs_mut()
+                .append_pair("depth", depth.to_string().as_str());
+        }
+        if let Some(submodules) = options.submodules {
+            url.query_pairs_mut()
+                .append_pair("submodules", submodules.to_string().as_str());
+        }
+        if options.lfs {
+            url.query_pairs_mut().append_pair("lfs", "true");
+        }
+
         // Put the precise commit in the fragment.
         url.set_fragment(self.source.commit.to_string().as_str().into());
 
@@ -337,8 +450,8 @@ impl From<PinnedGitSpec> for UrlOrPath {
 }
 
 impl From<PinnedUrlSpec> for UrlOrPath {
-    fn from(_value: PinnedUrlSpec) -> Self {
-        unimplemented!()
+    fn from(value: PinnedUrlSpec) -> Self {
+        UrlOrPath::Url(value.to_locked_url())
     }
 }
 
@@ -412,7 +525,24 @@ impl From<PinnedGitSpec> for LockedGitUrl {
 
 #[derive(Debug, Error)]
 /// An error that occurs when parsing a [`PinnedSourceSpec`].
-pub enum ParseError {}
+pub enum ParseError {
+    /// The locked url of a source archive has no sha256 hash.
+    #[error("the locked url '{0}' is missing the sha256 hash of the archive")]
+    MissingUrlHash(Url),
+
+    /// A hash of a locked url is not a valid hex digest.
+    #[error("'{value}' is not a valid {hash} hash")]
+    InvalidUrlHash {
+        /// The name of the hash.
+        hash: String,
+        /// The value in the lock-file.
+        value: String,
+    },
+
+    /// A mirror of a locked url is not a valid url.
+    #[error("'{0}' is not a valid mirror url")]
+    InvalidMirror(String),
+}
 
 impl TryFrom<UrlOrPath> for PinnedSourceSpec {
     type Error = ParseError;
@@ -427,7 +557,7 @@ impl TryFrom<UrlOrPath> for PinnedSourceSpec {
                         let pinned = locked_url.to_pinned_git_spec().unwrap();
                         Ok(pinned.into())
                     }
-                    false => unimplemented!("url not supported"),
+                    false => Ok(PinnedUrlSpec::from_locked_url(url)?.into()),
                 }
             }
             UrlOrPath::Path(path) => Ok(PinnedPathSpec { path }.into()),
@@ -491,6 +621,17 @@ pub enum SourceMismatchError {
         requested: Option<String>,
     },
 
+    #[error("the locked git options {locked:?} for '{git}' do not match the requested git options {requested:?}")]
+    /// The locked git options do not match the requested git options.
+    GitOptionsMismatch {
+        /// The git url.
+        git: Url,
+        /// The locked git options.
+        locked: GitFetchOptions,
+        /// The requested git options.
+        requested: GitFetchOptions,
+    },
+
     #[error("the locked source type does not match the requested type")]
     /// The locked source type does not match the requested type.
     SourceTypeMismatch,
@@ -511,6 +652,12 @@ impl PinnedPathSpec {
 }
 
 impl PinnedUrlSpec {
+    /// Returns the URLs to download the archive from, in the order they should
+    /// be tried.
+    pub fn urls(&self) -> impl Iterator<Item = &Url> + '_ {
+        std::iter::once(&self.url).chain(self.mirrors.iter())
+    }
+
     #[allow(clippy::result_large_err)]
     /// Verifies if the locked url satisfies the requested url.
     pub fn satisfies(&self, spec: &UrlSourceSpec) -> Result<(), SourceMismatchError> {
@@ -542,6 +689,18 @@ impl PinnedUrlSpec {
                 });
             }
         }
+        if let Some(blake2b) = &spec.blake2b {
+            if Some(blake2b) != self.blake2b.as_ref() {
+                return Err(SourceMismatchError::UrlHashMismatch {
+                    hash: "blake2b",
+                    url: self.url.clone(),
+                    locked: self
+                        .blake2b
+                        .map_or("None".to_string(), |blake2b| format!("{:x}", blake2b)),
+                    requested: format!("{:x}", blake2b),
+                });
+            }
+        }
         Ok(())
     }
 }
@@ -569,6 +728,15 @@ impl PinnedGitSpec {
             });
         }
 
+        // Check if the options match.
+        if self.source.options != spec.options {
+            return Err(SourceMismatchError::GitOptionsMismatch {
+                git: self.git.clone(),
+                locked: self.source.options.clone(),
+                requested: spec.options.clone(),
+            });
+        }
+
         // Check if requested rev matches.
         let locked_git_ref = self.source.reference.clone();
 
@@ -628,13 +796,18 @@ impl Display for PinnedGitSpec {
 
 #[cfg(test)]
 mod tests {
-    use std::str::FromStr;
+    use std::{num::NonZeroU32, str::FromStr};
 
-    use pixi_git::sha::GitSha;
-    use pixi_spec::{GitSpec, Reference};
+    use pixi_git::{git::GitFetchOptions, sha::GitSha};
+    use pixi_spec::{GitSpec, Reference, UrlSourceSpec};
+    use rattler_digest::{parse_digest_from_hex, Blake2b256, Md5, Sha256};
+    use rattler_lock::UrlOrPath;
     use url::Url;
 
-    use crate::{PinnedGitCheckout, PinnedGitSpec, SourceMismatchError};
+    use crate::{
+        pinned_source::ParseError, PinnedGitCheckout, PinnedGitSpec, PinnedSourceSpec,
+        PinnedUrlSpec, SourceMismatchError,
+    };
 
     #[test]
     fn test_spec_satisfies() {
@@ -644,6 +817,7 @@ mod tests {
                 commit: GitSha::from_str("9de9e1b48cc421f05fc6aa6918cade3033a38c32").unwrap(),
                 subdirectory: None,
                 reference: pixi_spec::Reference::Rev("9de9e1b".to_string()),
+                options: Default::default(),
             },
         };
 
@@ -651,6 +825,8 @@ mod tests {
             git: Url::parse("https://github.com/example/repo.git").unwrap(),
             subdirectory: None,
             rev: Some(pixi_spec::Reference::Rev("9de9e1b".to_string())),
+            options: Default::default(),
+            ignore_run_exports: Default::default(),
         };
 
         let result = locked_git_spec.satisfies(&requested_git_spec);
@@ -663,6 +839,7 @@ mod tests {
                 commit: GitSha::from_str("9de9e1b48cc421f05fc6aa6918cade3033a38c32").unwrap(),
                 subdirectory: None,
                 reference: pixi_spec::Reference::Rev("9de9e1b".to_string()),
+                options: Default::default(),
             },
         };
 
@@ -670,6 +847,8 @@ mod tests {
             git: Url::parse("https://github.com/example/repo.git").unwrap(),
             subdirectory: None,
             rev: Some(pixi_spec::Reference::Rev("9de9e1b".to_string())),
+            options: Default::default(),
+            ignore_run_exports: Default::default(),
         };
 
         let result = locked_git_spec_without_git_suffix.satisfies(&requested_git_spec);
@@ -682,6 +861,7 @@ mod tests {
                 commit: GitSha::from_str("9de9e1b48cc421f05fc6aa6918cade3033a38c32").unwrap(),
                 subdirectory: None,
                 reference: pixi_spec::Reference::Rev("9de9e1b".to_string()),
+                options: Default::default(),
             },
         };
 
@@ -689,6 +869,8 @@ mod tests {
             git: Url::parse("https://github.com/example/repo").unwrap(),
             subdirectory: None,
             rev: Some(pixi_spec::Reference::Rev("9de9e1b".to_string())),
+            options: Default::default(),
+            ignore_run_exports: Default::default(),
         };
 
         let result = locked_git_spec.satisfies(&requested_git_spec_without_suffix);
@@ -701,6 +883,7 @@ mod tests {
                 commit: GitSha::from_str("9de9e1b48cc421f05fc6aa6918cade3033a38c32").unwrap(),
                 subdirectory: None,
                 reference: pixi_spec::Reference::Rev("9de9e1b".to_string()),
+                options: Default::default(),
             },
         };
 
@@ -708,6 +891,8 @@ mod tests {
             git: Url::parse("https://github.com/example/repo.git").unwrap(),
             subdirectory: None,
             rev: Some(pixi_spec::Reference::Rev("9de9e1b".to_string())),
+            options: Default::default(),
+            ignore_run_exports: Default::default(),
         };
 
         let result = locked_git_spec.satisfies(&requested_git_spec);
@@ -720,6 +905,7 @@ mod tests {
                 commit: GitSha::from_str("9de9e1b48cc421f05fc6aa6918cade3033a38c32").unwrap(),
                 subdirectory: None,
                 reference: pixi_spec::Reference::Rev("9de9e1b".to_string()),
+                options: Default::default(),
             },
         };
 
@@ -727,6 +913,8 @@ mod tests {
             git: Url::parse("git+https://github.com/example/repo.git").unwrap(),
             subdirectory: None,
             rev: Some(pixi_spec::Reference::Rev("9de9e1b".to_string())),
+            options: Default::default(),
+            ignore_run_exports: Default::default(),
         };
 
         let result = locked_git_spec.satisfies(&requested_git_spec_with_prefix);
@@ -744,6 +932,7 @@ mod tests {
                 commit: GitSha::from_str("9de9e1b48cc421f05fc6aa6918cade3033a38c32").unwrap(),
                 subdirectory: None,
                 reference: pixi_spec::Reference::Rev("9de9e1b".to_string()),
+                options: Default::default(),
             },
         };
 
@@ -751,6 +940,8 @@ mod tests {
             git: Url::parse("https://github.com/example/repo.git").unwrap(),
             subdirectory: None,
             rev: Some(pixi_spec::Reference::Rev("d2e32".to_string())),
+            options: Default::default(),
+            ignore_run_exports: Default::default(),
         };
 
         let result = locked_git_spec.satisfies(&requested_git_spec).unwrap_err();
@@ -765,6 +956,7 @@ mod tests {
                 commit: GitSha::from_str("9de9e1b48cc421f05fc6aa6918cade3033a38c32").unwrap(),
                 subdirectory: None,
                 reference: pixi_spec::Reference::Rev("9de9e1b".to_string()),
+                options: Default::default(),
             },
         };
 
@@ -772,6 +964,8 @@ mod tests {
             git: Url::parse("https://github.com/example/repo.git").unwrap(),
             subdirectory: None,
             rev: Some(pixi_spec::Reference::Rev("9de9e1b".to_string())),
+            options: Default::default(),
+            ignore_run_exports: Default::default(),
         };
 
         let result = locked_git_spec.satisfies(&requested_git_spec).unwrap_err();
@@ -786,6 +980,7 @@ mod tests {
                 commit: GitSha::from_str("9de9e1b48cc421f05fc6aa6918cade3033a38c32").unwrap(),
                 subdirectory: None,
                 reference: Reference::DefaultBranch,
+                options: Default::default(),
             },
         };
 
@@ -795,6 +990,8 @@ mod tests {
             // we are not specifying the rev
             // and request the default branch
             rev: None,
+            options: Default::default(),
+            ignore_run_exports: Default::default(),
         };
 
         let result = locked_git_spec.satisfies(&requested_git_spec);
@@ -809,6 +1006,7 @@ mod tests {
                 commit: GitSha::from_str("9de9e1b48cc421f05fc6aa6918cade3033a38c32").unwrap(),
                 subdirectory: Some("some-subdir".to_string()),
                 reference: Reference::DefaultBranch,
+                options: Default::default(),
             },
         };
 
@@ -818,6 +1016,8 @@ mod tests {
             // we are not specifying the rev
             // and request the default branch
             rev: None,
+            options: Default::default(),
+            ignore_run_exports: Default::default(),
         };
 
         let result = locked_git_spec.satisfies(&requested_git_spec).unwrap_err();
@@ -833,6 +1033,7 @@ mod tests {
                 commit: GitSha::from_str("9de9e1b48cc421f05fc6aa6918cade3033a38c32").unwrap(),
                 subdirectory: None,
                 reference: Reference::DefaultBranch,
+                options: Default::default(),
             },
         };
 
@@ -842,6 +1043,8 @@ mod tests {
             // we are not specifying the rev
             // and request the default branch
             rev: None,
+            options: Default::default(),
+            ignore_run_exports: Default::default(),
         };
 
         let result = locked_git_spec.satisfies(&requested_git_spec).unwrap_err();
@@ -850,4 +1053,141 @@ mod tests {
             SourceMismatchError::GitSubdirectoryMismatch { .. }
         ));
     }
+
+    #[test]
+    fn test_git_options() {
+        let options = GitFetchOptions {
+            depth: NonZeroU32::new(1),
+            submodules: Some(false),
+            lfs: true,
+        };
+        let locked_git_spec = PinnedGitSpec {
+            git: Url::parse("https://github.com/example/repo.git").unwrap(),
+            source: PinnedGitCheckout {
+                commit: GitSha::from_str("9de9e1b48cc421f05fc6aa6918cade3033a38c32").unwrap(),
+                subdirectory: None,
+                reference: Reference::DefaultBranch,
+                options: options.clone(),
+            },
+        };
+
+        // The options survive a round trip through the lock file.
+        let locked_url = locked_git_spec.into_locked_git_url();
+        let pinned = locked_url.to_pinned_git_spec().unwrap();
+        assert_eq!(pinned.source.options, options);
+        assert_eq!(
+            Url::from(locked_url).as_str(),
+            "git+https://github.com/example/repo.git?depth=1&submodules=false&lfs=true#9de9e1b48cc421f05fc6aa6918cade3033a38c32"
+        );
+
+        let requested_git_spec = GitSpec {
+            git: Url::parse("https://github.com/example/repo.git").unwrap(),
+            subdirectory: None,
+            rev: None,
+            options: Default::default(),
+            ignore_run_exports: Default::default(),
+        };
+        let result = locked_git_spec.satisfies(&requested_git_spec).unwrap_err();
+        assert!(matches!(
+            result,
+            SourceMismatchError::GitOptionsMismatch { .. }
+        ));
+    }
+
+    #[test]
+    fn test_url_spec_satisfies() {
+        let url = Url::parse("https://example.com/foo-1.0.tar.gz").unwrap();
+        let mirror = Url::parse("https://mirror.example.com/foo-1.0.tar.gz").unwrap();
+        let sha256 = parse_digest_from_hex::<Sha256>(
+            "4b1c1f7f1b3b5c9b6e43f5f1c8e0c1a1e4f6d2b1f3e0c9a7b5d3f1e2c4a6b8d0",
+        )
+        .unwrap();
+        let blake2b = parse_digest_from_hex::<Blake2b256>(
+            "0c1a1e4f6d2b1f3e0c9a7b5d3f1e2c4a6b8d04b1c1f7f1b3b5c9b6e43f5f1c8e",
+        )
+        .unwrap();
+        let locked_url_spec = PinnedUrlSpec {
+            url: url.clone(),
+            sha256,
+            md5: None,
+            blake2b: Some(blake2b),
+            mirrors: vec![mirror.clone()],
+        };
+        assert_eq!(
+            locked_url_spec.urls().collect::<Vec<_>>(),
+            vec![&url, &mirror]
+        );
+
+        let requested_url_spec = UrlSourceSpec {
+            url: url.clone(),
+            md5: None,
+            sha256: Some(sha256),
+            blake2b: Some(blake2b),
+            mirrors: vec![mirror],
+        };
+        locked_url_spec.satisfies(&requested_url_spec).unwrap();
+
+        // A different blake2b hash in the manifest requires a new download.
+        let requested_url_spec = UrlSourceSpec {
+            blake2b: parse_digest_from_hex::<Blake2b256>(
+                "4b1c1f7f1b3b5c9b6e43f5f1c8e0c1a1e4f6d2b1f3e0c9a7b5d3f1e2c4a6b8d0",
+            ),
+            ..requested_url_spec
+        };
+        let result = locked_url_spec.satisfies(&requested_url_spec).unwrap_err();
+        assert!(matches!(
+            result,
+            SourceMismatchError::UrlHashMismatch {
+                hash: "blake2b",
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_url_spec_lock_round_trip() {
+        let sha256 = parse_digest_from_hex::<Sha256>(
+            "4b1c1f7f1b3b5c9b6e43f5f1c8e0c1a1e4f6d2b1f3e0c9a7b5d3f1e2c4a6b8d0",
+        )
+        .unwrap();
+        let locked_url_spec = PinnedUrlSpec {
+            url: Url::parse("https://example.com/foo-1.0.tar.gz?token=abc").unwrap(),
+            sha256,
+            md5: parse_digest_from_hex::<Md5>("b3af409bb8423187c75e6c7f5b683908"),
+            blake2b: parse_digest_from_hex::<Blake2b256>(
+                "0c1a1e4f6d2b1f3e0c9a7b5d3f1e2c4a6b8d04b1c1f7f1b3b5c9b6e43f5f1c8e",
+            ),
+            mirrors: vec![
+                Url::parse("https://mirror.example.com/foo-1.0.tar.gz").unwrap(),
+                Url::parse("https://other.example.com/foo.tar.gz?a=1&b=2").unwrap(),
+            ],
+        };
+
+        let location = UrlOrPath::from(PinnedSourceSpec::Url(locked_url_spec.clone()));
+        let pinned = PinnedSourceSpec::try_from(location).unwrap();
+        let pinned = pinned.as_url().unwrap();
+        assert_eq!(pinned.url, locked_url_spec.url);
+        assert_eq!(pinned.sha256, locked_url_spec.sha256);
+        assert_eq!(pinned.md5, locked_url_spec.md5);
+        assert_eq!(pinned.blake2b, locked_url_spec.blake2b);
+        assert_eq!(pinned.mirrors, locked_url_spec.mirrors);
+
+        // Only the sha256 hash is required.
+        let location = UrlOrPath::Url(
+            Url::parse(&format!(
+                "https://example.com/foo-1.0.tar.gz#sha256={sha256:x}"
+            ))
+            .unwrap(),
+        );
+        let pinned = PinnedSourceSpec::try_from(location).unwrap();
+        let pinned = pinned.as_url().unwrap();
+        assert_eq!(pinned.url.as_str(), "https://example.com/foo-1.0.tar.gz");
+        assert!(pinned.md5.is_none() && pinned.blake2b.is_none() && pinned.mirrors.is_empty());
+
+        let location = UrlOrPath::Url(Url::parse("https://example.com/foo-1.0.tar.gz").unwrap());
+        assert!(matches!(
+            PinnedSourceSpec::try_from(location),
+            Err(ParseError::MissingUrlHash(_))
+        ));
+    }
 }
@@ -1,5 +1,6 @@
+use pixi_spec::IgnoreRunExports;
 use rattler_conda_types::{MatchSpec, Matches, NamelessMatchSpec, PackageRecord};
-use rattler_digest::{Sha256, Sha256Hash};
+use rattler_digest::{digest::Digest, Sha256, Sha256Hash};
 use rattler_lock::{CondaPackageData, CondaSourceData};
 use serde::{Deserialize, Serialize};
 
@@ -21,6 +22,14 @@ pub struct SourceRecord {
     /// If this is `None`, the input hash was not computed or is not relevant
     /// for this record. The record can always be considered up to date.
     pub input_hash: Option<InputHash>,
+
+    /// The run exports that are ignored when the package is built.
+    ///
+    /// The lock-file has no field for them, they are covered by the locked
+    /// [`Self::input_hash`] instead, see [`InputHash::with_ignore_run_exports`].
+    /// A record that is read from the lock-file takes them from the requested
+    /// source spec.
+    pub ignore_run_exports: IgnoreRunExports,
 }
 
 /// Defines the hash of the input files that were used to build the metadata of
@@ -36,6 +45,41 @@ pub struct InputHash {
     pub globs: Vec<String>,
 }
 
+impl InputHash {
+    /// Combines the input hash of a source package with the run exports that
+    /// are ignored when it is built, these change the metadata of the package
+    /// just like its input files. Without ignored run exports the input hash
+    /// is returned unchanged.
+    pub fn with_ignore_run_exports(
+        input_hash: Option<InputHash>,
+        ignore_run_exports: &IgnoreRunExports,
+    ) -> Option<InputHash> {
+        if ignore_run_exports.is_empty() {
+            return input_hash;
+        }
+
+        let mut hasher = Sha256::default();
+        if let Some(input_hash) = &input_hash {
+            hasher.update(input_hash.hash);
+        }
+        for (key, names) in [
+            ("ignore-run-exports", &ignore_run_exports.by_name),
+            ("ignore-run-exports-from", &ignore_run_exports.from_package),
+        ] {
+            for name in names {
+                hasher.update(format!("{key}={}\n", name.as_normalized()));
+            }
+        }
+
+        Some(InputHash {
+            hash: hasher.finalize(),
+            globs: input_hash
+                .map(|input_hash| input_hash.globs)
+                .unwrap_or_default(),
+        })
+    }
+}
+
 impl From<SourceRecord> for CondaPackageData {
     fn from(value: SourceRecord) -> Self {
         CondaPackageData::Source(CondaSourceData {
@@ -60,6 +104,7 @@ impl TryFrom<CondaSourceData> for SourceRecord {
                 hash: hash.hash,
                 globs: hash.globs,
             }),
+            ignore_run_exports: IgnoreRunExports::default(),
         })
     }
 }
@@ -101,3 +146,50 @@ impl AsRef<PackageRecord> for SourceRecord {
         &self.package_record
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use pixi_spec::IgnoreRunExports;
+    use rattler_conda_types::PackageName;
+    use rattler_digest::Sha256Hash;
+
+    use crate::InputHash;
+
+    #[test]
+    fn test_input_hash_with_ignore_run_exports() {
+        let input_hash = InputHash {
+            hash: Sha256Hash::default(),
+            globs: vec!["pixi.toml".to_string()],
+        };
+
+        // Without ignored run exports the input hash is unchanged, so existing
+        // lock-files stay valid.
+        let unchanged =
+            InputHash::with_ignore_run_exports(Some(input_hash.clone()), &Default::default())
+                .unwrap();
+        assert_eq!(unchanged.hash, input_hash.hash);
+        assert!(InputHash::with_ignore_run_exports(None, &Default::default()).is_none());
+
+        let by_name = IgnoreRunExports {
+            by_name: vec![PackageName::new_unchecked("libzlib")],
+            from_package: Vec::new(),
+        };
+        let from_package = IgnoreRunExports {
+            by_name: Vec::new(),
+            from_package: vec![PackageName::new_unchecked("libzlib")],
+        };
+        let with_by_name =
+            InputHash::with_ignore_run_exports(Some(input_hash.clone()), &by_name).unwrap();
+        let with_from_package =
+            InputHash::with_ignore_run_exports(Some(input_hash.clone()), &from_package).unwrap();
+        assert_ne!(with_by_name.hash, input_hash.hash);
+        assert_ne!(with_by_name.hash, with_from_package.hash);
+        assert_eq!(with_by_name.globs, input_hash.globs);
+
+        // Immutable sources have no input files, but still lock the ignored run
+        // exports.
+        let without_files = InputHash::with_ignore_run_exports(None, &by_name).unwrap();
+        assert!(without_files.globs.is_empty());
+        assert_ne!(without_files.hash, with_by_name.hash);
+    }
+}
@@ -1,10 +1,12 @@
 use std::fmt::Display;
 
-use pixi_git::git::GitReference;
+use pixi_git::git::{GitFetchOptions, GitReference};
 use serde::{Serialize, Serializer};
 use thiserror::Error;
 use url::Url;
 
+use crate::IgnoreRunExports;
+
 /// A specification of a package from a git repository.
 #[derive(Debug, Clone, Hash, Eq, PartialEq, ::serde::Serialize, ::serde::Deserialize)]
 #[serde(rename_all = "kebab-case")]
@@ -19,6 +21,16 @@ pub struct GitSpec {
     /// The git subdirectory of the package
     #[serde(skip_serializing_if = "Option::is_none")]
     pub subdirectory: Option<String>,
+
+    /// How much of the repository is fetched and checked out, e.g. a shallow
+    /// clone without submodules.
+    #[serde(default, flatten)]
+    pub options: GitFetchOptions,
+
+    /// The run exports that are ignored when resolving the dependencies of
+    /// the package
+    #[serde(default, flatten)]
+    pub ignore_run_exports: IgnoreRunExports,
 }
 
 /// A reference to a specific commit in a git repository.
@@ -11,6 +11,7 @@
 mod detailed;
 mod git;
 mod path;
+mod run_exports;
 mod toml;
 mod url;
 
@@ -23,6 +24,7 @@ pub use path::{PathBinarySpec, PathSourceSpec, PathSpec};
 use rattler_conda_types::{
     ChannelConfig, NamedChannelOrUrl, NamelessMatchSpec, ParseChannelError, VersionSpec,
 };
+pub use run_exports::IgnoreRunExports;
 use thiserror::Error;
 pub use toml::TomlSpec;
 pub use url::{UrlBinarySpec, UrlSourceSpec, UrlSpec};
@@ -106,6 +108,8 @@ impl PixiSpec {
                 url,
                 md5: spec.md5,
                 sha256: spec.sha256,
+                blake2b: None,
+                mirrors: Vec::new(),
             })
         } else if spec.build.is_none()
             && spec.build_number.is_none()
@@ -337,6 +341,17 @@ impl SourceSpec {
     pub fn is_git(&self) -> bool {
         matches!(self, Self::Git(_))
     }
+
+    /// Returns the run exports that are ignored when the dependencies of the
+    /// package are resolved, or `None` if no run exports are ignored.
+    pub fn ignore_run_exports(&self) -> Option<&IgnoreRunExports> {
+        let ignore_run_exports = match self {
+            Self::Url(_) => return None,
+            Self::Git(git) => &git.ignore_run_exports,
+            Self::Path(path) => &path.ignore_run_exports,
+        };
+        (!ignore_run_exports.is_empty()).then_some(ignore_run_exports)
+    }
 }
 
 impl From<SourceSpec> for PixiSpec {
@@ -1,19 +1,31 @@
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 
 use itertools::Either;
 use rattler_conda_types::{package::ArchiveIdentifier, NamelessMatchSpec};
 use typed_path::{Utf8NativePathBuf, Utf8TypedPathBuf};
 
-use crate::{BinarySpec, SpecConversionError};
+use crate::{BinarySpec, IgnoreRunExports, SpecConversionError};
 
 /// A specification of a package from a git repository.
 #[derive(Debug, Clone, Hash, Eq, PartialEq)]
 pub struct PathSpec {
     /// The path to the package
     pub path: Utf8TypedPathBuf,
+
+    /// The run exports that are ignored when resolving the dependencies of
+    /// the package, only used for source packages
+    pub ignore_run_exports: IgnoreRunExports,
 }
 
 impl PathSpec {
+    /// Constructs a new instance that points to the given path.
+    pub fn new(path: impl Into<Utf8TypedPathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            ignore_run_exports: IgnoreRunExports::default(),
+        }
+    }
+
     /// Converts this instance into a [`NamelessMatchSpec`] if the path points
     /// to binary archive.
     pub fn try_into_nameless_match_spec(
@@ -35,6 +47,18 @@ impl PathSpec {
         resolve_path(Path::new(self.path.as_str()), root_dir)
     }
 
+    /// Returns true if the path, resolved relative to `root_dir`, lies within
+    /// `root_dir`. The path is normalized lexically, so `..` components that
+    /// step outside of `root_dir` are detected even if the path doesn't exist.
+    pub fn is_within(&self, root_dir: impl AsRef<Path>) -> Result<bool, SpecConversionError> {
+        let root_dir = normalize_path(root_dir.as_ref());
+        let path = normalize_path(&self.resolve(&root_dir)?);
+        Ok(
+            !matches!(path.components().next(), Some(Component::ParentDir))
+                && path.starts_with(root_dir),
+        )
+    }
+
     /// Converts this instance into a [`PathSourceSpec`] if the path points to a
     /// source package. Otherwise, returns this instance unmodified.
     #[allow(clippy::result_large_err)]
@@ -42,7 +66,10 @@ impl PathSpec {
         if self.is_binary() {
             Err(self)
         } else {
-            Ok(PathSourceSpec { path: self.path })
+            Ok(PathSourceSpec {
+                path: self.path,
+                ignore_run_exports: self.ignore_run_exports,
+            })
         }
     }
 
@@ -60,7 +87,10 @@ impl PathSpec {
         if self.is_binary() {
             Either::Right(PathBinarySpec { path: self.path })
         } else {
-            Either::Left(PathSourceSpec { path: self.path })
+            Either::Left(PathSourceSpec {
+                path: self.path,
+                ignore_run_exports: self.ignore_run_exports,
+            })
         }
     }
 }
@@ -71,11 +101,18 @@ impl PathSpec {
 pub struct PathSourceSpec {
     /// The path to the package. Either a directory or an archive.
     pub path: Utf8TypedPathBuf,
+
+    /// The run exports that are ignored when resolving the dependencies of
+    /// the package
+    pub ignore_run_exports: IgnoreRunExports,
 }
 
 impl From<PathSourceSpec> for PathSpec {
     fn from(value: PathSourceSpec) -> Self {
-        Self { path: value.path }
+        Self {
+            path: value.path,
+            ignore_run_exports: value.ignore_run_exports,
+        }
     }
 }
 
@@ -100,7 +137,7 @@ pub struct PathBinarySpec {
 
 impl From<PathBinarySpec> for PathSpec {
     fn from(value: PathBinarySpec) -> Self {
-        Self { path: value.path }
+        Self::new(value.path)
     }
 }
 
@@ -178,3 +215,42 @@ fn resolve_path(path: &Path, root_dir: impl AsRef<Path>) -> Result<PathBuf, Spec
         Ok(root_dir.as_ref().join(path))
     }
 }
+
+/// Removes the `.` and `..` components from a path without touching the
+/// filesystem.
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push(component);
+                }
+            }
+            _ => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_is_within() {
+        let root = if cfg!(windows) {
+            Path::new("C:\\workspace")
+        } else {
+            Path::new("/workspace")
+        };
+        let is_within = |path: &str| PathSpec::new(path).is_within(root).unwrap();
+        assert!(is_within("."));
+        assert!(is_within("packages/mylib"));
+        assert!(is_within("./packages/../mylib"));
+        assert!(!is_within(".."));
+        assert!(!is_within("../mylib"));
+        assert!(!is_within("packages/../../mylib"));
+    }
+}
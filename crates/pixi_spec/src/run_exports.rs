@@ -0,0 +1,31 @@
+use rattler_conda_types::PackageName;
+use serde::{Deserialize, Serialize};
+
+/// The run exports that are ignored when the dependencies of a source package
+/// are resolved by its build backend.
+#[derive(Debug, Default, Clone, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub struct IgnoreRunExports {
+    /// The names of the run exports that are ignored, regardless of the
+    /// package that exports them.
+    #[serde(
+        default,
+        rename = "ignore-run-exports",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub by_name: Vec<PackageName>,
+
+    /// The packages of which all the run exports are ignored.
+    #[serde(
+        default,
+        rename = "ignore-run-exports-from",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub from_package: Vec<PackageName>,
+}
+
+impl IgnoreRunExports {
+    /// Returns true if no run exports are ignored.
+    pub fn is_empty(&self) -> bool {
+        self.by_name.is_empty() && self.from_package.is_empty()
+    }
+}
@@ -1,14 +1,15 @@
-use std::{borrow::Cow, fmt::Display, path::PathBuf};
+use std::{borrow::Cow, fmt::Display, num::NonZeroU32, path::PathBuf};
 
 use itertools::Either;
+use pixi_git::git::GitFetchOptions;
 use pixi_toml::{TomlDigest, TomlFromStr};
 use rattler_conda_types::{
     version_spec::{ParseConstraintError, ParseVersionSpecError},
-    BuildNumberSpec, ChannelConfig, NamedChannelOrUrl, NamelessMatchSpec,
+    BuildNumberSpec, ChannelConfig, NamedChannelOrUrl, NamelessMatchSpec, PackageName,
     ParseStrictness::{Lenient, Strict},
     StringMatcher, VersionSpec,
 };
-use rattler_digest::{Md5Hash, Sha256Hash};
+use rattler_digest::{Blake2b256Hash, Md5Hash, Sha256Hash};
 use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
 use serde_with::serde_as;
 use thiserror::Error;
@@ -19,7 +20,9 @@ use toml_span::{
 };
 use url::Url;
 
-use crate::{BinarySpec, DetailedSpec, GitSpec, PathSpec, PixiSpec, Reference, UrlSpec};
+use crate::{
+    BinarySpec, DetailedSpec, GitSpec, IgnoreRunExports, PathSpec, PixiSpec, Reference, UrlSpec,
+};
 
 /// A TOML representation of a package specification.
 #[serde_as]
@@ -34,12 +37,21 @@ pub struct TomlSpec {
     /// The URL of the package
     pub url: Option<Url>,
 
+    /// Alternative URLs of the same archive, tried in order when the archive
+    /// can't be downloaded from `url`
+    pub mirrors: Option<Vec<Url>>,
+
     /// The git url of the package
     pub git: Option<Url>,
 
     /// The path to the package
     pub path: Option<String>,
 
+    /// A conda-forge style feedstock repository on GitHub (e.g.
+    /// `conda-forge/mylib-feedstock`), a shorthand for a `git` dependency on
+    /// the `recipe` directory of the repository.
+    pub feedstock: Option<String>,
+
     /// The git revision of the package
     pub branch: Option<String>,
 
@@ -52,6 +64,15 @@ pub struct TomlSpec {
     /// The git subdirectory of the package
     pub subdirectory: Option<String>,
 
+    /// The number of commits of history to fetch of the git repository
+    pub depth: Option<u32>,
+
+    /// Whether the submodules of the git repository are checked out
+    pub submodules: Option<bool>,
+
+    /// Whether the Git LFS files of the git repository are fetched
+    pub lfs: Option<bool>,
+
     /// The build string of the package (e.g. `py37_0`, `py37h6de7cb9_0`, `py*`)
     #[serde_as(as = "Option<serde_with::DisplayFromStr>")]
     pub build: Option<StringMatcher>,
@@ -76,6 +97,20 @@ pub struct TomlSpec {
     /// The sha256 hash of the package
     #[serde_as(as = "Option<rattler_digest::serde::SerializableHash::<rattler_digest::Sha256>>")]
     pub sha256: Option<Sha256Hash>,
+
+    /// The blake2b-256 hash of the package
+    #[serde_as(
+        as = "Option<rattler_digest::serde::SerializableHash::<rattler_digest::Blake2b256>>"
+    )]
+    pub blake2b: Option<Blake2b256Hash>,
+
+    /// The names of the run exports to ignore when resolving the dependencies
+    /// of a source package
+    pub ignore_run_exports: Option<Vec<PackageName>>,
+
+    /// The packages of which the run exports are ignored when resolving the
+    /// dependencies of a source package
+    pub ignore_run_exports_from: Option<Vec<PackageName>>,
 }
 
 /// Returns a more helpful message when a version spec is used incorrectly.
@@ -129,21 +164,40 @@ fn version_spec_error<T: Into<String>>(input: T) -> Option<impl Display> {
 
 #[derive(Error, Debug)]
 pub enum SpecError {
-    #[error("`branch`, `rev`, and `tag` are only valid when `git` is specified")]
+    #[error("`branch`, `rev`, and `tag` are only valid when `git` or `feedstock` is specified")]
     NotAGitSpec,
 
     #[error("only one of `branch`, `rev`, or `tag` can be specified")]
     MultipleGitRefs,
 
+    #[error(
+        "`depth`, `submodules`, and `lfs` are only valid when `git` or `feedstock` is specified"
+    )]
+    GitOptionsNotGit,
+
+    #[error("`depth` must be at least 1")]
+    InvalidDepth,
+
     #[error("one of `version`, `build`, `build-number`, `file-name`, `channel`, `subdir`, `md5`, `sha256`, `git`, `url`, or `path` must be specified")]
     MissingDetailedIdentifier,
 
-    #[error("only one of `url`, `path`, or `git` can be specified")]
+    #[error("only one of `url`, `path`, `git`, or `feedstock` can be specified")]
     MultipleIdentifiers,
 
+    #[error(
+        "`mirrors` and `blake2b` can only be used with a `url` that refers to a source archive"
+    )]
+    UrlOptionsNotSource,
+
+    #[error("invalid feedstock '{0}', expected the name of a package, or a GitHub repository like `conda-forge/<name>-feedstock`")]
+    InvalidFeedstock(String),
+
     #[error("{0} cannot be used with {1}")]
     InvalidCombination(Cow<'static, str>, Cow<'static, str>),
 
+    #[error("`ignore-run-exports` and `ignore-run-exports-from` can only be used with `git` or `path` source dependencies")]
+    IgnoreRunExportsNotSource,
+
     #[error(transparent)]
     NotABinary(NotBinary),
 }
@@ -163,17 +217,42 @@ pub enum NotBinary {
 }
 
 impl TomlSpec {
+    /// Returns the run exports that are ignored by this spec.
+    fn ignore_run_exports(&mut self) -> IgnoreRunExports {
+        IgnoreRunExports {
+            by_name: self.ignore_run_exports.take().unwrap_or_default(),
+            from_package: self.ignore_run_exports_from.take().unwrap_or_default(),
+        }
+    }
+
     fn validate_field_combinations(&self) -> Result<(), SpecError> {
-        if self.git.is_none() && (self.branch.is_some() || self.rev.is_some() || self.tag.is_some())
+        if self.git.is_none()
+            && self.feedstock.is_none()
+            && (self.branch.is_some() || self.rev.is_some() || self.tag.is_some())
         {
             return Err(SpecError::NotAGitSpec);
         }
 
-        let is_git = self.git.is_some();
+        if self.git.is_none()
+            && self.feedstock.is_none()
+            && (self.depth.is_some() || self.submodules.is_some() || self.lfs.is_some())
+        {
+            return Err(SpecError::GitOptionsNotGit);
+        }
+
+        if self.git.is_some() && self.feedstock.is_some() {
+            return Err(SpecError::MultipleIdentifiers);
+        }
+
+        let is_git = self.git.is_some() || self.feedstock.is_some();
         let is_path = self.path.is_some();
         let is_url = self.url.is_some();
 
-        let git_key = is_git.then_some("`git`");
+        let git_key = if self.feedstock.is_some() {
+            Some("`feedstock`")
+        } else {
+            is_git.then_some("`git`")
+        };
         let path_key = is_path.then_some("`path`");
         let url_key = is_url.then_some("`url`");
         let non_detailed_keys = [git_key, path_key, url_key]
@@ -242,20 +321,61 @@ impl TomlSpec {
             ));
         }
 
+        let uses_source_url_options = self.mirrors.is_some() || self.blake2b.is_some();
+        if uses_source_url_options && !is_url {
+            return Err(SpecError::UrlOptionsNotSource);
+        }
+
+        // A path to a binary archive is not a source dependency, its run
+        // exports are not computed by a build backend.
+        let ignores_run_exports =
+            self.ignore_run_exports.is_some() || self.ignore_run_exports_from.is_some();
+        let is_binary_path = self
+            .path
+            .as_deref()
+            .is_some_and(|path| PathSpec::new(path).is_binary());
+        if ignores_run_exports && (non_url_keys.is_empty() || is_binary_path) {
+            return Err(SpecError::IgnoreRunExportsNotSource);
+        }
+
+        Ok(())
+    }
+
+    /// Replaces a `feedstock` by the `git` repository it refers to, with the
+    /// `recipe` directory as the default subdirectory.
+    fn expand_feedstock(&mut self) -> Result<(), SpecError> {
+        if let Some(feedstock) = self.feedstock.take() {
+            self.git = Some(feedstock_url(&feedstock)?);
+            self.subdirectory
+                .get_or_insert_with(|| FEEDSTOCK_RECIPE_DIR.to_string());
+        }
         Ok(())
     }
 
     /// Convert the TOML representation into an actual [`PixiSpec`].
-    pub fn into_spec(self) -> Result<PixiSpec, SpecError> {
+    pub fn into_spec(mut self) -> Result<PixiSpec, SpecError> {
         self.validate_field_combinations()?;
+        self.expand_feedstock()?;
+        let ignore_run_exports = self.ignore_run_exports();
 
         let spec = match (self.url, self.path, self.git) {
-            (Some(url), None, None) => PixiSpec::Url(UrlSpec {
-                url,
-                md5: self.md5,
-                sha256: self.sha256,
+            (Some(url), None, None) => {
+                let url_spec = UrlSpec {
+                    url,
+                    md5: self.md5,
+                    sha256: self.sha256,
+                    blake2b: self.blake2b,
+                    mirrors: self.mirrors.unwrap_or_default(),
+                };
+                if url_spec.is_binary() && url_spec.has_source_only_options() {
+                    return Err(SpecError::UrlOptionsNotSource);
+                }
+                PixiSpec::Url(url_spec)
+            }
+            (None, Some(path), None) => PixiSpec::Path(PathSpec {
+                path: path.into(),
+                ignore_run_exports,
             }),
-            (None, Some(path), None) => PixiSpec::Path(PathSpec { path: path.into() }),
             (None, None, Some(git)) => {
                 let rev = match (self.branch, self.rev, self.tag) {
                     (Some(branch), None, None) => Some(Reference::Branch(branch)),
@@ -267,10 +387,20 @@ impl TomlSpec {
                     }
                 };
                 let subdirectory = self.subdirectory;
+                let depth = self
+                    .depth
+                    .map(|depth| NonZeroU32::new(depth).ok_or(SpecError::InvalidDepth))
+                    .transpose()?;
                 PixiSpec::Git(GitSpec {
                     git,
                     rev,
                     subdirectory,
+                    options: GitFetchOptions {
+                        depth,
+                        submodules: self.submodules,
+                        lfs: self.lfs.unwrap_or_default(),
+                    },
+                    ignore_run_exports,
                 })
             }
             (None, None, None) => {
@@ -304,15 +434,24 @@ impl TomlSpec {
     }
 
     /// Convert the TOML representation into an actual [`PixiSpec`].
-    pub fn into_binary_spec(self) -> Result<BinarySpec, SpecError> {
+    pub fn into_binary_spec(mut self) -> Result<BinarySpec, SpecError> {
         self.validate_field_combinations()?;
+        self.expand_feedstock()?;
+        if self.ignore_run_exports.is_some() || self.ignore_run_exports_from.is_some() {
+            return Err(SpecError::IgnoreRunExportsNotSource);
+        }
 
         let spec = match (self.url, self.path, self.git) {
             (Some(url), None, None) => {
+                if self.mirrors.is_some() || self.blake2b.is_some() {
+                    return Err(SpecError::UrlOptionsNotSource);
+                }
                 let url_spec = UrlSpec {
                     url,
                     md5: self.md5,
                     sha256: self.sha256,
+                    blake2b: None,
+                    mirrors: Vec::new(),
                 };
                 if let Either::Right(binary) = url_spec.into_source_or_binary() {
                     BinarySpec::Url(binary)
@@ -321,7 +460,7 @@ impl TomlSpec {
                 }
             }
             (None, Some(path), None) => {
-                let path_spec = PathSpec { path: path.into() };
+                let path_spec = PathSpec::new(path);
                 if let Either::Right(binary) = path_spec.into_source_or_binary() {
                     BinarySpec::Path(binary)
                 } else {
@@ -362,6 +501,33 @@ impl TomlSpec {
     }
 }
 
+/// The directory of a feedstock that contains the recipe.
+const FEEDSTOCK_RECIPE_DIR: &str = "recipe";
+
+/// Returns the git url of a feedstock on GitHub. A feedstock is either a
+/// repository like `conda-forge/mylib-feedstock`, or only the name of a
+/// package, which refers to its feedstock in the `conda-forge` organization.
+fn feedstock_url(feedstock: &str) -> Result<Url, SpecError> {
+    let invalid = || SpecError::InvalidFeedstock(feedstock.to_string());
+    let is_valid_segment = |segment: &str| {
+        !segment.is_empty()
+            && segment
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    };
+
+    let (owner, repository) = match feedstock.split_once('/') {
+        Some((owner, repository)) => (owner, Cow::Borrowed(repository)),
+        None if feedstock.ends_with("-feedstock") => ("conda-forge", Cow::Borrowed(feedstock)),
+        None => ("conda-forge", Cow::Owned(format!("{feedstock}-feedstock"))),
+    };
+    if !is_valid_segment(owner) || !is_valid_segment(&repository) {
+        return Err(invalid());
+    }
+
+    Url::parse(&format!("https://github.com/{owner}/{repository}")).map_err(|_| invalid())
+}
+
 struct TomlVersionSpecStr(VersionSpec);
 
 impl TomlVersionSpecStr {
@@ -395,14 +561,21 @@ impl<'de> toml_span::Deserialize<'de> for TomlSpec {
         let url = th
             .optional::<TomlFromStr<_>>("url")
             .map(TomlFromStr::into_inner);
+        let mirrors = th
+            .optional::<Vec<TomlFromStr<Url>>>("mirrors")
+            .map(|urls| urls.into_iter().map(TomlFromStr::into_inner).collect());
         let git = th
             .optional::<TomlFromStr<_>>("git")
             .map(TomlFromStr::into_inner);
         let path = th.optional("path");
+        let feedstock = th.optional("feedstock");
         let branch = th.optional("branch");
         let rev = th.optional("rev");
         let tag = th.optional("tag");
         let subdirectory = th.optional("subdirectory");
+        let depth = th.optional("depth");
+        let submodules = th.optional("submodules");
+        let lfs = th.optional("lfs");
         let build = th
             .optional::<TomlFromStr<_>>("build")
             .map(TomlFromStr::into_inner);
@@ -418,18 +591,32 @@ impl<'de> toml_span::Deserialize<'de> for TomlSpec {
         let sha256 = th
             .optional::<TomlDigest<rattler_digest::Sha256>>("sha256")
             .map(TomlDigest::into_inner);
+        let blake2b = th
+            .optional::<TomlDigest<rattler_digest::Blake2b256>>("blake2b")
+            .map(TomlDigest::into_inner);
+        let ignore_run_exports = th
+            .optional::<Vec<TomlFromStr<PackageName>>>("ignore-run-exports")
+            .map(|names| names.into_iter().map(TomlFromStr::into_inner).collect());
+        let ignore_run_exports_from = th
+            .optional::<Vec<TomlFromStr<PackageName>>>("ignore-run-exports-from")
+            .map(|names| names.into_iter().map(TomlFromStr::into_inner).collect());
 
         th.finalize(None)?;
 
         Ok(TomlSpec {
             version,
             url,
+            mirrors,
             git,
             path,
+            feedstock,
             branch,
             rev,
             tag,
             subdirectory,
+            depth,
+            submodules,
+            lfs,
             build,
             build_number,
             file_name,
@@ -437,6 +624,9 @@ impl<'de> toml_span::Deserialize<'de> for TomlSpec {
             subdir,
             md5,
             sha256,
+            blake2b,
+            ignore_run_exports,
+            ignore_run_exports_from,
         })
     }
 }
@@ -535,10 +725,13 @@ impl<'de> Deserialize<'de> for PathSpec {
         #[derive(Deserialize)]
         struct Raw {
             path: String,
+            #[serde(default, flatten)]
+            ignore_run_exports: IgnoreRunExports,
         }
 
         Raw::deserialize(deserializer).map(|raw| PathSpec {
             path: raw.path.into(),
+            ignore_run_exports: raw.ignore_run_exports,
         })
     }
 }
@@ -549,12 +742,15 @@ impl Serialize for PathSpec {
         S: Serializer,
     {
         #[derive(Serialize)]
-        struct Raw {
+        struct Raw<'a> {
             path: String,
+            #[serde(flatten)]
+            ignore_run_exports: &'a IgnoreRunExports,
         }
 
         Raw {
             path: self.path.to_string(),
+            ignore_run_exports: &self.ignore_run_exports,
         }
         .serialize(serializer)
     }
@@ -585,6 +781,8 @@ mod test {
             json!({ "url": "https://conda.anaconda.org/conda-forge/linux-64/21cmfast-3.3.1-py38h0db86a8_1.conda", "sha256": "315f5bdb76d078c43b8ac0064e4a0164612b1fce77c869345bfc94c75894edd3" }),
             json!({ "git": "https://github.com/conda-forge/21cmfast-feedstock" }),
             json!({ "git": "https://github.com/conda-forge/21cmfast-feedstock", "branch": "main" }),
+            json!({ "path": "foobar", "ignore-run-exports": ["libzlib"] }),
+            json!({ "git": "https://github.com/conda-forge/21cmfast-feedstock", "ignore-run-exports-from": ["zlib"] }),
             // Errors:
             json!({ "ver": "1.2.3" }),
             json!({ "path": "foobar", "version": "1.2.3" }),
@@ -593,6 +791,8 @@ mod test {
             json!({ "path": "foobar", "sha256": "315f5bdb76d078c43b8ac0064e4a0164612b1fce77c869345bfc94c75894edd3" }),
             json!({ "git": "https://github.com/conda-forge/21cmfast-feedstock", "branch": "main", "tag": "v1" }),
             json!({ "git": "https://github.com/conda-forge/21cmfast-feedstock", "sha256": "315f5bdb76d078c43b8ac0064e4a0164612b1fce77c869345bfc94c75894edd3" }),
+            json!({ "version": "1.2.3", "ignore-run-exports": ["libzlib"] }),
+            json!({ "path": "foobar-1.0-h123_0.conda", "ignore-run-exports": ["libzlib"] }),
             json! { "/path/style"},
             json! { "./path/style"},
             json! { "\\path\\style"},
@@ -629,4 +829,123 @@ mod test {
 
         insta::assert_yaml_snapshot!(snapshot);
     }
+
+    #[test]
+    fn test_url_mirrors() {
+        let spec: PixiSpec = serde_json::from_value(json!({
+            "url": "https://example.edu/sources/mylib-1.0.tar.gz",
+            "mirrors": ["https://mirror.example.com/mylib-1.0.tar.gz"],
+            "md5": "dc9eb5b2ffa32d2dc8d3e5b1b8de4cde",
+            "sha256": "315f5bdb76d078c43b8ac0064e4a0164612b1fce77c869345bfc94c75894edd3",
+            "blake2b": "1c6ae6d5b5d6a5e16f4fd7bc1a4d3c8d9eaf9bb8fa53fb4c8a6c9db44b1ec6fd",
+        }))
+        .unwrap();
+        let PixiSpec::Url(url_spec) = spec.clone() else {
+            panic!("expected a url spec");
+        };
+        assert!(url_spec.md5.is_some());
+        assert!(url_spec.sha256.is_some());
+        assert!(url_spec.blake2b.is_some());
+
+        let Either::Left(source) = url_spec.into_source_or_binary() else {
+            panic!("expected a source archive");
+        };
+        assert_eq!(
+            source.urls().map(Url::as_str).collect::<Vec<_>>(),
+            vec![
+                "https://example.edu/sources/mylib-1.0.tar.gz",
+                "https://mirror.example.com/mylib-1.0.tar.gz"
+            ]
+        );
+
+        // The spec survives a round trip.
+        let value = serde_json::to_value(&spec).unwrap();
+        assert_eq!(serde_json::from_value::<PixiSpec>(value).unwrap(), spec);
+
+        // Mirrors can't be passed to the solver, so they're not allowed for
+        // conda packages.
+        for invalid in [
+            json!({ "url": "https://example.com/mylib-1.0-h123_0.conda", "mirrors": ["https://mirror.example.com/mylib-1.0-h123_0.conda"] }),
+            json!({ "path": "mylib", "blake2b": "1c6ae6d5b5d6a5e16f4fd7bc1a4d3c8d9eaf9bb8fa53fb4c8a6c9db44b1ec6fd" }),
+            json!({ "git": "https://github.com/example/mylib", "mirrors": [] }),
+        ] {
+            assert!(serde_json::from_value::<PixiSpec>(invalid).is_err());
+        }
+    }
+
+    #[test]
+    fn test_feedstock() {
+        let spec: PixiSpec = serde_json::from_value(
+            json!({ "feedstock": "conda-forge/mylib-feedstock", "branch": "main" }),
+        )
+        .unwrap();
+        assert_eq!(
+            spec,
+            PixiSpec::Git(GitSpec {
+                git: Url::parse("https://github.com/conda-forge/mylib-feedstock").unwrap(),
+                rev: Some(Reference::Branch("main".to_string())),
+                subdirectory: Some("recipe".to_string()),
+                options: GitFetchOptions::default(),
+                ignore_run_exports: IgnoreRunExports::default(),
+            })
+        );
+
+        // Only the name of the package refers to the conda-forge feedstock, the
+        // subdirectory can be overridden.
+        let spec: PixiSpec =
+            serde_json::from_value(json!({ "feedstock": "mylib", "subdirectory": "." })).unwrap();
+        let PixiSpec::Git(git) = spec else {
+            panic!("expected a git spec");
+        };
+        assert_eq!(
+            git.git.as_str(),
+            "https://github.com/conda-forge/mylib-feedstock"
+        );
+        assert_eq!(git.subdirectory.as_deref(), Some("."));
+
+        for invalid in [
+            json!({ "feedstock": "conda-forge/" }),
+            json!({ "feedstock": "conda forge/mylib" }),
+            json!({ "feedstock": "mylib", "git": "https://github.com/conda-forge/mylib-feedstock" }),
+            json!({ "feedstock": "mylib", "version": "1.2.3" }),
+        ] {
+            assert!(serde_json::from_value::<PixiSpec>(invalid).is_err());
+        }
+
+        let binary: Result<BinarySpec, _> = TomlSpec::deserialize(json!({ "feedstock": "mylib" }))
+            .unwrap()
+            .into_binary_spec();
+        assert!(matches!(binary, Err(SpecError::NotABinary(NotBinary::Git))));
+    }
+
+    #[test]
+    fn test_git_options() {
+        let json = json!({
+            "git": "https://github.com/conda-forge/mylib-feedstock",
+            "depth": 1,
+            "submodules": false,
+            "lfs": true
+        });
+        let spec: PixiSpec = serde_json::from_value(json.clone()).unwrap();
+        let PixiSpec::Git(git) = &spec else {
+            panic!("expected a git spec");
+        };
+        assert_eq!(
+            git.options,
+            GitFetchOptions {
+                depth: NonZeroU32::new(1),
+                submodules: Some(false),
+                lfs: true,
+            }
+        );
+        assert_eq!(serde_json::to_value(&spec).unwrap(), json);
+
+        for invalid in [
+            json!({ "version": "1.2.3", "depth": 1 }),
+            json!({ "path": "foobar", "lfs": true }),
+            json!({ "git": "https://github.com/conda-forge/mylib-feedstock", "depth": 0 }),
+        ] {
+            assert!(serde_json::from_value::<PixiSpec>(invalid).is_err());
+        }
+    }
 }
@@ -1,7 +1,7 @@
 use crate::BinarySpec;
 use itertools::Either;
 use rattler_conda_types::{package::ArchiveIdentifier, NamelessMatchSpec};
-use rattler_digest::{Md5Hash, Sha256Hash};
+use rattler_digest::{Blake2b256Hash, Md5Hash, Sha256Hash};
 use serde_with::serde_as;
 use url::Url;
 
@@ -22,6 +22,18 @@ pub struct UrlSpec {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde_as(as = "Option<rattler_digest::serde::SerializableHash::<rattler_digest::Sha256>>")]
     pub sha256: Option<Sha256Hash>,
+
+    /// The blake2b-256 hash of the package
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[serde_as(
+        as = "Option<rattler_digest::serde::SerializableHash::<rattler_digest::Blake2b256>>"
+    )]
+    pub blake2b: Option<Blake2b256Hash>,
+
+    /// Alternative URLs of the same archive, tried in order when the archive
+    /// can't be downloaded from `url`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mirrors: Vec<Url>,
 }
 
 impl UrlSpec {
@@ -41,6 +53,12 @@ impl UrlSpec {
         }
     }
 
+    /// Returns true if the spec uses options that are only supported for
+    /// source archives.
+    pub fn has_source_only_options(&self) -> bool {
+        self.blake2b.is_some() || !self.mirrors.is_empty()
+    }
+
     /// Converts this instance into a [`UrlSourceSpec`] if the URL points to a
     /// source package. Otherwise, returns this instance unmodified.
     #[allow(clippy::result_large_err)]
@@ -52,6 +70,8 @@ impl UrlSpec {
                 url: self.url,
                 md5: self.md5,
                 sha256: self.sha256,
+                blake2b: self.blake2b,
+                mirrors: self.mirrors,
             })
         }
     }
@@ -70,6 +90,8 @@ impl UrlSpec {
                 url: self.url,
                 md5: self.md5,
                 sha256: self.sha256,
+                blake2b: self.blake2b,
+                mirrors: self.mirrors,
             })
         }
     }
@@ -91,6 +113,21 @@ pub struct UrlSourceSpec {
 
     /// The sha256 hash of the archive
     pub sha256: Option<Sha256Hash>,
+
+    /// The blake2b-256 hash of the archive
+    pub blake2b: Option<Blake2b256Hash>,
+
+    /// Alternative URLs of the same archive, tried in order when the archive
+    /// can't be downloaded from `url`.
+    pub mirrors: Vec<Url>,
+}
+
+impl UrlSourceSpec {
+    /// Returns the URLs to download the archive from, in the order they should
+    /// be tried.
+    pub fn urls(&self) -> impl Iterator<Item = &Url> + '_ {
+        std::iter::once(&self.url).chain(self.mirrors.iter())
+    }
 }
 
 impl From<UrlSourceSpec> for UrlSpec {
@@ -99,6 +136,8 @@ impl From<UrlSourceSpec> for UrlSpec {
             url: value.url,
             md5: value.md5,
             sha256: value.sha256,
+            blake2b: value.blake2b,
+            mirrors: value.mirrors,
         }
     }
 }
@@ -122,6 +161,8 @@ impl From<UrlBinarySpec> for UrlSpec {
             url: value.url,
             md5: value.md5,
             sha256: value.sha256,
+            blake2b: None,
+            mirrors: Vec::new(),
         }
     }
 }
@@ -0,0 +1,281 @@
+//! Limits the bandwidth of the downloads of the HTTP client.
+
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{ready, Context, Poll},
+    time::{Duration, Instant},
+};
+
+use bytes::Bytes;
+use http::Extensions;
+use http_body::{Frame, SizeHint};
+use pixi_config::{Bandwidth, NetworkConfig};
+use reqwest::{Body, Request, Response, ResponseBuilderExt};
+use reqwest_middleware::{Middleware, Next};
+use tokio::time::Sleep;
+use url::Url;
+
+/// Hands out the time at which a number of bytes may be received, so all the
+/// downloads that share the limiter together stay below its bandwidth.
+#[derive(Debug)]
+struct RateLimiter {
+    bytes_per_second: f64,
+    next_free: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    fn new(bandwidth: Bandwidth) -> Self {
+        Self {
+            bytes_per_second: bandwidth.bytes_per_second() as f64,
+            next_free: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Reserves the given number of bytes and returns how long the caller has
+    /// to wait before it may pass them on.
+    fn reserve(&self, bytes: usize) -> Duration {
+        let now = Instant::now();
+        let mut next_free = self.next_free.lock().expect("the lock is never poisoned");
+        let start = (*next_free).max(now);
+        *next_free = start + Duration::from_secs_f64(bytes as f64 / self.bytes_per_second);
+        start.saturating_duration_since(now)
+    }
+}
+
+/// A middleware that throttles the bodies of the responses to the configured
+/// maximum bandwidth, globally and per channel.
+///
+/// The limit of a channel applies to the requests of which the url starts
+/// with the url of the channel, on top of the global limit.
+#[derive(Debug, Default)]
+pub struct BandwidthMiddleware {
+    global: Option<Arc<RateLimiter>>,
+    per_channel: Vec<(String, Arc<RateLimiter>)>,
+}
+
+impl BandwidthMiddleware {
+    pub fn from_config(config: &NetworkConfig) -> Self {
+        Self {
+            global: config
+                .default
+                .max_bandwidth
+                .map(|bandwidth| Arc::new(RateLimiter::new(bandwidth))),
+            per_channel: config
+                .per_channel
+                .iter()
+                .filter_map(|(url, config)| {
+                    let bandwidth = config.max_bandwidth?;
+                    Some((
+                        url.as_str().trim_end_matches('/').to_string(),
+                        Arc::new(RateLimiter::new(bandwidth)),
+                    ))
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns true if no bandwidth is limited.
+    pub fn is_empty(&self) -> bool {
+        self.global.is_none() && self.per_channel.is_empty()
+    }
+
+    /// Returns the limiters that apply to a request to the given url.
+    fn limiters(&self, url: &Url) -> Vec<Arc<RateLimiter>> {
+        let url = url.as_str();
+        self.global
+            .iter()
+            .chain(
+                self.per_channel
+                    .iter()
+                    .filter(|(channel, _)| {
+                        url.strip_prefix(channel.as_str())
+                            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+                    })
+                    .map(|(_, limiter)| limiter),
+            )
+            .cloned()
+            .collect()
+    }
+}
+
+#[async_trait::async_trait]
+impl Middleware for BandwidthMiddleware {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+        let limiters = self.limiters(req.url());
+        let response = next.run(req, extensions).await?;
+        if limiters.is_empty() {
+            return Ok(response);
+        }
+        Ok(throttle(response, limiters))
+    }
+}
+
+/// Wraps the body of the response in a body that waits for the limiters
+/// before passing on every chunk.
+fn throttle(response: Response, limiters: Vec<Arc<RateLimiter>>) -> Response {
+    let url = response.url().clone();
+    let (parts, body) = http::Response::<Body>::from(response).into_parts();
+    let body = ThrottledBody {
+        inner: body,
+        limiters,
+        delayed: None,
+    };
+
+    let mut throttled = http::Response::builder()
+        .status(parts.status)
+        .version(parts.version)
+        .url(url)
+        .body(Body::wrap(body))
+        .expect("the parts of the response are valid");
+    *throttled.headers_mut() = parts.headers;
+    throttled.extensions_mut().extend(parts.extensions);
+    Response::from(throttled)
+}
+
+/// A body that holds back its frames until the limiters allow them.
+struct ThrottledBody {
+    inner: Body,
+    limiters: Vec<Arc<RateLimiter>>,
+    /// The frame that is held back, and the timer that releases it.
+    delayed: Option<(Frame<Bytes>, Pin<Box<Sleep>>)>,
+}
+
+impl http_body::Body for ThrottledBody {
+    type Data = Bytes;
+    type Error = reqwest::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        if let Some((_, sleep)) = &mut self.delayed {
+            ready!(sleep.as_mut().poll(cx));
+            let (frame, _) = self.delayed.take().expect("checked above");
+            return Poll::Ready(Some(Ok(frame)));
+        }
+
+        let frame = match ready!(Pin::new(&mut self.inner).poll_frame(cx)) {
+            Some(Ok(frame)) => frame,
+            other => return Poll::Ready(other),
+        };
+        let bytes = frame.data_ref().map_or(0, Bytes::len);
+        let delay = self
+            .limiters
+            .iter()
+            .map(|limiter| limiter.reserve(bytes))
+            .max()
+            .unwrap_or_default();
+        if delay.is_zero() {
+            return Poll::Ready(Some(Ok(frame)));
+        }
+
+        let mut sleep = Box::pin(tokio::time::sleep(delay));
+        if sleep.as_mut().poll(cx).is_ready() {
+            return Poll::Ready(Some(Ok(frame)));
+        }
+        self.delayed = Some((frame, sleep));
+        Poll::Pending
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.delayed.is_none() && self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        // Keep the length of the response, it is used to report the progress
+        // of the downloads.
+        let mut hint = self.inner.size_hint();
+        if let Some(bytes) = self
+            .delayed
+            .as_ref()
+            .and_then(|(frame, _)| frame.data_ref())
+        {
+            let bytes = bytes.len() as u64;
+            hint.set_lower(hint.lower() + bytes);
+            if let Some(upper) = hint.upper() {
+                hint.set_upper(upper + bytes);
+            }
+        }
+        hint
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use pixi_config::NetworkChannelConfig;
+
+    use super::*;
+
+    #[test]
+    fn test_reserve() {
+        let limiter = RateLimiter::new(Bandwidth::from_bytes_per_second(1000));
+        assert_eq!(limiter.reserve(1000), Duration::ZERO);
+        let delay = limiter.reserve(1000);
+        assert!(delay > Duration::from_millis(900) && delay <= Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_limiters_per_channel() {
+        let middleware = BandwidthMiddleware::from_config(&NetworkConfig {
+            default: NetworkChannelConfig::default(),
+            per_channel: HashMap::from([(
+                Url::parse("https://conda.anaconda.org/conda-forge/").unwrap(),
+                NetworkChannelConfig {
+                    max_bandwidth: Some(Bandwidth::from_bytes_per_second(1000)),
+                },
+            )]),
+        });
+        assert_eq!(
+            middleware
+                .limiters(
+                    &Url::parse("https://conda.anaconda.org/conda-forge/noarch/repodata.json")
+                        .unwrap()
+                )
+                .len(),
+            1
+        );
+        assert!(middleware
+            .limiters(&Url::parse("https://conda.anaconda.org/conda-forge-mirror/").unwrap())
+            .is_empty());
+        assert!(middleware
+            .limiters(&Url::parse("https://prefix.dev/conda-forge/").unwrap())
+            .is_empty());
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Marker;
+
+    #[tokio::test]
+    async fn test_throttle() {
+        let url =
+            Url::parse("https://conda.anaconda.org/conda-forge/noarch/repodata.json").unwrap();
+        let mut response = http::Response::builder()
+            .status(200)
+            .header("content-length", "1000")
+            .url(url.clone())
+            .body(vec![b'a'; 1000])
+            .unwrap();
+        response.extensions_mut().insert(Marker);
+
+        // Use up the first 100ms of the bandwidth, so the body has to wait
+        let limiter = Arc::new(RateLimiter::new(Bandwidth::from_bytes_per_second(10_000)));
+        limiter.reserve(1000);
+
+        let start = Instant::now();
+        let throttled = throttle(Response::from(response), vec![limiter]);
+        assert_eq!(throttled.url(), &url);
+        assert_eq!(throttled.content_length(), Some(1000));
+        assert_eq!(throttled.extensions().get::<Marker>(), Some(&Marker));
+        assert_eq!(throttled.bytes().await.unwrap().len(), 1000);
+        assert!(start.elapsed() >= Duration::from_millis(90));
+    }
+}
@@ -0,0 +1,650 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    path::Path,
+    str::FromStr,
+};
+
+use itertools::Itertools;
+use miette::{Context, IntoDiagnostic};
+use pixi_config::PinningStrategy;
+use rattler_conda_types::{
+    package::ArchiveIdentifier, MatchSpec, NamedChannelOrUrl, PackageName, PackageRecord,
+    ParseStrictness::Lenient, Platform, RepoDataRecord, Version,
+};
+use rattler_digest::{parse_digest_from_hex, Md5, Sha256};
+use rattler_lock::{PackageHashes, PypiPackageData, UrlOrPath};
+use serde::Deserialize;
+use url::Url;
+
+/// The category of the packages that are always installed by conda-lock.
+pub const MAIN_CATEGORY: &str = "main";
+
+/// A lock file created by [conda-lock](https://github.com/conda/conda-lock),
+/// usually called `conda-lock.yml`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct CondaLockFile {
+    version: u64,
+    metadata: CondaLockMetadata,
+    #[serde(default)]
+    package: Vec<CondaLockPackage>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct CondaLockMetadata {
+    #[serde(default)]
+    channels: Vec<CondaLockChannel>,
+    platforms: Vec<Platform>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct CondaLockChannel {
+    url: String,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+enum CondaLockManager {
+    Conda,
+    Pip,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct CondaLockPackage {
+    name: String,
+    version: String,
+    manager: CondaLockManager,
+    platform: Platform,
+    #[serde(default)]
+    dependencies: BTreeMap<String, String>,
+    url: Url,
+    #[serde(default)]
+    hash: CondaLockHash,
+    #[serde(default)]
+    category: Option<String>,
+    #[serde(default)]
+    categories: Vec<String>,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+struct CondaLockHash {
+    md5: Option<String>,
+    sha256: Option<String>,
+}
+
+/// A requirement of a manifest imported from a conda-lock file.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum CondaLockSpec {
+    Conda(MatchSpec),
+    Pypi(pep508_rs::Requirement),
+}
+
+/// A dependency of a manifest imported from a conda-lock file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CondaLockDependency {
+    pub spec: CondaLockSpec,
+    /// The platforms the dependency is limited to, empty if it is locked for
+    /// all platforms of the file.
+    pub platforms: Vec<Platform>,
+    /// The conda-lock category of the dependency, [`MAIN_CATEGORY`] for the
+    /// dependencies that are always installed.
+    pub category: String,
+}
+
+impl CondaLockPackage {
+    fn categories(&self) -> Vec<&str> {
+        if !self.categories.is_empty() {
+            self.categories.iter().map(String::as_str).collect()
+        } else {
+            vec![self.category.as_deref().unwrap_or(MAIN_CATEGORY)]
+        }
+    }
+
+    /// The category that determines in which feature the package is requested.
+    fn primary_category(&self) -> &str {
+        self.categories()[0]
+    }
+
+    /// Identifies the package across platforms.
+    fn key(&self) -> (&str, CondaLockManager, String) {
+        (
+            self.primary_category(),
+            self.manager,
+            self.normalized_name(),
+        )
+    }
+
+    /// The name the package is referred to by the dependencies of packages of
+    /// the same manager.
+    fn normalized_name(&self) -> String {
+        normalize_name(self.manager, &self.name)
+    }
+
+    fn hashes(
+        &self,
+    ) -> miette::Result<(
+        Option<rattler_digest::Md5Hash>,
+        Option<rattler_digest::Sha256Hash>,
+    )> {
+        let md5 = self
+            .hash
+            .md5
+            .as_deref()
+            .map(|hash| {
+                parse_digest_from_hex::<Md5>(hash)
+                    .ok_or_else(|| miette::miette!("invalid md5 hash '{hash}' of '{}'", self.name))
+            })
+            .transpose()?;
+        let sha256 = self
+            .hash
+            .sha256
+            .as_deref()
+            .map(|hash| {
+                parse_digest_from_hex::<Sha256>(hash).ok_or_else(|| {
+                    miette::miette!("invalid sha256 hash '{hash}' of '{}'", self.name)
+                })
+            })
+            .transpose()?;
+        Ok((md5, sha256))
+    }
+
+    fn to_repodata_record(&self) -> miette::Result<RepoDataRecord> {
+        let file_name = self
+            .url
+            .path_segments()
+            .and_then(|mut segments| segments.next_back())
+            .unwrap_or_default()
+            .to_string();
+        let identifier = ArchiveIdentifier::try_from_filename(&file_name)
+            .ok_or_else(|| miette::miette!("'{}' is not the url of a conda package", self.url))?;
+        let subdir = self
+            .url
+            .path_segments()
+            .and_then(|segments| segments.rev().nth(1))
+            .unwrap_or(self.platform.as_str())
+            .to_string();
+
+        let name = PackageName::from_str(&self.name).into_diagnostic()?;
+        let version = Version::from_str(&self.version)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("invalid version of '{}'", self.name))?;
+        let mut package_record = PackageRecord::new(name, version, identifier.build_string);
+        // conda-lock doesn't store the build number, by convention it is the last
+        // numeric part of the build string.
+        package_record.build_number = package_record
+            .build
+            .rsplit('_')
+            .find_map(|part| part.parse().ok())
+            .unwrap_or_default();
+        package_record.subdir = subdir;
+        package_record.depends = self
+            .dependencies
+            .iter()
+            .map(|(name, spec)| match spec.trim() {
+                "" | "*" => name.clone(),
+                spec => format!("{name} {spec}"),
+            })
+            .collect();
+        (package_record.md5, package_record.sha256) = self.hashes()?;
+
+        Ok(RepoDataRecord {
+            package_record,
+            file_name,
+            channel: self.url.join("..").ok().map(|url| url.to_string()),
+            url: self.url.clone(),
+        })
+    }
+
+    fn to_pypi_package_data(&self) -> miette::Result<PypiPackageData> {
+        let requires_dist = self
+            .dependencies
+            .iter()
+            .map(|(name, spec)| {
+                let requirement = match spec.trim() {
+                    "" | "*" => name.clone(),
+                    spec => format!("{name}{spec}"),
+                };
+                pep508_rs::Requirement::from_str(&requirement)
+                    .into_diagnostic()
+                    .wrap_err_with(|| {
+                        format!(
+                            "Can't parse '{requirement}' as dependency of '{}'",
+                            self.name
+                        )
+                    })
+            })
+            .collect::<miette::Result<_>>()?;
+        let hash = match self.hashes()? {
+            (Some(md5), Some(sha256)) => Some(PackageHashes::Md5Sha256(md5, sha256)),
+            (Some(md5), None) => Some(PackageHashes::Md5(md5)),
+            (None, Some(sha256)) => Some(PackageHashes::Sha256(sha256)),
+            (None, None) => None,
+        };
+
+        Ok(PypiPackageData {
+            name: pep508_rs::PackageName::from_str(&self.name).into_diagnostic()?,
+            version: pep440_rs::Version::from_str(&self.version)
+                .into_diagnostic()
+                .wrap_err_with(|| format!("invalid version of '{}'", self.name))?,
+            location: UrlOrPath::Url(self.url.clone()),
+            hash,
+            requires_dist,
+            requires_python: None,
+            editable: false,
+        })
+    }
+}
+
+fn normalize_name(manager: CondaLockManager, name: &str) -> String {
+    match manager {
+        CondaLockManager::Conda => name.to_lowercase(),
+        CondaLockManager::Pip => pep508_rs::PackageName::from_str(name)
+            .map(|name| name.to_string())
+            .unwrap_or_else(|_| name.to_lowercase()),
+    }
+}
+
+impl CondaLockFile {
+    /// Returns true if the path looks like a conda-lock file, e.g.
+    /// `conda-lock.yml` or `linux.conda-lock.yaml`.
+    pub fn is_conda_lock_file(path: &Path) -> bool {
+        path.file_name()
+            .map(|name| name.to_string_lossy())
+            .is_some_and(|name| {
+                name.ends_with("conda-lock.yml") || name.ends_with("conda-lock.yaml")
+            })
+    }
+
+    pub fn from_path(path: &Path) -> miette::Result<Self> {
+        let contents = fs_err::read_to_string(path).into_diagnostic()?;
+        let lock_file: CondaLockFile = serde_yaml::from_str(&contents)
+            .into_diagnostic()
+            .wrap_err_with(|| {
+                format!("Failed to parse '{}' as a conda-lock file", path.display())
+            })?;
+        if lock_file.version != 1 {
+            miette::bail!(
+                "version {} of the conda-lock file format is not supported",
+                lock_file.version
+            );
+        }
+        Ok(lock_file)
+    }
+
+    /// The channels the packages were locked from.
+    pub fn channels(&self) -> miette::Result<Vec<NamedChannelOrUrl>> {
+        self.metadata
+            .channels
+            .iter()
+            .map(|channel| {
+                NamedChannelOrUrl::from_str(&channel.url)
+                    .into_diagnostic()
+                    .wrap_err(format!("Can't parse '{}' as channel", channel.url))
+            })
+            .collect()
+    }
+
+    /// The platforms the packages are locked for.
+    pub fn platforms(&self) -> &[Platform] {
+        &self.metadata.platforms
+    }
+
+    /// The categories of the packages, the main category is always first.
+    pub fn categories(&self) -> Vec<&str> {
+        std::iter::once(MAIN_CATEGORY)
+            .chain(
+                self.package
+                    .iter()
+                    .flat_map(CondaLockPackage::categories)
+                    .filter(|category| *category != MAIN_CATEGORY)
+                    .unique()
+                    .sorted(),
+            )
+            .collect()
+    }
+
+    /// Returns the dependencies of a manifest that reproduces the locked
+    /// packages. conda-lock doesn't store which packages were requested, so
+    /// these are the packages that no other package of the same platform
+    /// depends on. Their versions are constrained with the pinning strategy.
+    pub fn to_manifest(
+        &self,
+        pinning_strategy: PinningStrategy,
+    ) -> miette::Result<Vec<CondaLockDependency>> {
+        // The packages that other packages depend on, per platform and category of
+        // the dependent package.
+        let dependents: HashSet<(Platform, CondaLockManager, String, &str)> = self
+            .package
+            .iter()
+            .flat_map(|package| {
+                package.dependencies.keys().map(|name| {
+                    (
+                        package.platform,
+                        package.manager,
+                        normalize_name(package.manager, name),
+                        package.primary_category(),
+                    )
+                })
+            })
+            .collect();
+
+        // A package is requested if nothing of its own category or the main
+        // category depends on it on some platform. It is then requested for all
+        // the platforms that it is locked for.
+        let requested: HashSet<(&str, CondaLockManager, String)> = self
+            .package
+            .iter()
+            .filter(|package| {
+                [package.primary_category(), MAIN_CATEGORY]
+                    .into_iter()
+                    .all(|category| {
+                        !dependents.contains(&(
+                            package.platform,
+                            package.manager,
+                            package.normalized_name(),
+                            category,
+                        ))
+                    })
+            })
+            .map(CondaLockPackage::key)
+            .collect();
+        let roots = self
+            .package
+            .iter()
+            .filter(|package| requested.contains(&package.key()))
+            .into_group_map_by(|package| package.key());
+
+        let mut dependencies = Vec::new();
+        for ((category, manager, _), packages) in roots.into_iter().sorted_by(|a, b| a.0.cmp(&b.0))
+        {
+            let name = &packages[0].name;
+            let versions: Vec<Version> = packages
+                .iter()
+                .map(|package| {
+                    Version::from_str(&package.version)
+                        .into_diagnostic()
+                        .wrap_err_with(|| format!("invalid version of '{}'", package.name))
+                })
+                .collect::<miette::Result<_>>()?;
+            let version_spec = pinning_strategy.determine_version_constraint(versions.iter());
+
+            let spec = match manager {
+                CondaLockManager::Conda => {
+                    let requirement = match version_spec {
+                        Some(version_spec) => format!("{name} {version_spec}"),
+                        None => name.clone(),
+                    };
+                    CondaLockSpec::Conda(
+                        MatchSpec::from_str(&requirement, Lenient)
+                            .into_diagnostic()
+                            .wrap_err(format!(
+                                "Can't parse '{}' as conda dependency",
+                                requirement
+                            ))?,
+                    )
+                }
+                CondaLockManager::Pip => {
+                    let requirement = match version_spec {
+                        Some(version_spec) => format!("{name}{version_spec}"),
+                        None => name.clone(),
+                    };
+                    CondaLockSpec::Pypi(
+                        pep508_rs::Requirement::from_str(&requirement)
+                            .into_diagnostic()
+                            .wrap_err(format!(
+                                "Can't parse '{}' as pypi dependency",
+                                requirement
+                            ))?,
+                    )
+                }
+            };
+
+            let platforms: Vec<Platform> = packages
+                .iter()
+                .map(|package| package.platform)
+                .unique()
+                .sorted()
+                .collect();
+            let platforms = if self
+                .platforms()
+                .iter()
+                .all(|platform| platforms.contains(platform))
+            {
+                Vec::new()
+            } else {
+                platforms
+            };
+
+            dependencies.push(CondaLockDependency {
+                spec,
+                platforms,
+                category: category.to_string(),
+            });
+        }
+
+        Ok(dependencies)
+    }
+
+    /// Returns the locked conda and PyPI packages of a platform that belong to
+    /// any of the categories.
+    pub fn locked_packages(
+        &self,
+        platform: Platform,
+        categories: &[&str],
+    ) -> miette::Result<(Vec<RepoDataRecord>, Vec<PypiPackageData>)> {
+        let mut conda_packages = Vec::new();
+        let mut pypi_packages = Vec::new();
+        for package in self.package.iter().filter(|package| {
+            package.platform == platform
+                && package
+                    .categories()
+                    .iter()
+                    .any(|category| categories.contains(category))
+        }) {
+            match package.manager {
+                CondaLockManager::Conda => conda_packages.push(package.to_repodata_record()?),
+                CondaLockManager::Pip => pypi_packages.push(package.to_pypi_package_data()?),
+            }
+        }
+        Ok((conda_packages, pypi_packages))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use rattler_conda_types::ParseStrictness::Strict;
+
+    use super::*;
+
+    const CONDA_LOCK_FILE: &str = r#"
+version: 1
+metadata:
+  content_hash:
+    linux-64: 0f1e2d3c
+    osx-arm64: 4b5a6978
+  channels:
+  - url: conda-forge
+    used_env_vars: []
+  platforms:
+  - linux-64
+  - osx-arm64
+  sources:
+  - environment.yml
+package:
+- name: python
+  version: 3.12.3
+  manager: conda
+  platform: linux-64
+  dependencies:
+    __glibc: '>=2.17,<3.0.a0'
+    libzlib: '>=1.2.13,<2.0.0a0'
+  url: https://conda.anaconda.org/conda-forge/linux-64/python-3.12.3-hab00c5b_0_cpython.conda
+  hash:
+    md5: 2540b74d304f71d3e89c81209db4db84
+    sha256: f9865bcbff69f15fd89a33a2da12ad616e98d65ce7c83c644b92e66e5016b227
+  category: main
+  optional: false
+- name: libzlib
+  version: 1.2.13
+  manager: conda
+  platform: linux-64
+  dependencies: {}
+  url: https://conda.anaconda.org/conda-forge/linux-64/libzlib-1.2.13-h4ab18f5_6.conda
+  hash:
+    md5: 27329162c0dc732bcf67a4e0cd488125
+    sha256: 8ced4afed6322172182af503f21725d072a589a6eb918f8a58135c1e00d35980
+  category: main
+  optional: false
+- name: python
+  version: 3.12.3
+  manager: conda
+  platform: osx-arm64
+  dependencies: {}
+  url: https://conda.anaconda.org/conda-forge/osx-arm64/python-3.12.3-h4a7b5fc_0_cpython.conda
+  hash:
+    md5: 8643ab37bece6ae8f112464068d9df9c
+    sha256: 3761481a9d5ed9f5f1ba6436e4440d1b80c2d8d0c08e9d1a7e9e4f2b1e7fc3d6
+  category: main
+  optional: false
+- name: pytest
+  version: 8.2.0
+  manager: conda
+  platform: linux-64
+  dependencies:
+    python: '>=3.8'
+  url: https://conda.anaconda.org/conda-forge/noarch/pytest-8.2.0-pyhd8ed1ab_0.conda
+  hash:
+    md5: 208e2fd4bce6986ab0af1c6223c4d1b7
+    sha256: 02227fea7b50132a75fb223c2d796306ffebd4dc6324897455f17cb54d16683d
+  category: dev
+  optional: true
+- name: Requests
+  version: 2.31.0
+  manager: pip
+  platform: linux-64
+  dependencies:
+    certifi: '>=2017.4.17'
+  url: https://files.pythonhosted.org/packages/70/8e/0e2d847013cb52cd35b38c009bb167a1a26b2ce6cd6965bf26b47bc0bf44/requests-2.31.0-py3-none-any.whl
+  hash:
+    sha256: 58cd2187c01e70e6e26505bca751777aa9f2ee0b7f4300988b709f44e013003f
+  category: main
+  optional: false
+- name: certifi
+  version: 2024.2.2
+  manager: pip
+  platform: linux-64
+  dependencies: {}
+  url: https://files.pythonhosted.org/packages/ba/06/a07f096c664aeb9f01624f858c3add0a4e913d6c96257acb4fce61e7de14/certifi-2024.2.2-py3-none-any.whl
+  hash:
+    sha256: dc383c07b76109f368f6106eee2b593b04a011ea4d55f652c6ca24a754d1cdd1
+  category: main
+  optional: false
+"#;
+
+    fn conda_lock_file() -> CondaLockFile {
+        let mut f = tempfile::NamedTempFile::new().unwrap();
+        f.write_all(CONDA_LOCK_FILE.as_bytes()).unwrap();
+        CondaLockFile::from_path(f.path()).unwrap()
+    }
+
+    #[test]
+    fn test_is_conda_lock_file() {
+        assert!(CondaLockFile::is_conda_lock_file(Path::new(
+            "conda-lock.yml"
+        )));
+        assert!(CondaLockFile::is_conda_lock_file(Path::new(
+            "envs/linux.conda-lock.yaml"
+        )));
+        assert!(!CondaLockFile::is_conda_lock_file(Path::new(
+            "environment.yml"
+        )));
+    }
+
+    #[test]
+    fn test_conda_lock_file_to_manifest() {
+        let lock_file = conda_lock_file();
+        assert_eq!(
+            lock_file.channels().unwrap(),
+            vec![NamedChannelOrUrl::from_str("conda-forge").unwrap()]
+        );
+        assert_eq!(lock_file.categories(), vec!["main", "dev"]);
+
+        let dependencies = lock_file.to_manifest(PinningStrategy::Semver).unwrap();
+        assert_eq!(
+            dependencies,
+            vec![
+                CondaLockDependency {
+                    spec: CondaLockSpec::Conda(
+                        MatchSpec::from_str("pytest >=8.2.0,<9", Strict).unwrap()
+                    ),
+                    platforms: vec![Platform::Linux64],
+                    category: "dev".to_string(),
+                },
+                CondaLockDependency {
+                    spec: CondaLockSpec::Conda(
+                        MatchSpec::from_str("python >=3.12.3,<4", Strict).unwrap()
+                    ),
+                    platforms: vec![],
+                    category: "main".to_string(),
+                },
+                CondaLockDependency {
+                    spec: CondaLockSpec::Pypi(
+                        pep508_rs::Requirement::from_str("Requests>=2.31.0,<3").unwrap()
+                    ),
+                    platforms: vec![Platform::Linux64],
+                    category: "main".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_conda_lock_file_locked_packages() {
+        let lock_file = conda_lock_file();
+
+        let (conda, pypi) = lock_file
+            .locked_packages(Platform::Linux64, &[MAIN_CATEGORY])
+            .unwrap();
+        assert_eq!(
+            conda
+                .iter()
+                .map(|record| record.file_name.as_str())
+                .collect_vec(),
+            vec![
+                "python-3.12.3-hab00c5b_0_cpython.conda",
+                "libzlib-1.2.13-h4ab18f5_6.conda"
+            ]
+        );
+        let python = &conda[0];
+        assert_eq!(python.package_record.build, "hab00c5b_0_cpython");
+        assert_eq!(python.package_record.build_number, 0);
+        assert_eq!(python.package_record.subdir, "linux-64");
+        assert_eq!(
+            python.package_record.depends,
+            vec!["__glibc >=2.17,<3.0.a0", "libzlib >=1.2.13,<2.0.0a0"]
+        );
+        assert_eq!(
+            python.channel.as_deref(),
+            Some("https://conda.anaconda.org/conda-forge/")
+        );
+        assert_eq!(conda[1].package_record.build_number, 6);
+        assert_eq!(
+            pypi.iter()
+                .map(|package| package.name.to_string())
+                .collect_vec(),
+            vec!["requests", "certifi"]
+        );
+        assert_eq!(
+            pypi[0].requires_dist,
+            vec![pep508_rs::Requirement::from_str("certifi>=2017.4.17").unwrap()]
+        );
+
+        let (conda, _) = lock_file
+            .locked_packages(Platform::Linux64, &[MAIN_CATEGORY, "dev"])
+            .unwrap();
+        assert_eq!(conda.len(), 3);
+        assert_eq!(conda[2].package_record.subdir, "noarch");
+    }
+}
@@ -0,0 +1,419 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use indexmap::IndexMap;
+use itertools::Itertools;
+use miette::{Context, IntoDiagnostic};
+use pixi_config::PinningStrategy;
+use rattler_conda_types::{
+    MatchSpec, NamedChannelOrUrl, PackageName, ParseStrictness::Lenient, Platform, PrefixRecord,
+    RepoDataRecord,
+};
+
+/// The host of the channels that are referred to by name.
+const CONDA_CHANNEL_HOST: &str = "conda.anaconda.org";
+
+/// An existing conda environment, read from the records of the installed
+/// packages in its `conda-meta` directory.
+#[derive(Debug, Clone)]
+pub struct CondaPrefix {
+    root: PathBuf,
+    records: Vec<PrefixRecord>,
+    /// The specs that were requested, by package name, according to the
+    /// `conda-meta/history` of the environment.
+    history_specs: Option<IndexMap<String, String>>,
+}
+
+/// Returns the requested specs of a `conda-meta/history` file. conda writes
+/// the specs of every transaction as a python list, e.g.
+/// `# update specs: ['python=3.12', 'numpy']`.
+fn parse_history(history: &str) -> IndexMap<String, String> {
+    let mut specs = IndexMap::new();
+    for line in history.lines() {
+        let Some((action, list)) = line
+            .strip_prefix('#')
+            .and_then(|line| line.split_once("specs:"))
+        else {
+            continue;
+        };
+        let action = action.trim();
+
+        // Every item of the list is quoted, the quotes of an item differ from the
+        // quotes inside of it.
+        let mut items = Vec::new();
+        let mut chars = list.trim().chars();
+        while let Some(c) = chars.next() {
+            if c == '\'' || c == '"' {
+                items.push(
+                    chars
+                        .by_ref()
+                        .take_while(|&end| end != c)
+                        .collect::<String>(),
+                );
+            }
+        }
+
+        for item in items {
+            let Some(name) = MatchSpec::from_str(&item, Lenient)
+                .ok()
+                .and_then(|spec| spec.name)
+            else {
+                continue;
+            };
+            let name = name.as_normalized().to_string();
+            match action {
+                "remove" => {
+                    specs.shift_remove(&name);
+                }
+                "update" | "install" | "create" => {
+                    specs.insert(name, item);
+                }
+                _ => {}
+            }
+        }
+    }
+    specs
+}
+
+/// Returns the name and version of the package of a python metadata
+/// directory, and the name of the metadata file inside of it, e.g.
+/// `numpy-1.26.4.dist-info` or `six-1.16.0-py3.12.egg-info`.
+fn parse_metadata_name(dir_name: &str) -> Option<(&str, &str, &str)> {
+    if let Some(stem) = dir_name.strip_suffix(".dist-info") {
+        let (name, version) = stem.rsplit_once('-')?;
+        return Some((name, version, "METADATA"));
+    }
+    let stem = dir_name.strip_suffix(".egg-info")?;
+    // Eggs can be tagged with the python version they were built for
+    let stem = match stem.rsplit_once('-') {
+        Some((rest, tag)) if tag.starts_with("py") => rest,
+        _ => stem,
+    };
+    let (name, version) = stem.rsplit_once('-')?;
+    Some((name, version, "PKG-INFO"))
+}
+
+impl CondaPrefix {
+    pub fn from_path(path: &Path) -> miette::Result<Self> {
+        let conda_meta = path.join("conda-meta");
+        if !conda_meta.is_dir() {
+            miette::bail!(
+                "'{}' is not a conda environment, it doesn't contain a 'conda-meta' directory",
+                path.display()
+            );
+        }
+        let records = PrefixRecord::collect_from_prefix(path)
+            .into_diagnostic()
+            .wrap_err_with(|| {
+                format!(
+                    "failed to read the installed packages of '{}'",
+                    path.display()
+                )
+            })?;
+        let history_path = conda_meta.join("history");
+        let history_specs = if history_path.is_file() {
+            let history = fs_err::read_to_string(&history_path).into_diagnostic()?;
+            Some(parse_history(&history)).filter(|specs| !specs.is_empty())
+        } else {
+            None
+        };
+
+        Ok(Self {
+            root: path.to_path_buf(),
+            records,
+            history_specs,
+        })
+    }
+
+    /// The platform of the environment, derived from the subdirs of its
+    /// packages.
+    pub fn platform(&self) -> Option<Platform> {
+        self.records
+            .iter()
+            .filter_map(|record| {
+                Platform::from_str(&record.repodata_record.package_record.subdir).ok()
+            })
+            .find(|platform| *platform != Platform::NoArch)
+    }
+
+    /// The channels the packages were installed from, the channel with the
+    /// most packages first.
+    pub fn channels(&self) -> miette::Result<Vec<NamedChannelOrUrl>> {
+        let counts = self
+            .records
+            .iter()
+            .filter_map(|record| record.repodata_record.url.join("..").ok())
+            .counts();
+        counts
+            .into_iter()
+            .sorted_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then(a.cmp(b)))
+            .map(|(url, _)| {
+                let path = url.path().trim_matches('/');
+                if url.host_str() == Some(CONDA_CHANNEL_HOST)
+                    && !path.is_empty()
+                    && !path.contains('/')
+                {
+                    return Ok(NamedChannelOrUrl::Name(path.to_string()));
+                }
+                NamedChannelOrUrl::from_str(url.as_str())
+                    .into_diagnostic()
+                    .wrap_err(format!("Can't parse '{}' as channel", url))
+            })
+            .collect()
+    }
+
+    /// Returns the conda dependencies of a manifest that reproduces the
+    /// environment. These are the packages that were requested according to
+    /// the history of the environment, or otherwise the packages that no other
+    /// package depends on. Their versions are constrained with the pinning
+    /// strategy.
+    pub fn to_manifest(&self, pinning_strategy: PinningStrategy) -> miette::Result<Vec<MatchSpec>> {
+        let requested: HashSet<&str> = match &self.history_specs {
+            Some(specs) => specs.keys().map(String::as_str).collect(),
+            None => {
+                let dependents: HashSet<PackageName> = self
+                    .records
+                    .iter()
+                    .flat_map(|record| &record.repodata_record.package_record.depends)
+                    .filter_map(|depend| MatchSpec::from_str(depend, Lenient).ok()?.name)
+                    .collect();
+                self.records
+                    .iter()
+                    .map(|record| &record.repodata_record.package_record.name)
+                    .filter(|name| !dependents.contains(*name))
+                    .map(PackageName::as_normalized)
+                    .collect()
+            }
+        };
+
+        self.records
+            .iter()
+            .map(|record| &record.repodata_record.package_record)
+            .filter(|record| requested.contains(record.name.as_normalized()))
+            .sorted_by(|a, b| a.name.cmp(&b.name))
+            .map(|record| {
+                let name = record.name.as_normalized();
+                let requirement = match pinning_strategy
+                    .determine_version_constraint([record.version.version()])
+                {
+                    Some(version_spec) => format!("{name} {version_spec}"),
+                    None => name.to_string(),
+                };
+                MatchSpec::from_str(&requirement, Lenient)
+                    .into_diagnostic()
+                    .wrap_err(format!("Can't parse '{}' as conda dependency", requirement))
+            })
+            .collect()
+    }
+
+    /// The records of the installed conda packages.
+    pub fn records(&self) -> Vec<RepoDataRecord> {
+        self.records
+            .iter()
+            .map(|record| record.repodata_record.clone())
+            .collect()
+    }
+
+    /// Returns the python packages that were installed with pip instead of
+    /// conda, pinned to their installed version. These are the `.dist-info`
+    /// and `.egg-info` metadata that isn't part of a conda package.
+    pub fn pypi_packages(&self) -> miette::Result<Vec<pep508_rs::Requirement>> {
+        let conda_files: HashSet<&Path> = self
+            .records
+            .iter()
+            .flat_map(|record| &record.files)
+            .map(PathBuf::as_path)
+            .collect();
+        // The site-packages directories of the python versions in the prefix
+        let mut site_packages_dirs = vec![PathBuf::from("Lib").join("site-packages")];
+        if let Ok(entries) = fs_err::read_dir(self.root.join("lib")) {
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().to_string();
+                if name.starts_with("python") {
+                    site_packages_dirs.push(PathBuf::from("lib").join(name).join("site-packages"));
+                }
+            }
+        }
+
+        let mut versions: HashMap<pep508_rs::PackageName, String> = HashMap::new();
+        for site_packages in site_packages_dirs {
+            let Ok(entries) = fs_err::read_dir(self.root.join(&site_packages)) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let dir_name = entry.file_name().to_string_lossy().to_string();
+                let Some((name, version, metadata)) = parse_metadata_name(&dir_name) else {
+                    continue;
+                };
+                // The metadata of an egg is either a directory or a single file
+                let metadata = if entry.path().is_dir() {
+                    site_packages.join(&dir_name).join(metadata)
+                } else {
+                    site_packages.join(&dir_name)
+                };
+                if conda_files.contains(metadata.as_path()) {
+                    continue;
+                }
+                let Ok(name) = pep508_rs::PackageName::from_str(name) else {
+                    continue;
+                };
+                versions.insert(name, version.to_string());
+            }
+        }
+
+        versions
+            .into_iter()
+            .sorted()
+            .map(|(name, version)| {
+                let requirement = format!("{name}=={version}");
+                pep508_rs::Requirement::from_str(&requirement)
+                    .into_diagnostic()
+                    .wrap_err(format!("Can't parse '{}' as pypi dependency", requirement))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rattler_conda_types::ParseStrictness::Strict;
+
+    use super::*;
+
+    fn write_record(prefix: &Path, name: &str, version: &str, subdir: &str, depends: &[&str]) {
+        let file_name = format!("{name}-{version}-h0_0.conda");
+        let files = if name == "numpy" {
+            vec!["lib/python3.12/site-packages/numpy-1.26.4.dist-info/METADATA"]
+        } else {
+            vec![]
+        };
+        let record = serde_json::json!({
+            "name": name,
+            "version": version,
+            "build": "h0_0",
+            "build_number": 0,
+            "subdir": subdir,
+            "depends": depends,
+            "fn": file_name,
+            "url": format!("https://conda.anaconda.org/conda-forge/{subdir}/{file_name}"),
+            "channel": "https://conda.anaconda.org/conda-forge",
+            "files": files,
+            "paths_data": { "paths_version": 1, "paths": [] },
+        });
+        fs_err::write(
+            prefix
+                .join("conda-meta")
+                .join(format!("{name}-{version}-h0_0.json")),
+            record.to_string(),
+        )
+        .unwrap();
+    }
+
+    fn fake_prefix(history: Option<&str>) -> (tempfile::TempDir, CondaPrefix) {
+        let prefix = tempfile::tempdir().unwrap();
+        fs_err::create_dir_all(prefix.path().join("conda-meta")).unwrap();
+        write_record(
+            prefix.path(),
+            "python",
+            "3.12.3",
+            "linux-64",
+            &["libzlib>=1.2.13"],
+        );
+        write_record(prefix.path(), "libzlib", "1.2.13", "linux-64", &[]);
+        write_record(
+            prefix.path(),
+            "numpy",
+            "1.26.4",
+            "linux-64",
+            &["python >=3.12"],
+        );
+        write_record(prefix.path(), "tzdata", "2024.1", "noarch", &[]);
+        if let Some(history) = history {
+            fs_err::write(prefix.path().join("conda-meta").join("history"), history).unwrap();
+        }
+        let site_packages = prefix.path().join("lib/python3.12/site-packages");
+        for dist_info in [
+            "numpy-1.26.4.dist-info",
+            "Flask_Cors-4.0.1.dist-info",
+            "six-1.16.0-py3.12.egg-info",
+        ] {
+            fs_err::create_dir_all(site_packages.join(dist_info)).unwrap();
+        }
+        fs_err::write(site_packages.join("pytz-2024.1.egg-info"), "").unwrap();
+
+        let conda_prefix = CondaPrefix::from_path(prefix.path()).unwrap();
+        (prefix, conda_prefix)
+    }
+
+    #[test]
+    fn test_parse_history() {
+        let specs = parse_history(
+            r#"==> 2024-05-01 10:00:00 <==
+# cmd: conda create -n lab python=3.12 numpy
+# update specs: ['python=3.12', 'numpy', "scipy[version='>=1.10,<2']"]
+==> 2024-05-02 10:00:00 <==
+# cmd: conda remove scipy
+# remove specs: ['scipy']
+"#,
+        );
+        assert_eq!(
+            specs,
+            IndexMap::from([
+                ("python".to_string(), "python=3.12".to_string()),
+                ("numpy".to_string(), "numpy".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_metadata_name() {
+        assert_eq!(
+            parse_metadata_name("numpy-1.26.4.dist-info"),
+            Some(("numpy", "1.26.4", "METADATA"))
+        );
+        assert_eq!(
+            parse_metadata_name("six-1.16.0-py3.12.egg-info"),
+            Some(("six", "1.16.0", "PKG-INFO"))
+        );
+        assert_eq!(
+            parse_metadata_name("pytz-2024.1.egg-info"),
+            Some(("pytz", "2024.1", "PKG-INFO"))
+        );
+        assert_eq!(parse_metadata_name("numpy"), None);
+    }
+
+    #[test]
+    fn test_conda_prefix_to_manifest() {
+        let (_prefix, conda_prefix) = fake_prefix(None);
+        assert_eq!(conda_prefix.platform(), Some(Platform::Linux64));
+        assert_eq!(
+            conda_prefix.channels().unwrap(),
+            vec![NamedChannelOrUrl::Name("conda-forge".to_string())]
+        );
+        assert_eq!(
+            conda_prefix.to_manifest(PinningStrategy::Semver).unwrap(),
+            vec![
+                MatchSpec::from_str("numpy >=1.26.4,<2", Strict).unwrap(),
+                MatchSpec::from_str("tzdata >=2024.1,<2025", Strict).unwrap(),
+            ]
+        );
+        assert_eq!(conda_prefix.records().len(), 4);
+        assert_eq!(
+            conda_prefix.pypi_packages().unwrap(),
+            vec![
+                pep508_rs::Requirement::from_str("flask-cors==4.0.1").unwrap(),
+                pep508_rs::Requirement::from_str("pytz==2024.1").unwrap(),
+                pep508_rs::Requirement::from_str("six==1.16.0").unwrap(),
+            ]
+        );
+
+        let (_prefix, conda_prefix) = fake_prefix(Some("# update specs: ['python=3.12']\n"));
+        assert_eq!(
+            conda_prefix.to_manifest(PinningStrategy::Semver).unwrap(),
+            vec![MatchSpec::from_str("python >=3.12.3,<4", Strict).unwrap()]
+        );
+    }
+}
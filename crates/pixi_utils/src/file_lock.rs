@@ -0,0 +1,173 @@
+use std::{
+    fmt, io,
+    ops::{Deref, DerefMut},
+    path::{Path, PathBuf},
+};
+
+use async_fd_lock::{LockError, LockWrite, RwLockWriteGuard};
+use serde::{Deserialize, Serialize};
+
+/// The process that holds the lock on a file, it is stored next to the file
+/// so other processes can tell the user who they are waiting for.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockOwner {
+    /// The id of the process.
+    pub pid: u32,
+    /// The command line of the process, without the executable.
+    pub command: String,
+}
+
+impl LockOwner {
+    /// The owner of the locks taken by the current process.
+    pub fn current() -> Self {
+        Self {
+            pid: std::process::id(),
+            command: std::env::args().skip(1).collect::<Vec<_>>().join(" "),
+        }
+    }
+}
+
+impl fmt::Display for LockOwner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`pixi {}` (pid {})", self.command, self.pid)
+    }
+}
+
+/// Returns the path of the file that stores the owner of the lock on the
+/// given file.
+fn owner_path(path: &Path) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".owner");
+    path.with_file_name(file_name)
+}
+
+/// Returns the process that holds the lock on the given file.
+pub async fn lock_owner(path: &Path) -> Option<LockOwner> {
+    let contents = tokio::fs::read(owner_path(path)).await.ok()?;
+    serde_json::from_slice(&contents).ok()
+}
+
+async fn write_lock_owner(path: &Path) -> io::Result<()> {
+    let contents = serde_json::to_vec(&LockOwner::current())?;
+    tokio::fs::write(owner_path(path), contents).await
+}
+
+/// A write lock on a file of which the owner is recorded next to the file.
+/// The record of the owner is removed when the lock is released.
+#[derive(Debug)]
+pub struct OwnedWriteGuard {
+    guard: RwLockWriteGuard<tokio::fs::File>,
+    owner_path: PathBuf,
+}
+
+impl Deref for OwnedWriteGuard {
+    type Target = RwLockWriteGuard<tokio::fs::File>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.guard
+    }
+}
+
+impl DerefMut for OwnedWriteGuard {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.guard
+    }
+}
+
+impl Drop for OwnedWriteGuard {
+    fn drop(&mut self) {
+        // Remove the owner while the lock is still held, so the owner that is
+        // recorded by the next process is not removed.
+        if let Err(err) = std::fs::remove_file(&self.owner_path) {
+            if err.kind() != io::ErrorKind::NotFound {
+                tracing::debug!(
+                    "failed to remove the owner of the lock '{}': {err}",
+                    self.owner_path.display()
+                );
+            }
+        }
+    }
+}
+
+/// Locks the file at the given path for writing, and records the current
+/// process as the owner of the lock until the lock is released.
+///
+/// If another process holds the lock, a warning with the owner of the lock is
+/// shown before waiting for it to be released.
+pub async fn lock_write_with_owner(
+    file: tokio::fs::File,
+    path: &Path,
+) -> Result<OwnedWriteGuard, LockError<tokio::fs::File>> {
+    let guard = match file.try_lock_write().await {
+        Ok(guard) => guard,
+        Err(err) => {
+            match lock_owner(path).await {
+                Some(owner) => tracing::warn!(
+                    "waiting for {owner} to release the lock on '{}'",
+                    path.display()
+                ),
+                None => tracing::warn!(
+                    "waiting for another process to release the lock on '{}'",
+                    path.display()
+                ),
+            }
+            err.file.lock_write().await?
+        }
+    };
+
+    if let Err(err) = write_lock_owner(path).await {
+        tracing::debug!(
+            "failed to record the owner of the lock on '{}': {err}",
+            path.display()
+        );
+    }
+    Ok(OwnedWriteGuard {
+        guard,
+        owner_path: owner_path(path),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    async fn open(path: &Path) -> tokio::fs::File {
+        tokio::fs::File::options()
+            .write(true)
+            .read(true)
+            .create(true)
+            .truncate(false)
+            .open(path)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_lock_write_with_owner() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".lock");
+
+        let guard = lock_write_with_owner(open(&path).await, &path)
+            .await
+            .unwrap();
+        assert_eq!(lock_owner(&path).await, Some(LockOwner::current()));
+
+        let second = tokio::spawn({
+            let path = path.clone();
+            async move {
+                lock_write_with_owner(open(&path).await, &path)
+                    .await
+                    .map(|_| ())
+                    .map_err(|err| err.error)
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(!second.is_finished());
+
+        drop(guard);
+        second.await.unwrap().unwrap();
+        assert!(!owner_path(&path).exists());
+    }
+}
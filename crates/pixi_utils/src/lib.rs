@@ -1,7 +1,13 @@
+pub mod bandwidth;
 pub mod cache;
 pub mod conda_environment_file;
+pub mod conda_lock_file;
+pub mod conda_prefix;
+mod file_lock;
 pub mod indicatif;
+mod long_path;
 mod prefix_guard;
+pub mod python_project_file;
 pub mod reqwest;
 
 mod executable_utils;
@@ -10,4 +16,6 @@ pub use executable_utils::{
 };
 
 pub use cache::EnvironmentHash;
+pub use file_lock::{lock_owner, lock_write_with_owner, LockOwner, OwnedWriteGuard};
+pub use long_path::to_extended_length;
 pub use prefix_guard::{AsyncPrefixGuard, AsyncWriteGuard};
@@ -0,0 +1,102 @@
+//! Conversion to extended-length paths on Windows. Deep conda prefixes
+//! regularly exceed `MAX_PATH` (260 characters), which makes the regular
+//! Win32 file APIs fail with cryptic I/O errors. Prefixing the path with
+//! `\\?\` lifts that limit.
+//!
+//! Pixi uses these paths where it removes environments and in its own caches
+//! of source builds and url sources. The prefix of an environment and the
+//! conda package cache are left to rattler: the prefix is embedded in the
+//! files of the environment and the directories of the cached packages are
+//! recorded in `conda-meta`, so neither can be an extended-length path.
+
+use std::{
+    borrow::Cow,
+    path::{Component, Path, PathBuf, Prefix},
+};
+
+/// Converts an absolute path to an extended-length path on Windows, e.g.
+/// `C:\envs\default` becomes `\\?\C:\envs\default` and `\\server\share`
+/// becomes `\\?\UNC\server\share`.
+///
+/// Extended-length paths are not normalized by Windows, so `.` and `..`
+/// components are resolved lexically. Relative paths, paths that already use
+/// the `\\?\` prefix and all paths on other platforms are returned unchanged.
+///
+/// Only use the result to access the filesystem, it should never be stored
+/// or embedded in the files of an environment.
+pub fn to_extended_length(path: &Path) -> Cow<'_, Path> {
+    if !cfg!(windows) {
+        return Cow::Borrowed(path);
+    }
+
+    let mut components = path.components();
+    let Some(Component::Prefix(prefix)) = components.next() else {
+        return Cow::Borrowed(path);
+    };
+    let mut extended = match prefix.kind() {
+        Prefix::Disk(letter) => PathBuf::from(format!(r"\\?\{}:\", letter as char)),
+        Prefix::UNC(server, share) => PathBuf::from(format!(
+            r"\\?\UNC\{}\{}\",
+            server.to_string_lossy(),
+            share.to_string_lossy()
+        )),
+        Prefix::Verbatim(_) | Prefix::VerbatimDisk(_) | Prefix::VerbatimUNC(_, _) => {
+            return Cow::Borrowed(path)
+        }
+        Prefix::DeviceNS(_) => return Cow::Borrowed(path),
+    };
+    if !path.has_root() {
+        // A path like `C:foo` is relative to the current directory of the drive.
+        return Cow::Borrowed(path);
+    }
+
+    for component in components {
+        match component {
+            Component::RootDir | Component::CurDir => {}
+            Component::ParentDir => {
+                // Never pop the prefix itself.
+                if extended.parent().is_some() {
+                    extended.pop();
+                }
+            }
+            Component::Normal(part) => extended.push(part),
+            Component::Prefix(_) => unreachable!("a prefix can only be the first component"),
+        }
+    }
+    Cow::Owned(extended)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(not(windows))]
+    fn test_unchanged_on_unix() {
+        let path = Path::new("/home/user/project/.pixi/envs/default");
+        assert_eq!(to_extended_length(path), path);
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn test_extended_length() {
+        let convert = |path: &str| to_extended_length(Path::new(path)).into_owned();
+        assert_eq!(
+            convert(r"C:\project\.pixi\envs\default"),
+            PathBuf::from(r"\\?\C:\project\.pixi\envs\default")
+        );
+        assert_eq!(
+            convert(r"C:\project\.\.pixi\..\envs"),
+            PathBuf::from(r"\\?\C:\project\envs")
+        );
+        assert_eq!(
+            convert(r"\\server\share\envs"),
+            PathBuf::from(r"\\?\UNC\server\share\envs")
+        );
+        assert_eq!(
+            convert(r"\\?\C:\already\extended"),
+            PathBuf::from(r"\\?\C:\already\extended")
+        );
+        assert_eq!(convert(r"relative\path"), PathBuf::from(r"relative\path"));
+    }
+}
@@ -1,10 +1,14 @@
-use std::{io, path::Path};
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
 
-use async_fd_lock::LockWrite;
 use serde::{Deserialize, Serialize};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::{self, io::AsyncSeekExt};
 
+use crate::file_lock::{lock_write_with_owner, OwnedWriteGuard};
+
 const GUARD_PATH: &str = ".guard";
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
@@ -22,12 +26,12 @@ enum GuardState {
 
 #[derive(Debug)]
 pub struct AsyncWriteGuard {
-    guard: async_fd_lock::RwLockWriteGuard<tokio::fs::File>,
+    guard: OwnedWriteGuard,
     state: GuardState,
 }
 
 impl AsyncWriteGuard {
-    async fn new(mut guard: async_fd_lock::RwLockWriteGuard<tokio::fs::File>) -> io::Result<Self> {
+    async fn new(mut guard: OwnedWriteGuard) -> io::Result<Self> {
         let mut bytes = Vec::new();
         guard.read_to_end(&mut bytes).await?;
         let state = serde_json::from_slice(&bytes).unwrap_or(GuardState::Unknown);
@@ -70,6 +74,7 @@ impl AsyncWriteGuard {
 
 pub struct AsyncPrefixGuard {
     guard: tokio::fs::File,
+    path: PathBuf,
 }
 
 impl AsyncPrefixGuard {
@@ -86,17 +91,21 @@ impl AsyncPrefixGuard {
             .read(true)
             .create(true)
             .truncate(false)
-            .open(guard_path)
+            .open(&guard_path)
             .await?;
 
         // Open the file
-        Ok(Self { guard: file })
+        Ok(Self {
+            guard: file,
+            path: guard_path,
+        })
     }
 
     /// Locks the guard for writing and returns a write guard which can be used
-    /// to unlock it.
+    /// to unlock it. If another process holds the lock, a warning names the
+    /// process that is waited for.
     pub async fn write(self) -> io::Result<AsyncWriteGuard> {
-        let write_guard = self.guard.lock_write().await?;
+        let write_guard = lock_write_with_owner(self.guard, &self.path).await?;
 
         AsyncWriteGuard::new(write_guard).await
     }
@@ -0,0 +1,947 @@
+use std::{collections::HashMap, fmt::Display, path::Path, str::FromStr};
+
+use indexmap::IndexMap;
+use itertools::Itertools;
+use miette::{Context, IntoDiagnostic};
+use serde::{de::IgnoredAny, Deserialize};
+
+/// The group of the development dependencies of poetry and uv.
+const DEV_GROUP: &str = "dev";
+
+/// The names of the lock files of poetry, PDM and uv.
+pub const PYTHON_PROJECT_LOCK_FILES: [&str; 3] = ["poetry.lock", "pdm.lock", "uv.lock"];
+
+/// The tool that manages a python project.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PythonProjectTool {
+    Poetry,
+    Pdm,
+    Uv,
+    /// A plain `[project]` table without tool specific dependencies.
+    Pep621,
+}
+
+impl Display for PythonProjectTool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PythonProjectTool::Poetry => write!(f, "poetry"),
+            PythonProjectTool::Pdm => write!(f, "pdm"),
+            PythonProjectTool::Uv => write!(f, "uv"),
+            PythonProjectTool::Pep621 => write!(f, "pep621"),
+        }
+    }
+}
+
+/// A `pyproject.toml` of a python project managed by poetry, PDM, uv or any
+/// other tool that uses the `[project]` table.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct PythonProjectFile {
+    #[serde(default)]
+    project: Option<ProjectTable>,
+    #[serde(default)]
+    dependency_groups: IndexMap<String, Vec<DependencyGroupEntry>>,
+    #[serde(default)]
+    build_system: Option<IgnoredAny>,
+    #[serde(default)]
+    tool: ToolTables,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct ProjectTable {
+    name: Option<String>,
+    #[serde(default)]
+    dependencies: Vec<String>,
+    #[serde(default)]
+    optional_dependencies: IndexMap<String, Vec<String>>,
+    requires_python: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(untagged)]
+enum DependencyGroupEntry {
+    Requirement(String),
+    Include {
+        #[serde(rename = "include-group")]
+        include_group: String,
+    },
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+struct ToolTables {
+    poetry: Option<PoetryTable>,
+    pdm: Option<PdmTable>,
+    uv: Option<UvTable>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct PoetryTable {
+    name: Option<String>,
+    #[serde(default)]
+    dependencies: IndexMap<String, PoetryDependency>,
+    #[serde(default)]
+    dev_dependencies: IndexMap<String, PoetryDependency>,
+    #[serde(default)]
+    group: IndexMap<String, PoetryGroup>,
+    #[serde(default)]
+    extras: IndexMap<String, Vec<String>>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct PoetryGroup {
+    #[serde(default)]
+    dependencies: IndexMap<String, PoetryDependency>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(untagged)]
+enum PoetryDependency {
+    Constraint(String),
+    Detailed(Box<PoetryDetailedDependency>),
+    Multiple(Vec<PoetryDetailedDependency>),
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+struct PoetryDetailedDependency {
+    version: Option<String>,
+    #[serde(default)]
+    extras: Vec<String>,
+    markers: Option<String>,
+    python: Option<String>,
+    #[serde(default)]
+    optional: bool,
+    git: Option<String>,
+    branch: Option<String>,
+    tag: Option<String>,
+    rev: Option<String>,
+    subdirectory: Option<String>,
+    url: Option<String>,
+    path: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct PdmTable {
+    #[serde(default)]
+    dev_dependencies: IndexMap<String, Vec<String>>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct UvTable {
+    #[serde(default)]
+    dev_dependencies: Vec<String>,
+    #[serde(default)]
+    sources: IndexMap<String, UvSource>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct UvSource {
+    git: Option<String>,
+    branch: Option<String>,
+    tag: Option<String>,
+    rev: Option<String>,
+    subdirectory: Option<String>,
+    url: Option<String>,
+}
+
+/// The dependencies of a python project, converted to PEP 508 requirements.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PythonProjectDependencies {
+    pub tool: PythonProjectTool,
+    /// The name of the project, if it is a package that can be installed with
+    /// its build system.
+    pub package_name: Option<String>,
+    /// The versions of python the project supports.
+    pub requires_python: Option<pep440_rs::VersionSpecifiers>,
+    pub dependencies: Vec<pep508_rs::Requirement>,
+    /// The optional dependencies and dependency groups, by the name of the
+    /// feature they map to.
+    pub groups: IndexMap<String, Vec<pep508_rs::Requirement>>,
+}
+
+impl PythonProjectFile {
+    pub fn from_path(path: &Path) -> miette::Result<Self> {
+        let contents = fs_err::read_to_string(path).into_diagnostic()?;
+        toml_edit::de::from_str(&contents)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("Failed to parse '{}' as a python project", path.display()))
+    }
+
+    /// The tool the dependencies of the project are managed with.
+    pub fn tool(&self) -> PythonProjectTool {
+        if self.tool.poetry.is_some() {
+            PythonProjectTool::Poetry
+        } else if self.tool.pdm.is_some() {
+            PythonProjectTool::Pdm
+        } else if self.tool.uv.is_some() {
+            PythonProjectTool::Uv
+        } else {
+            PythonProjectTool::Pep621
+        }
+    }
+
+    /// The name of the project.
+    pub fn name(&self) -> Option<&str> {
+        self.project
+            .as_ref()
+            .and_then(|project| project.name.as_deref())
+            .or_else(|| {
+                self.tool
+                    .poetry
+                    .as_ref()
+                    .and_then(|poetry| poetry.name.as_deref())
+            })
+    }
+
+    pub fn to_manifest(&self) -> miette::Result<PythonProjectDependencies> {
+        let mut dependencies = Vec::new();
+        let mut groups: IndexMap<String, Vec<pep508_rs::Requirement>> = IndexMap::new();
+        let mut requires_python = None;
+
+        if let Some(project) = &self.project {
+            dependencies.extend(self.parse_requirements(&project.dependencies)?);
+            for (extra, requirements) in &project.optional_dependencies {
+                groups
+                    .entry(feature_name(extra))
+                    .or_default()
+                    .extend(self.parse_requirements(requirements)?);
+            }
+            requires_python = project.requires_python.clone();
+        }
+
+        if let Some(poetry) = &self.tool.poetry {
+            let mut optional = IndexMap::new();
+            for (name, dependency) in &poetry.dependencies {
+                if name == "python" {
+                    if let PoetryDependency::Constraint(constraint) = dependency {
+                        requires_python = requires_python.or(poetry_to_pep440(constraint));
+                    }
+                    continue;
+                }
+                let Some((requirement, is_optional)) = poetry_requirement(name, dependency)? else {
+                    continue;
+                };
+                if is_optional {
+                    optional.insert(
+                        pep508_rs::PackageName::from_str(name).into_diagnostic()?,
+                        requirement,
+                    );
+                } else {
+                    dependencies.push(requirement);
+                }
+            }
+            // Optional dependencies are only installed through the extras that
+            // refer to them.
+            for (extra, names) in &poetry.extras {
+                let group = groups.entry(feature_name(extra)).or_default();
+                for name in names {
+                    let name = pep508_rs::PackageName::from_str(name).into_diagnostic()?;
+                    if let Some(requirement) = optional.get(&name) {
+                        group.push(requirement.clone());
+                    }
+                }
+            }
+
+            let poetry_groups = std::iter::once((DEV_GROUP, &poetry.dev_dependencies)).chain(
+                poetry
+                    .group
+                    .iter()
+                    .map(|(name, group)| (name.as_str(), &group.dependencies)),
+            );
+            for (group_name, group_dependencies) in poetry_groups {
+                for (name, dependency) in group_dependencies {
+                    if let Some((requirement, _)) = poetry_requirement(name, dependency)? {
+                        groups
+                            .entry(feature_name(group_name))
+                            .or_default()
+                            .push(requirement);
+                    }
+                }
+            }
+        }
+
+        if let Some(pdm) = &self.tool.pdm {
+            for (group, requirements) in &pdm.dev_dependencies {
+                groups
+                    .entry(feature_name(group))
+                    .or_default()
+                    .extend(self.parse_requirements(requirements)?);
+            }
+        }
+
+        if let Some(uv) = &self.tool.uv {
+            if !uv.dev_dependencies.is_empty() {
+                groups
+                    .entry(DEV_GROUP.to_string())
+                    .or_default()
+                    .extend(self.parse_requirements(&uv.dev_dependencies)?);
+            }
+        }
+
+        for group in self.dependency_groups.keys() {
+            let requirements = self.resolve_dependency_group(group, &mut Vec::new())?;
+            groups
+                .entry(feature_name(group))
+                .or_default()
+                .extend(requirements);
+        }
+
+        // Groups without dependencies don't need a feature.
+        groups.retain(|_, requirements| !requirements.is_empty());
+
+        let requires_python = requires_python
+            .map(|specifiers| {
+                pep440_rs::VersionSpecifiers::from_str(&specifiers)
+                    .into_diagnostic()
+                    .wrap_err(format!("Can't parse '{}' as python version", specifiers))
+            })
+            .transpose()?;
+
+        Ok(PythonProjectDependencies {
+            tool: self.tool(),
+            package_name: self
+                .build_system
+                .as_ref()
+                .and_then(|_| self.name())
+                .map(ToString::to_string),
+            requires_python,
+            dependencies,
+            groups,
+        })
+    }
+
+    /// Resolves the `include-group` entries of a dependency group.
+    fn resolve_dependency_group<'a>(
+        &'a self,
+        group: &'a str,
+        parents: &mut Vec<&'a str>,
+    ) -> miette::Result<Vec<pep508_rs::Requirement>> {
+        if parents.contains(&group) {
+            miette::bail!("the dependency group '{group}' includes itself");
+        }
+        let Some(entries) = self.dependency_groups.get(group) else {
+            miette::bail!("the dependency group '{group}' doesn't exist");
+        };
+
+        parents.push(group);
+        let mut requirements = Vec::new();
+        for entry in entries {
+            match entry {
+                DependencyGroupEntry::Requirement(requirement) => {
+                    requirements.extend(self.parse_requirements(std::slice::from_ref(requirement))?)
+                }
+                DependencyGroupEntry::Include { include_group } => {
+                    requirements.extend(self.resolve_dependency_group(include_group, parents)?)
+                }
+            }
+        }
+        parents.pop();
+
+        Ok(requirements)
+    }
+
+    /// Parses PEP 508 requirements, the `[tool.uv.sources]` of the packages
+    /// are applied as direct references.
+    fn parse_requirements(
+        &self,
+        requirements: &[String],
+    ) -> miette::Result<Vec<pep508_rs::Requirement>> {
+        requirements
+            .iter()
+            .map(|requirement| {
+                let mut requirement = pep508_rs::Requirement::from_str(requirement)
+                    .into_diagnostic()
+                    .wrap_err(format!("Can't parse '{}' as pypi dependency", requirement))?;
+                let source = self.tool.uv.as_ref().and_then(|uv| {
+                    uv.sources.iter().find_map(|(name, source)| {
+                        (pep508_rs::PackageName::from_str(name).ok().as_ref()
+                            == Some(&requirement.name))
+                        .then_some(source)
+                    })
+                });
+                if let Some(source) = source {
+                    let url = if let Some(git) = &source.git {
+                        Some(git_url(
+                            git,
+                            [&source.rev, &source.tag, &source.branch],
+                            source.subdirectory.as_deref(),
+                        ))
+                    } else {
+                        source.url.clone()
+                    };
+                    match url {
+                        Some(url) => {
+                            requirement = pep508_rs::Requirement::from_str(&format!(
+                                "{}{} @ {url}{}",
+                                requirement.name,
+                                format_extras(&requirement.extras),
+                                format_marker(requirement.marker.try_to_string())
+                            ))
+                            .into_diagnostic()
+                            .wrap_err(format!(
+                                "Can't use the uv source of '{}'",
+                                requirement.name
+                            ))?;
+                        }
+                        None => tracing::warn!(
+                            "Skipping the uv source of '{}', please add it manually",
+                            requirement.name
+                        ),
+                    }
+                }
+                Ok(requirement)
+            })
+            .collect()
+    }
+}
+
+/// The feature of a dependency group named `default`, which can't be named
+/// after the group as it would be merged into the default feature.
+const DEFAULT_GROUP_FEATURE: &str = "default-group";
+
+/// Returns the feature of a dependency group or extra. Features can only
+/// contain lowercase letters, numbers and dashes, and the name `default` is
+/// reserved for the default feature.
+pub fn feature_name(group: &str) -> String {
+    let name = group.to_lowercase().replace(['_', '.'], "-");
+    if name == "default" {
+        DEFAULT_GROUP_FEATURE.to_string()
+    } else {
+        name
+    }
+}
+
+fn format_extras(extras: &[impl Display]) -> String {
+    if extras.is_empty() {
+        String::new()
+    } else {
+        format!("[{}]", extras.iter().join(","))
+    }
+}
+
+fn format_marker(marker: Option<String>) -> String {
+    marker
+        .map(|marker| format!(" ; {marker}"))
+        .unwrap_or_default()
+}
+
+/// Returns the direct reference to a git repository.
+fn git_url(git: &str, reference: [&Option<String>; 3], subdirectory: Option<&str>) -> String {
+    // Convert scp-like urls, e.g. `git@github.com:user/repo.git`
+    let url = match git.split_once(':') {
+        Some((host, path)) if host.starts_with("git@") && !path.starts_with("//") => {
+            format!("ssh://{host}/{path}")
+        }
+        _ => git.to_string(),
+    };
+    let mut url = if url.starts_with("git+") {
+        url
+    } else {
+        format!("git+{url}")
+    };
+    if let Some(reference) = reference.into_iter().flatten().next() {
+        url = format!("{url}@{reference}");
+    }
+    if let Some(subdirectory) = subdirectory {
+        url = format!("{url}#subdirectory={subdirectory}");
+    }
+    url
+}
+
+/// Converts a poetry dependency to a requirement, and whether it is optional.
+/// Dependencies that can't be expressed as a PEP 508 requirement are skipped.
+fn poetry_requirement(
+    name: &str,
+    dependency: &PoetryDependency,
+) -> miette::Result<Option<(pep508_rs::Requirement, bool)>> {
+    let dependency = match dependency {
+        PoetryDependency::Constraint(version) => PoetryDetailedDependency {
+            version: Some(version.clone()),
+            ..PoetryDetailedDependency::default()
+        },
+        PoetryDependency::Detailed(dependency) => dependency.as_ref().clone(),
+        PoetryDependency::Multiple(dependencies) => {
+            tracing::warn!(
+                "Only using the first of the multiple constraints of '{name}', please add the others manually"
+            );
+            let Some(dependency) = dependencies.first() else {
+                return Ok(None);
+            };
+            dependency.clone()
+        }
+    };
+
+    let version_or_url = if let Some(git) = &dependency.git {
+        format!(
+            " @ {}",
+            git_url(
+                git,
+                [&dependency.rev, &dependency.tag, &dependency.branch],
+                dependency.subdirectory.as_deref()
+            )
+        )
+    } else if let Some(url) = &dependency.url {
+        format!(" @ {url}")
+    } else if dependency.path.is_some() {
+        tracing::warn!("Skipping the path dependency '{name}', please add it manually");
+        return Ok(None);
+    } else {
+        match dependency.version.as_deref() {
+            Some(version) => match poetry_to_pep440(version) {
+                Some(specifiers) => specifiers,
+                None if version.trim() == "*" => String::new(),
+                None => {
+                    tracing::warn!("Can't convert the version '{version}' of '{name}', using any version instead");
+                    String::new()
+                }
+            },
+            None => String::new(),
+        }
+    };
+
+    let mut markers = Vec::new();
+    if let Some(python) = dependency.python.as_deref().and_then(poetry_to_pep440) {
+        markers.extend(python.split(',').filter_map(|specifier| {
+            let specifier = specifier.trim();
+            let version_start = specifier.find(|c: char| c.is_ascii_digit())?;
+            let (operator, version) = specifier.split_at(version_start);
+            Some(format!("python_version {operator} '{version}'"))
+        }));
+    }
+    if let Some(marker) = &dependency.markers {
+        markers.push(format!("({marker})"));
+    }
+    let marker = (!markers.is_empty()).then(|| markers.join(" and "));
+
+    let requirement = format!(
+        "{name}{}{version_or_url}{}",
+        format_extras(&dependency.extras),
+        format_marker(marker)
+    );
+    let requirement = pep508_rs::Requirement::from_str(&requirement)
+        .into_diagnostic()
+        .wrap_err(format!("Can't parse '{}' as pypi dependency", requirement))?;
+    Ok(Some((requirement, dependency.optional)))
+}
+
+/// Converts a poetry version constraint, e.g. `^1.2`, `~1.2.3` or `>=1,<2`, to
+/// PEP 440 version specifiers. Returns `None` for constraints without a bound
+/// and for alternatives (`||`) that PEP 440 can't express.
+fn poetry_to_pep440(constraint: &str) -> Option<String> {
+    if constraint.contains("||") || constraint.contains('|') {
+        return None;
+    }
+
+    // Constraints are separated by commas or whitespace, and operators may be
+    // separated from their version by whitespace.
+    let mut terms: Vec<String> = Vec::new();
+    for token in constraint
+        .split([',', ' '])
+        .filter(|token| !token.is_empty())
+    {
+        match terms.last_mut() {
+            Some(last) if last.chars().all(|c| "<>=!~^".contains(c)) => last.push_str(token),
+            _ => terms.push(token.to_string()),
+        }
+    }
+
+    let specifiers = terms
+        .iter()
+        .filter(|term| term.as_str() != "*")
+        .map(|term| {
+            if let Some(version) = term.strip_prefix('^') {
+                bounded_range(version, |segments| {
+                    segments
+                        .iter()
+                        .position(|segment| *segment != 0)
+                        .unwrap_or(segments.len() - 1)
+                })
+            } else if let Some(version) = term.strip_prefix("~=") {
+                Some(format!("~={version}"))
+            } else if let Some(version) = term.strip_prefix('~') {
+                bounded_range(version, |segments| usize::from(segments.len() > 1))
+            } else if let Some(version) = term.strip_prefix("==") {
+                Some(format!("=={version}"))
+            } else if term.starts_with(['<', '>', '!']) {
+                Some(term.clone())
+            } else if let Some(version) = term.strip_prefix('=') {
+                Some(format!("=={version}"))
+            } else {
+                Some(format!("=={term}"))
+            }
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    (!specifiers.is_empty()).then(|| specifiers.join(","))
+}
+
+/// Returns `>=version,<upper`, where the upper bound increments the segment at
+/// the index returned by `bump_index` and drops the segments after it.
+fn bounded_range(version: &str, bump_index: impl Fn(&[u64]) -> usize) -> Option<String> {
+    let segments: Vec<u64> = version
+        .split('.')
+        .map(|segment| segment.parse().ok())
+        .collect::<Option<_>>()?;
+    if segments.is_empty() {
+        return None;
+    }
+    let index = bump_index(&segments);
+    let upper = segments
+        .iter()
+        .enumerate()
+        .map(|(i, segment)| match i.cmp(&index) {
+            std::cmp::Ordering::Less => *segment,
+            std::cmp::Ordering::Equal => segment + 1,
+            std::cmp::Ordering::Greater => 0,
+        })
+        .join(".");
+    Some(format!(">={version},<{upper}"))
+}
+
+/// The versions of the packages locked by the `poetry.lock`, `pdm.lock` or
+/// `uv.lock` of a python project.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PythonProjectLock {
+    /// The locked version by package, `None` if the package is locked at
+    /// several versions, for example for different platforms.
+    versions: HashMap<pep508_rs::PackageName, Option<pep440_rs::Version>>,
+}
+
+#[derive(Deserialize)]
+struct LockFileContents {
+    #[serde(default)]
+    package: Vec<LockedPackage>,
+}
+
+#[derive(Deserialize)]
+struct LockedPackage {
+    name: String,
+    version: Option<String>,
+}
+
+impl PythonProjectLock {
+    /// Returns true if the path is a lock file of poetry, PDM or uv.
+    pub fn is_lock_file(path: &Path) -> bool {
+        path.file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| PYTHON_PROJECT_LOCK_FILES.contains(&name))
+    }
+
+    pub fn from_path(path: &Path) -> miette::Result<Self> {
+        let contents = fs_err::read_to_string(path).into_diagnostic()?;
+        Self::from_toml(&contents)
+            .wrap_err_with(|| format!("Failed to parse '{}' as a lock file", path.display()))
+    }
+
+    fn from_toml(contents: &str) -> miette::Result<Self> {
+        let contents: LockFileContents = toml_edit::de::from_str(contents).into_diagnostic()?;
+        let mut versions: HashMap<_, Option<pep440_rs::Version>> = HashMap::new();
+        for package in contents.package {
+            // The project itself and dynamic packages don't have a version
+            let Some(version) = package.version else {
+                continue;
+            };
+            let name = pep508_rs::PackageName::from_str(&package.name).into_diagnostic()?;
+            let version = pep440_rs::Version::from_str(&version).into_diagnostic()?;
+            versions
+                .entry(name)
+                .and_modify(|locked| {
+                    if locked.as_ref() != Some(&version) {
+                        *locked = None;
+                    }
+                })
+                .or_insert(Some(version));
+        }
+        Ok(Self { versions })
+    }
+
+    /// Pins a requirement to the locked version of its package. Requirements
+    /// with a url, and of packages that are not locked at a single version,
+    /// are returned unchanged.
+    pub fn pin(&self, requirement: &pep508_rs::Requirement) -> pep508_rs::Requirement {
+        let mut requirement = requirement.clone();
+        if matches!(
+            requirement.version_or_url,
+            Some(pep508_rs::VersionOrUrl::Url(_))
+        ) {
+            return requirement;
+        }
+        if let Some(Some(version)) = self.versions.get(&requirement.name) {
+            let specifiers = pep440_rs::VersionSpecifiers::from_str(&format!("=={version}"))
+                .expect("a version is a valid specifier");
+            requirement.version_or_url =
+                Some(pep508_rs::VersionOrUrl::VersionSpecifier(specifiers));
+        }
+        requirement
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    fn parse(contents: &str) -> PythonProjectDependencies {
+        let mut f = tempfile::NamedTempFile::new().unwrap();
+        f.write_all(contents.as_bytes()).unwrap();
+        PythonProjectFile::from_path(f.path())
+            .unwrap()
+            .to_manifest()
+            .unwrap()
+    }
+
+    fn requirements(requirements: &[&str]) -> Vec<pep508_rs::Requirement> {
+        requirements
+            .iter()
+            .map(|requirement| pep508_rs::Requirement::from_str(requirement).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_poetry_to_pep440() {
+        assert_eq!(
+            poetry_to_pep440("^1.2.3").as_deref(),
+            Some(">=1.2.3,<2.0.0")
+        );
+        assert_eq!(
+            poetry_to_pep440("^0.2.3").as_deref(),
+            Some(">=0.2.3,<0.3.0")
+        );
+        assert_eq!(
+            poetry_to_pep440("^0.0.3").as_deref(),
+            Some(">=0.0.3,<0.0.4")
+        );
+        assert_eq!(poetry_to_pep440("^0.0").as_deref(), Some(">=0.0,<0.1"));
+        assert_eq!(
+            poetry_to_pep440("~1.2.3").as_deref(),
+            Some(">=1.2.3,<1.3.0")
+        );
+        assert_eq!(poetry_to_pep440("~1").as_deref(), Some(">=1,<2"));
+        assert_eq!(poetry_to_pep440("~=1.2").as_deref(), Some("~=1.2"));
+        assert_eq!(
+            poetry_to_pep440(">= 1.2, < 1.5").as_deref(),
+            Some(">=1.2,<1.5")
+        );
+        assert_eq!(
+            poetry_to_pep440(">=1.2 <1.5").as_deref(),
+            Some(">=1.2,<1.5")
+        );
+        assert_eq!(poetry_to_pep440("1.2.*").as_deref(), Some("==1.2.*"));
+        assert_eq!(poetry_to_pep440("2.0").as_deref(), Some("==2.0"));
+        assert_eq!(poetry_to_pep440("*"), None);
+        assert_eq!(poetry_to_pep440("^1.0 || ^2.0"), None);
+    }
+
+    #[test]
+    fn test_import_poetry_project() {
+        let dependencies = parse(
+            r#"
+            [tool.poetry]
+            name = "my-project"
+
+            [tool.poetry.dependencies]
+            python = "^3.10"
+            requests = "^2.31"
+            numpy = { version = ">=1.24", python = ">=3.11" }
+            rich = { version = "^13", extras = ["jupyter"], optional = true }
+            mylib = { git = "https://github.com/user/mylib.git", tag = "v1.0" }
+            local = { path = "../local", develop = true }
+
+            [tool.poetry.extras]
+            cli = ["rich"]
+
+            [tool.poetry.group.test.dependencies]
+            pytest = "~8.2"
+
+            [build-system]
+            requires = ["poetry-core"]
+            build-backend = "poetry.core.masonry.api"
+            "#,
+        );
+
+        assert_eq!(dependencies.tool, PythonProjectTool::Poetry);
+        assert_eq!(dependencies.package_name.as_deref(), Some("my-project"));
+        assert_eq!(
+            dependencies.requires_python,
+            Some(pep440_rs::VersionSpecifiers::from_str(">=3.10,<4.0").unwrap())
+        );
+        assert_eq!(
+            dependencies.dependencies,
+            requirements(&[
+                "requests>=2.31,<3.0",
+                "numpy>=1.24 ; python_version >= '3.11'",
+                "mylib @ git+https://github.com/user/mylib.git@v1.0",
+            ])
+        );
+        assert_eq!(
+            dependencies.groups,
+            IndexMap::from([
+                ("cli".to_string(), requirements(&["rich[jupyter]>=13,<14"])),
+                ("test".to_string(), requirements(&["pytest>=8.2,<8.3"])),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_import_uv_project() {
+        let dependencies = parse(
+            r#"
+            [project]
+            name = "my-project"
+            requires-python = ">=3.11"
+            dependencies = ["httpx>=0.27", "mylib"]
+
+            [project.optional-dependencies]
+            plot = ["matplotlib"]
+
+            [dependency-groups]
+            lint = ["ruff"]
+            dev = ["pytest", { include-group = "lint" }]
+
+            [tool.uv]
+            dev-dependencies = ["ipython"]
+
+            [tool.uv.sources]
+            mylib = { git = "https://github.com/user/mylib", rev = "abc123" }
+            "#,
+        );
+
+        assert_eq!(dependencies.tool, PythonProjectTool::Uv);
+        assert_eq!(dependencies.package_name, None);
+        assert_eq!(
+            dependencies.requires_python,
+            Some(pep440_rs::VersionSpecifiers::from_str(">=3.11").unwrap())
+        );
+        assert_eq!(
+            dependencies.dependencies,
+            requirements(&[
+                "httpx>=0.27",
+                "mylib @ git+https://github.com/user/mylib@abc123"
+            ])
+        );
+        assert_eq!(
+            dependencies.groups,
+            IndexMap::from([
+                ("plot".to_string(), requirements(&["matplotlib"])),
+                (
+                    "dev".to_string(),
+                    requirements(&["ipython", "pytest", "ruff"])
+                ),
+                ("lint".to_string(), requirements(&["ruff"])),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_import_default_group() {
+        let dependencies = parse(
+            r#"
+            [project]
+            name = "my-project"
+            dependencies = ["httpx"]
+
+            [project.optional-dependencies]
+            Default = ["rich"]
+
+            [dependency-groups]
+            default = ["pytest"]
+            "#,
+        );
+
+        // A group named `default` doesn't end up in the default feature.
+        assert_eq!(dependencies.dependencies, requirements(&["httpx"]));
+        assert_eq!(
+            dependencies.groups,
+            IndexMap::from([(
+                "default-group".to_string(),
+                requirements(&["rich", "pytest"])
+            )])
+        );
+        assert_eq!(feature_name("Dev_Tools.extra"), "dev-tools-extra");
+    }
+
+    #[test]
+    fn test_import_pdm_project() {
+        let dependencies = parse(
+            r#"
+            [project]
+            name = "my-project"
+            dependencies = ["flask"]
+
+            [tool.pdm.dev-dependencies]
+            test_tools = ["pytest>=8"]
+            "#,
+        );
+
+        assert_eq!(dependencies.tool, PythonProjectTool::Pdm);
+        assert_eq!(dependencies.dependencies, requirements(&["flask"]));
+        assert_eq!(
+            dependencies.groups,
+            IndexMap::from([("test-tools".to_string(), requirements(&["pytest>=8"]))])
+        );
+    }
+
+    #[test]
+    fn test_python_project_lock() {
+        let poetry_lock = PythonProjectLock::from_toml(
+            r#"
+            [[package]]
+            name = "Requests"
+            version = "2.31.0"
+
+            [[package]]
+            name = "numpy"
+            version = "1.26.4"
+
+            [metadata]
+            lock-version = "2.0"
+            "#,
+        )
+        .unwrap();
+        let uv_lock = PythonProjectLock::from_toml(
+            r#"
+            version = 1
+
+            [[package]]
+            name = "my-project"
+            source = { editable = "." }
+
+            [[package]]
+            name = "numpy"
+            version = "1.26.4"
+            source = { registry = "https://pypi.org/simple" }
+
+            [[package]]
+            name = "numpy"
+            version = "2.0.0"
+            source = { registry = "https://pypi.org/simple" }
+            "#,
+        )
+        .unwrap();
+
+        let pin = |lock: &PythonProjectLock, requirement: &str| {
+            lock.pin(&pep508_rs::Requirement::from_str(requirement).unwrap())
+                .to_string()
+        };
+        assert_eq!(
+            pin(&poetry_lock, "requests[socks]>=2.31"),
+            "requests[socks]==2.31.0"
+        );
+        assert_eq!(pin(&poetry_lock, "numpy"), "numpy==1.26.4");
+        assert_eq!(pin(&poetry_lock, "rich>=13"), "rich>=13");
+        assert_eq!(
+            pin(&poetry_lock, "requests @ https://example.com/requests.whl"),
+            "requests @ https://example.com/requests.whl"
+        );
+        // Packages that are locked at several versions are not pinned
+        assert_eq!(pin(&uv_lock, "numpy>=1.24"), "numpy>=1.24");
+
+        assert!(PythonProjectLock::is_lock_file(Path::new("a/uv.lock")));
+        assert!(PythonProjectLock::is_lock_file(Path::new("poetry.lock")));
+        assert!(!PythonProjectLock::is_lock_file(Path::new("pixi.lock")));
+    }
+}
@@ -16,13 +16,18 @@ use std::collections::HashMap;
 
 use pixi_config::Config;
 
+use crate::bandwidth::BandwidthMiddleware;
+
 /// The default retry policy employed by pixi.
 /// TODO: At some point we might want to make this configurable.
 pub fn default_retry_policy() -> ExponentialBackoff {
     ExponentialBackoff::builder().build_with_max_retries(3)
 }
 
-fn auth_middleware(config: &Config) -> Result<AuthenticationMiddleware, FileStorageError> {
+/// Returns the storage of the credentials of the channels and indexes, this is
+/// the keyring, the authentication file and the netrc file, unless the config
+/// overrides the authentication file.
+pub fn authentication_storage(config: &Config) -> Result<AuthenticationStorage, FileStorageError> {
     if let Some(auth_file) = config.authentication_override_file() {
         tracing::info!("Loading authentication from file: {:?}", auth_file);
 
@@ -35,10 +40,16 @@ fn auth_middleware(config: &Config) -> Result<AuthenticationMiddleware, FileStor
             authentication_storage::backends::file::FileStorage::new(PathBuf::from(&auth_file))?,
         ));
 
-        return Ok(AuthenticationMiddleware::new(store));
+        return Ok(store);
     }
 
-    Ok(AuthenticationMiddleware::default())
+    Ok(AuthenticationStorage::default())
+}
+
+fn auth_middleware(config: &Config) -> Result<AuthenticationMiddleware, FileStorageError> {
+    Ok(AuthenticationMiddleware::new(authentication_storage(
+        config,
+    )?))
 }
 
 pub fn mirror_middleware(config: &Config) -> MirrorMiddleware {
@@ -103,6 +114,13 @@ pub fn build_reqwest_clients(config: Option<&Config>) -> (Client, ClientWithMidd
 
     let mut client_builder = ClientBuilder::new(client.clone());
 
+    // Throttle before the mirrors are applied, so the limits of a channel
+    // also apply to its mirrors.
+    let bandwidth_middleware = BandwidthMiddleware::from_config(config.network());
+    if !bandwidth_middleware.is_empty() {
+        client_builder = client_builder.with(bandwidth_middleware);
+    }
+
     if !config.mirror_map().is_empty() {
         client_builder = client_builder
             .with(mirror_middleware(&config))
@@ -173,6 +173,26 @@ pub fn names_to_build_isolation<'a>(
     packages_to_build_isolation(names, env)
 }
 
+/// Convert the source distribution policy of the pypi-options to
+/// `uv_configuration::BuildOptions`
+pub fn pypi_options_to_build_options(
+    options: &PypiOptions,
+) -> Result<uv_configuration::BuildOptions, InvalidNameError> {
+    let no_build = if options.no_build == Some(true) {
+        uv_configuration::NoBuild::All
+    } else {
+        match isolated_names_to_packages(options.only_binary.as_deref())? {
+            Some(packages) if !packages.is_empty() => uv_configuration::NoBuild::Packages(packages),
+            _ => uv_configuration::NoBuild::None,
+        }
+    };
+    let no_binary = match isolated_names_to_packages(options.no_binary.as_deref())? {
+        Some(packages) if !packages.is_empty() => uv_configuration::NoBinary::Packages(packages),
+        _ => uv_configuration::NoBinary::None,
+    };
+    Ok(uv_configuration::BuildOptions::new(no_binary, no_build))
+}
+
 /// Convert pixi `IndexStrategy` to `uv_types::IndexStrategy`
 pub fn to_index_strategy(
     index_strategy: Option<&IndexStrategy>,
@@ -187,3 +207,50 @@ pub fn to_index_strategy(
         uv_configuration::IndexStrategy::default()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(name: &str) -> PackageName {
+        PackageName::new(name.to_string()).unwrap()
+    }
+
+    #[test]
+    fn test_pypi_options_to_build_options() {
+        // Everything may be built and installed from wheels by default
+        let options = pypi_options_to_build_options(&PypiOptions::default()).unwrap();
+        assert!(!options.no_build_package(&package("foo")));
+        assert!(!options.no_binary_package(&package("foo")));
+
+        // `no-build` disables building all packages, even with `only-binary`
+        let options = pypi_options_to_build_options(&PypiOptions {
+            no_build: Some(true),
+            only_binary: Some(vec!["foo".to_string()]),
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(options.no_build_package(&package("foo")));
+        assert!(options.no_build_package(&package("bar")));
+
+        // `only-binary` and `no-binary` only apply to the given packages
+        let options = pypi_options_to_build_options(&PypiOptions {
+            no_build: Some(false),
+            only_binary: Some(vec!["foo".to_string()]),
+            no_binary: Some(vec!["bar".to_string()]),
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(options.no_build_package(&package("foo")));
+        assert!(!options.no_build_package(&package("bar")));
+        assert!(options.no_binary_package(&package("bar")));
+        assert!(!options.no_binary_package(&package("foo")));
+
+        // Invalid package names are rejected
+        assert!(pypi_options_to_build_options(&PypiOptions {
+            only_binary: Some(vec!["not a package".to_string()]),
+            ..Default::default()
+        })
+        .is_err());
+    }
+}
@@ -5,7 +5,7 @@ use std::{
 
 // use pep440_rs::VersionSpecifiers;
 use pixi_manifest::{
-    pypi::{pypi_requirement::ParsedGitUrl, GitRev, VersionOrStar},
+    pypi::{pypi_options::TorchOptions, pypi_requirement::ParsedGitUrl, GitRev, VersionOrStar},
     PyPiRequirement,
 };
 use thiserror::Error;
@@ -83,7 +83,7 @@ pub fn as_uv_req(
     let name = PackageName::new(name.to_owned())?;
     let source = match req {
         PyPiRequirement::Version { version, index, .. } => {
-            // TODO: implement index later
+            // uv only looks up a package with a pinned index on that index
             RequirementSource::Registry {
                 specifier: to_version_specificers(version)?,
                 index: index.clone(),
@@ -193,3 +193,23 @@ pub fn as_uv_req(
         origin: None,
     })
 }
+
+/// Creates the constraints that pin the PyTorch packages to the index of the
+/// PyTorch wheels. The constraints also apply when a PyTorch package is only a
+/// transitive dependency.
+pub fn torch_constraints(index: &Url) -> Vec<uv_pypi_types::Requirement> {
+    TorchOptions::PACKAGES
+        .iter()
+        .map(|name| uv_pypi_types::Requirement {
+            name: PackageName::new(name.to_string()).expect("valid package name"),
+            extras: vec![],
+            marker: Default::default(),
+            source: RequirementSource::Registry {
+                specifier: VersionSpecifiers::from_iter(vec![]),
+                index: Some(index.clone()),
+                conflict: None,
+            },
+            origin: None,
+        })
+        .collect()
+}
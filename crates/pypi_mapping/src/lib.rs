@@ -8,9 +8,9 @@ use std::{
 use async_once_cell::OnceCell as AsyncCell;
 use custom_pypi_mapping::fetch_mapping_from_path;
 use http_cache_reqwest::{CACacheManager, Cache, CacheMode, HttpCache, HttpCacheOptions};
-use pixi_config::get_cache_dir;
-use rattler_conda_types::{PackageRecord, PackageUrl, RepoDataRecord};
-use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
+use pixi_config::{get_cache_dir, HttpCacheBackend};
+use rattler_conda_types::{PackageName, PackageRecord, PackageUrl, RepoDataRecord};
+use reqwest_middleware::{ClientBuilder, ClientWithMiddleware, Middleware};
 use reqwest_retry::{policies::ExponentialBackoff, RetryTransientMiddleware};
 use url::Url;
 
@@ -30,6 +30,10 @@ pub type ChannelName = String;
 pub type MappingMap = HashMap<ChannelName, MappingLocation>;
 pub type MappingByChannel = HashMap<String, HashMap<String, Option<String>>>;
 
+/// The PyPI packages that conda packages provide, as defined by the project.
+/// `None` if the conda package doesn't provide a PyPI package.
+pub type MappingOverrides = HashMap<PackageName, Option<pep508_rs::PackageName>>;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum MappingLocation {
     Path(PathBuf),
@@ -135,8 +139,28 @@ impl PurlSource {
     }
 }
 
+/// Creates the middleware of the given HTTP cache backend that stores the
+/// responses of a client in its own directory of the HTTP cache, or `None` if
+/// the backend doesn't cache responses.
+fn http_cache_middleware(backend: HttpCacheBackend, client: &str) -> Option<Arc<dyn Middleware>> {
+    match backend {
+        HttpCacheBackend::Cacache => Some(Arc::new(Cache(HttpCache {
+            mode: CacheMode::Default,
+            manager: CACacheManager {
+                path: get_cache_dir()
+                    .expect("missing cache directory")
+                    .join(pixi_consts::consts::HTTP_CACHE_DIR)
+                    .join(client),
+            },
+            options: HttpCacheOptions::default(),
+        }))),
+        HttpCacheBackend::Disabled => None,
+    }
+}
+
 pub async fn amend_pypi_purls(
     client: ClientWithMiddleware,
+    http_cache: HttpCacheBackend,
     mapping_source: &MappingSource,
     conda_packages: impl IntoIterator<Item = &mut RepoDataRecord>,
     reporter: Option<Arc<dyn Reporter>>,
@@ -144,20 +168,15 @@ pub async fn amend_pypi_purls(
     // Construct a client with a retry policy and local caching
     let retry_policy = ExponentialBackoff::builder().build_with_max_retries(3);
     let retry_strategy = RetryTransientMiddleware::new_with_policy(retry_policy);
-    let cache_strategy = Cache(HttpCache {
-        mode: CacheMode::Default,
-        manager: CACacheManager {
-            path: get_cache_dir()
-                .expect("missing cache directory")
-                .join(pixi_consts::consts::CONDA_PYPI_MAPPING_CACHE_DIR),
-        },
-        options: HttpCacheOptions::default(),
-    });
-
-    let client = ClientBuilder::from_client(client)
-        .with(cache_strategy)
-        .with(retry_strategy)
-        .build();
+
+    let mut client = ClientBuilder::from_client(client);
+    if let Some(cache) = http_cache_middleware(
+        http_cache,
+        pixi_consts::consts::CONDA_PYPI_MAPPING_CACHE_DIR,
+    ) {
+        client = client.with_arc(cache);
+    }
+    let client = client.with(retry_strategy).build();
 
     match mapping_source {
         MappingSource::Custom(mapping) => {
@@ -183,6 +202,35 @@ pub async fn amend_pypi_purls(
     Ok(())
 }
 
+/// Replaces the pypi purls of the records that are overridden by the project
+/// with the PyPI package of the override. Returns the records that are not
+/// overridden, these still have to be amended from the mapping source.
+pub fn apply_mapping_overrides<'r>(
+    overrides: &MappingOverrides,
+    conda_packages: impl IntoIterator<Item = &'r mut RepoDataRecord>,
+) -> Vec<&'r mut RepoDataRecord> {
+    let mut not_overridden = Vec::new();
+    for record in conda_packages {
+        let Some(pypi_name) = overrides.get(&record.package_record.name) else {
+            not_overridden.push(record);
+            continue;
+        };
+
+        let purls = record
+            .package_record
+            .purls
+            .get_or_insert_with(BTreeSet::new);
+        purls.retain(|purl| purl.package_type() != "pypi");
+        if let Some(name) = pypi_name {
+            let purl = PackageUrl::builder(String::from("pypi"), name.to_string())
+                .with_qualifier("source", PurlSource::ProjectDefinedMapping.as_str())
+                .expect("valid qualifier");
+            purls.insert(purl.build().expect("valid pypi package url"));
+        }
+    }
+    not_overridden
+}
+
 /// Returns `true` if the specified record refers to a conda-forge package.
 pub fn is_conda_forge_record(record: &RepoDataRecord) -> bool {
     record
@@ -1,11 +1,14 @@
 use miette::IntoDiagnostic;
+use pixi_manifest::pypi::pypi_options::MarkerEnvironmentOverrides;
 use rattler_conda_types::{PackageRecord, Platform};
 use uv_pep508::{MarkerEnvironment, MarkerEnvironmentBuilder};
 
 /// Determine the available env markers based on the platform and python package.
+/// The markers in `overrides` replace the markers that are derived from them.
 pub fn determine_marker_environment(
     platform: Platform,
     python_record: &PackageRecord,
+    overrides: Option<&MarkerEnvironmentOverrides>,
 ) -> miette::Result<MarkerEnvironment> {
     // Determine system specific information
     let (sys_platform, platform_system, os_name) = if platform.is_linux() {
@@ -48,30 +51,55 @@ pub fn determine_marker_environment(
         _ => "",
     };
 
+    let python_full_version = python_record.version.as_str();
+    let python_version = python_record
+        .version
+        .version()
+        .as_major_minor()
+        .map(|(major, minor)| format!("{major}.{minor}"))
+        .ok_or_else(|| {
+            miette::miette!(
+                "could not convert python version {}, to a major minor version",
+                &python_record.version
+            )
+        })?;
+
+    let overrides = overrides.cloned().unwrap_or_default();
     MarkerEnvironment::try_from(MarkerEnvironmentBuilder {
-        implementation_name,
-        implementation_version: python_record.version.as_str().as_ref(),
-        os_name,
-        platform_python_implementation,
-        platform_system,
-        python_full_version: python_record.version.as_str().as_ref(),
-        python_version: &python_record
-            .version
-            .version()
-            .as_major_minor()
-            .map(|(major, minor)| format!("{major}.{minor}"))
-            .ok_or_else(|| {
-                miette::miette!(
-                    "could not convert python version {}, to a major minor version",
-                    &python_record.version
-                )
-            })?,
-        sys_platform,
-        platform_machine,
+        implementation_name: overrides
+            .implementation_name
+            .as_deref()
+            .unwrap_or(implementation_name),
+        implementation_version: overrides
+            .implementation_version
+            .as_deref()
+            .unwrap_or(&python_full_version),
+        os_name: overrides.os_name.as_deref().unwrap_or(os_name),
+        platform_python_implementation: overrides
+            .platform_python_implementation
+            .as_deref()
+            .unwrap_or(platform_python_implementation),
+        platform_system: overrides
+            .platform_system
+            .as_deref()
+            .unwrap_or(platform_system),
+        python_full_version: overrides
+            .python_full_version
+            .as_deref()
+            .unwrap_or(&python_full_version),
+        python_version: overrides
+            .python_version
+            .as_deref()
+            .unwrap_or(&python_version),
+        sys_platform: overrides.sys_platform.as_deref().unwrap_or(sys_platform),
+        platform_machine: overrides
+            .platform_machine
+            .as_deref()
+            .unwrap_or(platform_machine),
 
         // I assume we can leave these empty
-        platform_release: "",
-        platform_version: "",
+        platform_release: overrides.platform_release.as_deref().unwrap_or(""),
+        platform_version: overrides.platform_version.as_deref().unwrap_or(""),
     })
     .into_diagnostic()
 }
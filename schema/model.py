@@ -14,6 +14,7 @@ from pydantic import (
     BaseModel,
     Field,
     PositiveFloat,
+    PositiveInt,
     StringConstraints,
 )
 
@@ -27,6 +28,7 @@ SCHEMA_URI = f"https://pixi.sh/v{VERSION}/schema/manifest/schema.json"
 NonEmptyStr = Annotated[str, StringConstraints(min_length=1)]
 Md5Sum = Annotated[str, StringConstraints(pattern=r"^[a-fA-F0-9]{32}$")]
 Sha256Sum = Annotated[str, StringConstraints(pattern=r"^[a-fA-F0-9]{64}$")]
+Blake2bSum = Annotated[str, StringConstraints(pattern=r"^[a-fA-F0-9]{64}$")]
 PathNoBackslash = Annotated[str, StringConstraints(pattern=r"^[^\\]+$")]
 Glob = NonEmptyStr
 UnsignedInt = Annotated[int, Field(strict=True, ge=0)]
@@ -130,6 +132,19 @@ class Workspace(StrictBaseModel):
         "- 'strict': only take the package from the channel it exist in first."
         "- 'disabled': group all dependencies together as if there is no channel difference.",
     )
+    exclude_newer: NonEmptyStr | None = Field(
+        None,
+        examples=["2024-06-01", "2024-06-01T12:00:00Z"],
+        description="Ignore the packages that were published after this date or RFC 3339 timestamp when solving",
+    )
+    per_environment_lock_files: bool | None = Field(
+        None,
+        description="Store the lock file of every environment in a separate file in `.pixi/locks/<environment>.lock` instead of a single `pixi.lock`",
+    )
+    allow_external_paths: bool | None = Field(
+        None,
+        description="Allow path dependencies that point outside of the workspace",
+    )
     platforms: list[Platform] = Field(description="The platforms that the project supports")
     license: NonEmptyStr | None = Field(
         None,
@@ -160,6 +175,16 @@ class Workspace(StrictBaseModel):
     build_variants: dict[NonEmptyStr, list[str]] | None = Field(
         None, description="The build variants of the project"
     )
+    build_variants_zip_keys: list[list[NonEmptyStr]] | None = Field(
+        None,
+        description="Groups of build variant keys whose values are combined pairwise instead of with every value of the other keys",
+        examples=[[["python", "numpy"]]],
+    )
+    variant_config: list[NonEmptyStr] | None = Field(
+        None,
+        description="Variant config files whose build variants are merged with `build-variants`, either a path relative to the workspace root or the name of a package that contains a `conda_build_config.yaml`",
+        examples=[["./variants.yaml", "conda-forge-pinning"]],
+    )
 
 
 ########################
@@ -167,6 +192,9 @@ class Workspace(StrictBaseModel):
 ########################
 
 
+CondaPackageName = NonEmptyStr
+
+
 class MatchspecTable(StrictBaseModel):
     """A precise description of a `conda` package version."""
 
@@ -194,16 +222,51 @@ class MatchspecTable(StrictBaseModel):
     url: NonEmptyStr | None = Field(None, description="The URL to the package")
     md5: Md5Sum | None = Field(None, description="The md5 hash of the package")
     sha256: Sha256Sum | None = Field(None, description="The sha256 hash of the package")
+    blake2b: Blake2bSum | None = Field(
+        None, description="The blake2b hash of the source archive at `url`"
+    )
+    mirrors: list[NonEmptyStr] | None = Field(
+        None,
+        description="Alternative URLs of the source archive at `url`, tried in order when downloading from `url` fails",
+    )
 
     git: NonEmptyStr | None = Field(None, description="The git URL to the repo")
+    feedstock: NonEmptyStr | None = Field(
+        None,
+        description="A feedstock repository on GitHub, a shorthand for a `git` dependency on its `recipe` directory. Only the name of a package refers to its conda-forge feedstock",
+        examples=["conda-forge/numpy-feedstock", "numpy"],
+    )
     rev: NonEmptyStr | None = Field(None, description="A git SHA revision to use")
     tag: NonEmptyStr | None = Field(None, description="A git tag to use")
     branch: NonEmptyStr | None = Field(None, description="A git branch to use")
     subdirectory: NonEmptyStr | None = Field(None, description="A subdirectory to use in the repo")
+    depth: PositiveInt | None = Field(
+        None,
+        description="Only fetch this many commits of history of the `git` repository",
+        examples=[1],
+    )
+    submodules: bool | None = Field(
+        None,
+        description="Whether the submodules of the `git` repository are checked out, defaults to `true`",
+    )
+    lfs: bool | None = Field(
+        None,
+        description="Whether the Git LFS files of the `git` repository are fetched, defaults to `false`",
+    )
+
+    ignore_run_exports: list[CondaPackageName] | None = Field(
+        None,
+        description="The names of the run exports to ignore when resolving the dependencies of a `git` or `path` source package",
+        examples=[["libzlib"]],
+    )
+    ignore_run_exports_from: list[CondaPackageName] | None = Field(
+        None,
+        description="The packages of which the run exports are ignored when resolving the dependencies of a `git` or `path` source package",
+        examples=[["zlib"]],
+    )
 
 
 MatchSpec = NonEmptyStr | MatchspecTable
-CondaPackageName = NonEmptyStr
 
 
 class _PyPIRequirement(StrictBaseModel):
@@ -276,6 +339,7 @@ PyPIRequirement = (
     | PyPIUrlRequirement
 )
 PyPIPackageName = NonEmptyStr
+RPackageName = NonEmptyStr
 
 DependenciesField = Field(
     None,
@@ -373,6 +437,32 @@ class SystemRequirements(StrictBaseModel):
     )
 
 
+#####################
+# Package overrides #
+#####################
+class PackageVersionOverride(StrictBaseModel):
+    version: NonEmptyStr = Field(
+        description="The version specifier every requirement on the package is replaced with",
+        examples=["<2"],
+    )
+
+
+class PackageReplaceOverride(StrictBaseModel):
+    replace_with: NonEmptyStr = Field(
+        description="The package that is used instead of the overridden package",
+        examples=["pillow-simd"],
+    )
+
+
+class PackageExcludeOverride(StrictBaseModel):
+    exclude: Literal[True] = Field(description="Exclude the package from the solve")
+
+
+PackageOverride = (
+    NonEmptyStr | PackageVersionOverride | PackageReplaceOverride | PackageExcludeOverride
+)
+
+
 #######################
 # Environment section #
 #######################
@@ -397,6 +487,29 @@ class Environment(StrictBaseModel):
     )
 
 
+#############################
+# External managers section #
+#############################
+class ExternalManager(StrictBaseModel):
+    """A package manager of another ecosystem that installs packages into the environments"""
+
+    cmd: NonEmptyStr = Field(
+        description="The command that installs the packages, it runs in the activated environment",
+        examples=["npm ci"],
+    )
+    inputs: list[Glob] | None = Field(
+        None,
+        description="Glob patterns of the files the installation is based on, the command only runs again when these change",
+        examples=[["package.json", "package-lock.json"]],
+    )
+    cwd: NonEmptyStr | None = Field(
+        None, description="The working directory of the command, relative to the workspace root"
+    )
+    environments: list[EnvironmentName] | None = Field(
+        None, description="The environments the command runs in, every environment if not set"
+    )
+
+
 ######################
 # Activation section #
 ######################
@@ -430,6 +543,9 @@ class Target(StrictBaseModel):
     pypi_dependencies: dict[PyPIPackageName, PyPIRequirement] | None = Field(
         None, description="The PyPI dependencies for this target"
     )
+    r_dependencies: dict[RPackageName, MatchSpec] | None = Field(
+        None, description="The R packages from CRAN for this target"
+    )
     tasks: dict[TaskName, TaskInlineTable | NonEmptyStr] | None = Field(
         None, description="The tasks of the target"
     )
@@ -465,6 +581,9 @@ class Feature(StrictBaseModel):
     pypi_dependencies: dict[PyPIPackageName, PyPIRequirement] | None = Field(
         None, description="The PyPI dependencies of this feature"
     )
+    r_dependencies: dict[RPackageName, MatchSpec] | None = Field(
+        None, description="The R packages from CRAN of this feature"
+    )
     tasks: dict[TaskName, TaskInlineTable | NonEmptyStr] | None = Field(
         None, description="The tasks provided by this feature"
     )
@@ -507,6 +626,43 @@ class FindLinksURL(StrictBaseModel):
     )
 
 
+class MarkerEnvironmentOverrides(StrictBaseModel):
+    """Overrides of the PEP 508 environment markers with which the PyPI dependencies of a platform are resolved"""
+
+    class Config:
+        extra = "forbid"
+        alias_generator = None
+
+    implementation_name: NonEmptyStr | None = Field(None, examples=["cpython"])
+    implementation_version: NonEmptyStr | None = Field(None, examples=["3.12.0"])
+    os_name: NonEmptyStr | None = Field(None, examples=["posix"])
+    platform_machine: NonEmptyStr | None = Field(None, examples=["aarch64"])
+    platform_python_implementation: NonEmptyStr | None = Field(None, examples=["CPython"])
+    platform_release: str | None = Field(None, examples=["5.15.0"])
+    platform_system: NonEmptyStr | None = Field(None, examples=["Linux"])
+    platform_version: str | None = Field(None)
+    python_full_version: NonEmptyStr | None = Field(None, examples=["3.12.0"])
+    python_version: NonEmptyStr | None = Field(None, examples=["3.12"])
+    sys_platform: NonEmptyStr | None = Field(None, examples=["linux"])
+
+
+class TorchOptions(StrictBaseModel):
+    """The index from which the PyTorch packages are installed"""
+
+    backend: Literal["cpu", "cu118", "cu121", "cu124", "rocm"] = Field(
+        description="The accelerator for which the PyTorch wheels are built",
+        examples=["cu124"],
+    )
+
+
+OwnershipPolicy = Annotated[
+    Literal["prefer-conda", "prefer-pypi", "error"],
+    Field(
+        description="The installer that owns a package that is provided by both conda and PyPI",
+    ),
+]
+
+
 class PyPIOptions(StrictBaseModel):
     """Options that determine the behavior of PyPI package resolution and installation"""
 
@@ -537,6 +693,39 @@ class PyPIOptions(StrictBaseModel):
         description="The strategy to use when resolving packages from multiple indexes",
         examples=["first-index", "unsafe-first-match", "unsafe-best-match"],
     )
+    no_build: bool | None = Field(
+        None,
+        description="Don't build source distributions, only install PyPI packages from wheels",
+    )
+    only_binary: list[PyPIPackageName] | None = Field(
+        None,
+        description="Packages that are only installed from wheels, and are never built from source",
+        examples=[["numpy"]],
+    )
+    no_binary: list[PyPIPackageName] | None = Field(
+        None,
+        description="Packages that are always built from source, their wheels are never used",
+        examples=[["pyyaml"]],
+    )
+    marker_environment: dict[Platform, MarkerEnvironmentOverrides] | None = Field(
+        None,
+        description="Overrides of the environment markers with which the PyPI dependencies of a platform are resolved",
+        examples=[{"linux-aarch64": {"platform_machine": "aarch64"}}],
+    )
+    torch: TorchOptions | None = Field(
+        None,
+        description="Install the PyTorch packages from the index of an accelerator",
+    )
+    ownership_policy: OwnershipPolicy | None = Field(
+        None,
+        description="The installer that owns the packages that are provided by both conda and PyPI",
+        examples=["prefer-conda", "prefer-pypi", "error"],
+    )
+    package_ownership_policy: dict[PyPIPackageName, OwnershipPolicy] | None = Field(
+        None,
+        description="The ownership policy of specific packages, which takes precedence over the `ownership-policy`",
+        examples=[{"numpy": "prefer-pypi"}],
+    )
 
 
 #######################
@@ -587,6 +776,21 @@ class Package(StrictBaseModel):
         examples=[{"linux": {"host-dependencies": {"python": "3.8"}}}],
     )
 
+    tests: PackageTests | None = Field(
+        None, description="The tests that `pixi build --test` runs against the built package"
+    )
+
+
+class PackageTests(StrictBaseModel):
+    commands: list[NonEmptyStr] | None = Field(
+        None,
+        description="The commands to run in the test environment, all of them have to succeed",
+        examples=[["python -c 'import foo'", "pytest tests"]],
+    )
+    dependencies: Dependencies = Field(
+        None, description="The dependencies of the test environment, besides the package itself"
+    )
+
 
 class Build(StrictBaseModel):
     backend: BuildBackend = Field(..., description="The build backend to instantiate")
@@ -641,6 +845,9 @@ class BaseManifest(StrictBaseModel):
     pypi_dependencies: dict[PyPIPackageName, PyPIRequirement] | None = Field(
         None, description="The PyPI dependencies"
     )
+    r_dependencies: dict[RPackageName, MatchSpec] | None = Field(
+        None, description="The R packages from CRAN, installed from the conda packages that provide them"
+    )
     pypi_options: PyPIOptions | None = Field(None, description="Options related to PyPI indexes")
     tasks: dict[TaskName, TaskInlineTable | NonEmptyStr] | None = Field(
         None, description="The tasks of the project"
@@ -648,6 +855,29 @@ class BaseManifest(StrictBaseModel):
     system_requirements: SystemRequirements | None = Field(
         None, description="The system requirements of the project"
     )
+    package_overrides: dict[CondaPackageName, PackageOverride] | None = Field(
+        None,
+        description="Overrides of conda packages that apply to every requirement on them, including transitive ones",
+        examples=[{"numpy": "<2", "pillow": {"replace-with": "pillow-simd"}}],
+    )
+    pypi_package_overrides: dict[PyPIPackageName, PackageOverride] | None = Field(
+        None,
+        description="Overrides of PyPI packages that apply to every requirement on them, including transitive ones",
+    )
+    pypi_conda_mapping: dict[CondaPackageName, PyPIPackageName | Literal[False]] | None = Field(
+        None,
+        description="The PyPI packages that conda packages provide, or `false` if a conda package doesn't provide one",
+        examples=[{"pytorch": "torch", "jupyter-ros": False}],
+    )
+    r_mapping: dict[RPackageName, CondaPackageName] | None = Field(
+        None,
+        description="The conda packages that provide R packages which don't follow the `r-<name>` naming convention of conda-forge",
+        examples=[{"DESeq2": "bioconductor-deseq2"}],
+    )
+    external_managers: dict[NonEmptyStr, ExternalManager] | None = Field(
+        None,
+        description="The package managers of other ecosystems that run in the environments after they are installed",
+    )
     environments: dict[EnvironmentName, Environment | list[FeatureName]] | None = Field(
         None,
         description="The environments of the project, defined as a full object or a list of feature names.",
@@ -15,9 +15,12 @@ use rattler_shell::{
     },
     shell::ShellEnum,
 };
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
+use std::sync::LazyLock;
+use tracing::instrument;
 
 // Setting a base prefix for the pixi package
 const PROJECT_PREFIX: &str = "PIXI_PROJECT_";
@@ -167,6 +170,62 @@ fn get_environment_variable_from_shell_environment(
         .collect()
 }
 
+/// Returns the values of the shell environment variables that the activation
+/// depends on: the variables it sets, and the variables that are referenced
+/// by the activation scripts of the manifest and of the installed packages.
+fn get_activation_input_variables<'a>(
+    environment: &Environment<'_>,
+    set_variables: impl Iterator<Item = &'a str>,
+) -> HashMap<String, Option<String>> {
+    let referenced = referenced_environment_variables(environment);
+    get_environment_variable_from_shell_environment(
+        set_variables
+            .chain(referenced.iter().map(String::as_str))
+            .unique()
+            .collect(),
+    )
+}
+
+/// Returns the names of the environment variables that the activation scripts
+/// of the environment refer to, like `$CUDA_HOME`, `${CUDA_HOME}`,
+/// `%CUDA_HOME%` or `$env:CUDA_HOME`.
+fn referenced_environment_variables(environment: &Environment<'_>) -> Vec<String> {
+    static VARIABLE: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(r"\$env:([A-Za-z_][A-Za-z0-9_]*)|\$\{?([A-Za-z_][A-Za-z0-9_]*)|%([A-Za-z_][A-Za-z0-9_]*)%")
+            .expect("the regex is valid")
+    });
+
+    let project_root = environment.project().root();
+    let manifest_scripts = environment
+        .activation_scripts(Some(environment.best_platform()))
+        .into_iter()
+        .map(|script| project_root.join(script));
+    let package_scripts = fs_err::read_dir(environment.dir().join("etc/conda/activate.d"))
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()));
+
+    manifest_scripts
+        .chain(package_scripts)
+        .filter_map(|path| fs_err::read_to_string(path).ok())
+        .flat_map(|contents| {
+            VARIABLE
+                .captures_iter(&contents)
+                .filter_map(|captures| {
+                    captures
+                        .iter()
+                        .skip(1)
+                        .flatten()
+                        .next()
+                        .map(|name| name.as_str().to_string())
+                })
+                .collect_vec()
+        })
+        .sorted()
+        .dedup()
+        .collect()
+}
+
 /// Try to get the activation cache from the cache file.
 /// If it can get the cache, it will validate it with the lock file and the current environment.
 /// If the cache is valid, it will return the environment variables from the cache.
@@ -198,13 +257,11 @@ async fn try_get_valid_activation_cache(
         }
     };
 
-    // Get the current environment variables
-    let current_input_env_vars = get_environment_variable_from_shell_environment(
-        cache
-            .environment_variables
-            .keys()
-            .map(String::as_str)
-            .collect(),
+    // Get the current values of the environment variables the activation
+    // depends on
+    let current_input_env_vars = get_activation_input_variables(
+        environment,
+        cache.environment_variables.keys().map(String::as_str),
     );
 
     // Hash the current state
@@ -219,15 +276,16 @@ async fn try_get_valid_activation_cache(
 }
 
 /// Runs and caches the activation script.
+#[instrument(name = "activation", skip_all, fields(environment = %environment.name().as_str()))]
 pub async fn run_activation(
     environment: &Environment<'_>,
     env_var_behavior: &CurrentEnvVarBehavior,
     lock_file: Option<&LockFile>,
     force_activate: bool,
-    experimental: bool,
+    use_cache: bool,
 ) -> miette::Result<HashMap<String, String>> {
     // If the user requested to use the cache and the lockfile is provided, we can try to use the cache.
-    if !force_activate && experimental {
+    if !force_activate && use_cache {
         let cache_file = environment
             .project()
             .activation_env_cache_folder()
@@ -313,11 +371,12 @@ pub async fn run_activation(
 
     // If the lock file is provided, and we can compute the environment hash, let's rewrite the
     // cache file.
-    if experimental {
+    if use_cache {
         if let Some(lock_file) = lock_file {
             // Get the current environment variables from the shell to be part of the hash
-            let current_input_env_vars = get_environment_variable_from_shell_environment(
-                activator_result.keys().map(String::as_str).collect(),
+            let current_input_env_vars = get_activation_input_variables(
+                environment,
+                activator_result.keys().map(String::as_str),
             );
             let cache_file = environment.activation_cache_file_path();
             let cache = ActivationCache {
@@ -427,14 +486,14 @@ pub(crate) async fn initialize_env_variables(
     env_var_behavior: CurrentEnvVarBehavior,
     lock_file: Option<&LockFile>,
     force_activate: bool,
-    experimental: bool,
+    use_cache: bool,
 ) -> miette::Result<HashMap<String, String>> {
     let activation_env = run_activation(
         environment,
         &env_var_behavior,
         lock_file,
         force_activate,
-        experimental,
+        use_cache,
     )
     .await?;
 
@@ -754,6 +813,135 @@ packages:
         );
     }
 
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_run_activation_cache_based_on_script_content() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let project = r#"
+        [project]
+        name = "pixi"
+        channels = []
+        platforms = []
+
+        [activation]
+        scripts = ["activate.sh"]
+        "#;
+        let project =
+            Project::from_str(temp_dir.path().join("pixi.toml").as_path(), project).unwrap();
+        let default_env = project.default_environment();
+        let script = temp_dir.path().join("activate.sh");
+
+        tokio_fs::write(&script, "export TEST=ACTIVATION123\n")
+            .await
+            .unwrap();
+        let env = run_activation(
+            &default_env,
+            &CurrentEnvVarBehavior::Include,
+            Some(&LockFile::default()),
+            false,
+            true,
+        )
+        .await
+        .unwrap();
+        assert_eq!(env.get("TEST").unwrap(), "ACTIVATION123");
+
+        // Editing the script invalidates the cache.
+        tokio_fs::write(&script, "export TEST=ACTIVATION456\n")
+            .await
+            .unwrap();
+        let env = run_activation(
+            &default_env,
+            &CurrentEnvVarBehavior::Include,
+            Some(&LockFile::default()),
+            false,
+            true,
+        )
+        .await
+        .unwrap();
+        assert_eq!(env.get("TEST").unwrap(), "ACTIVATION456");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_run_activation_cache_based_on_read_variables() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let project = r#"
+        [project]
+        name = "pixi"
+        channels = []
+        platforms = []
+
+        [activation]
+        scripts = ["activate.sh"]
+        "#;
+        let project =
+            Project::from_str(temp_dir.path().join("pixi.toml").as_path(), project).unwrap();
+        let default_env = project.default_environment();
+        tokio_fs::write(
+            temp_dir.path().join("activate.sh"),
+            "export TEST=\"from-${PIXI_TEST_ACTIVATION_INPUT}\"\n",
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            referenced_environment_variables(&default_env),
+            ["PIXI_TEST_ACTIVATION_INPUT"]
+        );
+
+        let activate = || {
+            run_activation(
+                &default_env,
+                &CurrentEnvVarBehavior::Include,
+                Some(&LockFile::default()),
+                false,
+                true,
+            )
+        };
+        std::env::set_var("PIXI_TEST_ACTIVATION_INPUT", "first");
+        let env = activate().await.unwrap();
+        assert_eq!(env.get("TEST").unwrap(), "from-first");
+
+        // A variable that the script only reads invalidates the cache too.
+        std::env::set_var("PIXI_TEST_ACTIVATION_INPUT", "second");
+        let env = activate().await.unwrap();
+        assert_eq!(env.get("TEST").unwrap(), "from-second");
+        std::env::remove_var("PIXI_TEST_ACTIVATION_INPUT");
+    }
+
+    #[test]
+    fn test_referenced_environment_variables() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let project = r#"
+        [project]
+        name = "pixi"
+        channels = []
+        platforms = []
+
+        [activation]
+        scripts = ["activate.sh", "activate.bat", "activate.ps1"]
+        "#;
+        let project =
+            Project::from_str(temp_dir.path().join("pixi.toml").as_path(), project).unwrap();
+        for (script, contents) in [
+            ("activate.sh", "export A=\"$CUDA_HOME/bin:${LD_PATH}\""),
+            ("activate.bat", "set A=%CUDA_HOME%\\bin"),
+            ("activate.ps1", "$env:PS_VAR = $env:OTHER_VAR"),
+        ] {
+            fs_err::write(temp_dir.path().join(script), contents).unwrap();
+        }
+        let activate_d = project
+            .default_environment()
+            .dir()
+            .join("etc/conda/activate.d");
+        fs_err::create_dir_all(&activate_d).unwrap();
+        fs_err::write(activate_d.join("package.sh"), "export B=$PACKAGE_VAR").unwrap();
+
+        assert_eq!(
+            referenced_environment_variables(&project.default_environment()),
+            ["CUDA_HOME", "LD_PATH", "OTHER_VAR", "PACKAGE_VAR", "PS_VAR"]
+        );
+    }
+
     // This test works, most of the times.., so this is a good test to run locally.
     // But it is to flaky for CI unfortunately!
     // #[tokio::test]
@@ -1,4 +1,5 @@
 use std::{
+    collections::BTreeMap,
     hash::{Hash, Hasher},
     io::SeekFrom,
     path::PathBuf,
@@ -8,10 +9,13 @@ use crate::{
     build::{cache::source_checkout_cache_key, SourceCheckout},
     utils::{move_file, MoveError},
 };
-use async_fd_lock::{LockWrite, RwLockWriteGuard};
 use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use pixi_spec::IgnoreRunExports;
+use pixi_utils::{lock_write_with_owner, to_extended_length, OwnedWriteGuard};
 use rattler_conda_types::{GenericVirtualPackage, Platform, RepoDataRecord};
+use rattler_digest::Sha256Hash;
 use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
 use thiserror::Error;
 use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
 use url::Url;
@@ -62,6 +66,15 @@ pub struct BuildInput {
 
     /// The virtual packages used to build the package
     pub build_virtual_packages: Vec<GenericVirtualPackage>,
+
+    /// The variants of the build
+    pub build_variants: BTreeMap<String, Vec<String>>,
+
+    /// The groups of build variant keys that are zipped together
+    pub build_variant_zip_keys: Vec<Vec<String>>,
+
+    /// The run exports that the consumer of the package ignores
+    pub ignore_run_exports: Option<IgnoreRunExports>,
 }
 
 impl BuildInput {
@@ -78,6 +91,9 @@ impl BuildInput {
             host_platform,
             host_virtual_packages,
             build_virtual_packages,
+            build_variants,
+            build_variant_zip_keys,
+            ignore_run_exports,
         } = self;
 
         // Hash some of the keys
@@ -87,6 +103,13 @@ impl BuildInput {
         host_platform.hash(&mut hasher);
         host_virtual_packages.hash(&mut hasher);
         build_virtual_packages.hash(&mut hasher);
+        build_variants.hash(&mut hasher);
+        if !build_variant_zip_keys.is_empty() {
+            build_variant_zip_keys.hash(&mut hasher);
+        }
+        if let Some(ignore_run_exports) = ignore_run_exports {
+            ignore_run_exports.hash(&mut hasher);
+        }
         let hash = URL_SAFE_NO_PAD.encode(hasher.finish().to_ne_bytes());
 
         format!("{name}-{version}-{target_platform}-{hash}",)
@@ -98,10 +121,12 @@ impl BuildCache {
     ///
     /// An additional directory is created by this cache inside the passed root
     /// which includes a version number. This is to ensure that the cache is
-    /// never corrupted if the format changes in the future.
+    /// never corrupted if the format changes in the future. On Windows the
+    /// root is accessed as an extended-length path, because the cache keys
+    /// make the paths of the cached packages long.
     pub fn new(root: PathBuf) -> Self {
         Self {
-            root: root.join("source-builds-v0"),
+            root: to_extended_length(&root).join("source-builds-v0"),
         }
     }
 
@@ -146,13 +171,15 @@ impl BuildCache {
                 )
             })?;
 
-        let mut locked_cache_file = cache_file.lock_write().await.map_err(|e| {
-            BuildCacheError::IoError(
-                "locking cache file".to_string(),
-                cache_file_path.clone(),
-                e.error,
-            )
-        })?;
+        let mut locked_cache_file = lock_write_with_owner(cache_file, &cache_file_path)
+            .await
+            .map_err(|e| {
+                BuildCacheError::IoError(
+                    "locking cache file".to_string(),
+                    cache_file_path.clone(),
+                    e.error,
+                )
+            })?;
 
         // Try to parse the contents of the file
         let mut cache_file_contents = String::new();
@@ -188,9 +215,17 @@ pub struct CachedBuild {
     pub record: RepoDataRecord,
 }
 
+#[serde_as]
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SourceInfo {
     pub globs: Vec<String>,
+
+    /// The hash of the files that match the globs when the package was built.
+    /// Builds that were cached without a hash are checked against the
+    /// modification time of the files instead.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[serde_as(as = "Option<rattler_digest::serde::SerializableHash::<rattler_digest::Sha256>>")]
+    pub hash: Option<Sha256Hash>,
 }
 
 /// A cache entry returned by [`BuildCache::entry`] which enables
@@ -198,7 +233,7 @@ pub struct SourceInfo {
 ///
 /// As long as this entry is held, no other process can access this cache entry.
 pub struct CacheEntry {
-    file: RwLockWriteGuard<tokio::fs::File>,
+    file: OwnedWriteGuard,
     cache_dir: PathBuf,
     cache_file_path: PathBuf,
 }
@@ -1,4 +1,5 @@
 mod build_cache;
+mod remote_build_cache;
 mod source_metadata_cache;
 
 use std::{
@@ -8,6 +9,7 @@ use std::{
 
 use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
 pub use build_cache::{BuildCache, BuildCacheError, BuildInput, CachedBuild, SourceInfo};
+pub use remote_build_cache::RemoteBuildCache;
 pub use source_metadata_cache::{
     CachedCondaMetadata, SourceMetadataCache, SourceMetadataError, SourceMetadataInput,
 };
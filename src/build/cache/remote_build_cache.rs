@@ -0,0 +1,743 @@
+//! A build cache that is shared between machines. Packages that are built
+//! from source are uploaded to a remote store, keyed by the hash of their
+//! inputs, so other machines or CI jobs can fetch them instead of building the
+//! same package again.
+//!
+//! The remote store is any HTTP(S) server that serves files with `GET` and
+//! accepts uploads with `PUT`, or an S3 bucket given as `s3://<bucket>/<prefix>`
+//! which is accessed through its HTTPS endpoint. The layout of the store is:
+//!
+//! ```text
+//! <remote>/<source>/<build-input>/entry.json      # immutable sources
+//! <remote>/<source>/<build-input>/<package>
+//! <remote>/path/<build-input>/globs.json          # mutable sources
+//! <remote>/path/<build-input>/<input-hash>/entry.json
+//! <remote>/path/<build-input>/<input-hash>/<package>
+//! ```
+//!
+//! Mutable sources, like path dependencies, are keyed by the hash of their
+//! input files. The globs of these inputs are only known after a build, so the
+//! globs of the last uploaded build are stored next to the entries.
+
+use std::{
+    hash::{Hash, Hasher},
+    path::{Component, Path, PathBuf},
+};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use miette::Diagnostic;
+use pixi_config::BuildCacheConfig;
+use rattler_conda_types::package::IndexJson;
+use rattler_digest::{Sha256, Sha256Hash};
+use rattler_package_streaming::ExtractError;
+use reqwest::StatusCode;
+use reqwest_middleware::ClientWithMiddleware;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_with::serde_as;
+use thiserror::Error;
+use url::Url;
+use xxhash_rust::xxh3::Xxh3;
+
+use crate::build::{cache::BuildInput, SourceCheckout};
+
+/// The name of the file that describes a build in the remote cache.
+const ENTRY_FILE_NAME: &str = "entry.json";
+
+/// The name of the file that lists the input globs of the last build of a
+/// mutable source in the remote cache.
+const GLOBS_FILE_NAME: &str = "globs.json";
+
+/// A build cache that is stored on a remote server.
+#[derive(Clone)]
+pub struct RemoteBuildCache {
+    url: Url,
+    client: ClientWithMiddleware,
+    upload: bool,
+}
+
+#[derive(Debug, Error, Diagnostic)]
+pub enum RemoteBuildCacheError {
+    #[error("failed to fetch '{0}' from the remote build cache")]
+    Fetch(Url, #[source] reqwest_middleware::Error),
+
+    #[error("failed to upload '{0}' to the remote build cache")]
+    Upload(Url, #[source] reqwest_middleware::Error),
+
+    #[error("the remote build cache contains an invalid entry at '{0}'")]
+    InvalidEntry(Url, #[source] serde_json::Error),
+
+    #[error(
+        "the sha256 of '{url}' is {actual:x}, but the remote build cache expects {expected:x}"
+    )]
+    HashMismatch {
+        url: Url,
+        expected: Sha256Hash,
+        actual: Sha256Hash,
+    },
+
+    #[error("the entry at '{0}' refers to '{1}', which is not the file name of a conda package")]
+    InvalidFileName(Url, String),
+
+    #[error("failed to read the index.json of '{0}'")]
+    InvalidPackage(Url, #[source] ExtractError),
+
+    #[error("the package at '{url}' is {actual}, but the remote build cache expects {expected}")]
+    PackageMismatch {
+        url: Url,
+        expected: String,
+        actual: String,
+    },
+
+    #[error("an IO error occurred while {0} {1}")]
+    IoError(String, PathBuf, #[source] std::io::Error),
+}
+
+/// Returns true if the name is the plain file name of a conda package, which
+/// can't point outside the directory it is joined to.
+fn is_package_file_name(name: &str) -> bool {
+    let mut components = Path::new(name).components();
+    matches!(
+        (components.next(), components.next()),
+        (Some(Component::Normal(_)), None)
+    ) && !name.contains(['/', '\\'])
+        && (name.ends_with(".conda") || name.ends_with(".tar.bz2"))
+}
+
+/// Returns the HTTPS endpoint of an `s3://<bucket>/<prefix>` url, other urls
+/// are returned as is.
+fn resolve_s3_url(url: Url) -> Url {
+    if url.scheme() != "s3" {
+        return url;
+    }
+    let Some(bucket) = url.host_str() else {
+        return url;
+    };
+    let mut resolved = Url::parse(&format!("https://{bucket}.s3.amazonaws.com/"))
+        .expect("the endpoint of a bucket should be a valid url");
+    resolved.set_path(url.path());
+    resolved
+}
+
+/// Describes a built package in the remote cache.
+#[serde_as]
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct RemoteBuildEntry {
+    /// The file name of the package, which is stored next to the entry.
+    file_name: String,
+
+    /// The sha256 of the package.
+    #[serde_as(as = "rattler_digest::serde::SerializableHash::<rattler_digest::Sha256>")]
+    sha256: Sha256Hash,
+
+    /// The globs of the input files of the build.
+    globs: Vec<String>,
+}
+
+/// The location of a build in the remote cache.
+#[derive(Debug, Clone)]
+pub struct RemoteBuildKey {
+    /// The directory of the build.
+    dir: Url,
+
+    /// The file with the input globs of the last build, for mutable sources.
+    globs_url: Option<Url>,
+
+    /// The `<name>-<version>-<build>` of the package that is expected in the
+    /// directory.
+    package: String,
+}
+
+/// A package that was fetched from the remote cache.
+#[derive(Debug)]
+pub struct RemoteBuild {
+    /// The location of the downloaded package.
+    pub package_file: PathBuf,
+
+    /// The sha256 of the package.
+    pub sha256: Sha256Hash,
+
+    /// The globs of the input files of the build.
+    pub globs: Vec<String>,
+}
+
+impl RemoteBuildCache {
+    /// Constructs a new instance that stores builds under the given url.
+    pub fn new(url: Url, client: ClientWithMiddleware, upload: bool) -> Self {
+        let mut url = resolve_s3_url(url);
+        // Make sure joining paths doesn't replace the last segment of the url.
+        if !url.path().ends_with('/') {
+            url.set_path(&format!("{}/", url.path()));
+        }
+        Self {
+            url,
+            client,
+            upload,
+        }
+    }
+
+    /// Constructs a remote cache from the configuration, or `None` if no
+    /// remote cache is configured.
+    pub fn from_config(config: &BuildCacheConfig, client: ClientWithMiddleware) -> Option<Self> {
+        let url = config.remote.clone()?;
+        Some(Self::new(url, client, config.upload()))
+    }
+
+    /// Whether locally built packages should be uploaded to the remote cache.
+    pub fn upload_enabled(&self) -> bool {
+        self.upload
+    }
+
+    /// Returns the directory in the remote cache of all the builds of the
+    /// source with the given input.
+    fn input_dir(&self, source: &SourceCheckout, input: &BuildInput) -> Url {
+        let source_key = if source.pinned.is_immutable() {
+            let mut hasher = Xxh3::new();
+            source.pinned.to_string().hash(&mut hasher);
+            URL_SAFE_NO_PAD.encode(hasher.finish().to_ne_bytes())
+        } else {
+            // The location of mutable sources differs between machines, these
+            // are identified by the hash of their input files instead.
+            "path".to_string()
+        };
+        self.url
+            .join(&format!("{source_key}/{}/", input.hash_key()))
+            .expect("the cache key should be a valid url path")
+    }
+
+    /// Returns the key of a build in the remote cache. Mutable sources are
+    /// identified by the hash of their input files, so `None` is returned if
+    /// that hash is not known.
+    pub fn key(
+        &self,
+        source: &SourceCheckout,
+        input: &BuildInput,
+        input_hash: Option<Sha256Hash>,
+    ) -> Option<RemoteBuildKey> {
+        let input_dir = self.input_dir(source, input);
+        let package = format!("{}-{}-{}", input.name, input.version, input.build);
+        if source.pinned.is_immutable() {
+            return Some(RemoteBuildKey {
+                dir: input_dir,
+                globs_url: None,
+                package,
+            });
+        }
+
+        let input_hash = input_hash?;
+        Some(RemoteBuildKey {
+            dir: input_dir
+                .join(&format!("{input_hash:x}/"))
+                .expect("a hash should be a valid url path"),
+            globs_url: Some(
+                input_dir
+                    .join(GLOBS_FILE_NAME)
+                    .expect("the file name should be a valid url path"),
+            ),
+            package,
+        })
+    }
+
+    /// Returns the input globs of the last build of a mutable source that was
+    /// uploaded to the remote cache, or `None` if the source was never
+    /// uploaded.
+    pub async fn input_globs(
+        &self,
+        source: &SourceCheckout,
+        input: &BuildInput,
+    ) -> Result<Option<Vec<String>>, RemoteBuildCacheError> {
+        let url = self
+            .input_dir(source, input)
+            .join(GLOBS_FILE_NAME)
+            .expect("the file name should be a valid url path");
+        self.get_json(url).await
+    }
+
+    /// Downloads the package of the build into the given directory. Returns
+    /// `None` if the remote cache doesn't contain the build.
+    pub async fn fetch(
+        &self,
+        key: &RemoteBuildKey,
+        destination_dir: &Path,
+    ) -> Result<Option<RemoteBuild>, RemoteBuildCacheError> {
+        let entry_url = key
+            .dir
+            .join(ENTRY_FILE_NAME)
+            .expect("the file name should be a valid url path");
+        let Some(entry) = self.get_json::<RemoteBuildEntry>(entry_url.clone()).await? else {
+            return Ok(None);
+        };
+
+        // The file name is joined to the destination directory, it may not
+        // point outside of it.
+        if !is_package_file_name(&entry.file_name) {
+            return Err(RemoteBuildCacheError::InvalidFileName(
+                entry_url,
+                entry.file_name,
+            ));
+        }
+
+        let package_url = key.dir.join(&entry.file_name).map_err(|_| {
+            RemoteBuildCacheError::InvalidEntry(
+                entry_url,
+                serde::de::Error::custom("the file name is not a valid url path"),
+            )
+        })?;
+        let Some(bytes) = self.get(package_url.clone()).await? else {
+            return Ok(None);
+        };
+
+        let actual = rattler_digest::compute_bytes_digest::<Sha256>(&bytes);
+        if actual != entry.sha256 {
+            return Err(RemoteBuildCacheError::HashMismatch {
+                url: package_url,
+                expected: entry.sha256,
+                actual,
+            });
+        }
+
+        let package_file = destination_dir.join(&entry.file_name);
+        fs_err::tokio::write(&package_file, &bytes)
+            .await
+            .map_err(|e| {
+                RemoteBuildCacheError::IoError(
+                    "writing package".to_string(),
+                    package_file.clone(),
+                    e,
+                )
+            })?;
+
+        // The sha256 only proves the package is the one the entry refers to,
+        // make sure it is also the package that was requested.
+        let index_json: IndexJson =
+            rattler_package_streaming::seek::read_package_file(&package_file)
+                .map_err(|e| RemoteBuildCacheError::InvalidPackage(package_url.clone(), e))?;
+        let package = format!(
+            "{}-{}-{}",
+            index_json.name.as_normalized(),
+            index_json.version,
+            index_json.build
+        );
+        if package != key.package {
+            return Err(RemoteBuildCacheError::PackageMismatch {
+                url: package_url,
+                expected: key.package.clone(),
+                actual: package,
+            });
+        }
+
+        Ok(Some(RemoteBuild {
+            package_file,
+            sha256: actual,
+            globs: entry.globs,
+        }))
+    }
+
+    /// Uploads a locally built package to the remote cache.
+    pub async fn upload(
+        &self,
+        key: &RemoteBuildKey,
+        package_file: &Path,
+        sha256: Sha256Hash,
+        globs: &[String],
+    ) -> Result<(), RemoteBuildCacheError> {
+        let file_name = package_file
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default()
+            .to_string();
+        let bytes = fs_err::tokio::read(package_file).await.map_err(|e| {
+            RemoteBuildCacheError::IoError(
+                "reading package".to_string(),
+                package_file.to_path_buf(),
+                e,
+            )
+        })?;
+
+        // Upload the package before the entry, so an entry always points to
+        // a package that exists.
+        let package_url = key
+            .dir
+            .join(&file_name)
+            .expect("a file name should be a valid url path");
+        self.put(package_url, bytes).await?;
+
+        let entry = RemoteBuildEntry {
+            file_name,
+            sha256,
+            globs: globs.to_vec(),
+        };
+        let entry_url = key
+            .dir
+            .join(ENTRY_FILE_NAME)
+            .expect("the file name should be a valid url path");
+        self.put(
+            entry_url,
+            serde_json::to_vec(&entry).expect("serialization to JSON should not fail"),
+        )
+        .await?;
+
+        if let Some(globs_url) = &key.globs_url {
+            self.put(
+                globs_url.clone(),
+                serde_json::to_vec(globs).expect("serialization to JSON should not fail"),
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Downloads the file at the url, or returns `None` if it doesn't exist.
+    async fn get(&self, url: Url) -> Result<Option<Vec<u8>>, RemoteBuildCacheError> {
+        let response = self
+            .client
+            .get(url.clone())
+            .send()
+            .await
+            .map_err(|e| RemoteBuildCacheError::Fetch(url.clone(), e))?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let bytes = response
+            .error_for_status()
+            .map_err(|e| RemoteBuildCacheError::Fetch(url.clone(), e.into()))?
+            .bytes()
+            .await
+            .map_err(|e| RemoteBuildCacheError::Fetch(url, e.into()))?;
+        Ok(Some(bytes.to_vec()))
+    }
+
+    /// Downloads and parses the JSON file at the url, or returns `None` if it
+    /// doesn't exist.
+    async fn get_json<T: DeserializeOwned>(
+        &self,
+        url: Url,
+    ) -> Result<Option<T>, RemoteBuildCacheError> {
+        let Some(bytes) = self.get(url.clone()).await? else {
+            return Ok(None);
+        };
+        serde_json::from_slice(&bytes)
+            .map(Some)
+            .map_err(|e| RemoteBuildCacheError::InvalidEntry(url, e))
+    }
+
+    /// Uploads the bytes to the url.
+    async fn put(&self, url: Url, bytes: Vec<u8>) -> Result<(), RemoteBuildCacheError> {
+        self.client
+            .put(url.clone())
+            .body(bytes)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status().map_err(Into::into))
+            .map_err(|e| RemoteBuildCacheError::Upload(url, e))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+    };
+
+    use http::Extensions;
+    use pixi_record::{PinnedPathSpec, PinnedSourceSpec, PinnedUrlSpec};
+    use rattler_conda_types::Platform;
+    use reqwest::{Method, Request, Response};
+    use reqwest_middleware::{Middleware, Next};
+
+    use super::*;
+
+    /// A package of the test channels that is used as the built package.
+    const PACKAGE_FILE_NAME: &str = "dummy-a-0.1.0-h9490d1a_0.conda";
+
+    /// A middleware that serves the requests from memory instead of a server.
+    #[derive(Clone, Default)]
+    struct MemoryStore {
+        files: Arc<Mutex<HashMap<String, Vec<u8>>>>,
+    }
+
+    impl MemoryStore {
+        fn client(&self) -> ClientWithMiddleware {
+            reqwest_middleware::ClientBuilder::new(reqwest::Client::new())
+                .with(self.clone())
+                .build()
+        }
+
+        fn urls(&self) -> Vec<String> {
+            let mut urls: Vec<_> = self.files.lock().unwrap().keys().cloned().collect();
+            urls.sort();
+            urls
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Middleware for MemoryStore {
+        async fn handle(
+            &self,
+            req: Request,
+            _extensions: &mut Extensions,
+            _next: Next<'_>,
+        ) -> reqwest_middleware::Result<Response> {
+            let url = req.url().to_string();
+            let mut files = self.files.lock().unwrap();
+            let response = if req.method() == Method::PUT {
+                let body = req
+                    .body()
+                    .and_then(|body| body.as_bytes())
+                    .unwrap_or_default();
+                files.insert(url, body.to_vec());
+                http::Response::builder().status(200).body(Vec::new())
+            } else {
+                match files.get(&url) {
+                    Some(bytes) => http::Response::builder().status(200).body(bytes.clone()),
+                    None => http::Response::builder().status(404).body(Vec::new()),
+                }
+            };
+            Ok(Response::from(response.unwrap()))
+        }
+    }
+
+    fn package_file() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/data/channels/channels/dummy_channel_1/win-64")
+            .join(PACKAGE_FILE_NAME)
+    }
+
+    fn path_source(path: &str) -> SourceCheckout {
+        SourceCheckout {
+            path: PathBuf::from(path),
+            pinned: PinnedSourceSpec::Path(PinnedPathSpec { path: path.into() }),
+        }
+    }
+
+    fn build_input() -> BuildInput {
+        BuildInput {
+            channel_urls: vec![Url::parse("https://prefix.dev/conda-forge").unwrap()],
+            target_platform: Platform::Win64,
+            name: "dummy-a".to_string(),
+            version: "0.1.0".to_string(),
+            build: "h9490d1a_0".to_string(),
+            host_platform: Platform::Win64,
+            host_virtual_packages: Vec::new(),
+            build_virtual_packages: Vec::new(),
+            build_variants: Default::default(),
+            build_variant_zip_keys: Vec::new(),
+            ignore_run_exports: None,
+        }
+    }
+
+    #[test]
+    fn test_remote_build_key() {
+        let cache = RemoteBuildCache::new(
+            Url::parse("https://cache.example.com/builds").unwrap(),
+            ClientWithMiddleware::from(reqwest::Client::new()),
+            false,
+        );
+        let input = build_input();
+        let input_key = input.hash_key();
+
+        // A path source is identified by the hash of its input files, which
+        // doesn't depend on where the source is located.
+        assert!(cache.key(&path_source("/a/foo"), &input, None).is_none());
+        let hash = Sha256Hash::default();
+        let key = cache
+            .key(&path_source("/a/foo"), &input, Some(hash))
+            .unwrap();
+        assert_eq!(
+            key.dir.as_str(),
+            format!("https://cache.example.com/builds/path/{input_key}/{hash:x}/")
+        );
+        assert_eq!(
+            key.globs_url.unwrap().as_str(),
+            format!("https://cache.example.com/builds/path/{input_key}/globs.json")
+        );
+        assert_eq!(
+            cache
+                .key(&path_source("/b/foo"), &input, Some(hash))
+                .unwrap()
+                .dir,
+            key.dir
+        );
+
+        // An immutable source is identified by its pinned location.
+        let url_source = SourceCheckout {
+            path: PathBuf::from("/a/foo"),
+            pinned: PinnedSourceSpec::Url(PinnedUrlSpec {
+                url: Url::parse("https://example.com/foo.tar.gz").unwrap(),
+                sha256: Sha256Hash::default(),
+                md5: None,
+                blake2b: None,
+                mirrors: Vec::new(),
+            }),
+        };
+        let key = cache.key(&url_source, &input, None).unwrap();
+        assert!(key.globs_url.is_none());
+        assert!(key.dir.as_str().ends_with(&format!("/{input_key}/")));
+        assert!(!key.dir.as_str().contains("/path/"));
+    }
+
+    #[test]
+    fn test_is_package_file_name() {
+        assert!(is_package_file_name("foo-1.0.0-h123_0.conda"));
+        assert!(is_package_file_name("foo-1.0.0-h123_0.tar.bz2"));
+
+        assert!(!is_package_file_name("foo-1.0.0-h123_0.zip"));
+        assert!(!is_package_file_name(""));
+        assert!(!is_package_file_name(".."));
+        assert!(!is_package_file_name("../../foo-1.0.0-h123_0.conda"));
+        assert!(!is_package_file_name("sub/foo-1.0.0-h123_0.conda"));
+        assert!(!is_package_file_name("..\\foo-1.0.0-h123_0.conda"));
+        assert!(!is_package_file_name("/tmp/foo-1.0.0-h123_0.conda"));
+    }
+
+    #[test]
+    fn test_resolve_s3_url() {
+        let cache = RemoteBuildCache::new(
+            Url::parse("s3://my-bucket/pixi/builds").unwrap(),
+            ClientWithMiddleware::from(reqwest::Client::new()),
+            false,
+        );
+        assert_eq!(
+            cache.url.as_str(),
+            "https://my-bucket.s3.amazonaws.com/pixi/builds/"
+        );
+
+        let url = Url::parse("https://cache.example.com/builds/").unwrap();
+        assert_eq!(resolve_s3_url(url.clone()), url);
+    }
+
+    #[tokio::test]
+    async fn test_upload_and_fetch() {
+        let store = MemoryStore::default();
+        let cache = RemoteBuildCache::new(
+            Url::parse("s3://my-bucket/builds").unwrap(),
+            store.client(),
+            true,
+        );
+        let source = path_source("/a/dummy-a");
+        let input = build_input();
+        let hash = Sha256Hash::default();
+        let key = cache.key(&source, &input, Some(hash)).unwrap();
+        let sha256 = rattler_digest::compute_file_digest::<Sha256>(package_file()).unwrap();
+        let globs = vec!["**/*.py".to_string()];
+
+        assert!(cache.input_globs(&source, &input).await.unwrap().is_none());
+        let destination = tempfile::tempdir().unwrap();
+        assert!(cache
+            .fetch(&key, destination.path())
+            .await
+            .unwrap()
+            .is_none());
+
+        cache
+            .upload(&key, &package_file(), sha256, &globs)
+            .await
+            .unwrap();
+
+        // The builds are uploaded to the HTTPS endpoint of the bucket.
+        let input_dir = format!(
+            "https://my-bucket.s3.amazonaws.com/builds/path/{}/",
+            input.hash_key()
+        );
+        assert_eq!(
+            store.urls(),
+            vec![
+                format!("{input_dir}{hash:x}/{PACKAGE_FILE_NAME}"),
+                format!("{input_dir}{hash:x}/entry.json"),
+                format!("{input_dir}globs.json"),
+            ]
+        );
+
+        assert_eq!(
+            cache.input_globs(&source, &input).await.unwrap(),
+            Some(globs.clone())
+        );
+        let fetched = cache
+            .fetch(&key, destination.path())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            fetched.package_file,
+            destination.path().join(PACKAGE_FILE_NAME)
+        );
+        assert_eq!(
+            fs_err::read(&fetched.package_file).unwrap(),
+            fs_err::read(package_file()).unwrap()
+        );
+        assert_eq!(fetched.sha256, sha256);
+        assert_eq!(fetched.globs, globs);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_rejects_hash_mismatch() {
+        let store = MemoryStore::default();
+        let cache = RemoteBuildCache::new(
+            Url::parse("https://cache.example.com/builds").unwrap(),
+            store.client(),
+            true,
+        );
+        let key = cache
+            .key(
+                &path_source("/a/dummy-a"),
+                &build_input(),
+                Some(Sha256Hash::default()),
+            )
+            .unwrap();
+        cache
+            .upload(&key, &package_file(), Sha256Hash::default(), &[])
+            .await
+            .unwrap();
+
+        let destination = tempfile::tempdir().unwrap();
+        let err = cache.fetch(&key, destination.path()).await.unwrap_err();
+        assert!(
+            matches!(err, RemoteBuildCacheError::HashMismatch { .. }),
+            "{err:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fetch_checks_index_json() {
+        let store = MemoryStore::default();
+        let cache = RemoteBuildCache::new(
+            Url::parse("https://cache.example.com/builds").unwrap(),
+            store.client(),
+            true,
+        );
+
+        // An entry with a valid sha256 that refers to another package than
+        // the one that is requested.
+        let input = BuildInput {
+            name: "dummy-b".to_string(),
+            ..build_input()
+        };
+        let key = cache
+            .key(
+                &path_source("/a/dummy-b"),
+                &input,
+                Some(Sha256Hash::default()),
+            )
+            .unwrap();
+        let sha256 = rattler_digest::compute_file_digest::<Sha256>(package_file()).unwrap();
+        cache
+            .upload(&key, &package_file(), sha256, &[])
+            .await
+            .unwrap();
+
+        let destination = tempfile::tempdir().unwrap();
+        let err = cache.fetch(&key, destination.path()).await.unwrap_err();
+        match err {
+            RemoteBuildCacheError::PackageMismatch {
+                expected, actual, ..
+            } => {
+                assert_eq!(expected, "dummy-b-0.1.0-h9490d1a_0");
+                assert_eq!(actual, "dummy-a-0.1.0-h9490d1a_0");
+            }
+            err => panic!("unexpected error: {err:?}"),
+        }
+    }
+}
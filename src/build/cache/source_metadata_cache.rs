@@ -1,7 +1,8 @@
-use async_fd_lock::{LockWrite, RwLockWriteGuard};
 use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
 use pixi_build_types::CondaPackageMetadata;
 use pixi_record::InputHash;
+use pixi_spec::IgnoreRunExports;
+use pixi_utils::{lock_write_with_owner, to_extended_length, OwnedWriteGuard};
 use rattler_conda_types::{GenericVirtualPackage, Platform};
 use serde::Deserialize;
 use serde_with::serde_derive::Serialize;
@@ -55,6 +56,12 @@ pub struct SourceMetadataInput {
 
     /// The variants of the build
     pub build_variants: BTreeMap<String, Vec<String>>,
+
+    /// The groups of build variant keys that are zipped together
+    pub build_variant_zip_keys: Vec<Vec<String>>,
+
+    /// The run exports that the consumer of the package ignores
+    pub ignore_run_exports: Option<IgnoreRunExports>,
 }
 
 impl SourceMetadataInput {
@@ -66,6 +73,12 @@ impl SourceMetadataInput {
         self.build_virtual_packages.hash(&mut hasher);
         self.host_virtual_packages.hash(&mut hasher);
         self.build_variants.hash(&mut hasher);
+        if !self.build_variant_zip_keys.is_empty() {
+            self.build_variant_zip_keys.hash(&mut hasher);
+        }
+        if let Some(ignore_run_exports) = &self.ignore_run_exports {
+            ignore_run_exports.hash(&mut hasher);
+        }
         format!(
             "{}-{}",
             self.host_platform,
@@ -79,10 +92,11 @@ impl SourceMetadataCache {
     ///
     /// An additional directory is created by this cache inside the passed root
     /// which includes a version number. This is to ensure that the cache is
-    /// never corrupted if the format changes in the future.
+    /// never corrupted if the format changes in the future. On Windows the
+    /// root is accessed as an extended-length path, like the build cache.
     pub fn new(root: PathBuf) -> Self {
         Self {
-            root: root.join("source-meta-v0"),
+            root: to_extended_length(&root).join("source-meta-v0"),
         }
     }
 
@@ -123,13 +137,15 @@ impl SourceMetadataCache {
                 )
             })?;
 
-        let mut locked_cache_file = cache_file.lock_write().await.map_err(|e| {
-            SourceMetadataError::IoError(
-                "locking cache file".to_string(),
-                cache_file_path.clone(),
-                e.error,
-            )
-        })?;
+        let mut locked_cache_file = lock_write_with_owner(cache_file, &cache_file_path)
+            .await
+            .map_err(|e| {
+                SourceMetadataError::IoError(
+                    "locking cache file".to_string(),
+                    cache_file_path.clone(),
+                    e.error,
+                )
+            })?;
 
         // Try to parse the contents of the file
         let mut cache_file_contents = String::new();
@@ -160,7 +176,7 @@ impl SourceMetadataCache {
 ///
 /// As long as this entry is held, no other process can access this cache entry.
 pub struct CacheEntry {
-    file: RwLockWriteGuard<tokio::fs::File>,
+    file: OwnedWriteGuard,
     path: PathBuf,
 }
 
@@ -1,11 +1,12 @@
 mod cache;
 mod reporters;
+mod url_source;
+mod variant_config;
 
 use std::{
     collections::HashMap,
     ffi::OsStr,
     hash::{Hash, Hasher},
-    ops::Not,
     path::{Component, Path, PathBuf},
     str::FromStr,
     sync::Arc,
@@ -19,7 +20,7 @@ use pixi_build_frontend::{BackendOverride, SetupRequest, ToolContext};
 use pixi_build_types::{
     procedures::{
         conda_build::{CondaBuildParams, CondaOutputIdentifier},
-        conda_metadata::CondaMetadataParams,
+        conda_metadata::{self, CondaMetadataParams},
     },
     ChannelConfiguration, CondaPackageMetadata, PlatformAndVirtualPackages,
 };
@@ -30,21 +31,26 @@ pub use pixi_glob::{GlobHashCache, GlobHashError};
 use pixi_glob::{GlobHashKey, GlobModificationTime, GlobModificationTimeError};
 use pixi_manifest::Targets;
 use pixi_record::{
-    InputHash, PinnedGitCheckout, PinnedGitSpec, PinnedPathSpec, PinnedSourceSpec, SourceRecord,
+    InputHash, PinnedGitCheckout, PinnedGitSpec, PinnedPathSpec, PinnedSourceSpec, PinnedUrlSpec,
+    SourceRecord,
 };
-use pixi_spec::{GitSpec, Reference, SourceSpec};
+use pixi_spec::{GitSpec, IgnoreRunExports, Reference, SourceSpec};
 use rattler_conda_types::{
     ChannelConfig, ChannelUrl, GenericVirtualPackage, PackageRecord, Platform, RepoDataRecord,
 };
-use rattler_digest::Sha256;
+use rattler_digest::{Sha256, Sha256Hash};
 use reporters::SourceReporter;
 pub use reporters::{BuildMetadataReporter, BuildReporter, SourceCheckoutReporter};
 use thiserror::Error;
 use tracing::instrument;
 use typed_path::{Utf8TypedPath, Utf8TypedPathBuf};
 use url::Url;
+use url_source::ExpectedDigests;
+use variant_config::VariantConfigError;
+pub(crate) use variant_config::VariantConfigFiles;
 use xxhash_rust::xxh3::Xxh3;
 
+pub use crate::build::cache::RemoteBuildCache;
 use crate::build::cache::{
     BuildCache, BuildInput, CachedBuild, CachedCondaMetadata, SourceInfo, SourceMetadataCache,
     SourceMetadataInput,
@@ -55,6 +61,45 @@ use crate::build::cache::{
 /// the input hash.
 const DEFAULT_BUILD_IGNORE_GLOBS: &[&str] = &["!.pixi/**"];
 
+/// The directory in the cache directory that packages are downloaded to from
+/// the remote build cache, before they are moved into the build cache.
+const REMOTE_BUILDS_DOWNLOAD_DIR: &str = "remote-builds-v0";
+
+/// Resolves the build variants of the workspace for the given platform. The
+/// variants of a target take precedence over the default variants.
+pub(crate) fn resolve_variant_configuration(
+    variant_config: &Targets<Option<HashMap<String, Vec<String>>>>,
+    platform: Platform,
+) -> HashMap<String, Vec<String>> {
+    let mut result = HashMap::new();
+
+    // Resolves from most specific to least specific.
+    for variants in variant_config.resolve(Some(platform)).flatten() {
+        // Update the hash map, but only items that are not already in the map.
+        for (key, value) in variants {
+            result.entry(key.clone()).or_insert_with(|| value.clone());
+        }
+    }
+
+    result
+}
+
+/// The zip keys as they are passed to the build backend, older backends don't
+/// know about them so they are only sent when they are used.
+pub(crate) fn zip_keys_param(zip_keys: Vec<Vec<String>>) -> Option<Vec<Vec<String>>> {
+    (!zip_keys.is_empty()).then_some(zip_keys)
+}
+
+/// The ignored run exports as they are passed to the build backend.
+fn ignore_run_exports_param(
+    ignore_run_exports: Option<&IgnoreRunExports>,
+) -> Option<conda_metadata::IgnoreRunExports> {
+    ignore_run_exports.map(|ignore_run_exports| conda_metadata::IgnoreRunExports {
+        by_name: ignore_run_exports.by_name.clone(),
+        from_package: ignore_run_exports.from_package.clone(),
+    })
+}
+
 /// The [`BuildContext`] is used to build packages from source.
 #[derive(Clone)]
 pub struct BuildContext {
@@ -62,10 +107,13 @@ pub struct BuildContext {
     glob_hash_cache: GlobHashCache,
     source_metadata_cache: SourceMetadataCache,
     build_cache: BuildCache,
+    remote_build_cache: Option<RemoteBuildCache>,
     cache_dir: PathBuf,
     work_dir: PathBuf,
     tool_context: Arc<ToolContext>,
     variant_config: Targets<Option<HashMap<String, Vec<String>>>>,
+    variant_zip_keys: Vec<Vec<String>>,
+    variant_config_files: VariantConfigFiles,
 
     /// The resolved Git references.
     git: GitResolver,
@@ -105,6 +153,9 @@ pub enum BuildError {
 
     #[error(transparent)]
     FetchError(Box<dyn Diagnostic + Send + Sync + 'static>),
+
+    #[error(transparent)]
+    VariantConfig(#[from] VariantConfigError),
 }
 
 /// Location of the source code for a package. This will be used as the input
@@ -120,7 +171,7 @@ pub struct SourceCheckout {
 }
 
 /// The metadata of a source checkout.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SourceMetadata {
     /// The source checkout that the manifest was extracted from.
     pub source: SourceCheckout,
@@ -142,10 +193,13 @@ impl BuildContext {
             glob_hash_cache: GlobHashCache::default(),
             source_metadata_cache: SourceMetadataCache::new(cache_dir.clone()),
             build_cache: BuildCache::new(cache_dir.clone()),
+            remote_build_cache: None,
             cache_dir,
             work_dir: dot_pixi_dir.join("build-v0"),
             tool_context,
             variant_config,
+            variant_zip_keys: Vec::new(),
+            variant_config_files: VariantConfigFiles::default(),
             git: GitResolver::default(),
         })
     }
@@ -158,14 +212,38 @@ impl BuildContext {
             .build_variants
             .clone();
 
-        Self::new(
+        let remote_build_cache = RemoteBuildCache::from_config(
+            &project.config().build().cache,
+            project.authenticated_client().clone(),
+        );
+
+        Ok(Self::new(
             get_cache_dir()?,
             project.pixi_dir(),
             project.channel_config(),
             variant,
             Arc::new(ToolContext::default()),
         )
-        .into_diagnostic()
+        .into_diagnostic()?
+        .with_variant_zip_keys(
+            project
+                .manifest()
+                .workspace
+                .workspace
+                .build_variants_zip_keys
+                .clone(),
+        )
+        .with_variant_config_files(VariantConfigFiles::new(
+            &project.manifest().workspace.workspace.variant_config,
+            project.root(),
+            project
+                .default_environment()
+                .channels()
+                .into_iter()
+                .cloned()
+                .collect(),
+        ))
+        .with_remote_build_cache(remote_build_cache))
     }
 
     pub fn with_tool_context(self, tool_context: Arc<ToolContext>) -> Self {
@@ -175,6 +253,34 @@ impl BuildContext {
         }
     }
 
+    /// Sets the groups of build variant keys whose values are zipped together.
+    pub fn with_variant_zip_keys(self, variant_zip_keys: Vec<Vec<String>>) -> Self {
+        Self {
+            variant_zip_keys,
+            ..self
+        }
+    }
+
+    /// Sets the variant config files that the build variants are merged with.
+    pub(crate) fn with_variant_config_files(
+        self,
+        variant_config_files: VariantConfigFiles,
+    ) -> Self {
+        Self {
+            variant_config_files,
+            ..self
+        }
+    }
+
+    /// Sets the remote cache that built packages are fetched from and uploaded
+    /// to.
+    pub fn with_remote_build_cache(self, remote_build_cache: Option<RemoteBuildCache>) -> Self {
+        Self {
+            remote_build_cache,
+            ..self
+        }
+    }
+
     /// Sets the input hash cache to use for caching input hashes.
     pub fn with_glob_hash_cache(self, glob_hash_cache: GlobHashCache) -> Self {
         Self {
@@ -183,20 +289,25 @@ impl BuildContext {
         }
     }
 
-    fn resolve_variant(&self, platform: Platform) -> HashMap<String, Vec<String>> {
-        let mut result = HashMap::new();
-
-        // Resolves from most specific to least specific.
-        for variants in self.variant_config.resolve(Some(platform)).flatten() {
-            // Update the hash map, but only items that are not already in the map.
-            for (key, value) in variants {
-                result.entry(key.clone()).or_insert_with(|| value.clone());
-            }
+    /// Resolves the build variants and their zip keys for the given platform.
+    /// The variants of the manifest take precedence over the variants from
+    /// the variant config files.
+    async fn resolve_variant(
+        &self,
+        platform: Platform,
+    ) -> Result<(HashMap<String, Vec<String>>, Vec<Vec<String>>), BuildError> {
+        let mut variants = resolve_variant_configuration(&self.variant_config, platform);
+        let mut zip_keys = self.variant_zip_keys.clone();
+        if !self.variant_config_files.is_empty() {
+            self.variant_config_files
+                .load(platform, &self.tool_context, &self.channel_config)
+                .await?
+                .merge_into(&mut variants, &mut zip_keys)?;
         }
 
-        tracing::info!("resolved variant configuration: {:?}", result);
+        tracing::info!("resolved variant configuration: {:?}", variants);
 
-        result
+        Ok((variants, zip_keys))
     }
 
     /// Extracts the metadata for a package from the given source specification.
@@ -217,6 +328,7 @@ impl BuildContext {
         let records = self
             .extract_records(
                 &source,
+                source_spec.ignore_run_exports(),
                 channels,
                 host_platform,
                 host_virtual_packages,
@@ -244,6 +356,8 @@ impl BuildContext {
         source_reporter: Option<Arc<dyn SourceReporter>>,
         build_id: usize,
     ) -> Result<RepoDataRecord, BuildError> {
+        let ignore_run_exports =
+            (!source_spec.ignore_run_exports.is_empty()).then_some(&source_spec.ignore_run_exports);
         let source_checkout = SourceCheckout {
             path: self
                 .fetch_pinned_source(&source_spec.source, source_reporter)
@@ -252,29 +366,51 @@ impl BuildContext {
         };
 
         let channels_urls: Vec<Url> = channels.iter().cloned().map(Into::into).collect::<Vec<_>>();
-
+        let (variant_configuration, variant_zip_keys) = self.resolve_variant(host_platform).await?;
+
+        let build_input = BuildInput {
+            channel_urls: channels.iter().cloned().map(Into::into).collect(),
+            target_platform: Platform::from_str(&source_spec.package_record.subdir)
+                .ok()
+                .unwrap_or(host_platform),
+            name: source_spec.package_record.name.as_normalized().to_string(),
+            version: source_spec.package_record.version.to_string(),
+            build: source_spec.package_record.build.clone(),
+            host_platform,
+            host_virtual_packages: host_virtual_packages.clone(),
+            build_virtual_packages: build_virtual_packages.clone(),
+            build_variants: variant_configuration.clone().into_iter().collect(),
+            build_variant_zip_keys: variant_zip_keys.clone(),
+            ignore_run_exports: ignore_run_exports.cloned(),
+        };
         let (cached_build, entry) = self
             .build_cache
-            .entry(
-                &source_checkout,
-                &BuildInput {
-                    channel_urls: channels.iter().cloned().map(Into::into).collect(),
-                    target_platform: Platform::from_str(&source_spec.package_record.subdir)
-                        .ok()
-                        .unwrap_or(host_platform),
-                    name: source_spec.package_record.name.as_normalized().to_string(),
-                    version: source_spec.package_record.version.to_string(),
-                    build: source_spec.package_record.build.clone(),
-                    host_platform,
-                    host_virtual_packages: host_virtual_packages.clone(),
-                    build_virtual_packages: build_virtual_packages.clone(),
-                },
-            )
+            .entry(&source_checkout, &build_input)
             .await?;
 
         // Check if there are already cached builds
         if let Some(build) = cached_build {
-            if let Some(record) = Self::cached_build_source_record(build, &source_checkout)? {
+            if let Some(record) = self
+                .cached_build_source_record(build, &source_checkout)
+                .await?
+            {
+                build_reporter.on_build_cached(build_id);
+                return Ok(record);
+            }
+        }
+
+        // Try to fetch the package from the remote cache before building it.
+        if let Some(remote_build_cache) = &self.remote_build_cache {
+            if let Some(fetched) = self
+                .fetch_remote_build(remote_build_cache, &source_checkout, &build_input)
+                .await?
+            {
+                let record = repodata_record(source_spec, &fetched.package_file, fetched.sha256)?;
+                let source = (!source_checkout.pinned.is_immutable()).then(|| SourceInfo {
+                    globs: fetched.globs,
+                    hash: fetched.input_hash,
+                });
+                let record = entry.insert(CachedBuild { source, record }).await?;
                 build_reporter.on_build_cached(build_id);
                 return Ok(record);
             }
@@ -322,7 +458,9 @@ impl BuildContext {
                         }
                         .key(),
                     ),
-                    variant_configuration: Some(self.resolve_variant(host_platform)),
+                    variant_configuration: Some(variant_configuration),
+                    variant_zip_keys: zip_keys_param(variant_zip_keys),
+                    ignore_run_exports: ignore_run_exports_param(ignore_run_exports),
                 },
                 build_reporter.as_conda_build_reporter(),
             )
@@ -343,50 +481,121 @@ impl BuildContext {
         // Add the sha256 to the package record.
         let sha = rattler_digest::compute_file_digest::<Sha256>(&build_result.output_file)
             .map_err(|e| BuildError::CalculateSha(build_result.output_file.clone(), e))?;
+        let record = repodata_record(source_spec, &build_result.output_file, sha)?;
 
-        // Update the package_record sha256 field and timestamp.
-        let mut package_record = source_spec.package_record.clone();
-        package_record.sha256 = Some(sha);
-        package_record.timestamp.get_or_insert_with(Utc::now);
-
-        // Construct a repodata record that represents the package
-        let record = RepoDataRecord {
-            package_record,
-            url: Url::from_file_path(&build_result.output_file).map_err(|_| {
-                BuildError::FrontendError(
-                    miette::miette!(
-                        "failed to convert returned path to URL: {}",
-                        build_result.output_file.display()
-                    )
-                    .into(),
-                )
-            })?,
-            channel: None,
-            file_name: build_result
-                .output_file
-                .file_name()
-                .and_then(OsStr::to_str)
-                .map(ToString::to_string)
-                .unwrap_or_default(),
+        // Record the hash of the input files of mutable sources, so the build
+        // is only repeated when they change.
+        let source = if source_checkout.pinned.is_immutable() {
+            None
+        } else {
+            let input_hash = self
+                .glob_hash_cache
+                .compute_hash(build_glob_hash_key(
+                    &source_checkout,
+                    &build_result.input_globs,
+                ))
+                .await?;
+            Some(SourceInfo {
+                globs: build_result.input_globs,
+                hash: Some(input_hash.hash),
+            })
         };
 
+        let input_hash = source.as_ref().and_then(|source| source.hash);
+        let input_globs = source
+            .as_ref()
+            .map(|source| source.globs.clone())
+            .unwrap_or_default();
+
         // Store the build in the cache
         let updated_record = entry
             .insert(CachedBuild {
-                source: source_checkout
-                    .pinned
-                    .is_immutable()
-                    .not()
-                    .then_some(SourceInfo {
-                        globs: build_result.input_globs,
-                    }),
+                source,
                 record: record.clone(),
             })
             .await?;
 
+        // Share the build with other machines, a failed upload doesn't fail the
+        // build.
+        if let Some(remote_build_cache) = self
+            .remote_build_cache
+            .as_ref()
+            .filter(|remote| remote.upload_enabled())
+        {
+            if let (Some(key), Ok(package_file)) = (
+                remote_build_cache.key(&source_checkout, &build_input, input_hash),
+                updated_record.url.to_file_path(),
+            ) {
+                if let Err(err) = remote_build_cache
+                    .upload(&key, &package_file, sha, &input_globs)
+                    .await
+                {
+                    tracing::warn!("{:?}", miette::Report::new(err));
+                }
+            }
+        }
+
         Ok(updated_record)
     }
 
+    /// Fetches a package from the remote cache into the local cache directory.
+    /// Returns `None` if the remote cache doesn't contain the package. The
+    /// remote cache is an optimization, so failures to fetch the package are
+    /// reported as warnings and the package is built locally instead.
+    async fn fetch_remote_build(
+        &self,
+        remote_build_cache: &RemoteBuildCache,
+        source_checkout: &SourceCheckout,
+        build_input: &BuildInput,
+    ) -> Result<Option<FetchedRemoteBuild>, BuildError> {
+        // Mutable sources are identified by the hash of their input files, for
+        // which the globs of a previous build are required.
+        let input_hash = if source_checkout.pinned.is_immutable() {
+            None
+        } else {
+            let globs = match remote_build_cache
+                .input_globs(source_checkout, build_input)
+                .await
+            {
+                Ok(Some(globs)) => globs,
+                Ok(None) => return Ok(None),
+                Err(err) => {
+                    tracing::warn!("{:?}", miette::Report::new(err));
+                    return Ok(None);
+                }
+            };
+            let input_hash = self
+                .glob_hash_cache
+                .compute_hash(build_glob_hash_key(source_checkout, &globs))
+                .await?;
+            Some(input_hash.hash)
+        };
+        let Some(key) = remote_build_cache.key(source_checkout, build_input, input_hash) else {
+            return Ok(None);
+        };
+
+        let download_dir = self.cache_dir.join(REMOTE_BUILDS_DOWNLOAD_DIR);
+        fs_err::tokio::create_dir_all(&download_dir).await?;
+        let download_dir = tempfile::tempdir_in(&download_dir)?;
+        match remote_build_cache.fetch(&key, download_dir.path()).await {
+            Ok(Some(fetched)) => {
+                tracing::debug!("fetched '{}' from the remote build cache", build_input.name);
+                Ok(Some(FetchedRemoteBuild {
+                    package_file: fetched.package_file,
+                    sha256: fetched.sha256,
+                    globs: fetched.globs,
+                    input_hash,
+                    _download_dir: download_dir,
+                }))
+            }
+            Ok(None) => Ok(None),
+            Err(err) => {
+                tracing::warn!("{:?}", miette::Report::new(err));
+                Ok(None)
+            }
+        }
+    }
+
     /// Acquires the source from the given source specification. A source
     /// specification can still not point to a specific pinned source. E.g. a
     /// git spec that points to a branch or a tag. This function will fetch the
@@ -402,7 +611,30 @@ impl BuildContext {
         source_reporter: Option<Arc<dyn SourceReporter>>,
     ) -> Result<SourceCheckout, BuildError> {
         match source_spec {
-            SourceSpec::Url(_) => unimplemented!("fetching URL sources is not yet implemented"),
+            SourceSpec::Url(url_spec) => {
+                let fetched = url_source::fetch_url_source(
+                    &self.tool_context.client,
+                    url_spec.urls(),
+                    &ExpectedDigests {
+                        sha256: url_spec.sha256,
+                        md5: url_spec.md5,
+                        blake2b: url_spec.blake2b,
+                    },
+                    &self.cache_dir,
+                )
+                .await
+                .map_err(|err| BuildError::FetchError(Box::new(err)))?;
+                Ok(SourceCheckout {
+                    path: fetched.path,
+                    pinned: PinnedSourceSpec::Url(PinnedUrlSpec {
+                        url: url_spec.url.clone(),
+                        sha256: fetched.sha256,
+                        md5: fetched.md5,
+                        blake2b: fetched.blake2b,
+                        mirrors: url_spec.mirrors.clone(),
+                    }),
+                })
+            }
             SourceSpec::Git(git_spec) => {
                 let fetched = self
                     .resolve_git(
@@ -426,6 +658,7 @@ impl BuildContext {
                             commit: fetched.git().precise().expect("should be precies"),
                             reference: git_spec.rev.clone().unwrap_or(Reference::DefaultBranch),
                             subdirectory: git_spec.subdirectory.clone(),
+                            options: git_spec.options.clone(),
                         },
                     }),
                 };
@@ -456,8 +689,20 @@ impl BuildContext {
         source_reporter: Option<Arc<dyn SourceReporter>>,
     ) -> Result<PathBuf, BuildError> {
         match source_spec {
-            PinnedSourceSpec::Url(_) => {
-                unimplemented!("fetching URL sources is not yet implemented")
+            PinnedSourceSpec::Url(pinned_url_spec) => {
+                let fetched = url_source::fetch_url_source(
+                    &self.tool_context.client,
+                    pinned_url_spec.urls(),
+                    &ExpectedDigests {
+                        sha256: Some(pinned_url_spec.sha256),
+                        md5: pinned_url_spec.md5,
+                        blake2b: pinned_url_spec.blake2b,
+                    },
+                    &self.cache_dir,
+                )
+                .await
+                .map_err(|err| BuildError::FetchError(Box::new(err)))?;
+                Ok(fetched.path)
             }
             PinnedSourceSpec::Git(pinned_git_spec) => {
                 let fetched = self
@@ -526,6 +771,7 @@ impl BuildContext {
             .git
             .fetch(
                 &git_url,
+                git.options,
                 self.tool_context.clone().client.clone(),
                 self.cache_dir.clone().join(CACHED_GIT_DIR),
                 reporter,
@@ -553,6 +799,7 @@ impl BuildContext {
             .git
             .fetch(
                 &git_url,
+                git.source.options,
                 self.tool_context.clone().client.clone(),
                 self.cache_dir.clone().join(CACHED_GIT_DIR),
                 reporter,
@@ -570,6 +817,7 @@ impl BuildContext {
     async fn extract_records(
         &self,
         source: &SourceCheckout,
+        ignore_run_exports: Option<&IgnoreRunExports>,
         channels: &[ChannelUrl],
         host_platform: Platform,
         host_virtual_packages: Vec<GenericVirtualPackage>,
@@ -579,7 +827,7 @@ impl BuildContext {
         build_id: usize,
     ) -> Result<Vec<SourceRecord>, BuildError> {
         let channel_urls = channels.iter().cloned().map(Into::into).collect::<Vec<_>>();
-        let variant_configuration = self.resolve_variant(host_platform);
+        let (variant_configuration, variant_zip_keys) = self.resolve_variant(host_platform).await?;
 
         let (cached_metadata, cache_entry) = self
             .source_metadata_cache
@@ -592,6 +840,8 @@ impl BuildContext {
                     host_platform,
                     host_virtual_packages: host_virtual_packages.clone(),
                     build_variants: variant_configuration.clone().into_iter().collect(),
+                    build_variant_zip_keys: variant_zip_keys.clone(),
+                    ignore_run_exports: ignore_run_exports.cloned(),
                 },
             )
             .await?;
@@ -611,6 +861,7 @@ impl BuildContext {
                         source,
                         metadata.packages,
                         metadata.input_hash,
+                        ignore_run_exports,
                     ));
                 } else {
                     tracing::debug!("found stale cached metadata.");
@@ -623,6 +874,7 @@ impl BuildContext {
                     source,
                     metadata.packages,
                     metadata.input_hash,
+                    ignore_run_exports,
                 ));
             }
         }
@@ -664,6 +916,8 @@ impl BuildContext {
                         .key(),
                     ),
                     variant_configuration: Some(variant_configuration),
+                    variant_zip_keys: zip_keys_param(variant_zip_keys),
+                    ignore_run_exports: ignore_run_exports_param(ignore_run_exports),
                 },
                 metadata_reporter.as_conda_metadata_reporter().clone(),
             )
@@ -700,15 +954,32 @@ impl BuildContext {
             source,
             metadata.packages,
             input_hash,
+            ignore_run_exports,
         ))
     }
 
-    fn cached_build_source_record(
+    async fn cached_build_source_record(
+        &self,
         cached_build: CachedBuild,
         source_checkout: &SourceCheckout,
     ) -> Result<Option<RepoDataRecord>, BuildError> {
         // Check to see if the cached build is up-to-date.
         if let Some(source_input) = cached_build.source {
+            // Compare the hash of the input files if the build recorded it, this
+            // also holds when the files were touched but not changed.
+            if let Some(hash) = source_input.hash {
+                let input_hash = self
+                    .glob_hash_cache
+                    .compute_hash(build_glob_hash_key(source_checkout, &source_input.globs))
+                    .await?;
+                if input_hash.hash == hash {
+                    tracing::debug!("found an up-to-date cached build.");
+                    return Ok(Some(cached_build.record));
+                }
+                tracing::debug!("found a stale cached build, the input files have changed");
+                return Ok(None);
+            }
+
             let glob_time = GlobModificationTime::from_patterns(
                 &source_checkout.path,
                 source_input
@@ -759,17 +1030,81 @@ impl BuildContext {
     }
 }
 
+/// A package that was downloaded from the remote build cache.
+struct FetchedRemoteBuild {
+    package_file: PathBuf,
+    sha256: Sha256Hash,
+    globs: Vec<String>,
+    input_hash: Option<Sha256Hash>,
+    /// The directory that contains the download, which is removed when the
+    /// package is dropped.
+    _download_dir: tempfile::TempDir,
+}
+
+/// Constructs the repodata record of a package that was built from the source
+/// record.
+fn repodata_record(
+    source_spec: &SourceRecord,
+    package_file: &Path,
+    sha256: Sha256Hash,
+) -> Result<RepoDataRecord, BuildError> {
+    // Update the package_record sha256 field and timestamp.
+    let mut package_record = source_spec.package_record.clone();
+    package_record.sha256 = Some(sha256);
+    package_record.timestamp.get_or_insert_with(Utc::now);
+
+    // Construct a repodata record that represents the package
+    Ok(RepoDataRecord {
+        package_record,
+        url: Url::from_file_path(package_file).map_err(|_| {
+            BuildError::FrontendError(
+                miette::miette!(
+                    "failed to convert returned path to URL: {}",
+                    package_file.display()
+                )
+                .into(),
+            )
+        })?,
+        channel: None,
+        file_name: package_file
+            .file_name()
+            .and_then(OsStr::to_str)
+            .map(ToString::to_string)
+            .unwrap_or_default(),
+    })
+}
+
+/// Returns the key of the hash of the files that are used as input to build a
+/// package. The build artifacts in the `.pixi` directory are never part of it.
+fn build_glob_hash_key(source_checkout: &SourceCheckout, globs: &[String]) -> GlobHashKey {
+    GlobHashKey {
+        root: source_checkout.path.clone(),
+        globs: globs
+            .iter()
+            .cloned()
+            .chain(DEFAULT_BUILD_IGNORE_GLOBS.iter().map(ToString::to_string))
+            .collect(),
+    }
+}
+
+/// Converts the metadata of the packages of a source checkout to records. The
+/// ignored run exports are part of the input hash of the records, because the
+/// lock-file has no other place for them.
 fn source_metadata_to_records(
     source: &SourceCheckout,
     packages: Vec<CondaPackageMetadata>,
     input_hash: Option<InputHash>,
+    ignore_run_exports: Option<&IgnoreRunExports>,
 ) -> Vec<SourceRecord> {
+    let ignore_run_exports = ignore_run_exports.cloned().unwrap_or_default();
+    let input_hash = InputHash::with_ignore_run_exports(input_hash, &ignore_run_exports);
     // Convert the metadata to repodata
     let packages = packages
         .into_iter()
         .map(|p| {
             SourceRecord {
                 input_hash: input_hash.clone(),
+                ignore_run_exports: ignore_run_exports.clone(),
                 source: source.pinned.clone(),
                 package_record: PackageRecord {
                     // We cannot now these values from the metadata because no actual package
@@ -882,3 +1217,156 @@ impl WorkDirKey {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lock_file::test_utils::record;
+
+    fn build_context(cache_dir: &Path) -> BuildContext {
+        BuildContext::new(
+            cache_dir.to_path_buf(),
+            cache_dir.join(".pixi"),
+            ChannelConfig::default_with_root_dir(cache_dir.to_path_buf()),
+            Targets::default(),
+            Arc::new(ToolContext::default()),
+        )
+        .unwrap()
+    }
+
+    fn source_checkout(path: &Path) -> SourceCheckout {
+        SourceCheckout {
+            path: path.to_path_buf(),
+            pinned: PinnedSourceSpec::Path(PinnedPathSpec {
+                path: path.to_string_lossy().as_ref().into(),
+            }),
+        }
+    }
+
+    fn cached_build(globs: &[&str], hash: Option<Sha256Hash>) -> CachedBuild {
+        let mut record = record("foo", "1.0.0");
+        record.package_record.timestamp = Some(Utc::now());
+        CachedBuild {
+            source: Some(SourceInfo {
+                globs: globs.iter().map(ToString::to_string).collect(),
+                hash,
+            }),
+            record,
+        }
+    }
+
+    #[test]
+    fn test_build_glob_hash_key() {
+        let source = source_checkout(Path::new("/src/foo"));
+        let key = build_glob_hash_key(&source, &["src/**".to_string()]);
+        assert_eq!(key.root, PathBuf::from("/src/foo"));
+        assert_eq!(key.globs, vec!["src/**", "!.pixi/**"]);
+    }
+
+    #[tokio::test]
+    async fn test_cached_build_source_record_hash() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let source_dir = tempfile::tempdir().unwrap();
+        fs_err::create_dir_all(source_dir.path().join("src")).unwrap();
+        fs_err::write(source_dir.path().join("src/main.c"), "int main() {}").unwrap();
+        let source = source_checkout(source_dir.path());
+        let globs = ["src/**"];
+
+        let context = build_context(cache_dir.path());
+        let hash = context
+            .glob_hash_cache
+            .compute_hash(build_glob_hash_key(
+                &source,
+                &globs.map(ToString::to_string),
+            ))
+            .await
+            .unwrap()
+            .hash;
+
+        // The input files are unchanged, even though they were touched.
+        fs_err::write(source_dir.path().join("src/main.c"), "int main() {}").unwrap();
+        let hit = context
+            .cached_build_source_record(cached_build(&globs, Some(hash)), &source)
+            .await
+            .unwrap();
+        assert!(hit.is_some());
+
+        // Files in the `.pixi` directory are never part of the input.
+        fs_err::create_dir_all(source_dir.path().join(".pixi")).unwrap();
+        fs_err::write(source_dir.path().join(".pixi/build.log"), "built").unwrap();
+        let context = build_context(cache_dir.path());
+        let hit = context
+            .cached_build_source_record(cached_build(&["**"], Some(hash)), &source)
+            .await
+            .unwrap();
+        assert!(hit.is_some());
+
+        // A glob that matches other files invalidates the cached build.
+        fs_err::write(source_dir.path().join("README.md"), "# foo").unwrap();
+        let context = build_context(cache_dir.path());
+        let miss = context
+            .cached_build_source_record(cached_build(&["**"], Some(hash)), &source)
+            .await
+            .unwrap();
+        assert!(miss.is_none());
+        let hit = context
+            .cached_build_source_record(cached_build(&globs, Some(hash)), &source)
+            .await
+            .unwrap();
+        assert!(hit.is_some());
+
+        // A changed input file invalidates the cached build.
+        fs_err::write(
+            source_dir.path().join("src/main.c"),
+            "int main() { return 1; }",
+        )
+        .unwrap();
+        let context = build_context(cache_dir.path());
+        let miss = context
+            .cached_build_source_record(cached_build(&globs, Some(hash)), &source)
+            .await
+            .unwrap();
+        assert!(miss.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_cached_build_source_record_modification_time() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let source_dir = tempfile::tempdir().unwrap();
+        fs_err::write(source_dir.path().join("main.c"), "int main() {}").unwrap();
+        let source = source_checkout(source_dir.path());
+        let context = build_context(cache_dir.path());
+
+        // Builds cached without a hash are checked against the modification
+        // time of the input files.
+        let hit = context
+            .cached_build_source_record(cached_build(&["*.c"], None), &source)
+            .await
+            .unwrap();
+        assert!(hit.is_some());
+
+        let mut stale = cached_build(&["*.c"], None);
+        stale.record.package_record.timestamp = Some(Utc::now() - chrono::Duration::hours(1));
+        let miss = context
+            .cached_build_source_record(stale, &source)
+            .await
+            .unwrap();
+        assert!(miss.is_none());
+
+        // Without matching files the package is rebuilt.
+        let miss = context
+            .cached_build_source_record(cached_build(&["*.rs"], None), &source)
+            .await
+            .unwrap();
+        assert!(miss.is_none());
+
+        // Builds cached without source information are always up-to-date.
+        let mut build = cached_build(&[], None);
+        build.source = None;
+        let hit = context
+            .cached_build_source_record(build, &source)
+            .await
+            .unwrap();
+        assert!(hit.is_some());
+    }
+}
@@ -0,0 +1,310 @@
+//! Downloads and unpacks the source archives of url source dependencies.
+
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use miette::Diagnostic;
+use pixi_consts::consts::CACHED_URL_SOURCES_DIR;
+use pixi_utils::to_extended_length;
+use rattler_digest::{
+    digest::Digest, Blake2b256, Blake2b256Hash, Md5, Md5Hash, Sha256, Sha256Hash,
+};
+use reqwest_middleware::ClientWithMiddleware;
+use thiserror::Error;
+use url::Url;
+
+/// The hashes that a downloaded archive has to match.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct ExpectedDigests {
+    pub sha256: Option<Sha256Hash>,
+    pub md5: Option<Md5Hash>,
+    pub blake2b: Option<Blake2b256Hash>,
+}
+
+/// A source archive that is unpacked in the cache.
+#[derive(Debug)]
+pub(crate) struct FetchedUrlSource {
+    /// The root directory of the unpacked sources.
+    pub path: PathBuf,
+    pub sha256: Sha256Hash,
+    pub md5: Option<Md5Hash>,
+    pub blake2b: Option<Blake2b256Hash>,
+}
+
+#[derive(Debug, Error, Diagnostic)]
+pub enum UrlSourceError {
+    #[error("failed to download '{0}'")]
+    Download(Url, #[source] reqwest_middleware::Error),
+
+    #[error("the {algorithm} hash of '{url}' is {actual}, but {expected} was expected")]
+    HashMismatch {
+        url: Url,
+        algorithm: &'static str,
+        expected: String,
+        actual: String,
+    },
+
+    #[error("'{0}' is not a supported source archive, expected a .tar.gz, .tgz, .tar.zst, .tar or .zip file")]
+    UnsupportedArchive(Url),
+
+    #[error("failed to unpack '{0}'")]
+    Unpack(Url, #[source] std::io::Error),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error("the source archive could not be fetched from any of its {} urls", .0.len())]
+    AllUrlsFailed(#[related] Vec<UrlSourceError>),
+}
+
+/// The archive formats that source archives can be unpacked from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ArchiveKind {
+    TarGz,
+    TarZst,
+    Tar,
+    Zip,
+}
+
+impl ArchiveKind {
+    /// Determines the format of the archive from the file name in the url.
+    fn from_url(url: &Url) -> Option<Self> {
+        let file_name = url.path_segments()?.last()?.to_lowercase();
+        if file_name.ends_with(".tar.gz") || file_name.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else if file_name.ends_with(".tar.zst") {
+            Some(Self::TarZst)
+        } else if file_name.ends_with(".tar") {
+            Some(Self::Tar)
+        } else if file_name.ends_with(".zip") {
+            Some(Self::Zip)
+        } else {
+            None
+        }
+    }
+}
+
+/// Fetches a source archive and unpacks it in the cache, trying the urls in
+/// order until one of them succeeds. An archive with a known sha256 hash is
+/// only downloaded if it isn't cached yet.
+pub(crate) async fn fetch_url_source(
+    client: &ClientWithMiddleware,
+    urls: impl IntoIterator<Item = &Url>,
+    expected: &ExpectedDigests,
+    cache_dir: &Path,
+) -> Result<FetchedUrlSource, UrlSourceError> {
+    let sources_dir = cache_dir.join(CACHED_URL_SOURCES_DIR);
+    if let Some(sha256) = expected.sha256 {
+        let unpacked = sources_dir.join(format!("{sha256:x}"));
+        if unpacked.is_dir() {
+            return Ok(FetchedUrlSource {
+                path: source_root(&unpacked)?,
+                sha256,
+                md5: expected.md5,
+                blake2b: expected.blake2b,
+            });
+        }
+    }
+    fs_err::tokio::create_dir_all(&sources_dir).await?;
+
+    let mut errors = Vec::new();
+    for url in urls {
+        match download_and_unpack(client, url, expected, &sources_dir).await {
+            Ok(fetched) => return Ok(fetched),
+            Err(err) => {
+                tracing::warn!("failed to fetch the source archive from '{url}': {err}");
+                errors.push(err);
+            }
+        }
+    }
+    Err(UrlSourceError::AllUrlsFailed(errors))
+}
+
+/// Downloads the archive from a single url, verifies its hashes and unpacks
+/// it in a directory named after its sha256 hash.
+async fn download_and_unpack(
+    client: &ClientWithMiddleware,
+    url: &Url,
+    expected: &ExpectedDigests,
+    sources_dir: &Path,
+) -> Result<FetchedUrlSource, UrlSourceError> {
+    let kind = ArchiveKind::from_url(url)
+        .ok_or_else(|| UrlSourceError::UnsupportedArchive(url.clone()))?;
+
+    let mut response = client
+        .get(url.clone())
+        .send()
+        .await
+        .and_then(|response| response.error_for_status().map_err(Into::into))
+        .map_err(|err| UrlSourceError::Download(url.clone(), err))?;
+
+    let mut archive = tempfile::NamedTempFile::new_in(sources_dir)?;
+    let mut sha256 = Sha256::new();
+    let mut md5 = Md5::new();
+    let mut blake2b = Blake2b256::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|err| UrlSourceError::Download(url.clone(), err.into()))?
+    {
+        sha256.update(&chunk);
+        md5.update(&chunk);
+        blake2b.update(&chunk);
+        archive.write_all(&chunk)?;
+    }
+    archive.flush()?;
+
+    let sha256 = sha256.finalize();
+    let md5 = md5.finalize();
+    let blake2b = blake2b.finalize();
+    verify_hash(url, "sha256", expected.sha256.as_ref(), &sha256)?;
+    verify_hash(url, "md5", expected.md5.as_ref(), &md5)?;
+    verify_hash(url, "blake2b", expected.blake2b.as_ref(), &blake2b)?;
+
+    let unpacked = sources_dir.join(format!("{sha256:x}"));
+    if !unpacked.is_dir() {
+        let unpack_dir = tempfile::tempdir_in(sources_dir)?;
+        let archive_path = archive.path().to_path_buf();
+        // The files of source archives are often nested deeply, so they are
+        // unpacked through an extended-length path on Windows. The returned
+        // path stays a regular path, as it is passed on to the build backends.
+        let unpack_path = to_extended_length(unpack_dir.path()).into_owned();
+        match tokio::task::spawn_blocking(move || unpack(&archive_path, kind, &unpack_path)).await {
+            Ok(result) => result.map_err(|err| UrlSourceError::Unpack(url.clone(), err))?,
+            Err(err) => std::panic::resume_unwind(err.into_panic()),
+        }
+
+        // Another process may have unpacked the same archive in the meantime.
+        if let Err(err) = fs_err::rename(
+            to_extended_length(unpack_dir.path()),
+            to_extended_length(&unpacked),
+        ) {
+            if !unpacked.is_dir() {
+                return Err(err.into());
+            }
+        }
+    }
+
+    Ok(FetchedUrlSource {
+        path: source_root(&unpacked)?,
+        sha256,
+        md5: Some(md5),
+        blake2b: Some(blake2b),
+    })
+}
+
+/// Returns an error if the hash doesn't match the expected hash.
+fn verify_hash<T: std::fmt::LowerHex + PartialEq>(
+    url: &Url,
+    algorithm: &'static str,
+    expected: Option<&T>,
+    actual: &T,
+) -> Result<(), UrlSourceError> {
+    match expected {
+        Some(expected) if expected != actual => Err(UrlSourceError::HashMismatch {
+            url: url.clone(),
+            algorithm,
+            expected: format!("{expected:x}"),
+            actual: format!("{actual:x}"),
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Unpacks the archive into the destination directory.
+fn unpack(archive: &Path, kind: ArchiveKind, destination: &Path) -> std::io::Result<()> {
+    let file = fs_err::File::open(archive)?;
+    match kind {
+        ArchiveKind::TarGz => {
+            tar::Archive::new(flate2::read::GzDecoder::new(file)).unpack(destination)
+        }
+        ArchiveKind::TarZst => tar::Archive::new(zstd::Decoder::new(file)?).unpack(destination),
+        ArchiveKind::Tar => tar::Archive::new(file).unpack(destination),
+        ArchiveKind::Zip => zip::ZipArchive::new(file)
+            .and_then(|mut zip| zip.extract(destination))
+            .map_err(std::io::Error::other),
+    }
+}
+
+/// Most source archives contain a single directory with the sources, like
+/// `mylib-1.0/`, in which case that directory is the root of the sources.
+fn source_root(unpacked: &Path) -> std::io::Result<PathBuf> {
+    let entries = fs_err::read_dir(unpacked)?.collect::<Result<Vec<_>, _>>()?;
+    if let [entry] = entries.as_slice() {
+        if entry.file_type()?.is_dir() {
+            return Ok(entry.path());
+        }
+    }
+    Ok(unpacked.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_archive_kind() {
+        let kind = |url: &str| ArchiveKind::from_url(&Url::parse(url).unwrap());
+        assert_eq!(
+            kind("https://example.edu/mylib-1.0.tar.gz"),
+            Some(ArchiveKind::TarGz)
+        );
+        assert_eq!(
+            kind("https://example.edu/mylib-1.0.TGZ?download=1"),
+            Some(ArchiveKind::TarGz)
+        );
+        assert_eq!(
+            kind("https://example.edu/mylib.zip"),
+            Some(ArchiveKind::Zip)
+        );
+        assert_eq!(
+            kind("https://example.edu/mylib-1.0.tar.zst"),
+            Some(ArchiveKind::TarZst)
+        );
+        assert_eq!(kind("https://example.edu/mylib-1.0.tar.bz2"), None);
+    }
+
+    #[test]
+    fn test_verify_hash() {
+        let url = Url::parse("https://example.edu/mylib-1.0.tar.gz").unwrap();
+        let hash = rattler_digest::compute_bytes_digest::<Sha256>(b"mylib");
+        let other = rattler_digest::compute_bytes_digest::<Sha256>(b"otherlib");
+        assert!(verify_hash(&url, "sha256", None, &hash).is_ok());
+        assert!(verify_hash(&url, "sha256", Some(&hash), &hash).is_ok());
+        assert!(matches!(
+            verify_hash(&url, "sha256", Some(&other), &hash),
+            Err(UrlSourceError::HashMismatch {
+                algorithm: "sha256",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_unpack_single_directory() {
+        let temp = tempfile::tempdir().unwrap();
+        let archive_path = temp.path().join("mylib-1.0.tar.gz");
+        {
+            let file = fs_err::File::create(&archive_path).unwrap();
+            let encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+            let mut builder = tar::Builder::new(encoder);
+            let contents = b"[package]\nname = \"mylib\"\n";
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, "mylib-1.0/pixi.toml", &contents[..])
+                .unwrap();
+            builder.into_inner().unwrap().finish().unwrap();
+        }
+
+        let destination = temp.path().join("unpacked");
+        unpack(&archive_path, ArchiveKind::TarGz, &destination).unwrap();
+        let root = source_root(&destination).unwrap();
+        assert_eq!(root, destination.join("mylib-1.0"));
+        assert!(root.join("pixi.toml").is_file());
+    }
+}
@@ -0,0 +1,631 @@
+//! Loads build variants from conda-build style variant config files, either
+//! from the workspace or from the root of a package like
+//! `conda-forge-pinning`.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use async_once_cell::OnceCell as AsyncCell;
+use itertools::Itertools;
+use miette::Diagnostic;
+use parking_lot::Mutex;
+use pixi_build_frontend::{tool::ToolCacheError, IsolatedToolSpec, ToolContext, ToolSpec};
+use pixi_manifest::VariantConfigSource;
+use rattler_conda_types::{ChannelConfig, MatchSpec, NamedChannelOrUrl, PackageName, Platform};
+use thiserror::Error;
+
+/// The file that contains the variant config in the root of a package.
+const PACKAGE_VARIANT_CONFIG_FILE: &str = "conda_build_config.yaml";
+
+/// The value of a null variant. Like conda-build, a `null` in a variant config
+/// file is passed to the build backend as `None`.
+const NULL_VARIANT: &str = "None";
+
+/// The variant config files of the workspace, in the order in which they are
+/// merged.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct VariantConfigFiles {
+    files: Vec<VariantConfigFile>,
+    channels: Vec<NamedChannelOrUrl>,
+
+    /// The path and contents of every file, so a package is only installed
+    /// and a file only read once.
+    contents: Arc<Vec<AsyncCell<(PathBuf, String)>>>,
+
+    /// The merged variant config of every platform that was loaded.
+    loaded: Arc<Mutex<HashMap<Platform, VariantConfig>>>,
+}
+
+#[derive(Debug, Clone)]
+enum VariantConfigFile {
+    Path(PathBuf),
+    Package(PackageName),
+}
+
+/// The variants and zip keys loaded from the variant config files.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(crate) struct VariantConfig {
+    pub variants: HashMap<String, Vec<String>>,
+    pub zip_keys: Vec<Vec<String>>,
+}
+
+#[derive(Debug, Error, Diagnostic)]
+pub enum VariantConfigError {
+    #[error("failed to read the variant config '{}'", .0.display())]
+    Read(PathBuf, #[source] std::io::Error),
+
+    #[error("failed to parse the variant config '{}'", .0.display())]
+    Parse(PathBuf, #[source] ParseVariantConfigError),
+
+    #[error("failed to install '{}' to read its variant config", .0.as_source())]
+    Install(PackageName, #[source] ToolCacheError),
+
+    #[error("the zipped build variants {} of the variant config files have different numbers of values", .0.iter().map(|key| format!("'{key}'")).join(", "))]
+    #[diagnostic(help = "zipped build variants need the same number of values")]
+    ZipKeys(Vec<String>),
+}
+
+#[derive(Debug, Error)]
+pub enum ParseVariantConfigError {
+    #[error(transparent)]
+    Yaml(#[from] serde_yaml::Error),
+
+    #[error("the selector '[{0}]' is not supported, only selectors on the platform like '[linux and aarch64]' are")]
+    UnsupportedSelector(String),
+}
+
+impl VariantConfigFiles {
+    /// Relative paths are resolved against the root of the workspace, and
+    /// packages are fetched from the given channels.
+    pub(crate) fn new(
+        sources: &[VariantConfigSource],
+        workspace_root: &Path,
+        channels: Vec<NamedChannelOrUrl>,
+    ) -> Self {
+        Self {
+            files: sources
+                .iter()
+                .map(|source| match source {
+                    VariantConfigSource::Path(path) => {
+                        VariantConfigFile::Path(workspace_root.join(path))
+                    }
+                    VariantConfigSource::Package(name) => VariantConfigFile::Package(name.clone()),
+                })
+                .collect(),
+            channels,
+            contents: Arc::new(sources.iter().map(|_| AsyncCell::new()).collect()),
+            loaded: Default::default(),
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Loads and merges the variant config files for the given platform. A
+    /// key in a later file overrides the same key in an earlier file.
+    ///
+    /// The result is memoized per platform, so the files are only read and
+    /// parsed once per platform.
+    pub(crate) async fn load(
+        &self,
+        platform: Platform,
+        tool_context: &ToolContext,
+        channel_config: &ChannelConfig,
+    ) -> Result<VariantConfig, VariantConfigError> {
+        if let Some(config) = self.loaded.lock().get(&platform) {
+            return Ok(config.clone());
+        }
+
+        let mut merged = VariantConfig::default();
+        for (file, contents) in self.files.iter().zip(self.contents.iter()) {
+            let (path, contents) = contents
+                .get_or_try_init(self.read(file, tool_context, channel_config))
+                .await?;
+            let config = parse_variant_config(contents, platform)
+                .map_err(|err| VariantConfigError::Parse(path.clone(), err))?;
+            merged.merge(config);
+        }
+        self.loaded.lock().insert(platform, merged.clone());
+        Ok(merged)
+    }
+
+    /// Reads a variant config file, a package is installed first.
+    async fn read(
+        &self,
+        file: &VariantConfigFile,
+        tool_context: &ToolContext,
+        channel_config: &ChannelConfig,
+    ) -> Result<(PathBuf, String), VariantConfigError> {
+        let path = match file {
+            VariantConfigFile::Path(path) => path.clone(),
+            VariantConfigFile::Package(name) => {
+                self.install_package(name, tool_context, channel_config)
+                    .await?
+            }
+        };
+        let contents = fs_err::tokio::read_to_string(&path)
+            .await
+            .map_err(|err| VariantConfigError::Read(path.clone(), err))?;
+        Ok((path, contents))
+    }
+
+    /// Installs the package in an isolated environment and returns the path
+    /// of its variant config file.
+    async fn install_package(
+        &self,
+        name: &PackageName,
+        tool_context: &ToolContext,
+        channel_config: &ChannelConfig,
+    ) -> Result<PathBuf, VariantConfigError> {
+        let spec = IsolatedToolSpec::from_specs(
+            [MatchSpec {
+                name: Some(name.clone()),
+                ..MatchSpec::default()
+            }],
+            self.channels.clone(),
+        )
+        .with_command(name.as_normalized());
+        let tool = tool_context
+            .instantiate(ToolSpec::Isolated(spec), channel_config)
+            .await
+            .map_err(|err| VariantConfigError::Install(name.clone(), err))?;
+        let prefix = tool
+            .as_isolated()
+            .expect("an isolated tool spec always results in an isolated tool")
+            .prefix();
+        Ok(prefix.join(PACKAGE_VARIANT_CONFIG_FILE))
+    }
+}
+
+impl VariantConfig {
+    /// Merges another config into this one, the keys of the other config take
+    /// precedence.
+    fn merge(&mut self, other: VariantConfig) {
+        self.variants.extend(other.variants);
+        for group in other.zip_keys {
+            self.zip_keys
+                .retain(|existing| !existing.iter().any(|key| group.contains(key)));
+            self.zip_keys.push(group);
+        }
+    }
+
+    /// Adds the variants of this config that are not defined by the
+    /// workspace, and its zip keys without the keys that the workspace
+    /// defines or zips itself. A zip group is kept as long as two or more of
+    /// its keys remain, which need to have the same number of values.
+    pub(crate) fn merge_into(
+        self,
+        variants: &mut HashMap<String, Vec<String>>,
+        zip_keys: &mut Vec<Vec<String>>,
+    ) -> Result<(), VariantConfigError> {
+        let defined_keys = variants.keys().cloned().collect::<Vec<_>>();
+        for (key, values) in self.variants {
+            variants.entry(key).or_insert(values);
+        }
+        let zipped_keys = zip_keys.iter().flatten().cloned().collect::<Vec<_>>();
+        for mut group in self.zip_keys {
+            group.retain(|key| {
+                !defined_keys.contains(key)
+                    && !zipped_keys.contains(key)
+                    && variants.contains_key(key)
+            });
+            if group.len() < 2 {
+                continue;
+            }
+            if !group.iter().map(|key| variants[key].len()).all_equal() {
+                return Err(VariantConfigError::ZipKeys(group));
+            }
+            zip_keys.push(group);
+        }
+        Ok(())
+    }
+}
+
+/// Parses a conda-build style variant config. Lines with a selector comment,
+/// e.g. `# [win]`, are only included if the selector matches the platform,
+/// selectors that refer to anything but the platform are an error.
+/// Only keys with a list of values and the `zip_keys` are used, other keys
+/// like `pin_run_as_build` are ignored. A `null` value is a [`NULL_VARIANT`],
+/// so it keeps its position in a zip group.
+pub(crate) fn parse_variant_config(
+    contents: &str,
+    platform: Platform,
+) -> Result<VariantConfig, ParseVariantConfigError> {
+    let mut selected = Vec::new();
+    for line in contents.lines() {
+        let included = match line_selector(line) {
+            Some(selector) => evaluate_selector(selector, platform).ok_or_else(|| {
+                ParseVariantConfigError::UnsupportedSelector(selector.to_string())
+            })?,
+            None => true,
+        };
+        if included {
+            selected.push(line);
+        }
+    }
+    let selected = selected.join("\n");
+
+    let mapping: serde_yaml::Mapping = match serde_yaml::from_str(&selected)? {
+        serde_yaml::Value::Mapping(mapping) => mapping,
+        _ => serde_yaml::Mapping::new(),
+    };
+
+    let mut config = VariantConfig::default();
+    for (key, value) in mapping {
+        let (Some(key), serde_yaml::Value::Sequence(values)) = (key.as_str(), value) else {
+            continue;
+        };
+        if key == "zip_keys" {
+            config.zip_keys = values
+                .iter()
+                .filter_map(|group| group.as_sequence())
+                .map(|group| group.iter().filter_map(scalar_to_string).collect())
+                .collect();
+        } else {
+            config.variants.insert(
+                key.to_string(),
+                values
+                    .iter()
+                    .filter_map(|value| match value {
+                        serde_yaml::Value::Null => Some(NULL_VARIANT.to_string()),
+                        value => scalar_to_string(value),
+                    })
+                    .collect(),
+            );
+        }
+    }
+    Ok(config)
+}
+
+fn scalar_to_string(value: &serde_yaml::Value) -> Option<String> {
+    match value {
+        serde_yaml::Value::String(value) => Some(value.clone()),
+        serde_yaml::Value::Number(value) => Some(value.to_string()),
+        serde_yaml::Value::Bool(value) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+/// Returns the selector of a line, e.g. `win` for `- 3.12  # [win]`.
+fn line_selector(line: &str) -> Option<&str> {
+    let (_, comment) = line.rsplit_once('#')?;
+    let comment = comment.trim();
+    comment.strip_prefix('[')?.strip_suffix(']')
+}
+
+/// Evaluates a selector like `linux and not aarch64`. Returns `None` for
+/// selectors that refer to anything but the platform, which are not
+/// supported.
+fn evaluate_selector(selector: &str, platform: Platform) -> Option<bool> {
+    let tokens = selector.replace('(', " ( ").replace(')', " ) ");
+    let tokens = tokens.split_whitespace().collect::<Vec<_>>();
+    let mut parser = SelectorParser {
+        tokens: &tokens,
+        platform,
+    };
+    parser.or().filter(|_| parser.tokens.is_empty())
+}
+
+/// A recursive descent parser for selectors that evaluates them while parsing.
+struct SelectorParser<'a> {
+    tokens: &'a [&'a str],
+    platform: Platform,
+}
+
+impl<'a> SelectorParser<'a> {
+    fn next(&mut self) -> Option<&'a str> {
+        let (first, rest) = self.tokens.split_first()?;
+        self.tokens = rest;
+        Some(*first)
+    }
+
+    fn or(&mut self) -> Option<bool> {
+        let mut result = self.and()?;
+        while self.tokens.first() == Some(&"or") {
+            self.next();
+            result |= self.and()?;
+        }
+        Some(result)
+    }
+
+    fn and(&mut self) -> Option<bool> {
+        let mut result = self.not()?;
+        while self.tokens.first() == Some(&"and") {
+            self.next();
+            result &= self.not()?;
+        }
+        Some(result)
+    }
+
+    fn not(&mut self) -> Option<bool> {
+        match self.next()? {
+            "not" => Some(!self.not()?),
+            "(" => {
+                let result = self.or()?;
+                (self.next()? == ")").then_some(result)
+            }
+            name => self.name(name),
+        }
+    }
+
+    fn name(&self, name: &str) -> Option<bool> {
+        let platform = self.platform;
+        let arch = platform.arch().map(|arch| arch.to_string());
+        let result = match name {
+            "win" => platform.is_windows(),
+            "linux" => platform.is_linux(),
+            "osx" => platform.is_osx(),
+            "unix" => platform.is_unix(),
+            "x86" => arch.as_deref() == Some("x86"),
+            "x86_64" => arch.as_deref() == Some("x86_64"),
+            "aarch64" | "arm64" | "ppc64le" | "s390x" | "armv6l" | "armv7l" => {
+                arch.as_deref() == Some(name)
+            }
+            "linux64" => platform == Platform::Linux64,
+            "win64" => platform == Platform::Win64,
+            "osx64" => platform == Platform::Osx64,
+            _ => return None,
+        };
+        Some(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VARIANT_CONFIG: &str = r#"
+python:
+  - 3.11.* *_cpython
+  - 3.12.* *_cpython
+numpy:
+  - 1.26
+  - 2.0
+c_compiler:
+  - gcc        # [linux]
+  - clang      # [osx]
+  - vs2019     # [win]
+cuda_compiler_version:
+  - None
+  - 12.4       # [linux64 or (linux and aarch64)]
+pin_run_as_build:
+  python:
+    min_pin: x.x
+zip_keys:
+  - - python
+    - numpy
+"#;
+
+    #[test]
+    fn test_parse_variant_config() {
+        let config = parse_variant_config(VARIANT_CONFIG, Platform::Linux64).unwrap();
+        assert_eq!(config.variants["c_compiler"], vec!["gcc"]);
+        assert_eq!(config.variants["numpy"], vec!["1.26", "2.0"]);
+        assert_eq!(
+            config.variants["cuda_compiler_version"],
+            vec!["None", "12.4"]
+        );
+        assert!(!config.variants.contains_key("pin_run_as_build"));
+        assert_eq!(
+            config.zip_keys,
+            vec![vec!["python".to_string(), "numpy".to_string()]]
+        );
+
+        let config = parse_variant_config(VARIANT_CONFIG, Platform::OsxArm64).unwrap();
+        assert_eq!(config.variants["c_compiler"], vec!["clang"]);
+        assert_eq!(config.variants["cuda_compiler_version"], vec!["None"]);
+    }
+
+    #[test]
+    fn test_parse_null_variant() {
+        let contents = r#"
+cuda_compiler_version:
+  - ~
+  - 12.4
+cuda_compiler:
+  - null
+  - cuda-nvcc
+zip_keys:
+  - - cuda_compiler_version
+    - cuda_compiler
+"#;
+        let config = parse_variant_config(contents, Platform::Linux64).unwrap();
+        assert_eq!(
+            config.variants["cuda_compiler_version"],
+            vec!["None", "12.4"]
+        );
+        assert_eq!(config.variants["cuda_compiler"], vec!["None", "cuda-nvcc"]);
+    }
+
+    #[test]
+    fn test_evaluate_selector() {
+        assert_eq!(evaluate_selector("win", Platform::Win64), Some(true));
+        assert_eq!(
+            evaluate_selector("unix and not osx", Platform::LinuxAarch64),
+            Some(true)
+        );
+        assert_eq!(
+            evaluate_selector("osx and arm64", Platform::OsxArm64),
+            Some(true)
+        );
+        assert_eq!(
+            evaluate_selector("osx and (arm64 or aarch64)", Platform::Osx64),
+            Some(false)
+        );
+        assert_eq!(evaluate_selector("py>=312", Platform::Linux64), None);
+        assert_eq!(evaluate_selector("linux and (", Platform::Linux64), None);
+    }
+
+    #[test]
+    fn test_unsupported_selector() {
+        let contents = r#"
+python:
+  - 3.12
+  - 3.13  # [py>=312]
+"#;
+        let err = parse_variant_config(contents, Platform::Linux64).unwrap_err();
+        assert!(matches!(
+            err,
+            ParseVariantConfigError::UnsupportedSelector(selector) if selector == "py>=312"
+        ));
+    }
+
+    #[test]
+    fn test_merge_into_mismatched_zip_keys() {
+        let contents = r#"
+python:
+  - 3.11
+  - 3.12
+numpy:
+  - 2.0
+zip_keys:
+  - - python
+    - numpy
+"#;
+        let config = parse_variant_config(contents, Platform::Linux64).unwrap();
+        let mut variants = HashMap::new();
+        let mut zip_keys = Vec::new();
+        let err = config.merge_into(&mut variants, &mut zip_keys).unwrap_err();
+        assert!(matches!(
+            err,
+            VariantConfigError::ZipKeys(keys) if keys == ["python", "numpy"]
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_load_path_sources() {
+        let workspace = tempfile::tempdir().unwrap();
+        fs_err::write(workspace.path().join("pinning.yaml"), VARIANT_CONFIG).unwrap();
+        fs_err::write(
+            workspace.path().join("variants.yaml"),
+            "numpy:\n  - 2.1\n  - 2.2\nlibpng:\n  - 1.6  # [win]\n",
+        )
+        .unwrap();
+        let files = VariantConfigFiles::new(
+            &[
+                VariantConfigSource::Path("pinning.yaml".into()),
+                VariantConfigSource::Path("variants.yaml".into()),
+            ],
+            workspace.path(),
+            Vec::new(),
+        );
+        let tool_context = ToolContext::default();
+        let channel_config = ChannelConfig::default_with_root_dir(workspace.path().to_path_buf());
+
+        // A key in a later file overrides the same key in an earlier file.
+        let config = files
+            .load(Platform::Linux64, &tool_context, &channel_config)
+            .await
+            .unwrap();
+        assert_eq!(config.variants["numpy"], vec!["2.1", "2.2"]);
+        assert_eq!(config.variants["c_compiler"], vec!["gcc"]);
+        assert!(!config.variants.contains_key("libpng"));
+        assert_eq!(
+            config.zip_keys,
+            vec![vec!["python".to_string(), "numpy".to_string()]]
+        );
+
+        // The files are only read once, and parsed once per platform.
+        fs_err::remove_file(workspace.path().join("pinning.yaml")).unwrap();
+        fs_err::remove_file(workspace.path().join("variants.yaml")).unwrap();
+        let win_config = files
+            .load(Platform::Win64, &tool_context, &channel_config)
+            .await
+            .unwrap();
+        assert_eq!(win_config.variants["c_compiler"], vec!["vs2019"]);
+        assert_eq!(win_config.variants["libpng"], vec!["1.6"]);
+        assert_eq!(
+            files
+                .load(Platform::Linux64, &tool_context, &channel_config)
+                .await
+                .unwrap(),
+            config
+        );
+
+        // A missing file is an error.
+        let files = VariantConfigFiles::new(
+            &[VariantConfigSource::Path("missing.yaml".into())],
+            workspace.path(),
+            Vec::new(),
+        );
+        let err = files
+            .load(Platform::Linux64, &tool_context, &channel_config)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, VariantConfigError::Read(path, _) if path.ends_with("missing.yaml")));
+    }
+
+    #[test]
+    fn test_merge_into() {
+        let config = VariantConfig {
+            variants: HashMap::from([
+                (
+                    "python".to_string(),
+                    vec!["3.11".to_string(), "3.12".to_string()],
+                ),
+                (
+                    "numpy".to_string(),
+                    vec!["1.26".to_string(), "2.0".to_string()],
+                ),
+                ("libpng".to_string(), vec!["1.6".to_string()]),
+            ]),
+            zip_keys: vec![vec!["python".to_string(), "numpy".to_string()]],
+        };
+        let mut variants = HashMap::from([("python".to_string(), vec!["3.13".to_string()])]);
+        let mut zip_keys = Vec::new();
+        config.merge_into(&mut variants, &mut zip_keys).unwrap();
+        assert_eq!(variants["python"], vec!["3.13"]);
+        assert_eq!(variants["libpng"], vec!["1.6"]);
+        assert!(zip_keys.is_empty());
+    }
+
+    #[test]
+    fn test_merge_into_partially_overridden_zip_keys() {
+        let pinning = r#"
+python:
+  - 3.11.* *_cpython
+  - 3.12.* *_cpython
+  - 3.10.* *_73_pypy
+numpy:
+  - 1.26
+  - 2.0
+  - 1.26
+python_impl:
+  - cpython
+  - cpython
+  - pypy
+zip_keys:
+  - - python
+    - numpy
+    - python_impl
+"#;
+        let config = parse_variant_config(pinning, Platform::Linux64).unwrap();
+        let mut variants = HashMap::from([("python".to_string(), vec!["3.13".to_string()])]);
+        let mut zip_keys = Vec::new();
+        config
+            .clone()
+            .merge_into(&mut variants, &mut zip_keys)
+            .unwrap();
+
+        // The keys that are not overridden stay zipped, so they don't expand
+        // to every combination of their values.
+        assert_eq!(variants["python"], vec!["3.13"]);
+        assert_eq!(
+            zip_keys,
+            vec![vec!["numpy".to_string(), "python_impl".to_string()]]
+        );
+
+        // A group with a single remaining key is dropped.
+        let mut variants = HashMap::from([
+            ("python".to_string(), vec!["3.13".to_string()]),
+            ("numpy".to_string(), vec!["2.1".to_string()]),
+        ]);
+        let mut zip_keys = Vec::new();
+        config.merge_into(&mut variants, &mut zip_keys).unwrap();
+        assert!(zip_keys.is_empty());
+    }
+}
@@ -1,17 +1,28 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Utc};
 use clap::Parser;
+use dialoguer::theme::ColorfulTheme;
 use indexmap::IndexMap;
+use itertools::Itertools;
 use miette::IntoDiagnostic;
-use pixi_manifest::{FeatureName, SpecType};
+use pixi_manifest::{FeatureName, FeaturesExt, SpecType};
+use pixi_progress::await_in_progress;
 use pixi_spec::{GitSpec, SourceSpec};
-use rattler_conda_types::{MatchSpec, PackageName};
+use rattler_conda_types::{Channel, MatchSpec, PackageName, Platform, Version};
 
 use super::has_specs::HasSpecs;
 use crate::{
     cli::cli_config::{DependencyConfig, PrefixUpdateConfig, ProjectConfig},
     environment::verify_prefix_location_unchanged,
-    project::{DependencyType, Project},
+    project::{DependencyType, Environment, MatchSpecs, Project, RSpecs},
+    repodata::Repodata,
 };
 
+/// The number of versions that are offered when selecting a version
+/// interactively.
+const INTERACTIVE_VERSION_COUNT: usize = 10;
+
 /// Adds dependencies to the project
 ///
 /// The dependencies should be defined as MatchSpec for conda package, or a PyPI
@@ -80,6 +91,11 @@ pub struct Args {
     /// Whether the pypi requirement should be editable
     #[arg(long, requires = "pypi")]
     pub editable: bool,
+
+    /// Select the version of packages without a version constraint from the
+    /// most recent versions available in the channels
+    #[arg(long, short = 'i', conflicts_with_all = ["pypi", "r", "git"])]
+    pub interactive: bool,
 }
 
 pub async fn execute(args: Args) -> miette::Result<()> {
@@ -100,7 +116,8 @@ pub async fn execute(args: Args) -> miette::Result<()> {
         .manifest
         .add_platforms(dependency_config.platforms.iter(), &FeatureName::Default)?;
 
-    let (match_specs, source_specs, pypi_deps) = match dependency_config.dependency_type() {
+    let (match_specs, source_specs, pypi_deps, r_specs) = match dependency_config.dependency_type()
+    {
         DependencyType::CondaDependency(spec_type) => {
             // if user passed some git configuration
             // we will use it to create pixi source specs
@@ -110,6 +127,12 @@ pub async fn execute(args: Args) -> miette::Result<()> {
                 .map(|(name, spec)| (name, (spec, spec_type)))
                 .collect();
 
+            let passed_specs = if args.interactive {
+                select_versions_interactively(&project, passed_specs).await?
+            } else {
+                passed_specs
+            };
+
             if let Some(git) = &dependency_config.git {
                 let source_specs = passed_specs
                     .iter()
@@ -121,13 +144,25 @@ pub async fn execute(args: Args) -> miette::Result<()> {
                             git: git.clone(),
                             rev: Some(git_reference),
                             subdirectory: dependency_config.subdir.clone(),
+                            options: Default::default(),
+                            ignore_run_exports: Default::default(),
                         };
                         (name.clone(), (SourceSpec::Git(git_spec), *spec_type))
                     })
                     .collect();
-                (IndexMap::default(), source_specs, IndexMap::default())
+                (
+                    IndexMap::default(),
+                    source_specs,
+                    IndexMap::default(),
+                    IndexMap::default(),
+                )
             } else {
-                (passed_specs, IndexMap::default(), IndexMap::default())
+                (
+                    passed_specs,
+                    IndexMap::default(),
+                    IndexMap::default(),
+                    IndexMap::default(),
+                )
             }
         }
         DependencyType::PypiDependency => {
@@ -148,7 +183,27 @@ pub async fn execute(args: Args) -> miette::Result<()> {
                     .collect(),
             };
 
-            (match_specs, source_specs, pypi_deps)
+            (match_specs, source_specs, pypi_deps, IndexMap::default())
+        }
+        DependencyType::RDependency => {
+            let r_specs: RSpecs = dependency_config
+                .specs()?
+                .into_iter()
+                .map(|(name, spec)| (name.as_source().to_string(), spec.into_nameless().1))
+                .collect();
+            verify_r_packages_available(
+                &project,
+                &r_specs,
+                &dependency_config.feature,
+                &dependency_config.platforms,
+            )
+            .await?;
+            (
+                IndexMap::default(),
+                IndexMap::default(),
+                IndexMap::default(),
+                r_specs,
+            )
         }
     };
     // TODO: add dry_run logic to add
@@ -163,6 +218,7 @@ pub async fn execute(args: Args) -> miette::Result<()> {
             match_specs,
             pypi_deps,
             source_specs,
+            r_specs,
             prefix_update_config,
             &args.dependency_config.feature,
             &args.dependency_config.platforms,
@@ -191,3 +247,217 @@ pub async fn execute(args: Args) -> miette::Result<()> {
     Project::warn_on_discovered_from_env(project_config.manifest_path.as_deref());
     Ok(())
 }
+
+/// Verifies that the conda packages that provide the R packages are available
+/// in the channels of the environments that include the feature they are added
+/// to, for every platform they are added for. A feature that isn't part of any
+/// environment yet is checked against the default environment.
+async fn verify_r_packages_available(
+    project: &Project,
+    r_specs: &RSpecs,
+    feature_name: &FeatureName,
+    platforms: &[Platform],
+) -> miette::Result<()> {
+    let r_mapping = &project.manifest.workspace.r_mapping;
+    let conda_names = r_specs
+        .keys()
+        .map(|r_name| Ok((r_name, r_mapping.conda_name(r_name).into_diagnostic()?)))
+        .collect::<miette::Result<Vec<_>>>()?;
+
+    let mut environments = project
+        .environments()
+        .into_iter()
+        .filter(|environment| environment.features().any(|f| f.name == *feature_name))
+        .collect_vec();
+    if environments.is_empty() {
+        environments.push(project.default_environment());
+    }
+    for environment in environments {
+        verify_r_packages_in_environment(project, &environment, &conda_names, platforms).await?;
+    }
+    Ok(())
+}
+
+/// Verifies that the conda packages that provide the R packages are available
+/// in the channels of the environment.
+async fn verify_r_packages_in_environment(
+    project: &Project,
+    environment: &Environment<'_>,
+    conda_names: &[(&String, PackageName)],
+    platforms: &[Platform],
+) -> miette::Result<()> {
+    let channel_config = project.channel_config();
+    let channels: Vec<Channel> = environment
+        .channels()
+        .into_iter()
+        .cloned()
+        .map(|channel| channel.into_channel(&channel_config))
+        .try_collect()
+        .into_diagnostic()?;
+    let platforms = if platforms.is_empty() {
+        environment.platforms().into_iter().sorted().collect_vec()
+    } else {
+        platforms.to_vec()
+    };
+
+    let repodata = await_in_progress("checking the availability of the R packages", |_| async {
+        project
+            .repodata_gateway()
+            .query(
+                channels.clone(),
+                platforms
+                    .iter()
+                    .copied()
+                    .chain([Platform::NoArch])
+                    .collect_vec(),
+                conda_names
+                    .iter()
+                    .map(|(_, name)| MatchSpec::from(name.clone()))
+                    .collect_vec(),
+            )
+            .await
+            .into_diagnostic()
+    })
+    .await?;
+
+    // The subdirs that every conda package is available for.
+    let mut subdirs: HashMap<&PackageName, HashSet<&str>> = HashMap::new();
+    for record in repodata.iter().flat_map(|repodata| repodata.iter()) {
+        subdirs
+            .entry(&record.package_record.name)
+            .or_default()
+            .insert(record.package_record.subdir.as_str());
+    }
+
+    for (r_name, conda_name) in conda_names {
+        let available = subdirs.get(conda_name);
+        if available.is_some_and(|subdirs| subdirs.contains(Platform::NoArch.as_str())) {
+            continue;
+        }
+        let missing = platforms
+            .iter()
+            .filter(|platform| {
+                !available.is_some_and(|subdirs| subdirs.contains(platform.as_str()))
+            })
+            .collect_vec();
+        if missing.is_empty() {
+            continue;
+        }
+        if missing.len() == platforms.len() {
+            miette::bail!(
+                help = "Map it to the conda package that provides it in the [r-mapping] table",
+                "the R package '{}' is not available as '{}' in the channels of the project",
+                r_name,
+                conda_name.as_source()
+            );
+        }
+        miette::bail!(
+            help = "Add it for the platforms it is available for with `--platform`",
+            "'{}', which provides the R package '{}', is not available for {}",
+            conda_name.as_source(),
+            r_name,
+            missing.iter().format(", ")
+        );
+    }
+
+    Ok(())
+}
+
+/// Lets the user pick a version for every spec that doesn't have a version
+/// constraint yet. The configured pinning strategy is applied to the selected
+/// version.
+async fn select_versions_interactively(
+    project: &Project,
+    match_specs: MatchSpecs,
+) -> miette::Result<MatchSpecs> {
+    let environment = project.default_environment();
+    let channel_config = project.channel_config();
+    let channels: Vec<Channel> = environment
+        .channels()
+        .into_iter()
+        .cloned()
+        .map(|channel| channel.into_channel(&channel_config))
+        .try_collect()
+        .into_diagnostic()?;
+    let platforms = environment
+        .platforms()
+        .into_iter()
+        .chain([Platform::NoArch])
+        .collect_vec();
+
+    let theme = ColorfulTheme {
+        active_item_style: console::Style::new().for_stderr().magenta(),
+        ..ColorfulTheme::default()
+    };
+
+    let mut result = IndexMap::with_capacity(match_specs.len());
+    for (name, (mut spec, spec_type)) in match_specs {
+        if spec.version.is_some() {
+            result.insert(name, (spec, spec_type));
+            continue;
+        }
+
+        let repodata = await_in_progress(
+            format!("querying available versions of {}", name.as_source()),
+            |_| async {
+                project
+                    .repodata_gateway()
+                    .query(
+                        channels.clone(),
+                        platforms.clone(),
+                        [MatchSpec::from(name.clone())],
+                    )
+                    .await
+                    .into_diagnostic()
+            },
+        )
+        .await?;
+
+        // Collect the versions together with the earliest time they were published.
+        let mut versions: HashMap<Version, Option<DateTime<Utc>>> = HashMap::new();
+        for record in repodata.iter().flat_map(|repodata| repodata.iter()) {
+            let timestamp = versions
+                .entry(record.package_record.version.version().clone())
+                .or_insert(record.package_record.timestamp);
+            if let Some(record_timestamp) = record.package_record.timestamp {
+                *timestamp = Some(timestamp.map_or(record_timestamp, |t| t.min(record_timestamp)));
+            }
+        }
+        let versions = versions
+            .into_iter()
+            .sorted_by(|(a, _), (b, _)| b.cmp(a))
+            .take(INTERACTIVE_VERSION_COUNT)
+            .collect_vec();
+        if versions.is_empty() {
+            miette::bail!(
+                "could not find any version of '{}' in the channels of the project",
+                name.as_source()
+            );
+        }
+
+        let items = versions
+            .iter()
+            .map(|(version, timestamp)| match timestamp {
+                Some(timestamp) => format!("{version} ({})", timestamp.format("%Y-%m-%d")),
+                None => version.to_string(),
+            })
+            .collect_vec();
+        let Some(selection) = dialoguer::Select::with_theme(&theme)
+            .with_prompt(format!(
+                "Select the version of {} to add",
+                console::style(name.as_source()).bold()
+            ))
+            .items(&items)
+            .default(0)
+            .interact_opt()
+            .into_diagnostic()?
+        else {
+            miette::bail!("no version selected for '{}'", name.as_source());
+        };
+
+        spec.version = project.conda_version_constraint(&name, [&versions[selection].0]);
+        result.insert(name, (spec, spec_type));
+    }
+
+    Ok(result)
+}
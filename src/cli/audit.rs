@@ -0,0 +1,1098 @@
+use std::{
+    cmp::Ordering,
+    collections::{BTreeSet, HashMap},
+    io::{stdout, Read, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use clap::{Parser, ValueEnum};
+use console::Color;
+use futures::{stream, StreamExt, TryStreamExt};
+use itertools::Itertools;
+use miette::{Context, IntoDiagnostic};
+use pixi_progress::await_in_progress;
+use rattler_conda_types::{PackageRecord, PrefixRecord};
+use rattler_lock::LockedPackageRef;
+use reqwest_middleware::ClientWithMiddleware;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cli::cli_config::{PrefixUpdateConfig, ProjectConfig},
+    lock_file::UpdateLockFileOptions,
+    prefix::Prefix,
+    Project,
+};
+
+/// The url of the OSV vulnerability database api.
+const OSV_API_URL: &str = "https://api.osv.dev/v1";
+
+/// The maximum number of queries in a single OSV batch request.
+const OSV_BATCH_SIZE: usize = 1000;
+
+/// Check the locked packages against the OSV vulnerability database
+///
+/// PyPI packages are checked by their name and version. Conda packages are
+/// checked by their `pkg:conda` package url and through the PyPI distributions
+/// they provide, of which the version is taken from the package url or from the
+/// installed environment.
+#[derive(Debug, Parser)]
+pub struct Args {
+    #[clap(flatten)]
+    pub project_config: ProjectConfig,
+
+    /// The environment to audit, can be repeated. Defaults to all
+    /// environments.
+    #[arg(long, short)]
+    pub environment: Option<Vec<String>>,
+
+    /// Fail if a vulnerability of at least this severity is found.
+    /// Vulnerabilities without a severity always fail.
+    #[arg(long, value_enum)]
+    pub fail_on: Option<Severity>,
+
+    /// Output the vulnerabilities in json format
+    #[arg(long)]
+    pub json: bool,
+
+    /// Check against a downloaded snapshot of the advisory database instead
+    /// of querying the OSV api. This is the `all.zip` of the PyPI ecosystem of
+    /// the OSV export, or a directory with its extracted json files. Conda
+    /// packages are matched against the advisories that affect their
+    /// `pkg:conda` package url.
+    #[arg(long, value_name = "PATH")]
+    pub advisory_db: Option<PathBuf>,
+
+    #[clap(flatten)]
+    pub prefix_update_config: PrefixUpdateConfig,
+}
+
+/// The severity of a vulnerability, as classified by the GitHub advisory
+/// database.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, ValueEnum, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Low,
+    #[value(alias = "medium")]
+    Moderate,
+    High,
+    Critical,
+}
+
+/// The ecosystem of a package that is checked against the database.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Ecosystem {
+    Conda,
+    PyPI,
+}
+
+impl Ecosystem {
+    /// Compares two versions of a package of the ecosystem, `None` if either
+    /// of them can't be parsed.
+    fn cmp_versions(self, a: &str, b: &str) -> Option<Ordering> {
+        match self {
+            Ecosystem::PyPI => Some(
+                pep440_rs::Version::from_str(a)
+                    .ok()?
+                    .cmp(&pep440_rs::Version::from_str(b).ok()?),
+            ),
+            Ecosystem::Conda => Some(
+                rattler_conda_types::Version::from_str(a)
+                    .ok()?
+                    .cmp(&rattler_conda_types::Version::from_str(b).ok()?),
+            ),
+        }
+    }
+}
+
+/// A locked package or distribution that is checked against the database.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct AuditedPackage {
+    ecosystem: Ecosystem,
+    /// The normalized name of the package in its ecosystem.
+    name: String,
+    version: String,
+    /// The name of the conda package that provides the distribution, if it
+    /// isn't a PyPI package.
+    conda_package: Option<String>,
+}
+
+impl AuditedPackage {
+    /// The package url of a conda package, without its version.
+    fn conda_purl(&self) -> String {
+        format!("pkg:conda/{}", self.name)
+    }
+
+    /// Compares the locked version with a version of an advisory, `None` if
+    /// either of them can't be parsed.
+    fn cmp_version(&self, version: &str) -> Option<Ordering> {
+        self.ecosystem.cmp_versions(&self.version, version)
+    }
+}
+
+/// An advisory of the OSV database, only the fields pixi uses.
+#[derive(Debug, Clone, Deserialize)]
+struct Advisory {
+    id: String,
+    #[serde(default)]
+    summary: Option<String>,
+    #[serde(default)]
+    aliases: Vec<String>,
+    #[serde(default)]
+    withdrawn: Option<String>,
+    #[serde(default)]
+    affected: Vec<Affected>,
+    #[serde(default)]
+    database_specific: Option<DatabaseSpecific>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Affected {
+    #[serde(default)]
+    package: Option<AffectedPackage>,
+    #[serde(default)]
+    ranges: Vec<AffectedRange>,
+    #[serde(default)]
+    versions: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct AffectedPackage {
+    ecosystem: String,
+    name: String,
+    #[serde(default)]
+    purl: Option<String>,
+}
+
+impl AffectedPackage {
+    /// Returns the ecosystem and the normalized name of the package, `None`
+    /// if it isn't a PyPI or conda package.
+    fn key(&self) -> Option<(Ecosystem, String)> {
+        if self.ecosystem == "PyPI" {
+            let name = pep508_rs::PackageName::from_str(&self.name).ok()?;
+            return Some((Ecosystem::PyPI, name.to_string()));
+        }
+        let name = self.purl.as_deref()?.strip_prefix("pkg:conda/")?;
+        let name = name.split(['@', '?', '#']).next()?.rsplit('/').next()?;
+        Some((Ecosystem::Conda, name.to_lowercase()))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct AffectedRange {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    events: Vec<RangeEvent>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct RangeEvent {
+    introduced: Option<String>,
+    fixed: Option<String>,
+    last_affected: Option<String>,
+}
+
+impl RangeEvent {
+    /// The version at which the event happens.
+    fn version(&self) -> Option<&str> {
+        self.introduced
+            .as_deref()
+            .or(self.fixed.as_deref())
+            .or(self.last_affected.as_deref())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct DatabaseSpecific {
+    severity: Option<String>,
+}
+
+impl Advisory {
+    fn severity(&self) -> Option<Severity> {
+        self.database_specific
+            .as_ref()
+            .and_then(|specific| specific.severity.as_deref())
+            .and_then(|severity| Severity::from_str(severity, true).ok())
+    }
+
+    /// Returns the entries of the advisory that are about the package.
+    fn affected_entries<'a>(
+        &'a self,
+        package: &'a AuditedPackage,
+    ) -> impl Iterator<Item = &'a Affected> + 'a {
+        self.affected.iter().filter(move |affected| {
+            affected
+                .package
+                .as_ref()
+                .and_then(AffectedPackage::key)
+                .is_some_and(|(ecosystem, name)| {
+                    ecosystem == package.ecosystem && name == package.name
+                })
+        })
+    }
+
+    /// Returns true if the locked version of the package is affected by the
+    /// advisory.
+    fn affects(&self, package: &AuditedPackage) -> bool {
+        self.affected_entries(package).any(|affected| {
+            affected
+                .versions
+                .iter()
+                .any(|v| package.cmp_version(v) == Some(Ordering::Equal))
+                || affected
+                    .ranges
+                    .iter()
+                    .filter(|range| range.kind == "ECOSYSTEM")
+                    .any(|range| range_contains(&range.events, package))
+        })
+    }
+
+    /// Returns the versions in which the vulnerability is fixed.
+    fn fixed_versions(&self, package: &AuditedPackage) -> Vec<String> {
+        self.affected_entries(package)
+            .flat_map(|affected| &affected.ranges)
+            .flat_map(|range| &range.events)
+            .filter_map(|event| event.fixed.clone())
+            .unique()
+            .collect()
+    }
+}
+
+/// Evaluates the events of an OSV range for the locked version of a package.
+/// The events are evaluated in the order of their versions, which the database
+/// doesn't guarantee.
+fn range_contains(events: &[RangeEvent], package: &AuditedPackage) -> bool {
+    let cmp_events = |a: &&RangeEvent, b: &&RangeEvent| match (a.version(), b.version()) {
+        (Some("0"), Some("0")) => Ordering::Equal,
+        (Some("0"), _) => Ordering::Less,
+        (_, Some("0")) => Ordering::Greater,
+        (Some(a), Some(b)) => package
+            .ecosystem
+            .cmp_versions(a, b)
+            .unwrap_or(Ordering::Equal),
+        _ => Ordering::Equal,
+    };
+    let mut affected = false;
+    for event in events.iter().sorted_by(cmp_events) {
+        if let Some(introduced) = &event.introduced {
+            if introduced == "0" || package.cmp_version(introduced).is_some_and(Ordering::is_ge) {
+                affected = true;
+            }
+        }
+        if let Some(fixed) = &event.fixed {
+            if package.cmp_version(fixed).is_some_and(Ordering::is_ge) {
+                affected = false;
+            }
+        }
+        if let Some(last_affected) = &event.last_affected {
+            if package
+                .cmp_version(last_affected)
+                .is_some_and(Ordering::is_gt)
+            {
+                affected = false;
+            }
+        }
+    }
+    affected
+}
+
+/// A vulnerability that affects a locked package.
+#[derive(Debug, Serialize)]
+struct Finding {
+    ecosystem: Ecosystem,
+    package: String,
+    version: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    conda_package: Option<String>,
+    id: String,
+    aliases: Vec<String>,
+    summary: Option<String>,
+    severity: Option<Severity>,
+    fixed_versions: Vec<String>,
+    environments: Vec<String>,
+}
+
+/// A PyPI distribution that a conda package provides: the name and version of
+/// the conda package, and the name of the distribution.
+type ProvidedDistribution = (String, String, pep508_rs::PackageName);
+
+/// Returns the versions of the PyPI distributions that the installed conda
+/// packages provide, from the names of their `.dist-info` directories.
+fn provided_distribution_versions(
+    records: &[PrefixRecord],
+) -> HashMap<ProvidedDistribution, String> {
+    let mut versions = HashMap::new();
+    for record in records {
+        let package_record = &record.repodata_record.package_record;
+        for file in &record.files {
+            if file.file_name().and_then(|name| name.to_str()) != Some("METADATA") {
+                continue;
+            }
+            let Some((name, version)) = file
+                .parent()
+                .and_then(|dir| dir.file_name())
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_suffix(".dist-info"))
+                .and_then(|stem| stem.rsplit_once('-'))
+            else {
+                continue;
+            };
+            let Ok(name) = pep508_rs::PackageName::from_str(name) else {
+                continue;
+            };
+            versions.insert(
+                (
+                    package_record.name.as_normalized().to_string(),
+                    package_record.version.to_string(),
+                    name,
+                ),
+                version.to_string(),
+            );
+        }
+    }
+    versions
+}
+
+/// Returns the PyPI distributions that a conda package provides. Their
+/// version is the one of the package url, or of the installed distribution.
+/// The version of the conda package can differ from the one of the
+/// distribution, so distributions of which the version isn't known are not
+/// checked.
+fn provided_distributions(
+    record: &PackageRecord,
+    provided_versions: &HashMap<ProvidedDistribution, String>,
+) -> Vec<AuditedPackage> {
+    let conda_package = record.name.as_normalized().to_string();
+    record
+        .purls
+        .iter()
+        .flatten()
+        .filter(|purl| purl.package_type() == "pypi")
+        .filter_map(|purl| {
+            let name = pep508_rs::PackageName::from_str(purl.name()).ok()?;
+            let version = purl.version().map(ToString::to_string).or_else(|| {
+                provided_versions
+                    .get(&(
+                        conda_package.clone(),
+                        record.version.to_string(),
+                        name.clone(),
+                    ))
+                    .cloned()
+            });
+            let Some(version) = version else {
+                tracing::debug!(
+                    "skipping the PyPI distribution '{name}' of '{conda_package}', its version is unknown"
+                );
+                return None;
+            };
+            Some(AuditedPackage {
+                ecosystem: Ecosystem::PyPI,
+                name: name.to_string(),
+                version,
+                conda_package: Some(conda_package.clone()),
+            })
+        })
+        .collect()
+}
+
+/// Collects the distributions of the environments that can be checked, with
+/// the environments they are locked in.
+fn collect_packages<'a>(
+    environments: impl IntoIterator<Item = (String, rattler_lock::Environment<'a>)>,
+    provided_versions: &HashMap<ProvidedDistribution, String>,
+) -> HashMap<AuditedPackage, BTreeSet<String>> {
+    let mut packages: HashMap<AuditedPackage, BTreeSet<String>> = HashMap::new();
+    for (env_name, env) in environments {
+        for package in env
+            .packages_by_platform()
+            .flat_map(|(_, packages)| packages)
+        {
+            let audited = match package {
+                LockedPackageRef::Pypi(pypi, _) => vec![AuditedPackage {
+                    ecosystem: Ecosystem::PyPI,
+                    name: pypi.name.to_string(),
+                    version: pypi.version.to_string(),
+                    conda_package: None,
+                }],
+                LockedPackageRef::Conda(conda) => {
+                    let record = conda.record();
+                    let conda_package = record.name.as_normalized().to_string();
+                    std::iter::once(AuditedPackage {
+                        ecosystem: Ecosystem::Conda,
+                        name: conda_package.clone(),
+                        version: record.version.to_string(),
+                        conda_package: Some(conda_package),
+                    })
+                    .chain(provided_distributions(record, provided_versions))
+                    .collect()
+                }
+            };
+            for package in audited {
+                packages
+                    .entry(package)
+                    .or_default()
+                    .insert(env_name.clone());
+            }
+        }
+    }
+    packages
+}
+
+/// Loads the advisories of an OSV export, either the zip file or a directory
+/// with the extracted json files.
+fn load_advisory_db(path: &Path) -> miette::Result<Vec<Advisory>> {
+    let mut contents = Vec::new();
+    if path.is_dir() {
+        for entry in fs_err::read_dir(path).into_diagnostic()? {
+            let path = entry.into_diagnostic()?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                contents.push(fs_err::read(&path).into_diagnostic()?);
+            }
+        }
+    } else {
+        let file = fs_err::File::open(path).into_diagnostic()?;
+        let mut archive = zip::ZipArchive::new(file).into_diagnostic()?;
+        for index in 0..archive.len() {
+            let mut entry = archive.by_index(index).into_diagnostic()?;
+            if !entry.name().ends_with(".json") {
+                continue;
+            }
+            let mut buffer = Vec::new();
+            entry.read_to_end(&mut buffer).into_diagnostic()?;
+            contents.push(buffer);
+        }
+    }
+
+    contents
+        .iter()
+        .map(|content| serde_json::from_slice(content).into_diagnostic())
+        .collect()
+}
+
+/// Matches the packages against the advisories of a downloaded database.
+fn match_offline(
+    packages: &[&AuditedPackage],
+    advisories: &[Advisory],
+) -> HashMap<AuditedPackage, Vec<Advisory>> {
+    let mut by_name: HashMap<(Ecosystem, String), Vec<&Advisory>> = HashMap::new();
+    for advisory in advisories {
+        for affected in &advisory.affected {
+            if let Some(key) = affected.package.as_ref().and_then(AffectedPackage::key) {
+                by_name.entry(key).or_default().push(advisory);
+            }
+        }
+    }
+
+    packages
+        .iter()
+        .filter_map(|package| {
+            let advisories = by_name
+                .get(&(package.ecosystem, package.name.clone()))?
+                .iter()
+                .filter(|advisory| advisory.affects(package))
+                .map(|advisory| (*advisory).clone())
+                .unique_by(|advisory| advisory.id.clone())
+                .collect_vec();
+            (!advisories.is_empty()).then(|| ((*package).clone(), advisories))
+        })
+        .collect()
+}
+
+#[derive(Deserialize)]
+struct BatchResponse {
+    results: Vec<BatchResult>,
+}
+
+#[derive(Deserialize)]
+struct BatchResult {
+    #[serde(default)]
+    vulns: Vec<BatchVulnerability>,
+    /// Set if the package has more advisories than fit in a response, these
+    /// are returned by the same query with this token.
+    #[serde(default)]
+    next_page_token: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct BatchVulnerability {
+    id: String,
+}
+
+/// Returns the OSV query of a package, for the page with the given token.
+fn osv_query(package: &AuditedPackage, page_token: Option<&str>) -> serde_json::Value {
+    let query_package = match package.ecosystem {
+        Ecosystem::PyPI => serde_json::json!({ "name": package.name, "ecosystem": "PyPI" }),
+        Ecosystem::Conda => serde_json::json!({ "purl": package.conda_purl() }),
+    };
+    let mut query = serde_json::json!({
+        "package": query_package,
+        "version": package.version,
+    });
+    if let Some(page_token) = page_token {
+        query["page_token"] = page_token.into();
+    }
+    query
+}
+
+/// Sends a batch of queries to the OSV api.
+async fn query_batch(
+    client: &ClientWithMiddleware,
+    queries: Vec<serde_json::Value>,
+) -> miette::Result<Vec<BatchResult>> {
+    let body = serde_json::to_vec(&serde_json::json!({ "queries": queries }))
+        .expect("the queries can be serialized");
+    let response = client
+        .post(format!("{OSV_API_URL}/querybatch"))
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(body)
+        .send()
+        .await
+        .into_diagnostic()?
+        .error_for_status()
+        .into_diagnostic()?
+        .bytes()
+        .await
+        .into_diagnostic()?;
+    let response: BatchResponse = serde_json::from_slice(&response).into_diagnostic()?;
+    Ok(response.results)
+}
+
+/// Queries the OSV api for the advisories that affect the packages.
+async fn match_online(
+    client: &ClientWithMiddleware,
+    packages: &[&AuditedPackage],
+    concurrency: usize,
+) -> miette::Result<HashMap<AuditedPackage, Vec<Advisory>>> {
+    let mut advisory_ids: HashMap<AuditedPackage, Vec<String>> = HashMap::new();
+    for chunk in packages.chunks(OSV_BATCH_SIZE) {
+        // The packages of which advisories are queried, with the token of the
+        // page of advisories to query.
+        let mut pending: Vec<(&AuditedPackage, Option<String>)> =
+            chunk.iter().map(|package| (*package, None)).collect();
+        while !pending.is_empty() {
+            let queries = pending
+                .iter()
+                .map(|(package, page_token)| osv_query(package, page_token.as_deref()))
+                .collect_vec();
+            let results = query_batch(client, queries).await?;
+            let mut next_pages = Vec::new();
+            for ((package, _), result) in pending.into_iter().zip(results) {
+                if !result.vulns.is_empty() {
+                    advisory_ids
+                        .entry(package.clone())
+                        .or_default()
+                        .extend(result.vulns.into_iter().map(|vuln| vuln.id));
+                }
+                if let Some(page_token) = result.next_page_token {
+                    next_pages.push((package, Some(page_token)));
+                }
+            }
+            pending = next_pages;
+        }
+    }
+
+    let ids = advisory_ids
+        .values()
+        .flatten()
+        .unique()
+        .cloned()
+        .collect_vec();
+    let advisories: HashMap<String, Advisory> = stream::iter(ids)
+        .map(|id| async move {
+            let response = client
+                .get(format!("{OSV_API_URL}/vulns/{id}"))
+                .send()
+                .await
+                .into_diagnostic()?
+                .error_for_status()
+                .into_diagnostic()?
+                .bytes()
+                .await
+                .into_diagnostic()?;
+            let advisory: Advisory = serde_json::from_slice(&response)
+                .into_diagnostic()
+                .with_context(|| format!("failed to parse the advisory '{id}'"))?;
+            Ok::<_, miette::Report>((id, advisory))
+        })
+        .buffer_unordered(concurrency)
+        .try_collect()
+        .await?;
+
+    Ok(advisory_ids
+        .into_iter()
+        .map(|(package, ids)| {
+            let advisories = ids
+                .iter()
+                .filter_map(|id| advisories.get(id).cloned())
+                .collect_vec();
+            (package, advisories)
+        })
+        .collect())
+}
+
+/// Removes the advisories that are aliases of another advisory, preferring
+/// the ones that have a severity. The same vulnerability is often published
+/// by several databases.
+fn deduplicate_advisories(mut advisories: Vec<Advisory>) -> Vec<Advisory> {
+    advisories.sort_by(|a, b| {
+        b.severity()
+            .is_some()
+            .cmp(&a.severity().is_some())
+            .then_with(|| a.id.cmp(&b.id))
+    });
+    let mut seen = BTreeSet::new();
+    advisories
+        .into_iter()
+        .filter(|advisory| advisory.withdrawn.is_none())
+        .filter(|advisory| {
+            let duplicate = seen.contains(&advisory.id)
+                || advisory.aliases.iter().any(|alias| seen.contains(alias));
+            seen.insert(advisory.id.clone());
+            seen.extend(advisory.aliases.iter().cloned());
+            !duplicate
+        })
+        .collect()
+}
+
+fn print_findings_as_table(findings: &[Finding]) -> std::io::Result<()> {
+    let mut writer = tabwriter::TabWriter::new(stdout());
+
+    let header_style = console::Style::new().bold();
+    writeln!(
+        writer,
+        "{}\t{}\t{}\t{}\t{}",
+        header_style.apply_to("Package"),
+        header_style.apply_to("Version"),
+        header_style.apply_to("Advisory"),
+        header_style.apply_to("Severity"),
+        header_style.apply_to("Fixed in"),
+    )?;
+
+    for finding in findings {
+        let package = match &finding.conda_package {
+            Some(conda_package) if *conda_package != finding.package => {
+                format!("{} (conda: {conda_package})", finding.package)
+            }
+            _ => finding.package.clone(),
+        };
+        let severity = match finding.severity {
+            Some(Severity::Critical) => console::style("critical").fg(Color::Red).bold(),
+            Some(Severity::High) => console::style("high").fg(Color::Red),
+            Some(Severity::Moderate) => console::style("moderate").fg(Color::Yellow),
+            Some(Severity::Low) => console::style("low"),
+            None => console::style("unknown").dim(),
+        };
+        writeln!(
+            writer,
+            "{package}\t{}\t{}\t{severity}\t{}",
+            finding.version,
+            finding.id,
+            finding.fixed_versions.join(", "),
+        )?;
+    }
+
+    writer.flush()
+}
+
+pub async fn execute(args: Args) -> miette::Result<()> {
+    let project = Project::load_or_else_discover(args.project_config.manifest_path.as_deref())?
+        .with_cli_config(args.prefix_update_config.config.clone());
+
+    let lock_file = project
+        .update_lock_file(UpdateLockFileOptions {
+            lock_file_usage: args.prefix_update_config.lock_file_usage(),
+            no_install: args.prefix_update_config.no_install,
+            max_concurrent_solves: project.config().max_concurrent_solves(),
+        })
+        .await?
+        .lock_file;
+
+    let environments = match args.environment {
+        Some(names) => names
+            .into_iter()
+            .map(|name| {
+                let env = lock_file
+                    .environment(&name)
+                    .ok_or_else(|| miette::miette!("unknown environment {}", name))?;
+                Ok((name, env))
+            })
+            .collect::<miette::Result<Vec<_>>>()?,
+        None => lock_file
+            .environments()
+            .map(|(name, env)| (name.to_string(), env))
+            .collect(),
+    };
+
+    // The versions of the PyPI distributions that conda packages provide are
+    // read from the installed environments.
+    let mut installed_records = Vec::new();
+    for (name, _) in &environments {
+        let Some(environment) = project.environment(name) else {
+            continue;
+        };
+        let dir = environment.dir();
+        if dir.join("conda-meta").is_dir() {
+            installed_records.extend(Prefix::new(dir).find_installed_packages()?);
+        }
+    }
+    let provided_versions = provided_distribution_versions(&installed_records);
+
+    let packages = collect_packages(environments, &provided_versions);
+    let audited = packages.keys().sorted().collect_vec();
+
+    let matches = if let Some(path) = &args.advisory_db {
+        let advisories = load_advisory_db(path).with_context(|| {
+            format!("failed to load the advisory database '{}'", path.display())
+        })?;
+        match_offline(&audited, &advisories)
+    } else {
+        await_in_progress("querying the OSV vulnerability database", |_| {
+            match_online(
+                project.authenticated_client(),
+                &audited,
+                project.config().max_concurrent_downloads(),
+            )
+        })
+        .await
+        .wrap_err("failed to query the OSV vulnerability database")?
+    };
+
+    let findings = matches
+        .into_iter()
+        .sorted_by(|(a, _), (b, _)| a.cmp(b))
+        .flat_map(|(package, advisories)| {
+            let environments = packages[&package].iter().cloned().collect_vec();
+            deduplicate_advisories(advisories)
+                .into_iter()
+                .map(move |advisory| Finding {
+                    ecosystem: package.ecosystem,
+                    package: package.name.clone(),
+                    version: package.version.clone(),
+                    conda_package: package.conda_package.clone(),
+                    severity: advisory.severity(),
+                    fixed_versions: advisory.fixed_versions(&package),
+                    id: advisory.id,
+                    aliases: advisory.aliases,
+                    summary: advisory.summary,
+                    environments: environments.clone(),
+                })
+        })
+        .collect_vec();
+
+    if args.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&findings).expect("the findings can be serialized")
+        );
+    } else if findings.is_empty() {
+        eprintln!(
+            "{}No known vulnerabilities found in {} packages",
+            console::style(console::Emoji("✔ ", "")).green(),
+            audited.len()
+        );
+    } else {
+        print_findings_as_table(&findings).expect("an io error occurred");
+        eprintln!(
+            "{}Found {} vulnerabilities in {} packages",
+            console::style(console::Emoji("✘ ", "")).red(),
+            findings.len(),
+            findings
+                .iter()
+                .map(|f| (&f.package, &f.version))
+                .unique()
+                .count()
+        );
+    }
+
+    if let Some(fail_on) = args.fail_on {
+        let failing = findings
+            .iter()
+            .filter(|finding| {
+                finding
+                    .severity
+                    .map_or(true, |severity| severity >= fail_on)
+            })
+            .count();
+        if failing > 0 {
+            miette::bail!(
+                "found {failing} vulnerabilities with a severity of at least {}",
+                fail_on
+                    .to_possible_value()
+                    .expect("severities have a name")
+                    .get_name()
+            );
+        }
+    }
+
+    Project::warn_on_discovered_from_env(args.project_config.manifest_path.as_deref());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use http::Extensions;
+    use rattler_conda_types::{PackageName, PackageUrl, Version};
+    use reqwest::Request;
+    use reqwest_middleware::{Middleware, Next};
+
+    use super::*;
+
+    fn advisory(json: serde_json::Value) -> Advisory {
+        serde_json::from_value(json).unwrap()
+    }
+
+    fn pypi_package(name: &str, version: &str) -> AuditedPackage {
+        AuditedPackage {
+            ecosystem: Ecosystem::PyPI,
+            name: name.to_string(),
+            version: version.to_string(),
+            conda_package: None,
+        }
+    }
+
+    /// A middleware that answers the requests instead of the OSV api, and
+    /// records the bodies of the batch queries.
+    struct MockOsv {
+        queries: Arc<Mutex<Vec<serde_json::Value>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Middleware for MockOsv {
+        async fn handle(
+            &self,
+            req: Request,
+            _extensions: &mut Extensions,
+            _next: Next<'_>,
+        ) -> reqwest_middleware::Result<reqwest::Response> {
+            let body = if req.url().path().ends_with("/querybatch") {
+                let query: serde_json::Value =
+                    serde_json::from_slice(req.body().and_then(|b| b.as_bytes()).unwrap()).unwrap();
+                self.queries.lock().unwrap().push(query.clone());
+                // The advisories of `requests` are split over two pages.
+                let results = query["queries"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|query| {
+                        match (
+                            query["package"]["name"].as_str(),
+                            query["page_token"].as_str(),
+                        ) {
+                            (Some("requests"), None) => serde_json::json!({
+                                "vulns": [{ "id": "GHSA-1" }],
+                                "next_page_token": "page-2",
+                            }),
+                            (Some("requests"), Some("page-2")) => {
+                                serde_json::json!({ "vulns": [{ "id": "GHSA-2" }] })
+                            }
+                            _ => serde_json::json!({}),
+                        }
+                    })
+                    .collect_vec();
+                serde_json::json!({ "results": results })
+            } else {
+                let id = req.url().path().rsplit('/').next().unwrap();
+                serde_json::json!({ "id": id })
+            };
+            Ok(reqwest::Response::from(
+                http::Response::builder()
+                    .status(200)
+                    .body(serde_json::to_vec(&body).unwrap())
+                    .unwrap(),
+            ))
+        }
+    }
+
+    #[test]
+    fn test_advisory_affects() {
+        let advisory = advisory(serde_json::json!({
+            "id": "GHSA-test",
+            "database_specific": { "severity": "MODERATE" },
+            "affected": [{
+                "package": { "ecosystem": "PyPI", "name": "Requests" },
+                "ranges": [{
+                    "type": "ECOSYSTEM",
+                    "events": [{ "introduced": "0" }, { "fixed": "2.31.0" }, { "introduced": "3.0" }, { "last_affected": "3.1" }],
+                }],
+                "versions": ["1.0"],
+            }],
+        }));
+        let package = |version: &str| AuditedPackage {
+            ecosystem: Ecosystem::PyPI,
+            name: "requests".to_string(),
+            version: version.to_string(),
+            conda_package: None,
+        };
+        let affects = |version: &str| advisory.affects(&package(version));
+
+        assert!(affects("1.0"));
+        assert!(affects("2.30.0"));
+        assert!(!affects("2.31.0"));
+        assert!(affects("3.1"));
+        assert!(!affects("3.2"));
+        assert_eq!(advisory.severity(), Some(Severity::Moderate));
+        assert_eq!(advisory.fixed_versions(&package("1.0")), ["2.31.0"]);
+    }
+
+    #[test]
+    fn test_advisory_affects_conda_package() {
+        let advisory = advisory(serde_json::json!({
+            "id": "TEST-conda",
+            "affected": [{
+                "package": { "ecosystem": "conda", "name": "openssl", "purl": "pkg:conda/openssl" },
+                "ranges": [{
+                    "type": "ECOSYSTEM",
+                    "events": [{ "introduced": "3.0.0" }, { "fixed": "3.0.13" }],
+                }],
+            }],
+        }));
+        let package = |ecosystem: Ecosystem, version: &str| AuditedPackage {
+            ecosystem,
+            name: "openssl".to_string(),
+            version: version.to_string(),
+            conda_package: Some("openssl".to_string()),
+        };
+
+        assert!(advisory.affects(&package(Ecosystem::Conda, "3.0.12")));
+        assert!(!advisory.affects(&package(Ecosystem::Conda, "3.0.13")));
+        assert!(!advisory.affects(&package(Ecosystem::Conda, "1.1.1w")));
+        assert!(!advisory.affects(&package(Ecosystem::PyPI, "3.0.12")));
+        assert_eq!(
+            package(Ecosystem::Conda, "3.0.12").conda_purl(),
+            "pkg:conda/openssl"
+        );
+    }
+
+    #[test]
+    fn test_deduplicate_advisories() {
+        let advisories = vec![
+            advisory(serde_json::json!({ "id": "PYSEC-1", "aliases": ["GHSA-1"] })),
+            advisory(serde_json::json!({
+                "id": "GHSA-1",
+                "aliases": ["PYSEC-1", "CVE-1"],
+                "database_specific": { "severity": "HIGH" },
+            })),
+            advisory(serde_json::json!({ "id": "PYSEC-2", "withdrawn": "2024-01-01T00:00:00Z" })),
+        ];
+        let ids = deduplicate_advisories(advisories)
+            .into_iter()
+            .map(|advisory| advisory.id)
+            .collect_vec();
+        assert_eq!(ids, ["GHSA-1"]);
+    }
+
+    #[test]
+    fn test_range_contains_unsorted_events() {
+        let events: Vec<RangeEvent> = serde_json::from_value(serde_json::json!([
+            { "introduced": "3.0" },
+            { "fixed": "3.2" },
+            { "fixed": "2.31.0" },
+            { "introduced": "0" },
+        ]))
+        .unwrap();
+        let contains = |version: &str| range_contains(&events, &pypi_package("requests", version));
+
+        assert!(contains("1.0"));
+        assert!(!contains("2.31.0"));
+        assert!(!contains("2.32"));
+        assert!(contains("3.1"));
+        assert!(!contains("3.2"));
+    }
+
+    #[test]
+    fn test_provided_distributions() {
+        let prefix_record: PrefixRecord = serde_json::from_value(serde_json::json!({
+            "name": "pyyaml",
+            "version": "6.0.1",
+            "build": "py312_0",
+            "build_number": 0,
+            "subdir": "linux-64",
+            "fn": "pyyaml-6.0.1-py312_0.conda",
+            "url": "https://conda.anaconda.org/conda-forge/linux-64/pyyaml-6.0.1-py312_0.conda",
+            "channel": "https://conda.anaconda.org/conda-forge/",
+            "files": [
+                "lib/python3.12/site-packages/PyYAML-6.0.1.dist-info/METADATA",
+                "lib/python3.12/site-packages/yaml/__init__.py",
+            ],
+            "paths_data": { "paths_version": 1, "paths": [] },
+        }))
+        .unwrap();
+        let versions = provided_distribution_versions(&[prefix_record]);
+
+        let mut record = PackageRecord::new(
+            PackageName::new_unchecked("pyyaml"),
+            Version::from_str("6.0.1").unwrap(),
+            "py312_0".to_string(),
+        );
+        record.purls = Some(
+            [
+                PackageUrl::from_str("pkg:pypi/pyyaml").unwrap(),
+                PackageUrl::from_str("pkg:pypi/yaml-extra@1.2").unwrap(),
+                PackageUrl::from_str("pkg:pypi/unknown").unwrap(),
+            ]
+            .into_iter()
+            .collect(),
+        );
+
+        let provided = provided_distributions(&record, &versions)
+            .into_iter()
+            .map(|package| (package.name, package.version))
+            .sorted()
+            .collect_vec();
+        assert_eq!(
+            provided,
+            [
+                ("pyyaml".to_string(), "6.0.1".to_string()),
+                ("yaml-extra".to_string(), "1.2".to_string()),
+            ]
+        );
+
+        // The installed distribution is of another version of the package.
+        record.version = Version::from_str("6.0.2").unwrap().into();
+        let provided = provided_distributions(&record, &versions)
+            .into_iter()
+            .map(|package| package.name)
+            .collect_vec();
+        assert_eq!(provided, ["yaml-extra"]);
+    }
+
+    #[tokio::test]
+    async fn test_match_online_follows_pages() {
+        let queries = Arc::new(Mutex::new(Vec::new()));
+        let client = reqwest_middleware::ClientBuilder::new(reqwest::Client::new())
+            .with(MockOsv {
+                queries: queries.clone(),
+            })
+            .build();
+        let requests = pypi_package("requests", "2.30.0");
+        let numpy = pypi_package("numpy", "1.26.0");
+
+        let matches = match_online(&client, &[&requests, &numpy], 1)
+            .await
+            .unwrap();
+
+        let ids = matches[&requests]
+            .iter()
+            .map(|advisory| advisory.id.as_str())
+            .collect_vec();
+        assert_eq!(ids, ["GHSA-1", "GHSA-2"]);
+        assert!(!matches.contains_key(&numpy));
+
+        let queries = queries.lock().unwrap();
+        assert_eq!(queries.len(), 2);
+        assert_eq!(
+            queries[1]["queries"],
+            serde_json::json!([{
+                "package": { "name": "requests", "ecosystem": "PyPI" },
+                "version": "2.30.0",
+                "page_token": "page-2",
+            }])
+        );
+    }
+}
@@ -0,0 +1,272 @@
+use clap::Parser;
+use miette::{Diagnostic, IntoDiagnostic};
+use pixi_config::Config;
+use rattler_networking::{Authentication, AuthenticationStorage};
+use thiserror::Error;
+use url::Url;
+
+use crate::pypi_auth::index_credentials_key;
+
+/// Command line arguments that contain authentication data
+#[derive(Parser, Debug)]
+pub struct LoginArgs {
+    /// The host to authenticate with (e.g. repo.prefix.dev), or the url of
+    /// the index when `--pypi` is used
+    host: String,
+
+    /// The token to use (for authentication with prefix.dev)
+    #[clap(long)]
+    token: Option<String>,
+
+    /// The username to use (for basic HTTP authentication)
+    #[clap(long)]
+    username: Option<String>,
+
+    /// The password to use (for basic HTTP authentication)
+    #[clap(long)]
+    password: Option<String>,
+
+    /// The token to use on anaconda.org / quetz authentication
+    #[clap(long)]
+    conda_token: Option<String>,
+
+    /// Store the credentials for a PyPI index url instead of a conda host
+    #[clap(long)]
+    pypi: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct LogoutArgs {
+    /// The host to remove authentication for, or the url of the index when
+    /// `--pypi` is used
+    host: String,
+
+    /// Remove the credentials of a PyPI index url instead of a conda host
+    #[clap(long)]
+    pypi: bool,
+}
+
+#[derive(Parser, Debug)]
+enum Subcommand {
+    /// Store authentication information for a given host
+    Login(LoginArgs),
+    /// Remove authentication information for a given host
+    Logout(LogoutArgs),
+}
+
+/// Login to prefix.dev or anaconda.org servers to access private channels, or
+/// to private PyPI indexes
+#[derive(Parser, Debug)]
+pub struct Args {
+    #[clap(subcommand)]
+    subcommand: Subcommand,
+}
+
+#[derive(Debug, Error, Diagnostic)]
+enum AuthenticationError {
+    #[error("failed to parse the url '{0}'")]
+    InvalidUrl(String, #[source] url::ParseError),
+
+    #[error("password must be provided when using basic authentication")]
+    MissingPassword,
+
+    #[error("no authentication method provided")]
+    NoAuthenticationMethod,
+
+    #[error("authentication with prefix.dev requires a token")]
+    #[diagnostic(help("use `--token` to provide one"))]
+    PrefixDevBadMethod,
+
+    #[error("authentication with anaconda.org requires a conda token")]
+    #[diagnostic(help("use `--conda-token` to provide one"))]
+    AnacondaOrgBadMethod,
+
+    #[error("authentication with a PyPI index requires a username and a password, or a token")]
+    #[diagnostic(help(
+        "use `--username` and `--password`, or `--token` which is stored with the username `__token__`"
+    ))]
+    PypiBadMethod,
+
+    #[error("failed to interact with the authentication storage system: {0}")]
+    Storage(String),
+}
+
+/// Returns the key under which the credentials of a conda host are stored.
+/// Top-level domains are stored as a wildcard, so they also match their
+/// subdomains.
+fn conda_host_key(host: &str) -> Result<String, AuthenticationError> {
+    let host = if host.contains("://") {
+        Url::parse(host)
+            .map_err(|err| AuthenticationError::InvalidUrl(host.to_string(), err))?
+            .host_str()
+            .unwrap_or_default()
+            .to_string()
+    } else {
+        host.to_string()
+    };
+
+    if host.matches('.').count() == 1 {
+        Ok(format!("*.{host}"))
+    } else {
+        Ok(host)
+    }
+}
+
+/// Returns the key under which the credentials of a PyPI index are stored.
+fn pypi_index_key(index_url: &str) -> Result<String, AuthenticationError> {
+    let url = Url::parse(index_url)
+        .map_err(|err| AuthenticationError::InvalidUrl(index_url.to_string(), err))?;
+    index_credentials_key(&url).ok_or_else(|| {
+        AuthenticationError::InvalidUrl(index_url.to_string(), url::ParseError::EmptyHost)
+    })
+}
+
+fn login(args: LoginArgs, storage: AuthenticationStorage) -> Result<(), AuthenticationError> {
+    let key = if args.pypi {
+        pypi_index_key(&args.host)?
+    } else {
+        conda_host_key(&args.host)?
+    };
+    println!("Authenticating with {key}");
+
+    let auth = if let Some(conda_token) = args.conda_token {
+        Authentication::CondaToken(conda_token)
+    } else if let Some(username) = args.username {
+        let Some(password) = args.password else {
+            return Err(AuthenticationError::MissingPassword);
+        };
+        Authentication::BasicHTTP { username, password }
+    } else if let Some(token) = args.token {
+        if args.pypi {
+            // PyPI compatible indexes expect api tokens as the password of the
+            // `__token__` user.
+            Authentication::BasicHTTP {
+                username: "__token__".to_string(),
+                password: token,
+            }
+        } else {
+            Authentication::BearerToken(token)
+        }
+    } else {
+        return Err(AuthenticationError::NoAuthenticationMethod);
+    };
+
+    if args.pypi {
+        if !matches!(auth, Authentication::BasicHTTP { .. }) {
+            return Err(AuthenticationError::PypiBadMethod);
+        }
+    } else if key.contains("prefix.dev") && !matches!(auth, Authentication::BearerToken(_)) {
+        return Err(AuthenticationError::PrefixDevBadMethod);
+    } else if key.contains("anaconda.org") && !matches!(auth, Authentication::CondaToken(_)) {
+        return Err(AuthenticationError::AnacondaOrgBadMethod);
+    }
+
+    storage
+        .store(&key, &auth)
+        .map_err(|err| AuthenticationError::Storage(format!("{err:#}")))
+}
+
+fn logout(args: LogoutArgs, storage: AuthenticationStorage) -> Result<(), AuthenticationError> {
+    let key = if args.pypi {
+        pypi_index_key(&args.host)?
+    } else {
+        conda_host_key(&args.host)?
+    };
+    println!("Removing authentication for {key}");
+
+    storage
+        .delete(&key)
+        .map_err(|err| AuthenticationError::Storage(format!("{err:#}")))
+}
+
+pub async fn execute(args: Args) -> miette::Result<()> {
+    let storage =
+        pixi_utils::reqwest::authentication_storage(&Config::load_global()).into_diagnostic()?;
+
+    match args.subcommand {
+        Subcommand::Login(args) => login(args, storage)?,
+        Subcommand::Logout(args) => logout(args, storage)?,
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use rattler_networking::authentication_storage::backends::file::FileStorage;
+
+    use super::*;
+
+    fn file_storage(dir: &std::path::Path) -> AuthenticationStorage {
+        let mut storage = AuthenticationStorage::new();
+        storage.add_backend(std::sync::Arc::new(
+            FileStorage::new(dir.join("credentials.json")).unwrap(),
+        ));
+        storage
+    }
+
+    #[test]
+    fn test_conda_host_key() {
+        assert_eq!(conda_host_key("prefix.dev").unwrap(), "*.prefix.dev");
+        assert_eq!(
+            conda_host_key("https://repo.prefix.dev/my-channel").unwrap(),
+            "repo.prefix.dev"
+        );
+    }
+
+    #[test]
+    fn test_pypi_login() {
+        let dir = tempfile::tempdir().unwrap();
+        let index = "https://pypi.example.com/simple/";
+
+        login(
+            LoginArgs {
+                host: index.to_string(),
+                token: Some("pypi-token".to_string()),
+                username: None,
+                password: None,
+                conda_token: None,
+                pypi: true,
+            },
+            file_storage(dir.path()),
+        )
+        .unwrap();
+        assert_eq!(
+            file_storage(dir.path())
+                .get("pypi+https://pypi.example.com/simple")
+                .unwrap(),
+            Some(Authentication::BasicHTTP {
+                username: "__token__".to_string(),
+                password: "pypi-token".to_string(),
+            })
+        );
+
+        let err = login(
+            LoginArgs {
+                host: index.to_string(),
+                token: None,
+                username: None,
+                password: None,
+                conda_token: Some("conda-token".to_string()),
+                pypi: true,
+            },
+            file_storage(dir.path()),
+        )
+        .unwrap_err();
+        assert!(matches!(err, AuthenticationError::PypiBadMethod));
+
+        logout(
+            LogoutArgs {
+                host: index.to_string(),
+                pypi: true,
+            },
+            file_storage(dir.path()),
+        )
+        .unwrap();
+        assert_eq!(
+            file_storage(dir.path())
+                .get("pypi+https://pypi.example.com/simple")
+                .unwrap(),
+            None
+        );
+    }
+}
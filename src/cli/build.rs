@@ -1,39 +1,205 @@
-use std::{path::PathBuf, sync::Arc, time::Duration};
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
 
 use clap::Parser;
+use fancy_display::FancyDisplay;
 use indicatif::ProgressBar;
+use itertools::{Either, Itertools};
 use miette::{Context, IntoDiagnostic};
-use pixi_build_frontend::{BackendOverride, CondaBuildReporter, SetupRequest};
+use pixi_build_frontend::{
+    BackendOverride, CondaBuildReporter, NoopCondaMetadataReporter, Protocol, SetupRequest,
+};
 use pixi_build_types::{
-    procedures::conda_build::CondaBuildParams, ChannelConfiguration, PlatformAndVirtualPackages,
+    procedures::{conda_build::CondaBuildParams, conda_metadata::CondaMetadataParams},
+    BuildProgress, ChannelConfiguration, CondaPackageMetadata, PlatformAndVirtualPackages,
 };
 use pixi_config::ConfigCli;
-use pixi_manifest::FeaturesExt;
-use rattler_conda_types::{GenericVirtualPackage, Platform};
+use pixi_glob::GlobHash;
+use pixi_manifest::{FeaturesExt, PackageTests};
+use pixi_progress::{await_in_progress, global_multi_progress, wrap_in_progress};
+use pixi_spec::PixiSpec;
+use rattler::{
+    install::{IndicatifReporter, Installer},
+    package_cache::PackageCache,
+};
+use rattler_conda_types::{
+    package::IndexJson, Channel, GenericVirtualPackage, MatchSpec, PackageRecord, ParseStrictness,
+    Platform, RepoDataRecord,
+};
+use rattler_digest::{Sha256, Sha256Hash};
+use rattler_shell::shell::{Shell, ShellScript};
+use rattler_solve::{resolvo::Solver, SolverImpl, SolverTask};
+use serde::Serialize;
+use url::Url;
 
 use crate::{
-    cli::cli_config::ProjectConfig,
+    build::{resolve_variant_configuration, VariantConfigFiles},
+    cli::{
+        cli_config::ProjectConfig,
+        shell::{interactive_shell, start_interactive_shell},
+    },
+    environment::{get_update_lock_file_and_prefix, LockFileUsage},
+    lock_file::{SolveFailure, UpdateMode},
+    prefix::Prefix,
     repodata::Repodata,
+    signing::{sign_package, SecretKey},
     utils::{move_file, MoveError},
-    Project,
+    Project, UpdateLockFileOptions,
 };
 
 #[derive(Parser, Debug)]
 #[clap(verbatim_doc_comment)]
 pub struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     #[clap(flatten)]
     pub project_config: ProjectConfig,
 
     #[clap(flatten)]
     pub config_cli: ConfigCli,
 
-    /// The target platform to build for (defaults to the current platform)
+    /// The target platform to build for (defaults to the current platform).
+    /// The build dependencies are still installed for the current platform,
+    /// which allows cross-compiling packages
     #[clap(long, short, default_value_t = Platform::current())]
     pub target_platform: Platform,
 
     /// The output directory to place the build artifacts
     #[clap(long, short, default_value = ".")]
     pub output_dir: PathBuf,
+
+    /// Rebuild the package when its source files change, and update the
+    /// environments that depend on it
+    #[clap(long)]
+    pub watch: bool,
+
+    /// Run the tests of `[package.tests]` against the built packages, in a
+    /// clean environment that contains the package and the test dependencies
+    #[clap(long)]
+    pub test: bool,
+
+    /// When the build fails, keep the work directory of the build backend and
+    /// offer to start a shell in it, with the host environment of the build
+    /// activated
+    #[clap(long, conflicts_with = "watch")]
+    pub debug_shell: bool,
+
+    /// Sign the built packages with the given minisign secret key. The
+    /// signatures are written next to the packages, with the `.minisig`
+    /// extension
+    #[clap(long)]
+    pub sign_key: Option<PathBuf>,
+}
+
+#[derive(Parser, Debug)]
+pub enum Command {
+    #[clap(name = "outputs")]
+    Outputs(OutputsArgs),
+}
+
+/// List the packages that the build backend resolves for every build variant,
+/// without building them.
+///
+/// This shows how the build variants expand and which dependencies every
+/// output ends up with.
+#[derive(Parser, Debug)]
+pub struct OutputsArgs {
+    #[clap(flatten)]
+    pub project_config: ProjectConfig,
+
+    #[clap(flatten)]
+    pub config_cli: ConfigCli,
+
+    /// The target platform to resolve the outputs for (defaults to the
+    /// current platform)
+    #[clap(long, short, default_value_t = Platform::current())]
+    pub target_platform: Platform,
+
+    /// Print the outputs as JSON
+    #[clap(long)]
+    pub json: bool,
+}
+
+/// How often the input files are checked for changes in watch mode.
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The files that never trigger a rebuild in watch mode: the environments, the
+/// repository and the build artifacts that are written to the output directory.
+const WATCH_IGNORE_GLOBS: &[&str] = &[
+    "!.pixi/**",
+    "!.git/**",
+    "!**/*.conda",
+    "!**/*.tar.bz2",
+    "!**/*.minisig",
+    "!**/pixi-build-summary.json",
+];
+
+/// The files that are watched when the build backend doesn't report its input
+/// globs: the files of the project, without the directories that typically
+/// contain build output or caches.
+const DEFAULT_WATCH_GLOBS: &[&str] = &[
+    "**",
+    "!**/target/**",
+    "!**/node_modules/**",
+    "!**/__pycache__/**",
+    "!**/.*/**",
+];
+
+/// How deep the environments of the build backend are searched for in the work
+/// directory of a failed build.
+const DEBUG_SHELL_PREFIX_DEPTH: usize = 3;
+
+/// The name of the file in the output directory that lists the built packages.
+const BUILD_SUMMARY_FILE_NAME: &str = "pixi-build-summary.json";
+
+/// A package that was built, as it is written to the build summary.
+#[derive(Debug, Serialize)]
+struct BuiltPackageSummary {
+    name: String,
+    version: String,
+    build: String,
+    subdir: String,
+    /// The location of the package in the output directory
+    file: PathBuf,
+    /// The build variants with which the package was built
+    variant: BTreeMap<String, String>,
+    /// The result of the package tests, when they were requested with `--test`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tests: Option<TestStatus>,
+    /// The location of the signature of the package, when it was signed with
+    /// `--sign-key`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    signature: Option<PathBuf>,
+}
+
+/// A package that the build backend would build, as it is printed by `pixi
+/// build outputs`.
+#[derive(Debug, Serialize)]
+struct PackageOutput {
+    name: String,
+    version: String,
+    build: String,
+    build_number: u64,
+    subdir: String,
+    depends: Vec<String>,
+    constraints: Vec<String>,
+    /// The build variants with which the package is resolved
+    variant: BTreeMap<String, String>,
+}
+
+/// The result of running the package tests against a built package.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum TestStatus {
+    Passed,
+    Failed,
+    /// The package can't run on the current platform.
+    Skipped,
 }
 
 struct ProgressReporter {
@@ -74,26 +240,553 @@ impl CondaBuildReporter for ProgressReporter {
     fn on_build_output(&self, _operation: usize, line: String) {
         self.progress_bar.suspend(|| eprintln!("{}", line))
     }
+
+    fn on_build_progress(&self, _operation: usize, progress: BuildProgress) {
+        self.progress_bar.set_message(progress.to_string());
+    }
 }
 
 pub async fn execute(args: Args) -> miette::Result<()> {
-    let project = Project::load_or_else_discover(args.project_config.manifest_path.as_deref())?
-        .with_cli_config(args.config_cli);
+    if let Some(Command::Outputs(args)) = args.command {
+        return outputs(args).await;
+    }
+
+    let mut project = Project::load_or_else_discover(args.project_config.manifest_path.as_deref())?
+        .with_cli_config(args.config_cli.clone());
+
+    // Read the key before building, so an invalid key doesn't waste a build.
+    let sign_key = args
+        .sign_key
+        .as_deref()
+        .map(SecretKey::from_path)
+        .transpose()?;
+
+    let mut input_globs = Vec::new();
+    loop {
+        // In watch mode failures don't stop watching, the next change can fix
+        // them.
+        match build(
+            &project,
+            Platform::current(),
+            args.target_platform,
+            &args.output_dir,
+            args.test,
+            args.debug_shell,
+            sign_key.as_ref(),
+        )
+        .await
+        {
+            Ok(globs) if args.watch => {
+                input_globs = globs;
+                if let Err(err) = install_dependent_environments(&project).await {
+                    eprintln!("{err:?}");
+                }
+            }
+            Ok(_) => return Ok(()),
+            Err(err) if args.watch => eprintln!("{err:?}"),
+            Err(err) => return Err(err),
+        }
+
+        eprintln!(
+            "{}Watching for changes in '{}', press Ctrl-C to stop",
+            console::style(console::Emoji("👀 ", "")).dim(),
+            project.root().display()
+        );
+        wait_for_changes(project.root(), &input_globs).await?;
 
+        // Reload the project, the manifest might have changed as well.
+        project =
+            Project::from_path(&project.manifest_path())?.with_cli_config(args.config_cli.clone());
+    }
+}
+
+/// Builds the package of the project for every combination of the build
+/// variants into the output directory, and returns the globs of the files that
+/// the build backend used as input. With `test`, the package tests are run
+/// against every built package. With `debug_shell`, a failed build starts a
+/// shell in its work directory. With `sign_key`, every built package is
+/// signed.
+///
+/// The build dependencies are resolved for the `build_platform` that runs the
+/// build, and the package is built for the `target_platform`.
+async fn build(
+    project: &Project,
+    build_platform: Platform,
+    target_platform: Platform,
+    output_dir: &Path,
+    test: bool,
+    debug_shell: bool,
+    sign_key: Option<&SecretKey>,
+) -> miette::Result<Vec<String>> {
     // TODO: Implement logic to take the source code from a VCS instead of from a
     // local channel so that that information is also encoded in the manifest.
+    let channel_config = project.channel_config();
+    let protocol = setup_protocol(project).await?;
+    let work_dir = create_work_dir(project).await?;
 
-    // Instantiate a protocol for the source directory.
+    let progress = Arc::new(ProgressReporter::new(project.name()));
+    // Build platform virtual packages
+    let build_platform_virtual_packages: Vec<GenericVirtualPackage> = project
+        .default_environment()
+        .virtual_packages(build_platform)
+        .into_iter()
+        .map(GenericVirtualPackage::from)
+        .collect();
+
+    // Host platform virtual packages
+    let host_platform_virtual_packages: Vec<GenericVirtualPackage> = project
+        .default_environment()
+        .virtual_packages(target_platform)
+        .into_iter()
+        .map(GenericVirtualPackage::from)
+        .collect();
+
+    let (variants, zip_keys) = load_build_variants(project, target_platform).await?;
+    let channel_base_urls: Vec<_> = project
+        .default_environment()
+        .channel_urls(&channel_config)
+        .into_diagnostic()?
+        .into_iter()
+        .map(Into::into)
+        .collect();
+
+    // Build the package once for every combination of the build variants.
+    fs_err::create_dir_all(output_dir)
+        .into_diagnostic()
+        .with_context(|| {
+            format!(
+                "failed to create output directory '{0}'",
+                output_dir.display()
+            )
+        })?;
+    let mut summary: Vec<BuiltPackageSummary> = Vec::new();
+    let mut input_globs = Vec::new();
+    for (idx, variant) in variant_combinations(&variants, &zip_keys)?
+        .into_iter()
+        .enumerate()
+    {
+        // The combination has a single value per key.
+        let variant_configuration: HashMap<String, Vec<String>> = variant
+            .iter()
+            .map(|(key, value)| (key.clone(), vec![value.clone()]))
+            .collect();
+        let result = protocol
+            .conda_build(
+                &CondaBuildParams {
+                    build_platform_virtual_packages: Some(build_platform_virtual_packages.clone()),
+                    host_platform: Some(PlatformAndVirtualPackages {
+                        platform: target_platform,
+                        virtual_packages: Some(host_platform_virtual_packages.clone()),
+                    }),
+                    channel_base_urls: Some(channel_base_urls.clone()),
+                    channel_configuration: ChannelConfiguration {
+                        base_url: channel_config.channel_alias.clone(),
+                    },
+                    outputs: None,
+                    editable: false,
+                    work_directory: work_dir.path().join(format!("variant-{idx}")),
+                    variant_configuration: Some(variant_configuration),
+                    variant_zip_keys: None,
+                    ignore_run_exports: None,
+                },
+                progress.clone(),
+            )
+            .await
+            .wrap_err_with(|| {
+                if variant.is_empty() {
+                    "during the building of the project the following error occurred".to_string()
+                } else {
+                    format!(
+                        "during the building of the variant {} the following error occurred",
+                        format_variant(&variant)
+                    )
+                }
+            });
+        let result = match result {
+            Ok(result) => result,
+            Err(err) if debug_shell => {
+                let work_dir = work_dir.into_path();
+                start_debug_shell(project, &work_dir.join(format!("variant-{idx}"))).await?;
+                return Err(err);
+            }
+            Err(err) => return Err(err),
+        };
+
+        // Move the built packages to the output directory.
+        for package in result.packages {
+            // When cross-compiling, the backend should build the package for
+            // the target platform and not for the platform it runs on.
+            if package.subdir != target_platform.as_str()
+                && package.subdir != Platform::NoArch.as_str()
+            {
+                miette::bail!(
+                    "the build backend built '{}' for '{}', but the target platform is '{}'",
+                    package.output_file.display(),
+                    package.subdir,
+                    target_platform
+                );
+            }
+
+            let dest = move_to_output_dir(&package.output_file, output_dir)?;
+            for glob in package.input_globs {
+                if !input_globs.contains(&glob) {
+                    input_globs.push(glob);
+                }
+            }
+
+            // Variants that the package doesn't use result in the same package.
+            if summary.iter().any(|built| built.file == dest) {
+                continue;
+            }
+
+            println!(
+                "{}Successfully built '{}'",
+                console::style(console::Emoji("✔ ", "")).green(),
+                dest.display()
+            );
+            let signature = sign_key
+                .map(|key| sign_package(key, &dest))
+                .transpose()
+                .with_context(|| format!("failed to sign '{}'", dest.display()))?;
+            summary.push(BuiltPackageSummary {
+                name: package.name,
+                version: package.version,
+                build: package.build,
+                subdir: package.subdir,
+                file: dest,
+                variant: variant.clone(),
+                tests: None,
+                signature,
+            });
+        }
+    }
+
+    if test {
+        match project
+            .manifest()
+            .package
+            .as_ref()
+            .and_then(|package| package.tests.as_ref())
+        {
+            Some(tests) => {
+                for (idx, built) in summary.iter_mut().enumerate() {
+                    let test_dir = work_dir.path().join(format!("test-{idx}"));
+                    built.tests = Some(
+                        test_built_package(project, tests, built, build_platform, &test_dir)
+                            .await?,
+                    );
+                }
+            }
+            None => tracing::warn!(
+                "the package doesn't define `[package.tests]`, there are no tests to run"
+            ),
+        }
+    }
+
+    // Write a summary of the built packages, so tools can pick up the artifacts.
+    let summary_path = output_dir.join(BUILD_SUMMARY_FILE_NAME);
+    let summary = serde_json::to_string_pretty(&summary).into_diagnostic()?;
+    fs_err::write(&summary_path, summary)
+        .into_diagnostic()
+        .with_context(|| {
+            format!(
+                "failed to write the build summary to '{}'",
+                summary_path.display()
+            )
+        })?;
+
+    let failed = summary
+        .iter()
+        .filter(|built| built.tests == Some(TestStatus::Failed))
+        .map(|built| format!("'{}'", built.file.display()))
+        .collect_vec();
+    if !failed.is_empty() {
+        miette::bail!("the tests of {} failed", failed.join(", "));
+    }
+
+    Ok(input_globs)
+}
+
+/// Prints the packages that the build backend resolves for every combination of
+/// the build variants.
+async fn outputs(args: OutputsArgs) -> miette::Result<()> {
+    let project = Project::load_or_else_discover(args.project_config.manifest_path.as_deref())?
+        .with_cli_config(args.config_cli);
     let channel_config = project.channel_config();
+    let protocol = setup_protocol(&project).await?;
+    let work_dir = create_work_dir(&project).await?;
+    let build_platform = Platform::current();
+
+    let build_virtual_packages: Vec<GenericVirtualPackage> = project
+        .default_environment()
+        .virtual_packages(build_platform)
+        .into_iter()
+        .map(GenericVirtualPackage::from)
+        .collect();
+    let host_virtual_packages: Vec<GenericVirtualPackage> = project
+        .default_environment()
+        .virtual_packages(args.target_platform)
+        .into_iter()
+        .map(GenericVirtualPackage::from)
+        .collect();
+    let (variants, zip_keys) = load_build_variants(&project, args.target_platform).await?;
+    let channel_base_urls: Vec<_> = project
+        .default_environment()
+        .channel_urls(&channel_config)
+        .into_diagnostic()?
+        .into_iter()
+        .map(Into::into)
+        .collect();
+
+    let mut outputs: Vec<PackageOutput> = Vec::new();
+    for (idx, variant) in variant_combinations(&variants, &zip_keys)?
+        .into_iter()
+        .enumerate()
+    {
+        // The combination has a single value per key.
+        let variant_configuration: HashMap<String, Vec<String>> = variant
+            .iter()
+            .map(|(key, value)| (key.clone(), vec![value.clone()]))
+            .collect();
+        let metadata = protocol
+            .get_conda_metadata(
+                &CondaMetadataParams {
+                    build_platform: Some(PlatformAndVirtualPackages {
+                        platform: build_platform,
+                        virtual_packages: Some(build_virtual_packages.clone()),
+                    }),
+                    host_platform: Some(PlatformAndVirtualPackages {
+                        platform: args.target_platform,
+                        virtual_packages: Some(host_virtual_packages.clone()),
+                    }),
+                    channel_base_urls: Some(channel_base_urls.clone()),
+                    channel_configuration: ChannelConfiguration {
+                        base_url: channel_config.channel_alias.clone(),
+                    },
+                    variant_configuration: Some(variant_configuration),
+                    variant_zip_keys: None,
+                    work_directory: work_dir.path().join(format!("variant-{idx}")),
+                    ignore_run_exports: None,
+                },
+                Arc::new(NoopCondaMetadataReporter),
+            )
+            .await
+            .wrap_err_with(|| {
+                if variant.is_empty() {
+                    "failed to resolve the outputs of the project".to_string()
+                } else {
+                    format!(
+                        "failed to resolve the outputs of the variant {}",
+                        format_variant(&variant)
+                    )
+                }
+            })?;
+
+        add_package_outputs(&mut outputs, &variant, metadata.packages);
+    }
+
+    if args.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&outputs).into_diagnostic()?
+        );
+        return Ok(());
+    }
+
+    write_outputs(&mut std::io::stdout(), &outputs).into_diagnostic()
+}
+
+/// Adds the packages that the backend resolved for a variant to the outputs.
+/// Variants that a package doesn't use result in the same package, these are
+/// only added once.
+fn add_package_outputs(
+    outputs: &mut Vec<PackageOutput>,
+    variant: &BTreeMap<String, String>,
+    packages: Vec<CondaPackageMetadata>,
+) {
+    for package in packages {
+        let output = PackageOutput {
+            name: package.name.as_normalized().to_string(),
+            version: package.version.to_string(),
+            build: package.build,
+            build_number: package.build_number,
+            subdir: package.subdir.to_string(),
+            depends: package.depends,
+            constraints: package.constraints,
+            variant: variant.clone(),
+        };
+        if outputs.iter().any(|existing| {
+            existing.name == output.name
+                && existing.version == output.version
+                && existing.build == output.build
+                && existing.subdir == output.subdir
+        }) {
+            continue;
+        }
+        outputs.push(output);
+    }
+}
+
+/// Writes the outputs in the human-readable format of `pixi build outputs`.
+fn write_outputs(
+    writer: &mut impl std::io::Write,
+    outputs: &[PackageOutput],
+) -> std::io::Result<()> {
+    for output in outputs {
+        writeln!(
+            writer,
+            "{} {} {} ({})",
+            console::style(&output.name).bold(),
+            output.version,
+            output.build,
+            output.subdir
+        )?;
+        if !output.variant.is_empty() {
+            writeln!(writer, "  variant: {}", format_variant(&output.variant))?;
+        }
+        for depend in &output.depends {
+            writeln!(writer, "  depends: {depend}")?;
+        }
+        for constraint in &output.constraints {
+            writeln!(writer, "  constrains: {constraint}")?;
+        }
+    }
+    Ok(())
+}
+
+/// Starts a shell in the work directory of a failed build, so the failure can
+/// be debugged interactively. The host environment of the build is activated,
+/// or the build environment if the backend didn't create a host environment.
+///
+/// The activation is also written to a script in the work directory, so the
+/// user can re-enter the environment later.
+async fn start_debug_shell(project: &Project, work_dir: &Path) -> miette::Result<()> {
+    fs_err::create_dir_all(work_dir).into_diagnostic()?;
+
+    let prefixes = find_prefixes(work_dir, DEBUG_SHELL_PREFIX_DEPTH);
+    let prefix = prefixes
+        .iter()
+        .find(|prefix| {
+            prefix
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().contains("host"))
+        })
+        .or(prefixes.first());
+    let env = match prefix {
+        Some(prefix) => Prefix::new(prefix).run_activation().await?,
+        None => HashMap::new(),
+    };
+
+    let shell = interactive_shell();
+    let mut script = ShellScript::new(shell.clone(), Platform::current());
+    for (key, value) in &env {
+        script.set_env_var(key, value).into_diagnostic()?;
+    }
+    let script_path = work_dir.join(format!("pixi-debug-env.{}", shell.extension()));
+    fs_err::write(&script_path, script.contents().into_diagnostic()?).into_diagnostic()?;
+    let mut source_command = String::new();
+    shell
+        .run_script(&mut source_command, &script_path)
+        .into_diagnostic()?;
 
-    let tool_context = pixi_build_frontend::ToolContext::builder()
+    eprintln!(
+        "The work directory of the failed build is kept at '{}'",
+        work_dir.display()
+    );
+    for prefix in &prefixes {
+        eprintln!("  environment of the build: '{}'", prefix.display());
+    }
+    eprintln!(
+        "Re-enter the environment of the build with:\n\n    cd '{}' && {}\n",
+        work_dir.display(),
+        source_command.trim()
+    );
+
+    let interactive = console::Term::stderr().is_term()
+        && dialoguer::Confirm::new()
+            .with_prompt("Start a shell in the work directory of the build?")
+            .default(true)
+            .interact_opt()
+            .ok()
+            .flatten()
+            == Some(true);
+    if interactive {
+        start_interactive_shell(
+            &env,
+            &format!("{}:debug", project.name()),
+            project.config().change_ps1(),
+            work_dir,
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// Returns the conda environments in `dir`, searching at most `depth` levels
+/// deep.
+fn find_prefixes(dir: &Path, depth: usize) -> Vec<PathBuf> {
+    if dir.join("conda-meta").is_dir() {
+        return vec![dir.to_path_buf()];
+    }
+    if depth == 0 {
+        return Vec::new();
+    }
+    fs_err::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .sorted()
+        .flat_map(|path| find_prefixes(&path, depth - 1))
+        .collect()
+}
+
+/// Creates the context in which the build backend and the packages with
+/// variant configs are installed.
+fn tool_context(project: &Project) -> pixi_build_frontend::ToolContext {
+    pixi_build_frontend::ToolContext::builder()
         .with_gateway(project.repodata_gateway().clone())
+        .with_config(project.config().clone())
         .with_client(project.authenticated_client().clone())
-        .build();
+        .build()
+}
+
+/// Loads the build variants and zip keys of the workspace for the platform,
+/// the variants of the manifest take precedence over the variants from the
+/// variant config files. Both are expanded into the combinations that
+/// `pixi build` builds.
+async fn load_build_variants(
+    project: &Project,
+    platform: Platform,
+) -> miette::Result<(HashMap<String, Vec<String>>, Vec<Vec<String>>)> {
+    let workspace = &project.manifest().workspace.workspace;
+    let mut variants = resolve_variant_configuration(&workspace.build_variants, platform);
+    let mut zip_keys = workspace.build_variants_zip_keys.clone();
+    let files = VariantConfigFiles::new(
+        &workspace.variant_config,
+        project.root(),
+        project
+            .default_environment()
+            .channels()
+            .into_iter()
+            .cloned()
+            .collect(),
+    );
+    if !files.is_empty() {
+        files
+            .load(platform, &tool_context(project), &project.channel_config())
+            .await?
+            .merge_into(&mut variants, &mut zip_keys)?;
+    }
+    Ok((variants, zip_keys))
+}
 
-    let protocol = pixi_build_frontend::BuildFrontend::default()
-        .with_channel_config(channel_config.clone())
-        .with_tool_context(Arc::new(tool_context))
+/// Instantiates the build backend of the package in the project root.
+async fn setup_protocol(project: &Project) -> miette::Result<Protocol> {
+    pixi_build_frontend::BuildFrontend::default()
+        .with_channel_config(project.channel_config())
+        .with_tool_context(Arc::new(tool_context(project)))
         .setup_protocol(SetupRequest {
             source_dir: project.root().to_path_buf(),
             build_tool_override: BackendOverride::from_env(),
@@ -101,10 +794,12 @@ pub async fn execute(args: Args) -> miette::Result<()> {
         })
         .await
         .into_diagnostic()
-        .wrap_err("unable to setup the build-backend to build the project")?;
+        .wrap_err("unable to setup the build-backend to build the project")
+}
 
-    // Construct a temporary directory to build the package in. This path is also
-    // automatically removed after the build finishes.
+/// Constructs a temporary directory in the `.pixi` directory for the build
+/// backend to work in. The directory is removed when it is dropped.
+async fn create_work_dir(project: &Project) -> miette::Result<tempfile::TempDir> {
     let pixi_dir = &project.pixi_dir();
     tokio::fs::create_dir_all(pixi_dir)
         .await
@@ -116,115 +811,705 @@ pub async fn execute(args: Args) -> miette::Result<()> {
             )
         })?;
 
-    let work_dir = tempfile::Builder::new()
+    tempfile::Builder::new()
         .prefix("pixi-build-")
-        .tempdir_in(project.pixi_dir())
+        .tempdir_in(pixi_dir)
         .into_diagnostic()
-        .context("failed to create temporary working directory in the .pixi directory")?;
+        .context("failed to create temporary working directory in the .pixi directory")
+}
 
-    let progress = Arc::new(ProgressReporter::new(project.name()));
-    // Build platform virtual packages
-    let build_platform_virtual_packages: Vec<GenericVirtualPackage> = project
-        .default_environment()
-        .virtual_packages(Platform::current())
+/// Returns the platform for which the test environment of a package built for
+/// `subdir` is created, or `None` if the package can't run on the
+/// `build_platform`. A cross-compiled package can only be tested when the
+/// build platform emulates the target platform.
+fn test_platform(subdir: &str, build_platform: Platform) -> Option<Platform> {
+    match subdir.parse::<Platform>().ok()? {
+        Platform::NoArch => Some(build_platform),
+        platform if platform == build_platform => Some(platform),
+        Platform::Osx64 if build_platform == Platform::OsxArm64 => Some(Platform::Osx64),
+        Platform::Win64 if build_platform == Platform::WinArm64 => Some(Platform::Win64),
+        _ => None,
+    }
+}
+
+/// Runs the commands of the package tests in a clean environment that contains
+/// the built package and the test dependencies. The commands run in the empty
+/// `test_dir`, so they test the installed package and not the source tree. All
+/// the commands have to exit successfully for the tests to pass.
+async fn test_built_package(
+    project: &Project,
+    tests: &PackageTests,
+    built: &BuiltPackageSummary,
+    build_platform: Platform,
+    test_dir: &Path,
+) -> miette::Result<TestStatus> {
+    let Some(platform) = test_platform(&built.subdir, build_platform) else {
+        eprintln!(
+            "{}Skipped the tests of '{}', it can't run on {}",
+            console::style(console::Emoji("⏭ ", "")).yellow(),
+            built.file.display(),
+            build_platform
+        );
+        return Ok(TestStatus::Skipped);
+    };
+
+    let test_prefix = create_test_prefix(project, tests, &built.file, platform)
+        .await
+        .with_context(|| {
+            format!(
+                "failed to create the test environment of '{}'",
+                built.file.display()
+            )
+        })?;
+    let activation_env = Prefix::new(test_prefix.path()).run_activation().await?;
+    let command_env: HashMap<String, String> = std::env::vars().chain(activation_env).collect();
+    fs_err::create_dir_all(test_dir).into_diagnostic()?;
+
+    for command in &tests.commands {
+        let script = deno_task_shell::parser::parse(command.trim()).map_err(|err| {
+            miette::miette!("failed to parse the test command '{command}': {err}")
+        })?;
+        let status_code = deno_task_shell::execute(
+            script,
+            command_env.clone(),
+            test_dir,
+            Default::default(),
+            Default::default(),
+        )
+        .await;
+        if status_code != 0 {
+            eprintln!(
+                "{}The test command '{command}' of '{}' exited with code {status_code}",
+                console::style(console::Emoji("✘ ", "")).red(),
+                built.file.display()
+            );
+            return Ok(TestStatus::Failed);
+        }
+    }
+
+    eprintln!(
+        "{}The tests of '{}' passed",
+        console::style(console::Emoji("✔ ", "")).green(),
+        built.file.display()
+    );
+    Ok(TestStatus::Passed)
+}
+
+/// Solves and installs a temporary environment for the `platform` with the
+/// built package and the dependencies of the package tests. The environment
+/// is removed when the returned directory is dropped.
+async fn create_test_prefix(
+    project: &Project,
+    tests: &PackageTests,
+    package_file: &Path,
+    platform: Platform,
+) -> miette::Result<tempfile::TempDir> {
+    let channel_config = project.channel_config();
+
+    // The built package is installed from the output directory, it is pinned
+    // exactly so the solver can't pick another version from the channels.
+    let index_json: IndexJson =
+        rattler_package_streaming::seek::read_package_file(package_file).into_diagnostic()?;
+    let package_file = dunce::canonicalize(package_file).into_diagnostic()?;
+    let sha256 = rattler_digest::compute_file_digest::<Sha256>(&package_file).into_diagnostic()?;
+    let package_record = PackageRecord::from_index_json(
+        index_json,
+        fs_err::metadata(&package_file).ok().map(|m| m.len()),
+        Some(sha256),
+        None,
+    )
+    .into_diagnostic()?;
+    let package_spec = MatchSpec::from_str(
+        &format!(
+            "{}=={}={}",
+            package_record.name.as_normalized(),
+            package_record.version,
+            package_record.build
+        ),
+        ParseStrictness::Strict,
+    )
+    .into_diagnostic()?;
+    let package_depends = package_record
+        .depends
+        .iter()
+        .map(|depend| MatchSpec::from_str(depend, ParseStrictness::Lenient))
+        .collect::<Result<Vec<_>, _>>()
+        .into_diagnostic()?;
+    let package = RepoDataRecord {
+        url: Url::from_file_path(&package_file)
+            .map_err(|_| miette::miette!("invalid path '{}'", package_file.display()))?,
+        channel: None,
+        file_name: package_file
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        package_record,
+    };
+
+    let mut test_specs = Vec::new();
+    for (name, spec) in &tests.dependencies {
+        match spec.clone().into_source_or_binary() {
+            Either::Left(_) => miette::bail!(
+                "the test dependency '{}' is a source dependency, which is not supported",
+                name.as_source()
+            ),
+            Either::Right(binary) => {
+                let nameless_spec = binary
+                    .try_into_nameless_match_spec(&channel_config)
+                    .into_diagnostic()?;
+                test_specs.push(MatchSpec::from_nameless(nameless_spec, Some(name.clone())));
+            }
+        }
+    }
+
+    let environment = project.default_environment();
+    let channels: Vec<Channel> = environment
+        .channels()
         .into_iter()
-        .map(GenericVirtualPackage::from)
-        .collect();
+        .cloned()
+        .map(|channel| channel.into_channel(&channel_config))
+        .try_collect()
+        .into_diagnostic()?;
+    // Channels whose sharded repodata fails fall back to the full repodata.
+    let platforms = [platform, Platform::NoArch];
+    let query_specs = test_specs
+        .iter()
+        .chain(&package_depends)
+        .cloned()
+        .collect_vec();
+    let repodata = await_in_progress("fetching repodata for the test environment", |_| {
+        project.config().query_with_sharded_fallback(
+            project.authenticated_client(),
+            project.repodata_gateway().clone(),
+            &channels,
+            &platforms,
+            &query_specs,
+            |gateway| {
+                let channels = channels.clone();
+                let query_specs = query_specs.clone();
+                async move {
+                    gateway
+                        .query(channels, platforms, query_specs)
+                        .recursive(true)
+                        .await
+                }
+            },
+        )
+    })
+    .await
+    .into_diagnostic()?;
 
-    // Host platform virtual packages
-    let host_platform_virtual_packages: Vec<GenericVirtualPackage> = project
-        .default_environment()
-        .virtual_packages(args.target_platform)
+    let virtual_packages = environment
+        .virtual_packages(platform)
         .into_iter()
         .map(GenericVirtualPackage::from)
         .collect();
+    let available_packages = repodata
+        .iter()
+        .map(|repodata| repodata.iter().collect_vec())
+        .chain([vec![&package]])
+        .collect_vec();
+    let specs = std::iter::once(package_spec).chain(test_specs).collect();
+    let solved_records = wrap_in_progress("solving the test environment", move || {
+        Solver.solve(SolverTask {
+            specs,
+            virtual_packages,
+            ..SolverTask::from_iter(available_packages)
+        })
+    })
+    .into_diagnostic()
+    .context(SolveFailure(
+        "failed to solve the test environment".to_string(),
+    ))?;
 
-    // Build the individual packages.
-    let result = protocol
-        .conda_build(
-            &CondaBuildParams {
-                build_platform_virtual_packages: Some(build_platform_virtual_packages),
-                host_platform: Some(PlatformAndVirtualPackages {
-                    platform: args.target_platform,
-                    virtual_packages: Some(host_platform_virtual_packages),
-                }),
-                channel_base_urls: Some(
-                    project
-                        .default_environment()
-                        .channel_urls(&channel_config)
-                        .into_diagnostic()?
-                        .into_iter()
-                        .map(Into::into)
-                        .collect(),
-                ),
-                channel_configuration: ChannelConfiguration {
-                    base_url: channel_config.channel_alias,
-                },
-                outputs: None,
-                editable: false,
-                work_directory: work_dir.path().to_path_buf(),
-                variant_configuration: Some(Default::default()),
-            },
-            progress.clone(),
+    let test_prefix = tempfile::Builder::new()
+        .prefix("pixi-test-")
+        .tempdir_in(project.pixi_dir())
+        .into_diagnostic()
+        .context("failed to create the test environment in the .pixi directory")?;
+    Installer::new()
+        .with_download_client(project.authenticated_client().clone())
+        .with_target_platform(platform)
+        .with_reporter(
+            IndicatifReporter::builder()
+                .with_multi_progress(global_multi_progress())
+                .clear_when_done(true)
+                .finish(),
         )
+        .with_package_cache(PackageCache::new(
+            pixi_config::get_cache_dir()?.join(pixi_consts::consts::CONDA_PACKAGE_CACHE_DIR),
+        ))
+        .install(test_prefix.path(), solved_records)
         .await
-        .wrap_err("during the building of the project the following error occurred")?;
-
-    // Move the built packages to the output directory.
-    let output_dir = args.output_dir;
-    for package in result.packages {
-        fs_err::create_dir_all(&output_dir)
-            .into_diagnostic()
-            .with_context(|| {
-                format!(
-                    "failed to create output directory '{0}'",
-                    output_dir.display()
-                )
-            })?;
+        .into_diagnostic()?;
 
-        let file_name = package.output_file.file_name().ok_or_else(|| {
-            miette::miette!(
-                "output file '{0}' does not have a file name",
-                package.output_file.display()
-            )
-        })?;
-        let dest = output_dir.join(file_name);
-        if let Err(err) = move_file(&package.output_file, &dest) {
-            match err {
-                MoveError::CopyFailed(err) => {
-                    return Err(err).into_diagnostic().with_context(|| {
-                        format!(
-                            "failed to copy {} to {}",
-                            package.output_file.display(),
-                            dest.display()
-                        )
-                    });
-                }
-                MoveError::FailedToRemove(e) => {
-                    tracing::warn!(
-                        "failed to remove {} after copying it to the output directory: {}",
-                        package.output_file.display(),
-                        e
-                    );
-                }
-                MoveError::MoveFailed(e) => {
-                    return Err(e).into_diagnostic().with_context(|| {
-                        format!(
-                            "failed to move {} to {}",
-                            package.output_file.display(),
-                            dest.display()
-                        )
-                    })
-                }
-            }
+    Ok(test_prefix)
+}
+
+/// Returns the globs of the files that are watched for changes. Without input
+/// globs of the build backend, the files of the project are watched.
+fn watch_globs(input_globs: &[String]) -> Vec<String> {
+    let globs = if input_globs.is_empty() {
+        DEFAULT_WATCH_GLOBS
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    } else {
+        input_globs.to_vec()
+    };
+    globs
+        .into_iter()
+        .chain(WATCH_IGNORE_GLOBS.iter().map(ToString::to_string))
+        .collect()
+}
+
+/// Computes the hash of the files that match the globs. Hashing walks the
+/// directory tree and reads the files, so it runs on the blocking thread pool.
+async fn watch_hash(root: &Path, globs: &[String]) -> miette::Result<Sha256Hash> {
+    let root = root.to_path_buf();
+    let globs = globs.to_vec();
+    tokio::task::spawn_blocking(move || {
+        GlobHash::from_patterns(&root, globs.iter().map(String::as_str))
+            .map(|glob_hash| glob_hash.hash)
+    })
+    .await
+    .into_diagnostic()?
+    .into_diagnostic()
+}
+
+/// Blocks until the files that match the input globs of the build backend
+/// change.
+async fn wait_for_changes(root: &Path, input_globs: &[String]) -> miette::Result<()> {
+    let globs = watch_globs(input_globs);
+    let initial = watch_hash(root, &globs).await?;
+    loop {
+        tokio::time::sleep(WATCH_INTERVAL).await;
+        if watch_hash(root, &globs).await? != initial {
+            return Ok(());
         }
+    }
+}
 
-        println!(
-            "{}Successfully built '{}'",
+/// Re-installs the environments that depend on the package of the project
+/// through a path dependency, so they use the package that was just built.
+async fn install_dependent_environments(project: &Project) -> miette::Result<()> {
+    let root = dunce::canonicalize(project.root()).into_diagnostic()?;
+    for environment in project.environments() {
+        let depends_on_project = environment
+            .combined_dependencies(Some(environment.best_platform()))
+            .iter_specs()
+            .any(|(_, spec)| match spec {
+                PixiSpec::Path(path) => path
+                    .resolve(project.root())
+                    .ok()
+                    .and_then(|path| dunce::canonicalize(path).ok())
+                    .is_some_and(|path| path == root),
+                _ => false,
+            });
+        if !depends_on_project {
+            continue;
+        }
+
+        get_update_lock_file_and_prefix(
+            &environment,
+            UpdateMode::Revalidate,
+            UpdateLockFileOptions {
+                lock_file_usage: LockFileUsage::Update,
+                no_install: false,
+                max_concurrent_solves: project.config().max_concurrent_solves(),
+            },
+        )
+        .await?;
+        eprintln!(
+            "{}Updated the {} environment",
             console::style(console::Emoji("✔ ", "")).green(),
-            dest.display()
+            environment.name().fancy_display()
         );
     }
-
     Ok(())
 }
+
+/// Moves a built package to the output directory and returns its new location.
+fn move_to_output_dir(output_file: &Path, output_dir: &Path) -> miette::Result<PathBuf> {
+    let file_name = output_file.file_name().ok_or_else(|| {
+        miette::miette!(
+            "output file '{0}' does not have a file name",
+            output_file.display()
+        )
+    })?;
+    let dest = output_dir.join(file_name);
+    if let Err(err) = move_file(output_file, &dest) {
+        match err {
+            MoveError::CopyFailed(err) => {
+                return Err(err).into_diagnostic().with_context(|| {
+                    format!(
+                        "failed to copy {} to {}",
+                        output_file.display(),
+                        dest.display()
+                    )
+                });
+            }
+            MoveError::FailedToRemove(e) => {
+                tracing::warn!(
+                    "failed to remove {} after copying it to the output directory: {}",
+                    output_file.display(),
+                    e
+                );
+            }
+            MoveError::MoveFailed(e) => {
+                return Err(e).into_diagnostic().with_context(|| {
+                    format!(
+                        "failed to move {} to {}",
+                        output_file.display(),
+                        dest.display()
+                    )
+                })
+            }
+        }
+    }
+    Ok(dest)
+}
+
+/// Returns every combination of the values of the build variants. Without
+/// build variants there is a single, empty, combination.
+///
+/// The keys of a zip group are not combined with each other, instead the
+/// first values of the keys form one combination, the second values the next,
+/// and so on. The keys of a group therefore need the same number of values.
+///
+/// A null variant, which is spelled `None` like in conda-build, is a value of
+/// its own: it results in a combination in which the key is `None`.
+fn variant_combinations(
+    variants: &HashMap<String, Vec<String>>,
+    zip_keys: &[Vec<String>],
+) -> miette::Result<Vec<BTreeMap<String, String>>> {
+    // A variant without values would result in no combinations, and thus in
+    // nothing being built.
+    if let Some(key) = variants
+        .iter()
+        .filter(|(_, values)| values.is_empty())
+        .map(|(key, _)| key)
+        .min()
+    {
+        miette::bail!("the build variant '{key}' has no values");
+    }
+
+    // Every dimension is a list of alternatives, and every alternative assigns
+    // a value to one or more keys.
+    let mut dimensions: Vec<Vec<Vec<(&String, &String)>>> = Vec::new();
+    for group in zip_keys {
+        let keys = group
+            .iter()
+            .filter_map(|key| variants.get_key_value(key))
+            .sorted_by(|(a, _), (b, _)| a.cmp(b))
+            .collect_vec();
+        let Some((first_key, first_values)) = keys.first() else {
+            continue;
+        };
+        if let Some((key, values)) = keys
+            .iter()
+            .find(|(_, values)| values.len() != first_values.len())
+        {
+            miette::bail!(
+                help = "zipped build variants need the same number of values",
+                "the build variant '{first_key}' has {} values, but '{key}' which is zipped with it has {}",
+                first_values.len(),
+                values.len()
+            );
+        }
+        dimensions.push(
+            (0..first_values.len())
+                .map(|idx| {
+                    keys.iter()
+                        .map(|&(key, values)| (key, &values[idx]))
+                        .collect()
+                })
+                .collect(),
+        );
+    }
+    for (key, values) in variants {
+        if zip_keys.iter().flatten().any(|zipped| zipped == key) {
+            continue;
+        }
+        dimensions.push(values.iter().map(|value| vec![(key, value)]).collect());
+    }
+    dimensions.sort_by_key(|dimension| {
+        dimension
+            .first()
+            .and_then(|alternative| alternative.first())
+            .map(|&(key, _)| key)
+    });
+
+    Ok(dimensions
+        .into_iter()
+        .fold(vec![BTreeMap::new()], |combinations, alternatives| {
+            combinations
+                .into_iter()
+                .flat_map(|combination| {
+                    alternatives.iter().map(move |alternative| {
+                        let mut combination = combination.clone();
+                        for (key, value) in alternative {
+                            combination.insert(key.to_string(), value.to_string());
+                        }
+                        combination
+                    })
+                })
+                .collect()
+        }))
+}
+
+/// Formats a combination of build variants as `key=value` pairs.
+fn format_variant(variant: &BTreeMap<String, String>) -> String {
+    variant
+        .iter()
+        .map(|(key, value)| format!("{key}={value}"))
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use rattler_conda_types::{NoArchType, PackageName};
+
+    use super::*;
+
+    #[test]
+    fn test_test_platform() {
+        // A package built for another platform can't be tested.
+        assert_eq!(test_platform("linux-aarch64", Platform::Linux64), None);
+        assert_eq!(test_platform("win-64", Platform::Linux64), None);
+
+        // Unless the build platform emulates it, the test environment is
+        // created for the target platform then.
+        assert_eq!(
+            test_platform("osx-64", Platform::OsxArm64),
+            Some(Platform::Osx64)
+        );
+        assert_eq!(
+            test_platform("linux-64", Platform::Linux64),
+            Some(Platform::Linux64)
+        );
+        assert_eq!(
+            test_platform("noarch", Platform::LinuxAarch64),
+            Some(Platform::LinuxAarch64)
+        );
+        assert_eq!(test_platform("not-a-platform", Platform::Linux64), None);
+    }
+
+    #[test]
+    fn test_target_platform_argument() {
+        let args = Args::try_parse_from(["build", "--target-platform", "linux-aarch64"]).unwrap();
+        assert_eq!(args.target_platform, Platform::LinuxAarch64);
+        let args = Args::try_parse_from(["build"]).unwrap();
+        assert_eq!(args.target_platform, Platform::current());
+    }
+
+    #[test]
+    fn test_variant_combinations() {
+        assert_eq!(
+            variant_combinations(&HashMap::new(), &[]).unwrap(),
+            vec![BTreeMap::new()]
+        );
+
+        let variants = HashMap::from([
+            (
+                "python".to_string(),
+                vec!["3.11".to_string(), "3.12".to_string()],
+            ),
+            ("numpy".to_string(), vec!["2".to_string()]),
+        ]);
+        let combinations = variant_combinations(&variants, &[])
+            .unwrap()
+            .iter()
+            .map(format_variant)
+            .collect_vec();
+        assert_eq!(
+            combinations,
+            vec!["numpy=2, python=3.11", "numpy=2, python=3.12"]
+        );
+    }
+
+    #[test]
+    fn test_empty_variant() {
+        let variants = HashMap::from([
+            ("python".to_string(), vec!["3.12".to_string()]),
+            ("numpy".to_string(), vec![]),
+        ]);
+        assert_eq!(
+            variant_combinations(&variants, &[])
+                .unwrap_err()
+                .to_string(),
+            "the build variant 'numpy' has no values"
+        );
+    }
+
+    #[test]
+    fn test_zipped_variant_combinations() {
+        let variants = HashMap::from([
+            (
+                "python".to_string(),
+                vec!["3.11".to_string(), "3.12".to_string()],
+            ),
+            (
+                "numpy".to_string(),
+                vec!["1.26".to_string(), "2.0".to_string()],
+            ),
+            ("cuda".to_string(), vec!["11".to_string(), "12".to_string()]),
+        ]);
+        let zip_keys = vec![vec!["python".to_string(), "numpy".to_string()]];
+        let combinations = variant_combinations(&variants, &zip_keys)
+            .unwrap()
+            .iter()
+            .map(format_variant)
+            .collect_vec();
+        assert_eq!(
+            combinations,
+            vec![
+                "cuda=11, numpy=1.26, python=3.11",
+                "cuda=11, numpy=2.0, python=3.12",
+                "cuda=12, numpy=1.26, python=3.11",
+                "cuda=12, numpy=2.0, python=3.12",
+            ]
+        );
+
+        let variants = HashMap::from([
+            (
+                "python".to_string(),
+                vec!["3.11".to_string(), "3.12".to_string()],
+            ),
+            ("numpy".to_string(), vec!["2.0".to_string()]),
+        ]);
+        assert!(variant_combinations(&variants, &zip_keys).is_err());
+    }
+
+    #[test]
+    fn test_null_variant_combinations() {
+        let variants = HashMap::from([
+            (
+                "cuda_compiler_version".to_string(),
+                vec!["None".to_string(), "12.4".to_string()],
+            ),
+            (
+                "cuda_compiler".to_string(),
+                vec!["None".to_string(), "cuda-nvcc".to_string()],
+            ),
+        ]);
+        let zip_keys = vec![vec![
+            "cuda_compiler_version".to_string(),
+            "cuda_compiler".to_string(),
+        ]];
+        let combinations = variant_combinations(&variants, &zip_keys)
+            .unwrap()
+            .iter()
+            .map(format_variant)
+            .collect_vec();
+        assert_eq!(
+            combinations,
+            vec![
+                "cuda_compiler=None, cuda_compiler_version=None",
+                "cuda_compiler=cuda-nvcc, cuda_compiler_version=12.4",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_find_prefixes() {
+        let work_dir = tempfile::tempdir().unwrap();
+        for prefix in ["work/host_env", "work/build_env", "too/deep/to/find"] {
+            fs_err::create_dir_all(work_dir.path().join(prefix).join("conda-meta")).unwrap();
+        }
+
+        let prefixes = find_prefixes(work_dir.path(), DEBUG_SHELL_PREFIX_DEPTH);
+        assert_eq!(
+            prefixes,
+            vec![
+                work_dir.path().join("work/build_env"),
+                work_dir.path().join("work/host_env")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_watch_globs() {
+        let globs = watch_globs(&["src/**".to_string()]);
+        assert_eq!(globs[0], "src/**");
+        assert!(globs[1..].iter().all(|glob| glob.starts_with('!')));
+
+        // Without input globs the whole project is watched, except for the
+        // directories with build output and caches
+        let globs = watch_globs(&[]);
+        assert_eq!(globs[0], "**");
+        assert!(globs.contains(&"!**/target/**".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_watch_hash() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path();
+        fs_err::create_dir_all(root.join("src")).unwrap();
+        fs_err::write(root.join("src/lib.rs"), "fn main() {}").unwrap();
+        let globs = watch_globs(&["**".to_string()]);
+        let initial = watch_hash(root, &globs).await.unwrap();
+
+        // Build artifacts and environments don't trigger a rebuild
+        fs_err::write(root.join("foo-0.1.0-h123_0.conda"), "package").unwrap();
+        fs_err::write(root.join(BUILD_SUMMARY_FILE_NAME), "[]").unwrap();
+        fs_err::create_dir_all(root.join(".pixi/envs")).unwrap();
+        fs_err::write(root.join(".pixi/envs/file"), "").unwrap();
+        assert_eq!(watch_hash(root, &globs).await.unwrap(), initial);
+
+        // A changed source file does
+        fs_err::write(root.join("src/lib.rs"), "fn main() { }").unwrap();
+        assert_ne!(watch_hash(root, &globs).await.unwrap(), initial);
+    }
+
+    #[test]
+    fn test_package_outputs() {
+        let package = |name: &str, build: &str, depends: &[&str]| CondaPackageMetadata {
+            name: PackageName::new_unchecked(name),
+            version: "0.1.0".parse().unwrap(),
+            build: build.to_string(),
+            build_number: 0,
+            subdir: Platform::Linux64,
+            depends: depends.iter().map(ToString::to_string).collect(),
+            constraints: vec![],
+            license: None,
+            license_family: None,
+            noarch: NoArchType::none(),
+        };
+        let variant = |python: &str| BTreeMap::from([("python".to_string(), python.to_string())]);
+
+        let mut outputs = Vec::new();
+        add_package_outputs(
+            &mut outputs,
+            &variant("3.11"),
+            vec![
+                package("foo", "py311_0", &["python 3.11.*"]),
+                package("foo-data", "0", &[]),
+            ],
+        );
+        // `foo-data` doesn't use the python variant, it is only listed once
+        add_package_outputs(
+            &mut outputs,
+            &variant("3.12"),
+            vec![
+                package("foo", "py312_0", &["python 3.12.*"]),
+                package("foo-data", "0", &[]),
+            ],
+        );
+        assert_eq!(
+            outputs
+                .iter()
+                .map(|output| format!("{}-{}", output.name, output.build))
+                .collect_vec(),
+            ["foo-py311_0", "foo-data-0", "foo-py312_0"]
+        );
+
+        console::set_colors_enabled(false);
+        let mut rendered = Vec::new();
+        write_outputs(&mut rendered, &outputs[..2]).unwrap();
+        assert_eq!(
+            String::from_utf8(rendered).unwrap(),
+            "foo 0.1.0 py311_0 (linux-64)\n  variant: python=3.11\n  depends: python 3.11.*\nfoo-data 0.1.0 0 (linux-64)\n  variant: python=3.11\n"
+        );
+    }
+}
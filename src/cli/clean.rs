@@ -13,6 +13,7 @@ use fs_err::tokio as tokio_fs;
 use indicatif::ProgressBar;
 use miette::IntoDiagnostic;
 use pixi_progress::{global_multi_progress, long_running_progress_style};
+use pixi_utils::to_extended_length;
 use std::str::FromStr;
 
 #[derive(Parser, Debug)]
@@ -57,6 +58,11 @@ pub struct CacheArgs {
     #[arg(long)]
     pub mapping: bool,
 
+    /// Clean only the HTTP cache, for example the responses that were cached
+    /// before `cache.http-backend` was changed.
+    #[arg(long)]
+    pub http: bool,
+
     /// Clean only `exec` cache
     #[arg(long)]
     pub exec: bool,
@@ -69,6 +75,10 @@ pub struct CacheArgs {
     #[arg(long)]
     pub tool: bool,
 
+    /// Clean only the cache of solved environments.
+    #[arg(long)]
+    pub solves: bool,
+
     /// Answer yes to all questions.
     #[clap(short = 'y', long = "yes", alias = "assume-yes")]
     assume_yes: bool,
@@ -153,7 +163,19 @@ async fn clean_cache(args: CacheArgs) -> miette::Result<()> {
         dirs.push(cache_dir.join(consts::CONDA_REPODATA_CACHE_DIR));
     }
     if args.mapping {
-        dirs.push(cache_dir.join(consts::CONDA_PYPI_MAPPING_CACHE_DIR));
+        dirs.push(
+            cache_dir
+                .join(consts::HTTP_CACHE_DIR)
+                .join(consts::CONDA_PYPI_MAPPING_CACHE_DIR),
+        );
+        // The mapping responses were cached outside of the HTTP cache before.
+        let legacy_mapping_dir = cache_dir.join(consts::CONDA_PYPI_MAPPING_CACHE_DIR);
+        if legacy_mapping_dir.exists() {
+            dirs.push(legacy_mapping_dir);
+        }
+    }
+    if args.http {
+        dirs.push(cache_dir.join(consts::HTTP_CACHE_DIR));
     }
     if args.exec {
         dirs.push(cache_dir.join(consts::CACHED_ENVS_DIR));
@@ -164,6 +186,9 @@ async fn clean_cache(args: CacheArgs) -> miette::Result<()> {
         // This will be removed in a future release.
         dirs.push(cache_dir.join(consts::_CACHED_BUILD_ENVS_DIR));
     }
+    if args.solves {
+        dirs.push(cache_dir.join(consts::CACHED_SOLVES_DIR));
+    }
     if dirs.is_empty() && (args.assume_yes || dialoguer::Confirm::new()
                 .with_prompt("No cache types specified using the flags.\nDo you really want to remove all cache directories from your machine?")
                 .interact_opt()
@@ -207,7 +232,7 @@ async fn remove_folder_with_progress(
     ));
 
     // Ignore errors
-    let result = tokio_fs::remove_dir_all(&folder).await;
+    let result = tokio_fs::remove_dir_all(to_extended_length(&folder)).await;
     if let Err(e) = result {
         tracing::info!("Failed to remove folder {:?}: {}", folder, e);
     }
@@ -222,6 +222,12 @@ pub struct DependencyConfig {
     #[arg(long, conflicts_with_all = ["host", "build"])]
     pub pypi: bool,
 
+    /// The specified dependencies are R packages from CRAN, which are added as
+    /// the conda packages that provide them. Conflicts with `host`, `build`,
+    /// `pypi` and `git`
+    #[arg(long, conflicts_with_all = ["host", "build", "pypi", "git"])]
+    pub r: bool,
+
     /// The platform(s) for which the dependency should be modified
     #[arg(long = "platform", short)]
     pub platforms: Vec<Platform>,
@@ -247,6 +253,8 @@ impl DependencyConfig {
     pub(crate) fn dependency_type(&self) -> DependencyType {
         if self.pypi {
             DependencyType::PypiDependency
+        } else if self.r {
+            DependencyType::RDependency
         } else if self.host {
             DependencyType::CondaDependency(SpecType::Host)
         } else if self.build {
@@ -0,0 +1,392 @@
+//! Discovery and execution of external `pixi-<name>` subcommands.
+//!
+//! Any executable on the `PATH` named `pixi-<name>` can be invoked as
+//! `pixi <name>`. Extensions can optionally describe themselves by
+//! responding to `pixi-<name> --pixi-metadata` with a JSON document that
+//! follows [`ExtensionMetadata`].
+
+use std::{
+    collections::BTreeMap,
+    ffi::OsString,
+    io::Write,
+    path::{Path, PathBuf},
+    process::Stdio,
+};
+
+use clap::CommandFactory;
+use is_executable::IsExecutable;
+use itertools::Itertools;
+use miette::{Context, IntoDiagnostic};
+use serde::{Deserialize, Serialize};
+
+use crate::Project;
+
+/// The prefix of executables that are considered pixi extensions.
+const EXTENSION_PREFIX: &str = "pixi-";
+
+/// The argument passed to an extension to request its metadata.
+pub const METADATA_ARG: &str = "--pixi-metadata";
+
+/// The metadata an extension reports when invoked with `--pixi-metadata`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct ExtensionMetadata {
+    /// A short, single line, description of the extension.
+    #[serde(default)]
+    pub description: Option<String>,
+
+    /// The version of the extension.
+    #[serde(default)]
+    pub version: Option<String>,
+
+    /// The subcommands of the extension, used as completion hints.
+    #[serde(default)]
+    pub completions: Vec<String>,
+}
+
+/// An extension found on the `PATH`.
+#[derive(Debug, Clone)]
+pub struct ExternalCommand {
+    /// The name of the subcommand, e.g. `diff` for `pixi-diff`.
+    pub name: String,
+
+    /// The path to the executable.
+    pub path: PathBuf,
+}
+
+impl ExternalCommand {
+    /// Runs the extension with `--pixi-metadata` and parses the result.
+    ///
+    /// Returns `None` if the extension does not implement the metadata
+    /// protocol, which is not an error since the protocol is optional.
+    pub fn metadata(&self) -> Option<ExtensionMetadata> {
+        let output = std::process::Command::new(&self.path)
+            .arg(METADATA_ARG)
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+            .ok()?;
+
+        if !output.status.success() {
+            return None;
+        }
+
+        match serde_json::from_slice(&output.stdout) {
+            Ok(metadata) => Some(metadata),
+            Err(err) => {
+                tracing::debug!(
+                    "invalid metadata reported by '{}': {err}",
+                    self.path.display()
+                );
+                None
+            }
+        }
+    }
+}
+
+/// Returns the name of the subcommand provided by an executable, if the file
+/// name looks like `pixi-<name>`.
+fn extension_name(path: &Path) -> Option<String> {
+    let file_name = if cfg!(windows) {
+        path.file_stem()?
+    } else {
+        path.file_name()?
+    };
+    let name = file_name.to_str()?.strip_prefix(EXTENSION_PREFIX)?;
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+/// Finds all `pixi-<name>` executables on the `PATH`.
+///
+/// If the same extension is found multiple times the first one wins, mirroring
+/// how the shell would resolve it.
+pub fn find_external_commands() -> BTreeMap<String, ExternalCommand> {
+    let mut commands = BTreeMap::new();
+    let Some(path) = std::env::var_os("PATH") else {
+        return commands;
+    };
+
+    for dir in std::env::split_paths(&path) {
+        let Ok(entries) = fs_err::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Some(name) = extension_name(&path) else {
+                continue;
+            };
+            if !path.is_file() || !path.is_executable() {
+                continue;
+            }
+            commands
+                .entry(name.clone())
+                .or_insert(ExternalCommand { name, path });
+        }
+    }
+
+    commands
+}
+
+/// Finds the extension providing the given subcommand.
+pub fn find_external_command(name: &str) -> Option<ExternalCommand> {
+    find_external_commands().remove(name)
+}
+
+/// Returns the environment variables that are passed to an extension so it
+/// doesn't have to rediscover the context pixi is running in.
+fn extension_context_env() -> Vec<(String, String)> {
+    let mut env = Vec::new();
+    if let Ok(exe_path) = std::env::current_exe() {
+        env.push((
+            "PIXI_EXE".to_string(),
+            exe_path.to_string_lossy().into_owned(),
+        ));
+    }
+
+    let Ok(project) = Project::discover() else {
+        return env;
+    };
+    env.push((
+        "PIXI_PROJECT_ROOT".to_string(),
+        project.root().to_string_lossy().into_owned(),
+    ));
+    env.push((
+        "PIXI_PROJECT_MANIFEST".to_string(),
+        project.manifest_path().to_string_lossy().into_owned(),
+    ));
+    if let Ok(environment) = project.environment_from_name_or_env_var(None) {
+        env.push((
+            "PIXI_ENVIRONMENT_NAME".to_string(),
+            environment.name().to_string(),
+        ));
+    }
+    env
+}
+
+/// Executes an external subcommand, the first argument is the name of the
+/// subcommand.
+pub fn execute_external_command(args: Vec<OsString>) -> miette::Result<()> {
+    let Some((name, rest)) = args.split_first() else {
+        miette::bail!("no subcommand provided");
+    };
+    let name = name.to_string_lossy();
+
+    let Some(command) = find_external_command(&name) else {
+        miette::bail!(
+            help = "run `pixi --list` to see all available commands",
+            "no such command: `{name}`"
+        );
+    };
+
+    let status = std::process::Command::new(&command.path)
+        .args(rest)
+        .envs(extension_context_env())
+        .status()
+        .into_diagnostic()
+        .with_context(|| format!("failed to execute '{}'", command.path.display()))?;
+
+    if !status.success() {
+        super::timings::exit(status.code().unwrap_or(1));
+    }
+
+    Ok(())
+}
+
+/// Print the help of pixi, a built-in command or an extension.
+#[derive(clap::Parser, Debug)]
+pub struct HelpArgs {
+    /// The command, and its subcommands, to print the help of.
+    pub command: Vec<String>,
+}
+
+/// Prints the help of pixi followed by the extensions, or the help of the
+/// given command. The help of an extension starts with its metadata followed
+/// by the output of `pixi-<name> --help`.
+pub fn help(args: HelpArgs) -> miette::Result<()> {
+    let mut cli = crate::cli::Args::command();
+    cli.build();
+
+    let Some((name, rest)) = args.command.split_first() else {
+        cli.print_help().into_diagnostic()?;
+        let mut writer = tabwriter::TabWriter::new(std::io::stdout());
+        writeln!(writer).into_diagnostic()?;
+        write_extensions(&mut writer, &find_external_commands())?;
+        return writer.flush().into_diagnostic();
+    };
+
+    if let Some(mut subcommand) = cli.find_subcommand_mut(name) {
+        for name in rest {
+            subcommand = subcommand.find_subcommand_mut(name).ok_or_else(|| {
+                miette::miette!(
+                    help = "run `pixi help` to see all available commands",
+                    "no such command: `{}`",
+                    args.command.join(" ")
+                )
+            })?;
+        }
+        return subcommand.print_long_help().into_diagnostic();
+    }
+
+    let Some(command) = find_external_command(name) else {
+        miette::bail!(
+            help = "run `pixi --list` to see all available commands",
+            "no such command: `{name}`"
+        );
+    };
+
+    let metadata = command.metadata().unwrap_or_default();
+    println!("{}", format_extension_header(&command, &metadata));
+    if let Some(description) = &metadata.description {
+        println!("{description}");
+    }
+    if !metadata.completions.is_empty() {
+        println!(
+            "{} {}",
+            console::style("Subcommands:").bold(),
+            metadata.completions.iter().join(", ")
+        );
+    }
+    println!();
+
+    std::process::Command::new(&command.path)
+        .args(rest)
+        .arg("--help")
+        .envs(extension_context_env())
+        .status()
+        .into_diagnostic()
+        .with_context(|| format!("failed to execute '{}'", command.path.display()))?;
+    Ok(())
+}
+
+/// Formats the name, version and path of an extension.
+fn format_extension_header(command: &ExternalCommand, metadata: &ExtensionMetadata) -> String {
+    let mut header = format!("pixi {}", command.name);
+    if let Some(version) = &metadata.version {
+        header.push_str(&format!(" {version}"));
+    }
+    format!(
+        "{} ({})",
+        console::style(header).bold(),
+        command.path.display()
+    )
+}
+
+/// Writes the extensions with their metadata as a table.
+fn write_extensions(
+    writer: &mut impl Write,
+    external: &BTreeMap<String, ExternalCommand>,
+) -> miette::Result<()> {
+    if external.is_empty() {
+        return Ok(());
+    }
+
+    let header_style = console::Style::new().bold();
+    writeln!(writer, "{}", header_style.apply_to("Extensions:")).into_diagnostic()?;
+    for command in external.values() {
+        let metadata = command.metadata().unwrap_or_default();
+        writeln!(
+            writer,
+            "    {}\t{}\t{}",
+            command.name,
+            metadata.version.as_deref().unwrap_or_default(),
+            metadata
+                .description
+                .unwrap_or_else(|| command.path.display().to_string())
+        )
+        .into_diagnostic()?;
+        if !metadata.completions.is_empty() {
+            writeln!(
+                writer,
+                "    \t\t{}",
+                console::style(metadata.completions.iter().join(", ")).dim()
+            )
+            .into_diagnostic()?;
+        }
+    }
+    Ok(())
+}
+
+/// Prints all built-in commands and extensions, used by `pixi --list`.
+pub fn list_commands() -> miette::Result<()> {
+    let mut writer = tabwriter::TabWriter::new(std::io::stdout());
+    let header_style = console::Style::new().bold();
+
+    writeln!(writer, "{}", header_style.apply_to("Installed commands:")).into_diagnostic()?;
+    let cli = crate::cli::Args::command();
+    for subcommand in cli.get_subcommands().filter(|cmd| !cmd.is_hide_set()) {
+        writeln!(
+            writer,
+            "    {}\t{}",
+            subcommand.get_name(),
+            subcommand
+                .get_about()
+                .map(|about| about.to_string())
+                .unwrap_or_default()
+        )
+        .into_diagnostic()?;
+    }
+
+    write_extensions(&mut writer, &find_external_commands())?;
+
+    writer.flush().into_diagnostic()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extension_name() {
+        assert_eq!(
+            extension_name(Path::new("/usr/bin/pixi-diff")),
+            Some("diff".to_string())
+        );
+        assert_eq!(extension_name(Path::new("/usr/bin/pixi-")), None);
+        assert_eq!(extension_name(Path::new("/usr/bin/pixi")), None);
+        assert_eq!(extension_name(Path::new("/usr/bin/conda-pixi")), None);
+    }
+
+    #[test]
+    fn test_parse_metadata() {
+        let metadata: ExtensionMetadata = serde_json::from_str(
+            r#"{"description": "Show the diff", "version": "0.1.0", "completions": ["lock"]}"#,
+        )
+        .unwrap();
+        assert_eq!(metadata.description.as_deref(), Some("Show the diff"));
+        assert_eq!(metadata.version.as_deref(), Some("0.1.0"));
+        assert_eq!(metadata.completions, vec!["lock".to_string()]);
+
+        // All fields are optional
+        let metadata: ExtensionMetadata = serde_json::from_str("{}").unwrap();
+        assert_eq!(metadata, ExtensionMetadata::default());
+    }
+
+    #[test]
+    fn test_write_extensions() {
+        let external = BTreeMap::from([(
+            "diff".to_string(),
+            ExternalCommand {
+                name: "diff".to_string(),
+                path: PathBuf::from("/non-existent/pixi-diff"),
+            },
+        )]);
+
+        let mut output = Vec::new();
+        write_extensions(&mut output, &external).unwrap();
+        let output = console::strip_ansi_codes(std::str::from_utf8(&output).unwrap()).to_string();
+        assert_eq!(output, "Extensions:\n    diff\t\t/non-existent/pixi-diff\n");
+
+        let mut output = Vec::new();
+        write_extensions(&mut output, &BTreeMap::new()).unwrap();
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    fn test_help_of_unknown_command() {
+        let err = help(HelpArgs {
+            command: vec!["install".to_string(), "non-existent".to_string()],
+        })
+        .unwrap_err();
+        assert_eq!(err.to_string(), "no such command: `install non-existent`");
+    }
+}
@@ -3,7 +3,7 @@ use crate::Project;
 use clap::Parser;
 use miette::{IntoDiagnostic, WrapErr};
 use pixi_config;
-use pixi_config::Config;
+use pixi_config::{Config, FindLinks};
 use pixi_consts::consts;
 use rattler_conda_types::NamedChannelOrUrl;
 use std::{path::PathBuf, str::FromStr};
@@ -290,8 +290,34 @@ fn alter_config(
                     }
                     config.pypi_config.extra_index_urls = new_urls;
                 }
+                "pypi-config.find-links" => {
+                    let input = FindLinks::from_str(&value.expect("value must be provided"))
+                        .map_err(|e| miette::miette!("Invalid URL: {}", e))?;
+                    let mut new_find_links = config.pypi_config().find_links.clone();
+                    if is_prepend {
+                        new_find_links.insert(0, input);
+                    } else {
+                        new_find_links.push(input);
+                    }
+                    config.pypi_config.find_links = new_find_links;
+                }
+                "pypi-config.cache-refresh-packages" => {
+                    let input = value.expect("value must be provided");
+                    let mut new_packages = config.pypi_config().cache_refresh_packages.clone();
+                    if is_prepend {
+                        new_packages.insert(0, input);
+                    } else {
+                        new_packages.push(input);
+                    }
+                    config.pypi_config.cache_refresh_packages = new_packages;
+                }
                 _ => {
-                    let list_keys = ["default-channels", "pypi-config.extra-index-urls"];
+                    let list_keys = [
+                        "default-channels",
+                        "pypi-config.extra-index-urls",
+                        "pypi-config.find-links",
+                        "pypi-config.cache-refresh-packages",
+                    ];
                     let msg_cmd = if is_prepend { "prepend" } else { "append" };
                     return Err(miette::miette!(
                         "{} is only supported for list keys: {}",
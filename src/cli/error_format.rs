@@ -0,0 +1,157 @@
+//! Stable exit codes and machine-readable reporting of the final error.
+//!
+//! Wrappers around pixi can branch on the exit code of the process to find out
+//! what category of failure occurred, and request the final diagnostic as JSON
+//! with `--error-format json`.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use miette::{JSONReportHandler, Report};
+use serde::Serialize;
+
+use crate::{lock_file::SolveFailure, project::ProjectError, task::TaskGraphError};
+
+/// The format in which the final error is reported.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ErrorFormat {
+    /// A human-readable diagnostic.
+    #[default]
+    Human,
+
+    /// A single JSON object on stderr.
+    Json,
+}
+
+static JSON_ERRORS: AtomicBool = AtomicBool::new(false);
+
+/// Sets the global format in which the final error is reported.
+pub(crate) fn set_error_format(format: ErrorFormat) {
+    JSON_ERRORS.store(format == ErrorFormat::Json, Ordering::Relaxed);
+}
+
+/// The category of a failure. Every category maps to a stable exit code.
+///
+/// When a task exits with a non-zero exit code pixi exits with the exit code of
+/// the task instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ErrorCategory {
+    /// Any failure that doesn't fall in one of the other categories.
+    Other,
+
+    /// The manifest could not be found or parsed.
+    Manifest,
+
+    /// An environment could not be solved.
+    Solve,
+
+    /// A network request failed.
+    Network,
+
+    /// A task could not be found or executed.
+    Task,
+}
+
+impl ErrorCategory {
+    /// Determines the category of an error by walking its chain of causes.
+    pub fn from_report(report: &Report) -> Self {
+        if contains::<ProjectError>(report) || contains::<pixi_manifest::TomlError>(report) {
+            ErrorCategory::Manifest
+        } else if contains::<SolveFailure>(report) {
+            ErrorCategory::Solve
+        } else if contains::<reqwest::Error>(report)
+            || contains::<reqwest_middleware::Error>(report)
+        {
+            ErrorCategory::Network
+        } else if contains::<TaskGraphError>(report)
+            || contains::<crate::task::TaskExecutionError>(report)
+            || contains::<crate::cli::run::TaskExecutionError>(report)
+        {
+            ErrorCategory::Task
+        } else {
+            ErrorCategory::Other
+        }
+    }
+
+    /// The exit code of the process for this category. `2` is skipped because
+    /// it is used by clap for invalid command line arguments.
+    pub fn exit_code(self) -> i32 {
+        match self {
+            ErrorCategory::Other => 1,
+            ErrorCategory::Manifest => 3,
+            ErrorCategory::Solve => 4,
+            ErrorCategory::Network => 5,
+            ErrorCategory::Task => 6,
+        }
+    }
+}
+
+/// Returns true if the error or any of its causes is of type `E`.
+fn contains<E: std::error::Error + Send + Sync + 'static>(report: &Report) -> bool {
+    report.downcast_ref::<E>().is_some() || report.chain().any(|err| err.is::<E>())
+}
+
+/// The JSON document written to stderr with `--error-format json`.
+#[derive(Serialize)]
+struct JsonError {
+    exit_code: i32,
+    category: ErrorCategory,
+    diagnostic: serde_json::Value,
+}
+
+/// Reports the error in the requested format and exits the process with the
+/// exit code of the category of the error.
+pub fn exit_with_error(report: Report) -> ! {
+    let category = ErrorCategory::from_report(&report);
+
+    if JSON_ERRORS.load(Ordering::Relaxed) {
+        let mut rendered = String::new();
+        let diagnostic = JSONReportHandler::new()
+            .render_report(&mut rendered, report.as_ref())
+            .ok()
+            .and_then(|_| serde_json::from_str(&rendered).ok())
+            .unwrap_or_else(|| serde_json::json!({ "message": report.to_string() }));
+        let error = JsonError {
+            exit_code: category.exit_code(),
+            category,
+            diagnostic,
+        };
+        eprintln!(
+            "{}",
+            serde_json::to_string(&error).expect("failed to serialize error")
+        );
+    } else {
+        eprintln!("Error: {:?}", report);
+    }
+
+    std::process::exit(category.exit_code())
+}
+
+#[cfg(test)]
+mod tests {
+    use miette::{IntoDiagnostic, WrapErr};
+
+    use super::*;
+
+    #[test]
+    fn test_error_category() {
+        let report = miette::miette!("something went wrong");
+        assert_eq!(ErrorCategory::from_report(&report), ErrorCategory::Other);
+
+        let report = Err::<(), _>(miette::miette!("unsolvable"))
+            .wrap_err(SolveFailure("failed to solve".to_string()))
+            .wrap_err("failed to update the lock file")
+            .unwrap_err();
+        assert_eq!(ErrorCategory::from_report(&report), ErrorCategory::Solve);
+
+        let report = Err::<(), _>(ProjectError::NoFileFound)
+            .wrap_err("failed to load project")
+            .unwrap_err();
+        assert_eq!(ErrorCategory::from_report(&report), ErrorCategory::Manifest);
+
+        let report = Err::<(), _>(std::io::Error::other("oops"))
+            .into_diagnostic()
+            .unwrap_err();
+        assert_eq!(ErrorCategory::from_report(&report), ErrorCategory::Other);
+    }
+}
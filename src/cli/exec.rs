@@ -15,7 +15,7 @@ use rattler_virtual_packages::{VirtualPackage, VirtualPackageOverrides};
 use reqwest_middleware::ClientWithMiddleware;
 
 use super::cli_config::ChannelsConfig;
-use crate::prefix::Prefix;
+use crate::{lock_file::SolveFailure, prefix::Prefix};
 
 /// Run a command in a temporary environment.
 #[derive(Parser, Debug)]
@@ -31,7 +31,7 @@ pub struct Args {
     pub specs: Vec<MatchSpec>,
 
     #[clap(flatten)]
-    channels: ChannelsConfig,
+    pub channels: ChannelsConfig,
 
     /// The platform to create the environment for.
     #[clap(long, short, default_value_t = Platform::current())]
@@ -73,7 +73,7 @@ pub async fn execute(args: Args) -> miette::Result<()> {
         .with_context(|| format!("failed to execute '{}'", &command))?;
 
     // Return the exit code of the command
-    std::process::exit(status.code().unwrap_or(1));
+    super::timings::exit(status.code().unwrap_or(1));
 }
 
 /// Creates a prefix for the `pixi exec` command.
@@ -146,18 +146,37 @@ pub async fn create_exec_prefix(
         args.specs.clone()
     };
 
-    let channels = args.channels.resolve_from_config(config)?;
-
-    // Get the repodata for the specs
-    let repodata = await_in_progress("fetching repodata for environment", |_| async {
-        gateway
-            .query(channels, [args.platform, Platform::NoArch], specs.clone())
-            .recursive(true)
-            .execute()
-            .await
-            .into_diagnostic()
+    let channels = args
+        .channels
+        .resolve_from_config(config)?
+        .into_iter()
+        .collect::<Vec<_>>();
+
+    // Get the repodata for the specs, channels whose sharded repodata fails
+    // fall back to the full repodata.
+    let platforms = [args.platform, Platform::NoArch];
+    let repodata = await_in_progress("fetching repodata for environment", |_| {
+        config.query_with_sharded_fallback(
+            client,
+            gateway,
+            &channels,
+            &platforms,
+            &specs,
+            |gateway| {
+                let channels = channels.clone();
+                let specs = specs.clone();
+                async move {
+                    gateway
+                        .query(channels, platforms, specs)
+                        .recursive(true)
+                        .execute()
+                        .await
+                }
+            },
+        )
     })
     .await
+    .into_diagnostic()
     .context("failed to get repodata")?;
 
     // Determine virtual packages of the current platform
@@ -185,7 +204,7 @@ pub async fn create_exec_prefix(
         })
     })
     .into_diagnostic()
-    .context("failed to solve environment")?;
+    .context(SolveFailure("failed to solve environment".to_string()))?;
 
     // Install the environment
     Installer::new()
@@ -0,0 +1,385 @@
+use std::collections::{HashMap, VecDeque};
+
+use clap::Parser;
+use console::Color;
+use fancy_display::FancyDisplay;
+use itertools::Itertools;
+use miette::WrapErr;
+use pixi_manifest::FeaturesExt;
+use rattler_conda_types::Platform;
+use rattler_lock::LockedPackageRef;
+
+use crate::{
+    cli::cli_config::{PrefixUpdateConfig, ProjectConfig},
+    lock_file::{dependency_name, UpdateLockFileOptions},
+    project::Environment,
+    Project,
+};
+
+/// Explain why a package is part of an environment.
+///
+/// Shows the direct dependencies that pulled the package in, the constraints
+/// that bound its version and, for environments in a solve-group, the
+/// constraints placed on it by the other environments in the group.
+#[derive(Debug, Parser)]
+pub struct Args {
+    /// The name of the package to explain.
+    pub package: String,
+
+    /// The platform to explain the package for. Defaults to the current
+    /// platform.
+    #[arg(long, short)]
+    pub platform: Option<Platform>,
+
+    /// The environment to explain the package for. Defaults to the default
+    /// environment.
+    #[arg(short, long)]
+    pub environment: Option<String>,
+
+    #[clap(flatten)]
+    pub project_config: ProjectConfig,
+
+    #[clap(flatten)]
+    pub prefix_update_config: PrefixUpdateConfig,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum PackageSource {
+    Conda,
+    Pypi,
+}
+
+/// A package from the lock file together with the requirements it places on
+/// other packages.
+#[derive(Debug)]
+struct Node {
+    name: String,
+    version: String,
+    source: PackageSource,
+    /// The name of each dependency and the requirement as written in the
+    /// package metadata.
+    requires: Vec<(String, String)>,
+}
+
+impl Node {
+    fn from_locked_package(package: LockedPackageRef<'_>) -> Self {
+        match package {
+            LockedPackageRef::Conda(conda) => {
+                let record = conda.record();
+                Node {
+                    name: record.name.as_normalized().to_string(),
+                    version: record.version.to_string(),
+                    source: PackageSource::Conda,
+                    requires: record
+                        .depends
+                        .iter()
+                        .filter_map(|depend| {
+                            let name = dependency_name(depend)?;
+                            Some((name.as_normalized().to_string(), depend.clone()))
+                        })
+                        .collect(),
+                }
+            }
+            LockedPackageRef::Pypi(pypi, _) => Node {
+                name: pypi.name.as_dist_info_name().into_owned(),
+                version: pypi.version.to_string(),
+                source: PackageSource::Pypi,
+                requires: pypi
+                    .requires_dist
+                    .iter()
+                    .map(|req| (req.name.as_dist_info_name().into_owned(), req.to_string()))
+                    .collect(),
+            },
+        }
+    }
+
+    fn styled_version(&self) -> console::StyledObject<&str> {
+        match self.source {
+            PackageSource::Conda => console::style(self.version.as_str()).fg(Color::Yellow),
+            PackageSource::Pypi => console::style(self.version.as_str()).fg(Color::Blue),
+        }
+    }
+}
+
+/// Returns the names of the direct dependencies of an environment together
+/// with the requirement as written in the manifest.
+pub(crate) fn direct_requirements(
+    environment: &Environment<'_>,
+    platform: Platform,
+) -> HashMap<String, String> {
+    let conda = environment
+        .combined_dependencies(Some(platform))
+        .iter_specs()
+        .map(|(name, spec)| {
+            (
+                name.as_normalized().to_string(),
+                spec.to_toml_value().to_string(),
+            )
+        })
+        .collect_vec();
+    let pypi = environment
+        .pypi_dependencies(Some(platform))
+        .iter_specs()
+        .map(|(name, req)| {
+            (
+                name.as_normalized().as_dist_info_name().into_owned(),
+                req.to_string(),
+            )
+        })
+        .collect_vec();
+    conda.into_iter().chain(pypi).collect()
+}
+
+/// Finds the shortest path from every direct dependency to the target by
+/// walking the reverse dependency graph breadth-first.
+///
+/// Each returned path starts with a direct dependency and ends with the
+/// target.
+fn paths_from_direct_dependencies(
+    nodes: &HashMap<String, Node>,
+    direct: &HashMap<String, String>,
+    target: &str,
+) -> Vec<Vec<String>> {
+    let mut needed_by: HashMap<&str, Vec<&str>> = HashMap::new();
+    for node in nodes.values() {
+        for (dep, _) in &node.requires {
+            needed_by.entry(dep.as_str()).or_default().push(&node.name);
+        }
+    }
+
+    // Records for every visited package the next package on the way to the
+    // target.
+    let mut towards_target: HashMap<&str, Option<&str>> = HashMap::from([(target, None)]);
+    let mut queue = VecDeque::from([target]);
+    while let Some(name) = queue.pop_front() {
+        for &parent in needed_by.get(name).into_iter().flatten() {
+            if !towards_target.contains_key(parent) {
+                towards_target.insert(parent, Some(name));
+                queue.push_back(parent);
+            }
+        }
+    }
+
+    towards_target
+        .keys()
+        .filter(|name| **name != target && direct.contains_key(**name))
+        .sorted()
+        .map(|&start| {
+            let mut path = vec![start.to_string()];
+            let mut current = start;
+            while let Some(&Some(next)) = towards_target.get(current) {
+                path.push(next.to_string());
+                current = next;
+            }
+            path
+        })
+        .collect()
+}
+
+pub async fn execute(args: Args) -> miette::Result<()> {
+    let project = Project::load_or_else_discover(args.project_config.manifest_path.as_deref())
+        .wrap_err("Failed to load project")?;
+
+    let environment = project
+        .environment_from_name_or_env_var(args.environment)
+        .wrap_err("Environment not found")?;
+
+    let lock_file = project
+        .update_lock_file(UpdateLockFileOptions {
+            lock_file_usage: args.prefix_update_config.lock_file_usage(),
+            no_install: args.prefix_update_config.no_install,
+            max_concurrent_solves: project.config().max_concurrent_solves(),
+        })
+        .await
+        .wrap_err("Failed to update lock file")?;
+
+    let platform = args.platform.unwrap_or_else(|| environment.best_platform());
+    let nodes: HashMap<String, Node> = lock_file
+        .lock_file
+        .environment(environment.name().as_str())
+        .and_then(|env| env.packages(platform).map(Vec::from_iter))
+        .unwrap_or_default()
+        .into_iter()
+        .map(Node::from_locked_package)
+        .map(|node| (node.name.clone(), node))
+        .collect();
+
+    // Conda names are lowercase, PyPI names are compared by their dist-info
+    // names.
+    let lowercase = args.package.to_lowercase();
+    let Some(target) = [lowercase.clone(), lowercase.replace(['-', '.'], "_")]
+        .into_iter()
+        .find_map(|name| nodes.get(&name))
+    else {
+        miette::bail!(
+            "package '{}' is not part of environment {} for platform '{}'",
+            args.package,
+            environment.name().fancy_display(),
+            platform
+        );
+    };
+
+    let direct = direct_requirements(&environment, platform);
+
+    println!(
+        "{} {} is part of environment {} for {}",
+        console::style(&target.name).bold(),
+        target.styled_version(),
+        environment.name().fancy_display(),
+        platform
+    );
+
+    // Whether the package is requested in the manifest.
+    if let Some(spec) = direct.get(&target.name) {
+        println!(
+            "\n{} it is a direct dependency: {}",
+            console::style("Requested:").bold(),
+            console::style(spec).fg(Color::Green)
+        );
+    }
+
+    // The packages that require the package, these bound its version.
+    let required_by = nodes
+        .values()
+        .flat_map(|node| {
+            node.requires
+                .iter()
+                .filter(|(dep, _)| dep == &target.name)
+                .map(move |(_, requirement)| (node, requirement))
+        })
+        .sorted_by(|(a, _), (b, _)| a.name.cmp(&b.name))
+        .collect_vec();
+    if !required_by.is_empty() {
+        println!("\n{}", console::style("Required by:").bold());
+        for (node, requirement) in required_by {
+            println!(
+                "  {} {} requires {}{}",
+                if direct.contains_key(&node.name) {
+                    console::style(node.name.as_str()).fg(Color::Green).bold()
+                } else {
+                    console::style(node.name.as_str())
+                },
+                node.styled_version(),
+                console::style(requirement).dim(),
+                if direct.contains_key(&node.name) {
+                    " (direct)"
+                } else {
+                    ""
+                }
+            );
+        }
+    }
+
+    // How the direct dependencies lead to the package.
+    let paths = paths_from_direct_dependencies(&nodes, &direct, &target.name);
+    if !paths.is_empty() {
+        println!("\n{}", console::style("Pulled in through:").bold());
+        for path in paths {
+            println!(
+                "  {}",
+                path.iter()
+                    .enumerate()
+                    .map(|(idx, name)| if idx == 0 {
+                        console::style(name.as_str()).fg(Color::Green).bold()
+                    } else {
+                        console::style(name.as_str())
+                    })
+                    .join(" → ")
+            );
+        }
+    } else if !direct.contains_key(&target.name) {
+        if target.name == "python" && !environment.pypi_dependencies(Some(platform)).is_empty() {
+            println!(
+                "\n{} python is required to install the PyPI dependencies",
+                console::style("Pulled in through:").bold()
+            );
+        } else {
+            println!(
+                "\n{} no direct dependency of this environment requires it",
+                console::style("Pulled in through:").bold()
+            );
+        }
+    }
+
+    // Constraints placed on the package by other environments in the same
+    // solve-group.
+    if let Some(solve_group) = environment.solve_group() {
+        let siblings = solve_group
+            .environments()
+            .filter(|env| env.name() != environment.name())
+            .filter_map(|env| {
+                direct_requirements(&env, platform)
+                    .remove(&target.name)
+                    .map(|spec| (env, spec))
+            })
+            .collect_vec();
+        if !siblings.is_empty() {
+            println!(
+                "\n{} its version is shared with the solve-group '{}'",
+                console::style("Solve-group:").bold(),
+                solve_group.name()
+            );
+            for (env, spec) in siblings {
+                println!(
+                    "  {} requires {}",
+                    env.name().fancy_display(),
+                    console::style(spec).dim()
+                );
+            }
+        }
+    }
+
+    Project::warn_on_discovered_from_env(args.project_config.manifest_path.as_deref());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(name: &str, requires: &[&str]) -> (String, Node) {
+        (
+            name.to_string(),
+            Node {
+                name: name.to_string(),
+                version: "1.0".to_string(),
+                source: PackageSource::Conda,
+                requires: requires
+                    .iter()
+                    .map(|dep| (dep.to_string(), format!("{dep} >=1")))
+                    .collect(),
+            },
+        )
+    }
+
+    #[test]
+    fn test_paths_from_direct_dependencies() {
+        let nodes = HashMap::from([
+            node("scikit-learn", &["scipy", "numpy"]),
+            node("scipy", &["numpy"]),
+            node("pandas", &["numpy"]),
+            node("numpy", &[]),
+            node("rich", &[]),
+        ]);
+        let direct = HashMap::from([
+            ("scikit-learn".to_string(), "*".to_string()),
+            ("pandas".to_string(), "*".to_string()),
+            ("rich".to_string(), "*".to_string()),
+        ]);
+
+        let paths = paths_from_direct_dependencies(&nodes, &direct, "numpy");
+        assert_eq!(
+            paths,
+            vec![
+                vec!["pandas".to_string(), "numpy".to_string()],
+                vec!["scikit-learn".to_string(), "numpy".to_string()],
+            ]
+        );
+
+        let paths = paths_from_direct_dependencies(&nodes, &direct, "scipy");
+        assert_eq!(
+            paths,
+            vec![vec!["scikit-learn".to_string(), "scipy".to_string()]]
+        );
+    }
+}
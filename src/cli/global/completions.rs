@@ -0,0 +1,91 @@
+use std::path::Path;
+
+use clap::{Parser, ValueEnum};
+
+use crate::global::{
+    completions::{setup_instructions, CompletionShell},
+    CompletionsDir,
+};
+
+/// The shells for which the completions exposed by `pixi global` can be set up
+#[derive(ValueEnum, Clone, Debug, Copy, Eq, PartialEq)]
+enum Shell {
+    /// Bourne Again SHell (bash)
+    Bash,
+    /// Friendly Interactive SHell (fish)
+    Fish,
+    /// Z SHell (zsh)
+    Zsh,
+}
+
+impl From<Shell> for CompletionShell {
+    fn from(shell: Shell) -> Self {
+        match shell {
+            Shell::Bash => CompletionShell::Bash,
+            Shell::Fish => CompletionShell::Fish,
+            Shell::Zsh => CompletionShell::Zsh,
+        }
+    }
+}
+
+/// Print the lines to add to your shell configuration to load the completions
+/// of globally installed tools.
+///
+/// Example:
+///    pixi global completions setup
+///    pixi global completions setup --shell zsh
+#[derive(Parser, Debug)]
+#[clap(verbatim_doc_comment)]
+pub struct SetupArgs {
+    /// The shell to print the instructions for, defaults to the shell in `$SHELL`
+    #[arg(short, long)]
+    shell: Option<Shell>,
+}
+
+/// Interact with the shell completions exposed by globally installed tools
+///
+/// `pixi global completions setup`
+/// prints the configuration needed to load the exposed completions in your shell
+#[derive(Parser, Debug)]
+pub enum SubCommand {
+    #[clap(name = "setup")]
+    Setup(SetupArgs),
+}
+
+pub async fn execute(args: SubCommand) -> miette::Result<()> {
+    match args {
+        SubCommand::Setup(args) => setup(args).await?,
+    }
+    Ok(())
+}
+
+/// Determines the shell from the `SHELL` environment variable.
+fn shell_from_env() -> Option<Shell> {
+    let shell = std::env::var_os("SHELL")?;
+    let name = Path::new(&shell).file_name()?.to_str()?;
+    Shell::from_str(name, true).ok()
+}
+
+async fn setup(args: SetupArgs) -> miette::Result<()> {
+    let completions_dir = CompletionsDir::from_env().await?;
+
+    let shells: Vec<CompletionShell> = match args.shell.or_else(shell_from_env) {
+        Some(shell) => vec![shell.into()],
+        None => CompletionShell::ALL.to_vec(),
+    };
+
+    eprintln!(
+        "Shell completions of global tools are exposed in '{}'.",
+        completions_dir.path().display()
+    );
+    for shell in shells {
+        let (config_file, snippet) = setup_instructions(shell, &completions_dir);
+        eprintln!(
+            "\nTo enable them in {}, add the following to {}:",
+            console::style(shell.as_str()).bold(),
+            config_file
+        );
+        println!("{snippet}");
+    }
+    Ok(())
+}
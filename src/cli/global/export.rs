@@ -0,0 +1,122 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+use fancy_display::FancyDisplay;
+use fs_err as fs;
+use miette::IntoDiagnostic;
+use pixi_config::{Config, ConfigCli};
+use pixi_spec::PixiSpec;
+use rattler_conda_types::{
+    version_spec::EqualityOperator, NamelessMatchSpec, StringMatcher, VersionSpec,
+};
+
+use crate::global::{self, EnvironmentName};
+
+/// Export the global manifest with every dependency pinned to the installed
+/// version.
+///
+/// The result can be replayed on another machine with `pixi global import`.
+///
+/// Example:
+///    pixi global export > global.toml
+///    pixi global export --output global.toml
+#[derive(Parser, Debug)]
+#[clap(verbatim_doc_comment)]
+pub struct Args {
+    /// The file to write the exported manifest to, defaults to stdout.
+    #[arg(long, short)]
+    output: Option<PathBuf>,
+
+    /// Export the manifest as is, without pinning the installed versions.
+    #[arg(long)]
+    no_pin: bool,
+
+    #[clap(flatten)]
+    config: ConfigCli,
+}
+
+pub async fn execute(args: Args) -> miette::Result<()> {
+    let config = Config::with_cli_config(&args.config);
+    let project = global::Project::discover_or_create()
+        .await?
+        .with_cli_config(config.clone());
+
+    let mut manifest = project.manifest.clone();
+    if !args.no_pin {
+        let env_names: Vec<EnvironmentName> = project.environments().keys().cloned().collect();
+        for env_name in env_names {
+            pin_installed_versions(&project, &mut manifest, &env_name).await?;
+        }
+    }
+
+    let contents = manifest.document.to_string();
+    match args.output {
+        Some(path) => {
+            fs::write(&path, contents).into_diagnostic()?;
+            eprintln!(
+                "{}Exported {} environments to '{}'",
+                console::style(console::Emoji("✔ ", "")).green(),
+                project.environments().len(),
+                path.display()
+            );
+        }
+        None => print!("{contents}"),
+    }
+    Ok(())
+}
+
+/// Replaces the binary dependencies of an environment with the exact version
+/// and build that is currently installed.
+async fn pin_installed_versions(
+    project: &global::Project,
+    manifest: &mut global::project::Manifest,
+    env_name: &EnvironmentName,
+) -> miette::Result<()> {
+    let Some(environment) = project.environment(env_name) else {
+        return Ok(());
+    };
+    let installed = project
+        .environment_prefix(env_name)
+        .await?
+        .find_installed_packages()?;
+    let channel_config = project.config().global_channel_config();
+
+    for (name, spec) in environment.dependencies() {
+        // Source dependencies are always rebuilt.
+        let Some(spec) = spec
+            .clone()
+            .try_into_nameless_match_spec(channel_config)
+            .into_diagnostic()?
+        else {
+            continue;
+        };
+        let Some(record) = installed
+            .iter()
+            .map(|record| &record.repodata_record.package_record)
+            .find(|record| &record.name == name)
+        else {
+            tracing::warn!(
+                "{} is not installed in environment {}, it will not be pinned",
+                name.as_source(),
+                env_name.fancy_display()
+            );
+            continue;
+        };
+
+        let pinned = NamelessMatchSpec {
+            version: Some(VersionSpec::Exact(
+                EqualityOperator::Equals,
+                record.version.version().clone(),
+            )),
+            build: Some(StringMatcher::Exact(record.build.clone())),
+            channel: spec.channel,
+            ..NamelessMatchSpec::default()
+        };
+        manifest.add_pixi_spec(
+            env_name,
+            name,
+            PixiSpec::from_nameless_matchspec(pinned, channel_config),
+        )?;
+    }
+    Ok(())
+}
@@ -1,11 +1,16 @@
+use std::path::{Component, Path, PathBuf};
+
 use clap::Parser;
 use itertools::Itertools;
-use miette::Context;
+use miette::{Context, IntoDiagnostic};
 use pixi_config::{Config, ConfigCli};
 
 use crate::{
     cli::global::revert_environment_after_error,
-    global::{self, EnvironmentName, ExposedName, Mapping, StateChanges},
+    global::{
+        self, exposed_paths::remove_exposed_path, EnvironmentName, ExposedName, Mapping,
+        StateChange, StateChanges,
+    },
 };
 
 /// Add exposed binaries from an environment to your global environment
@@ -44,6 +49,36 @@ pub struct RemoveArgs {
     config: ConfigCli,
 }
 
+/// Expose a file or directory of an environment at another location, e.g. man
+/// pages or shared libraries
+///
+/// A symbolic link to the path is created at the target location, it is
+/// removed again when the environment is uninstalled.
+///
+/// Example:
+/// - pixi global expose path man-db share/man --to ~/.local/share/man
+/// - pixi global expose path man-db share/man --remove
+#[derive(Parser, Debug)]
+#[clap(arg_required_else_help = true, verbatim_doc_comment)]
+pub struct PathArgs {
+    /// The environment that contains the path
+    environment: EnvironmentName,
+
+    /// The path to expose, relative to the root of the environment
+    path: String,
+
+    /// The location to expose the path at
+    #[arg(long, required_unless_present = "remove")]
+    to: Option<PathBuf>,
+
+    /// Stop exposing the path and remove its link
+    #[arg(long, conflicts_with = "to")]
+    remove: bool,
+
+    #[clap(flatten)]
+    config: ConfigCli,
+}
+
 /// Interact with the exposure of binaries in the global environment
 ///
 /// `pixi global expose add python310=python3.10 --environment myenv`
@@ -58,6 +93,8 @@ pub enum SubCommand {
     Add(AddArgs),
     #[clap(name = "remove")]
     Remove(RemoveArgs),
+    #[clap(name = "path")]
+    Path(PathArgs),
 }
 
 /// Expose some binaries
@@ -65,6 +102,7 @@ pub async fn execute(args: SubCommand) -> miette::Result<()> {
     match args {
         SubCommand::Add(args) => add(args).await?,
         SubCommand::Remove(args) => remove(args).await?,
+        SubCommand::Path(args) => path(args).await?,
     }
     Ok(())
 }
@@ -163,3 +201,87 @@ pub async fn remove(args: RemoveArgs) -> miette::Result<()> {
     }
     Ok(())
 }
+
+/// Normalizes a path inside an environment, which has to be relative and stay
+/// inside of the environment.
+fn environment_relative_path(path: &str) -> miette::Result<String> {
+    let components = Path::new(path)
+        .components()
+        .filter(|component| !matches!(component, Component::CurDir))
+        .map(|component| match component {
+            Component::Normal(name) => Ok(name.to_string_lossy().into_owned()),
+            _ => Err(miette::miette!(
+                "'{path}' has to be a path relative to the root of the environment"
+            )),
+        })
+        .collect::<miette::Result<Vec<_>>>()?;
+    if components.is_empty() {
+        miette::bail!("'{path}' has to be a path relative to the root of the environment");
+    }
+    Ok(components.join("/"))
+}
+
+/// Returns the absolute location a path is exposed at, expanding a leading
+/// `~` to the home directory.
+fn absolute_target(target: &Path) -> miette::Result<PathBuf> {
+    let target = match target.strip_prefix("~") {
+        Ok(rest) => dirs::home_dir()
+            .ok_or_else(|| miette::miette!("Couldn't determine the home directory"))?
+            .join(rest),
+        Err(_) => target.to_path_buf(),
+    };
+    std::path::absolute(target).into_diagnostic()
+}
+
+pub async fn path(args: PathArgs) -> miette::Result<()> {
+    let config = Config::with_cli_config(&args.config);
+    let project_original = global::Project::discover_or_create()
+        .await?
+        .with_cli_config(config.clone());
+    let env_name = &args.environment;
+    let path = environment_relative_path(&args.path)?;
+
+    let mut project = project_original.clone();
+    if args.remove {
+        let target = project.manifest.remove_exposed_path(env_name, &path)?;
+        let env_dir = project.environment_dir(env_name).await?;
+        let mut state_changes = StateChanges::new_with_env(env_name.clone());
+        if remove_exposed_path(&env_dir, &target).await? {
+            state_changes.insert_change(env_name, StateChange::RemovedPath(target));
+        }
+        project.manifest.save().await?;
+        state_changes.report();
+        return Ok(());
+    }
+
+    let Some(target) = &args.to else {
+        miette::bail!("The location to expose '{path}' at has to be given with `--to`");
+    };
+    let target = absolute_target(target)?;
+
+    async fn apply_changes(
+        env_name: &EnvironmentName,
+        path: &str,
+        target: &Path,
+        project: &mut global::Project,
+    ) -> miette::Result<StateChanges> {
+        let mut state_changes = StateChanges::new_with_env(env_name.clone());
+        project.manifest.add_exposed_path(env_name, path, target)?;
+        state_changes |= project.sync_environment(env_name, None).await?;
+        Ok(state_changes)
+    }
+
+    match apply_changes(env_name, &path, &target, &mut project).await {
+        Ok(state_changes) => {
+            project.manifest.save().await?;
+            state_changes.report();
+            Ok(())
+        }
+        Err(err) => {
+            revert_environment_after_error(env_name, &project_original)
+                .await
+                .wrap_err("Couldn't expose path. Reverting also failed.")?;
+            Err(err)
+        }
+    }
+}
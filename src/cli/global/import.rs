@@ -0,0 +1,90 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+use fancy_display::FancyDisplay;
+use miette::Context;
+use pixi_config::{Config, ConfigCli};
+
+use crate::{
+    cli::global::revert_environment_after_error,
+    global::{self, project::Manifest, EnvironmentName},
+};
+
+/// Import environments from a file created with `pixi global export`.
+///
+/// Environments in the file replace the installed environments with the same
+/// name, other installed environments are left untouched.
+///
+/// Example:
+///    pixi global import global.toml
+///    pixi global import global.toml --environment ruff
+#[derive(Parser, Debug)]
+#[clap(verbatim_doc_comment)]
+pub struct Args {
+    /// The file to import the environments from.
+    file: PathBuf,
+
+    /// Only import the given environments, defaults to all environments in
+    /// the file.
+    #[arg(long, short)]
+    environment: Vec<EnvironmentName>,
+
+    #[clap(flatten)]
+    config: ConfigCli,
+}
+
+pub async fn execute(args: Args) -> miette::Result<()> {
+    let config = Config::with_cli_config(&args.config);
+    let project_original = global::Project::discover_or_create()
+        .await?
+        .with_cli_config(config.clone());
+
+    let imported = Manifest::from_path(&args.file)
+        .wrap_err_with(|| format!("failed to read '{}'", args.file.display()))?;
+
+    let env_names: Vec<EnvironmentName> = if args.environment.is_empty() {
+        imported.parsed.envs.keys().cloned().collect()
+    } else {
+        for env_name in &args.environment {
+            if !imported.parsed.envs.contains_key(env_name) {
+                miette::bail!(
+                    "Environment {} doesn't exist in '{}'",
+                    env_name.fancy_display(),
+                    args.file.display()
+                );
+            }
+        }
+        args.environment.clone()
+    };
+
+    let mut last_updated_project = project_original;
+    for env_name in &env_names {
+        let mut project = last_updated_project.clone();
+        project.manifest.import_environment(&imported, env_name)?;
+        project.manifest.save().await?;
+
+        match project
+            .sync_environment(env_name, None)
+            .await
+            .wrap_err_with(|| format!("Couldn't import {}", env_name.fancy_display()))
+        {
+            Ok(state_changes) => state_changes.report(),
+            Err(err) => {
+                last_updated_project.manifest.save().await?;
+                revert_environment_after_error(env_name, &last_updated_project)
+                    .await
+                    .wrap_err("Couldn't import environment. Reverting also failed.")?;
+                return Err(err);
+            }
+        }
+        last_updated_project = project;
+    }
+
+    eprintln!(
+        "{}Imported {} environments from '{}'",
+        console::style(console::Emoji("✔ ", "")).green(),
+        env_names.len(),
+        args.file.display()
+    );
+    Ok(())
+}
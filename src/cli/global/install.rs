@@ -1,13 +1,22 @@
+use std::path::PathBuf;
+
 use clap::Parser;
 use fancy_display::FancyDisplay;
+use indexmap::IndexMap;
 use itertools::Itertools;
 use miette::{Context, IntoDiagnostic};
-use rattler_conda_types::{MatchSpec, NamedChannelOrUrl, Platform};
+use pixi_manifest::{pypi::PyPiPackageName, PyPiRequirement};
+use pixi_spec::{GitSpec, PathSourceSpec, Reference, SourceSpec};
+use rattler_conda_types::{MatchSpec, NamedChannelOrUrl, PackageName, Platform};
+use url::Url;
 
 use crate::{
     cli::{global::revert_environment_after_error, has_specs::HasSpecs},
     global::{
-        self, common::NotChangedReason, list::list_global_environments, project::ExposedType,
+        self,
+        common::NotChangedReason,
+        list::list_global_environments,
+        project::{ExposedType, LockedVersions},
         EnvChanges, EnvState, EnvironmentName, Mapping, Project, StateChange, StateChanges,
     },
 };
@@ -20,13 +29,45 @@ use pixi_config::{self, Config, ConfigCli};
 /// - pixi global install jupyter --with polars
 /// - pixi global install --expose python3.8=python python=3.8
 /// - pixi global install --environment science --expose jupyter --expose ipython jupyter ipython polars
+/// - pixi global install --path ./my-tool
+/// - pixi global install --git https://github.com/user/my-tool --branch main
+/// - pixi global install --pypi ruff
 #[derive(Parser, Debug, Clone)]
 #[clap(arg_required_else_help = true, verbatim_doc_comment)]
 pub struct Args {
     /// Specifies the packages that are to be installed.
-    #[arg(num_args = 1.., required = true)]
+    #[arg(num_args = 1.., required_unless_present_any = ["path", "git"])]
     packages: Vec<String>,
 
+    /// Install the packages from PyPI, together with python, for tools that
+    /// aren't available as conda packages.
+    #[arg(long, conflicts_with_all = ["path", "git"])]
+    pypi: bool,
+
+    /// Build and install the package from a local source directory.
+    #[arg(long, conflicts_with_all = ["packages", "git"])]
+    path: Option<PathBuf>,
+
+    /// Build and install the package from a git repository.
+    #[arg(long, conflicts_with = "packages")]
+    git: Option<Url>,
+
+    /// The git branch to build.
+    #[arg(long, requires = "git", conflicts_with_all = ["tag", "rev"])]
+    branch: Option<String>,
+
+    /// The git tag to build.
+    #[arg(long, requires = "git", conflicts_with_all = ["branch", "rev"])]
+    tag: Option<String>,
+
+    /// The git revision to build.
+    #[arg(long, requires = "git", conflicts_with_all = ["branch", "tag"])]
+    rev: Option<String>,
+
+    /// The subdirectory of the git repository that contains the package.
+    #[arg(long, requires = "git")]
+    subdir: Option<String>,
+
     /// The channels to consider as a name or a url.
     /// Multiple channels can be specified by using this field multiple times.
     ///
@@ -61,6 +102,14 @@ pub struct Args {
     /// Specifies that the packages should be reinstalled even if they are already installed.
     #[arg(action, long)]
     force_reinstall: bool,
+
+    /// Create Start Menu shortcuts for the exposed executables (Windows only).
+    #[arg(long)]
+    shortcut: bool,
+
+    /// Add the global binary directory to the user PATH in the registry (Windows only).
+    #[arg(long)]
+    add_to_path: bool,
 }
 
 impl HasSpecs for Args {
@@ -69,14 +118,107 @@ impl HasSpecs for Args {
     }
 }
 
+impl Args {
+    /// Returns the packages as PyPI requirements, used with `--pypi`.
+    fn pypi_requirements(&self) -> miette::Result<IndexMap<PyPiPackageName, PyPiRequirement>> {
+        self.packages
+            .iter()
+            .map(|package| {
+                let requirement: pep508_rs::Requirement = package.parse().into_diagnostic()?;
+                let name = PyPiPackageName::from_normalized(requirement.name.clone());
+                let requirement = PyPiRequirement::try_from(requirement).into_diagnostic()?;
+                Ok((name, requirement))
+            })
+            .collect()
+    }
+
+    /// Returns the source to build the package from, if `--path` or `--git`
+    /// was specified.
+    fn source_spec(&self) -> miette::Result<Option<SourceSpec>> {
+        if let Some(path) = &self.path {
+            // The global manifest lives somewhere else, so store the absolute path.
+            let path = dunce::canonicalize(path)
+                .into_diagnostic()
+                .wrap_err_with(|| format!("failed to find '{}'", path.display()))?;
+            let path = path
+                .to_str()
+                .ok_or_else(|| miette::miette!("'{}' is not valid UTF-8", path.display()))?;
+            return Ok(Some(SourceSpec::Path(PathSourceSpec {
+                path: path.into(),
+                ignore_run_exports: Default::default(),
+            })));
+        }
+
+        let Some(git) = &self.git else {
+            return Ok(None);
+        };
+        let rev = if let Some(branch) = &self.branch {
+            Some(Reference::Branch(branch.clone()))
+        } else if let Some(tag) = &self.tag {
+            Some(Reference::Tag(tag.clone()))
+        } else {
+            self.rev.clone().map(Reference::Rev)
+        };
+        Ok(Some(SourceSpec::Git(GitSpec {
+            git: git.clone(),
+            rev,
+            subdirectory: self.subdir.clone(),
+            options: Default::default(),
+            ignore_run_exports: Default::default(),
+        })))
+    }
+}
+
 pub async fn execute(args: Args) -> miette::Result<()> {
     let config = Config::with_cli_config(&args.config);
     let project_original = global::Project::discover_or_create()
         .await?
         .with_cli_config(config.clone());
 
+    // Determine the names of the packages that are built from source.
+    let mut sources = IndexMap::new();
+    if let Some(source) = args.source_spec()? {
+        let channels = if args.channels.is_empty() {
+            project_original.config().default_channels()
+        } else {
+            args.channels.clone()
+        };
+        let names = project_original
+            .source_package_names(
+                &source,
+                &channels,
+                args.platform.unwrap_or_else(Platform::current),
+            )
+            .await?;
+        if names.is_empty() {
+            miette::bail!("the source doesn't provide any packages");
+        }
+        if names.len() > 1 && args.environment.is_none() {
+            miette::bail!(
+                help = "use `--environment` to install them together",
+                "the source provides multiple packages: {}",
+                names.iter().map(PackageName::as_source).join(", ")
+            );
+        }
+        sources.extend(names.into_iter().map(|name| (name, source.clone())));
+    }
+
+    let pypi_requirements = if args.pypi {
+        args.pypi_requirements()?
+    } else {
+        IndexMap::new()
+    };
+
     let env_names = match &args.environment {
         Some(env_name) => Vec::from([env_name.clone()]),
+        None if !pypi_requirements.is_empty() => pypi_requirements
+            .keys()
+            .map(|name| name.as_normalized().as_ref().parse().into_diagnostic())
+            .collect::<miette::Result<Vec<_>>>()?,
+        None if !sources.is_empty() => sources
+            .keys()
+            .map(|package_name| package_name.as_normalized().parse().into_diagnostic())
+            .collect::<miette::Result<Vec<_>>>()?,
         None => args
             .specs()?
             .iter()
@@ -94,9 +236,28 @@ pub async fn execute(args: Args) -> miette::Result<()> {
         miette::bail!("Can't add packages with `--with` for more than one environment");
     }
 
+    if (args.shortcut || args.add_to_path) && !cfg!(windows) {
+        tracing::warn!("`--shortcut` and `--add-to-path` only have an effect on Windows");
+    }
+
     let mut env_changes = EnvChanges::default();
     let mut last_updated_project = project_original;
-    let specs = args.specs()?;
+    let mut specs = if args.pypi {
+        IndexMap::new()
+    } else {
+        args.specs()?
+    };
+    // The source packages are matched by name, the source itself is added to the
+    // manifest separately.
+    specs.extend(sources.keys().map(|name| {
+        (
+            name.clone(),
+            MatchSpec {
+                name: Some(name.clone()),
+                ..MatchSpec::default()
+            },
+        )
+    }));
     for env_name in &env_names {
         let specs = if multiple_envs {
             specs
@@ -112,10 +273,24 @@ pub async fn execute(args: Args) -> miette::Result<()> {
                 .map(|(_, spec)| spec)
                 .collect_vec()
         };
+        let pypi_requirements = pypi_requirements
+            .iter()
+            .filter(|(name, _)| {
+                !multiple_envs || env_name.as_str() == name.as_normalized().as_ref()
+            })
+            .map(|(name, requirement)| (name.clone(), requirement.clone()))
+            .collect();
         let mut project = last_updated_project.clone();
-        match setup_environment(env_name, &args, &specs, &mut project)
-            .await
-            .wrap_err_with(|| format!("Couldn't install {}", env_name.fancy_display()))
+        match setup_environment(
+            env_name,
+            &args,
+            &specs,
+            &pypi_requirements,
+            &sources,
+            &mut project,
+        )
+        .await
+        .wrap_err_with(|| format!("Couldn't install {}", env_name.fancy_display()))
         {
             Ok(state_changes) => {
                 if state_changes.has_changed() {
@@ -139,6 +314,14 @@ pub async fn execute(args: Args) -> miette::Result<()> {
         last_updated_project = project;
     }
 
+    if args.add_to_path && global::desktop::add_to_user_path(&last_updated_project.bin_dir)? {
+        eprintln!(
+            "{}Added {} to the user PATH, restart your terminal to use the exposed executables.",
+            console::style(console::Emoji("✔ ", "")).green(),
+            last_updated_project.bin_dir.path().display()
+        );
+    }
+
     // After installing, we always want to list the changed environments
     list_global_environments(
         &last_updated_project,
@@ -155,6 +338,8 @@ async fn setup_environment(
     env_name: &EnvironmentName,
     args: &Args,
     specs: &[MatchSpec],
+    pypi_requirements: &IndexMap<PyPiPackageName, PyPiRequirement>,
+    sources: &IndexMap<PackageName, SourceSpec>,
     project: &mut Project,
 ) -> miette::Result<StateChanges> {
     let mut state_changes = StateChanges::new_with_env(env_name.clone());
@@ -177,6 +362,16 @@ async fn setup_environment(
 
     // Add the dependencies to the environment
     for spec in specs.iter().chain(&args.with) {
+        if let Some(source) = spec
+            .name
+            .as_ref()
+            .and_then(|name| sources.get_key_value(name))
+        {
+            project
+                .manifest
+                .add_source_dependency(env_name, source.0, source.1.clone())?;
+            continue;
+        }
         project.manifest.add_dependency(
             env_name,
             spec,
@@ -184,6 +379,29 @@ async fn setup_environment(
         )?;
     }
 
+    // PyPI packages need a python interpreter to be installed into
+    let python = PackageName::new_unchecked("python");
+    if !pypi_requirements.is_empty() {
+        for (name, requirement) in pypi_requirements {
+            project
+                .manifest
+                .add_pypi_dependency(env_name, name, requirement.clone())?;
+        }
+        if project
+            .environment(env_name)
+            .is_some_and(|environment| !environment.dependencies().contains_key(&python))
+        {
+            project.manifest.add_dependency(
+                env_name,
+                &MatchSpec {
+                    name: Some(python.clone()),
+                    ..MatchSpec::default()
+                },
+                project.clone().config().global_channel_config(),
+            )?;
+        }
+    }
+
     if !args.expose.is_empty() {
         project.manifest.remove_all_exposed_mappings(env_name)?;
         // Only add the exposed mappings that were requested
@@ -193,13 +411,24 @@ async fn setup_environment(
     }
 
     if !args.force_reinstall && project.environment_in_sync(env_name).await? {
-        return Ok(StateChanges::new_with_env(env_name.clone()));
+        if !args.shortcut {
+            return Ok(StateChanges::new_with_env(env_name.clone()));
+        }
+        // Only the shortcuts have to be added
+        add_shortcuts(env_name, project)?;
+        let state_changes = project
+            .expose_executables_from_environment(env_name)
+            .await?;
+        project.manifest.save().await?;
+        return Ok(state_changes);
     }
 
     // Installing the environment to be able to find the bin paths later
-    let _ = project.install_environment(env_name).await?;
+    let _ = project
+        .install_environment(env_name, LockedVersions::Keep)
+        .await?;
 
-    let with_package_names = args
+    let mut with_package_names = args
         .with
         .iter()
         .map(|spec| {
@@ -208,12 +437,20 @@ async fn setup_environment(
                 .ok_or_else(|| miette::miette!("could not find package name in MatchSpec {}", spec))
         })
         .collect::<miette::Result<Vec<_>>>()?;
+    // Only expose the console scripts of the PyPI packages, not python itself
+    if !pypi_requirements.is_empty() {
+        with_package_names.push(python);
+    }
 
     // Sync exposed binaries
     let expose_type = ExposedType::new(args.expose.clone(), with_package_names);
 
     project.sync_exposed_names(env_name, expose_type).await?;
 
+    if args.shortcut {
+        add_shortcuts(env_name, project)?;
+    }
+
     // Figure out added packages and their corresponding versions
     state_changes |= project.added_packages(specs, env_name).await?;
 
@@ -225,3 +462,66 @@ async fn setup_environment(
     project.manifest.save().await?;
     Ok(state_changes)
 }
+
+/// Adds a Start Menu shortcut for every exposed executable of the environment.
+fn add_shortcuts(env_name: &EnvironmentName, project: &mut Project) -> miette::Result<()> {
+    let exposed_names = project
+        .environment(env_name)
+        .map(|environment| {
+            environment
+                .exposed()
+                .iter()
+                .map(|mapping| mapping.exposed_name().clone())
+                .collect_vec()
+        })
+        .unwrap_or_default();
+    for exposed_name in &exposed_names {
+        project.manifest.add_shortcut(env_name, exposed_name)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_source_spec() {
+        // Packages are installed from a channel by default
+        let args = Args::try_parse_from(["install", "ruff"]).unwrap();
+        assert!(args.source_spec().unwrap().is_none());
+
+        let dir = tempfile::tempdir().unwrap();
+        let args =
+            Args::try_parse_from(["install", "--path", dir.path().to_str().unwrap()]).unwrap();
+        let Some(SourceSpec::Path(spec)) = args.source_spec().unwrap() else {
+            panic!("expected a path source");
+        };
+        assert_eq!(
+            spec.path.as_str(),
+            dunce::canonicalize(dir.path()).unwrap().to_str().unwrap()
+        );
+
+        let args = Args::try_parse_from([
+            "install",
+            "--git",
+            "https://github.com/user/my-tool",
+            "--tag",
+            "v1.0",
+            "--subdir",
+            "crates/my-tool",
+        ])
+        .unwrap();
+        let Some(SourceSpec::Git(spec)) = args.source_spec().unwrap() else {
+            panic!("expected a git source");
+        };
+        assert_eq!(spec.git.as_str(), "https://github.com/user/my-tool");
+        assert_eq!(spec.rev, Some(Reference::Tag("v1.0".to_string())));
+        assert_eq!(spec.subdirectory.as_deref(), Some("crates/my-tool"));
+
+        // A source can't be combined with packages, and git options require a
+        // git source
+        assert!(Args::try_parse_from(["install", "--path", ".", "ruff"]).is_err());
+        assert!(Args::try_parse_from(["install", "--branch", "main", "ruff"]).is_err());
+    }
+}
@@ -3,11 +3,15 @@ use clap::Parser;
 use crate::global::{self, EnvironmentName};
 
 mod add;
+mod completions;
 mod edit;
+mod export;
 mod expose;
+mod import;
 mod install;
 mod list;
 mod remove;
+mod run;
 mod sync;
 mod uninstall;
 mod update;
@@ -32,7 +36,11 @@ pub enum Command {
     #[command(subcommand)]
     Expose(expose::SubCommand),
     Update(update::Args),
-    #[command(hide = true)]
+    Export(export::Args),
+    Import(import::Args),
+    Run(run::Args),
+    #[command(subcommand)]
+    Completions(completions::SubCommand),
     Upgrade(upgrade::Args),
     #[clap(alias = "ua")]
     #[command(hide = true)]
@@ -62,6 +70,10 @@ pub async fn execute(cmd: Args) -> miette::Result<()> {
         Command::Sync(args) => sync::execute(args).await?,
         Command::Expose(subcommand) => expose::execute(subcommand).await?,
         Command::Update(args) => update::execute(args).await?,
+        Command::Export(args) => export::execute(args).await?,
+        Command::Import(args) => import::execute(args).await?,
+        Command::Run(args) => run::execute(args).await?,
+        Command::Completions(subcommand) => completions::execute(subcommand).await?,
         Command::Upgrade(args) => upgrade::execute(args).await?,
         Command::UpgradeAll(args) => upgrade_all::execute(args).await?,
     };
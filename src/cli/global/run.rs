@@ -0,0 +1,110 @@
+use clap::{Parser, ValueHint};
+use pixi_config::ConfigCli;
+use rattler_conda_types::{MatchSpec, Platform};
+
+use crate::cli::{cli_config::ChannelsConfig, exec};
+
+/// Run a package without installing it globally.
+///
+/// The package is installed into a cached environment, which is reused on the
+/// next run, and the executable with the name of the package is started.
+///
+/// Example:
+///    pixi global run ruff check .
+///    pixi global run "cowpy==1.1.5" hello
+///    pixi global run --executable rg ripgrep --version
+#[derive(Parser, Debug)]
+#[clap(
+    trailing_var_arg = true,
+    arg_required_else_help = true,
+    verbatim_doc_comment
+)]
+pub struct Args {
+    /// The package to run.
+    package: MatchSpec,
+
+    /// The arguments to pass to the executable.
+    #[clap(num_args = 0.., allow_hyphen_values = true, value_hint = ValueHint::CommandWithArguments)]
+    args: Vec<String>,
+
+    /// The executable to run, defaults to the name of the package.
+    #[clap(long)]
+    executable: Option<String>,
+
+    /// Additional packages to install into the environment.
+    #[clap(long)]
+    with: Vec<MatchSpec>,
+
+    #[clap(flatten)]
+    channels: ChannelsConfig,
+
+    /// The platform to create the environment for.
+    #[clap(long, short, default_value_t = Platform::current())]
+    platform: Platform,
+
+    /// If specified a new environment is always created even if one already
+    /// exists.
+    #[clap(long)]
+    force_reinstall: bool,
+
+    #[clap(flatten)]
+    config: ConfigCli,
+}
+
+pub async fn execute(args: Args) -> miette::Result<()> {
+    let executable = executable_name(&args.package, args.executable)?;
+
+    exec::execute(exec::Args {
+        command: std::iter::once(executable).chain(args.args).collect(),
+        specs: std::iter::once(args.package).chain(args.with).collect(),
+        channels: args.channels,
+        platform: args.platform,
+        force_reinstall: args.force_reinstall,
+        config: args.config,
+    })
+    .await
+}
+
+/// Returns the executable to run, which defaults to the name of the package.
+fn executable_name(package: &MatchSpec, executable: Option<String>) -> miette::Result<String> {
+    if let Some(executable) = executable {
+        return Ok(executable);
+    }
+    package
+        .name
+        .as_ref()
+        .map(|name| name.as_normalized().to_string())
+        .ok_or_else(|| {
+            miette::miette!(
+                help = "specify the executable to run with `--executable`",
+                "could not determine the executable of '{}'",
+                package
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use rattler_conda_types::ParseStrictness;
+
+    use super::*;
+
+    #[test]
+    fn test_executable_name() {
+        let spec = |spec: &str| MatchSpec::from_str(spec, ParseStrictness::Lenient).unwrap();
+
+        assert_eq!(executable_name(&spec("ruff >=0.5"), None).unwrap(), "ruff");
+        assert_eq!(
+            executable_name(&spec("ripgrep"), Some("rg".to_string())).unwrap(),
+            "rg"
+        );
+
+        // A spec without a name needs an explicit executable
+        let nameless = MatchSpec::default();
+        assert!(executable_name(&nameless, None).is_err());
+        assert_eq!(
+            executable_name(&nameless, Some("cowpy".to_string())).unwrap(),
+            "cowpy"
+        );
+    }
+}
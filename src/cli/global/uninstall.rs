@@ -63,5 +63,20 @@ pub async fn execute(args: Args) -> miette::Result<()> {
         }
         last_updated_project = project;
     }
+
+    // Once the last environment is gone, the binary directory doesn't need to
+    // be on the `PATH` anymore, unless pixi itself lives there
+    let pixi_in_bin_dir = std::env::current_exe()
+        .is_ok_and(|exe| exe.starts_with(last_updated_project.bin_dir.path()));
+    if last_updated_project.environments().is_empty()
+        && !pixi_in_bin_dir
+        && global::desktop::remove_from_user_path(&last_updated_project.bin_dir)?
+    {
+        eprintln!(
+            "{}Removed {} from the user PATH.",
+            console::style(console::Emoji("✔ ", "")).green(),
+            last_updated_project.bin_dir.path().display()
+        );
+    }
     Ok(())
 }
@@ -1,6 +1,6 @@
 use crate::cli::global::revert_environment_after_error;
 use crate::global::common::check_all_exposed;
-use crate::global::project::ExposedType;
+use crate::global::project::{ExposedType, LockedVersions};
 use crate::global::{self, StateChanges};
 use crate::global::{EnvironmentName, Project};
 use clap::Parser;
@@ -8,6 +8,9 @@ use fancy_display::FancyDisplay;
 use pixi_config::{Config, ConfigCli};
 
 /// Updates environments in the global environment.
+///
+/// This ignores the lock files of the environments and locks the updated
+/// versions.
 #[derive(Parser, Debug, Clone)]
 pub struct Args {
     /// Specifies the environments that are to be updated.
@@ -44,7 +47,9 @@ pub async fn execute(args: Args) -> miette::Result<()> {
         };
 
         // Reinstall the environment
-        let environment_update = project.install_environment(env_name).await?;
+        let environment_update = project
+            .install_environment(env_name, LockedVersions::Update)
+            .await?;
 
         let mut state_changes = StateChanges::default();
 
@@ -1,37 +1,282 @@
+use std::io::{stdout, Write};
+
 use clap::Parser;
-use rattler_conda_types::Platform;
+use fancy_display::FancyDisplay;
+use itertools::{Either, Itertools};
+use miette::{Context, IntoDiagnostic};
+use pixi_config::{Config, ConfigCli};
+use pixi_spec::PixiSpec;
+use rattler_conda_types::{
+    version_spec::RangeOperator, PackageName, StringMatcher, Version, VersionSpec,
+};
 
-use crate::cli::{cli_config::ChannelsConfig, has_specs::HasSpecs};
+use crate::{
+    cli::global::revert_environment_after_error,
+    global::{
+        self,
+        common::check_all_exposed,
+        project::{ExposedType, LockedVersions},
+        EnvironmentName, Project, StateChanges,
+    },
+};
 
-/// Upgrade specific package which is installed globally.
-/// This command has been removed, please use `pixi global update` instead
+/// Upgrade the packages of global environments to their latest version,
+/// disregarding the version constraints in the global manifest.
+///
+/// The upgraded dependencies are written back to the manifest with the new
+/// version as lower bound.
+///
+/// Example:
+///    pixi global upgrade ruff
+///    pixi global upgrade --all --exclude python
+///    pixi global upgrade --all --dry-run
 #[derive(Parser, Debug)]
-// TODO: Uncomment as soon we implement this
-//#[clap(arg_required_else_help = true)]
+#[clap(verbatim_doc_comment)]
 pub struct Args {
-    /// Specifies the packages to upgrade.
-    // TODO: Uncomment as soon we implement this
-    //#[arg(required = true)]
-    pub specs: Vec<String>,
+    /// The environments to upgrade.
+    #[arg(required_unless_present = "all", conflicts_with = "all")]
+    environments: Vec<EnvironmentName>,
+
+    /// Upgrade all global environments.
+    #[arg(long)]
+    all: bool,
+
+    /// Environments that should not be upgraded when using `--all`.
+    #[arg(long, requires = "all")]
+    exclude: Vec<EnvironmentName>,
+
+    /// Only show the upgrades that would be made, without installing them or
+    /// modifying the manifest.
+    #[arg(short = 'n', long)]
+    dry_run: bool,
 
     #[clap(flatten)]
-    channels: ChannelsConfig,
+    config: ConfigCli,
+}
 
-    /// The platform to install the package for.
-    #[clap(long, default_value_t = Platform::current())]
-    platform: Platform,
+/// A direct dependency of a global environment whose version changes.
+struct PackageUpgrade {
+    environment: EnvironmentName,
+    name: PackageName,
+    before: Option<Version>,
+    after: Version,
 }
 
-impl HasSpecs for Args {
-    fn packages(&self) -> Vec<&str> {
-        self.specs.iter().map(AsRef::as_ref).collect()
+pub async fn execute(args: Args) -> miette::Result<()> {
+    let config = Config::with_cli_config(&args.config);
+    let project_original = global::Project::discover_or_create()
+        .await?
+        .with_cli_config(config.clone());
+
+    let env_names: Vec<EnvironmentName> = if args.all {
+        for env_name in &args.exclude {
+            if project_original.environment(env_name).is_none() {
+                miette::bail!("Environment {} doesn't exist", env_name.fancy_display());
+            }
+        }
+        project_original
+            .environments()
+            .keys()
+            .filter(|env_name| !args.exclude.contains(env_name))
+            .cloned()
+            .collect()
+    } else {
+        args.environments.clone()
+    };
+
+    let mut upgrades = Vec::new();
+    let mut last_updated_project = project_original;
+    for env_name in &env_names {
+        let mut project = last_updated_project.clone();
+        match upgrade_environment(env_name, &mut project, args.dry_run)
+            .await
+            .wrap_err_with(|| format!("Couldn't upgrade {}", env_name.fancy_display()))
+        {
+            Ok((env_upgrades, state_changes)) => {
+                upgrades.extend(env_upgrades);
+                state_changes.report();
+            }
+            Err(err) => {
+                if !args.dry_run {
+                    last_updated_project.manifest.save().await?;
+                    revert_environment_after_error(env_name, &last_updated_project)
+                        .await
+                        .wrap_err("Couldn't upgrade environment. Reverting also failed.")?;
+                }
+                return Err(err);
+            }
+        }
+        if !args.dry_run {
+            project.manifest.save().await?;
+        }
+        last_updated_project = project;
+    }
+
+    if upgrades.is_empty() {
+        eprintln!(
+            "{}All global environments are already up-to-date",
+            console::style(console::Emoji("✔ ", "")).green()
+        );
+    } else {
+        print_upgrades_as_table(&upgrades)
+            .into_diagnostic()
+            .context("failed to print upgrade table")?;
+    }
+    Ok(())
+}
+
+/// Relaxes the version constraints of the environment, solves it and, unless
+/// `dry_run` is set, installs the result and pins the new versions in the
+/// manifest.
+async fn upgrade_environment(
+    env_name: &EnvironmentName,
+    project: &mut Project,
+    dry_run: bool,
+) -> miette::Result<(Vec<PackageUpgrade>, StateChanges)> {
+    let environment = project
+        .environment(env_name)
+        .ok_or_else(|| miette::miette!("Environment {} not found", env_name.fancy_display()))?;
+
+    // Drop the version constraints of the binary dependencies
+    let channel_config = project.config().global_channel_config().clone();
+    let mut relaxed = Vec::new();
+    for (name, spec) in environment.dependencies().clone() {
+        let Either::Right(binary) = spec.into_source_or_binary() else {
+            continue;
+        };
+        let mut nameless_spec = binary
+            .try_into_nameless_match_spec(&channel_config)
+            .into_diagnostic()?;
+        nameless_spec.version = None;
+        // If the build contains a wildcard, keep it
+        nameless_spec.build = match nameless_spec.build {
+            Some(build @ StringMatcher::Glob(_) | build @ StringMatcher::Regex(_)) => Some(build),
+            _ => None,
+        };
+        nameless_spec.build_number = None;
+        relaxed.push((name, nameless_spec));
     }
+    for (name, nameless_spec) in &relaxed {
+        project.manifest.add_pixi_spec(
+            env_name,
+            name,
+            PixiSpec::from_nameless_matchspec(nameless_spec.clone(), &channel_config),
+        )?;
+    }
+
+    let solved_environment = project
+        .solve_environment(env_name, LockedVersions::Update)
+        .await?;
+    // Don't create the environment on a dry run, if it isn't installed yet
+    let installed = project.installed_packages(env_name)?;
+
+    let upgrades = project
+        .environment(env_name)
+        .into_iter()
+        .flat_map(|environment| environment.dependencies().keys())
+        .filter_map(|name| {
+            let after = solved_environment
+                .records
+                .iter()
+                .map(|record| record.package_record())
+                .find(|record| &record.name == name)?
+                .version
+                .version()
+                .clone();
+            let before = installed
+                .iter()
+                .map(|record| &record.repodata_record.package_record)
+                .find(|record| &record.name == name)
+                .map(|record| record.version.version().clone());
+            (before.as_ref() != Some(&after)).then(|| PackageUpgrade {
+                environment: env_name.clone(),
+                name: name.clone(),
+                before,
+                after,
+            })
+        })
+        .collect_vec();
+
+    if dry_run {
+        return Ok((upgrades, StateChanges::default()));
+    }
+
+    // See what executables were installed prior to the upgrade
+    let env_binaries = project.executables(env_name).await?;
+    let all_exposed = project
+        .environment(env_name)
+        .is_some_and(|environment| check_all_exposed(&env_binaries, environment.exposed()));
+    let expose_type = if all_exposed {
+        ExposedType::default()
+    } else {
+        ExposedType::subset()
+    };
+
+    // Pin the relaxed dependencies to the version they were upgraded to
+    for (name, mut nameless_spec) in relaxed {
+        let Some(record) = solved_environment
+            .records
+            .iter()
+            .map(|record| record.package_record())
+            .find(|record| record.name == name)
+        else {
+            continue;
+        };
+        nameless_spec.version = Some(VersionSpec::Range(
+            RangeOperator::GreaterEquals,
+            record.version.version().clone(),
+        ));
+        project.manifest.add_pixi_spec(
+            env_name,
+            &name,
+            PixiSpec::from_nameless_matchspec(nameless_spec, &channel_config),
+        )?;
+    }
+
+    let environment_update = project
+        .install_solved_environment(env_name, solved_environment)
+        .await?;
+    let mut state_changes = StateChanges::default();
+    state_changes.insert_change(
+        env_name,
+        global::StateChange::UpdatedEnvironment(environment_update),
+    );
+
+    // Sync executables exposed names with the manifest
+    project.sync_exposed_names(env_name, expose_type).await?;
+
+    // Expose or prune executables of the new environment
+    state_changes |= project
+        .expose_executables_from_environment(env_name)
+        .await?;
+
+    Ok((upgrades, state_changes))
 }
 
-pub async fn execute(_args: Args) -> miette::Result<()> {
-    Err(
-        miette::miette!("You can call `pixi global update` for most use cases").wrap_err(
-            "`pixi global upgrade` has been removed, and will be re-added in future releases",
-        ),
-    )
+/// Prints the upgrades of direct dependencies as a table.
+fn print_upgrades_as_table(upgrades: &[PackageUpgrade]) -> std::io::Result<()> {
+    let mut writer = tabwriter::TabWriter::new(stdout());
+    let header_style = console::Style::new().bold();
+    writeln!(
+        writer,
+        "{}\t{}\t{}\t{}",
+        header_style.apply_to("Environment"),
+        header_style.apply_to("Package"),
+        header_style.apply_to("Before"),
+        header_style.apply_to("After"),
+    )?;
+    for upgrade in upgrades {
+        writeln!(
+            writer,
+            "{}\t{}\t{}\t{}",
+            upgrade.environment.fancy_display(),
+            console::style(upgrade.name.as_normalized()).green().bold(),
+            upgrade
+                .before
+                .as_ref()
+                .map_or_else(|| "-".to_string(), ToString::to_string),
+            upgrade.after,
+        )?;
+    }
+    writer.flush()
 }
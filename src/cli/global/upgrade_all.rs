@@ -5,7 +5,7 @@ use rattler_conda_types::Platform;
 use crate::cli::cli_config::ChannelsConfig;
 
 /// Upgrade all globally installed packages
-/// This command has been removed, please use `pixi global update` instead
+/// This command has been removed, please use `pixi global upgrade --all` instead
 #[derive(Parser, Debug)]
 pub struct Args {
     #[clap(flatten)]
@@ -21,7 +21,7 @@ pub struct Args {
 
 pub async fn execute(_args: Args) -> miette::Result<()> {
     Err(
-        miette::miette!("You can call `pixi global update` for most use cases")
+        miette::miette!("You can call `pixi global upgrade --all` instead")
             .wrap_err("`pixi global upgrade-all` has been removed"),
     )
 }
@@ -21,6 +21,7 @@ use toml_edit::ser::to_string;
 use crate::{
     global,
     global::{BinDir, EnvRoot},
+    lock_file::lock_file_paths,
     task::TaskName,
     Project,
 };
@@ -356,11 +357,17 @@ fn dir_size(path: impl Into<PathBuf>) -> miette::Result<String> {
 }
 
 /// Returns last update time of file, formatted: DD-MM-YYYY H:M:S
-fn last_updated(path: impl Into<PathBuf>) -> miette::Result<String> {
-    let time = fs_err::metadata(path.into())
+/// Returns when the most recently changed lock-file of the project was
+/// modified.
+fn last_updated(project: &Project) -> miette::Result<String> {
+    let time = lock_file_paths(project)?
+        .into_iter()
+        .map(|path| fs_err::metadata(path).and_then(|metadata| metadata.modified()))
+        .collect::<Result<Vec<_>, _>>()
         .into_diagnostic()?
-        .modified()
-        .into_diagnostic()?;
+        .into_iter()
+        .max()
+        .ok_or_else(|| miette::miette!("the project is not locked"))?;
     let formatted_time = DateTime::<Local>::from(time)
         .format("%d-%m-%Y %H:%M:%S")
         .to_string();
@@ -390,7 +397,7 @@ pub async fn execute(args: Args) -> miette::Result<()> {
     let project_info = project.clone().map(|p| ProjectInfo {
         name: p.name().to_string(),
         manifest_path: p.manifest_path(),
-        last_updated: last_updated(p.lock_file_path()).ok(),
+        last_updated: last_updated(&p).ok(),
         pixi_folder_size,
         version: p.version().clone().map(|v| v.to_string()),
     });
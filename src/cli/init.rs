@@ -1,5 +1,6 @@
 use std::{
     cmp::PartialEq,
+    collections::HashMap,
     fs,
     io::{ErrorKind, Write},
     path::{Path, PathBuf},
@@ -7,16 +8,28 @@ use std::{
 };
 
 use clap::{Parser, ValueEnum};
+use itertools::Itertools;
 use miette::{Context, IntoDiagnostic};
 use minijinja::{context, Environment};
-use pixi_config::{get_default_author, Config};
+use pixi_config::{get_default_author, Config, FindLinks};
 use pixi_consts::consts;
 use pixi_manifest::{
-    pyproject::PyProjectManifest, DependencyOverwriteBehavior, FeatureName, SpecType,
+    pyproject::PyProjectManifest, DependencyOverwriteBehavior, FeatureName, FeaturesExt, SpecType,
 };
 use pixi_spec::PixiSpec;
-use pixi_utils::conda_environment_file::CondaEnvFile;
-use rattler_conda_types::{NamedChannelOrUrl, Platform};
+use pixi_utils::{
+    conda_environment_file::CondaEnvFile,
+    conda_lock_file::{CondaLockFile, CondaLockSpec, MAIN_CATEGORY},
+    conda_prefix::CondaPrefix,
+    python_project_file::{feature_name, PythonProjectFile, PythonProjectLock},
+    reqwest::build_reqwest_clients,
+};
+use pypi_mapping::prefix_pypi_name_mapping::conda_pypi_name_compressed_mapping;
+use rattler_conda_types::{
+    MatchSpec, NamedChannelOrUrl, NamelessMatchSpec, ParseStrictness::Lenient, Platform,
+};
+use rattler_lock::{LockFile, PypiPackageEnvironmentData};
+use reqwest_middleware::ClientWithMiddleware;
 use tokio::fs::OpenOptions;
 use url::Url;
 use uv_normalize::PackageName;
@@ -44,7 +57,8 @@ pub struct Args {
     #[arg(short, long = "platform", id = "platform")]
     pub platforms: Vec<String>,
 
-    /// Environment.yml file to bootstrap the project.
+    /// Environment.yml, conda-lock.yml, pyproject.toml, poetry.lock, pdm.lock
+    /// or uv.lock file to bootstrap the project.
     #[arg(short = 'i', long = "import")]
     pub env_file: Option<PathBuf>,
 
@@ -57,6 +71,21 @@ pub struct Args {
     #[arg(long, conflicts_with_all = ["env_file", "format"], alias = "pyproject", hide = true)]
     pub pyproject_toml: bool,
 
+    /// Add the PyPI dependencies of an imported `pyproject.toml` as conda
+    /// dependencies if conda-forge has an equivalent package.
+    #[arg(long, requires = "env_file")]
+    pub prefer_conda: bool,
+
+    /// An existing conda environment to create the project from, the project
+    /// requests the same packages.
+    #[arg(long, value_name = "PREFIX", conflicts_with_all = ["env_file", "format", "pyproject_toml"])]
+    pub from_prefix: Option<PathBuf>,
+
+    /// Also create a lock file with the exact packages installed in the conda
+    /// environment given by `--from-prefix`.
+    #[arg(long, requires = "from_prefix")]
+    pub lock: bool,
+
     /// Source Control Management used for this project
     #[arg(short = 's', long = "scm", ignore_case = true)]
     pub scm: Option<GitAttributes>,
@@ -75,11 +104,12 @@ name = "{{ name }}"
 platforms = {{ platforms }}
 version = "{{ version }}"
 
-{%- if index_url or extra_indexes %}
+{%- if index_url or extra_index_urls or find_links %}
 
 [pypi-options]
 {% if index_url %}index-url = "{{ index_url }}"{% endif %}
 {% if extra_index_urls %}extra-index-urls = {{ extra_index_urls }}{% endif %}
+{% if find_links %}find-links = [{{ find_links|join(", ") }}]{% endif %}
 {%- endif %}
 
 [tasks]
@@ -136,11 +166,12 @@ channels = {{ channels }}
 platforms = {{ platforms }}
 
 
-{%- if index_url or extra_indexes %}
+{%- if index_url or extra_index_urls or find_links %}
 
 [tool.pixi.pypi-options]
 {% if index_url %}index-url = "{{ index_url }}"{% endif %}
 {% if extra_index_urls %}extra-index-urls = {{ extra_index_urls }}{% endif %}
+{% if find_links %}find-links = [{{ find_links|join(", ") }}]{% endif %}
 {%- endif %}
 
 [tool.pixi.pypi-dependencies]
@@ -210,9 +241,91 @@ pub async fn execute(args: Args) -> miette::Result<()> {
         args.platforms.clone()
     };
 
+    // Create a 'pixi.toml' manifest, and optionally a lock file, from the packages
+    // installed in an existing conda environment
+    if let Some(prefix_path) = &args.from_prefix {
+        if pixi_manifest_path.is_file() {
+            miette::bail!("{} already exists", consts::PROJECT_MANIFEST);
+        }
+
+        import_conda_prefix(
+            &env,
+            prefix_path,
+            &pixi_manifest_path,
+            default_name,
+            version,
+            author.as_ref(),
+            &args.platforms,
+            args.channels.clone(),
+            &config,
+            args.lock,
+        )?;
+    // Create a 'pixi.toml' manifest by importing the dependencies of a python
+    // project managed by another tool
+    } else if let Some(python_project_path) = args.env_file.as_deref().filter(|path| {
+        path.file_name() == Some(consts::PYPROJECT_MANIFEST.as_ref())
+            || PythonProjectLock::is_lock_file(path)
+    }) {
+        if pixi_manifest_path.is_file() {
+            miette::bail!("{} already exists", consts::PROJECT_MANIFEST);
+        }
+
+        // A lock file is imported with the project next to it, of which the
+        // dependencies are pinned to the locked versions
+        let (python_project_path, lock) = if PythonProjectLock::is_lock_file(python_project_path) {
+            let lock = PythonProjectLock::from_path(python_project_path)?;
+            let python_project_path = python_project_path
+                .parent()
+                .unwrap_or(Path::new(""))
+                .join(consts::PYPROJECT_MANIFEST);
+            (python_project_path, Some(lock))
+        } else {
+            (python_project_path.to_path_buf(), None)
+        };
+
+        let conda_names = if args.prefer_conda {
+            let (_, client) = build_reqwest_clients(Some(&config));
+            conda_package_names(&client).await?
+        } else {
+            HashMap::new()
+        };
+
+        import_python_project(
+            &env,
+            &python_project_path,
+            lock.as_ref(),
+            &pixi_manifest_path,
+            default_name,
+            version,
+            author.as_ref(),
+            &platforms,
+            &config,
+            &conda_names,
+        )?;
+    // Create a 'pixi.toml' manifest and a lock file by importing a conda-lock
+    // file
+    } else if let Some(conda_lock_path) = args
+        .env_file
+        .as_deref()
+        .filter(|path| CondaLockFile::is_conda_lock_file(path))
+    {
+        if pixi_manifest_path.is_file() {
+            miette::bail!("{} already exists", consts::PROJECT_MANIFEST);
+        }
+
+        import_conda_lock_file(
+            &env,
+            conda_lock_path,
+            &pixi_manifest_path,
+            default_name,
+            version,
+            author.as_ref(),
+            &args.platforms,
+            &config,
+        )?;
     // Create a 'pixi.toml' manifest and populate it by importing a conda
     // environment file
-    if let Some(env_file_path) = args.env_file {
+    } else if let Some(env_file_path) = args.env_file {
         // Check if the 'pixi.toml' file doesn't already exist. We don't want to
         // overwrite it.
         if pixi_manifest_path.is_file() {
@@ -237,6 +350,7 @@ pub async fn execute(args: Args) -> miette::Result<()> {
             &platforms,
             None,
             &vec![],
+            &vec![],
         );
         let mut project = Project::from_str(&pixi_manifest_path, &rv)?;
         let channel_config = project.channel_config();
@@ -288,6 +402,7 @@ pub async fn execute(args: Args) -> miette::Result<()> {
 
         let index_url = config.pypi_config.index_url;
         let extra_index_urls = config.pypi_config.extra_index_urls;
+        let find_links = render_find_links(&config.pypi_config.find_links);
 
         // Dialog with user to create a 'pyproject.toml' or 'pixi.toml' manifest
         // If nothing is defined but there is a `pyproject.toml` file, ask the user.
@@ -390,6 +505,7 @@ pub async fn execute(args: Args) -> miette::Result<()> {
                         platforms,
                         index_url => index_url.as_ref(),
                         extra_index_urls => &extra_index_urls,
+                        find_links => &find_links,
                     },
                 )
                 .unwrap();
@@ -435,6 +551,7 @@ pub async fn execute(args: Args) -> miette::Result<()> {
                 &platforms,
                 index_url.as_ref(),
                 &extra_index_urls,
+                &find_links,
             );
             save_manifest_file(&pixi_manifest_path, rv)?;
         };
@@ -463,6 +580,493 @@ pub async fn execute(args: Args) -> miette::Result<()> {
     Ok(())
 }
 
+/// Creates a manifest with the dependencies of a python project managed by
+/// poetry, PDM or uv. The dependency groups become features with an environment
+/// of the same name. If the lock file of the project is given, the dependencies
+/// are pinned to the versions it locked. The PyPI dependencies that have a
+/// conda package in `conda_names` are added as conda dependencies.
+#[allow(clippy::too_many_arguments)]
+fn import_python_project(
+    env: &Environment<'_>,
+    python_project_path: &Path,
+    lock: Option<&PythonProjectLock>,
+    manifest_path: &Path,
+    default_name: String,
+    version: &str,
+    author: Option<&(String, String)>,
+    platforms: &[String],
+    config: &Config,
+    conda_names: &HashMap<pep508_rs::PackageName, rattler_conda_types::PackageName>,
+) -> miette::Result<()> {
+    let python_project = PythonProjectFile::from_path(python_project_path)?;
+    let dependencies = python_project.to_manifest()?;
+    let name = python_project
+        .name()
+        .map(ToString::to_string)
+        .unwrap_or(default_name);
+
+    let mut rv = render_project(
+        env,
+        name,
+        version,
+        author,
+        config.default_channels(),
+        &platforms.to_vec(),
+        None,
+        &vec![],
+        &vec![],
+    );
+    // A package is installed in editable mode, like in a pyproject manifest
+    let is_project_dir = python_project_path
+        .canonicalize()
+        .is_ok_and(|path| path.parent() == manifest_path.parent());
+    if let Some(package_name) = dependencies.package_name.as_deref() {
+        if is_project_dir {
+            rv.push_str(&format!(
+                "[pypi-dependencies]\n{} = {{ path = \".\", editable = true }}\n",
+                toml_edit::Key::new(package_name)
+            ));
+        } else {
+            tracing::warn!(
+                "'{}' is not in the project directory, add the package '{package_name}' manually",
+                python_project_path.display()
+            );
+        }
+    }
+    let mut project = Project::from_str(manifest_path, &rv)?;
+    let channel_config = project.channel_config();
+
+    let python_spec = match &dependencies.requires_python {
+        Some(requires_python) => {
+            format!("python {}", requires_python.to_string().replace(' ', ""))
+        }
+        None => "python".to_string(),
+    };
+    let python_spec = MatchSpec::from_str(&python_spec, Lenient).into_diagnostic()?;
+    let (Some(python), python_spec) = python_spec.into_nameless() else {
+        unreachable!("the python spec is named");
+    };
+    project.manifest.add_dependency(
+        &python,
+        &PixiSpec::from_nameless_matchspec(python_spec, &channel_config),
+        SpecType::Run,
+        &[],
+        &FeatureName::Default,
+        DependencyOverwriteBehavior::Overwrite,
+    )?;
+
+    let self_name = dependencies
+        .package_name
+        .as_deref()
+        .and_then(|name| pep508_rs::PackageName::from_str(name).ok());
+    let groups = std::iter::once((FeatureName::Default, &dependencies.dependencies)).chain(
+        dependencies
+            .groups
+            .iter()
+            .map(|(group, requirements)| (FeatureName::Named(group.clone()), requirements)),
+    );
+    for (feature_name, requirements) in groups {
+        let mut features = feature_name.name().map(|name| vec![name.to_string()]);
+        for requirement in requirements {
+            // References to the extras of the project itself include their features
+            if Some(&requirement.name) == self_name.as_ref() {
+                if let Some(features) = &mut features {
+                    features.extend(
+                        requirement
+                            .extras
+                            .iter()
+                            .map(|extra| feature_name(&extra.to_string())),
+                    );
+                }
+                continue;
+            }
+
+            let requirement = &match lock {
+                Some(lock) => lock.pin(requirement),
+                None => requirement.clone(),
+            };
+            if let Some((name, spec)) = conda_equivalent(requirement, conda_names) {
+                project.manifest.add_dependency(
+                    &name,
+                    &PixiSpec::from_nameless_matchspec(spec, &channel_config),
+                    SpecType::Run,
+                    &[],
+                    &feature_name,
+                    DependencyOverwriteBehavior::Overwrite,
+                )?;
+            } else {
+                project.manifest.add_pep508_dependency(
+                    requirement,
+                    &[],
+                    &feature_name,
+                    None,
+                    DependencyOverwriteBehavior::Overwrite,
+                    &None,
+                )?;
+            }
+        }
+        if let Some(features) = features {
+            project.manifest.add_environment(
+                features[0].clone(),
+                Some(features.into_iter().unique().collect()),
+                None,
+                false,
+            )?;
+        }
+    }
+    project.save()?;
+
+    eprintln!(
+        "{}Created {} from the {} project",
+        console::style(console::Emoji("✔ ", "")).green(),
+        project.manifest_path().display(),
+        dependencies.tool
+    );
+
+    Ok(())
+}
+
+/// Returns the conda-forge package that provides a PyPI package, by the name of
+/// the PyPI package.
+async fn conda_package_names(
+    client: &ClientWithMiddleware,
+) -> miette::Result<HashMap<pep508_rs::PackageName, rattler_conda_types::PackageName>> {
+    let mapping = conda_pypi_name_compressed_mapping(client).await?;
+    let mut conda_names: HashMap<pep508_rs::PackageName, Vec<String>> = HashMap::new();
+    for (conda_name, pypi_name) in mapping {
+        let Some(pypi_name) =
+            pypi_name.and_then(|name| pep508_rs::PackageName::from_str(&name).ok())
+        else {
+            continue;
+        };
+        conda_names.entry(pypi_name).or_default().push(conda_name);
+    }
+
+    // Prefer the package with the same name, otherwise the one with the shortest
+    // name as the other packages are usually variants of it.
+    Ok(conda_names
+        .into_iter()
+        .filter_map(|(pypi_name, conda_names)| {
+            let conda_name = conda_names
+                .iter()
+                .find(|name| name.as_str() == pypi_name.as_ref())
+                .or_else(|| {
+                    conda_names
+                        .iter()
+                        .min_by(|a, b| a.len().cmp(&b.len()).then(a.cmp(b)))
+                })?;
+            let conda_name = rattler_conda_types::PackageName::from_str(conda_name).ok()?;
+            Some((pypi_name, conda_name))
+        })
+        .collect())
+}
+
+/// Returns the conda package and spec that is equivalent to a PyPI requirement.
+/// Requirements with extras, markers or a direct reference stay PyPI
+/// dependencies.
+fn conda_equivalent(
+    requirement: &pep508_rs::Requirement,
+    conda_names: &HashMap<pep508_rs::PackageName, rattler_conda_types::PackageName>,
+) -> Option<(rattler_conda_types::PackageName, NamelessMatchSpec)> {
+    let conda_name = conda_names.get(&requirement.name)?;
+    if !requirement.extras.is_empty() || !requirement.marker.is_true() {
+        return None;
+    }
+    let spec = match &requirement.version_or_url {
+        None => String::new(),
+        Some(pep508_rs::VersionOrUrl::VersionSpecifier(specifiers)) => {
+            specifiers.to_string().replace(' ', "")
+        }
+        Some(pep508_rs::VersionOrUrl::Url(_)) => return None,
+    };
+    let spec = if spec.is_empty() {
+        NamelessMatchSpec::default()
+    } else {
+        NamelessMatchSpec::from_str(&spec, Lenient).ok()?
+    };
+    Some((conda_name.clone(), spec))
+}
+
+/// Creates a manifest with the requested packages of a conda-lock file, and a
+/// lock file with the packages that it locked.
+#[allow(clippy::too_many_arguments)]
+fn import_conda_lock_file(
+    env: &Environment<'_>,
+    conda_lock_path: &Path,
+    manifest_path: &Path,
+    name: String,
+    version: &str,
+    author: Option<&(String, String)>,
+    platforms: &[String],
+    config: &Config,
+) -> miette::Result<()> {
+    let conda_lock = CondaLockFile::from_path(conda_lock_path)?;
+
+    let platforms = if platforms.is_empty() {
+        conda_lock
+            .platforms()
+            .iter()
+            .map(Platform::to_string)
+            .collect()
+    } else {
+        platforms.to_vec()
+    };
+    let mut channels = conda_lock.channels()?;
+    if channels.is_empty() {
+        channels = config.default_channels();
+    }
+
+    let rv = render_project(
+        env,
+        name,
+        version,
+        author,
+        channels,
+        &platforms,
+        None,
+        &vec![],
+        &vec![],
+    );
+    let mut project = Project::from_str(manifest_path, &rv)?;
+    let channel_config = project.channel_config();
+    let pinning_strategy = config.pinning_strategy.unwrap_or_default();
+    for dependency in conda_lock.to_manifest(pinning_strategy)? {
+        // The categories other than the main category become features
+        let feature_name = if dependency.category == MAIN_CATEGORY {
+            FeatureName::Default
+        } else {
+            FeatureName::Named(dependency.category)
+        };
+        match dependency.spec {
+            CondaLockSpec::Conda(spec) => {
+                let (Some(name), spec) = spec.clone().into_nameless() else {
+                    miette::bail!("the conda-lock dependency '{spec}' has no package name");
+                };
+                let spec = PixiSpec::from_nameless_matchspec(spec, &channel_config);
+                project.manifest.add_dependency(
+                    &name,
+                    &spec,
+                    SpecType::Run,
+                    &dependency.platforms,
+                    &feature_name,
+                    DependencyOverwriteBehavior::Overwrite,
+                )?;
+            }
+            CondaLockSpec::Pypi(requirement) => {
+                project.manifest.add_pep508_dependency(
+                    &requirement,
+                    &dependency.platforms,
+                    &feature_name,
+                    None,
+                    DependencyOverwriteBehavior::Overwrite,
+                    &None,
+                )?;
+            }
+        }
+    }
+    for category in conda_lock.categories().into_iter().skip(1) {
+        project.manifest.add_environment(
+            category.to_string(),
+            Some(vec![category.to_string()]),
+            None,
+            false,
+        )?;
+    }
+    project.save()?;
+    eprintln!(
+        "{}Created {}",
+        console::style(console::Emoji("✔ ", "")).green(),
+        project.manifest_path().display()
+    );
+
+    // Lock the packages of the conda-lock file, the environment of a category
+    // also contains the packages of the main category.
+    let mut builder = LockFile::builder();
+    for environment in project.environments() {
+        let environment_name = environment.name().to_string();
+        let categories = if environment.name().is_default() {
+            vec![MAIN_CATEGORY]
+        } else {
+            vec![MAIN_CATEGORY, environment_name.as_str()]
+        };
+
+        let channels: Vec<String> = environment
+            .channel_urls(&channel_config)
+            .into_diagnostic()?
+            .into_iter()
+            .map(|channel| channel.to_string())
+            .collect();
+        builder.set_channels(&environment_name, channels);
+
+        let mut has_pypi_packages = false;
+        for platform in environment.platforms() {
+            let (conda_packages, pypi_packages) =
+                conda_lock.locked_packages(platform, &categories)?;
+            for record in conda_packages {
+                builder.add_conda_package(&environment_name, platform, record.into());
+            }
+            for package in pypi_packages {
+                builder.add_pypi_package(
+                    &environment_name,
+                    platform,
+                    package,
+                    PypiPackageEnvironmentData::default(),
+                );
+                has_pypi_packages = true;
+            }
+        }
+        if has_pypi_packages {
+            builder.set_pypi_indexes(&environment_name, environment.pypi_options().into());
+        }
+    }
+    let lock_file_path = project.lock_file_path();
+    builder
+        .finish()
+        .to_path(&lock_file_path)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("failed to write {}", lock_file_path.display()))?;
+    eprintln!(
+        "{}Created {} from {}",
+        console::style(console::Emoji("✔ ", "")).green(),
+        lock_file_path.display(),
+        conda_lock_path.display()
+    );
+
+    Ok(())
+}
+
+/// Creates a manifest that requests the packages installed in an existing conda
+/// environment, and optionally a lock file with the exact installed packages.
+#[allow(clippy::too_many_arguments)]
+fn import_conda_prefix(
+    env: &Environment<'_>,
+    prefix_path: &Path,
+    manifest_path: &Path,
+    name: String,
+    version: &str,
+    author: Option<&(String, String)>,
+    platforms: &[String],
+    channels: Option<Vec<NamedChannelOrUrl>>,
+    config: &Config,
+    lock: bool,
+) -> miette::Result<()> {
+    let conda_prefix = CondaPrefix::from_path(prefix_path)?;
+    let prefix_platform = conda_prefix.platform().unwrap_or_else(Platform::current);
+
+    let platforms = if platforms.is_empty() {
+        vec![prefix_platform.to_string()]
+    } else {
+        platforms.to_vec()
+    };
+    let channels = match channels {
+        Some(channels) => channels,
+        None => {
+            let channels = conda_prefix.channels()?;
+            if channels.is_empty() {
+                config.default_channels()
+            } else {
+                channels
+            }
+        }
+    };
+
+    let rv = render_project(
+        env,
+        name,
+        version,
+        author,
+        channels,
+        &platforms,
+        None,
+        &vec![],
+        &vec![],
+    );
+    let mut project = Project::from_str(manifest_path, &rv)?;
+    let channel_config = project.channel_config();
+    let pinning_strategy = config.pinning_strategy.unwrap_or_default();
+    for spec in conda_prefix.to_manifest(pinning_strategy)? {
+        let (Some(name), spec) = spec.clone().into_nameless() else {
+            miette::bail!("the dependency '{spec}' of the prefix has no package name");
+        };
+        project.manifest.add_dependency(
+            &name,
+            &PixiSpec::from_nameless_matchspec(spec, &channel_config),
+            SpecType::Run,
+            &[],
+            &FeatureName::Default,
+            DependencyOverwriteBehavior::Overwrite,
+        )?;
+    }
+    let pypi_packages = conda_prefix.pypi_packages()?;
+    for requirement in &pypi_packages {
+        project.manifest.add_pep508_dependency(
+            requirement,
+            &[],
+            &FeatureName::Default,
+            None,
+            DependencyOverwriteBehavior::Overwrite,
+            &None,
+        )?;
+    }
+    project.save()?;
+    eprintln!(
+        "{}Created {} from {}",
+        console::style(console::Emoji("✔ ", "")).green(),
+        project.manifest_path().display(),
+        prefix_path.display()
+    );
+
+    if !lock {
+        return Ok(());
+    }
+    if !project
+        .default_environment()
+        .platforms()
+        .contains(&prefix_platform)
+    {
+        miette::bail!(
+            "the platform '{}' of '{}' is not a platform of the project, it can't be locked",
+            prefix_platform,
+            prefix_path.display()
+        );
+    }
+
+    // Only the platform of the prefix is locked, the packages of the other
+    // platforms are solved when the environment is installed.
+    let mut builder = LockFile::builder();
+    let environment = project.default_environment();
+    let environment_name = environment.name().to_string();
+    let channels: Vec<String> = environment
+        .channel_urls(&channel_config)
+        .into_diagnostic()?
+        .into_iter()
+        .map(|channel| channel.to_string())
+        .collect();
+    builder.set_channels(&environment_name, channels);
+    for record in conda_prefix.records() {
+        builder.add_conda_package(&environment_name, prefix_platform, record.into());
+    }
+    let lock_file_path = project.lock_file_path();
+    builder
+        .finish()
+        .to_path(&lock_file_path)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("failed to write {}", lock_file_path.display()))?;
+    eprintln!(
+        "{}Created {} from {}",
+        console::style(console::Emoji("✔ ", "")).green(),
+        lock_file_path.display(),
+        prefix_path.display()
+    );
+    if !pypi_packages.is_empty() {
+        tracing::warn!(
+            "The packages installed with pip are not locked, they are resolved when the environment is installed"
+        );
+    }
+
+    Ok(())
+}
+
 #[allow(clippy::too_many_arguments)]
 fn render_project(
     env: &Environment<'_>,
@@ -473,6 +1077,7 @@ fn render_project(
     platforms: &Vec<String>,
     index_url: Option<&Url>,
     extra_index_urls: &Vec<Url>,
+    find_links: &Vec<String>,
 ) -> String {
     env.render_named_str(
         consts::PROJECT_MANIFEST,
@@ -485,11 +1090,27 @@ fn render_project(
             platforms,
             index_url,
             extra_index_urls,
+            find_links,
         },
     )
     .unwrap()
 }
 
+/// Renders the flat indexes from the config as the inline tables of the
+/// `find-links` of the `pypi-options`.
+fn render_find_links(find_links: &[FindLinks]) -> Vec<String> {
+    find_links
+        .iter()
+        .map(|find_link| match find_link {
+            FindLinks::Url(url) => format!("{{ url = {} }}", toml_edit::Value::from(url.as_str())),
+            FindLinks::Path(path) => format!(
+                "{{ path = {} }}",
+                toml_edit::Value::from(path.display().to_string())
+            ),
+        })
+        .collect()
+}
+
 /// Save the rendered template to a file, and print a message to the user.
 fn save_manifest_file(path: &Path, content: String) -> miette::Result<()> {
     fs_err::write(path, content).into_diagnostic()?;
@@ -622,4 +1243,123 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_prefer_conda_requires_import() {
+        assert!(Args::try_parse_from(["init", "--prefer-conda"]).is_err());
+        let args =
+            Args::try_parse_from(["init", "--import", "pyproject.toml", "--prefer-conda"]).unwrap();
+        assert!(args.prefer_conda);
+    }
+
+    #[test]
+    fn test_conda_equivalent() {
+        let conda_names = HashMap::from([(
+            pep508_rs::PackageName::from_str("numpy").unwrap(),
+            rattler_conda_types::PackageName::from_str("numpy").unwrap(),
+        )]);
+        let equivalent = |requirement: &str| {
+            conda_equivalent(
+                &pep508_rs::Requirement::from_str(requirement).unwrap(),
+                &conda_names,
+            )
+            .map(|(name, spec)| format!("{} {}", name.as_normalized(), spec))
+        };
+
+        assert_eq!(equivalent("numpy>=1.24"), Some("numpy >=1.24".to_string()));
+        assert_eq!(
+            conda_equivalent(
+                &pep508_rs::Requirement::from_str("numpy").unwrap(),
+                &conda_names
+            )
+            .map(|(_, spec)| spec),
+            Some(NamelessMatchSpec::default())
+        );
+        assert_eq!(equivalent("requests>=2"), None);
+        assert_eq!(equivalent("numpy[dev]>=1.24"), None);
+        assert_eq!(equivalent("numpy>=1.24 ; sys_platform == 'linux'"), None);
+        assert_eq!(
+            equivalent("numpy @ https://example.com/numpy-2.0.0.tar.gz"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_import_python_project() {
+        let dir = tempdir().unwrap();
+        let dir = dir.path().canonicalize().unwrap();
+        let python_project_path = dir.join(consts::PYPROJECT_MANIFEST);
+        fs_err::write(
+            &python_project_path,
+            r#"
+            [project]
+            name = "my-project"
+            requires-python = ">=3.11"
+            dependencies = ["numpy>=1.24", "requests"]
+
+            [project.optional-dependencies]
+            test = ["pytest"]
+
+            [dependency-groups]
+            default = ["numpy>=2"]
+
+            [build-system]
+            requires = ["hatchling"]
+            build-backend = "hatchling.build"
+            "#,
+        )
+        .unwrap();
+        let manifest_path = dir.join(consts::PROJECT_MANIFEST);
+        let conda_names = HashMap::from([(
+            pep508_rs::PackageName::from_str("numpy").unwrap(),
+            rattler_conda_types::PackageName::from_str("numpy").unwrap(),
+        )]);
+
+        import_python_project(
+            &Environment::new(),
+            &python_project_path,
+            None,
+            &manifest_path,
+            "default-name".to_string(),
+            "0.1.0",
+            None,
+            &[Platform::Linux64.to_string()],
+            &Config::default(),
+            &conda_names,
+        )
+        .unwrap();
+
+        let manifest: toml_edit::DocumentMut = fs_err::read_to_string(&manifest_path)
+            .unwrap()
+            .parse()
+            .unwrap();
+        let workspace = manifest
+            .get("workspace")
+            .or(manifest.get("project"))
+            .unwrap();
+        assert_eq!(workspace["name"].as_str(), Some("my-project"));
+
+        // The PyPI packages with a conda equivalent become conda dependencies.
+        assert_eq!(manifest["dependencies"]["numpy"].as_str(), Some(">=1.24"));
+        assert_eq!(manifest["dependencies"]["python"].as_str(), Some(">=3.11"));
+        assert!(manifest["pypi-dependencies"].get("requests").is_some());
+        assert!(manifest["pypi-dependencies"].get("numpy").is_none());
+        assert_eq!(
+            manifest["pypi-dependencies"]["my-project"]["editable"].as_bool(),
+            Some(true)
+        );
+
+        // The groups become features with an environment, the `default`
+        // group doesn't clash with the default feature and environment.
+        assert!(manifest["feature"]["test"]["pypi-dependencies"]
+            .get("pytest")
+            .is_some());
+        assert_eq!(
+            manifest["feature"]["default-group"]["dependencies"]["numpy"].as_str(),
+            Some(">=2")
+        );
+        assert!(manifest["environments"].get("test").is_some());
+        assert!(manifest["environments"].get("default-group").is_some());
+        assert!(manifest["environments"].get("default").is_none());
+    }
 }
@@ -1,11 +1,14 @@
 use crate::cli::cli_config::ProjectConfig;
 use crate::environment::get_update_lock_file_and_prefix;
 use crate::lock_file::UpdateMode;
+use crate::prefix::Prefix;
+use crate::repair::{repair_prefix, Damage};
 use crate::{Project, UpdateLockFileOptions};
 use clap::Parser;
 use fancy_display::FancyDisplay;
 use itertools::Itertools;
 use pixi_config::ConfigCli;
+use pixi_manifest::ExcludeNewer;
 
 /// Install all dependencies
 #[derive(Parser, Debug)]
@@ -25,11 +28,22 @@ pub struct Args {
 
     #[arg(long, short, conflicts_with = "environment")]
     pub all: bool,
+
+    /// Verify the files of the installed packages and reinstall the packages
+    /// with missing or modified files
+    #[arg(long)]
+    pub repair: bool,
+
+    /// Ignore packages that were published after this date (e.g. `2024-06-01`)
+    /// or RFC 3339 timestamp, overrides the `exclude-newer` of the manifest.
+    #[clap(long)]
+    pub exclude_newer: Option<ExcludeNewer>,
 }
 
 pub async fn execute(args: Args) -> miette::Result<()> {
     let project = Project::load_or_else_discover(args.project_config.manifest_path.as_deref())?
-        .with_cli_config(args.config);
+        .with_cli_config(args.config)
+        .with_exclude_newer(args.exclude_newer);
 
     // Install either:
     //
@@ -52,8 +66,39 @@ pub async fn execute(args: Args) -> miette::Result<()> {
     for env in envs {
         let environment = project.environment_from_name_or_env_var(Some(env))?;
 
+        // Forget the damaged packages, so they are installed again below. They
+        // are verified against the up-to-date lock-file, the packages that don't
+        // match it are replaced anyway.
+        let repair = if args.repair {
+            let lock_file = project
+                .update_lock_file(UpdateLockFileOptions {
+                    lock_file_usage: args.lock_file_usage.into(),
+                    no_install: false,
+                    max_concurrent_solves: project.config().max_concurrent_solves(),
+                })
+                .await?;
+            let repair = repair_prefix(
+                &environment,
+                &Prefix::new(environment.dir()),
+                &lock_file.lock_file,
+            )
+            .await?;
+            for package in &repair.damaged {
+                eprintln!(
+                    "{}Reinstalling {} in the {} environment: {}",
+                    console::style(console::Emoji("⚠️ ", "")).yellow(),
+                    console::style(package.name.as_normalized()).bold(),
+                    environment.name().fancy_display(),
+                    format_damage(&package.damage)
+                );
+            }
+            Some(repair)
+        } else {
+            None
+        };
+
         // Update the prefix by installing all packages
-        get_update_lock_file_and_prefix(
+        let result = get_update_lock_file_and_prefix(
             &environment,
             UpdateMode::Revalidate,
             UpdateLockFileOptions {
@@ -62,7 +107,13 @@ pub async fn execute(args: Args) -> miette::Result<()> {
                 max_concurrent_solves: project.config().max_concurrent_solves(),
             },
         )
-        .await?;
+        .await;
+        if let Some(repair) = repair {
+            repair.finish()?;
+        }
+        let (lock_file, _) = result?;
+
+        lock_file.sync_external_managers(&environment).await?;
 
         installed_envs.push(environment.name().clone());
     }
@@ -94,3 +145,14 @@ pub async fn execute(args: Args) -> miette::Result<()> {
     Project::warn_on_discovered_from_env(args.project_config.manifest_path.as_deref());
     Ok(())
 }
+
+/// Formats the first few problems of a damaged package.
+fn format_damage(damage: &[Damage]) -> String {
+    const MAX_SHOWN: usize = 3;
+    let shown = damage.iter().take(MAX_SHOWN).join(", ");
+    if damage.len() > MAX_SHOWN {
+        format!("{shown} and {} more", damage.len() - MAX_SHOWN)
+    } else {
+        shown
+    }
+}
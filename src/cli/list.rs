@@ -1,25 +1,36 @@
 use std::borrow::Cow;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::io;
 use std::io::{stdout, Write};
+use std::str::FromStr;
 
 use clap::Parser;
 use console::Color;
+use futures::{stream, StreamExt, TryStreamExt};
 use human_bytes::human_bytes;
 use itertools::Itertools;
-use miette::IntoDiagnostic;
+use miette::{Context, IntoDiagnostic};
 
 use crate::cli::cli_config::{PrefixUpdateConfig, ProjectConfig};
-use crate::lock_file::{UpdateLockFileOptions, UvResolutionContext};
+use crate::clobbers::{find_clobbers, Clobber};
+use crate::lock_file::{UpdateLockFileOptions, UpdateMode, UvResolutionContext};
+use crate::prefix::Prefix;
+use crate::project::Environment;
+use crate::repodata::Repodata;
 use crate::Project;
 use fancy_display::FancyDisplay;
-use pixi_manifest::FeaturesExt;
+use pixi_manifest::{CondaDependencies, FeaturesExt, PyPiDependencies, PyPiRequirement};
+use pixi_progress::await_in_progress;
 use pixi_uv_conversions::{
     pypi_options_to_index_locations, to_uv_normalize, to_uv_version, ConversionError,
 };
 use pypi_modifiers::pypi_tags::{get_pypi_tags, is_python_record};
-use rattler_conda_types::Platform;
-use rattler_lock::{CondaPackageData, LockedPackageRef, PypiPackageData, UrlOrPath};
-use serde::Serialize;
+use rattler_conda_types::{Channel, MatchSpec, Platform};
+use rattler_lock::{CondaPackageData, LockedPackageRef, PypiIndexes, PypiPackageData, UrlOrPath};
+use rattler_repodata_gateway::RepoData;
+use reqwest_middleware::ClientWithMiddleware;
+use serde::{Deserialize, Serialize};
+use url::Url;
 use uv_distribution::RegistryWheelIndex;
 
 // an enum to sort by size or name
@@ -69,6 +80,28 @@ pub struct Args {
     /// Only list packages that are explicitly defined in the project.
     #[arg(short = 'x', long)]
     pub explicit: bool,
+
+    /// Only list the PyPI packages that are installed in editable mode, with
+    /// the source directory they point to.
+    #[arg(long)]
+    pub editable: bool,
+
+    /// Only list the distributions that are provided by both a conda package
+    /// and a PyPI package.
+    #[arg(long)]
+    pub conflicts: bool,
+
+    /// Only list the packages for which a newer version is available in the
+    /// channels or the PyPI indexes, with the latest version and the
+    /// requested spec.
+    #[arg(long)]
+    pub outdated: bool,
+
+    /// List the files of the installed environment that are provided by
+    /// multiple conda packages, with the package whose file is installed and
+    /// the packages whose files are clobbered.
+    #[arg(long, conflicts_with_all = ["platform", "conflicts", "outdated", "editable", "explicit"])]
+    pub clobbers: bool,
 }
 
 fn serde_skip_is_editable(editable: &bool) -> bool {
@@ -86,6 +119,10 @@ struct PackageToOutput {
     is_explicit: bool,
     #[serde(skip_serializing_if = "serde_skip_is_editable")]
     is_editable: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    latest_version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    requested_spec: Option<String>,
 }
 
 /// Get directory size
@@ -133,6 +170,22 @@ impl PackageExt {
         }
     }
 
+    /// Returns the normalized names of the PyPI distributions that the package
+    /// provides. Conda packages provide the distributions of their pypi purls.
+    fn distribution_names(&self) -> Vec<pep508_rs::PackageName> {
+        match self {
+            Self::Conda(value) => value
+                .record()
+                .purls
+                .iter()
+                .flatten()
+                .filter(|purl| purl.package_type() == "pypi")
+                .filter_map(|purl| pep508_rs::PackageName::from_str(purl.name()).ok())
+                .collect(),
+            Self::PyPI(value, _) => vec![value.name.clone()],
+        }
+    }
+
     /// Returns the version string of the package
     pub fn version(&self) -> Cow<'_, str> {
         match self {
@@ -146,7 +199,7 @@ pub async fn execute(args: Args) -> miette::Result<()> {
     let project = Project::load_or_else_discover(args.project_config.manifest_path.as_deref())?;
     let environment = project.environment_from_name_or_env_var(args.environment)?;
 
-    let lock_file = project
+    let mut lock_file = project
         .update_lock_file(UpdateLockFileOptions {
             lock_file_usage: args.prefix_update_config.lock_file_usage(),
             no_install: args.prefix_update_config.no_install,
@@ -154,6 +207,35 @@ pub async fn execute(args: Args) -> miette::Result<()> {
         })
         .await?;
 
+    // The clobbered files are read from the installed environment.
+    if args.clobbers {
+        let prefix = if args.prefix_update_config.no_install {
+            Prefix::new(environment.dir())
+        } else {
+            lock_file
+                .prefix(&environment, UpdateMode::QuickValidate)
+                .await?
+        };
+        let clobbers = installed_clobbers(&prefix, args.regex.as_deref())?;
+
+        if args.json || args.json_pretty {
+            json_packages(&clobbers, args.json_pretty);
+        } else if clobbers.is_empty() {
+            eprintln!(
+                "{}No clobbered files found.",
+                console::style(console::Emoji("✔ ", "")).green(),
+            );
+        } else {
+            if !environment.is_default() {
+                eprintln!("Environment: {}", environment.name().fancy_display());
+            }
+            print_clobbers_as_table(&clobbers).expect("an io error occurred");
+        }
+
+        Project::warn_on_discovered_from_env(args.project_config.manifest_path.as_deref());
+        return Ok(());
+    }
+
     // Load the platform
     let platform = args.platform.unwrap_or_else(|| environment.best_platform());
 
@@ -221,10 +303,51 @@ pub async fn execute(args: Args) -> miette::Result<()> {
             .map(|(name, _)| name.as_normalized().as_dist_info_name().into_owned()),
     );
 
+    // Determine the distributions that are provided by both a conda and a PyPI
+    // package.
+    let conflicting_names = if args.conflicts {
+        conflicting_distributions(&locked_deps_ext)
+    } else {
+        HashSet::new()
+    };
+
+    // Look up the latest versions of the packages to determine which ones are
+    // outdated.
+    let latest_versions = if args.outdated {
+        let pypi_indexes = lock_file
+            .lock_file
+            .environment(environment.name().as_str())
+            .and_then(|env| env.pypi_indexes());
+        Some(LatestVersions::fetch(&environment, pypi_indexes, platform, &locked_deps_ext).await?)
+    } else {
+        None
+    };
+    let conda_dependencies = environment.combined_dependencies(Some(platform));
+    let pypi_dependencies = environment.pypi_dependencies(Some(platform));
+
     let mut packages_to_output = locked_deps_ext
         .iter()
-        .map(|p| create_package_to_output(p, &project_dependency_names, registry_index.as_mut()))
-        .collect::<Result<Vec<PackageToOutput>, _>>()?;
+        .filter(|p| {
+            !args.conflicts
+                || p.distribution_names()
+                    .iter()
+                    .any(|name| conflicting_names.contains(name))
+        })
+        .map(|p| {
+            let mut package =
+                create_package_to_output(p, &project_dependency_names, registry_index.as_mut())?;
+            if let Some(latest_versions) = &latest_versions {
+                package.latest_version = latest_versions.newer_version(p);
+                package.requested_spec = requested_spec(p, &conda_dependencies, &pypi_dependencies);
+            }
+            Ok(package)
+        })
+        .collect::<miette::Result<Vec<PackageToOutput>>>()?;
+
+    // Filter packages by outdated if needed
+    if args.outdated {
+        packages_to_output.retain(|p| p.latest_version.is_some());
+    }
 
     // Filter packages by regex if needed
     if let Some(regex) = args.regex {
@@ -243,6 +366,14 @@ pub async fn execute(args: Args) -> miette::Result<()> {
             .collect::<Vec<_>>();
     }
 
+    // Filter packages by editable if needed
+    if args.editable {
+        packages_to_output = packages_to_output
+            .into_iter()
+            .filter(|p| p.is_editable)
+            .collect::<Vec<_>>();
+    }
+
     // Sort according to the sorting strategy
     match args.sort_by {
         SortBy::Size => {
@@ -276,13 +407,37 @@ pub async fn execute(args: Args) -> miette::Result<()> {
         }
 
         // print packages as table
-        print_packages_as_table(&packages_to_output).expect("an io error occurred");
+        if args.outdated {
+            print_outdated_packages_as_table(&packages_to_output)
+        } else {
+            print_packages_as_table(&packages_to_output)
+        }
+        .expect("an io error occurred");
     }
 
     Project::warn_on_discovered_from_env(args.project_config.manifest_path.as_deref());
     Ok(())
 }
 
+/// Returns the names of the distributions that are provided by a conda package
+/// and are also locked as a PyPI package.
+fn conflicting_distributions(packages: &[PackageExt]) -> HashSet<pep508_rs::PackageName> {
+    let conda_names: HashSet<_> = packages
+        .iter()
+        .filter(|p| p.as_conda().is_some())
+        .flat_map(PackageExt::distribution_names)
+        .collect();
+    packages
+        .iter()
+        .filter_map(|p| match p {
+            PackageExt::PyPI(data, _) if conda_names.contains(&data.name) => {
+                Some(data.name.clone())
+            }
+            _ => None,
+        })
+        .collect()
+}
+
 fn print_packages_as_table(packages: &Vec<PackageToOutput>) -> io::Result<()> {
     let mut writer = tabwriter::TabWriter::new(stdout());
 
@@ -334,7 +489,89 @@ fn print_packages_as_table(packages: &Vec<PackageToOutput>) -> io::Result<()> {
     writer.flush()
 }
 
-fn json_packages(packages: &Vec<PackageToOutput>, json_pretty: bool) {
+fn print_outdated_packages_as_table(packages: &Vec<PackageToOutput>) -> io::Result<()> {
+    let mut writer = tabwriter::TabWriter::new(stdout());
+
+    let header_style = console::Style::new().bold();
+    writeln!(
+        writer,
+        "{}\t{}\t{}\t{}\t{}",
+        header_style.apply_to("Package"),
+        header_style.apply_to("Version"),
+        header_style.apply_to("Latest"),
+        header_style.apply_to("Spec"),
+        header_style.apply_to("Kind"),
+    )?;
+
+    for package in packages {
+        if package.is_explicit {
+            write!(
+                writer,
+                "{}",
+                console::style(&package.name).fg(Color::Green).bold()
+            )?
+        } else {
+            write!(writer, "{}", &package.name)?;
+        };
+
+        writeln!(
+            writer,
+            "\t{}\t{}\t{}\t{}",
+            &package.version,
+            console::style(package.latest_version.as_deref().unwrap_or("")).fg(Color::Yellow),
+            package.requested_spec.as_deref().unwrap_or(""),
+            &package.kind,
+        )?;
+    }
+
+    writer.flush()
+}
+
+/// Returns the clobbered files of the prefix of which the path matches the
+/// regex.
+fn installed_clobbers(prefix: &Prefix, regex: Option<&str>) -> miette::Result<Vec<Clobber>> {
+    let mut clobbers = find_clobbers(&prefix.find_installed_packages()?);
+    if let Some(regex) = regex {
+        let regex = regex::Regex::new(regex).map_err(|_| miette::miette!("Invalid regex"))?;
+        clobbers.retain(|clobber| regex.is_match(&clobber.path.to_string_lossy()));
+    }
+    Ok(clobbers)
+}
+
+fn print_clobbers_as_table(clobbers: &[Clobber]) -> io::Result<()> {
+    let mut writer = tabwriter::TabWriter::new(stdout());
+
+    let header_style = console::Style::new().bold();
+    writeln!(
+        writer,
+        "{}\t{}\t{}",
+        header_style.apply_to("Path"),
+        header_style.apply_to("Installed from"),
+        header_style.apply_to("Clobbered"),
+    )?;
+
+    for clobber in clobbers {
+        writeln!(
+            writer,
+            "{}\t{}\t{}",
+            clobber.path.display(),
+            clobber
+                .package
+                .as_ref()
+                .map(|name| name.as_normalized())
+                .unwrap_or(""),
+            clobber
+                .clobbered
+                .iter()
+                .map(|name| name.as_normalized())
+                .join(", "),
+        )?;
+    }
+
+    writer.flush()
+}
+
+fn json_packages<T: Serialize>(packages: &[T], json_pretty: bool) {
     let json_string = if json_pretty {
         serde_json::to_string_pretty(&packages)
     } else {
@@ -367,24 +604,25 @@ fn create_package_to_output<'a, 'b>(
             pkg.record().size,
             Some(pkg.record().name.as_source().to_owned()),
         ),
-        PackageExt::PyPI(p, name) => {
-            if let Some(registry_index) = registry_index {
+        PackageExt::PyPI(p, name) => match registry_index {
+            // An editable package is not in the cache, it points to its source
+            // directory.
+            Some(registry_index) if !p.editable => {
                 let entry = registry_index.get(name).find(|i| {
                     i.dist.filename.version == to_uv_version(&p.version).expect("invalid version")
                 });
                 let size = entry.and_then(|e| get_dir_size(e.dist.path.clone()).ok());
                 let name = entry.map(|e| e.dist.filename.to_string());
                 (size, name)
-            } else {
-                match &p.location {
-                    UrlOrPath::Url(url) => (None, Some(url.to_string())),
-                    UrlOrPath::Path(path) => (
-                        get_dir_size(std::path::Path::new(path.as_str())).ok(),
-                        Some(path.to_string()),
-                    ),
-                }
             }
-        }
+            _ => match &p.location {
+                UrlOrPath::Url(url) => (None, Some(url.to_string())),
+                UrlOrPath::Path(path) => (
+                    get_dir_size(std::path::Path::new(path.as_str())).ok(),
+                    Some(path.to_string()),
+                ),
+            },
+        },
     };
 
     let is_explicit = project_dependency_names.contains(&name);
@@ -402,5 +640,497 @@ fn create_package_to_output<'a, 'b>(
         source,
         is_explicit,
         is_editable,
+        latest_version: None,
+        requested_spec: None,
+    })
+}
+
+/// Returns the specs with which the package is requested in the manifest, or
+/// `None` if it isn't a direct dependency.
+fn requested_spec(
+    package: &PackageExt,
+    conda_dependencies: &CondaDependencies,
+    pypi_dependencies: &PyPiDependencies,
+) -> Option<String> {
+    match package {
+        PackageExt::Conda(pkg) => conda_dependencies.get(&pkg.record().name).map(|specs| {
+            specs
+                .iter()
+                .map(|spec| match spec.as_version_spec() {
+                    Some(version_spec) => version_spec.to_string(),
+                    None => spec.to_toml_value().to_string(),
+                })
+                .join(", ")
+        }),
+        PackageExt::PyPI(pkg, _) => pypi_dependencies
+            .iter()
+            .find(|(name, _)| name.as_normalized() == &pkg.name)
+            .map(|(_, requirements)| {
+                requirements
+                    .iter()
+                    .map(|requirement| match requirement {
+                        PyPiRequirement::Version { version, .. }
+                        | PyPiRequirement::RawVersion(version) => version.to_string(),
+                        _ => requirement.to_string(),
+                    })
+                    .join(", ")
+            }),
+    }
+}
+
+/// The latest versions of the locked packages that are available in the
+/// channels and the PyPI indexes of an environment.
+struct LatestVersions {
+    conda: HashMap<rattler_conda_types::PackageName, rattler_conda_types::Version>,
+    pypi: HashMap<pep508_rs::PackageName, pep440_rs::Version>,
+}
+
+impl LatestVersions {
+    async fn fetch(
+        environment: &Environment<'_>,
+        pypi_indexes: Option<&PypiIndexes>,
+        platform: Platform,
+        packages: &[PackageExt],
+    ) -> miette::Result<Self> {
+        Ok(Self {
+            conda: latest_conda_versions(environment, platform, packages).await?,
+            pypi: latest_pypi_versions(environment, pypi_indexes, packages).await?,
+        })
+    }
+
+    /// Returns the latest version of the package if it is newer than the
+    /// locked version.
+    fn newer_version(&self, package: &PackageExt) -> Option<String> {
+        match package {
+            PackageExt::Conda(pkg) => {
+                let record = pkg.record();
+                self.conda
+                    .get(&record.name)
+                    .filter(|latest| *latest > record.version.version())
+                    .map(ToString::to_string)
+            }
+            PackageExt::PyPI(pkg, _) => self
+                .pypi
+                .get(&pkg.name)
+                .filter(|latest| **latest > pkg.version)
+                .map(ToString::to_string),
+        }
+    }
+}
+
+/// Returns the latest versions of the locked binary conda packages that are
+/// available in the channels of the environment.
+async fn latest_conda_versions(
+    environment: &Environment<'_>,
+    platform: Platform,
+    packages: &[PackageExt],
+) -> miette::Result<HashMap<rattler_conda_types::PackageName, rattler_conda_types::Version>> {
+    let specs = packages
+        .iter()
+        .filter_map(PackageExt::as_conda)
+        .filter_map(CondaPackageData::as_binary)
+        .map(|binary| MatchSpec::from(binary.package_record.name.clone()))
+        .collect_vec();
+    if specs.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let project = environment.project();
+    let channel_config = project.channel_config();
+    let channels: Vec<Channel> = environment
+        .channels()
+        .into_iter()
+        .cloned()
+        .map(|channel| channel.into_channel(&channel_config))
+        .try_collect()
+        .into_diagnostic()?;
+
+    let repodata = await_in_progress("fetching the latest conda package versions", |_| async {
+        project
+            .repodata_gateway()
+            .query(channels, [platform, Platform::NoArch], specs)
+            .await
+            .into_diagnostic()
     })
+    .await?;
+
+    let mut latest_versions = HashMap::new();
+    for record in repodata.iter().flat_map(RepoData::iter) {
+        let version = record.package_record.version.version();
+        let latest = latest_versions
+            .entry(record.package_record.name.clone())
+            .or_insert_with(|| version.clone());
+        if version > latest {
+            *latest = version.clone();
+        }
+    }
+    Ok(latest_versions)
+}
+
+/// The part of a project page of the JSON simple repository API (PEP 691)
+/// that lists the versions of the project (PEP 700).
+#[derive(Deserialize)]
+struct SimpleProjectVersions {
+    versions: Option<Vec<String>>,
+}
+
+/// The versions of a project on an index.
+#[derive(Debug, PartialEq)]
+enum IndexVersions {
+    /// The versions that the index lists for the project.
+    Versions(Vec<pep440_rs::Version>),
+    /// The index doesn't have the project.
+    NotFound,
+    /// The index doesn't list the versions of its projects, it doesn't serve
+    /// the JSON simple API or doesn't support PEP 700.
+    Unsupported,
+}
+
+/// Parses the versions from a project page of the JSON simple API.
+fn parse_index_versions(body: &[u8]) -> IndexVersions {
+    match serde_json::from_slice::<SimpleProjectVersions>(body) {
+        Ok(SimpleProjectVersions {
+            versions: Some(versions),
+        }) => IndexVersions::Versions(
+            versions
+                .iter()
+                .filter_map(|version| pep440_rs::Version::from_str(version).ok())
+                .collect(),
+        ),
+        _ => IndexVersions::Unsupported,
+    }
+}
+
+/// Returns the latest versions of the locked PyPI registry packages that are
+/// available in the indexes the environment was locked with. Pre-releases are
+/// only considered for packages that are locked at a pre-release.
+///
+/// Indexes that don't list the versions of their projects through the JSON
+/// simple API (PEP 700) are skipped with a warning, the packages from them
+/// can't be checked.
+async fn latest_pypi_versions(
+    environment: &Environment<'_>,
+    pypi_indexes: Option<&PypiIndexes>,
+    packages: &[PackageExt],
+) -> miette::Result<HashMap<pep508_rs::PackageName, pep440_rs::Version>> {
+    let packages = packages
+        .iter()
+        .filter_map(|p| match p {
+            PackageExt::PyPI(data, _) if !data.editable => match &data.location {
+                UrlOrPath::Url(url) if !url.scheme().starts_with("git+") => Some(data),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect_vec();
+    if packages.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let indexes = match pypi_indexes {
+        Some(indexes) if !indexes.indexes.is_empty() => indexes.indexes.clone(),
+        _ => vec![Url::parse("https://pypi.org/simple/").expect("the PyPI url is valid")],
+    };
+
+    let project = environment.project();
+    let client = project.authenticated_client();
+    let requests =
+        packages
+            .iter()
+            .cartesian_product(indexes.iter())
+            .map(|(package, index)| async move {
+                let versions = fetch_pypi_versions(client, index, &package.name).await?;
+                Ok::<_, miette::Report>((*package, index, versions))
+            });
+
+    let responses = await_in_progress("fetching the latest PyPI package versions", |_| {
+        stream::iter(requests)
+            .buffer_unordered(project.config().max_concurrent_downloads())
+            .try_collect::<Vec<_>>()
+    })
+    .await?;
+
+    let mut latest_versions = HashMap::new();
+    let mut unsupported_indexes = BTreeSet::new();
+    for (package, index, versions) in responses {
+        let versions = match versions {
+            IndexVersions::Versions(versions) => versions,
+            IndexVersions::NotFound => continue,
+            IndexVersions::Unsupported => {
+                unsupported_indexes.insert(index.as_str());
+                continue;
+            }
+        };
+        let Some(version) = latest_pypi_version(&package.version, versions) else {
+            continue;
+        };
+        let latest = latest_versions
+            .entry(package.name.clone())
+            .or_insert_with(|| version.clone());
+        if version > *latest {
+            *latest = version;
+        }
+    }
+    for index in unsupported_indexes {
+        tracing::warn!(
+            "the index '{index}' doesn't list the versions of its packages (PEP 700), the packages from it can't be checked for newer versions"
+        );
+    }
+    Ok(latest_versions)
+}
+
+/// Returns the latest of the versions of a PyPI package. Pre-releases are only
+/// considered if the locked version is a pre-release.
+fn latest_pypi_version(
+    locked: &pep440_rs::Version,
+    versions: Vec<pep440_rs::Version>,
+) -> Option<pep440_rs::Version> {
+    let allow_prerelease = locked.any_prerelease();
+    versions
+        .into_iter()
+        .filter(|version| allow_prerelease || !version.any_prerelease())
+        .max()
+}
+
+/// Fetches the versions of a project from the JSON simple API of an index.
+async fn fetch_pypi_versions(
+    client: &ClientWithMiddleware,
+    index: &Url,
+    name: &pep508_rs::PackageName,
+) -> miette::Result<IndexVersions> {
+    let url = format!("{}/{name}/", index.as_str().trim_end_matches('/'));
+    let response = client
+        .get(&url)
+        .header(
+            reqwest::header::ACCEPT,
+            "application/vnd.pypi.simple.v1+json",
+        )
+        .send()
+        .await
+        .into_diagnostic()
+        .with_context(|| format!("failed to query '{url}'"))?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(IndexVersions::NotFound);
+    }
+    if !response.status().is_success() {
+        tracing::debug!("'{url}' returned {}", response.status());
+        return Ok(IndexVersions::Unsupported);
+    }
+
+    let body = response
+        .bytes()
+        .await
+        .into_diagnostic()
+        .with_context(|| format!("failed to query '{url}'"))?;
+    let versions = parse_index_versions(&body);
+    if versions == IndexVersions::Unsupported {
+        tracing::debug!("'{url}' doesn't list the versions of the project");
+    }
+    Ok(versions)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::clobbers::tests::prefix_record;
+
+    fn pypi_package(name: &str, version: &str, location: &str, editable: bool) -> PackageExt {
+        let data = PypiPackageData {
+            name: name.parse().unwrap(),
+            version: pep440_rs::Version::from_str(version).unwrap(),
+            location: location.parse().unwrap(),
+            hash: None,
+            requires_dist: vec![],
+            requires_python: None,
+            editable,
+        };
+        let name = to_uv_normalize(&data.name).unwrap();
+        PackageExt::PyPI(data, name)
+    }
+
+    #[test]
+    fn test_editable_package_to_output() {
+        let editable = pypi_package("mypkg", "0.1.0", "./mypkg", true);
+        let package = create_package_to_output(&editable, &["mypkg".to_string()], None).unwrap();
+        assert!(package.is_editable);
+        assert!(package.is_explicit);
+        assert_eq!(package.source.as_deref(), Some("./mypkg"));
+        assert_eq!(
+            serde_json::to_value(&package).unwrap()["is_editable"],
+            serde_json::Value::Bool(true)
+        );
+
+        // A package that isn't editable doesn't report the field
+        let registry = pypi_package(
+            "rich",
+            "13.8.0",
+            "https://files.pythonhosted.org/packages/rich-13.8.0-py3-none-any.whl",
+            false,
+        );
+        let package = create_package_to_output(&registry, &[], None).unwrap();
+        assert!(!package.is_editable);
+        assert!(serde_json::to_value(&package)
+            .unwrap()
+            .get("is_editable")
+            .is_none());
+    }
+
+    #[test]
+    fn test_conflicting_distributions() {
+        let mut numpy = crate::lock_file::test_utils::record("numpy", "2.1.0");
+        numpy.package_record.purls =
+            Some([rattler_conda_types::PackageUrl::from_str("pkg:pypi/numpy").unwrap()].into());
+        let packages = [
+            PackageExt::Conda(numpy.into()),
+            PackageExt::Conda(crate::lock_file::test_utils::record("python", "3.12.0").into()),
+            pypi_package(
+                "numpy",
+                "2.1.1",
+                "https://files.pythonhosted.org/packages/numpy-2.1.1-py3-none-any.whl",
+                false,
+            ),
+            pypi_package(
+                "rich",
+                "13.8.0",
+                "https://files.pythonhosted.org/packages/rich-13.8.0-py3-none-any.whl",
+                false,
+            ),
+        ];
+        assert_eq!(
+            conflicting_distributions(&packages),
+            HashSet::from(["numpy".parse().unwrap()])
+        );
+    }
+
+    #[test]
+    fn test_latest_pypi_version() {
+        let versions = |versions: &[&str]| {
+            versions
+                .iter()
+                .map(|version| pep440_rs::Version::from_str(version).unwrap())
+                .collect_vec()
+        };
+        let available = versions(&["1.0.0", "1.2.0", "2.0.0rc1"]);
+
+        assert_eq!(
+            latest_pypi_version(&"1.0.0".parse().unwrap(), available.clone()),
+            Some("1.2.0".parse().unwrap())
+        );
+        assert_eq!(
+            latest_pypi_version(&"2.0.0b1".parse().unwrap(), available),
+            Some("2.0.0rc1".parse().unwrap())
+        );
+        assert_eq!(latest_pypi_version(&"1.0.0".parse().unwrap(), vec![]), None);
+
+        assert_eq!(
+            parse_index_versions(
+                br#"{"name": "rich", "versions": ["13.7.1", "13.8.0"], "files": []}"#
+            ),
+            IndexVersions::Versions(versions(&["13.7.1", "13.8.0"]))
+        );
+        // Indexes without PEP 700 support don't list the versions.
+        assert_eq!(
+            parse_index_versions(br#"{"name": "rich", "files": []}"#),
+            IndexVersions::Unsupported
+        );
+        assert_eq!(
+            parse_index_versions(b"<html></html>"),
+            IndexVersions::Unsupported
+        );
+    }
+
+    #[test]
+    fn test_outdated_packages() {
+        let conda =
+            PackageExt::Conda(crate::lock_file::test_utils::record("numpy", "1.26.4").into());
+        let pypi = pypi_package(
+            "rich",
+            "13.8.0",
+            "https://files.pythonhosted.org/packages/rich-13.8.0-py3-none-any.whl",
+            false,
+        );
+        let latest_versions = LatestVersions {
+            conda: HashMap::from([(
+                rattler_conda_types::PackageName::new_unchecked("numpy"),
+                "2.1.0".parse().unwrap(),
+            )]),
+            pypi: HashMap::from([("rich".parse().unwrap(), "13.8.0".parse().unwrap())]),
+        };
+        assert_eq!(
+            latest_versions.newer_version(&conda),
+            Some("2.1.0".to_string())
+        );
+        // The locked version is already the latest
+        assert_eq!(latest_versions.newer_version(&pypi), None);
+
+        let conda_dependencies = CondaDependencies::from_iter([(
+            rattler_conda_types::PackageName::new_unchecked("numpy"),
+            pixi_spec::PixiSpec::Version(">=1.26,<2".parse().unwrap()),
+        )]);
+        let pypi_dependencies = PyPiDependencies::from_iter([(
+            "rich".parse().unwrap(),
+            PyPiRequirement::RawVersion("~=13.0".parse().unwrap()),
+        )]);
+        assert_eq!(
+            requested_spec(&conda, &conda_dependencies, &pypi_dependencies),
+            Some(">=1.26,<2".to_string())
+        );
+        assert_eq!(
+            requested_spec(&pypi, &conda_dependencies, &pypi_dependencies),
+            Some("~=13.0".to_string())
+        );
+        assert_eq!(
+            requested_spec(&pypi, &conda_dependencies, &PyPiDependencies::default()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_installed_clobbers() {
+        let dir = tempfile::tempdir().unwrap();
+        let conda_meta = dir.path().join("conda-meta");
+        fs_err::create_dir_all(&conda_meta).unwrap();
+        for record in [
+            prefix_record("foo", &[("bin/tool", None), ("share/data", None)]),
+            prefix_record(
+                "bar",
+                &[
+                    ("bin/tool__clobber-from-bar", Some("bin/tool")),
+                    ("share/data__clobber-from-bar", Some("share/data")),
+                ],
+            ),
+        ] {
+            record
+                .write_to_path(
+                    conda_meta.join(format!(
+                        "{}-1.0-0.json",
+                        record.repodata_record.package_record.name.as_normalized()
+                    )),
+                    true,
+                )
+                .unwrap();
+        }
+        let prefix = Prefix::new(dir.path());
+
+        let clobbers = installed_clobbers(&prefix, None).unwrap();
+        assert_eq!(
+            clobbers.iter().map(ToString::to_string).collect_vec(),
+            [
+                "'bin/tool' of 'foo' clobbers 'bar'",
+                "'share/data' of 'foo' clobbers 'bar'"
+            ]
+        );
+        assert_eq!(
+            serde_json::to_value(&clobbers[0]).unwrap(),
+            serde_json::json!({"path": "bin/tool", "package": "foo", "clobbered": ["bar"]})
+        );
+
+        // The regex filters the paths.
+        let clobbers = installed_clobbers(&prefix, Some("^bin/")).unwrap();
+        assert_eq!(clobbers.len(), 1);
+        assert_eq!(clobbers[0].path, PathBuf::from("bin/tool"));
+        assert!(installed_clobbers(&prefix, Some("(")).is_err());
+    }
 }
@@ -0,0 +1,187 @@
+use std::{io::stdout, path::PathBuf, process::Command, str::FromStr};
+
+use clap::{Parser, ValueEnum};
+use miette::{Context, IntoDiagnostic};
+use pixi_consts::consts;
+use rattler_lock::LockFile;
+
+use super::read_lock_file;
+use crate::{
+    cli::cli_config::ProjectConfig,
+    diff::{LockFileDiff, LockFileJsonDiff},
+    lock_file::merge_lock_files,
+    Project,
+};
+
+/// The format in which the diff is printed.
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum DiffFormat {
+    /// A human readable overview of the changes.
+    Text,
+    /// A markdown table per environment and platform, e.g. for pull request
+    /// comments.
+    Markdown,
+    /// A machine readable JSON document.
+    Json,
+}
+
+/// Show the packages that changed in the lock file compared to an older
+/// version of it.
+///
+/// By default the lock file is compared against the one committed in `HEAD`.
+#[derive(Debug, Parser)]
+pub struct Args {
+    #[clap(flatten)]
+    pub project_config: ProjectConfig,
+
+    /// The git revision to compare the lock file against.
+    #[arg(long, value_name = "REV", conflicts_with = "file")]
+    pub since: Option<String>,
+
+    /// The lock file to compare the lock file against.
+    #[arg(long, value_name = "PATH")]
+    pub file: Option<PathBuf>,
+
+    /// The format in which the diff is printed.
+    #[arg(long, value_enum, default_value = "text")]
+    pub format: DiffFormat,
+}
+
+pub async fn execute(args: Args) -> miette::Result<()> {
+    let project = Project::load_or_else_discover(args.project_config.manifest_path.as_deref())?;
+
+    let current = read_lock_file(&project)?;
+
+    let previous = match &args.file {
+        Some(path) => LockFile::from_path(path)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("failed to read '{}'", path.display()))?,
+        None => lock_file_at_revision(&project, args.since.as_deref().unwrap_or("HEAD"))?,
+    };
+
+    let diff = LockFileDiff::from_lock_files(&previous, &current);
+    match args.format {
+        DiffFormat::Json => {
+            let json_diff = LockFileJsonDiff::new(&project, diff);
+            let json = serde_json::to_string_pretty(&json_diff).expect("failed to convert to json");
+            println!("{}", json);
+        }
+        DiffFormat::Markdown => print!("{}", diff.to_markdown()),
+        DiffFormat::Text if diff.is_empty() => {
+            eprintln!(
+                "{}No changes to the lock file",
+                console::style(console::Emoji("✔ ", "")).green()
+            );
+        }
+        DiffFormat::Text => diff
+            .print_to(stdout())
+            .into_diagnostic()
+            .context("failed to print lock-file diff")?,
+    }
+
+    Ok(())
+}
+
+/// Reads the lock file of the project as it was committed in the git revision
+/// `rev`.
+fn lock_file_at_revision(project: &Project, rev: &str) -> miette::Result<LockFile> {
+    if project.per_environment_lock_files() {
+        let lock_files_dir = format!("{}/{}", consts::PIXI_DIR, consts::LOCKS_DIR);
+        let paths = git(
+            project,
+            ["ls-tree", "-r", "--name-only", rev, "--", &lock_files_dir],
+        )?;
+        let lock_files = paths
+            .lines()
+            .filter(|path| path.ends_with(".lock"))
+            .map(|path| parse_lock_file_at_revision(project, rev, path))
+            .collect::<miette::Result<Vec<_>>>()?;
+        if !lock_files.is_empty() {
+            return Ok(merge_lock_files(&lock_files));
+        }
+    }
+
+    parse_lock_file_at_revision(project, rev, consts::PROJECT_LOCK_FILE)
+}
+
+/// Parses the lock file at `path` as it was committed in the git revision
+/// `rev`.
+fn parse_lock_file_at_revision(
+    project: &Project,
+    rev: &str,
+    path: &str,
+) -> miette::Result<LockFile> {
+    let contents = git(project, ["show", &format!("{rev}:./{path}")])
+        .wrap_err_with(|| format!("failed to read '{path}' at revision '{rev}'"))?;
+    LockFile::from_str(&contents)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("failed to parse '{path}' at revision '{rev}'"))
+}
+
+/// Runs a git command in the root of the project and returns its output.
+fn git<'a>(project: &Project, args: impl IntoIterator<Item = &'a str>) -> miette::Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(project.root())
+        .args(args)
+        .output()
+        .into_diagnostic()
+        .wrap_err("failed to run `git`")?;
+    if !output.status.success() {
+        miette::bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    String::from_utf8(output.stdout).into_diagnostic()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    fn run_git(root: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(root)
+            .args(["-c", "user.name=pixi", "-c", "user.email=pixi@example.com"])
+            .args(args)
+            .status()
+            .unwrap();
+        assert!(status.success(), "git {args:?} failed");
+    }
+
+    #[test]
+    fn test_per_environment_lock_files_at_revision() {
+        let fixture = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/data/satisfiability/solve-groups-pypi");
+        let lock_file = LockFile::from_path(&fixture.join(consts::PROJECT_LOCK_FILE)).unwrap();
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manifest = fs_err::read_to_string(fixture.join(consts::PROJECT_MANIFEST))
+            .unwrap()
+            .replacen(
+                "[project]",
+                "[project]\nper-environment-lock-files = true",
+                1,
+            );
+        let manifest_path = temp_dir.path().join(consts::PROJECT_MANIFEST);
+        fs_err::write(&manifest_path, &manifest).unwrap();
+        let project = Project::from_str(&manifest_path, &manifest).unwrap();
+        let locks_dir = project.environment_lock_files_dir();
+        fs_err::create_dir_all(&locks_dir).unwrap();
+        lock_file.to_path(&locks_dir.join("default.lock")).unwrap();
+
+        run_git(temp_dir.path(), &["init", "--quiet"]);
+        run_git(temp_dir.path(), &["add", "--all", "--force"]);
+        run_git(temp_dir.path(), &["commit", "--quiet", "--message", "lock"]);
+
+        // The lock files inside `.pixi/locks` are found, not only the
+        // directory itself.
+        let committed = lock_file_at_revision(&project, "HEAD").unwrap();
+        assert_eq!(
+            committed.environments().len(),
+            lock_file.environments().len()
+        );
+        assert!(LockFileDiff::from_lock_files(&committed, &lock_file).is_empty());
+    }
+}
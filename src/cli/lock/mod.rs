@@ -0,0 +1,152 @@
+use clap::Parser;
+use miette::{Context, IntoDiagnostic};
+use pixi_config::ConfigCli;
+use pixi_manifest::ExcludeNewer;
+use rattler_conda_types::Platform;
+use rattler_lock::LockFile;
+
+use crate::{
+    cli::cli_config::ProjectConfig,
+    diff::LockFileDiff,
+    environment::LockFileUsage,
+    load_lock_file,
+    lock_file::{prune_unlocked_platforms, read_environment_lock_files, UpdateContext},
+    Project, UpdateLockFileOptions,
+};
+
+pub mod diff;
+pub mod vendor;
+pub mod verify;
+
+#[derive(Debug, Parser)]
+pub enum Command {
+    Diff(diff::Args),
+    Vendor(vendor::Args),
+    Verify(verify::Args),
+}
+
+/// Update the lock file of the project without installing the environments,
+/// or inspect it with one of the subcommands.
+#[derive(Debug, Parser)]
+#[clap(args_conflicts_with_subcommands = true)]
+pub struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[clap(flatten)]
+    pub project_config: ProjectConfig,
+
+    #[clap(flatten)]
+    pub config: ConfigCli,
+
+    /// The platforms to lock, can be repeated. The other platforms keep their
+    /// locked packages. Defaults to all platforms.
+    #[arg(long, short)]
+    pub platform: Option<Vec<Platform>>,
+
+    /// Remove the platforms that are not given with `--platform` from the
+    /// lock file. This is a one-shot: the next update of the lock file locks
+    /// the removed platforms again, use the `lock-platforms` configuration to
+    /// keep them out of the lock file.
+    #[arg(long, requires = "platform")]
+    pub prune_others: bool,
+
+    /// Don't install the (solve) environments needed for pypi-dependencies
+    /// solving.
+    #[arg(long)]
+    pub no_install: bool,
+
+    /// Ignore packages that were published after this date (e.g. `2024-06-01`)
+    /// or RFC 3339 timestamp, overrides the `exclude-newer` of the manifest.
+    #[clap(long)]
+    pub exclude_newer: Option<ExcludeNewer>,
+}
+
+pub async fn execute(mut cmd: Args) -> miette::Result<()> {
+    match cmd.command.take() {
+        Some(Command::Diff(args)) => diff::execute(args).await?,
+        Some(Command::Vendor(args)) => vendor::execute(args).await?,
+        Some(Command::Verify(args)) => verify::execute(args).await?,
+        None => lock(cmd).await?,
+    };
+    Ok(())
+}
+
+/// Updates the lock file of the project.
+async fn lock(args: Args) -> miette::Result<()> {
+    let mut project = Project::load_or_else_discover(args.project_config.manifest_path.as_deref())?
+        .with_cli_config(args.config)
+        .with_exclude_newer(args.exclude_newer);
+    let original_lock_file = load_lock_file(&project).await?;
+
+    // Only the given platforms are locked, the others are removed from the
+    // lock-file.
+    if args.prune_others {
+        project = project.with_lock_platforms(args.platform.clone().unwrap_or_default());
+    }
+
+    let updated_lock_file = match args.platform {
+        Some(platforms) if !args.prune_others => {
+            // The platforms that are no longer locked are removed, even if they
+            // are not given.
+            let lock_file = prune_unlocked_platforms(&project, &original_lock_file)
+                .unwrap_or_else(|| original_lock_file.clone());
+            let updated = UpdateContext::builder(&project)
+                .with_lock_file(lock_file)
+                .with_platforms(platforms.into_iter().collect())
+                .with_no_install(args.no_install)
+                .finish()
+                .await?
+                .update()
+                .await?;
+            updated.write_to_disk()?;
+            updated.lock_file
+        }
+        _ => {
+            project
+                .update_lock_file(UpdateLockFileOptions {
+                    lock_file_usage: LockFileUsage::Update,
+                    no_install: args.no_install,
+                    max_concurrent_solves: project.config().max_concurrent_solves(),
+                })
+                .await?
+                .lock_file
+        }
+    };
+
+    let diff = LockFileDiff::from_lock_files(&original_lock_file, &updated_lock_file);
+    if diff.is_empty() {
+        eprintln!(
+            "{}Lock-file was already up-to-date",
+            console::style(console::Emoji("✔ ", "")).green()
+        );
+    } else {
+        diff.print()
+            .into_diagnostic()
+            .context("failed to print lock-file diff")?;
+    }
+
+    Ok(())
+}
+
+/// Reads the lock file of the project as it is on disk, without updating it.
+fn read_lock_file(project: &Project) -> miette::Result<LockFile> {
+    if project.per_environment_lock_files() {
+        if let Some(lock_file) = read_environment_lock_files(&project.environment_lock_files_dir())?
+        {
+            return Ok(lock_file);
+        }
+    }
+
+    let lock_file_path = project.lock_file_path();
+    if !lock_file_path.is_file() {
+        miette::bail!(
+            help = "Run `pixi install` to create the lock file.",
+            "'{}' doesn't exist",
+            lock_file_path.display()
+        );
+    }
+    LockFile::from_path(&lock_file_path)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("failed to read '{}'", lock_file_path.display()))
+}
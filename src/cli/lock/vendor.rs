@@ -0,0 +1,248 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use clap::Parser;
+use futures::{stream, StreamExt, TryStreamExt};
+use itertools::Itertools;
+use miette::{Context, IntoDiagnostic};
+use rattler_conda_types::{ChannelInfo, PackageRecord, Platform, RepoData};
+use rattler_digest::{HashingWriter, Sha256, Sha256Hash};
+use rattler_lock::{CondaPackageData, LockedPackageRef, UrlOrPath};
+use reqwest_middleware::ClientWithMiddleware;
+use url::Url;
+
+use super::read_lock_file;
+use crate::{
+    cli::cli_config::ProjectConfig,
+    lock_file::{pypi_file_name, VendorDir},
+    Project,
+};
+
+/// Download every package of the lock file into a directory, to install the
+/// environments on a machine without network access.
+///
+/// Conda packages are stored in `conda/<subdir>`, wheels and source
+/// distributions in `pypi`. Point the `vendor-dir` configuration to the
+/// directory to install from it.
+#[derive(Debug, Parser)]
+pub struct Args {
+    #[clap(flatten)]
+    pub project_config: ProjectConfig,
+
+    /// The directory to download the packages into.
+    #[arg(long, short, default_value = "vendor")]
+    pub output: PathBuf,
+
+    /// The environment to vendor, can be repeated. Defaults to all
+    /// environments.
+    #[arg(long, short)]
+    pub environment: Option<Vec<String>>,
+
+    /// The platform to vendor, can be repeated. Defaults to all platforms.
+    #[arg(long, short)]
+    pub platform: Option<Vec<Platform>>,
+
+    /// Also write a `repodata.json` for every subdir, which turns the conda
+    /// packages into a channel that can be solved against.
+    #[arg(long)]
+    pub repodata: bool,
+}
+
+/// A file that has to be downloaded into the vendor directory.
+struct VendoredFile {
+    url: Url,
+    destination: PathBuf,
+    sha256: Option<Sha256Hash>,
+}
+
+pub async fn execute(args: Args) -> miette::Result<()> {
+    let project = Project::load_or_else_discover(args.project_config.manifest_path.as_deref())?;
+    let lock_file = read_lock_file(&project)?;
+    let vendor_dir = VendorDir::new(&args.output);
+
+    let environments = match &args.environment {
+        Some(names) => names
+            .iter()
+            .map(|name| {
+                lock_file
+                    .environment(name)
+                    .ok_or_else(|| miette::miette!("unknown environment '{name}'"))
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+        None => lock_file.environments().map(|(_, env)| env).collect(),
+    };
+
+    let mut files = Vec::new();
+    let mut seen = HashSet::new();
+    let mut records_by_subdir: BTreeMap<String, BTreeMap<String, PackageRecord>> = BTreeMap::new();
+    let mut skipped = Vec::new();
+    for environment in environments {
+        for (platform, packages) in environment.packages_by_platform() {
+            if args
+                .platform
+                .as_ref()
+                .is_some_and(|platforms| !platforms.contains(&platform))
+            {
+                continue;
+            }
+
+            for package in packages {
+                match package {
+                    LockedPackageRef::Conda(CondaPackageData::Binary(data)) => {
+                        let UrlOrPath::Url(url) = &data.location else {
+                            skipped.push(package.name().to_string());
+                            continue;
+                        };
+                        if !matches!(url.scheme(), "http" | "https") {
+                            skipped.push(package.name().to_string());
+                            continue;
+                        }
+                        let subdir = &data.package_record.subdir;
+                        records_by_subdir
+                            .entry(subdir.clone())
+                            .or_default()
+                            .insert(data.file_name.clone(), data.package_record.clone());
+                        if seen.insert(url.clone()) {
+                            files.push(VendoredFile {
+                                url: url.clone(),
+                                destination: vendor_dir.conda_package_path(subdir, &data.file_name),
+                                sha256: data.package_record.sha256,
+                            });
+                        }
+                    }
+                    LockedPackageRef::Conda(CondaPackageData::Source(_)) => {
+                        skipped.push(package.name().to_string());
+                    }
+                    LockedPackageRef::Pypi(data, _) => {
+                        let (UrlOrPath::Url(url), Some(file_name)) =
+                            (&data.location, pypi_file_name(&data.location))
+                        else {
+                            skipped.push(package.name().to_string());
+                            continue;
+                        };
+                        if seen.insert(url.clone()) {
+                            files.push(VendoredFile {
+                                url: url.clone(),
+                                destination: vendor_dir.pypi_package_path(&file_name),
+                                sha256: data.hash.as_ref().and_then(|hash| hash.sha256()).copied(),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    for name in skipped.iter().unique() {
+        tracing::warn!("skipping '{name}', it is not downloaded from a channel or an index");
+    }
+
+    let client = project.authenticated_client().clone();
+    let file_count = files.len();
+    stream::iter(files)
+        .map(|file| download(&client, file))
+        .buffer_unordered(project.config().max_concurrent_downloads())
+        .try_collect::<Vec<_>>()
+        .await?;
+
+    if args.repodata {
+        // A channel always requires a `noarch` subdir.
+        records_by_subdir
+            .entry(Platform::NoArch.to_string())
+            .or_default();
+        for (subdir, records) in records_by_subdir {
+            write_repodata(&vendor_dir.conda_subdir(&subdir), subdir, records)?;
+        }
+    }
+
+    eprintln!(
+        "{}Vendored {file_count} packages into '{}'",
+        console::style(console::Emoji("✔ ", "")).green(),
+        args.output.display()
+    );
+    Ok(())
+}
+
+/// Downloads a file into the vendor directory, files that already exist with
+/// the expected hash are not downloaded again.
+async fn download(client: &ClientWithMiddleware, file: VendoredFile) -> miette::Result<()> {
+    let VendoredFile {
+        url,
+        destination,
+        sha256,
+    } = file;
+    if let Some(expected) = sha256 {
+        if destination.is_file()
+            && rattler_digest::compute_file_digest::<Sha256>(&destination).ok() == Some(expected)
+        {
+            return Ok(());
+        }
+    }
+
+    let mut response = client
+        .get(url.clone())
+        .send()
+        .await
+        .into_diagnostic()?
+        .error_for_status()
+        .into_diagnostic()
+        .wrap_err_with(|| format!("failed to download '{url}'"))?;
+
+    // Download to a temporary file first to never leave a partial download
+    // behind.
+    if let Some(parent) = destination.parent() {
+        fs_err::create_dir_all(parent).into_diagnostic()?;
+    }
+    let mut partial = destination.clone().into_os_string();
+    partial.push(".partial");
+    let partial = PathBuf::from(partial);
+    let mut writer =
+        HashingWriter::<_, Sha256>::new(fs_err::File::create(&partial).into_diagnostic()?);
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .into_diagnostic()
+        .wrap_err_with(|| format!("failed to download '{url}'"))?
+    {
+        writer.write_all(&chunk).into_diagnostic()?;
+    }
+    let (_, hash) = writer.finalize();
+
+    if let Some(expected) = sha256 {
+        if hash != expected {
+            fs_err::remove_file(&partial).into_diagnostic()?;
+            miette::bail!(
+                "the sha256 of '{url}' is {hash:x}, but the lock file expects {expected:x}"
+            );
+        }
+    }
+    fs_err::rename(&partial, &destination).into_diagnostic()
+}
+
+/// Writes the `repodata.json` of a subdir of the vendored conda packages.
+fn write_repodata(
+    directory: &Path,
+    subdir: String,
+    records: BTreeMap<String, PackageRecord>,
+) -> miette::Result<()> {
+    let (conda_packages, packages): (Vec<_>, Vec<_>) = records
+        .into_iter()
+        .partition(|(file_name, _)| file_name.ends_with(".conda"));
+    let repodata = RepoData {
+        info: Some(ChannelInfo {
+            subdir,
+            base_url: None,
+        }),
+        packages: packages.into_iter().collect(),
+        conda_packages: conda_packages.into_iter().collect(),
+        removed: Default::default(),
+        version: Some(2),
+    };
+
+    fs_err::create_dir_all(directory).into_diagnostic()?;
+    let repodata_json = serde_json::to_string_pretty(&repodata).into_diagnostic()?;
+    fs_err::write(directory.join("repodata.json"), repodata_json).into_diagnostic()
+}
@@ -0,0 +1,351 @@
+use std::{collections::HashMap, fmt::Display};
+
+use clap::Parser;
+use futures::{stream, StreamExt};
+use itertools::Itertools;
+use miette::IntoDiagnostic;
+use pixi_consts::consts;
+use pixi_manifest::FeaturesExt;
+use rattler_conda_types::Platform;
+use rattler_lock::{LockFile, LockedPackageRef, UrlOrPath};
+use url::Url;
+
+use super::read_lock_file;
+use crate::{cli::cli_config::ProjectConfig, lock_file::LockedPackageName, Project};
+
+/// The number of urls that are checked at the same time with `--online`.
+const CONCURRENT_REQUESTS: usize = 50;
+
+/// Check the integrity of the lock file, exits with a non-zero exit code if a
+/// problem is found.
+///
+/// Verifies that every package has a hash, that conda packages come from the
+/// channels of their environment, that no package is locked twice and that
+/// every environment and platform of the manifest is locked.
+#[derive(Debug, Parser)]
+pub struct Args {
+    #[clap(flatten)]
+    pub project_config: ProjectConfig,
+
+    /// Also check that the url of every package is reachable.
+    #[arg(long)]
+    pub online: bool,
+}
+
+/// A problem found in the lock file.
+enum Problem {
+    MissingEnvironment(String),
+    MissingPlatform(String, Platform),
+    MissingHash(String, Platform, String),
+    DuplicatePackage(String, Platform, String),
+    UnknownChannel(String, Platform, String, String),
+    Unreachable(Url, String),
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let env = |name: &str| consts::ENVIRONMENT_STYLE.apply_to(name.to_string());
+        let platform = |platform: &Platform| consts::PLATFORM_STYLE.apply_to(*platform);
+        match self {
+            Problem::MissingEnvironment(name) => {
+                write!(f, "environment {} is not locked", env(name))
+            }
+            Problem::MissingPlatform(name, p) => write!(
+                f,
+                "platform {} of environment {} is not locked",
+                platform(p),
+                env(name)
+            ),
+            Problem::MissingHash(name, p, package) => {
+                write!(f, "{package} in {}:{} has no hash", env(name), platform(p))
+            }
+            Problem::DuplicatePackage(name, p, package) => write!(
+                f,
+                "{package} is locked more than once in {}:{}",
+                env(name),
+                platform(p)
+            ),
+            Problem::UnknownChannel(name, p, package, url) => write!(
+                f,
+                "{package} in {}:{} comes from '{url}', which is not a channel of the environment",
+                env(name),
+                platform(p)
+            ),
+            Problem::Unreachable(url, reason) => write!(f, "'{url}' is not reachable: {reason}"),
+        }
+    }
+}
+
+pub async fn execute(args: Args) -> miette::Result<()> {
+    let project = Project::load_or_else_discover(args.project_config.manifest_path.as_deref())?;
+    let lock_file = read_lock_file(&project)?;
+
+    let mut problems = verify_lock_file(&project, &lock_file)?;
+    if args.online {
+        problems.extend(verify_urls(&project, &lock_file).await);
+    }
+
+    if problems.is_empty() {
+        eprintln!(
+            "{}The lock file is valid",
+            console::style(console::Emoji("✔ ", "")).green()
+        );
+        return Ok(());
+    }
+
+    for problem in &problems {
+        eprintln!("{} {problem}", console::style("✘").red());
+    }
+    miette::bail!(
+        "found {} problem(s) in '{}'",
+        problems.len(),
+        consts::PROJECT_LOCK_FILE
+    )
+}
+
+/// Checks the invariants of the lock file that don't require network access.
+fn verify_lock_file(project: &Project, lock_file: &LockFile) -> miette::Result<Vec<Problem>> {
+    let channel_config = project.channel_config();
+    let mut problems = Vec::new();
+
+    for environment in project.environments() {
+        let env_name = environment.name().as_str().to_string();
+        let Some(locked_environment) = lock_file.environment(&env_name) else {
+            problems.push(Problem::MissingEnvironment(env_name));
+            continue;
+        };
+
+        let channels = environment
+            .channel_urls(&channel_config)
+            .into_diagnostic()?;
+
+        for platform in environment
+            .locked_platforms()
+            .into_iter()
+            .sorted_by_key(|platform| platform.as_str())
+        {
+            let Some(packages) = locked_environment.packages(platform) else {
+                problems.push(Problem::MissingPlatform(env_name.clone(), platform));
+                continue;
+            };
+
+            let mut occurrences: HashMap<LockedPackageName, usize> = HashMap::new();
+            for package in packages {
+                let package_name = package.name().to_string();
+                *occurrences.entry(package.into()).or_default() += 1;
+
+                if !has_hash(package) {
+                    problems.push(Problem::MissingHash(
+                        env_name.clone(),
+                        platform,
+                        package_name.clone(),
+                    ));
+                }
+
+                // Conda packages must come from one of the channels of the
+                // environment.
+                if let LockedPackageRef::Conda(data) = package {
+                    if let Some(UrlOrPath::Url(url)) = data.as_binary().map(|data| &data.location) {
+                        if !channels
+                            .iter()
+                            .any(|channel| url.as_str().starts_with(channel.as_str()))
+                        {
+                            problems.push(Problem::UnknownChannel(
+                                env_name.clone(),
+                                platform,
+                                package_name,
+                                url.to_string(),
+                            ));
+                        }
+                    }
+                }
+            }
+
+            for (name, _) in occurrences
+                .into_iter()
+                .filter(|(_, count)| *count > 1)
+                .sorted_by_key(|(name, _)| display_name(name))
+            {
+                problems.push(Problem::DuplicatePackage(
+                    env_name.clone(),
+                    platform,
+                    display_name(&name),
+                ));
+            }
+        }
+    }
+
+    Ok(problems)
+}
+
+fn display_name(name: &LockedPackageName) -> String {
+    match name {
+        LockedPackageName::Conda(name) => name.as_normalized().to_string(),
+        LockedPackageName::Pypi(name) => name.to_string(),
+    }
+}
+
+/// Returns true if the package is locked with a hash. Packages that are built
+/// from source, installed from a local path or from a git repository don't
+/// have one.
+fn has_hash(package: LockedPackageRef<'_>) -> bool {
+    match package {
+        LockedPackageRef::Conda(data) => match data.as_binary() {
+            Some(binary) => {
+                binary.package_record.md5.is_some() || binary.package_record.sha256.is_some()
+            }
+            None => true,
+        },
+        LockedPackageRef::Pypi(data, _) => data.hash.is_some() || !requires_hash(&data.location),
+    }
+}
+
+/// Returns true if a PyPI package at this location is expected to have a
+/// hash. Only archives that are downloaded from an index or url have one, git
+/// repositories and local paths, also when written as `file://` urls, don't.
+fn requires_hash(location: &UrlOrPath) -> bool {
+    match location {
+        UrlOrPath::Url(url) => {
+            let scheme = url.scheme();
+            let scheme = scheme.strip_prefix("direct+").unwrap_or(scheme);
+            matches!(scheme, "http" | "https")
+        }
+        UrlOrPath::Path(_) => false,
+    }
+}
+
+/// Checks that the url of every package in the lock file can be reached.
+async fn verify_urls(project: &Project, lock_file: &LockFile) -> Vec<Problem> {
+    let urls = lock_file
+        .environments()
+        .flat_map(|(_, environment)| {
+            environment
+                .packages_by_platform()
+                .flat_map(|(_, packages)| packages)
+                .filter_map(|package| match package.location() {
+                    UrlOrPath::Url(url) => Some(url.clone()),
+                    UrlOrPath::Path(_) => None,
+                })
+                .collect_vec()
+        })
+        .filter(|url| matches!(url.scheme(), "http" | "https"))
+        .unique()
+        .collect_vec();
+
+    let client = project.authenticated_client();
+    stream::iter(urls)
+        .map(|url| async move {
+            match client.head(url.clone()).send().await {
+                Ok(response) if response.status().is_success() => None,
+                Ok(response) => Some(Problem::Unreachable(url, response.status().to_string())),
+                Err(err) => Some(Problem::Unreachable(url, err.to_string())),
+            }
+        })
+        .buffer_unordered(CONCURRENT_REQUESTS)
+        .filter_map(|problem| async move { problem })
+        .collect()
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::Path, str::FromStr};
+
+    use super::*;
+
+    #[test]
+    fn test_verify_lock_file() {
+        console::set_colors_enabled(false);
+        let project = Project::from_str(
+            Path::new("pixi.toml"),
+            r#"
+        [project]
+        name = "verify"
+        channels = ["conda-forge"]
+        platforms = ["linux-64", "win-64"]
+
+        [feature.test.dependencies]
+        foo = "*"
+
+        [environments]
+        test = ["test"]
+        "#,
+        )
+        .unwrap();
+        let sha256 = "663e752ba19bebb97b32ca541cfdeebedfc50664d9423637eda4c9c47343fdbb";
+        let lock_file = LockFile::from_str(&format!(
+            r#"version: 4
+environments:
+  default:
+    channels:
+    - url: https://conda.anaconda.org/conda-forge/
+    packages:
+      linux-64:
+      - conda: https://conda.anaconda.org/conda-forge/linux-64/foo-1.0-h0_0.conda
+      - conda: https://conda.anaconda.org/conda-forge/linux-64/foo-2.0-h0_0.conda
+      - conda: https://conda.anaconda.org/bioconda/linux-64/bar-1.0-h0_0.conda
+packages:
+- kind: conda
+  name: foo
+  version: '1.0'
+  build: h0_0
+  subdir: linux-64
+  url: https://conda.anaconda.org/conda-forge/linux-64/foo-1.0-h0_0.conda
+  sha256: {sha256}
+- kind: conda
+  name: foo
+  version: '2.0'
+  build: h0_0
+  subdir: linux-64
+  url: https://conda.anaconda.org/conda-forge/linux-64/foo-2.0-h0_0.conda
+  sha256: {sha256}
+- kind: conda
+  name: bar
+  version: '1.0'
+  build: h0_0
+  subdir: linux-64
+  url: https://conda.anaconda.org/bioconda/linux-64/bar-1.0-h0_0.conda
+"#
+        ))
+        .unwrap();
+
+        let problems = verify_lock_file(&project, &lock_file)
+            .unwrap()
+            .iter()
+            .map(ToString::to_string)
+            .collect_vec();
+        assert_eq!(
+            problems,
+            vec![
+                "bar in default:linux-64 has no hash",
+                "bar in default:linux-64 comes from 'https://conda.anaconda.org/bioconda/linux-64/bar-1.0-h0_0.conda', which is not a channel of the environment",
+                "foo is locked more than once in default:linux-64",
+                "platform win-64 of environment default is not locked",
+                "environment test is not locked",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_requires_hash() {
+        for (location, expected) in [
+            (
+                "https://files.pythonhosted.org/packages/rich-13.7.1-py3-none-any.whl",
+                true,
+            ),
+            ("direct+https://example.com/rich-13.7.1.tar.gz", true),
+            (
+                "git+https://github.com/Textualize/rich.git?rev=v13.7.1#3f5b5b8c",
+                false,
+            ),
+            ("file:///home/user/rich", false),
+            ("./rich", false),
+        ] {
+            assert_eq!(
+                requires_hash(&UrlOrPath::from_str(location).unwrap()),
+                expected,
+                "{location}"
+            );
+        }
+    }
+}
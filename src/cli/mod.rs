@@ -1,29 +1,37 @@
-use clap::Parser;
+use clap::{CommandFactory, FromArgMatches, Parser};
 use clap_verbosity_flag::Verbosity;
 use indicatif::ProgressDrawTarget;
 use miette::IntoDiagnostic;
 use pixi_consts::consts;
 use pixi_progress::global_multi_progress;
 use pixi_utils::indicatif::IndicatifWriter;
-use std::{env, io::IsTerminal};
+use std::{env, ffi::OsString, io::IsTerminal};
 use tracing_subscriber::{
-    filter::LevelFilter, prelude::__tracing_subscriber_SubscriberExt, util::SubscriberInitExt,
-    EnvFilter,
+    filter::{filter_fn, LevelFilter},
+    prelude::__tracing_subscriber_SubscriberExt,
+    util::SubscriberInitExt,
+    EnvFilter, Layer,
 };
 
 pub mod add;
+pub mod audit;
+pub mod auth;
 mod build;
 pub mod clean;
 pub mod cli_config;
+pub mod command_info;
 pub mod completion;
 pub mod config;
+pub mod error_format;
 pub mod exec;
+pub mod explain;
 pub mod global;
 pub mod has_specs;
 pub mod info;
 pub mod init;
 pub mod install;
 pub mod list;
+pub mod lock;
 pub mod project;
 pub mod remove;
 pub mod run;
@@ -31,11 +39,14 @@ pub mod search;
 pub mod self_update;
 pub mod shell;
 pub mod shell_hook;
+pub mod stats;
 pub mod task;
+mod timings;
 pub mod tree;
 pub mod update;
 pub mod upgrade;
 pub mod upload;
+pub mod warmup;
 
 #[derive(Parser, Debug)]
 #[command(
@@ -64,10 +75,15 @@ Ask a question on the Prefix Discord server: https://discord.gg/kKV8ZxyzY4
 For more information, see the documentation at: https://pixi.sh
 ", consts::PIXI_VERSION)
 )]
-#[clap(arg_required_else_help = true)]
+#[clap(arg_required_else_help = true, disable_help_subcommand = true)]
 struct Args {
     #[command(subcommand)]
-    command: Command,
+    command: Option<Command>,
+
+    /// List all commands, including `pixi-<name>` extensions found on the
+    /// PATH.
+    #[clap(long)]
+    list: bool,
 
     /// The verbosity level
     /// (-v for warning, -vv for info, -vvv for debug, -vvvv for trace, -q for
@@ -82,6 +98,32 @@ struct Args {
     /// Hide all progress bars, always turned on if stderr is not a terminal.
     #[clap(long, default_value = "false", global = true, env = "PIXI_NO_PROGRESS")]
     no_progress: bool,
+
+    /// The format in which progress is reported. `json-lines` replaces the
+    /// progress bars with machine-readable events on stderr.
+    #[clap(
+        long,
+        default_value = "bars",
+        global = true,
+        env = "PIXI_PROGRESS_FORMAT"
+    )]
+    progress_format: ProgressFormatArg,
+
+    /// The format in which the final error is reported. `json` writes the
+    /// diagnostic as a single JSON object to stderr.
+    #[clap(
+        long,
+        default_value = "human",
+        global = true,
+        env = "PIXI_ERROR_FORMAT"
+    )]
+    error_format: error_format::ErrorFormat,
+
+    /// Print how long the internal phases of the command took, like loading
+    /// the configuration, solving and installing environments, when it
+    /// finishes.
+    #[clap(long, global = true)]
+    timings: bool,
 }
 impl Args {
     /// Whether to show progress bars or not, based on the terminal and the user's preference.
@@ -105,6 +147,7 @@ pub enum Command {
     Remove(remove::Args),
     #[clap(visible_alias = "i")]
     Install(install::Args),
+    Warmup(warmup::Args),
     Update(update::Args),
     Upgrade(upgrade::Args),
 
@@ -125,15 +168,19 @@ pub enum Command {
     List(list::Args),
     #[clap(visible_alias = "t")]
     Tree(tree::Args),
+    Explain(explain::Args),
+    Audit(audit::Args),
+    Lock(lock::Args),
 
     // Global level commands
     #[clap(visible_alias = "g")]
     Global(global::Args),
-    Auth(rattler::cli::auth::Args),
+    Auth(auth::Args),
     Config(config::Args),
     Info(info::Args),
     Upload(upload::Args),
     Search(search::Args),
+    Stats(stats::Args),
     #[cfg_attr(not(feature = "self_update"), clap(hide = true))]
     SelfUpdate(self_update::Args),
     Clean(clean::Args),
@@ -141,6 +188,14 @@ pub enum Command {
 
     // Build
     Build(build::Args),
+
+    /// Print the help of pixi, including the `pixi-<name>` extensions found on
+    /// the PATH, or the help of the given command.
+    Help(command_info::HelpArgs),
+
+    /// Any other subcommand is forwarded to a `pixi-<name>` executable on the PATH.
+    #[command(external_subcommand)]
+    External(Vec<OsString>),
 }
 
 #[derive(Parser, Debug, Default, Copy, Clone)]
@@ -170,7 +225,10 @@ impl From<LockFileUsageArgs> for crate::environment::LockFileUsage {
 }
 
 pub async fn execute() -> miette::Result<()> {
-    let args = Args::parse();
+    let matches = Args::command().get_matches();
+    let command_name = command_name(&matches);
+    let mut args = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    error_format::set_error_format(args.error_format);
     set_console_colors(&args);
     let use_colors = console::colors_enabled_stderr();
 
@@ -183,11 +241,24 @@ pub async fn execute() -> miette::Result<()> {
         )
     }))?;
 
+    let Some(command) = args.command.take() else {
+        if args.list {
+            return command_info::list_commands();
+        }
+        return Args::command().print_help().into_diagnostic();
+    };
+
     // Hide all progress bars if the user requested it.
     if args.no_progress() {
         global_multi_progress().set_draw_target(ProgressDrawTarget::hidden());
     }
 
+    // Replace the progress bars with structured events.
+    if let ProgressFormatArg::JsonLines = args.progress_format {
+        global_multi_progress().set_draw_target(ProgressDrawTarget::hidden());
+        pixi_progress::set_progress_format(pixi_progress::ProgressFormat::JsonLines);
+    }
+
     let (low_level_filter, level_filter, pixi_level) = match args.verbose.log_level_filter() {
         clap_verbosity_flag::log::LevelFilter::Off => {
             (LevelFilter::OFF, LevelFilter::OFF, LevelFilter::OFF)
@@ -245,13 +316,35 @@ pub async fn execute() -> miette::Result<()> {
         .with_writer(IndicatifWriter::new(pixi_progress::global_multi_progress()))
         .without_time();
 
+    // Record the duration of the internal phases if requested.
+    let timings_layer = args.timings.then(timings::enable);
+
     tracing_subscriber::registry()
-        .with(env_filter)
-        .with(fmt_layer)
+        .with(fmt_layer.with_filter(env_filter))
+        .with(timings_layer.map(|layer| layer.with_filter(filter_fn(timings::is_timed))))
         .init();
 
+    // Collect the statistics of this invocation, they are only written if the
+    // user opted in.
+    crate::stats::start();
+
     // Execute the command
-    execute_command(args.command).await
+    let result = execute_command(command).await;
+    crate::stats::finish(&command_name, result.is_ok());
+    timings::report();
+    result
+}
+
+/// Returns the name of the invoked subcommand including the names of its
+/// nested subcommands, e.g. `project channel add`.
+fn command_name(matches: &clap::ArgMatches) -> String {
+    let mut names = Vec::new();
+    let mut matches = matches;
+    while let Some((name, sub_matches)) = matches.subcommand() {
+        names.push(name);
+        matches = sub_matches;
+    }
+    names.join(" ")
 }
 
 /// Execute the actual command
@@ -264,14 +357,16 @@ pub async fn execute_command(command: Command) -> miette::Result<()> {
         Command::Clean(cmd) => clean::execute(cmd).await,
         Command::Run(cmd) => run::execute(cmd).await,
         Command::Global(cmd) => global::execute(cmd).await,
-        Command::Auth(cmd) => rattler::cli::auth::execute(cmd).await.into_diagnostic(),
+        Command::Auth(cmd) => auth::execute(cmd).await,
         Command::Install(cmd) => install::execute(cmd).await,
+        Command::Warmup(cmd) => warmup::execute(cmd).await,
         Command::Shell(cmd) => shell::execute(cmd).await,
         Command::ShellHook(cmd) => shell_hook::execute(cmd).await,
         Command::Task(cmd) => task::execute(cmd),
         Command::Info(cmd) => info::execute(cmd).await,
         Command::Upload(cmd) => upload::execute(cmd).await,
         Command::Search(cmd) => search::execute(cmd).await,
+        Command::Stats(cmd) => stats::execute(cmd),
         Command::Project(cmd) => project::execute(cmd).await,
         Command::Remove(cmd) => remove::execute(cmd).await,
         #[cfg(feature = "self_update")]
@@ -280,10 +375,15 @@ pub async fn execute_command(command: Command) -> miette::Result<()> {
         Command::SelfUpdate(cmd) => self_update::execute_stub(cmd).await,
         Command::List(cmd) => list::execute(cmd).await,
         Command::Tree(cmd) => tree::execute(cmd).await,
+        Command::Explain(cmd) => explain::execute(cmd).await,
+        Command::Audit(cmd) => audit::execute(cmd).await,
+        Command::Lock(cmd) => lock::execute(cmd).await,
         Command::Update(cmd) => update::execute(cmd).await,
         Command::Upgrade(cmd) => upgrade::execute(cmd).await,
         Command::Exec(args) => exec::execute(args).await,
         Command::Build(args) => build::execute(args).await,
+        Command::Help(args) => command_info::help(args),
+        Command::External(args) => command_info::execute_external_command(args),
     }
 }
 
@@ -299,6 +399,17 @@ pub enum ColorOutput {
     Auto,
 }
 
+/// The format in which progress is reported.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default)]
+pub enum ProgressFormatArg {
+    /// Show progress bars.
+    #[default]
+    Bars,
+
+    /// Write one JSON event per line to stderr.
+    JsonLines,
+}
+
 fn set_console_colors(args: &Args) {
     // Honor FORCE_COLOR and NO_COLOR environment variables.
     // Those take precedence over the CLI flag and PIXI_COLOR
@@ -322,3 +433,19 @@ fn set_console_colors(args: &Args) {
         ColorOutput::Auto => {} // Let `console` detect if colors should be enabled
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_name() {
+        let name = |args: &[&str]| command_name(&Args::command().get_matches_from(args));
+        assert_eq!(name(&["pixi", "install"]), "install");
+        assert_eq!(
+            name(&["pixi", "project", "channel", "add", "bioconda"]),
+            "project channel add"
+        );
+        assert_eq!(name(&["pixi", "run", "test", "--verbose"]), "run");
+    }
+}
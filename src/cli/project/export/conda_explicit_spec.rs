@@ -1,15 +1,13 @@
-use std::{
-    collections::HashSet,
-    path::{Path, PathBuf},
-};
+use std::path::{Path, PathBuf};
 
 use clap::Parser;
 use miette::{Context, IntoDiagnostic};
 use rattler_conda_types::{
     ExplicitEnvironmentEntry, ExplicitEnvironmentSpec, PackageRecord, Platform, RepoDataRecord,
 };
-use rattler_lock::{CondaPackageData, Environment, LockedPackageRef};
+use rattler_lock::{CondaBinaryData, CondaPackageData, Environment, LockedPackageRef};
 
+use super::locked_environment_platforms;
 use crate::{
     cli::cli_config::{PrefixUpdateConfig, ProjectConfig},
     lock_file::UpdateLockFileOptions,
@@ -136,10 +134,21 @@ fn render_env_platform(
         }
     }
 
+    render_conda_packages(output_dir, env_name, platform, conda_packages_from_lockfile)
+}
+
+/// Renders the binary conda packages of an environment as a conda explicit
+/// spec file in the output directory.
+pub(super) fn render_conda_packages(
+    output_dir: &Path,
+    env_name: &str,
+    platform: &Platform,
+    conda_packages: Vec<CondaBinaryData>,
+) -> miette::Result<()> {
     // Topologically sort packages
-    let repodata = conda_packages_from_lockfile
-        .iter()
-        .map(|p| RepoDataRecord::try_from(p.clone()))
+    let repodata = conda_packages
+        .into_iter()
+        .map(RepoDataRecord::try_from)
         .collect::<Result<Vec<_>, _>>()
         .into_diagnostic()
         .with_context(|| "Failed to convert conda packages to RepoDataRecords")?;
@@ -171,45 +180,8 @@ pub async fn execute(args: Args) -> miette::Result<()> {
         .await?
         .lock_file;
 
-    let mut environments = Vec::new();
-    if let Some(env_names) = args.environment {
-        for env_name in &env_names {
-            environments.push((
-                env_name.to_string(),
-                lockfile
-                    .environment(env_name)
-                    .ok_or(miette::miette!("unknown environment {}", env_name))?,
-            ));
-        }
-    } else {
-        for (env_name, env) in lockfile.environments() {
-            environments.push((env_name.to_string(), env));
-        }
-    };
-
-    let mut env_platform = Vec::new();
-
-    for (env_name, env) in environments {
-        let available_platforms: HashSet<Platform> = HashSet::from_iter(env.platforms());
-
-        if let Some(ref platforms) = args.platform {
-            for plat in platforms {
-                if available_platforms.contains(plat) {
-                    env_platform.push((env_name.clone(), env, *plat));
-                } else {
-                    tracing::warn!(
-                        "Platform {} not available for environment {}. Skipping...",
-                        plat,
-                        env_name,
-                    );
-                }
-            }
-        } else {
-            for plat in available_platforms {
-                env_platform.push((env_name.clone(), env, plat));
-            }
-        }
-    }
+    let env_platform =
+        locked_environment_platforms(&lockfile, args.environment, args.platform.as_deref())?;
 
     fs_err::create_dir_all(&args.output_dir).ok();
 
@@ -0,0 +1,225 @@
+use std::{fmt::Write, path::PathBuf};
+
+use clap::Parser;
+use miette::{Context, IntoDiagnostic};
+use pixi_consts::consts;
+use pixi_manifest::FeaturesExt;
+
+use super::container_platform;
+use crate::{cli::cli_config::ProjectConfig, Project};
+
+/// The conda packages that provide a C or C++ compiler.
+const COMPILER_PACKAGES: &[&str] = &[
+    "c-compiler",
+    "cxx-compiler",
+    "compilers",
+    "gcc",
+    "gxx",
+    "clang",
+    "clangxx",
+];
+
+#[derive(Debug, Parser)]
+#[clap(arg_required_else_help = false)]
+pub struct Args {
+    #[clap(flatten)]
+    pub project_config: ProjectConfig,
+
+    /// The directory to write the `devcontainer.json` and `Dockerfile` to.
+    /// Defaults to the `.devcontainer` directory of the project.
+    pub output_dir: Option<PathBuf>,
+
+    /// The environment to install in the devcontainer. Defaults to the
+    /// default environment.
+    #[arg(short, long)]
+    pub environment: Option<String>,
+
+    /// The version of pixi that is installed in the devcontainer. Defaults to
+    /// the version of this pixi.
+    #[arg(long)]
+    pub pixi_version: Option<String>,
+
+    /// Overwrite an existing `devcontainer.json` or `Dockerfile`
+    #[arg(long)]
+    pub force: bool,
+}
+
+/// What goes into the rendered devcontainer.
+struct DevcontainerOptions<'a> {
+    name: &'a str,
+    environment: &'a str,
+    pixi_version: &'a str,
+    python: bool,
+    compiler: bool,
+}
+
+/// Renders the Dockerfile of the devcontainer, which only installs pixi. The
+/// environment is installed once the container is created.
+fn render_dockerfile(options: &DevcontainerOptions) -> String {
+    let mut dockerfile = String::new();
+    writeln!(
+        dockerfile,
+        "# Generated by `pixi project export devcontainer`"
+    )
+    .unwrap();
+    writeln!(
+        dockerfile,
+        "FROM mcr.microsoft.com/devcontainers/base:jammy"
+    )
+    .unwrap();
+    writeln!(dockerfile).unwrap();
+    writeln!(dockerfile, "ARG PIXI_VERSION=v{}", options.pixi_version).unwrap();
+    writeln!(dockerfile).unwrap();
+    writeln!(
+        dockerfile,
+        "RUN curl -L -o /usr/local/bin/pixi -fsSL --compressed \"https://github.com/prefix-dev/pixi/releases/download/${{PIXI_VERSION}}/pixi-$(uname -m)-unknown-linux-musl\" \\"
+    )
+    .unwrap();
+    writeln!(dockerfile, "    && chmod +x /usr/local/bin/pixi \\").unwrap();
+    writeln!(dockerfile, "    && pixi info").unwrap();
+    writeln!(dockerfile).unwrap();
+    writeln!(
+        dockerfile,
+        "# set some user and workdir settings to work nicely with vscode"
+    )
+    .unwrap();
+    writeln!(dockerfile, "USER vscode").unwrap();
+    writeln!(dockerfile, "WORKDIR /home/vscode").unwrap();
+    writeln!(dockerfile).unwrap();
+    writeln!(
+        dockerfile,
+        "RUN echo 'eval \"$(pixi completion -s bash)\"' >> /home/vscode/.bashrc"
+    )
+    .unwrap();
+    dockerfile
+}
+
+/// Renders the `devcontainer.json`. The `.pixi` directory is mounted in a
+/// volume because some packages contain files that only differ in case, which
+/// breaks on the case insensitive filesystems of macOS and Windows.
+fn render_devcontainer_json(options: &DevcontainerOptions) -> String {
+    let env = options.environment;
+    let prefix = format!(
+        "${{containerWorkspaceFolder}}/{}/{}/{env}",
+        consts::PIXI_DIR,
+        consts::ENVIRONMENTS_DIR
+    );
+
+    let mut extensions = Vec::new();
+    let mut settings = serde_json::Map::new();
+    if options.python {
+        extensions.push("ms-python.python");
+        settings.insert(
+            "python.defaultInterpreterPath".to_string(),
+            format!("{prefix}/bin/python").into(),
+        );
+    }
+    if options.compiler {
+        extensions.push("ms-vscode.cpptools");
+    }
+
+    let install = if env == consts::DEFAULT_ENVIRONMENT_NAME {
+        "pixi install".to_string()
+    } else {
+        format!("pixi install -e {env}")
+    };
+    let devcontainer = serde_json::json!({
+        "name": options.name,
+        "build": {
+            "dockerfile": "Dockerfile",
+            "context": "..",
+        },
+        "customizations": {
+            "vscode": {
+                "settings": settings,
+                "extensions": extensions,
+            }
+        },
+        "mounts": [format!(
+            "source=${{localWorkspaceFolderBasename}}-pixi,target=${{containerWorkspaceFolder}}/{},type=volume",
+            consts::PIXI_DIR
+        )],
+        "postCreateCommand": format!("sudo chown vscode {} && {install}", consts::PIXI_DIR),
+    });
+
+    let mut json = serde_json::to_string_pretty(&devcontainer).expect("json can be serialized");
+    json.push('\n');
+    json
+}
+
+pub async fn execute(args: Args) -> miette::Result<()> {
+    let project = Project::load_or_else_discover(args.project_config.manifest_path.as_deref())?;
+    let environment = project.environment_from_name_or_env_var(args.environment)?;
+    let platform = container_platform(&environment)?;
+
+    let dependencies = environment.combined_dependencies(Some(platform));
+    let python = !environment.pypi_dependencies(Some(platform)).is_empty()
+        || dependencies
+            .names()
+            .any(|name| name.as_normalized() == "python");
+    let compiler = dependencies
+        .names()
+        .any(|name| COMPILER_PACKAGES.contains(&name.as_normalized()));
+
+    let pixi_version = args
+        .pixi_version
+        .unwrap_or_else(|| consts::PIXI_VERSION.to_string());
+    let options = DevcontainerOptions {
+        name: project.name(),
+        environment: environment.name().as_str(),
+        pixi_version: pixi_version.trim_start_matches('v'),
+        python,
+        compiler,
+    };
+
+    let output_dir = args
+        .output_dir
+        .unwrap_or_else(|| project.root().join(".devcontainer"));
+    let devcontainer_path = output_dir.join("devcontainer.json");
+    let dockerfile_path = output_dir.join("Dockerfile");
+    if !args.force {
+        if let Some(existing) = [&devcontainer_path, &dockerfile_path]
+            .into_iter()
+            .find(|path| path.exists())
+        {
+            miette::bail!(
+                "'{}' already exists, use `--force` to overwrite it",
+                existing.display()
+            );
+        }
+    }
+
+    fs_err::create_dir_all(&output_dir).into_diagnostic()?;
+    fs_err::write(&devcontainer_path, render_devcontainer_json(&options))
+        .into_diagnostic()
+        .wrap_err("failed to write the devcontainer")?;
+    fs_err::write(&dockerfile_path, render_dockerfile(&options))
+        .into_diagnostic()
+        .wrap_err("failed to write the Dockerfile of the devcontainer")?;
+
+    eprintln!(
+        "{}Exported the devcontainer to {}",
+        console::style(console::Emoji("✔ ", "")).green(),
+        output_dir.display()
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_devcontainer() {
+        let options = DevcontainerOptions {
+            name: "my-project",
+            environment: "dev",
+            pixi_version: "0.40.2",
+            python: true,
+            compiler: true,
+        };
+        insta::assert_snapshot!(render_devcontainer_json(&options));
+        insta::assert_snapshot!(render_dockerfile(&options));
+    }
+}
@@ -0,0 +1,75 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+use fancy_display::FancyDisplay;
+use miette::{Context, IntoDiagnostic};
+use pixi_consts::consts;
+
+use crate::{cli::cli_config::ProjectConfig, Project};
+
+#[derive(Debug, Parser)]
+#[clap(arg_required_else_help = false)]
+pub struct Args {
+    #[clap(flatten)]
+    pub project_config: ProjectConfig,
+
+    /// The `.envrc` to add the activation to. Defaults to the `.envrc` of the
+    /// project.
+    pub output_path: Option<PathBuf>,
+
+    /// The environment direnv activates. Defaults to the default environment.
+    #[arg(short, long)]
+    pub environment: Option<String>,
+}
+
+/// Returns the line of the `.envrc` that activates the environment.
+fn envrc_line(environment: &str) -> String {
+    if environment == consts::DEFAULT_ENVIRONMENT_NAME {
+        "eval \"$(pixi shell-hook --format direnv)\"".to_string()
+    } else {
+        format!("eval \"$(pixi shell-hook --format direnv -e {environment})\"")
+    }
+}
+
+pub async fn execute(args: Args) -> miette::Result<()> {
+    let project = Project::load_or_else_discover(args.project_config.manifest_path.as_deref())?;
+    let environment = project.environment_from_name_or_env_var(args.environment)?;
+
+    let output_path = args
+        .output_path
+        .unwrap_or_else(|| project.root().join(".envrc"));
+    let line = envrc_line(environment.name().as_str());
+
+    let mut envrc = if output_path.is_file() {
+        fs_err::read_to_string(&output_path).into_diagnostic()?
+    } else {
+        String::new()
+    };
+    if envrc.lines().any(|existing| existing.trim() == line) {
+        eprintln!(
+            "{}{} already activates the environment",
+            console::style(console::Emoji("✔ ", "")).green(),
+            output_path.display()
+        );
+        return Ok(());
+    }
+    if !envrc.is_empty() && !envrc.ends_with('\n') {
+        envrc.push('\n');
+    }
+    envrc.push_str("# Generated by `pixi project export direnv`\n");
+    envrc.push_str(&line);
+    envrc.push('\n');
+
+    fs_err::write(&output_path, envrc)
+        .into_diagnostic()
+        .wrap_err("failed to write the .envrc")?;
+
+    eprintln!(
+        "{}Added the activation of the environment {} to {}, run `direnv allow` to approve it",
+        console::style(console::Emoji("✔ ", "")).green(),
+        environment.name().fancy_display(),
+        output_path.display()
+    );
+
+    Ok(())
+}
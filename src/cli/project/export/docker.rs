@@ -0,0 +1,267 @@
+use std::{
+    fmt::Write,
+    path::{Component, Path, PathBuf},
+};
+
+use clap::Parser;
+use itertools::Itertools;
+use miette::{Context, IntoDiagnostic};
+use pixi_consts::consts;
+use rattler_lock::UrlOrPath;
+
+use super::container_platform;
+use crate::{
+    cli::cli_config::{PrefixUpdateConfig, ProjectConfig},
+    lock_file::UpdateLockFileOptions,
+    Project,
+};
+
+/// The image the environments are installed on in the production stage.
+const DEFAULT_BASE_IMAGE: &str = "ubuntu:24.04";
+
+#[derive(Debug, Parser)]
+#[clap(arg_required_else_help = false)]
+pub struct Args {
+    #[clap(flatten)]
+    pub project_config: ProjectConfig,
+
+    /// Explicit path to write the Dockerfile to, defaults to stdout
+    pub output_path: Option<PathBuf>,
+
+    /// The environment to install in the image. Defaults to the default
+    /// environment.
+    #[arg(short, long)]
+    pub environment: Option<String>,
+
+    /// The image of the production stage
+    #[arg(long, default_value = DEFAULT_BASE_IMAGE)]
+    pub base_image: String,
+
+    /// The version of the pixi image of the build stage. Defaults to the
+    /// version of this pixi.
+    #[arg(long)]
+    pub pixi_version: Option<String>,
+
+    /// The command the container runs in the environment, passed after `--`
+    #[arg(last = true)]
+    pub command: Vec<String>,
+
+    #[clap(flatten)]
+    pub prefix_update_config: PrefixUpdateConfig,
+}
+
+/// What goes into the rendered Dockerfile.
+struct DockerfileOptions<'a> {
+    environment: &'a str,
+    base_image: &'a str,
+    pixi_version: &'a str,
+    command: &'a [String],
+    /// The paths of the local PyPI packages, relative to the project root.
+    /// They are copied into the production stage because the environment
+    /// refers to their sources.
+    local_paths: Vec<String>,
+}
+
+/// Renders a multi-stage Dockerfile that installs the locked environment with
+/// pixi in the build stage, and only copies the environment and an activation
+/// entrypoint into the production stage.
+fn render_dockerfile(options: &DockerfileOptions) -> String {
+    let env = options.environment;
+    let prefix = format!(
+        "/app/{}/{}/{env}",
+        consts::PIXI_DIR,
+        consts::ENVIRONMENTS_DIR
+    );
+
+    let mut dockerfile = String::new();
+    writeln!(dockerfile, "# Generated by `pixi project export docker`").unwrap();
+    writeln!(
+        dockerfile,
+        "# Add `{}` to the `.dockerignore` so the local environments aren't copied into the build.",
+        consts::PIXI_DIR
+    )
+    .unwrap();
+    writeln!(
+        dockerfile,
+        "FROM ghcr.io/prefix-dev/pixi:{} AS build",
+        options.pixi_version
+    )
+    .unwrap();
+    writeln!(dockerfile, "WORKDIR /app").unwrap();
+    writeln!(dockerfile, "COPY . .").unwrap();
+    writeln!(
+        dockerfile,
+        "# use `--locked` to ensure the lock file is up to date with the manifest"
+    )
+    .unwrap();
+    writeln!(dockerfile, "RUN pixi install --locked -e {env}").unwrap();
+    writeln!(
+        dockerfile,
+        "# create an entrypoint that activates the environment without pixi"
+    )
+    .unwrap();
+    writeln!(
+        dockerfile,
+        "RUN pixi shell-hook --frozen -e {env} -s bash > /shell-hook"
+    )
+    .unwrap();
+    writeln!(dockerfile, "RUN echo '#!/bin/bash' > /app/entrypoint.sh").unwrap();
+    writeln!(dockerfile, "RUN cat /shell-hook >> /app/entrypoint.sh").unwrap();
+    writeln!(dockerfile, "RUN echo 'exec \"$@\"' >> /app/entrypoint.sh").unwrap();
+    writeln!(dockerfile).unwrap();
+    writeln!(dockerfile, "FROM {} AS production", options.base_image).unwrap();
+    writeln!(dockerfile, "WORKDIR /app").unwrap();
+    writeln!(
+        dockerfile,
+        "# the environment has to keep the prefix it was installed in"
+    )
+    .unwrap();
+    writeln!(dockerfile, "COPY --from=build {prefix} {prefix}").unwrap();
+    writeln!(
+        dockerfile,
+        "COPY --from=build --chmod=0755 /app/entrypoint.sh /app/entrypoint.sh"
+    )
+    .unwrap();
+    for path in &options.local_paths {
+        writeln!(dockerfile, "COPY --from=build /app/{path} /app/{path}").unwrap();
+    }
+    writeln!(dockerfile, "ENTRYPOINT [ \"/app/entrypoint.sh\" ]").unwrap();
+    if !options.command.is_empty() {
+        writeln!(
+            dockerfile,
+            "CMD [ {} ]",
+            options
+                .command
+                .iter()
+                .map(|arg| serde_json::to_string(arg).expect("a string can be serialized"))
+                .join(", ")
+        )
+        .unwrap();
+    }
+
+    dockerfile
+}
+
+/// Returns the paths of the local PyPI packages relative to the project root,
+/// normalized and without duplicates. Only the project is copied into the
+/// image, so paths outside of it are rejected.
+fn local_paths<'a>(paths: impl IntoIterator<Item = &'a str>) -> miette::Result<Vec<String>> {
+    let mut local_paths = Vec::new();
+    for path in paths {
+        let mut components = Vec::new();
+        for component in Path::new(path).components() {
+            match component {
+                Component::CurDir => {}
+                Component::Normal(name) => components.push(name.to_string_lossy()),
+                Component::ParentDir if components.pop().is_some() => {}
+                _ => miette::bail!(
+                    help = "only the project directory is copied into the image, move the package into it",
+                    "the local PyPI package '{path}' is outside of the project"
+                ),
+            }
+        }
+        if !components.is_empty() {
+            local_paths.push(components.join("/"));
+        }
+    }
+    Ok(local_paths.into_iter().sorted().dedup().collect())
+}
+
+pub async fn execute(args: Args) -> miette::Result<()> {
+    let project = Project::load_or_else_discover(args.project_config.manifest_path.as_deref())?
+        .with_cli_config(args.prefix_update_config.config.clone());
+    let environment = project.environment_from_name_or_env_var(args.environment)?;
+
+    let platform = container_platform(&environment)?;
+
+    let lock_file = project
+        .update_lock_file(UpdateLockFileOptions {
+            lock_file_usage: args.prefix_update_config.lock_file_usage(),
+            no_install: args.prefix_update_config.no_install,
+            max_concurrent_solves: project.config().max_concurrent_solves(),
+        })
+        .await?
+        .lock_file;
+
+    let Some(locked_environment) = lock_file.environment(environment.name().as_str()) else {
+        miette::bail!(
+            "environment '{}' is not part of the lock file",
+            environment.name()
+        );
+    };
+
+    // Local PyPI packages are installed from, or in the case of editable
+    // packages refer to, their sources.
+    let local_paths = local_paths(
+        locked_environment
+            .pypi_packages(platform)
+            .into_iter()
+            .flatten()
+            .filter_map(|(package, _)| match &package.location {
+                UrlOrPath::Path(path) => Some(path.as_str()),
+                UrlOrPath::Url(_) => None,
+            }),
+    )?;
+
+    let pixi_version = args
+        .pixi_version
+        .unwrap_or_else(|| consts::PIXI_VERSION.to_string());
+    let dockerfile = render_dockerfile(&DockerfileOptions {
+        environment: environment.name().as_str(),
+        base_image: &args.base_image,
+        pixi_version: &pixi_version,
+        command: &args.command,
+        local_paths,
+    });
+
+    if let Some(output_path) = args.output_path {
+        fs_err::write(&output_path, dockerfile)
+            .into_diagnostic()
+            .with_context(|| {
+                format!("failed to write the Dockerfile: {}", output_path.display())
+            })?;
+    } else {
+        print!("{dockerfile}");
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_dockerfile() {
+        let dockerfile = render_dockerfile(&DockerfileOptions {
+            environment: "prod",
+            base_image: DEFAULT_BASE_IMAGE,
+            pixi_version: "0.40.2",
+            command: &[
+                "uvicorn".to_string(),
+                "my_project:app".to_string(),
+                "--host".to_string(),
+                "0.0.0.0".to_string(),
+            ],
+            local_paths: vec!["src/my_project".to_string()],
+        });
+        insta::assert_snapshot!(dockerfile);
+    }
+
+    #[test]
+    fn test_local_paths() {
+        assert_eq!(
+            local_paths([
+                "./src/my_project",
+                "src/my_project",
+                ".",
+                "libs/../src/other"
+            ])
+            .unwrap(),
+            ["src/my_project", "src/other"]
+        );
+        assert!(local_paths(["../shared"]).is_err());
+        assert!(local_paths(["src/../../shared"]).is_err());
+        assert!(local_paths(["/opt/shared"]).is_err());
+    }
+}
@@ -0,0 +1,613 @@
+use std::{
+    fmt::Write,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use clap::Parser;
+use itertools::Itertools;
+use miette::{Context, IntoDiagnostic};
+use pixi_consts::consts;
+use rattler_conda_types::Platform;
+
+use crate::{
+    cli::cli_config::ProjectConfig, lock_file::environment_lock_file_candidates,
+    project::Environment, Project,
+};
+
+/// The version of the `prefix-dev/setup-pixi` action the workflow uses.
+const SETUP_PIXI_VERSION: &str = "v0.8.0";
+
+#[derive(Debug, Parser)]
+#[clap(arg_required_else_help = false)]
+pub struct Args {
+    #[clap(flatten)]
+    pub project_config: ProjectConfig,
+
+    /// Path to write the workflow to. Defaults to `.github/workflows/pixi.yml`
+    /// in the root of the git repository of the project.
+    pub output_path: Option<PathBuf>,
+
+    /// The environments to run the tasks in. Defaults to all environments
+    /// with tasks.
+    #[arg(short, long)]
+    pub environment: Option<Vec<String>>,
+
+    /// The tasks to run. Defaults to all tasks, except the ones starting with
+    /// `_`.
+    #[arg(short, long)]
+    pub task: Option<Vec<String>>,
+
+    /// The version of pixi the workflow installs. Defaults to the version of
+    /// this pixi.
+    #[arg(long)]
+    pub pixi_version: Option<String>,
+
+    /// The branch of which pushes run the workflow and write the caches.
+    /// Defaults to the default branch of the `origin` remote, or the current
+    /// branch.
+    #[arg(long)]
+    pub branch: Option<String>,
+
+    /// Overwrite an existing workflow
+    #[arg(long)]
+    pub force: bool,
+}
+
+/// Returns the GitHub hosted runner of a platform.
+fn runner(platform: Platform) -> Option<&'static str> {
+    match platform {
+        Platform::Linux64 => Some("ubuntu-latest"),
+        Platform::LinuxAarch64 => Some("ubuntu-24.04-arm"),
+        Platform::Osx64 => Some("macos-13"),
+        Platform::OsxArm64 => Some("macos-latest"),
+        Platform::Win64 => Some("windows-latest"),
+        _ => None,
+    }
+}
+
+/// A workflow that runs the tasks of a project.
+struct Workflow {
+    jobs: Vec<WorkflowJob>,
+    pixi_version: String,
+    /// The branch of which pushes run the workflow and write the caches.
+    branch: String,
+    /// The manifest of the project relative to the root of the repository,
+    /// if the project isn't located in the root.
+    manifest_path: Option<String>,
+}
+
+/// A job that runs the tasks of an environment on the runners of its
+/// platforms.
+#[derive(Debug, PartialEq)]
+struct WorkflowJob {
+    /// The id of the job, which is derived from the name of the environment.
+    id: String,
+    environment: String,
+    runners: Vec<&'static str>,
+    /// The lock-files that can lock the environment, relative to the root of
+    /// the repository.
+    lock_files: Vec<String>,
+    /// The tasks with the runners they're limited to, if they're not
+    /// available on all runners of the job.
+    tasks: Vec<(String, Option<Vec<&'static str>>)>,
+}
+
+/// Returns a valid job id for an environment name. Job ids may only contain
+/// alphanumeric characters, `-` and `_`, and have to start with a letter or
+/// `_`.
+fn job_id(environment: &str) -> String {
+    let id: String = environment
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if id.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        id
+    } else {
+        format!("_{id}")
+    }
+}
+
+/// Returns the root of the git repository that contains the path, or `None`
+/// if the path is not in a git repository.
+fn repository_root(path: &Path) -> Option<&Path> {
+    path.ancestors().find(|dir| dir.join(".git").exists())
+}
+
+/// Returns the path relative to the root of the repository with forward
+/// slashes, as GitHub Actions expects it on every runner.
+fn repository_path(path: &Path, repository_root: &Path) -> String {
+    path.strip_prefix(repository_root)
+        .unwrap_or(path)
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .join("/")
+}
+
+/// Returns the default branch of the `origin` remote of the repository, or
+/// the current branch if the repository has no such remote.
+fn default_branch(repository_root: &Path) -> Option<String> {
+    let git = |args: &[&str]| {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(repository_root)
+            .output()
+            .ok()?;
+        let stdout = String::from_utf8(output.stdout).ok()?;
+        let stdout = stdout.trim();
+        (output.status.success() && !stdout.is_empty()).then(|| stdout.to_string())
+    };
+    git(&["symbolic-ref", "--short", "refs/remotes/origin/HEAD"])
+        .map(|branch| branch.trim_start_matches("origin/").to_string())
+        .or_else(|| git(&["branch", "--show-current"]))
+}
+
+/// Quotes a string for YAML if it isn't a plain scalar.
+fn yaml_string(value: &str) -> String {
+    if !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || " -_./=".contains(c))
+    {
+        value.to_string()
+    } else {
+        serde_json::to_string(value).expect("a string can be serialized")
+    }
+}
+
+/// Renders a workflow with a job per environment. The package cache of pixi is
+/// cached next to the environments that `setup-pixi` caches, and both caches
+/// are only written on the branch of the workflow to stay within the cache
+/// limits.
+fn render_workflow(workflow_spec: &Workflow) -> String {
+    let branch = &workflow_spec.branch;
+    let mut workflow = String::new();
+    writeln!(
+        workflow,
+        "# Generated by `pixi project export github-actions`"
+    )
+    .unwrap();
+    writeln!(workflow, "name: pixi").unwrap();
+    writeln!(workflow).unwrap();
+    writeln!(workflow, "on:").unwrap();
+    writeln!(workflow, "  push:").unwrap();
+    writeln!(workflow, "    branches: [{}]", yaml_string(branch)).unwrap();
+    writeln!(workflow, "  pull_request:").unwrap();
+    writeln!(workflow, "  workflow_dispatch:").unwrap();
+    writeln!(workflow).unwrap();
+    if let Some(manifest_path) = &workflow_spec.manifest_path {
+        let project_dir = manifest_path.rsplit_once('/').map_or(".", |(dir, _)| dir);
+        writeln!(workflow, "defaults:").unwrap();
+        writeln!(workflow, "  run:").unwrap();
+        writeln!(
+            workflow,
+            "    working-directory: {}",
+            yaml_string(project_dir)
+        )
+        .unwrap();
+        writeln!(workflow).unwrap();
+    }
+    writeln!(workflow, "jobs:").unwrap();
+    for job in &workflow_spec.jobs {
+        let id = &job.id;
+        let env = &job.environment;
+        writeln!(workflow, "  {id}:").unwrap();
+        writeln!(
+            workflow,
+            "    name: {}",
+            yaml_string(&format!("{env} (${{{{ matrix.os }}}})"))
+        )
+        .unwrap();
+        writeln!(workflow, "    runs-on: ${{{{ matrix.os }}}}").unwrap();
+        writeln!(workflow, "    strategy:").unwrap();
+        writeln!(workflow, "      fail-fast: false").unwrap();
+        writeln!(workflow, "      matrix:").unwrap();
+        writeln!(workflow, "        os: [{}]", job.runners.join(", ")).unwrap();
+        writeln!(workflow, "    steps:").unwrap();
+        writeln!(workflow, "      - uses: actions/checkout@v4").unwrap();
+        writeln!(workflow, "      - name: Set the pixi cache directory").unwrap();
+        writeln!(workflow, "        shell: bash").unwrap();
+        writeln!(
+            workflow,
+            "        run: echo \"PIXI_CACHE_DIR=${{{{ runner.temp }}}}/pixi-cache\" >> \"$GITHUB_ENV\""
+        )
+        .unwrap();
+        writeln!(workflow, "      - uses: actions/cache@v4").unwrap();
+        writeln!(workflow, "        with:").unwrap();
+        writeln!(workflow, "          path: ${{{{ env.PIXI_CACHE_DIR }}}}").unwrap();
+        let lock_files = job
+            .lock_files
+            .iter()
+            .map(|path| format!("'{}'", path.replace('\'', "''")))
+            .join(", ");
+        writeln!(
+            workflow,
+            "          key: pixi-cache-{id}-${{{{ matrix.os }}}}-${{{{ hashFiles({lock_files}) }}}}"
+        )
+        .unwrap();
+        writeln!(
+            workflow,
+            "          restore-keys: pixi-cache-{id}-${{{{ matrix.os }}}}-"
+        )
+        .unwrap();
+        writeln!(
+            workflow,
+            "      - uses: prefix-dev/setup-pixi@{SETUP_PIXI_VERSION}"
+        )
+        .unwrap();
+        writeln!(workflow, "        with:").unwrap();
+        writeln!(
+            workflow,
+            "          pixi-version: v{}",
+            workflow_spec.pixi_version
+        )
+        .unwrap();
+        if let Some(manifest_path) = &workflow_spec.manifest_path {
+            writeln!(
+                workflow,
+                "          manifest-path: {}",
+                yaml_string(manifest_path)
+            )
+            .unwrap();
+        }
+        writeln!(workflow, "          environments: {}", yaml_string(env)).unwrap();
+        writeln!(workflow, "          cache: true").unwrap();
+        writeln!(
+            workflow,
+            "          cache-write: ${{{{ github.event_name == 'push' && github.ref_name == '{}' }}}}",
+            branch.replace('\'', "''")
+        )
+        .unwrap();
+        for (task, runners) in &job.tasks {
+            writeln!(workflow, "      - name: {}", yaml_string(task)).unwrap();
+            if let Some(runners) = runners {
+                writeln!(
+                    workflow,
+                    "        if: {}",
+                    runners
+                        .iter()
+                        .map(|runner| format!("matrix.os == '{runner}'"))
+                        .join(" || ")
+                )
+                .unwrap();
+            }
+            let task = shlex::try_quote(task).unwrap_or_else(|_| task.into());
+            writeln!(
+                workflow,
+                "        run: {}",
+                yaml_string(&format!("pixi run -e {env} {task}"))
+            )
+            .unwrap();
+        }
+    }
+    workflow
+}
+
+/// Returns a job for every environment that has tasks to run on a GitHub
+/// hosted runner. The tasks are filtered by the given names, or all tasks
+/// that don't start with `_` are run.
+fn workflow_jobs(
+    environments: Vec<Environment<'_>>,
+    selected_tasks: Option<&[String]>,
+    repository_root: &Path,
+) -> miette::Result<Vec<WorkflowJob>> {
+    let mut jobs: Vec<WorkflowJob> = Vec::new();
+    for environment in environments {
+        let platforms = environment
+            .platforms()
+            .into_iter()
+            .filter_map(|platform| runner(platform).map(|runner| (platform, runner)))
+            .sorted_by_key(|(_, runner)| *runner)
+            .collect_vec();
+        if platforms.is_empty() {
+            tracing::warn!(
+                "Skipping the environment '{}' as none of its platforms has a GitHub hosted runner",
+                environment.name()
+            );
+            continue;
+        }
+
+        // The runners each task is available on.
+        let mut task_runners: Vec<(String, Vec<&'static str>)> = Vec::new();
+        for (platform, runner) in &platforms {
+            let tasks = environment.tasks(Some(*platform)).into_diagnostic()?;
+            for name in tasks.keys().sorted() {
+                let name = name.as_str();
+                let selected = match selected_tasks {
+                    Some(selected) => selected.iter().any(|task| task == name),
+                    None => !name.starts_with('_'),
+                };
+                if !selected {
+                    continue;
+                }
+                match task_runners.iter_mut().find(|(task, _)| task == name) {
+                    Some((_, runners)) => runners.push(*runner),
+                    None => task_runners.push((name.to_string(), vec![*runner])),
+                }
+            }
+        }
+        if task_runners.is_empty() {
+            continue;
+        }
+        task_runners.sort();
+
+        // Environment names that only differ in the characters that are
+        // replaced would share a job id.
+        let mut id = job_id(environment.name().as_str());
+        while jobs.iter().any(|job| job.id == id) {
+            id.push('_');
+        }
+
+        let runners = platforms.iter().map(|(_, runner)| *runner).collect_vec();
+        jobs.push(WorkflowJob {
+            id,
+            environment: environment.name().as_str().to_string(),
+            lock_files: environment_lock_file_candidates(&environment)
+                .iter()
+                .map(|path| repository_path(path, repository_root))
+                .collect(),
+            tasks: task_runners
+                .into_iter()
+                .map(|(task, task_runners)| {
+                    let limited = (task_runners.len() < runners.len()).then_some(task_runners);
+                    (task, limited)
+                })
+                .collect(),
+            runners,
+        });
+    }
+    Ok(jobs)
+}
+
+pub async fn execute(args: Args) -> miette::Result<()> {
+    let project = Project::load_or_else_discover(args.project_config.manifest_path.as_deref())?;
+
+    let environments = match &args.environment {
+        Some(names) => names
+            .iter()
+            .map(|name| project.environment_from_name_or_env_var(Some(name.clone())))
+            .collect::<Result<Vec<_>, _>>()?,
+        None => project.environments(),
+    };
+
+    // GitHub only runs the workflows in the root of the repository, which may
+    // contain the project in a subdirectory.
+    let repository_root = repository_root(project.root()).unwrap_or(project.root());
+    let jobs = workflow_jobs(environments, args.task.as_deref(), repository_root)?;
+    if jobs.is_empty() {
+        miette::bail!("there are no tasks to run in the workflow");
+    }
+
+    let pixi_version = args
+        .pixi_version
+        .unwrap_or_else(|| consts::PIXI_VERSION.to_string());
+    let branch = match args.branch {
+        Some(branch) => branch,
+        None => default_branch(repository_root).ok_or_else(|| {
+            miette::miette!(
+                help = "Pass the branch that runs the workflow with `--branch`",
+                "failed to determine the default branch of the repository"
+            )
+        })?,
+    };
+    let workflow = render_workflow(&Workflow {
+        jobs,
+        pixi_version: pixi_version.trim_start_matches('v').to_string(),
+        branch,
+        manifest_path: (project.root() != repository_root)
+            .then(|| repository_path(&project.manifest_path(), repository_root)),
+    });
+
+    let output_path = args.output_path.unwrap_or_else(|| {
+        repository_root
+            .join(".github")
+            .join("workflows")
+            .join("pixi.yml")
+    });
+    if output_path.exists() && !args.force {
+        miette::bail!(
+            "'{}' already exists, use `--force` to overwrite it",
+            output_path.display()
+        );
+    }
+    if let Some(parent) = output_path.parent() {
+        fs_err::create_dir_all(parent).into_diagnostic()?;
+    }
+    fs_err::write(&output_path, workflow)
+        .into_diagnostic()
+        .wrap_err("failed to write the workflow")?;
+
+    eprintln!(
+        "{}Exported the workflow to {}",
+        console::style(console::Emoji("✔ ", "")).green(),
+        output_path.display()
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_workflow() {
+        let jobs = vec![
+            WorkflowJob {
+                id: "default".to_string(),
+                environment: "default".to_string(),
+                runners: vec!["macos-latest", "ubuntu-latest", "windows-latest"],
+                lock_files: vec!["pixi.lock".to_string()],
+                tasks: vec![
+                    ("build".to_string(), None),
+                    ("test:unit".to_string(), Some(vec!["ubuntu-latest"])),
+                ],
+            },
+            WorkflowJob {
+                id: "lint".to_string(),
+                environment: "lint".to_string(),
+                runners: vec!["ubuntu-latest"],
+                lock_files: vec!["pixi.lock".to_string()],
+                tasks: vec![("lint".to_string(), None)],
+            },
+        ];
+        insta::assert_snapshot!(render_workflow(&Workflow {
+            jobs,
+            pixi_version: "0.40.2".to_string(),
+            branch: "main".to_string(),
+            manifest_path: None,
+        }));
+    }
+
+    #[test]
+    fn test_render_workflow_of_nested_project() {
+        let workflow = render_workflow(&Workflow {
+            jobs: vec![WorkflowJob {
+                id: "_311".to_string(),
+                environment: "311".to_string(),
+                runners: vec!["ubuntu-latest"],
+                lock_files: vec![
+                    "python/pixi.lock".to_string(),
+                    "python/.pixi/locks/311.lock".to_string(),
+                ],
+                tasks: vec![("test".to_string(), None)],
+            }],
+            pixi_version: "0.40.2".to_string(),
+            branch: "develop".to_string(),
+            manifest_path: Some("python/pixi.toml".to_string()),
+        });
+
+        // Pushes to the branch run the workflow and write the caches.
+        assert!(workflow.contains("    branches: [develop]\n"));
+        assert!(workflow.contains("github.ref_name == 'develop'"));
+        assert!(!workflow.contains("main"));
+
+        // The tasks run in the directory of the project.
+        assert!(workflow.contains("defaults:\n  run:\n    working-directory: python\n"));
+        assert!(workflow.contains("          manifest-path: python/pixi.toml\n"));
+
+        // The cache is invalidated by the lock-files of the environment.
+        assert!(workflow.contains(
+            "key: pixi-cache-_311-${{ matrix.os }}-${{ hashFiles('python/pixi.lock', 'python/.pixi/locks/311.lock') }}"
+        ));
+        assert!(workflow.contains("  _311:\n    name: \"311 (${{ matrix.os }})\"\n"));
+        assert!(workflow.contains("          environments: \"311\"\n"));
+        assert!(workflow.contains("        run: pixi run -e 311 test\n"));
+    }
+
+    #[test]
+    fn test_job_id() {
+        assert_eq!(job_id("default"), "default");
+        assert_eq!(job_id("py-311"), "py-311");
+        assert_eq!(job_id("311"), "_311");
+        assert_eq!(job_id("-foo"), "_-foo");
+        assert_eq!(job_id("foo.bar"), "foo_bar");
+    }
+
+    #[test]
+    fn test_repository_path() {
+        let root = Path::new("/repo");
+        assert_eq!(
+            repository_path(&root.join("python").join("pixi.toml"), root),
+            "python/pixi.toml"
+        );
+        assert_eq!(repository_path(&root.join("pixi.lock"), root), "pixi.lock");
+    }
+
+    #[test]
+    fn test_repository_root() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let project_dir = temp_dir.path().join("python");
+        fs_err::create_dir_all(&project_dir).unwrap();
+        assert_eq!(repository_root(&project_dir), None);
+
+        fs_err::create_dir(temp_dir.path().join(".git")).unwrap();
+        assert_eq!(repository_root(&project_dir), Some(temp_dir.path()));
+    }
+
+    #[test]
+    fn test_workflow_jobs() {
+        let repository_root = Path::new("/repo");
+        let project = Project::from_str(
+            &repository_root.join("python").join("pixi.toml"),
+            r#"
+            [workspace]
+            name = "foo"
+            channels = []
+            platforms = ["linux-64", "win-64", "linux-ppc64le"]
+            per-environment-lock-files = true
+
+            [tasks]
+            build = "echo build"
+            _setup = "echo setup"
+
+            [target.linux-64.tasks]
+            unit = "echo unit"
+
+            [feature.py311.tasks]
+            lint = "echo lint"
+
+            [environments]
+            311 = ["py311"]
+            "#,
+        )
+        .unwrap();
+
+        let jobs = workflow_jobs(project.environments(), None, repository_root).unwrap();
+        let default = jobs
+            .iter()
+            .find(|job| job.environment == "default")
+            .unwrap();
+        assert_eq!(
+            *default,
+            WorkflowJob {
+                id: "default".to_string(),
+                environment: "default".to_string(),
+                // linux-ppc64le has no GitHub hosted runner.
+                runners: vec!["ubuntu-latest", "windows-latest"],
+                lock_files: vec![
+                    "python/pixi.lock".to_string(),
+                    "python/.pixi/locks/default.lock".to_string(),
+                ],
+                // Tasks starting with `_` are skipped.
+                tasks: vec![
+                    ("build".to_string(), None),
+                    ("unit".to_string(), Some(vec!["ubuntu-latest"])),
+                ],
+            }
+        );
+        let py311 = jobs.iter().find(|job| job.environment == "311").unwrap();
+        assert_eq!(py311.id, "_311");
+        assert_eq!(
+            py311.lock_files,
+            vec!["python/pixi.lock", "python/.pixi/locks/311.lock"]
+        );
+        assert_eq!(
+            py311
+                .tasks
+                .iter()
+                .map(|(task, _)| task.as_str())
+                .collect_vec(),
+            vec!["build", "lint", "unit"]
+        );
+
+        // Only the environments with one of the selected tasks get a job.
+        let jobs = workflow_jobs(
+            project.environments(),
+            Some(&["lint".to_string()]),
+            repository_root,
+        )
+        .unwrap();
+        assert_eq!(
+            jobs.iter().map(|job| job.id.as_str()).collect_vec(),
+            vec!["_311"]
+        );
+        assert_eq!(jobs[0].tasks, vec![("lint".to_string(), None)]);
+    }
+}
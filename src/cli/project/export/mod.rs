@@ -1,7 +1,18 @@
 pub mod conda_environment;
 pub mod conda_explicit_spec;
+pub mod devcontainer;
+pub mod direnv;
+pub mod docker;
+pub mod github_actions;
+pub mod requirements;
+pub mod sbom;
+pub mod vscode_settings;
+
+use std::collections::HashSet;
 
 use clap::Parser;
+use rattler_conda_types::Platform;
+use rattler_lock::{Environment, LockFile};
 
 /// Commands to export projects to other formats
 #[derive(Parser, Debug)]
@@ -17,12 +28,104 @@ pub enum Command {
     CondaExplicitSpec(conda_explicit_spec::Args),
     /// Export project environment to a conda environment.yaml file
     CondaEnvironment(conda_environment::Args),
+    /// Export the PyPI packages of a project environment to a requirements.txt
+    /// file with hashes
+    Requirements(requirements::Args),
+    /// Export a software bill of materials of a project environment in the
+    /// CycloneDX or SPDX format
+    Sbom(sbom::Args),
+    /// Export a multi-stage Dockerfile that installs a project environment
+    /// from the lock file
+    #[clap(visible_alias = "dockerfile")]
+    Docker(docker::Args),
+    /// Export a devcontainer that provisions a project environment with pixi
+    Devcontainer(devcontainer::Args),
+    /// Export the VSCode settings that point the Python and C/C++ extensions
+    /// at the interpreter and compiler of a project environment
+    VscodeSettings(vscode_settings::Args),
+    /// Add the activation of a project environment to the `.envrc` of direnv
+    Direnv(direnv::Args),
+    /// Export a GitHub Actions workflow that runs the tasks of the project on
+    /// its platforms and environments
+    GithubActions(github_actions::Args),
 }
 
 pub async fn execute(args: Args) -> miette::Result<()> {
     match args.command {
         Command::CondaExplicitSpec(args) => conda_explicit_spec::execute(args).await?,
         Command::CondaEnvironment(args) => conda_environment::execute(args).await?,
+        Command::Requirements(args) => requirements::execute(args).await?,
+        Command::Sbom(args) => sbom::execute(args).await?,
+        Command::Docker(args) => docker::execute(args).await?,
+        Command::Devcontainer(args) => devcontainer::execute(args).await?,
+        Command::VscodeSettings(args) => vscode_settings::execute(args).await?,
+        Command::Direnv(args) => direnv::execute(args).await?,
+        Command::GithubActions(args) => github_actions::execute(args).await?,
     };
     Ok(())
 }
+
+/// Returns the linux platform of the environment that is used in a container.
+fn container_platform(environment: &crate::project::Environment<'_>) -> miette::Result<Platform> {
+    let platforms = environment.platforms();
+    [Platform::Linux64, Platform::LinuxAarch64]
+        .into_iter()
+        .find(|platform| platforms.contains(platform))
+        .ok_or_else(|| {
+            miette::miette!(
+                "the environment '{}' doesn't support a linux platform that can be used in a container",
+                environment.name()
+            )
+        })
+}
+
+/// Returns the locked environments with the platforms to export. Defaults to
+/// all environments and all their locked platforms, platforms that are not
+/// locked for an environment are skipped.
+fn locked_environment_platforms<'l>(
+    lock_file: &'l LockFile,
+    environments: Option<Vec<String>>,
+    platforms: Option<&[Platform]>,
+) -> miette::Result<Vec<(String, Environment<'l>, Platform)>> {
+    let mut selected = Vec::new();
+    if let Some(env_names) = environments {
+        for env_name in &env_names {
+            selected.push((
+                env_name.to_string(),
+                lock_file
+                    .environment(env_name)
+                    .ok_or(miette::miette!("unknown environment {}", env_name))?,
+            ));
+        }
+    } else {
+        for (env_name, env) in lock_file.environments() {
+            selected.push((env_name.to_string(), env));
+        }
+    };
+
+    let mut env_platform = Vec::new();
+
+    for (env_name, env) in selected {
+        let available_platforms: HashSet<Platform> = HashSet::from_iter(env.platforms());
+
+        if let Some(platforms) = platforms {
+            for plat in platforms {
+                if available_platforms.contains(plat) {
+                    env_platform.push((env_name.clone(), env, *plat));
+                } else {
+                    tracing::warn!(
+                        "Platform {} not available for environment {}. Skipping...",
+                        plat,
+                        env_name,
+                    );
+                }
+            }
+        } else {
+            for plat in available_platforms {
+                env_platform.push((env_name.clone(), env, plat));
+            }
+        }
+    }
+
+    Ok(env_platform)
+}
@@ -0,0 +1,350 @@
+use std::{fmt::Write, path::PathBuf};
+
+use clap::Parser;
+use miette::{Context, IntoDiagnostic};
+use pixi_record::LockedGitUrl;
+use rattler_conda_types::Platform;
+use rattler_lock::{
+    CondaPackageData, Environment, FindLinksUrlOrPath, PackageHashes, PypiPackageData, UrlOrPath,
+};
+use url::Url;
+
+use super::{conda_explicit_spec, locked_environment_platforms};
+use crate::{
+    cli::cli_config::{PrefixUpdateConfig, ProjectConfig},
+    install_pypi::utils::strip_direct_scheme,
+    lock_file::UpdateLockFileOptions,
+    Project,
+};
+
+#[derive(Debug, Parser)]
+#[clap(arg_required_else_help = false)]
+pub struct Args {
+    #[clap(flatten)]
+    pub project_config: ProjectConfig,
+
+    /// Output directory for the rendered requirements files
+    pub output_dir: PathBuf,
+
+    /// The environment to render. Can be repeated for multiple environments.
+    /// Defaults to all environments.
+    #[arg(short, long)]
+    pub environment: Option<Vec<String>>,
+
+    /// The platform to render. Can be repeated for multiple platforms.
+    /// Defaults to all platforms available for selected environments.
+    #[arg(short, long)]
+    pub platform: Option<Vec<Platform>>,
+
+    /// Also render the conda packages of the environment as a conda explicit
+    /// spec file.
+    #[arg(long, default_value = "false")]
+    pub conda_explicit_spec: bool,
+
+    #[clap(flatten)]
+    pub prefix_update_config: PrefixUpdateConfig,
+}
+
+/// Renders the hashes of a distribution as the `--hash` options of pip. pip
+/// rejects md5 hashes in hash-checking mode, so a distribution with only an
+/// md5 hash is treated as unhashed.
+fn hash_options(hash: Option<&PackageHashes>) -> Vec<String> {
+    match hash {
+        Some(PackageHashes::Sha256(sha256) | PackageHashes::Md5Sha256(_, sha256)) => {
+            vec![format!("--hash=sha256:{sha256:x}")]
+        }
+        Some(PackageHashes::Md5(_)) | None => Vec::new(),
+    }
+}
+
+/// Renders a locked git url of pixi as the `git+<repo>@<commit>` form that
+/// pip understands. Pixi locks the commit in the fragment, a url that already
+/// has the pip form, with at most a `#subdirectory=` fragment, is kept.
+fn pip_git_url(url: &Url) -> miette::Result<String> {
+    if url
+        .fragment()
+        .map_or(true, |fragment| fragment.contains('='))
+    {
+        return Ok(url.to_string());
+    }
+    let pinned = LockedGitUrl::parse(url.as_str())?.to_pinned_git_spec()?;
+    let mut git_url = format!("git+{}@{}", pinned.git, pinned.source.commit);
+    if let Some(subdirectory) = &pinned.source.subdirectory {
+        write!(git_url, "#subdirectory={subdirectory}").unwrap();
+    }
+    Ok(git_url)
+}
+
+/// Renders a locked PyPI package as a line of a `requirements.txt`.
+///
+/// Packages from an index are pinned to their version, direct urls and git
+/// repositories are referenced by their url, and local paths by their path
+/// relative to the project root. Only archives are pinned to their hash, pip
+/// can't verify the hash of a git repository or a local directory. Returns
+/// the line and whether it is pinned to a hash.
+fn requirement_line(package: &PypiPackageData) -> miette::Result<(String, bool)> {
+    let (requirement, hashes) = match &package.location {
+        UrlOrPath::Path(path) => {
+            // A relative path has to look like a path, otherwise pip reads it as
+            // the name of a package.
+            let path = if path.is_relative() && !path.as_str().starts_with('.') {
+                format!("./{path}")
+            } else {
+                path.to_string()
+            };
+            let requirement = if package.editable {
+                format!("-e {path}")
+            } else {
+                path
+            };
+            (requirement, Vec::new())
+        }
+        UrlOrPath::Url(url) if LockedGitUrl::is_locked_git_url(url) => (
+            format!("{} @ {}", package.name, pip_git_url(url)?),
+            Vec::new(),
+        ),
+        UrlOrPath::Url(url) if url.scheme().starts_with("direct") || url.scheme() == "file" => (
+            format!("{} @ {}", package.name, strip_direct_scheme(url)),
+            hash_options(package.hash.as_ref()),
+        ),
+        UrlOrPath::Url(_) => (
+            format!("{}=={}", package.name, package.version),
+            hash_options(package.hash.as_ref()),
+        ),
+    };
+
+    let hashed = !hashes.is_empty();
+    let mut line = requirement;
+    for hash in hashes {
+        write!(line, " \\\n    {hash}").unwrap();
+    }
+    Ok((line, hashed))
+}
+
+/// A rendered `requirements.txt`.
+struct Requirements {
+    /// The content of the file.
+    content: String,
+    /// The names of the packages that are not pinned to a hash. A file with
+    /// such packages can't be installed with `--require-hashes`.
+    unhashed: Vec<String>,
+}
+
+/// Renders the PyPI packages of the environment as a `requirements.txt`.
+/// Returns `None` if the environment doesn't contain PyPI packages for the
+/// platform.
+fn render_requirements(
+    env: &Environment,
+    platform: Platform,
+) -> miette::Result<Option<Requirements>> {
+    let Some(mut pypi_packages) = env
+        .pypi_packages(platform)
+        .map(|packages| packages.map(|(package, _)| package).collect::<Vec<_>>())
+    else {
+        miette::bail!("platform '{platform}' not found");
+    };
+    if pypi_packages.is_empty() {
+        return Ok(None);
+    }
+    pypi_packages.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let (lines, hashed): (Vec<_>, Vec<_>) = pypi_packages
+        .iter()
+        .map(|package| {
+            requirement_line(package)
+                .with_context(|| format!("failed to export '{}'", package.name))
+        })
+        .collect::<miette::Result<Vec<_>>>()?
+        .into_iter()
+        .unzip();
+    let unhashed = pypi_packages
+        .iter()
+        .zip(hashed)
+        .filter(|(_, hashed)| !hashed)
+        .map(|(package, _)| package.name.to_string())
+        .collect::<Vec<_>>();
+
+    let mut requirements = String::new();
+    requirements.push_str("# Generated by `pixi project export`\n");
+    if !unhashed.is_empty() {
+        writeln!(
+            requirements,
+            "# Not pinned to a hash, install without `--require-hashes`: {}",
+            unhashed.join(", ")
+        )
+        .unwrap();
+    }
+    if let Some(indexes) = env.pypi_indexes() {
+        for (idx, index) in indexes.indexes.iter().enumerate() {
+            if idx == 0 {
+                writeln!(requirements, "--index-url {index}").unwrap();
+            } else {
+                writeln!(requirements, "--extra-index-url {index}").unwrap();
+            }
+        }
+        for find_links in &indexes.find_links {
+            match find_links {
+                FindLinksUrlOrPath::Url(url) => writeln!(requirements, "--find-links {url}"),
+                FindLinksUrlOrPath::Path(path) => {
+                    writeln!(requirements, "--find-links {}", path.display())
+                }
+            }
+            .unwrap();
+        }
+    }
+    for line in lines {
+        writeln!(requirements, "{line}").unwrap();
+    }
+
+    Ok(Some(Requirements {
+        content: requirements,
+        unhashed,
+    }))
+}
+
+pub async fn execute(args: Args) -> miette::Result<()> {
+    let project = Project::load_or_else_discover(args.project_config.manifest_path.as_deref())?
+        .with_cli_config(args.prefix_update_config.config.clone());
+
+    let lockfile = project
+        .update_lock_file(UpdateLockFileOptions {
+            lock_file_usage: args.prefix_update_config.lock_file_usage(),
+            no_install: args.prefix_update_config.no_install,
+            max_concurrent_solves: project.config().max_concurrent_solves(),
+        })
+        .await?
+        .lock_file;
+
+    let env_platform =
+        locked_environment_platforms(&lockfile, args.environment, args.platform.as_deref())?;
+
+    fs_err::create_dir_all(&args.output_dir).ok();
+
+    for (env_name, env, platform) in env_platform {
+        if let Some(requirements) = render_requirements(&env, platform)
+            .with_context(|| format!("failed to export the environment '{env_name}'"))?
+        {
+            tracing::info!("Creating requirements for env: {env_name} platform: {platform}");
+            let target = args
+                .output_dir
+                .join(format!("{}_{}_requirements.txt", env_name, platform));
+            if !requirements.unhashed.is_empty() {
+                tracing::warn!(
+                    "{} can't be installed with `--require-hashes`, the packages {} are not pinned to a hash",
+                    target.display(),
+                    requirements.unhashed.join(", ")
+                );
+            }
+            fs_err::write(&target, requirements.content)
+                .into_diagnostic()
+                .with_context(|| {
+                    format!("failed to write requirements file: {}", target.display())
+                })?;
+        }
+
+        if args.conda_explicit_spec {
+            let conda_packages = env
+                .conda_packages(platform)
+                .into_iter()
+                .flatten()
+                .map(|package| match package {
+                    CondaPackageData::Binary(binary) => Ok(binary.clone()),
+                    CondaPackageData::Source(source) => Err(miette::miette!(
+                        "the conda source package '{}' is not supported in a conda explicit spec",
+                        source.package_record.name.as_source()
+                    )),
+                })
+                .collect::<miette::Result<Vec<_>>>()?;
+            conda_explicit_spec::render_conda_packages(
+                &args.output_dir,
+                &env_name,
+                &platform,
+                conda_packages,
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::Path, str::FromStr};
+
+    use rattler_lock::LockFile;
+
+    use super::*;
+
+    #[test]
+    fn test_render_requirements() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/data/mock-projects/test-project-export/pixi.lock");
+        let lockfile = LockFile::from_path(&path).unwrap();
+
+        for (env_name, env) in lockfile.environments() {
+            for platform in env.platforms() {
+                if let Some(requirements) = render_requirements(&env, platform).unwrap() {
+                    insta::assert_snapshot!(
+                        format!("test_render_requirements_{}_{}", env_name, platform),
+                        requirements.content
+                    );
+                    assert!(requirements.unhashed.is_empty());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_requirement_line_without_hash() {
+        let package = |location: &str, editable: bool| PypiPackageData {
+            name: "mypkg".parse().unwrap(),
+            version: pep440_rs::Version::from_str("0.1.0").unwrap(),
+            location: location.parse().unwrap(),
+            hash: None,
+            requires_dist: vec![],
+            requires_python: None,
+            editable,
+        };
+
+        assert_eq!(
+            requirement_line(&package("mypkg", true)).unwrap(),
+            ("-e ./mypkg".to_string(), false)
+        );
+        assert_eq!(
+            requirement_line(&package(
+                "git+https://github.com/mypkg.git?subdirectory=python&rev=v0.1#29932f3915935d773dc8d52c292cadd81c81071d",
+                false
+            ))
+            .unwrap(),
+            (
+                "mypkg @ git+https://github.com/mypkg.git@29932f3915935d773dc8d52c292cadd81c81071d#subdirectory=python"
+                    .to_string(),
+                false
+            )
+        );
+
+        // pip rejects md5 hashes in hash-checking mode.
+        let mut md5_only = package("https://pypi.org/mypkg-0.1.0-py3-none-any.whl", false);
+        md5_only.hash = Some(PackageHashes::Md5(
+            rattler_digest::parse_digest_from_hex::<rattler_digest::Md5>(
+                "b3af409bb8423187c75e6c7f5b683908",
+            )
+            .unwrap(),
+        ));
+        assert_eq!(
+            requirement_line(&md5_only).unwrap(),
+            ("mypkg==0.1.0".to_string(), false)
+        );
+
+        let mut hashed = package("https://pypi.org/mypkg-0.1.0-py3-none-any.whl", false);
+        hashed.hash = Some(PackageHashes::Sha256(
+            rattler_digest::parse_digest_from_hex::<rattler_digest::Sha256>(
+                "355216845c60bd96232cd8d8c40e8f9765cc86f46880e43a8fd22dc1a1a8cab1",
+            )
+            .unwrap(),
+        ));
+        let (line, is_hashed) = requirement_line(&hashed).unwrap();
+        assert!(is_hashed);
+        assert!(line.starts_with("mypkg==0.1.0 \\\n    --hash=sha256:"));
+    }
+}
@@ -0,0 +1,696 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use clap::{Parser, ValueEnum};
+use itertools::Itertools;
+use miette::{Context, IntoDiagnostic};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use pixi_consts::consts;
+use rattler_conda_types::Platform;
+use rattler_digest::{compute_bytes_digest, Sha256};
+use rattler_lock::{Environment, LockedPackageRef, PackageHashes, UrlOrPath};
+use serde_json::{json, Value};
+
+use crate::{
+    cli::cli_config::{PrefixUpdateConfig, ProjectConfig},
+    install_pypi::utils::strip_direct_scheme,
+    lock_file::{dependency_name, UpdateLockFileOptions},
+    Project,
+};
+
+/// The characters that are percent-encoded in the components of a package
+/// url.
+const PURL_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'.')
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'~');
+
+#[derive(Debug, Clone, Copy, Default, ValueEnum)]
+pub enum SbomFormat {
+    /// CycloneDX 1.5 json
+    #[default]
+    Cyclonedx,
+    /// SPDX 2.3 json
+    Spdx,
+}
+
+#[derive(Debug, Parser)]
+#[clap(arg_required_else_help = false)]
+pub struct Args {
+    #[clap(flatten)]
+    pub project_config: ProjectConfig,
+
+    /// Explicit path to write the SBOM to, defaults to stdout
+    pub output_path: Option<PathBuf>,
+
+    /// The format of the SBOM
+    #[arg(long, value_enum, default_value_t)]
+    pub format: SbomFormat,
+
+    /// The environment to describe. Defaults to the default environment.
+    #[arg(short, long)]
+    pub environment: Option<String>,
+
+    /// The platform to describe. Defaults to the current platform.
+    #[arg(short, long)]
+    pub platform: Option<Platform>,
+
+    #[clap(flatten)]
+    pub prefix_update_config: PrefixUpdateConfig,
+}
+
+/// The information about the document itself.
+struct SbomMetadata {
+    project_name: String,
+    project_version: Option<String>,
+    environment: String,
+    platform: Platform,
+    created: DateTime<Utc>,
+}
+
+impl SbomMetadata {
+    fn document_name(&self) -> String {
+        format!(
+            "{}-{}-{}",
+            self.project_name, self.environment, self.platform
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum HashAlgorithm {
+    Md5,
+    Sha256,
+}
+
+/// A locked package as it is described by the SBOM.
+struct SbomPackage {
+    /// The unique reference of the package in the document.
+    id: String,
+    name: String,
+    version: String,
+    /// The package url of the package itself.
+    purl: Option<String>,
+    /// The package urls of the PyPI distributions that a conda package
+    /// provides.
+    provided_purls: Vec<String>,
+    download_url: Option<String>,
+    license: Option<String>,
+    hashes: Vec<(HashAlgorithm, String)>,
+    /// The references of the locked packages this package depends on.
+    depends_on: Vec<String>,
+}
+
+/// Encodes a component of a package url.
+fn purl_encode(value: &str) -> String {
+    utf8_percent_encode(value, PURL_ENCODE_SET).to_string()
+}
+
+fn pypi_hashes(hash: Option<&PackageHashes>) -> Vec<(HashAlgorithm, String)> {
+    match hash {
+        Some(PackageHashes::Md5Sha256(md5, sha256)) => vec![
+            (HashAlgorithm::Sha256, format!("{sha256:x}")),
+            (HashAlgorithm::Md5, format!("{md5:x}")),
+        ],
+        Some(PackageHashes::Sha256(sha256)) => vec![(HashAlgorithm::Sha256, format!("{sha256:x}"))],
+        Some(PackageHashes::Md5(md5)) => vec![(HashAlgorithm::Md5, format!("{md5:x}"))],
+        None => Vec::new(),
+    }
+}
+
+/// Collects the locked packages of the environment for the platform.
+///
+/// Dependencies are resolved to the locked packages that provide them, a PyPI
+/// requirement can also be provided by a conda package with a matching PyPI
+/// purl.
+fn collect_packages(env: &Environment, platform: Platform) -> miette::Result<Vec<SbomPackage>> {
+    let Some(packages) = env.packages(platform) else {
+        miette::bail!("platform '{platform}' not found");
+    };
+    let packages = packages.collect_vec();
+
+    let mut conda_ids = HashMap::new();
+    let mut pypi_ids = HashMap::new();
+    for package in &packages {
+        match package {
+            LockedPackageRef::Conda(conda) => {
+                let record = conda.record();
+                let id = format!("conda:{}", record.name.as_normalized());
+                for purl in record.purls.iter().flatten() {
+                    if purl.package_type() == "pypi" {
+                        pypi_ids
+                            .entry(purl.name().to_lowercase().replace('_', "-"))
+                            .or_insert_with(|| id.clone());
+                    }
+                }
+                conda_ids.insert(record.name.as_normalized().to_string(), id);
+            }
+            LockedPackageRef::Pypi(pypi, _) => {
+                // A PyPI package takes precedence over a conda package that
+                // provides the same distribution.
+                pypi_ids.insert(pypi.name.to_string(), format!("pypi:{}", pypi.name));
+            }
+        }
+    }
+
+    let mut sbom_packages = packages
+        .into_iter()
+        .map(|package| match package {
+            LockedPackageRef::Conda(conda) => {
+                let record = conda.record();
+                let name = record.name.as_normalized().to_string();
+                let version = record.version.to_string();
+                let purl = format!(
+                    "pkg:conda/{}@{}?build={}&subdir={}",
+                    purl_encode(&name),
+                    purl_encode(&version),
+                    purl_encode(&record.build),
+                    purl_encode(&record.subdir)
+                );
+                let hashes = record
+                    .sha256
+                    .map(|sha256| (HashAlgorithm::Sha256, format!("{sha256:x}")))
+                    .into_iter()
+                    .chain(
+                        record
+                            .md5
+                            .map(|md5| (HashAlgorithm::Md5, format!("{md5:x}"))),
+                    )
+                    .collect();
+                let depends_on = record
+                    .depends
+                    .iter()
+                    .filter_map(|dependency| {
+                        let dependency_name = dependency_name(dependency)?;
+                        conda_ids.get(dependency_name.as_normalized()).cloned()
+                    })
+                    .unique()
+                    .collect();
+                SbomPackage {
+                    id: format!("conda:{name}"),
+                    purl: Some(purl),
+                    provided_purls: record
+                        .purls
+                        .iter()
+                        .flatten()
+                        .map(ToString::to_string)
+                        .sorted()
+                        .collect(),
+                    download_url: match conda.location() {
+                        UrlOrPath::Url(url) => Some(url.to_string()),
+                        UrlOrPath::Path(_) => None,
+                    },
+                    license: record.license.clone(),
+                    hashes,
+                    depends_on,
+                    name,
+                    version,
+                }
+            }
+            LockedPackageRef::Pypi(pypi, _) => {
+                let name = pypi.name.to_string();
+                let version = pypi.version.to_string();
+                let (purl, download_url) = match &pypi.location {
+                    // Local packages aren't published, so they can't be
+                    // identified by a package url.
+                    UrlOrPath::Path(_) => (None, None),
+                    UrlOrPath::Url(url) => (
+                        Some(format!(
+                            "pkg:pypi/{}@{}",
+                            purl_encode(&name),
+                            purl_encode(&version)
+                        )),
+                        Some(strip_direct_scheme(url).to_string()),
+                    ),
+                };
+                let depends_on = pypi
+                    .requires_dist
+                    .iter()
+                    .filter_map(|requirement| pypi_ids.get(&requirement.name.to_string()).cloned())
+                    .unique()
+                    .collect();
+                SbomPackage {
+                    id: format!("pypi:{name}"),
+                    purl,
+                    provided_purls: Vec::new(),
+                    download_url,
+                    license: None,
+                    hashes: pypi_hashes(pypi.hash.as_ref()),
+                    depends_on,
+                    name,
+                    version,
+                }
+            }
+        })
+        .collect_vec();
+    sbom_packages.sort_by(|a, b| a.id.cmp(&b.id));
+
+    Ok(sbom_packages)
+}
+
+/// Renders the packages as a CycloneDX 1.5 json document.
+fn render_cyclonedx(metadata: &SbomMetadata, packages: &[SbomPackage]) -> Value {
+    let components = packages
+        .iter()
+        .map(|package| {
+            let mut component = json!({
+                "type": "library",
+                "bom-ref": package.id,
+                "name": package.name,
+                "version": package.version,
+            });
+            if let Some(purl) = &package.purl {
+                component["purl"] = json!(purl);
+            }
+            if !package.hashes.is_empty() {
+                component["hashes"] = package
+                    .hashes
+                    .iter()
+                    .map(|(algorithm, hash)| {
+                        let algorithm = match algorithm {
+                            HashAlgorithm::Md5 => "MD5",
+                            HashAlgorithm::Sha256 => "SHA-256",
+                        };
+                        json!({ "alg": algorithm, "content": hash })
+                    })
+                    .collect();
+            }
+            if let Some(license) = &package.license {
+                component["licenses"] = if is_spdx_expression(license) {
+                    json!([{ "expression": license }])
+                } else {
+                    json!([{ "license": { "name": license } }])
+                };
+            }
+            if let Some(url) = &package.download_url {
+                component["externalReferences"] = json!([{ "type": "distribution", "url": url }]);
+            }
+            if !package.provided_purls.is_empty() {
+                component["properties"] = package
+                    .provided_purls
+                    .iter()
+                    .map(|purl| json!({ "name": "pixi:provides-purl", "value": purl }))
+                    .collect();
+            }
+            component
+        })
+        .collect_vec();
+
+    let dependencies = std::iter::once(json!({
+        "ref": metadata.project_name,
+        "dependsOn": packages.iter().map(|package| &package.id).collect_vec(),
+    }))
+    .chain(packages.iter().map(|package| {
+        json!({
+            "ref": package.id,
+            "dependsOn": package.depends_on,
+        })
+    }))
+    .collect_vec();
+
+    let mut project = json!({
+        "type": "application",
+        "bom-ref": metadata.project_name,
+        "name": metadata.project_name,
+    });
+    if let Some(version) = &metadata.project_version {
+        project["version"] = json!(version);
+    }
+
+    json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": "1.5",
+        "version": 1,
+        "metadata": {
+            "timestamp": metadata.created.to_rfc3339_opts(SecondsFormat::Secs, true),
+            "tools": {
+                "components": [{
+                    "type": "application",
+                    "name": "pixi",
+                    "version": consts::PIXI_VERSION,
+                }],
+            },
+            "component": project,
+            "properties": [
+                { "name": "pixi:environment", "value": metadata.environment },
+                { "name": "pixi:platform", "value": metadata.platform.as_str() },
+            ],
+        },
+        "components": components,
+        "dependencies": dependencies,
+    })
+}
+
+/// Returns an SPDX identifier for a package reference, which may only contain
+/// letters, numbers, `.` and `-`.
+fn spdx_id(id: &str) -> String {
+    let id = id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect::<String>();
+    format!("SPDXRef-Package-{id}")
+}
+
+/// Returns true if the license is a valid SPDX license expression.
+fn is_spdx_expression(license: &str) -> bool {
+    spdx::Expression::parse(license).is_ok()
+}
+
+/// Returns the SPDX license references of the licenses of the packages that
+/// aren't SPDX license expressions, like `BSD` or `Apache 2.0`, by their
+/// text.
+fn spdx_license_refs(packages: &[SbomPackage]) -> BTreeMap<&str, String> {
+    let mut refs = BTreeMap::new();
+    for license in packages
+        .iter()
+        .filter_map(|package| package.license.as_deref())
+        .filter(|license| !is_spdx_expression(license))
+        .unique()
+        .sorted()
+    {
+        let id = license
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '.' {
+                    c
+                } else {
+                    '-'
+                }
+            })
+            .collect::<String>();
+        let id = match id.trim_matches('-') {
+            "" => "LicenseRef-unknown".to_string(),
+            id => format!("LicenseRef-{id}"),
+        };
+        // Different texts can map to the same identifier.
+        let id = std::iter::once(id.clone())
+            .chain((2..).map(|n| format!("{id}-{n}")))
+            .find(|id| !refs.values().any(|existing| existing == id))
+            .expect("there is an unused identifier");
+        refs.insert(license, id);
+    }
+    refs
+}
+
+/// Renders the packages as an SPDX 2.3 json document.
+fn render_spdx(metadata: &SbomMetadata, packages: &[SbomPackage]) -> Value {
+    let license_refs = spdx_license_refs(packages);
+
+    let project_id = "SPDXRef-Project".to_string();
+    let mut project = json!({
+        "SPDXID": project_id,
+        "name": metadata.project_name,
+        "downloadLocation": "NOASSERTION",
+        "filesAnalyzed": false,
+        "licenseConcluded": "NOASSERTION",
+        "licenseDeclared": "NOASSERTION",
+    });
+    if let Some(version) = &metadata.project_version {
+        project["versionInfo"] = json!(version);
+    }
+
+    let spdx_packages = std::iter::once(project)
+        .chain(packages.iter().map(|package| {
+            let checksums = package
+                .hashes
+                .iter()
+                .map(|(algorithm, hash)| {
+                    let algorithm = match algorithm {
+                        HashAlgorithm::Md5 => "MD5",
+                        HashAlgorithm::Sha256 => "SHA256",
+                    };
+                    json!({ "algorithm": algorithm, "checksumValue": hash })
+                })
+                .collect_vec();
+            let external_refs = package
+                .purl
+                .iter()
+                .chain(&package.provided_purls)
+                .map(|purl| {
+                    json!({
+                        "referenceCategory": "PACKAGE-MANAGER",
+                        "referenceType": "purl",
+                        "referenceLocator": purl,
+                    })
+                })
+                .collect_vec();
+            json!({
+                "SPDXID": spdx_id(&package.id),
+                "name": package.name,
+                "versionInfo": package.version,
+                "downloadLocation": package.download_url.as_deref().unwrap_or("NOASSERTION"),
+                "filesAnalyzed": false,
+                "licenseConcluded": "NOASSERTION",
+                "licenseDeclared": match package.license.as_deref() {
+                    Some(license) => license_refs.get(license).map_or(license, String::as_str),
+                    None => "NOASSERTION",
+                },
+                "checksums": checksums,
+                "externalRefs": external_refs,
+            })
+        }))
+        .collect_vec();
+
+    let relationships = std::iter::once(json!({
+        "spdxElementId": "SPDXRef-DOCUMENT",
+        "relationshipType": "DESCRIBES",
+        "relatedSpdxElement": project_id,
+    }))
+    .chain(packages.iter().map(|package| {
+        json!({
+            "spdxElementId": project_id,
+            "relationshipType": "CONTAINS",
+            "relatedSpdxElement": spdx_id(&package.id),
+        })
+    }))
+    .chain(packages.iter().flat_map(|package| {
+        package.depends_on.iter().map(|dependency| {
+            json!({
+                "spdxElementId": spdx_id(&package.id),
+                "relationshipType": "DEPENDS_ON",
+                "relatedSpdxElement": spdx_id(dependency),
+            })
+        })
+    }))
+    .collect_vec();
+
+    // The namespace has to be unique for every document, so it is derived from
+    // the packages it describes.
+    let digest = compute_bytes_digest::<Sha256>(
+        packages
+            .iter()
+            .map(|package| format!("{}@{}", package.id, package.version))
+            .join("\n"),
+    );
+    let document_name = metadata.document_name();
+
+    json!({
+        "spdxVersion": "SPDX-2.3",
+        "dataLicense": "CC0-1.0",
+        "SPDXID": "SPDXRef-DOCUMENT",
+        "name": document_name,
+        "documentNamespace": format!("https://pixi.sh/spdx/{document_name}-{digest:x}"),
+        "creationInfo": {
+            "created": metadata.created.to_rfc3339_opts(SecondsFormat::Secs, true),
+            "creators": [format!("Tool: pixi-{}", consts::PIXI_VERSION)],
+        },
+        "packages": spdx_packages,
+        "hasExtractedLicensingInfos": license_refs
+            .iter()
+            .map(|(license, id)| {
+                json!({ "licenseId": id, "name": license, "extractedText": license })
+            })
+            .collect_vec(),
+        "relationships": relationships,
+    })
+}
+
+/// Renders the SBOM of the locked environment for the platform.
+fn render_sbom(
+    format: SbomFormat,
+    metadata: &SbomMetadata,
+    env: &Environment,
+) -> miette::Result<String> {
+    let packages = collect_packages(env, metadata.platform)?;
+    let document = match format {
+        SbomFormat::Cyclonedx => render_cyclonedx(metadata, &packages),
+        SbomFormat::Spdx => render_spdx(metadata, &packages),
+    };
+    Ok(serde_json::to_string_pretty(&document).expect("the SBOM can be serialized"))
+}
+
+pub async fn execute(args: Args) -> miette::Result<()> {
+    let project = Project::load_or_else_discover(args.project_config.manifest_path.as_deref())?
+        .with_cli_config(args.prefix_update_config.config.clone());
+    let environment = project.environment_from_name_or_env_var(args.environment)?;
+    let platform = args.platform.unwrap_or_else(|| environment.best_platform());
+
+    let lock_file = project
+        .update_lock_file(UpdateLockFileOptions {
+            lock_file_usage: args.prefix_update_config.lock_file_usage(),
+            no_install: args.prefix_update_config.no_install,
+            max_concurrent_solves: project.config().max_concurrent_solves(),
+        })
+        .await?
+        .lock_file;
+    let Some(locked_environment) = lock_file.environment(environment.name().as_str()) else {
+        miette::bail!(
+            "environment '{}' is not part of the lock file",
+            environment.name()
+        );
+    };
+
+    let metadata = SbomMetadata {
+        project_name: project.name().to_string(),
+        project_version: project.version().as_ref().map(ToString::to_string),
+        environment: environment.name().to_string(),
+        platform,
+        created: Utc::now(),
+    };
+    let sbom = render_sbom(args.format, &metadata, &locked_environment)
+        .with_context(|| format!("failed to export the environment '{}'", environment.name()))?;
+
+    if let Some(output_path) = args.output_path {
+        fs_err::write(&output_path, sbom)
+            .into_diagnostic()
+            .with_context(|| format!("failed to write the SBOM: {}", output_path.display()))?;
+    } else {
+        println!("{sbom}");
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use rattler_lock::LockFile;
+
+    use super::*;
+
+    fn metadata(platform: Platform) -> SbomMetadata {
+        SbomMetadata {
+            project_name: "test-project-export".to_string(),
+            project_version: Some("0.1.0".to_string()),
+            environment: "default".to_string(),
+            platform,
+            created: DateTime::from_timestamp(0, 0).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_render_sbom() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/data/mock-projects/test-project-export/pixi.lock");
+        let lockfile = LockFile::from_path(&path).unwrap();
+        let env = lockfile.environment("default").unwrap();
+        let metadata = metadata(Platform::Linux64);
+        let packages = collect_packages(&env, Platform::Linux64).unwrap();
+
+        let cyclonedx = render_cyclonedx(&metadata, &packages);
+        let components = cyclonedx["components"].as_array().unwrap();
+        assert_eq!(components.len(), packages.len());
+        let python = components
+            .iter()
+            .find(|component| component["bom-ref"] == "conda:python")
+            .unwrap();
+        assert!(python["purl"]
+            .as_str()
+            .unwrap()
+            .starts_with("pkg:conda/python@3.12.5?build="));
+        assert!(python["hashes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|hash| hash["alg"] == "SHA-256"));
+        let pypi = components
+            .iter()
+            .find(|component| component["bom-ref"].as_str().unwrap().starts_with("pypi:"))
+            .unwrap();
+        assert!(pypi["purl"].as_str().unwrap().starts_with("pkg:pypi/"));
+
+        let spdx = render_spdx(&metadata, &packages);
+        assert_eq!(
+            spdx["packages"].as_array().unwrap().len(),
+            packages.len() + 1
+        );
+        assert!(spdx["relationships"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(
+                |relationship| relationship["relationshipType"] == "DEPENDS_ON"
+                    && relationship["spdxElementId"] == "SPDXRef-Package-conda-python"
+            ));
+    }
+
+    #[test]
+    fn test_spdx_licenses() {
+        let package = |name: &str, license: Option<&str>| SbomPackage {
+            id: format!("conda:{name}"),
+            name: name.to_string(),
+            version: "1.0".to_string(),
+            purl: None,
+            provided_purls: Vec::new(),
+            download_url: None,
+            license: license.map(ToString::to_string),
+            hashes: Vec::new(),
+            depends_on: Vec::new(),
+        };
+        let packages = [
+            package("a", Some("MIT OR Apache-2.0")),
+            package("b", Some("Apache 2.0")),
+            package("c", Some("Apache_2.0")),
+            package("d", Some("BSD")),
+            package("e", None),
+        ];
+
+        let spdx = render_spdx(&metadata(Platform::Linux64), &packages);
+        let licenses = spdx["packages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .skip(1)
+            .map(|package| package["licenseDeclared"].as_str().unwrap())
+            .collect_vec();
+        assert_eq!(
+            licenses,
+            [
+                "MIT OR Apache-2.0",
+                "LicenseRef-Apache-2.0",
+                "LicenseRef-Apache-2.0-2",
+                "LicenseRef-BSD",
+                "NOASSERTION"
+            ]
+        );
+        assert_eq!(
+            spdx["hasExtractedLicensingInfos"][0],
+            json!({
+                "licenseId": "LicenseRef-Apache-2.0",
+                "name": "Apache 2.0",
+                "extractedText": "Apache 2.0",
+            })
+        );
+
+        let cyclonedx = render_cyclonedx(&metadata(Platform::Linux64), &packages);
+        assert_eq!(
+            cyclonedx["components"][0]["licenses"],
+            json!([{ "expression": "MIT OR Apache-2.0" }])
+        );
+        assert_eq!(
+            cyclonedx["components"][1]["licenses"],
+            json!([{ "license": { "name": "Apache 2.0" } }])
+        );
+    }
+}
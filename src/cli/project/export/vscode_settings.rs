@@ -0,0 +1,577 @@
+use std::{
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use clap::Parser;
+use itertools::Itertools;
+use miette::{Context, IntoDiagnostic};
+use rattler::install::PythonInfo;
+use rattler_conda_types::{PackageRecord, Platform};
+use rattler_lock::LockedPackageRef;
+
+use crate::{
+    cli::cli_config::{PrefixUpdateConfig, ProjectConfig},
+    lock_file::UpdateLockFileOptions,
+    Project,
+};
+
+#[derive(Debug, Parser)]
+#[clap(arg_required_else_help = false)]
+pub struct Args {
+    #[clap(flatten)]
+    pub project_config: ProjectConfig,
+
+    /// The settings file to update. Defaults to the `.vscode/settings.json` of
+    /// the project.
+    pub output_path: Option<PathBuf>,
+
+    /// The environment that provides the interpreter and compiler. Defaults to
+    /// the default environment.
+    #[arg(short, long)]
+    pub environment: Option<String>,
+
+    #[clap(flatten)]
+    pub prefix_update_config: PrefixUpdateConfig,
+}
+
+/// Returns the python interpreter that the locked python package installs in
+/// the prefix.
+fn python_interpreter(
+    records: &[&PackageRecord],
+    prefix: &Path,
+    platform: Platform,
+) -> miette::Result<Option<PathBuf>> {
+    let Some(python_record) = records
+        .iter()
+        .find(|record| record.name.as_normalized() == "python")
+    else {
+        return Ok(None);
+    };
+    let python_info = PythonInfo::from_python_record(python_record, platform).into_diagnostic()?;
+    Ok(Some(prefix.join(python_info.path)))
+}
+
+/// Returns the target triplet of the GCC compilers of conda-forge.
+fn gcc_triplet(platform: Platform) -> Option<&'static str> {
+    match platform {
+        Platform::Linux64 => Some("x86_64-conda-linux-gnu"),
+        Platform::LinuxAarch64 => Some("aarch64-conda-linux-gnu"),
+        Platform::LinuxPpc64le => Some("powerpc64le-conda-linux-gnu"),
+        _ => None,
+    }
+}
+
+/// Returns the C++, or otherwise the C compiler, that the locked compiler
+/// packages install in the prefix. Compilers outside of the prefix, like the
+/// one of Visual Studio, are not known.
+fn compiler(records: &[&PackageRecord], prefix: &Path, platform: Platform) -> Option<PathBuf> {
+    let has_package = |name: &str| {
+        records
+            .iter()
+            .any(|record| record.name.as_normalized() == name)
+    };
+    let bin_dir = if platform.is_windows() {
+        prefix.join("Library").join("bin")
+    } else {
+        prefix.join("bin")
+    };
+    let executable = |name: &str| {
+        if platform.is_windows() {
+            bin_dir.join(format!("{name}.exe"))
+        } else {
+            bin_dir.join(name)
+        }
+    };
+    let gcc = |package: &str, compiler: &str| {
+        let triplet = gcc_triplet(platform)?;
+        has_package(&format!("{package}_{platform}"))
+            .then(|| executable(&format!("{triplet}-{compiler}")))
+    };
+
+    gcc("gxx_impl", "c++")
+        .or_else(|| has_package("clangxx").then(|| executable("clang++")))
+        .or_else(|| gcc("gcc_impl", "cc"))
+        .or_else(|| has_package("clang").then(|| executable("clang")))
+}
+
+/// Removes the comments and trailing commas that VS Code allows in its JSON
+/// files, the strings are kept as they are.
+fn strip_jsonc(contents: &str) -> String {
+    // Replace the comments with whitespace
+    let mut json = String::with_capacity(contents.len());
+    let mut chars = contents.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                json.push(c);
+                while let Some(c) = chars.next() {
+                    json.push(c);
+                    match c {
+                        '\\' => json.extend(chars.next()),
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        json.push(c);
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+                json.push(' ');
+            }
+            _ => json.push(c),
+        }
+    }
+
+    // Remove the commas that are followed by the end of an object or array
+    let mut stripped = String::with_capacity(json.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    for (index, c) in json.char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+        } else if c == '"' {
+            in_string = true;
+        } else if c == ',' && json[index + 1..].trim_start().starts_with(['}', ']']) {
+            continue;
+        }
+        stripped.push(c);
+    }
+    stripped
+}
+
+/// A member of the top-level object of a JSONC document.
+struct JsoncMember {
+    key: String,
+    /// The start of the key.
+    key_start: usize,
+    /// The value, without the whitespace and comments around it.
+    value: Range<usize>,
+}
+
+/// The top-level object of a JSONC document.
+struct JsoncObject {
+    members: Vec<JsoncMember>,
+    /// The end of the last token before the closing brace of the object.
+    end: usize,
+    /// Whether the last member is followed by a comma.
+    trailing_comma: bool,
+}
+
+/// Returns the end of the string that starts at the given index.
+fn jsonc_string_end(contents: &str, start: usize) -> usize {
+    let mut chars = contents[start + 1..].char_indices();
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '"' => return start + 1 + index + 1,
+            _ => {}
+        }
+    }
+    contents.len()
+}
+
+/// Finds the members of the top-level object of a JSONC document that is
+/// known to be a valid object, skipping the comments.
+fn parse_jsonc_object(contents: &str) -> miette::Result<JsoncObject> {
+    let mut members = Vec::new();
+    let mut depth = 0usize;
+    let mut key: Option<(String, usize)> = None;
+    let mut value_start = None;
+    let mut last_end = 0;
+    let mut last_char = ' ';
+    let mut index = 0;
+    while index < contents.len() {
+        let rest = &contents[index..];
+        let c = rest.chars().next().expect("the rest is not empty");
+        if c.is_whitespace() {
+            index += c.len_utf8();
+            continue;
+        }
+        if rest.starts_with("//") {
+            index += rest.find('\n').unwrap_or(rest.len());
+            continue;
+        }
+        if rest.starts_with("/*") {
+            index += rest[2..].find("*/").map_or(rest.len(), |end| end + 4);
+            continue;
+        }
+
+        let end = if c == '"' {
+            jsonc_string_end(contents, index)
+        } else {
+            index + c.len_utf8()
+        };
+        match c {
+            '"' if depth == 1 && key.is_none() => {
+                let name = serde_json::from_str(&contents[index..end]).into_diagnostic()?;
+                key = Some((name, index));
+            }
+            '{' | '[' if depth == 0 => depth = 1,
+            '{' | '[' => {
+                if depth == 1 && key.is_some() {
+                    value_start.get_or_insert(index);
+                }
+                depth += 1;
+            }
+            '}' | ']' | ',' if depth == 1 => {
+                if let (Some((key, key_start)), Some(start)) = (key.take(), value_start.take()) {
+                    members.push(JsoncMember {
+                        key,
+                        key_start,
+                        value: start..last_end,
+                    });
+                }
+                if c != ',' {
+                    return Ok(JsoncObject {
+                        members,
+                        end: last_end,
+                        trailing_comma: last_char == ',',
+                    });
+                }
+            }
+            '}' | ']' => depth -= 1,
+            ':' if depth == 1 => {}
+            _ => {
+                if depth == 1 && key.is_some() {
+                    value_start.get_or_insert(index);
+                }
+            }
+        }
+        last_end = end;
+        last_char = c;
+        index = end;
+    }
+    miette::bail!("the settings are not a JSON object")
+}
+
+/// Merges the settings into the existing settings file, keeping all other
+/// settings and the comments. The values of existing settings are replaced in
+/// place, new settings are added to the end of the object.
+fn merge_settings(
+    existing: Option<&str>,
+    settings: serde_json::Map<String, serde_json::Value>,
+) -> miette::Result<String> {
+    let Some(existing) = existing.filter(|existing| !existing.trim().is_empty()) else {
+        let mut json = serde_json::to_string_pretty(&settings).into_diagnostic()?;
+        json.push('\n');
+        return Ok(json);
+    };
+
+    // Make sure the file is valid before it's edited.
+    if !serde_json::from_str::<serde_json::Value>(&strip_jsonc(existing))
+        .into_diagnostic()?
+        .is_object()
+    {
+        miette::bail!("the settings are not a JSON object");
+    }
+    let object = parse_jsonc_object(existing)?;
+
+    let mut edits = Vec::new();
+    let mut new_members = Vec::new();
+    for (key, value) in settings {
+        let value = serde_json::to_string(&value).into_diagnostic()?;
+        let existing_members = object
+            .members
+            .iter()
+            .filter(|member| member.key == key)
+            .collect_vec();
+        if existing_members.is_empty() {
+            let key = serde_json::to_string(&key).into_diagnostic()?;
+            new_members.push(format!("{key}: {value}"));
+        }
+        for member in existing_members {
+            edits.push((member.value.clone(), value.clone()));
+        }
+    }
+
+    if !new_members.is_empty() {
+        // Indent the new settings like the first existing setting.
+        let indent = object
+            .members
+            .first()
+            .and_then(|member| {
+                let line_start = existing[..member.key_start]
+                    .rfind('\n')
+                    .map_or(0, |index| index + 1);
+                let indent = &existing[line_start..member.key_start];
+                indent.chars().all(char::is_whitespace).then_some(indent)
+            })
+            .unwrap_or("    ");
+        let mut insertion = String::new();
+        if !object.members.is_empty() && !object.trailing_comma {
+            insertion.push(',');
+        }
+        insertion.push_str(
+            &new_members
+                .iter()
+                .map(|member| format!("\n{indent}{member}"))
+                .join(","),
+        );
+        if object.members.is_empty() {
+            insertion.push('\n');
+        }
+        edits.push((object.end..object.end, insertion));
+    }
+
+    let mut merged = existing.to_string();
+    for (range, replacement) in edits
+        .into_iter()
+        .sorted_by_key(|(range, _)| range.start)
+        .rev()
+    {
+        merged.replace_range(range, &replacement);
+    }
+    Ok(merged)
+}
+
+pub async fn execute(args: Args) -> miette::Result<()> {
+    let project = Project::load_or_else_discover(args.project_config.manifest_path.as_deref())?
+        .with_cli_config(args.prefix_update_config.config.clone());
+    let environment = project.environment_from_name_or_env_var(args.environment)?;
+
+    // The paths only depend on the locked packages, the environment doesn't
+    // have to be installed.
+    let lock_file = project
+        .update_lock_file(UpdateLockFileOptions {
+            lock_file_usage: args.prefix_update_config.lock_file_usage(),
+            no_install: true,
+            max_concurrent_solves: project.config().max_concurrent_solves(),
+        })
+        .await?;
+    let platform = environment.best_platform();
+    let locked_packages = lock_file
+        .lock_file
+        .environment(environment.name().as_str())
+        .and_then(|env| env.packages(platform).map(Vec::from_iter))
+        .unwrap_or_default();
+    let records = locked_packages
+        .iter()
+        .filter_map(|package| match package {
+            LockedPackageRef::Conda(package) => Some(package.record()),
+            LockedPackageRef::Pypi(..) => None,
+        })
+        .collect_vec();
+    let prefix = environment.dir();
+
+    let mut settings = serde_json::Map::new();
+    if let Some(python) = python_interpreter(&records, &prefix, platform)? {
+        settings.insert(
+            "python.defaultInterpreterPath".to_string(),
+            python.display().to_string().into(),
+        );
+    }
+    if let Some(compiler) = compiler(&records, &prefix, platform) {
+        settings.insert(
+            "C_Cpp.default.compilerPath".to_string(),
+            compiler.display().to_string().into(),
+        );
+    }
+    if settings.is_empty() {
+        miette::bail!(
+            "the environment '{}' contains neither python nor a C/C++ compiler",
+            environment.name()
+        );
+    }
+
+    let output_path = args
+        .output_path
+        .unwrap_or_else(|| project.root().join(".vscode").join("settings.json"));
+    let existing = if output_path.is_file() {
+        Some(fs_err::read_to_string(&output_path).into_diagnostic()?)
+    } else {
+        None
+    };
+    let settings = merge_settings(existing.as_deref(), settings)
+        .wrap_err_with(|| format!("failed to update '{}'", output_path.display()))?;
+
+    if let Some(parent) = output_path.parent() {
+        fs_err::create_dir_all(parent).into_diagnostic()?;
+    }
+    fs_err::write(&output_path, settings).into_diagnostic()?;
+
+    eprintln!(
+        "{}Updated {}",
+        console::style(console::Emoji("✔ ", "")).green(),
+        output_path.display()
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use rattler_conda_types::{PackageName, Version};
+
+    use super::*;
+
+    fn record(name: &str, version: &str) -> PackageRecord {
+        PackageRecord::new(
+            PackageName::new_unchecked(name),
+            Version::from_str(version).unwrap(),
+            "0".to_string(),
+        )
+    }
+
+    fn parse(settings: &str) -> serde_json::Value {
+        serde_json::from_str(&strip_jsonc(settings)).unwrap()
+    }
+
+    #[test]
+    fn test_merge_settings() {
+        let mut settings = serde_json::Map::new();
+        settings.insert(
+            "python.defaultInterpreterPath".to_string(),
+            "/project/.pixi/envs/default/bin/python".into(),
+        );
+        let merged = merge_settings(
+            Some(r#"{ "editor.formatOnSave": true, "python.defaultInterpreterPath": "python" }"#),
+            settings.clone(),
+        )
+        .unwrap();
+        let merged = parse(&merged);
+        assert_eq!(merged["editor.formatOnSave"], true);
+        assert_eq!(
+            merged["python.defaultInterpreterPath"],
+            "/project/.pixi/envs/default/bin/python"
+        );
+
+        let merged = merge_settings(
+            Some(
+                r#"{
+                    // The formatter of the project
+                    "editor.formatOnSave": true, /* "files.trimTrailingWhitespace": true, */
+                    "search.exclude": { "**/.pixi": true, "a//b/*c*/": true, },
+                    "files.encoding": "utf8\\",
+                }"#,
+            ),
+            settings.clone(),
+        )
+        .unwrap();
+        let parsed = parse(&merged);
+        assert_eq!(parsed["editor.formatOnSave"], true);
+        assert_eq!(parsed["search.exclude"]["a//b/*c*/"], true);
+        assert_eq!(parsed["files.encoding"], "utf8\\");
+        assert!(parsed.get("files.trimTrailingWhitespace").is_none());
+        assert_eq!(
+            parsed["python.defaultInterpreterPath"],
+            "/project/.pixi/envs/default/bin/python"
+        );
+
+        // The comments are kept.
+        assert!(merged.contains("// The formatter of the project\n"));
+        assert!(merged.contains("/* \"files.trimTrailingWhitespace\": true, */"));
+
+        assert!(merge_settings(Some("[]"), settings.clone()).is_err());
+        assert!(merge_settings(None, settings).is_ok());
+    }
+
+    #[test]
+    fn test_merge_settings_keeps_formatting() {
+        let mut settings = serde_json::Map::new();
+        settings.insert("python.defaultInterpreterPath".to_string(), "python".into());
+        settings.insert("C_Cpp.default.compilerPath".to_string(), "c++".into());
+
+        // An existing setting is replaced in place, a new one is appended
+        // with the indentation of the file.
+        let existing = "{\n  // Use the environment\n  \"python.defaultInterpreterPath\": /* old */ \"/usr/bin/python\", // system\n  \"editor.rulers\": [80]\n}\n";
+        assert_eq!(
+            merge_settings(Some(existing), settings.clone()).unwrap(),
+            "{\n  // Use the environment\n  \"python.defaultInterpreterPath\": /* old */ \"python\", // system\n  \"editor.rulers\": [80],\n  \"C_Cpp.default.compilerPath\": \"c++\"\n}\n"
+        );
+
+        // A trailing comma is reused.
+        let existing = "{\n    \"editor.rulers\": [80],\n}";
+        let merged = merge_settings(Some(existing), settings.clone()).unwrap();
+        assert!(merged.starts_with("{\n    \"editor.rulers\": [80],\n    \""));
+        assert_eq!(parse(&merged)["C_Cpp.default.compilerPath"], "c++");
+
+        // An empty object with a comment.
+        let existing = "{\n    // nothing yet\n}";
+        let merged = merge_settings(Some(existing), settings).unwrap();
+        assert!(merged.starts_with("{\n    \""));
+        assert!(merged.ends_with("\n    // nothing yet\n}"));
+        assert_eq!(parse(&merged)["python.defaultInterpreterPath"], "python");
+    }
+
+    #[test]
+    fn test_python_interpreter() {
+        let prefix = Path::new("/project/.pixi/envs/default");
+        let records = [record("python", "3.12.1"), record("numpy", "2.0.0")];
+        let records = records.iter().collect_vec();
+        assert_eq!(
+            python_interpreter(&records, prefix, Platform::Linux64).unwrap(),
+            Some(prefix.join("bin/python3.12"))
+        );
+        assert_eq!(
+            python_interpreter(&records, prefix, Platform::Win64).unwrap(),
+            Some(prefix.join("python.exe"))
+        );
+        assert_eq!(
+            python_interpreter(&records[1..], prefix, Platform::Linux64).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_compiler() {
+        let prefix = Path::new("/project/.pixi/envs/default");
+        let gcc = [record("gcc_impl_linux-64", "13.2.0")];
+        let gxx = [
+            record("gcc_impl_linux-64", "13.2.0"),
+            record("gxx_impl_linux-64", "13.2.0"),
+        ];
+        let clang = [record("clang", "18.1.0"), record("clangxx", "18.1.0")];
+        let compiler_of = |records: &[PackageRecord], platform| {
+            compiler(&records.iter().collect_vec(), prefix, platform)
+        };
+
+        assert_eq!(
+            compiler_of(&gxx, Platform::Linux64),
+            Some(prefix.join("bin/x86_64-conda-linux-gnu-c++"))
+        );
+        assert_eq!(
+            compiler_of(&gcc, Platform::Linux64),
+            Some(prefix.join("bin/x86_64-conda-linux-gnu-cc"))
+        );
+        assert_eq!(
+            compiler_of(&clang, Platform::OsxArm64),
+            Some(prefix.join("bin/clang++"))
+        );
+        assert_eq!(
+            compiler_of(&clang, Platform::Win64),
+            Some(prefix.join("Library/bin/clang++.exe"))
+        );
+        assert_eq!(
+            compiler_of(&clang[..1], Platform::Linux64),
+            Some(prefix.join("bin/clang"))
+        );
+        assert_eq!(
+            compiler_of(&[record("python", "3.12.1")], Platform::Linux64),
+            None
+        );
+    }
+}
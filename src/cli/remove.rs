@@ -1,12 +1,17 @@
 use clap::Parser;
+use fancy_display::FancyDisplay;
+use itertools::Itertools;
 use miette::Context;
+use pixi_consts::consts;
+use pixi_manifest::EnvironmentName;
 
+use crate::diff::LockFileDiff;
 use crate::environment::get_update_lock_file_and_prefix;
 use crate::Project;
 use crate::{DependencyType, UpdateLockFileOptions};
 
 use crate::cli::cli_config::{DependencyConfig, PrefixUpdateConfig, ProjectConfig};
-use crate::lock_file::UpdateMode;
+use crate::lock_file::{load_lock_file, prune_lock_file, write_lock_file, UpdateMode};
 
 use super::has_specs::HasSpecs;
 
@@ -27,6 +32,12 @@ pub struct Args {
 
     #[clap(flatten)]
     pub prefix_update_config: PrefixUpdateConfig,
+
+    /// Drop the packages that are no longer required by any of the remaining
+    /// dependencies from the lock-file, instead of re-solving the affected
+    /// environments.
+    #[clap(long)]
+    pub prune: bool,
 }
 
 pub async fn execute(args: Args) -> miette::Result<()> {
@@ -56,6 +67,21 @@ pub async fn execute(args: Args) -> miette::Result<()> {
                     ))?;
             }
         }
+        DependencyType::RDependency => {
+            for name in dependency_config.specs()?.keys() {
+                project
+                    .manifest
+                    .remove_r_dependency(
+                        name.as_source(),
+                        &dependency_config.platforms,
+                        &dependency_config.feature,
+                    )
+                    .wrap_err(format!(
+                        "failed to remove R dependency: '{}'",
+                        name.as_source()
+                    ))?;
+            }
+        }
         DependencyType::CondaDependency(spec_type) => {
             for name in dependency_config.specs()?.keys() {
                 project
@@ -76,10 +102,16 @@ pub async fn execute(args: Args) -> miette::Result<()> {
 
     project.save()?;
 
+    let original_lock_file = load_lock_file(&project).await?;
+    if args.prune && !prefix_update_config.no_lockfile_update {
+        write_lock_file(&project, &prune_lock_file(&project, &original_lock_file))
+            .context("failed to write the pruned lock-file")?;
+    }
+
     // TODO: update all environments touched by this feature defined.
     // updating prefix after removing from toml
     if !prefix_update_config.no_lockfile_update {
-        get_update_lock_file_and_prefix(
+        let (lock_file, _) = get_update_lock_file_and_prefix(
             &project.default_environment(),
             UpdateMode::Revalidate,
             UpdateLockFileOptions {
@@ -89,6 +121,9 @@ pub async fn execute(args: Args) -> miette::Result<()> {
             },
         )
         .await?;
+
+        let diff = LockFileDiff::from_lock_files(&original_lock_file, &lock_file.lock_file);
+        print_removed_packages(&diff, prefix_update_config.no_install);
     }
 
     dependency_config.display_success("Removed", Default::default());
@@ -96,3 +131,39 @@ pub async fn execute(args: Args) -> miette::Result<()> {
     Project::warn_on_discovered_from_env(project_config.manifest_path.as_deref());
     Ok(())
 }
+
+/// Reports the packages that disappeared from the lock-file of the default
+/// environment on the current platform, these are removed from the prefix
+/// on the next install.
+fn print_removed_packages(diff: &LockFileDiff, no_install: bool) {
+    let Some(removed) = diff
+        .environment
+        .get(consts::DEFAULT_ENVIRONMENT_NAME)
+        .and_then(|platforms| platforms.get(&rattler_conda_types::Platform::current()))
+        .map(|packages| &packages.removed)
+        .filter(|removed| !removed.is_empty())
+    else {
+        return;
+    };
+
+    eprintln!(
+        "{}{} {} no longer required and {} removed from the {} environment{}:",
+        console::style(console::Emoji("✔ ", "")).green(),
+        removed.len(),
+        if removed.len() == 1 {
+            "package is"
+        } else {
+            "packages are"
+        },
+        if no_install { "will be" } else { "were" },
+        EnvironmentName::Default.fancy_display(),
+        if no_install {
+            " on the next install"
+        } else {
+            ""
+        },
+    );
+    for package in removed.iter().sorted_by_key(|p| p.name()) {
+        eprintln!("    {} {}", console::style("-").red(), package.name());
+    }
+}
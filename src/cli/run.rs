@@ -206,13 +206,16 @@ pub async fn execute(args: Args) -> miette::Result<()> {
                         args.prefix_update_config.update_mode(),
                     )
                     .await?;
+                lock_file
+                    .sync_external_managers(&executable_task.run_environment)
+                    .await?;
 
                 let command_env = get_task_env(
                     &executable_task.run_environment,
                     args.clean_env || executable_task.task().clean_env(),
                     Some(&lock_file.lock_file),
                     project.config().force_activate(),
-                    project.config().experimental_activation_cache_usage(),
+                    project.config().activation_cache(),
                 )
                 .await?;
                 entry.insert(command_env)
@@ -230,7 +233,7 @@ pub async fn execute(args: Args) -> miette::Result<()> {
                 if code == 127 {
                     command_not_found(&project, explicit_environment);
                 }
-                std::process::exit(code);
+                super::timings::exit(code);
             }
             Err(err) => return Err(err.into()),
         }
@@ -274,7 +277,7 @@ fn command_not_found<'p>(project: &'p Project, explicit_environment: Option<Envi
 }
 
 #[derive(Debug, Error, Diagnostic)]
-enum TaskExecutionError {
+pub(crate) enum TaskExecutionError {
     #[error("the script exited with a non-zero exit code {0}")]
     NonZeroExitCode(i32),
 
@@ -13,7 +13,7 @@ use miette::IntoDiagnostic;
 use pixi_config::{default_channel_config, Config};
 use pixi_progress::await_in_progress;
 use pixi_utils::reqwest::build_reqwest_clients;
-use rattler_conda_types::{MatchSpec, PackageName, Platform, RepoDataRecord};
+use rattler_conda_types::{MatchSpec, PackageName, ParseStrictness, Platform, RepoDataRecord};
 use rattler_repodata_gateway::{GatewayError, RepoData};
 use regex::Regex;
 use strsim::jaro;
@@ -46,6 +46,87 @@ pub struct Args {
     /// Limit the number of search results
     #[clap(short, long)]
     pub limit: Option<usize>,
+
+    /// Only show packages that depend on this package
+    #[arg(long, value_name = "PACKAGE")]
+    pub depends_on: Option<PackageName>,
+
+    /// Only show packages with this license, matched case-insensitively and
+    /// with support for wildcards (`*`)
+    #[arg(long)]
+    pub license: Option<String>,
+
+    /// Print the matching records as JSON, with all their repodata fields
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// The filters on the records of the packages that are searched, on top of
+/// the name of the package.
+#[derive(Debug, Default)]
+struct RecordFilter {
+    depends_on: Option<PackageName>,
+    license: Option<(String, Regex)>,
+}
+
+impl RecordFilter {
+    fn from_args(args: &Args) -> miette::Result<Self> {
+        let license = args
+            .license
+            .as_deref()
+            .map(|license| {
+                let pattern = license.split('*').map(regex::escape).join(".*");
+                let regex = Regex::new(&format!("(?i)^{pattern}$")).into_diagnostic()?;
+                Ok::<_, miette::Report>((license.to_string(), regex))
+            })
+            .transpose()?;
+        Ok(Self {
+            depends_on: args.depends_on.clone(),
+            license,
+        })
+    }
+
+    /// Returns true if the record passes all the filters.
+    fn matches(&self, record: &RepoDataRecord) -> bool {
+        let package_record = &record.package_record;
+        if let Some(depends_on) = &self.depends_on {
+            let depends = package_record.depends.iter().any(|dependency| {
+                MatchSpec::from_str(dependency, ParseStrictness::Lenient)
+                    .is_ok_and(|spec| spec.name.as_ref() == Some(depends_on))
+            });
+            if !depends {
+                return false;
+            }
+        }
+        if let Some((_, license)) = &self.license {
+            if !package_record
+                .license
+                .as_deref()
+                .is_some_and(|value| license.is_match(value))
+            {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Returns true if no filters are set.
+    fn is_empty(&self) -> bool {
+        self.depends_on.is_none() && self.license.is_none()
+    }
+
+    /// Describes the filters that are set, e.g. `--license 'MIT'`.
+    fn describe(&self) -> String {
+        let depends_on = self
+            .depends_on
+            .as_ref()
+            .map(|name| format!("--depends-on '{}'", name.as_source()));
+        let license = self
+            .license
+            .as_ref()
+            .map(|(license, _)| format!("--license '{license}'"));
+        depends_on.into_iter().chain(license).join(" and ")
+    }
 }
 
 /// fetch packages from `repo_data` using `repodata_query_func` based on
@@ -55,6 +136,7 @@ async fn search_package_by_filter<F, QF, FR>(
     all_package_names: Vec<PackageName>,
     repodata_query_func: QF,
     filter_func: F,
+    record_filter: &RecordFilter,
     only_latest: bool,
 ) -> miette::Result<Vec<RepoDataRecord>>
 where
@@ -84,6 +166,7 @@ where
             // sort records by version, get the latest one of each package
             let records_of_repo: HashMap<String, RepoDataRecord> = repo
                 .into_iter()
+                .filter(|record| record_filter.matches(record))
                 .sorted_by(|a, b| a.package_record.version.cmp(&b.package_record.version))
                 .map(|record| {
                     (
@@ -99,7 +182,11 @@ where
         packages.sort_by(|a, b| a.package_record.version.cmp(&b.package_record.version));
     } else {
         for repo in repos {
-            packages.extend(repo.into_iter().cloned().collect_vec());
+            packages.extend(
+                repo.into_iter()
+                    .filter(|record| record_filter.matches(record))
+                    .cloned(),
+            );
         }
     }
 
@@ -141,6 +228,7 @@ pub async fn execute_impl<W: Write>(
         channels.iter().map(|c| c.name()).format(", ")
     );
 
+    let record_filter = RecordFilter::from_args(&args)?;
     let package_name_filter = args.package;
 
     let client = project
@@ -150,13 +238,33 @@ pub async fn execute_impl<W: Write>(
 
     let config = Config::load_global();
 
-    // Fetch the all names from the repodata using gateway
-    let gateway = config.gateway(client.clone());
-
-    let all_names = await_in_progress("loading all package names", |_| async {
-        gateway
-            .names(channels.clone(), [args.platform, Platform::NoArch])
-            .await
+    // Fetch the all names from the repodata using gateway. Channels whose
+    // sharded repodata fails fall back to the full repodata, the gateway that
+    // succeeded is used for the queries below.
+    let channels = channels.into_iter().collect::<Vec<_>>();
+    let platforms = [args.platform, Platform::NoArch];
+    let probe_specs = MatchSpec::from_str(
+        &package_name_filter.replace('*', ""),
+        ParseStrictness::Lenient,
+    )
+    .ok()
+    .into_iter()
+    .collect::<Vec<_>>();
+    let (gateway, all_names) = await_in_progress("loading all package names", |_| {
+        config.query_with_sharded_fallback(
+            &client,
+            config.gateway(client.clone()),
+            &channels,
+            &platforms,
+            &probe_specs,
+            |gateway| {
+                let channels = channels.clone();
+                async move {
+                    let names = gateway.names(channels, platforms).await?;
+                    Ok::<_, GatewayError>((gateway, names))
+                }
+            },
+        )
     })
     .await
     .into_diagnostic()?;
@@ -184,7 +292,9 @@ pub async fn execute_impl<W: Write>(
             &package_name_filter,
             all_names,
             repodata_query_func,
+            &record_filter,
             args.limit,
+            args.json,
             out,
         )
         .await?
@@ -194,7 +304,15 @@ pub async fn execute_impl<W: Write>(
     else {
         let package_name = PackageName::try_from(package_name_filter).into_diagnostic()?;
 
-        search_exact_package(package_name, all_names, repodata_query_func, out).await?
+        search_exact_package(
+            package_name,
+            all_names,
+            repodata_query_func,
+            &record_filter,
+            args.json,
+            out,
+        )
+        .await?
     };
 
     Project::warn_on_discovered_from_env(args.project_config.manifest_path.as_deref());
@@ -211,6 +329,8 @@ async fn search_exact_package<W: Write, QF, FR>(
     package_name: PackageName,
     all_repodata_names: Vec<PackageName>,
     repodata_query_func: QF,
+    record_filter: &RecordFilter,
+    json: bool,
     out: &mut W,
 ) -> miette::Result<Option<Vec<RepoDataRecord>>>
 where
@@ -218,11 +338,13 @@ where
     FR: Future<Output = Result<Vec<RepoData>, GatewayError>>,
 {
     let package_name_search = package_name.clone();
+    let all_package_names_contain = all_repodata_names.contains(&package_name);
     let packages = search_package_by_filter(
         &package_name_search,
         all_repodata_names,
         repodata_query_func,
         |pn, n| pn == n,
+        record_filter,
         false,
     )
     .await?;
@@ -248,9 +370,23 @@ where
 
     if packages.is_empty() {
         let normalized_package_name = package_name.as_normalized();
+        if !record_filter.is_empty() && all_package_names_contain {
+            return Err(miette::miette!(
+                help = "remove the filters to see all the versions",
+                "Package {normalized_package_name} has no versions that match {}",
+                record_filter.describe()
+            ));
+        }
         return Err(miette::miette!("Package {normalized_package_name} not found, please use a wildcard '*' in the search name for a broader result."));
     }
 
+    // Print all the matching records, from the newest to the oldest
+    if json {
+        let records = packages.iter().rev().collect_vec();
+        print_json(&records, out)?;
+        return Ok(packages.last().map(|package| vec![package.clone()]));
+    }
+
     let newest_package = packages.last();
     if let Some(newest_package) = newest_package {
         let other_versions = packages
@@ -267,6 +403,15 @@ where
     Ok(newest_package.map(|package| vec![package.clone()]))
 }
 
+/// Prints the records as a JSON array.
+fn print_json<W: Write>(records: &[&RepoDataRecord], out: &mut W) -> miette::Result<()> {
+    let json = serde_json::to_string_pretty(records).into_diagnostic()?;
+    match writeln!(out, "{json}") {
+        Err(e) if e.kind() != std::io::ErrorKind::BrokenPipe => Err(e).into_diagnostic(),
+        _ => Ok(()),
+    }
+}
+
 fn format_additional_builds_string(builds: Option<Vec<&RepoDataRecord>>) -> String {
     let builds = builds.unwrap_or_default();
     match builds.len() {
@@ -448,12 +593,15 @@ fn print_package_info<W: Write>(
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn search_package_by_wildcard<W: Write, QF, FR>(
     package_name: PackageName,
     package_name_filter: &str,
     all_package_names: Vec<PackageName>,
     repodata_query_func: QF,
+    record_filter: &RecordFilter,
     limit: Option<usize>,
+    json: bool,
     out: &mut W,
 ) -> miette::Result<Option<Vec<RepoDataRecord>>>
 where
@@ -464,19 +612,25 @@ where
         .expect("Expect only characters and/or * (wildcard).");
 
     let package_name_search = package_name.clone();
+    let normalized_package_name = package_name.as_normalized();
 
-    let mut packages = await_in_progress("searching packages", |_| async {
-        let packages = search_package_by_filter(
-            &package_name_search,
-            all_package_names.clone(),
-            repodata_query_func.clone(),
-            |pn, _| wildcard_pattern.is_match(pn.as_normalized()),
-            true,
-        )
-        .await?;
+    // Only fall back to a fuzzy search if no package names match the wildcard,
+    // not if the filters exclude all the packages that match it.
+    let names_match = all_package_names
+        .iter()
+        .any(|name| wildcard_pattern.is_match(name.as_normalized()));
 
-        if !packages.is_empty() {
-            return Ok(packages);
+    let mut packages = await_in_progress("searching packages", |_| async {
+        if names_match {
+            return search_package_by_filter(
+                &package_name_search,
+                all_package_names,
+                repodata_query_func,
+                |pn, _| wildcard_pattern.is_match(pn.as_normalized()),
+                record_filter,
+                true,
+            )
+            .await;
         }
 
         tracing::info!("No packages found with wildcard search, trying with fuzzy search.");
@@ -486,13 +640,13 @@ where
             all_package_names,
             repodata_query_func,
             |pn, n| jaro(pn.as_normalized(), n.as_normalized()) > similarity,
+            record_filter,
             true,
         )
         .await
     })
     .await?;
 
-    let normalized_package_name = package_name.as_normalized();
     packages.sort_by(|a, b| {
         let ord = jaro(
             b.package_record.name.as_normalized(),
@@ -510,9 +664,25 @@ where
     });
 
     if packages.is_empty() {
+        if names_match && !record_filter.is_empty() {
+            return Err(miette::miette!(
+                help = "remove the filters to see all the packages",
+                "No packages matching '{package_name_filter}' match {}",
+                record_filter.describe()
+            ));
+        }
         return Err(miette::miette!("Could not find {normalized_package_name}"));
     }
 
+    if json {
+        let records = packages
+            .iter()
+            .take(limit.unwrap_or(usize::MAX))
+            .collect_vec();
+        print_json(&records, out)?;
+        return Ok(Some(packages));
+    }
+
     if let Err(e) = print_matching_packages(&packages, out, limit) {
         if e.kind() != std::io::ErrorKind::BrokenPipe {
             return Err(e).into_diagnostic();
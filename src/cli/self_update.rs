@@ -1,25 +1,66 @@
-use std::io::{Seek, Write};
+use std::{
+    io::{Seek, Write},
+    path::PathBuf,
+};
 
 use flate2::read::GzDecoder;
 use tar::Archive;
 
 use miette::{Context, IntoDiagnostic};
+use pixi_config::{pixi_home, Config, ReleaseChannel};
 use pixi_consts::consts;
+use rattler_digest::{parse_digest_from_hex, Sha256};
 use reqwest::Client;
 use serde::Deserialize;
 use tempfile::{NamedTempFile, TempDir};
 
+use crate::signing::{self, PublicKey};
+
+/// The name of the directory in the pixi home that contains the binary that
+/// was replaced by the last update.
+const ROLLBACK_DIR: &str = "self-update";
+
+/// The name of the file that contains the version of the replaced binary.
+const ROLLBACK_VERSION_FILE: &str = "previous-version";
+
+/// The minisign public key that signs the release archives of pixi. The
+/// releases are not signed yet, so only builds that set it verify the
+/// signature by default. Other builds verify it when keys are configured in
+/// `self-update.public-keys`.
+const RELEASE_PUBLIC_KEY: Option<&str> = option_env!("PIXI_RELEASE_PUBLIC_KEY");
+
+/// The number of recent releases that are searched for the latest release of
+/// the beta channel.
+const BETA_RELEASES_PER_PAGE: usize = 20;
+
 /// Update pixi to the latest version or a specific version.
 #[derive(Debug, clap::Parser)]
 pub struct Args {
     /// The desired version (to downgrade or upgrade to). Update to the latest version if not specified.
     #[clap(long)]
     version: Option<String>,
+
+    /// The release channel to update from: `stable` or `beta`.
+    /// Defaults to the `self-update.channel` configuration, or `stable`.
+    #[clap(long, conflicts_with = "version")]
+    channel: Option<ReleaseChannel>,
+
+    /// Restore the pixi binary that was replaced by the last update.
+    #[clap(long, conflicts_with_all = ["version", "channel"])]
+    rollback: bool,
+
+    /// Replace pixi without verifying the signature of the release archive,
+    /// even if public keys are configured. The archive is still verified
+    /// against its sha256 checksum.
+    #[clap(long)]
+    no_verify_signature: bool,
 }
 
 #[derive(Debug, Deserialize)]
 struct GithubRelease {
     tag_name: String,
+    #[serde(default)]
+    draft: bool,
     assets: Vec<GithubReleaseAsset>,
 }
 
@@ -62,14 +103,35 @@ fn default_archive_name() -> Option<String> {
 }
 
 pub async fn execute(args: Args) -> miette::Result<()> {
+    if args.rollback {
+        return rollback();
+    }
+
+    let config = Config::load_global();
+    let channel = args
+        .channel
+        .unwrap_or_else(|| config.self_update().channel());
+    let public_keys = if args.no_verify_signature {
+        Vec::new()
+    } else {
+        trusted_public_keys(RELEASE_PUBLIC_KEY, &config.self_update().public_keys)?
+    };
+    if public_keys.is_empty() {
+        tracing::warn!("The signature of the pixi archive is not verified");
+    }
+
     // Retrieve the target version information from github.
-    let target_version_json = match retrieve_target_version(&args.version).await {
+    let target_version_json = match retrieve_target_version(&args.version, channel).await {
         Ok(target_version_json) => target_version_json,
         Err(err) => match args.version {
             Some(version) => {
                 miette::bail!("The version you specified is not available: {}", version)
             }
-            None => miette::bail!("Failed to fetch latest version from github: {}", err),
+            None => miette::bail!(
+                "Failed to fetch the latest {} version from github: {}",
+                channel,
+                err
+            ),
         },
     };
 
@@ -79,7 +141,7 @@ pub async fn execute(args: Args) -> miette::Result<()> {
     // Get the current version of the pixi binary
     let current_version = consts::PIXI_VERSION;
 
-    // Stop here if the target version is the same as the current version
+    // Stop here if the target version is the same as the current version.
     if target_version == current_version {
         eprintln!(
             "{}pixi is already up-to-date (version {})",
@@ -100,11 +162,8 @@ pub async fn execute(args: Args) -> miette::Result<()> {
     let archive_name = default_archive_name()
         .expect("Could not find the default archive name for the current platform");
 
-    let url = target_version_json
-        .assets
-        .iter()
-        .find(|asset| asset.name == archive_name)
-        .expect("Could not find the archive in the release")
+    let url = find_asset(&target_version_json, &archive_name)
+        .ok_or_else(|| miette::miette!("Could not find '{}' in the release", archive_name))?
         .browser_download_url
         .clone();
 
@@ -132,6 +191,16 @@ pub async fn execute(args: Args) -> miette::Result<()> {
         console::style(console::Emoji("✔ ", "")).green(),
     );
 
+    // Verify the archive before anything of it is unpacked
+    verify_archive(
+        &client,
+        &target_version_json,
+        &archive_name,
+        archived_tempfile.path(),
+        &public_keys,
+    )
+    .await?;
+
     // Seek to the beginning of the file before uncompressing it
     let _ = archived_tempfile.rewind();
 
@@ -157,8 +226,12 @@ pub async fn execute(args: Args) -> miette::Result<()> {
     // Get the new binary path used for self-replacement
     let new_binary_path = binary_tempdir.path().join(pixi_binary_name());
 
+    // Keep the current binary, so the update can be rolled back
+    let backup = stage_backup()?;
+
     // Replace the current binary with the new binary
     self_replace::self_replace(new_binary_path).into_diagnostic()?;
+    backup.commit()?;
 
     eprintln!(
         "{}Pixi has been updated to version {}.",
@@ -169,6 +242,195 @@ pub async fn execute(args: Args) -> miette::Result<()> {
     Ok(())
 }
 
+/// Returns the public keys of which one has to sign the release archive: the
+/// key of the pixi releases and the configured keys. Without any keys the
+/// signature is not verified.
+fn trusted_public_keys(
+    release_key: Option<&str>,
+    configured_keys: &[String],
+) -> miette::Result<Vec<PublicKey>> {
+    let mut public_keys = Vec::new();
+    if let Some(release_key) = release_key {
+        public_keys.push(
+            release_key
+                .parse::<PublicKey>()
+                .into_diagnostic()
+                .context("invalid release public key in this build of pixi")?,
+        );
+    }
+    for key in configured_keys {
+        public_keys.push(
+            key.parse::<PublicKey>()
+                .into_diagnostic()
+                .context("invalid public key in the `self-update.public-keys` configuration")?,
+        );
+    }
+    Ok(public_keys)
+}
+
+/// Returns the asset of the release with the given name.
+fn find_asset<'a>(release: &'a GithubRelease, name: &str) -> Option<&'a GithubReleaseAsset> {
+    release.assets.iter().find(|asset| asset.name == name)
+}
+
+/// Downloads the text of a release asset.
+async fn download_text(client: &Client, asset: &GithubReleaseAsset) -> miette::Result<String> {
+    client
+        .get(&asset.browser_download_url)
+        .header("User-Agent", user_agent())
+        .send()
+        .await
+        .and_then(|res| res.error_for_status())
+        .into_diagnostic()?
+        .text()
+        .await
+        .into_diagnostic()
+        .with_context(|| format!("Failed to download '{}'", asset.name))
+}
+
+/// Parses the checksum file of a release archive, which contains the
+/// hexadecimal sha256 optionally followed by the name of the archive.
+fn parse_checksum_file(contents: &str) -> Option<rattler_digest::Sha256Hash> {
+    let hex = contents.split_whitespace().next()?;
+    parse_digest_from_hex::<Sha256>(hex)
+}
+
+/// Verifies the downloaded archive against the sha256 checksum that is
+/// published with the release and against its minisign signature. The
+/// signature is skipped without public keys.
+async fn verify_archive(
+    client: &Client,
+    release: &GithubRelease,
+    archive_name: &str,
+    archive_path: &std::path::Path,
+    public_keys: &[PublicKey],
+) -> miette::Result<()> {
+    match find_asset(release, &format!("{archive_name}.sha256")) {
+        Some(asset) => {
+            let contents = download_text(client, asset).await?;
+            let expected = parse_checksum_file(&contents).ok_or_else(|| {
+                miette::miette!("The checksum file '{}' is not valid", asset.name)
+            })?;
+            let actual =
+                rattler_digest::compute_file_digest::<Sha256>(archive_path).into_diagnostic()?;
+            if actual != expected {
+                miette::bail!(
+                    "The sha256 of the downloaded archive is {:x}, but the release expects {:x}",
+                    actual,
+                    expected
+                );
+            }
+            eprintln!(
+                "{}Pixi archive checksum verified.",
+                console::style(console::Emoji("✔ ", "")).green(),
+            );
+        }
+        None => tracing::warn!(
+            "The release doesn't contain a checksum of '{}', the archive is not verified",
+            archive_name
+        ),
+    }
+
+    if public_keys.is_empty() {
+        return Ok(());
+    }
+    let signature_name = format!("{archive_name}.{}", signing::SIGNATURE_EXTENSION);
+    let asset = find_asset(release, &signature_name).ok_or_else(|| {
+        miette::miette!(
+            help = "pass `--no-verify-signature` to update without verifying the signature",
+            "The release doesn't contain the signature '{}'",
+            signature_name
+        )
+    })?;
+    let signature = download_text(client, asset).await?;
+    signing::verify_package(public_keys, archive_path, &signature)
+        .into_diagnostic()
+        .context("Failed to verify the signature of the pixi archive")?;
+    eprintln!(
+        "{}Pixi archive signature verified.",
+        console::style(console::Emoji("✔ ", "")).green(),
+    );
+    Ok(())
+}
+
+/// The directory that contains the binary that was replaced by the last
+/// update.
+fn rollback_dir() -> miette::Result<PathBuf> {
+    pixi_home()
+        .map(|home| home.join(ROLLBACK_DIR))
+        .ok_or_else(|| miette::miette!("Could not determine the pixi home directory"))
+}
+
+/// A copy of the current binary in the rollback directory, which only replaces
+/// the binary of the previous update once it is committed.
+struct StagedBackup {
+    binary: NamedTempFile,
+    rollback_dir: PathBuf,
+}
+
+impl StagedBackup {
+    /// Replaces the binary of the previous update by the staged copy, together
+    /// with its version. Called once the current binary has been replaced.
+    fn commit(self) -> miette::Result<()> {
+        self.binary
+            .persist(self.rollback_dir.join(pixi_binary_name()))
+            .into_diagnostic()?;
+        fs_err::write(
+            self.rollback_dir.join(ROLLBACK_VERSION_FILE),
+            consts::PIXI_VERSION,
+        )
+        .into_diagnostic()?;
+        Ok(())
+    }
+}
+
+/// Copies the current binary next to the binary of the previous update. The
+/// previous binary is kept until the copy is committed, so a failing update
+/// doesn't lose it. Dropping the staged copy removes it.
+fn stage_backup() -> miette::Result<StagedBackup> {
+    let current_exe = std::env::current_exe().into_diagnostic()?;
+    let rollback_dir = rollback_dir()?;
+    fs_err::create_dir_all(&rollback_dir).into_diagnostic()?;
+    let binary = NamedTempFile::new_in(&rollback_dir).into_diagnostic()?;
+    fs_err::copy(&current_exe, binary.path()).into_diagnostic()?;
+    Ok(StagedBackup {
+        binary,
+        rollback_dir,
+    })
+}
+
+/// Replaces the current binary with the binary that was replaced by the last
+/// update. The current binary is kept in its place, so a rollback can be
+/// undone by rolling back again.
+fn rollback() -> miette::Result<()> {
+    let rollback_dir = rollback_dir()?;
+    let previous_binary = rollback_dir.join(pixi_binary_name());
+    if !previous_binary.is_file() {
+        miette::bail!("There is no previous version of pixi to roll back to");
+    }
+    let previous_version = fs_err::read_to_string(rollback_dir.join(ROLLBACK_VERSION_FILE))
+        .unwrap_or_else(|_| "an unknown version".to_string());
+
+    // Move the previous binary out of the way, so the current binary can take
+    // its place.
+    let tempdir = tempfile::tempdir().into_diagnostic()?;
+    let restored_binary = tempdir.path().join(pixi_binary_name());
+    fs_err::copy(&previous_binary, &restored_binary).into_diagnostic()?;
+
+    let backup = stage_backup()?;
+    self_replace::self_replace(&restored_binary).into_diagnostic()?;
+    backup.commit()?;
+
+    eprintln!(
+        "{}Pixi has been rolled back from {} to {}.",
+        console::style(console::Emoji("✔ ", "")).green(),
+        consts::PIXI_VERSION,
+        previous_version.trim()
+    );
+
+    Ok(())
+}
+
 /// Unpack files from a tar.gz archive to a target directory.
 fn unpack_tar_gz(
     archived_tempfile: &NamedTempFile,
@@ -197,18 +459,27 @@ fn unpack_tar_gz(
     Ok(())
 }
 
-async fn retrieve_target_version(version: &Option<String>) -> miette::Result<GithubRelease> {
+async fn retrieve_target_version(
+    version: &Option<String>,
+    channel: ReleaseChannel,
+) -> miette::Result<GithubRelease> {
     // Fetch the target version from github.
     // The target version is:
-    // - the latest version if no version is specified
     // - the specified version if a version is specified
-    let url = if let Some(version) = version {
-        format!(
+    // - the latest release, or the latest release including pre-releases for
+    //   the beta channel
+    let url = match (version, channel) {
+        (Some(version), _) => format!(
             "https://api.github.com/repos/prefix-dev/pixi/releases/tags/v{}",
             version
-        )
-    } else {
-        "https://api.github.com/repos/prefix-dev/pixi/releases/latest".to_string()
+        ),
+        (None, ReleaseChannel::Stable) => {
+            "https://api.github.com/repos/prefix-dev/pixi/releases/latest".to_string()
+        }
+        (None, ReleaseChannel::Beta) => format!(
+            "https://api.github.com/repos/prefix-dev/pixi/releases?per_page={}",
+            BETA_RELEASES_PER_PAGE
+        ),
     };
 
     let client = Client::new();
@@ -236,12 +507,33 @@ async fn retrieve_target_version(version: &Option<String>) -> miette::Result<Git
         .await
         .expect("Failed to fetch GitHub release body, body text panic.");
 
+    if version.is_none() && channel == ReleaseChannel::Beta {
+        let releases = serde_json::from_str::<Vec<GithubRelease>>(&body)
+            .into_diagnostic()
+            .with_context(|| format!("Failed to parse the Releases from github: {:#?}", body))?;
+        return latest_release(releases)
+            .ok_or_else(|| miette::miette!("No published release found on github"));
+    }
+
     // compare target version with current version
     serde_json::from_str::<GithubRelease>(&body)
         .into_diagnostic()
         .with_context(|| format!("Failed to parse the Release from github: {:#?}", body))
 }
 
+/// Returns the most recent published release, including pre-releases but not
+/// rolling releases like the nightly release, which are not tagged with a
+/// version. GitHub lists the most recent release first.
+fn latest_release(releases: Vec<GithubRelease>) -> Option<GithubRelease> {
+    releases.into_iter().find(|release| {
+        !release.draft
+            && release
+                .tag_name
+                .strip_prefix('v')
+                .is_some_and(|version| version.starts_with(|c: char| c.is_ascii_digit()))
+    })
+}
+
 fn pixi_binary_name() -> String {
     format!("pixi{}", std::env::consts::EXE_SUFFIX)
 }
@@ -257,6 +549,64 @@ pub async fn execute_stub(_: Args) -> miette::Result<()> {
 mod tests {
     use std::path::PathBuf;
 
+    use super::{latest_release, parse_checksum_file, trusted_public_keys, GithubRelease};
+
+    fn release(tag_name: &str, draft: bool) -> GithubRelease {
+        GithubRelease {
+            tag_name: tag_name.to_string(),
+            draft,
+            assets: Vec::new(),
+        }
+    }
+
+    #[test]
+    pub fn test_latest_release() {
+        let releases = vec![
+            release("nightly", false),
+            release("v0.42.0", true),
+            release("v0.42.0-beta.1", false),
+            release("v0.41.4", false),
+        ];
+        assert_eq!(latest_release(releases).unwrap().tag_name, "v0.42.0-beta.1");
+        assert!(latest_release(vec![release("nightly", false)]).is_none());
+        assert!(latest_release(vec![release("dev-build", false)]).is_none());
+    }
+
+    #[test]
+    pub fn test_trusted_public_keys() {
+        let key = "RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3";
+
+        // Without a release key or configured keys the signature is not
+        // verified.
+        assert!(trusted_public_keys(None, &[]).unwrap().is_empty());
+
+        assert_eq!(trusted_public_keys(Some(key), &[]).unwrap().len(), 1);
+        assert_eq!(
+            trusted_public_keys(Some(key), &[key.to_string()])
+                .unwrap()
+                .len(),
+            2
+        );
+        assert_eq!(
+            trusted_public_keys(None, &[key.to_string()]).unwrap().len(),
+            1
+        );
+        assert!(trusted_public_keys(None, &["not a key".to_string()]).is_err());
+    }
+
+    #[test]
+    pub fn test_parse_checksum_file() {
+        let hash = "0c37a3ee6bc8e54a3e1a3a4d5a7ad34d0d1d0e7f7a7b1a6c9b3c8e1f2a3b4c5d";
+        let with_name = format!("{hash}  pixi-x86_64-unknown-linux-musl.tar.gz\n");
+        assert_eq!(
+            format!("{:x}", parse_checksum_file(&with_name).unwrap()),
+            hash
+        );
+        assert_eq!(format!("{:x}", parse_checksum_file(hash).unwrap()), hash);
+        assert!(parse_checksum_file("not a hash").is_none());
+        assert!(parse_checksum_file("").is_none());
+    }
+
     #[test]
     pub fn test_unarchive_flat_structure() {
         // This archive contains a single file named "a_file"
@@ -1,4 +1,4 @@
-use std::{collections::HashMap, io::Write};
+use std::{collections::HashMap, io::Write, path::Path};
 
 use clap::Parser;
 use miette::IntoDiagnostic;
@@ -56,6 +56,7 @@ fn start_powershell(
     pwsh: PowerShell,
     env: &HashMap<String, String>,
     prompt: String,
+    cwd: &Path,
 ) -> miette::Result<Option<i32>> {
     // create a tempfile for activation
     let mut temp_file = tempfile::Builder::new()
@@ -78,6 +79,7 @@ fn start_powershell(
     let temp_path = temp_file.into_temp_path();
 
     let mut command = std::process::Command::new(pwsh.executable());
+    command.current_dir(cwd);
     command.arg("-NoLogo");
     command.arg("-NoExit");
     command.arg("-File");
@@ -95,6 +97,7 @@ fn start_cmdexe(
     cmdexe: CmdExe,
     env: &HashMap<String, String>,
     prompt: String,
+    cwd: &Path,
 ) -> miette::Result<Option<i32>> {
     // create a tempfile for activation
     let mut temp_file = tempfile::Builder::new()
@@ -115,6 +118,7 @@ fn start_cmdexe(
     temp_file.write(prompt.as_bytes()).into_diagnostic()?;
 
     let mut command = std::process::Command::new(cmdexe.executable());
+    command.current_dir(cwd);
     command.arg("/K");
     command.arg(temp_file.path());
 
@@ -129,12 +133,14 @@ fn start_cmdexe(
 /// - `shell`: The type of shell to start. Must implement the `Shell` and `Copy` traits.
 /// - `args`: A vector of arguments to pass to the shell.
 /// - `env`: A HashMap containing environment variables to set in the shell.
+/// - `cwd`: The directory to start the shell in.
 #[cfg(target_family = "unix")]
 async fn start_unix_shell<T: Shell + Copy + 'static>(
     shell: T,
     args: Vec<&str>,
     env: &HashMap<String, String>,
     prompt: String,
+    cwd: &Path,
 ) -> miette::Result<Option<i32>> {
     // create a tempfile for activation
     let mut temp_file = tempfile::Builder::new()
@@ -160,6 +166,7 @@ async fn start_unix_shell<T: Shell + Copy + 'static>(
     temp_file.write(prompt.as_bytes()).into_diagnostic()?;
 
     let mut command = std::process::Command::new(shell.executable());
+    command.current_dir(cwd);
     command.args(args);
 
     // Space added before `source` to automatically ignore it in history.
@@ -184,10 +191,12 @@ async fn start_unix_shell<T: Shell + Copy + 'static>(
 /// # Arguments
 /// - `shell`: The Nushell (also contains executable location)
 /// - `env`: A HashMap containing environment variables to set in the shell.
+/// - `cwd`: The directory to start the shell in.
 async fn start_nu_shell(
     shell: rattler_shell::shell::NuShell,
     env: &HashMap<String, String>,
     prompt: String,
+    cwd: &Path,
 ) -> miette::Result<Option<i32>> {
     // create a tempfile for activation
     let mut temp_file = tempfile::Builder::new()
@@ -218,6 +227,7 @@ async fn start_nu_shell(
     temp_file.write(prompt.as_bytes()).into_diagnostic()?;
 
     let mut command = std::process::Command::new(shell.executable());
+    command.current_dir(cwd);
     command.arg("--execute");
     command.arg(format!("source {}", temp_file.path().display()));
 
@@ -254,6 +264,9 @@ pub async fn execute(args: Args) -> miette::Result<()> {
         },
     )
     .await?;
+    if !args.prefix_update_config.no_install() {
+        lock_file_data.sync_external_managers(&environment).await?;
+    }
 
     // Get the environment variables we need to set activate the environment in the shell.
     let env = project
@@ -262,62 +275,95 @@ pub async fn execute(args: Args) -> miette::Result<()> {
             CurrentEnvVarBehavior::Exclude,
             Some(&lock_file_data.lock_file),
             project.config().force_activate(),
-            project.config().experimental_activation_cache_usage(),
+            project.config().activation_cache(),
         )
         .await?;
 
     tracing::debug!("Pixi environment activation:\n{:?}", env);
 
-    // Start the shell as the last part of the activation script based on the default shell.
-    let interactive_shell: ShellEnum = ShellEnum::from_parent_process()
+    let cwd = std::env::current_dir().into_diagnostic()?;
+    let res = start_interactive_shell(env, &prompt_name, project.config().change_ps1(), &cwd).await;
+
+    match res {
+        Ok(Some(code)) => super::timings::exit(code),
+        Ok(None) => super::timings::exit(0),
+        Err(e) => {
+            eprintln!("Error starting shell: {}", e);
+            super::timings::exit(1);
+        }
+    }
+}
+
+/// Returns the shell that the user runs pixi from.
+pub(crate) fn interactive_shell() -> ShellEnum {
+    ShellEnum::from_parent_process()
         .or_else(ShellEnum::from_env)
-        .unwrap_or_default();
+        .unwrap_or_default()
+}
+
+/// Starts the interactive shell of the user in `cwd`, with the environment
+/// variables set, and returns its exit code once the user leaves it.
+pub(crate) async fn start_interactive_shell(
+    env: &HashMap<String, String>,
+    prompt_name: &str,
+    change_ps1: bool,
+    cwd: &Path,
+) -> miette::Result<Option<i32>> {
+    // Start the shell as the last part of the activation script based on the default shell.
+    let interactive_shell = interactive_shell();
 
     tracing::info!("Starting shell: {:?}", interactive_shell);
 
-    let prompt = if project.config().change_ps1() {
+    let prompt = if change_ps1 {
         match interactive_shell {
-            ShellEnum::NuShell(_) => prompt::get_nu_prompt(prompt_name.as_str()),
-            ShellEnum::PowerShell(_) => prompt::get_powershell_prompt(prompt_name.as_str()),
-            ShellEnum::Bash(_) => prompt::get_bash_hook(prompt_name.as_str()),
-            ShellEnum::Zsh(_) => prompt::get_zsh_hook(prompt_name.as_str()),
-            ShellEnum::Fish(_) => prompt::get_fish_prompt(prompt_name.as_str()),
+            ShellEnum::NuShell(_) => prompt::get_nu_prompt(prompt_name),
+            ShellEnum::PowerShell(_) => prompt::get_powershell_prompt(prompt_name),
+            ShellEnum::Bash(_) => prompt::get_bash_hook(prompt_name),
+            ShellEnum::Zsh(_) => prompt::get_zsh_hook(prompt_name),
+            ShellEnum::Fish(_) => prompt::get_fish_prompt(prompt_name),
             ShellEnum::Xonsh(_) => prompt::get_xonsh_prompt(),
-            ShellEnum::CmdExe(_) => prompt::get_cmd_prompt(prompt_name.as_str()),
+            ShellEnum::CmdExe(_) => prompt::get_cmd_prompt(prompt_name),
         }
     } else {
         "".to_string()
     };
 
-    #[cfg(target_family = "windows")]
-    let res = match interactive_shell {
-        ShellEnum::NuShell(nushell) => start_nu_shell(nushell, env, prompt).await,
-        ShellEnum::PowerShell(pwsh) => start_powershell(pwsh, env, prompt),
-        ShellEnum::CmdExe(cmdexe) => start_cmdexe(cmdexe, env, prompt),
+    start_shell(interactive_shell, env, prompt, cwd).await
+}
+
+#[cfg(target_family = "windows")]
+async fn start_shell(
+    interactive_shell: ShellEnum,
+    env: &HashMap<String, String>,
+    prompt: String,
+    cwd: &Path,
+) -> miette::Result<Option<i32>> {
+    match interactive_shell {
+        ShellEnum::NuShell(nushell) => start_nu_shell(nushell, env, prompt, cwd).await,
+        ShellEnum::PowerShell(pwsh) => start_powershell(pwsh, env, prompt, cwd),
+        ShellEnum::CmdExe(cmdexe) => start_cmdexe(cmdexe, env, prompt, cwd),
         _ => {
             miette::bail!("Unsupported shell: {:?}", interactive_shell);
         }
-    };
+    }
+}
 
-    #[cfg(target_family = "unix")]
-    let res = match interactive_shell {
-        ShellEnum::NuShell(nushell) => start_nu_shell(nushell, env, prompt).await,
-        ShellEnum::PowerShell(pwsh) => start_powershell(pwsh, env, prompt),
-        ShellEnum::Bash(bash) => start_unix_shell(bash, vec!["-l", "-i"], env, prompt).await,
-        ShellEnum::Zsh(zsh) => start_unix_shell(zsh, vec!["-l", "-i"], env, prompt).await,
-        ShellEnum::Fish(fish) => start_unix_shell(fish, vec![], env, prompt).await,
-        ShellEnum::Xonsh(xonsh) => start_unix_shell(xonsh, vec![], env, prompt).await,
+#[cfg(target_family = "unix")]
+async fn start_shell(
+    interactive_shell: ShellEnum,
+    env: &HashMap<String, String>,
+    prompt: String,
+    cwd: &Path,
+) -> miette::Result<Option<i32>> {
+    match interactive_shell {
+        ShellEnum::NuShell(nushell) => start_nu_shell(nushell, env, prompt, cwd).await,
+        ShellEnum::PowerShell(pwsh) => start_powershell(pwsh, env, prompt, cwd),
+        ShellEnum::Bash(bash) => start_unix_shell(bash, vec!["-l", "-i"], env, prompt, cwd).await,
+        ShellEnum::Zsh(zsh) => start_unix_shell(zsh, vec!["-l", "-i"], env, prompt, cwd).await,
+        ShellEnum::Fish(fish) => start_unix_shell(fish, vec![], env, prompt, cwd).await,
+        ShellEnum::Xonsh(xonsh) => start_unix_shell(xonsh, vec![], env, prompt, cwd).await,
         _ => {
             miette::bail!("Unsupported shell: {:?}", interactive_shell)
         }
-    };
-
-    match res {
-        Ok(Some(code)) => std::process::exit(code),
-        Ok(None) => std::process::exit(0),
-        Err(e) => {
-            eprintln!("Error starting shell: {}", e);
-            std::process::exit(1);
-        }
     }
 }
@@ -1,6 +1,6 @@
-use std::{collections::HashMap, default::Default};
+use std::{collections::HashMap, default::Default, ffi::OsStr, fmt::Write, path::PathBuf};
 
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use miette::IntoDiagnostic;
 use pixi_config::{ConfigCliActivation, ConfigCliPrompt};
 use rattler_lock::LockFile;
@@ -16,6 +16,7 @@ use crate::environment::get_update_lock_file_and_prefix;
 use crate::{
     activation::get_activator,
     cli::cli_config::{PrefixUpdateConfig, ProjectConfig},
+    lock_file::environment_lock_file_candidates,
     project::{Environment, HasProjectRef},
     Project, UpdateLockFileOptions,
 };
@@ -44,14 +45,30 @@ pub struct Args {
     #[arg(long, short)]
     environment: Option<String>,
 
-    /// Emit the environment variables set by running the activation as JSON
-    #[clap(long, default_value = "false", conflicts_with = "shell")]
+    /// Emit the environment variables set by running the activation as JSON,
+    /// the same as `--format json`
+    #[clap(long, default_value = "false", conflicts_with_all = ["shell", "format"])]
     json: bool,
 
+    /// The format of the output
+    #[clap(long, value_enum, default_value_t, conflicts_with = "shell")]
+    format: ShellHookFormat,
+
     #[clap(flatten)]
     prompt_config: ConfigCliPrompt,
 }
 
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ShellHookFormat {
+    /// The activation script of the shell
+    #[default]
+    Shell,
+    /// The environment variables set by running the activation as JSON
+    Json,
+    /// A snippet for the `.envrc` of direnv
+    Direnv,
+}
+
 #[derive(Serialize)]
 struct ShellEnv<'a> {
     environment_variables: &'a HashMap<String, String>,
@@ -115,6 +132,78 @@ async fn generate_environment_json(
     serde_json::to_string(&shell_env).into_diagnostic()
 }
 
+/// Generates a snippet for the `.envrc` of direnv that exports the variables
+/// of the activated environment. The directories prepended to the `PATH` are
+/// added with `PATH_add`, so direnv can restore the `PATH` when leaving the
+/// directory.
+async fn generate_direnv_snippet(
+    environment: &Environment<'_>,
+    lock_file: &LockFile,
+    force_activate: bool,
+    experimental_cache: bool,
+) -> miette::Result<String> {
+    let project = environment.project();
+    let environment_variables = project
+        .get_activated_environment_variables(
+            environment,
+            CurrentEnvVarBehavior::Exclude,
+            Some(lock_file),
+            force_activate,
+            experimental_cache,
+        )
+        .await?;
+
+    let watch_files = std::iter::once(project.manifest_path())
+        .chain(environment_lock_file_candidates(environment))
+        .collect::<Vec<_>>();
+    render_direnv_snippet(
+        &watch_files,
+        environment_variables,
+        std::env::var_os("PATH").as_deref(),
+    )
+}
+
+/// Renders the direnv snippet, `current_path` is the `PATH` the activation
+/// prepended its directories to.
+fn render_direnv_snippet(
+    watch_files: &[PathBuf],
+    environment_variables: &HashMap<String, String>,
+    current_path: Option<&OsStr>,
+) -> miette::Result<String> {
+    let quote = |value: &str| shlex::try_quote(value).map(|quoted| quoted.into_owned());
+
+    let mut snippet = String::new();
+    for path in watch_files {
+        let path = quote(&path.to_string_lossy()).into_diagnostic()?;
+        writeln!(snippet, "watch_file {path}").unwrap();
+    }
+
+    if let Some(path) = environment_variables.get("PATH") {
+        let current_paths: Vec<PathBuf> = current_path
+            .map(|path| std::env::split_paths(path).collect())
+            .unwrap_or_default();
+        let added_paths: Vec<PathBuf> = std::env::split_paths(path)
+            .filter(|path| !current_paths.contains(path))
+            .collect();
+        // `PATH_add` prepends, so the first directory is added last
+        for path in added_paths.iter().rev() {
+            let path = quote(&path.to_string_lossy()).into_diagnostic()?;
+            writeln!(snippet, "PATH_add {path}").unwrap();
+        }
+    }
+
+    let mut variables: Vec<_> = environment_variables
+        .iter()
+        .filter(|(name, _)| name.as_str() != "PATH")
+        .collect();
+    variables.sort();
+    for (name, value) in variables {
+        writeln!(snippet, "export {name}={}", quote(value).into_diagnostic()?).unwrap();
+    }
+
+    Ok(snippet)
+}
+
 /// Prints the activation script to the stdout.
 pub async fn execute(args: Args) -> miette::Result<()> {
     let config = args
@@ -135,20 +224,37 @@ pub async fn execute(args: Args) -> miette::Result<()> {
         },
     )
     .await?;
+    if !args.prefix_update_config.no_install() {
+        lock_file_data.sync_external_managers(&environment).await?;
+    }
 
-    let output = match args.json {
-        true => {
+    let format = if args.json {
+        ShellHookFormat::Json
+    } else {
+        args.format
+    };
+    let output = match format {
+        ShellHookFormat::Json => {
             generate_environment_json(
                 &environment,
                 &lock_file_data.lock_file,
                 project.config().force_activate(),
-                project.config().experimental_activation_cache_usage(),
+                project.config().activation_cache(),
+            )
+            .await?
+        }
+        ShellHookFormat::Direnv => {
+            generate_direnv_snippet(
+                &environment,
+                &lock_file_data.lock_file,
+                project.config().force_activate(),
+                project.config().activation_cache(),
             )
             .await?
         }
         // Skipping the activated environment caching for the script.
         // As it can still run scripts.
-        false => generate_activation_script(args.shell, &environment).await?,
+        ShellHookFormat::Shell => generate_activation_script(args.shell, &environment).await?,
     };
 
     // Print the output - either a JSON object or a shell script
@@ -215,4 +321,33 @@ mod tests {
         assert!(script.contains(&format!("$env.{path_var_name} = ")));
         assert!(script.contains("$env.CONDA_PREFIX = "));
     }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_render_direnv_snippet() {
+        let environment_variables = HashMap::from([
+            (
+                "PATH".to_string(),
+                "/project/.pixi/envs/default/bin:/usr/bin".to_string(),
+            ),
+            (
+                "CONDA_PREFIX".to_string(),
+                "/project/.pixi/envs/default".to_string(),
+            ),
+            ("PIXI_PROMPT".to_string(), "(my project) ".to_string()),
+        ]);
+        let snippet = render_direnv_snippet(
+            &[PathBuf::from("/project/pixi.toml")],
+            &environment_variables,
+            Some(OsStr::new("/usr/bin")),
+        )
+        .unwrap();
+        assert_eq!(
+            snippet,
+            "watch_file /project/pixi.toml\n\
+             PATH_add /project/.pixi/envs/default/bin\n\
+             export CONDA_PREFIX=/project/.pixi/envs/default\n\
+             export PIXI_PROMPT='(my project) '\n"
+        );
+    }
 }
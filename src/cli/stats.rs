@@ -0,0 +1,190 @@
+use std::{collections::BTreeMap, io::Write, time::Duration};
+
+use clap::Parser;
+use miette::IntoDiagnostic;
+use serde::Serialize;
+
+use crate::stats::{self, Phase, StatsEntry};
+
+/// Inspect the local usage statistics.
+///
+/// Statistics are only recorded when `experimental.local-stats` is enabled in
+/// the configuration, and they never leave this machine.
+#[derive(Parser, Debug)]
+pub struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Parser, Debug)]
+pub enum Command {
+    /// Summarize where the recorded commands spend their time.
+    Show(ShowArgs),
+}
+
+#[derive(Parser, Debug)]
+pub struct ShowArgs {
+    /// Only summarize the invocations of this command, e.g. `install` or
+    /// `global install`.
+    #[arg(long)]
+    pub command: Option<String>,
+
+    /// Output the summary in json format
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// The summary of all the recorded invocations of a command.
+#[derive(Debug, Default, Serialize)]
+struct CommandSummary {
+    runs: usize,
+    failed: usize,
+    solves: usize,
+    duration_ms: u64,
+    phases_ms: BTreeMap<Phase, u64>,
+}
+
+impl CommandSummary {
+    fn add(&mut self, entry: &StatsEntry) {
+        self.runs += 1;
+        if !entry.success {
+            self.failed += 1;
+        }
+        self.solves += entry.solves;
+        self.duration_ms += entry.duration_ms;
+        for (phase, ms) in &entry.phases_ms {
+            *self.phases_ms.entry(*phase).or_default() += ms;
+        }
+    }
+}
+
+/// Groups the entries by command.
+fn summarize<'a>(
+    entries: impl IntoIterator<Item = &'a StatsEntry>,
+) -> BTreeMap<String, CommandSummary> {
+    let mut summaries = BTreeMap::<String, CommandSummary>::new();
+    for entry in entries {
+        summaries
+            .entry(entry.command.clone())
+            .or_default()
+            .add(entry);
+    }
+    summaries
+}
+
+fn format_ms(ms: u64) -> String {
+    let duration = Duration::from_millis(ms);
+    if duration.as_secs() >= 60 {
+        format!("{}m {}s", duration.as_secs() / 60, duration.as_secs() % 60)
+    } else {
+        format!("{:.1}s", duration.as_secs_f64())
+    }
+}
+
+pub fn execute(args: Args) -> miette::Result<()> {
+    match args.command {
+        Command::Show(args) => show(args),
+    }
+}
+
+fn show(args: ShowArgs) -> miette::Result<()> {
+    let entries = stats::read_entries()?;
+    let summaries = summarize(
+        entries
+            .iter()
+            .filter(|entry| args.command.as_ref().map_or(true, |c| &entry.command == c)),
+    );
+
+    if args.json {
+        let json = serde_json::to_string_pretty(&summaries).into_diagnostic()?;
+        println!("{}", json);
+        return Ok(());
+    }
+
+    if summaries.is_empty() {
+        eprintln!(
+            "No statistics recorded yet, enable them with `pixi config set --global experimental.local-stats true`"
+        );
+        return Ok(());
+    }
+
+    let mut writer = tabwriter::TabWriter::new(std::io::stdout());
+    let header_style = console::Style::new().bold();
+    write!(
+        writer,
+        "{}\t{}\t{}\t{}\t{}",
+        header_style.apply_to("Command"),
+        header_style.apply_to("Runs"),
+        header_style.apply_to("Failed"),
+        header_style.apply_to("Total"),
+        header_style.apply_to("Average"),
+    )
+    .into_diagnostic()?;
+    for phase in Phase::ALL {
+        write!(writer, "\t{}", header_style.apply_to(phase.as_str())).into_diagnostic()?;
+    }
+    writeln!(writer).into_diagnostic()?;
+
+    for (command, summary) in &summaries {
+        write!(
+            writer,
+            "{}\t{}\t{}\t{}\t{}",
+            command,
+            summary.runs,
+            summary.failed,
+            format_ms(summary.duration_ms),
+            format_ms(summary.duration_ms / summary.runs as u64),
+        )
+        .into_diagnostic()?;
+        for phase in Phase::ALL {
+            match summary.phases_ms.get(&phase) {
+                Some(ms) => write!(writer, "\t{}", format_ms(*ms)).into_diagnostic()?,
+                None => write!(writer, "\t-").into_diagnostic()?,
+            }
+        }
+        writeln!(writer).into_diagnostic()?;
+    }
+    writer.flush().into_diagnostic()?;
+
+    if let Some(path) = stats::stats_file_path() {
+        eprintln!(
+            "\nPhases can run concurrently, their sum can exceed the total. Recorded in '{}'",
+            path.display()
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summarize() {
+        let entries: Vec<StatsEntry> = [
+            r#"{"timestamp":"2025-01-01T12:00:00Z","pixi_version":"0.40.0","command":"install","success":true,"duration_ms":1500,"solves":2,"phases_ms":{"solve":700,"link":200}}"#,
+            r#"{"timestamp":"2025-01-01T12:01:00Z","pixi_version":"0.40.0","command":"install","success":false,"duration_ms":500,"solves":1,"phases_ms":{"solve":300,"download":100}}"#,
+            r#"{"timestamp":"2025-01-01T12:02:00Z","pixi_version":"0.40.0","command":"run","success":true,"duration_ms":100}"#,
+        ]
+        .iter()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+
+        let summaries = summarize(&entries);
+        let install = &summaries["install"];
+        assert_eq!(install.runs, 2);
+        assert_eq!(install.failed, 1);
+        assert_eq!(install.solves, 3);
+        assert_eq!(install.duration_ms, 2000);
+        assert_eq!(install.phases_ms[&Phase::Solve], 1000);
+        assert_eq!(install.phases_ms[&Phase::Download], 100);
+        assert_eq!(summaries["run"].phases_ms.len(), 0);
+    }
+
+    #[test]
+    fn test_format_ms() {
+        assert_eq!(format_ms(1300), "1.3s");
+        assert_eq!(format_ms(125_000), "2m 5s");
+    }
+}
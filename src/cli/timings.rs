@@ -0,0 +1,255 @@
+//! Collects the duration of the internal phases of a command, like solving
+//! and installing environments, from the tracing spans that mark them. Used by
+//! the global `--timings` flag.
+
+use std::{
+    fmt,
+    io::Write,
+    sync::{Arc, OnceLock},
+    time::{Duration, Instant},
+};
+
+use parking_lot::Mutex;
+use tracing::{
+    field::{Field, Visit},
+    span::{Attributes, Id},
+    Metadata, Subscriber,
+};
+use tracing_subscriber::{layer::Context, registry::LookupSpan, Layer};
+
+/// The names of the spans that mark a phase, and how the phase is shown.
+const PHASES: &[(&str, &str)] = &[
+    ("load_config", "config load"),
+    ("parse_manifest", "manifest parse"),
+    ("fetch_repodata", "repodata fetch"),
+    ("resolve_conda", "solve conda"),
+    ("resolve_pypi", "solve pypi"),
+    ("install_environment", "install"),
+    ("activation", "activation"),
+];
+
+/// The layer of this invocation, if `--timings` was passed.
+static TIMINGS: OnceLock<TimingsLayer> = OnceLock::new();
+
+/// Starts recording the timings of this invocation and returns the layer that
+/// records them.
+pub(crate) fn enable() -> TimingsLayer {
+    TIMINGS.get_or_init(TimingsLayer::new).clone()
+}
+
+/// Writes the recorded timings to stderr, if they are recorded. Failing to
+/// write them doesn't fail the command.
+pub(crate) fn report() {
+    if let Some(timings) = TIMINGS.get() {
+        report_to(timings, std::io::stderr());
+    }
+}
+
+fn report_to(timings: &TimingsLayer, writer: impl Write) {
+    if let Err(e) = timings.write(writer) {
+        tracing::warn!("failed to write the timings: {e}");
+    }
+}
+
+/// Writes the recorded timings and exits the process with the given code.
+/// Used instead of [`std::process::exit`] by commands that exit early.
+pub(crate) fn exit(code: i32) -> ! {
+    report();
+    std::process::exit(code)
+}
+
+/// Returns true if the span marks a phase that is timed.
+pub(crate) fn is_timed(metadata: &Metadata<'_>) -> bool {
+    metadata.is_span() && PHASES.iter().any(|(name, _)| *name == metadata.name())
+}
+
+/// The duration of a single occurrence of a phase.
+#[derive(Debug)]
+struct Timing {
+    phase: &'static str,
+    /// The values of the fields of the span, e.g. the environment and the
+    /// platform.
+    label: String,
+    started: Instant,
+    duration: Duration,
+}
+
+/// Stored in the extensions of a span while it's open.
+struct SpanStart {
+    started: Instant,
+    label: String,
+}
+
+/// Joins the values of the fields of a span.
+#[derive(Default)]
+struct LabelVisitor(Vec<String>);
+
+impl Visit for LabelVisitor {
+    fn record_str(&mut self, _field: &Field, value: &str) {
+        self.0.push(value.to_string());
+    }
+
+    fn record_debug(&mut self, _field: &Field, value: &dyn fmt::Debug) {
+        self.0.push(format!("{:?}", value));
+    }
+}
+
+/// A tracing layer that records the duration of the timed spans.
+#[derive(Clone)]
+pub(crate) struct TimingsLayer {
+    started: Instant,
+    timings: Arc<Mutex<Vec<Timing>>>,
+}
+
+impl TimingsLayer {
+    pub(crate) fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            timings: Arc::default(),
+        }
+    }
+
+    /// Writes the recorded phases in the order they started, followed by the
+    /// total duration of the command.
+    pub(crate) fn write(&self, writer: impl Write) -> std::io::Result<()> {
+        let mut timings = self.timings.lock();
+        timings.sort_by_key(|timing| timing.started);
+
+        let mut writer = tabwriter::TabWriter::new(writer);
+        let header_style = console::Style::new().bold();
+        writeln!(writer, "{}", header_style.apply_to("Timings"))?;
+        for timing in timings.iter() {
+            let phase = PHASES
+                .iter()
+                .find(|(name, _)| *name == timing.phase)
+                .map_or(timing.phase, |(_, phase)| phase);
+            writeln!(
+                writer,
+                "  {}\t{}\t{:.2?}",
+                phase, timing.label, timing.duration
+            )?;
+        }
+        writeln!(
+            writer,
+            "  {}\t\t{:.2?}",
+            header_style.apply_to("total"),
+            self.started.elapsed()
+        )?;
+        writer.flush()
+    }
+}
+
+impl<S> Layer<S> for TimingsLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut visitor = LabelVisitor::default();
+        attrs.record(&mut visitor);
+        span.extensions_mut().insert(SpanStart {
+            started: Instant::now(),
+            label: visitor.0.join(" "),
+        });
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+        let Some(start) = span.extensions_mut().remove::<SpanStart>() else {
+            return;
+        };
+        self.timings.lock().push(Timing {
+            phase: span.name(),
+            label: start.label,
+            started: start.started,
+            duration: start.started.elapsed(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tracing_subscriber::{filter::filter_fn, prelude::*};
+
+    use super::*;
+
+    #[test]
+    fn test_timings_layer() {
+        let layer = TimingsLayer::new();
+        let subscriber =
+            tracing_subscriber::registry().with(layer.clone().with_filter(filter_fn(is_timed)));
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info_span!("resolve_conda", group = "default", platform = "linux-64")
+                .in_scope(|| {
+                    tracing::info_span!("untimed").in_scope(|| {});
+                });
+            tracing::info_span!("activation", environment = "test").in_scope(|| {});
+        });
+
+        let timings = layer.timings.lock();
+        let phases = timings
+            .iter()
+            .map(|timing| (timing.phase, timing.label.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            phases,
+            vec![
+                ("resolve_conda", "default linux-64"),
+                ("activation", "test")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_write_timings() {
+        let layer = TimingsLayer::new();
+        let subscriber =
+            tracing_subscriber::registry().with(layer.clone().with_filter(filter_fn(is_timed)));
+        tracing::subscriber::with_default(subscriber, || {
+            let install = tracing::info_span!("install_environment", environment = "default");
+            install.in_scope(|| {
+                tracing::info_span!("activation", environment = "default").in_scope(|| {});
+            });
+            // The install closes after the activation, but started before it.
+            drop(install);
+        });
+
+        let mut output = Vec::new();
+        layer.write(&mut output).unwrap();
+        let output = console::strip_ansi_codes(std::str::from_utf8(&output).unwrap()).to_string();
+        let lines = output
+            .lines()
+            .map(|line| line.split_whitespace().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], ["Timings"]);
+        assert_eq!(lines[1][..2], ["install", "default"]);
+        assert_eq!(lines[2][..2], ["activation", "default"]);
+        assert_eq!(lines[3][0], "total");
+    }
+
+    /// A writer that always fails.
+    struct FailingWriter;
+
+    impl Write for FailingWriter {
+        fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::ErrorKind::BrokenPipe.into())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Err(std::io::ErrorKind::BrokenPipe.into())
+        }
+    }
+
+    #[test]
+    fn test_report_ignores_write_errors() {
+        let layer = TimingsLayer::new();
+        assert!(layer.write(FailingWriter).is_err());
+        // Reporting only logs the error.
+        report_to(&layer, FailingWriter);
+    }
+}
@@ -11,11 +11,15 @@ use itertools::Itertools;
 use miette::{IntoDiagnostic, WrapErr};
 use pixi_manifest::FeaturesExt;
 use rattler_conda_types::Platform;
-use rattler_lock::LockedPackageRef;
+use rattler_lock::{LockFile, LockedPackageRef};
 use regex::Regex;
+use serde::Serialize;
 
 use crate::{
-    cli::cli_config::{PrefixUpdateConfig, ProjectConfig},
+    cli::{
+        cli_config::{PrefixUpdateConfig, ProjectConfig},
+        explain,
+    },
     lock_file::UpdateLockFileOptions,
     project::{Environment, Project},
 };
@@ -54,8 +58,22 @@ pub struct Args {
     pub prefix_update_config: PrefixUpdateConfig,
 
     /// Invert tree and show what depends on given package in the regex argument
-    #[arg(short, long, requires = "regex")]
+    #[arg(short, long, visible_alias = "why", requires = "regex")]
     pub invert: bool,
+
+    /// Show the packages that the environments in the solve-group of the
+    /// environment share, with the requirement each environment places on
+    /// them
+    #[arg(long, conflicts_with = "invert")]
+    pub solve_group: bool,
+
+    /// The number of levels of the tree to show below the top-level packages
+    #[arg(long, conflicts_with = "solve_group")]
+    pub depth: Option<usize>,
+
+    /// Output the tree in json format
+    #[arg(long, conflicts_with = "solve_group")]
+    pub json: bool,
 }
 
 struct Symbols {
@@ -90,6 +108,22 @@ pub async fn execute(args: Args) -> miette::Result<()> {
         .wrap_err("Failed to update lock file")?;
 
     let platform = args.platform.unwrap_or_else(|| environment.best_platform());
+
+    if args.solve_group {
+        let stdout = std::io::stdout();
+        let mut handle = stdout.lock();
+        print_solve_group_tree(
+            &mut handle,
+            &environment,
+            platform,
+            &lock_file.lock_file,
+            &args.regex,
+        )
+        .wrap_err("Couldn't print the solve-group tree")?;
+        Project::warn_on_discovered_from_env(args.project_config.manifest_path.as_deref());
+        return Ok(());
+    }
+
     let locked_deps = lock_file
         .lock_file
         .environment(environment.name().as_str())
@@ -100,6 +134,22 @@ pub async fn execute(args: Args) -> miette::Result<()> {
 
     let direct_deps = direct_dependencies(&environment, &platform, &dep_map);
 
+    if args.json {
+        let tree = if args.invert {
+            let inverted_dep_map = invert_dep_map(&dep_map);
+            let roots = inverted_tree_roots(&inverted_dep_map, &args.regex)?;
+            json_tree(&roots, &inverted_dep_map, &direct_deps, true, args.depth)
+        } else {
+            let roots = dependency_tree_roots(&dep_map, &direct_deps, &args.regex)?.into_names();
+            json_tree(&roots, &dep_map, &direct_deps, false, args.depth)
+        };
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&tree).expect("the tree can be serialized")
+        );
+        return Ok(());
+    }
+
     if !environment.is_default() {
         eprintln!("Environment: {}", environment.name().fancy_display());
     }
@@ -112,44 +162,134 @@ pub async fn execute(args: Args) -> miette::Result<()> {
             &invert_dep_map(&dep_map),
             &direct_deps,
             &args.regex,
+            args.depth,
         )
         .wrap_err("Couldn't print the inverted dependency tree")?;
     } else {
-        print_dependency_tree(&mut handle, &dep_map, &direct_deps, &args.regex)
+        print_dependency_tree(&mut handle, &dep_map, &direct_deps, &args.regex, args.depth)
             .wrap_err("Couldn't print the dependency tree")?;
     }
     Project::warn_on_discovered_from_env(args.project_config.manifest_path.as_deref());
     Ok(())
 }
 
-/// Filter and print an inverted dependency tree
-fn print_inverted_dependency_tree(
+/// Print the packages whose version is shared by the environments of a
+/// solve-group, together with the requirement every environment places on
+/// them.
+fn print_solve_group_tree(
     handle: &mut StdoutLock,
-    inverted_dep_map: &HashMap<String, Package>,
-    direct_deps: &HashSet<String>,
+    environment: &Environment<'_>,
+    platform: Platform,
+    lock_file: &LockFile,
     regex: &Option<String>,
 ) -> miette::Result<()> {
-    let regex = regex
-        .as_ref()
-        .ok_or_else(|| miette::miette!("The -i flag requires a package name."))?;
+    let Some(solve_group) = environment.solve_group() else {
+        miette::bail!(
+            "environment {} is not part of a solve-group",
+            environment.name().fancy_display()
+        );
+    };
 
-    let regex = Regex::new(regex)
+    let regex = regex
+        .as_deref()
+        .map(Regex::new)
+        .transpose()
         .into_diagnostic()
         .wrap_err("Invalid regular expression")?;
 
-    let root_pkg_names: Vec<_> = inverted_dep_map
-        .keys()
-        .filter(|p| regex.is_match(p))
-        .collect();
+    // The locked packages and the direct dependencies of every environment in
+    // the group.
+    let environments = solve_group
+        .environments()
+        .map(|env| {
+            let locked_deps = lock_file
+                .environment(env.name().as_str())
+                .and_then(|env| env.packages(platform).map(Vec::from_iter))
+                .unwrap_or_default();
+            let dep_map = generate_dependency_map(&locked_deps);
+            let direct_deps = explain::direct_requirements(&env, platform);
+            (env, dep_map, direct_deps)
+        })
+        .collect_vec();
+
+    // The direct dependencies that are locked in more than one environment,
+    // their version is pinned by the whole group.
+    let shared = environments
+        .iter()
+        .flat_map(|(_, _, direct_deps)| direct_deps.keys())
+        .unique()
+        .filter(|name| regex.as_ref().map_or(true, |regex| regex.is_match(name)))
+        .filter(|name| {
+            environments
+                .iter()
+                .filter(|(_, dep_map, _)| dep_map.contains_key(*name))
+                .count()
+                > 1
+        })
+        .sorted()
+        .collect_vec();
 
-    if root_pkg_names.is_empty() {
-        return Err(miette::miette!(
-            "Nothing depends on the given regular expression"
-        ));
+    eprintln!("Solve-group: {}", console::style(solve_group.name()).bold());
+    if shared.is_empty() {
+        eprintln!("No dependencies are shared between the environments of the solve-group");
+        return Ok(());
     }
 
+    for (index, name) in shared.iter().enumerate() {
+        let last = index == shared.len() - 1;
+        let (symbol, prefix) = if last {
+            (UTF8_SYMBOLS.ell, UTF8_SYMBOLS.empty)
+        } else {
+            (UTF8_SYMBOLS.tee, UTF8_SYMBOLS.down)
+        };
+
+        let Some(pkg) = environments
+            .iter()
+            .find_map(|(_, dep_map, _)| dep_map.get(*name))
+        else {
+            continue;
+        };
+        print_package(handle, &format!("{symbol} "), pkg, true, false)?;
+
+        let locked_in = environments
+            .iter()
+            .filter(|(_, dep_map, _)| dep_map.contains_key(*name))
+            .collect_vec();
+        for (env_index, (env, _, direct_deps)) in locked_in.iter().enumerate() {
+            let env_symbol = if env_index == locked_in.len() - 1 {
+                UTF8_SYMBOLS.ell
+            } else {
+                UTF8_SYMBOLS.tee
+            };
+            let requirement = match direct_deps.get(*name) {
+                Some(spec) => format!("requires {}", console::style(spec).dim()),
+                None => console::style("(transitive)").dim().to_string(),
+            };
+            writeln!(
+                handle,
+                "{prefix} {env_symbol} {} {requirement}",
+                env.name().fancy_display()
+            )
+            .into_diagnostic()
+            .wrap_err("Failed to write package information")?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Filter and print an inverted dependency tree
+fn print_inverted_dependency_tree(
+    handle: &mut StdoutLock,
+    inverted_dep_map: &HashMap<String, Package>,
+    direct_deps: &HashSet<String>,
+    regex: &Option<String>,
+    depth: Option<usize>,
+) -> miette::Result<()> {
+    let root_pkg_names = inverted_tree_roots(inverted_dep_map, regex)?;
+
     let mut visited_pkgs = HashSet::new();
-    for pkg_name in root_pkg_names {
+    for pkg_name in &root_pkg_names {
         if let Some(pkg) = inverted_dep_map.get(pkg_name) {
             let visited = !visited_pkgs.insert(pkg_name.clone());
             print_package(handle, "\n", pkg, direct_deps.contains(&pkg.name), visited)?;
@@ -162,6 +302,7 @@ fn print_inverted_dependency_tree(
                     inverted_dep_map,
                     direct_deps,
                     &mut visited_pkgs,
+                    depth,
                 )?;
             }
         }
@@ -170,7 +311,36 @@ fn print_inverted_dependency_tree(
     Ok(())
 }
 
-/// Recursively print inverted dependency tree leaf nodes
+/// Returns the names of the packages an inverted tree starts with, the
+/// packages matching the regex.
+fn inverted_tree_roots(
+    inverted_dep_map: &HashMap<String, Package>,
+    regex: &Option<String>,
+) -> miette::Result<Vec<String>> {
+    let regex = regex
+        .as_ref()
+        .ok_or_else(|| miette::miette!("The -i flag requires a package name."))?;
+
+    let regex = Regex::new(regex)
+        .into_diagnostic()
+        .wrap_err("Invalid regular expression")?;
+
+    let root_pkg_names = inverted_dep_map
+        .keys()
+        .filter(|p| regex.is_match(p))
+        .cloned()
+        .collect_vec();
+
+    if root_pkg_names.is_empty() {
+        return Err(miette::miette!(
+            "Nothing depends on the given regular expression"
+        ));
+    }
+    Ok(root_pkg_names)
+}
+
+/// Recursively print inverted dependency tree leaf nodes, up to `depth`
+/// levels deep
 fn print_inverted_leaf(
     handle: &mut StdoutLock,
     pkg: &Package,
@@ -178,7 +348,12 @@ fn print_inverted_leaf(
     inverted_dep_map: &HashMap<String, Package>,
     direct_deps: &HashSet<String>,
     visited_pkgs: &mut HashSet<String>,
+    depth: Option<usize>,
 ) -> miette::Result<()> {
+    if depth == Some(0) {
+        return Ok(());
+    }
+
     let needed_count = pkg.needed_by.len();
     for (index, needed_name) in pkg.needed_by.iter().enumerate() {
         let last = index == needed_count - 1;
@@ -212,6 +387,7 @@ fn print_inverted_leaf(
                     inverted_dep_map,
                     direct_deps,
                     visited_pkgs,
+                    depth.map(|depth| depth - 1),
                 )?;
             }
         }
@@ -225,6 +401,7 @@ fn print_transitive_dependency_tree(
     dep_map: &HashMap<String, Package>,
     direct_deps: &HashSet<String>,
     filtered_keys: Vec<String>,
+    depth: Option<usize>,
 ) -> miette::Result<()> {
     let mut visited_pkgs = HashSet::new();
 
@@ -243,6 +420,7 @@ fn print_transitive_dependency_tree(
                 dep_map,
                 &mut visited_pkgs,
                 direct_deps,
+                depth,
             )?;
         }
     }
@@ -255,31 +433,22 @@ fn print_dependency_tree(
     dep_map: &HashMap<String, Package>,
     direct_deps: &HashSet<String>,
     regex: &Option<String>,
+    depth: Option<usize>,
 ) -> miette::Result<()> {
-    let mut filtered_deps = direct_deps.clone();
-
-    if let Some(regex) = regex {
-        let regex = Regex::new(regex)
-            .into_diagnostic()
-            .wrap_err("Invalid regular expression")?;
-
-        filtered_deps.retain(|p| regex.is_match(p));
-
-        if filtered_deps.is_empty() {
-            let mut filtered_keys = dep_map.keys().cloned().collect_vec();
-            filtered_keys.retain(|p| regex.is_match(p));
-
-            if filtered_keys.is_empty() {
-                return Err(miette::miette!(
-                    "No dependencies matched the given regular expression"
-                ));
-            }
-
+    let filtered_deps = match dependency_tree_roots(dep_map, direct_deps, regex)? {
+        TreeRoots::Direct(filtered_deps) => filtered_deps,
+        TreeRoots::Transitive(filtered_keys) => {
             tracing::info!("No top-level dependencies matched the regular expression, showing matching transitive dependencies");
 
-            return print_transitive_dependency_tree(handle, dep_map, direct_deps, filtered_keys);
+            return print_transitive_dependency_tree(
+                handle,
+                dep_map,
+                direct_deps,
+                filtered_keys,
+                depth,
+            );
         }
-    }
+    };
 
     let mut visited_pkgs = HashSet::new();
     let direct_dep_count = filtered_deps.len();
@@ -316,13 +485,62 @@ fn print_dependency_tree(
                 dep_map,
                 &mut visited_pkgs,
                 direct_deps,
+                depth,
             )?;
         }
     }
     Ok(())
 }
 
-/// Recursively print top-down dependency tree nodes
+/// The packages a top-down dependency tree starts with.
+enum TreeRoots {
+    /// The direct dependencies that match the regex.
+    Direct(Vec<String>),
+    /// The transitive dependencies that match the regex, if no direct
+    /// dependency does.
+    Transitive(Vec<String>),
+}
+
+impl TreeRoots {
+    fn into_names(self) -> Vec<String> {
+        match self {
+            TreeRoots::Direct(names) | TreeRoots::Transitive(names) => names,
+        }
+    }
+}
+
+/// Returns the packages a top-down dependency tree starts with.
+fn dependency_tree_roots(
+    dep_map: &HashMap<String, Package>,
+    direct_deps: &HashSet<String>,
+    regex: &Option<String>,
+) -> miette::Result<TreeRoots> {
+    let mut filtered_deps = direct_deps.iter().cloned().collect_vec();
+
+    if let Some(regex) = regex {
+        let regex = Regex::new(regex)
+            .into_diagnostic()
+            .wrap_err("Invalid regular expression")?;
+
+        filtered_deps.retain(|p| regex.is_match(p));
+
+        if filtered_deps.is_empty() {
+            let mut filtered_keys = dep_map.keys().cloned().collect_vec();
+            filtered_keys.retain(|p| regex.is_match(p));
+
+            if filtered_keys.is_empty() {
+                return Err(miette::miette!(
+                    "No dependencies matched the given regular expression"
+                ));
+            }
+
+            return Ok(TreeRoots::Transitive(filtered_keys));
+        }
+    }
+    Ok(TreeRoots::Direct(filtered_deps))
+}
+
+/// Recursively print top-down dependency tree nodes, up to `depth` levels deep
 fn print_dependency_leaf(
     handle: &mut StdoutLock,
     pkg: &Package,
@@ -330,7 +548,12 @@ fn print_dependency_leaf(
     dep_map: &HashMap<String, Package>,
     visited_pkgs: &mut HashSet<String>,
     direct_deps: &HashSet<String>,
+    depth: Option<usize>,
 ) -> miette::Result<()> {
+    if depth == Some(0) {
+        return Ok(());
+    }
+
     let dep_count = pkg.dependencies.len();
     for (index, dep_name) in pkg.dependencies.iter().enumerate() {
         let last = index == dep_count - 1;
@@ -360,7 +583,15 @@ fn print_dependency_leaf(
             } else {
                 format!("{}{} ", prefix, UTF8_SYMBOLS.down)
             };
-            print_dependency_leaf(handle, dep, new_prefix, dep_map, visited_pkgs, direct_deps)?;
+            print_dependency_leaf(
+                handle,
+                dep,
+                new_prefix,
+                dep_map,
+                visited_pkgs,
+                direct_deps,
+                depth.map(|depth| depth - 1),
+            )?;
         } else {
             let visited = !visited_pkgs.insert(dep_name.clone());
 
@@ -408,7 +639,7 @@ fn print_package(
     .map_err(|e| {
         if e.kind() == std::io::ErrorKind::BrokenPipe {
             // Exit gracefully
-            std::process::exit(0);
+            super::timings::exit(0);
         } else {
             e
         }
@@ -452,7 +683,8 @@ fn direct_dependencies(
     project_dependency_names
 }
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
 enum PackageSource {
     Conda,
     Pypi,
@@ -571,3 +803,146 @@ fn invert_dep_map(dep_map: &HashMap<String, Package>) -> HashMap<String, Package
 
     inverted_deps
 }
+
+/// A package in the json output of the tree.
+#[derive(Debug, Serialize)]
+struct TreeNode {
+    name: String,
+    version: String,
+    source: PackageSource,
+    direct: bool,
+    /// Whether the package was already shown earlier in the tree, its
+    /// dependencies are only listed the first time.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    visited: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    dependencies: Vec<TreeNode>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    needed_by: Vec<TreeNode>,
+}
+
+/// Builds the json tree that starts at the given packages, following their
+/// dependencies or, when `invert` is set, the packages that need them.
+fn json_tree(
+    roots: &[String],
+    dep_map: &HashMap<String, Package>,
+    direct_deps: &HashSet<String>,
+    invert: bool,
+    depth: Option<usize>,
+) -> Vec<TreeNode> {
+    let mut visited_pkgs = HashSet::new();
+    roots
+        .iter()
+        .sorted()
+        .map(|name| json_node(name, dep_map, direct_deps, invert, depth, &mut visited_pkgs))
+        .collect()
+}
+
+fn json_node(
+    name: &str,
+    dep_map: &HashMap<String, Package>,
+    direct_deps: &HashSet<String>,
+    invert: bool,
+    depth: Option<usize>,
+    visited_pkgs: &mut HashSet<String>,
+) -> TreeNode {
+    let visited = !visited_pkgs.insert(name.to_string());
+    let Some(pkg) = dep_map.get(name) else {
+        // Dependencies that aren't locked, like virtual packages.
+        return TreeNode {
+            name: name.to_string(),
+            version: String::new(),
+            source: PackageSource::Conda,
+            direct: false,
+            visited,
+            dependencies: Vec::new(),
+            needed_by: Vec::new(),
+        };
+    };
+
+    let children = if visited || depth == Some(0) {
+        Vec::new()
+    } else {
+        let child_names = if invert {
+            &pkg.needed_by
+        } else {
+            &pkg.dependencies
+        };
+        child_names
+            .iter()
+            .map(|child| {
+                json_node(
+                    child,
+                    dep_map,
+                    direct_deps,
+                    invert,
+                    depth.map(|depth| depth - 1),
+                    visited_pkgs,
+                )
+            })
+            .collect()
+    };
+    let (dependencies, needed_by) = if invert {
+        (Vec::new(), children)
+    } else {
+        (children, Vec::new())
+    };
+
+    TreeNode {
+        name: pkg.name.clone(),
+        version: pkg.version.clone(),
+        source: pkg.source,
+        direct: direct_deps.contains(&pkg.name),
+        visited,
+        dependencies,
+        needed_by,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(name: &str, dependencies: &[&str]) -> (String, Package) {
+        (
+            name.to_string(),
+            Package {
+                name: name.to_string(),
+                version: "1.0".to_string(),
+                dependencies: dependencies.iter().map(ToString::to_string).collect(),
+                needed_by: Vec::new(),
+                source: PackageSource::Conda,
+            },
+        )
+    }
+
+    #[test]
+    fn test_json_tree_depth_and_invert() {
+        let dep_map: HashMap<_, _> = [
+            package("app", &["lib", "__glibc"]),
+            package("lib", &["base"]),
+            package("base", &[]),
+        ]
+        .into_iter()
+        .collect();
+        let direct_deps: HashSet<String> = ["app".to_string()].into_iter().collect();
+
+        let tree = json_tree(&["app".to_string()], &dep_map, &direct_deps, false, Some(1));
+        assert_eq!(tree.len(), 1);
+        assert!(tree[0].direct);
+        let names = tree[0]
+            .dependencies
+            .iter()
+            .map(|node| node.name.as_str())
+            .collect_vec();
+        assert_eq!(names, ["lib", "__glibc"]);
+        assert!(tree[0].dependencies[0].dependencies.is_empty());
+
+        let inverted_dep_map = invert_dep_map(&dep_map);
+        let roots = inverted_tree_roots(&inverted_dep_map, &Some("^base$".to_string())).unwrap();
+        let tree = json_tree(&roots, &inverted_dep_map, &direct_deps, true, None);
+        assert_eq!(tree[0].name, "base");
+        assert_eq!(tree[0].needed_by[0].name, "lib");
+        assert_eq!(tree[0].needed_by[0].needed_by[0].name, "app");
+    }
+}
@@ -1,4 +1,7 @@
-use std::{cmp::Ordering, collections::HashSet};
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+};
 
 use crate::{
     cli::cli_config::ProjectConfig,
@@ -6,7 +9,7 @@ use crate::{
 };
 use crate::{
     load_lock_file,
-    lock_file::{filter_lock_file, UpdateContext},
+    lock_file::{dependency_closure, filter_lock_file, LockedPackageName, UpdateContext},
     Project,
 };
 use clap::Parser;
@@ -15,7 +18,7 @@ use itertools::Itertools;
 use miette::{Context, IntoDiagnostic, MietteDiagnostic};
 use pixi_config::ConfigCli;
 use pixi_consts::consts;
-use pixi_manifest::EnvironmentName;
+use pixi_manifest::{EnvironmentName, ExcludeNewer};
 use rattler_conda_types::Platform;
 use rattler_lock::{LockFile, LockedPackageRef};
 
@@ -43,6 +46,11 @@ pub struct Args {
     /// Output the changes in JSON format.
     #[clap(long)]
     pub json: bool,
+
+    /// Ignore packages that were published after this date (e.g. `2024-06-01`)
+    /// or RFC 3339 timestamp, overrides the `exclude-newer` of the manifest.
+    #[clap(long)]
+    pub exclude_newer: Option<ExcludeNewer>,
 }
 
 #[derive(Parser, Debug, Default)]
@@ -50,6 +58,11 @@ pub struct UpdateSpecsArgs {
     /// The packages to update
     pub packages: Option<Vec<String>>,
 
+    /// Also update the packages the given packages depend on, all other
+    /// packages stay locked.
+    #[clap(long, short = 'r', requires = "packages")]
+    pub recursive: bool,
+
     /// The environments to update. If none is specified, all environments are
     /// updated.
     #[clap(long = "environment", short = 'e')]
@@ -62,10 +75,9 @@ pub struct UpdateSpecsArgs {
 }
 
 /// A distilled version of `UpdateSpecsArgs`.
-/// TODO: In the future if we want to add `--recursive` this datastructure could
-///     be used to store information about recursive packages.
 struct UpdateSpecs {
     packages: Option<HashSet<String>>,
+    recursive: bool,
     environments: Option<HashSet<EnvironmentName>>,
     platforms: Option<HashSet<Platform>>,
 }
@@ -74,6 +86,7 @@ impl From<UpdateSpecsArgs> for UpdateSpecs {
     fn from(args: UpdateSpecsArgs) -> Self {
         Self {
             packages: args.packages.map(|args| args.into_iter().collect()),
+            recursive: args.recursive,
             environments: args.environments.map(|args| args.into_iter().collect()),
             platforms: args.platforms.map(|args| args.into_iter().collect()),
         }
@@ -83,11 +96,15 @@ impl From<UpdateSpecsArgs> for UpdateSpecs {
 impl UpdateSpecs {
     /// Returns true if the package should be relaxed according to the user
     /// input.
+    ///
+    /// When updating recursively, `dependencies` contains the packages to
+    /// update together with everything they depend on in the environment.
     fn should_relax(
         &self,
         environment_name: &EnvironmentName,
         platform: &Platform,
         package: LockedPackageRef<'_>,
+        dependencies: Option<&HashSet<LockedPackageName>>,
     ) -> bool {
         // Check if the platform is in the list of platforms to update.
         if let Some(platforms) = &self.platforms {
@@ -105,7 +122,11 @@ impl UpdateSpecs {
 
         // Check if the package is in the list of packages to update.
         if let Some(packages) = &self.packages {
-            if !packages.contains(package.name()) {
+            let relax = match dependencies {
+                Some(dependencies) => dependencies.contains(&LockedPackageName::from(package)),
+                None => packages.contains(package.name()),
+            };
+            if !relax {
                 return false;
             }
         }
@@ -124,7 +145,8 @@ impl UpdateSpecs {
 pub async fn execute(args: Args) -> miette::Result<()> {
     let config = args.config;
     let project = Project::load_or_else_discover(args.project_config.manifest_path.as_deref())?
-        .with_cli_config(config);
+        .with_cli_config(config)
+        .with_exclude_newer(args.exclude_newer);
 
     let specs = UpdateSpecs::from(args.specs);
 
@@ -266,8 +288,42 @@ fn ensure_package_exists(
 }
 
 /// Constructs a new lock-file where some of the constraints have been removed.
+///
+/// The remaining packages stay locked, so the solver only picks new versions
+/// for the relaxed packages.
 fn unlock_packages(project: &Project, lock_file: &LockFile, specs: &UpdateSpecs) -> LockFile {
+    let dependencies = recursive_dependencies(lock_file, specs);
     filter_lock_file(project, lock_file, |env, platform, package| {
-        !specs.should_relax(env.name(), &platform, package)
+        let dependencies = dependencies.get(&(env.name().as_str(), platform));
+        !specs.should_relax(env.name(), &platform, package, dependencies)
     })
 }
+
+/// Determines, per environment and platform, the packages to update together
+/// with all the packages they depend on. Returns an empty map unless the
+/// packages are updated recursively.
+fn recursive_dependencies<'lock>(
+    lock_file: &'lock LockFile,
+    specs: &UpdateSpecs,
+) -> HashMap<(&'lock str, Platform), HashSet<LockedPackageName>> {
+    let mut result = HashMap::new();
+    let Some(packages) = specs.packages.as_ref().filter(|_| specs.recursive) else {
+        return result;
+    };
+
+    for (environment_name, environment) in lock_file.environments() {
+        for (platform, locked_packages) in environment.packages_by_platform() {
+            let locked_packages = locked_packages.collect_vec();
+            let roots = locked_packages
+                .iter()
+                .filter(|package| packages.contains(package.name()))
+                .map(|package| LockedPackageName::from(*package))
+                .collect_vec();
+            result.insert(
+                (environment_name, platform),
+                dependency_closure(locked_packages, roots),
+            );
+        }
+    }
+    result
+}
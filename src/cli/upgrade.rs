@@ -1,4 +1,7 @@
 use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::io::{stdout, Write};
+use std::path::Path;
 
 use crate::cli::cli_config::ProjectConfig;
 use crate::project::{MatchSpecs, PypiDeps};
@@ -11,14 +14,23 @@ use miette::MietteDiagnostic;
 use miette::{Context, IntoDiagnostic};
 
 use super::cli_config::PrefixUpdateConfig;
-use crate::diff::LockFileJsonDiff;
+use crate::diff::{JsonPackageType, LockFileDiff, LockFileJsonDiff};
 use pep508_rs::MarkerTree;
 use pep508_rs::Requirement;
+use pixi_consts::consts;
 use pixi_manifest::FeatureName;
+use pixi_manifest::FeaturesExt;
 use pixi_manifest::PyPiRequirement;
 use pixi_manifest::SpecType;
+use pixi_progress::await_in_progress;
 use pixi_spec::PixiSpec;
+use rattler::package_cache::CacheKey;
+use rattler_conda_types::package::{AboutJson, PackageFile};
 use rattler_conda_types::{MatchSpec, StringMatcher};
+use rattler_lock::{LockedPackage, UrlOrPath};
+use reqwest_middleware::ClientWithMiddleware;
+use serde::Serialize;
+use url::Url;
 
 /// Update the version of packages to the latest possible version, disregarding the manifest version constraints
 #[derive(Parser, Debug, Default)]
@@ -37,6 +49,9 @@ pub struct Args {
     pub json: bool,
 
     /// Only show the changes that would be made, without actually updating the manifest, lock file, or environment.
+    ///
+    /// Combined with `--json` this reports the direct dependencies that would
+    /// be upgraded, which is useful for bots that open upgrade pull requests.
     #[clap(short = 'n', long)]
     pub dry_run: bool,
 }
@@ -78,6 +93,7 @@ pub async fn execute(args: Args) -> miette::Result<()> {
             match_specs,
             pypi_deps,
             IndexMap::default(),
+            IndexMap::default(),
             &args.prefix_update_config,
             &args.specs.feature,
             &[],
@@ -106,12 +122,27 @@ pub async fn execute(args: Args) -> miette::Result<()> {
     // Is there something to report?
     if let Some(update_deps) = update_deps {
         let diff = update_deps.lock_file_diff;
+        let mut upgrades = PackageUpgrade::from_diff(&project, &diff);
+        if args.dry_run {
+            await_in_progress("fetching the metadata of the upgraded packages", |_| {
+                fetch_metadata(project.authenticated_client(), &mut upgrades)
+            })
+            .await;
+        }
         // Format as json?
         if args.json {
-            let json_diff = LockFileJsonDiff::new(&project, diff);
-            let json = serde_json::to_string_pretty(&json_diff).expect("failed to convert to json");
+            let report = UpgradeJsonReport {
+                diff: LockFileJsonDiff::new(&project, diff),
+                upgrades,
+            };
+            let json = serde_json::to_string_pretty(&report).expect("failed to convert to json");
             println!("{}", json);
         } else {
+            if args.dry_run {
+                print_upgrades_as_table(&upgrades)
+                    .into_diagnostic()
+                    .context("failed to print upgrade table")?;
+            }
             diff.print()
                 .into_diagnostic()
                 .context("failed to print lock-file diff")?;
@@ -127,6 +158,281 @@ pub async fn execute(args: Args) -> miette::Result<()> {
     Ok(())
 }
 
+/// The JSON output of `pixi upgrade --json`.
+///
+/// This contains the full lock-file diff together with a summary of the
+/// direct dependencies that are upgraded.
+#[derive(Serialize)]
+struct UpgradeJsonReport {
+    #[serde(flatten)]
+    diff: LockFileJsonDiff,
+    upgrades: Vec<PackageUpgrade>,
+}
+
+/// A direct dependency whose locked version changes because of the upgrade.
+#[derive(Serialize)]
+struct PackageUpgrade {
+    name: String,
+    #[serde(rename = "type")]
+    ty: JsonPackageType,
+    before: String,
+    after: String,
+    /// The `environment/platform` combinations in which this upgrade happens.
+    environments: Vec<String>,
+    /// The location of the upgraded artifact.
+    url: String,
+    /// The time the upgraded conda package was published, if recorded in
+    /// the repodata.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timestamp: Option<chrono::DateTime<chrono::Utc>>,
+    /// The homepage of the package, from the `info/about.json` of the conda
+    /// package in the package cache or from its PyPI project metadata. Only
+    /// looked up with `--dry-run`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    homepage: Option<String>,
+    /// The changelog of the package, from the project urls of its PyPI
+    /// project metadata. Conda channels don't record a changelog.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    changelog: Option<String>,
+    /// The document with the metadata of a PyPI package.
+    #[serde(skip)]
+    metadata_url: Option<Url>,
+    /// The package cache entries of the new and the old version of a conda
+    /// package, either of which may hold its metadata.
+    #[serde(skip)]
+    cache_keys: Vec<String>,
+}
+
+impl PackageUpgrade {
+    /// Extracts the upgrades of direct dependencies from a lock-file diff.
+    ///
+    /// Transitive dependencies are not included because they are not
+    /// controlled by the user, they are still part of the full lock-file diff.
+    fn from_diff(project: &Project, diff: &LockFileDiff) -> Vec<Self> {
+        let mut upgrades: IndexMap<(String, String, String), PackageUpgrade> = IndexMap::new();
+        for (environment_name, platforms) in &diff.environment {
+            let Some(environment) = project.environment(environment_name.as_str()) else {
+                continue;
+            };
+            for (platform, packages) in platforms {
+                let conda_dependencies = environment.combined_dependencies(Some(*platform));
+                let pypi_dependencies = environment.pypi_dependencies(Some(*platform));
+                for (previous, current) in &packages.changed {
+                    let upgrade = match (previous, current) {
+                        (LockedPackage::Conda(previous), LockedPackage::Conda(current)) => {
+                            if !conda_dependencies.contains_key(&current.record().name) {
+                                continue;
+                            }
+                            PackageUpgrade {
+                                name: current.record().name.as_normalized().to_string(),
+                                ty: JsonPackageType::Conda,
+                                before: previous.record().version.to_string(),
+                                after: current.record().version.to_string(),
+                                environments: Vec::new(),
+                                url: current.location().to_string(),
+                                timestamp: current.record().timestamp,
+                                homepage: None,
+                                changelog: None,
+                                metadata_url: None,
+                                cache_keys: vec![
+                                    CacheKey::from(current.record()).to_string(),
+                                    CacheKey::from(previous.record()).to_string(),
+                                ],
+                            }
+                        }
+                        (LockedPackage::Pypi(previous, _), LockedPackage::Pypi(current, _)) => {
+                            if !pypi_dependencies.contains_key(&current.name) {
+                                continue;
+                            }
+                            PackageUpgrade {
+                                name: current.name.as_dist_info_name().into_owned(),
+                                ty: JsonPackageType::Pypi,
+                                before: previous.version.to_string(),
+                                after: current.version.to_string(),
+                                environments: Vec::new(),
+                                url: current.location.to_string(),
+                                timestamp: None,
+                                homepage: None,
+                                changelog: None,
+                                metadata_url: match &current.location {
+                                    UrlOrPath::Url(url) => {
+                                        pypi_metadata_url(url, &current.name, &current.version)
+                                    }
+                                    UrlOrPath::Path(_) => None,
+                                },
+                                cache_keys: Vec::new(),
+                            }
+                        }
+                        _ => continue,
+                    };
+
+                    // Rebuilds of the same version are not considered an upgrade.
+                    if upgrade.before == upgrade.after {
+                        continue;
+                    }
+
+                    upgrades
+                        .entry((
+                            upgrade.name.clone(),
+                            upgrade.before.clone(),
+                            upgrade.after.clone(),
+                        ))
+                        .or_insert(upgrade)
+                        .environments
+                        .push(format!("{}/{}", environment_name, platform));
+                }
+            }
+        }
+
+        upgrades
+            .into_values()
+            .sorted_by(|a, b| a.name.cmp(&b.name))
+            .collect()
+    }
+}
+
+/// Returns the homepage from the `info/about.json` of the first of the
+/// packages that is extracted in the package cache. The package itself isn't
+/// downloaded, so the homepage is unknown if neither version is cached.
+fn cached_homepage(package_cache: &Path, cache_keys: &[String]) -> Option<String> {
+    cache_keys.iter().find_map(|key| {
+        let about = AboutJson::from_package_directory(package_cache.join(key))
+            .inspect_err(|err| tracing::debug!("failed to read the about.json of '{key}': {err}"))
+            .ok()?;
+        about.home.first().map(Url::to_string)
+    })
+}
+
+/// Returns the url of the PyPI JSON API of a package that is downloaded from
+/// PyPI. Other indexes don't serve this API.
+fn pypi_metadata_url(
+    location: &Url,
+    name: &pep508_rs::PackageName,
+    version: &pep440_rs::Version,
+) -> Option<Url> {
+    if location.host_str() != Some("files.pythonhosted.org") {
+        return None;
+    }
+    Url::parse(&format!("https://pypi.org/pypi/{name}/{version}/json")).ok()
+}
+
+/// Looks up the homepage and changelog of the upgraded packages. Metadata
+/// that can't be fetched is left out.
+async fn fetch_metadata(client: &ClientWithMiddleware, upgrades: &mut [PackageUpgrade]) {
+    let urls = upgrades
+        .iter()
+        .filter_map(|upgrade| upgrade.metadata_url.clone())
+        .unique()
+        .collect_vec();
+    let documents: HashMap<Url, serde_json::Value> =
+        futures::future::join_all(urls.into_iter().map(|url| async move {
+            let document = fetch_json(client, &url).await?;
+            Some((url, document))
+        }))
+        .await
+        .into_iter()
+        .flatten()
+        .collect();
+
+    let package_cache = pixi_config::get_cache_dir()
+        .ok()
+        .map(|cache_dir| cache_dir.join(consts::CONDA_PACKAGE_CACHE_DIR));
+    for upgrade in upgrades.iter_mut() {
+        if matches!(upgrade.ty, JsonPackageType::Conda) {
+            upgrade.homepage = package_cache
+                .as_deref()
+                .and_then(|package_cache| cached_homepage(package_cache, &upgrade.cache_keys));
+            continue;
+        }
+        let Some(document) = upgrade
+            .metadata_url
+            .as_ref()
+            .and_then(|url| documents.get(url))
+        else {
+            continue;
+        };
+        let info = &document["info"];
+        upgrade.homepage =
+            project_url(info, &["homepage", "home"]).or_else(|| non_empty(&info["home_page"]));
+        upgrade.changelog =
+            project_url(info, &["changelog", "changes", "release notes", "history"]);
+    }
+}
+
+/// Fetches a JSON document, returns `None` if it can't be fetched.
+async fn fetch_json(client: &ClientWithMiddleware, url: &Url) -> Option<serde_json::Value> {
+    let response = match client.get(url.clone()).send().await {
+        Ok(response) if response.status().is_success() => response,
+        Ok(response) => {
+            tracing::debug!("failed to fetch '{url}': {}", response.status());
+            return None;
+        }
+        Err(err) => {
+            tracing::debug!("failed to fetch '{url}': {err}");
+            return None;
+        }
+    };
+    let body = response
+        .bytes()
+        .await
+        .inspect_err(|err| tracing::debug!("failed to fetch '{url}': {err}"))
+        .ok()?;
+    serde_json::from_slice(&body)
+        .inspect_err(|err| tracing::debug!("failed to parse '{url}': {err}"))
+        .ok()
+}
+
+/// Returns the first of the PyPI project urls whose label matches one of the
+/// given labels, case-insensitively.
+fn project_url(info: &serde_json::Value, labels: &[&str]) -> Option<String> {
+    let urls = info["project_urls"].as_object()?;
+    labels.iter().find_map(|label| {
+        urls.iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(label))
+            .and_then(|(_, url)| non_empty(url))
+    })
+}
+
+fn non_empty(value: &serde_json::Value) -> Option<String> {
+    value
+        .as_str()
+        .filter(|value| !value.is_empty())
+        .map(ToString::to_string)
+}
+
+/// Prints the upgrades of direct dependencies as a table.
+fn print_upgrades_as_table(upgrades: &[PackageUpgrade]) -> std::io::Result<()> {
+    if upgrades.is_empty() {
+        return Ok(());
+    }
+
+    let mut writer = tabwriter::TabWriter::new(stdout());
+    let header_style = console::Style::new().bold();
+    writeln!(
+        writer,
+        "{}\t{}\t{}\t{}\t{}\t{}",
+        header_style.apply_to("Package"),
+        header_style.apply_to("Before"),
+        header_style.apply_to("After"),
+        header_style.apply_to("Environments"),
+        header_style.apply_to("Homepage"),
+        header_style.apply_to("Url"),
+    )?;
+    for upgrade in upgrades {
+        writeln!(
+            writer,
+            "{}\t{}\t{}\t{}\t{}\t{}",
+            console::style(&upgrade.name).green().bold(),
+            upgrade.before,
+            upgrade.after,
+            upgrade.environments.iter().join(", "),
+            upgrade.homepage.as_deref().unwrap_or(""),
+            upgrade.url,
+        )?;
+    }
+    writer.flush()
+}
+
 /// Parses the specifications for dependencies from the given feature, arguments, and project.
 ///
 /// This function processes the dependencies and PyPi dependencies specified in the feature,
@@ -332,3 +638,72 @@ fn ensure_package_exists(package_name: &str, available_packages: &[String]) -> m
     }
     .into())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn test_cached_homepage() {
+        let package_cache = tempfile::tempdir().unwrap();
+        let info = package_cache
+            .path()
+            .join("numpy-1.26.4-py312h0_0")
+            .join("info");
+        fs_err::create_dir_all(&info).unwrap();
+        fs_err::write(info.join("about.json"), r#"{"home": "https://numpy.org/"}"#).unwrap();
+
+        let cache_keys = [
+            "numpy-2.0.0-py312h0_0".to_string(),
+            "numpy-1.26.4-py312h0_0".to_string(),
+        ];
+        assert_eq!(
+            cached_homepage(package_cache.path(), &cache_keys).as_deref(),
+            Some("https://numpy.org/")
+        );
+        assert_eq!(
+            cached_homepage(package_cache.path(), &cache_keys[..1]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_metadata_urls() {
+        let name = pep508_rs::PackageName::from_str("requests").unwrap();
+        let version = pep440_rs::Version::from_str("2.32.3").unwrap();
+        let location = Url::parse(
+            "https://files.pythonhosted.org/packages/f9/9b/requests-2.32.3-py3-none-any.whl",
+        )
+        .unwrap();
+        assert_eq!(
+            pypi_metadata_url(&location, &name, &version)
+                .unwrap()
+                .as_str(),
+            "https://pypi.org/pypi/requests/2.32.3/json"
+        );
+        let location = Url::parse("https://example.com/requests-2.32.3-py3-none-any.whl").unwrap();
+        assert!(pypi_metadata_url(&location, &name, &version).is_none());
+    }
+
+    #[test]
+    fn test_project_url() {
+        let info = serde_json::json!({
+            "home_page": "",
+            "project_urls": {
+                "Homepage": "https://requests.readthedocs.io",
+                "Changelog": "https://github.com/psf/requests/blob/main/HISTORY.md",
+            }
+        });
+        assert_eq!(
+            project_url(&info, &["homepage", "home"]).as_deref(),
+            Some("https://requests.readthedocs.io")
+        );
+        assert_eq!(
+            project_url(&info, &["changelog", "changes"]).as_deref(),
+            Some("https://github.com/psf/requests/blob/main/HISTORY.md")
+        );
+        assert_eq!(non_empty(&info["home_page"]), None);
+    }
+}
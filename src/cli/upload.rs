@@ -1,20 +1,46 @@
-use std::path::PathBuf;
+use std::future::Future;
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::SystemTime;
 
+use base64::{engine::general_purpose::STANDARD, Engine};
 use clap::Parser;
 use futures::TryStreamExt;
 use indicatif::HumanBytes;
-use miette::{Diagnostic, IntoDiagnostic};
-use reqwest::StatusCode;
+use miette::{Context, Diagnostic, IntoDiagnostic};
+use pixi_config::Config;
+use rattler_conda_types::{
+    package::{ArchiveType, IndexJson},
+    ChannelInfo, PackageRecord, RepoData,
+};
+use reqwest::{header::LOCATION, Method, StatusCode};
 
-use rattler_digest::{compute_file_digest, Sha256};
-use rattler_networking::AuthenticationMiddleware;
+use rattler_digest::{compute_file_digest, Md5, Sha256};
+use rattler_networking::{
+    retry_policies::{default_retry_policy, RetryDecision, RetryPolicy},
+    AuthenticationMiddleware, AuthenticationStorage,
+};
 use thiserror::Error;
-use tokio::fs::File;
+use tokio::{fs::File, io::AsyncSeekExt};
 use tokio_util::io::ReaderStream;
+use url::Url;
 
 use pixi_progress;
 
+use crate::signing::signature_path;
+
+/// The size of the chunks in which the package file is streamed to the
+/// server.
+const UPLOAD_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// The file that is locked while the repodata of a local channel is updated.
+const REPODATA_LOCK_FILE_NAME: &str = ".repodata.lock";
+
+/// The version of the tus protocol (https://tus.io) that is used for
+/// resumable uploads.
+const TUS_VERSION: &str = "1.0.0";
+
 #[allow(rustdoc::bare_urls)]
 /// Upload a conda package
 ///
@@ -22,14 +48,28 @@ use pixi_progress;
 /// Example:
 ///     pixi upload https://prefix.dev/api/v1/upload/my_channel my_package.conda
 ///
+/// A `file://` url uploads the package to a channel directory on disk and
+/// updates the repodata of the channel:
+///     pixi upload file:///srv/channels/my_channel my_package.conda
+///
 /// Use `pixi auth login` to authenticate with the server.
 #[derive(Parser, Debug)]
 pub struct Args {
-    /// The host + channel to upload to
+    /// The host + channel to upload to, or the `file://` url of a local
+    /// channel directory
     host: String,
 
     /// The file to upload
     package_file: PathBuf,
+
+    /// Validate the package file, the credentials for the host and that the
+    /// host accepts them, without uploading the package
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Don't fail when the package already exists in the channel
+    #[arg(long)]
+    skip_existing: bool,
 }
 
 /// Upload a package to a prefix.dev channel
@@ -42,6 +82,11 @@ pub async fn execute(args: Args) -> miette::Result<()> {
         .to_string();
 
     let filesize = args.package_file.metadata().into_diagnostic()?.len();
+    let index_json = validate_package(&args.package_file)?;
+
+    if let Some(channel_dir) = local_channel_dir(&args.host)? {
+        return upload_to_local_channel(&args, &channel_dir, index_json, filesize).await;
+    }
 
     println!("Uploading package to: {}", args.host);
     println!(
@@ -50,47 +95,57 @@ pub async fn execute(args: Args) -> miette::Result<()> {
         HumanBytes(filesize)
     );
 
+    let storage =
+        pixi_utils::reqwest::authentication_storage(&Config::load_global()).into_diagnostic()?;
+
+    if args.dry_run {
+        validate_credentials(&storage, &args.host)?;
+    }
+
     let client = reqwest_middleware::ClientBuilder::new(reqwest::Client::new())
-        .with_arc(Arc::new(AuthenticationMiddleware::default()))
+        .with_arc(Arc::new(AuthenticationMiddleware::new(storage)))
         .build();
 
+    if args.dry_run {
+        validate_upload(&client, &args.host).await?;
+        eprintln!(
+            "{} Package {} {} {} can be uploaded, skipped the upload because of --dry-run",
+            console::style("✔").green(),
+            index_json.name.as_source(),
+            index_json.version,
+            index_json.build
+        );
+        return Ok(());
+    }
+
     let sha256sum = format!(
         "{:x}",
         compute_file_digest::<Sha256>(&args.package_file).into_diagnostic()?
     );
 
-    let file = File::open(&args.package_file).await.into_diagnostic()?;
-
-    let progress_bar = indicatif::ProgressBar::new(filesize)
-        .with_prefix("Uploading")
-        .with_style(pixi_progress::default_bytes_style());
-
-    let reader_stream = ReaderStream::new(file)
-        .inspect_ok(move |bytes| {
-            progress_bar.inc(bytes.len() as u64);
+    // A host that supports resumable uploads continues an interrupted upload
+    // from the last byte it received, other hosts receive the package file
+    // from the start on every attempt.
+    let retry_policy = default_retry_policy();
+    let response = if supports_resumable_uploads(&client, &args.host).await {
+        upload_resumable(
+            &client,
+            &retry_policy,
+            &args,
+            &filename,
+            filesize,
+            &sha256sum,
+        )
+        .await?
+    } else {
+        send_with_retries(&retry_policy, |_| {
+            send_package(&client, &args, &filename, filesize, &sha256sum)
         })
-        .inspect_err(|e| {
-            println!("Error while uploading: {}", e);
-        });
-
-    let body = reqwest::Body::wrap_stream(reader_stream);
-
-    let response = client
-        .post(args.host.clone())
-        .header("X-File-Sha256", sha256sum)
-        .header("X-File-Name", filename)
-        .header("Content-Length", filesize)
-        .header("Content-Type", "application/octet-stream")
-        .body(body)
-        .send()
-        .await
-        .map_err(|e| UploadError::RequestFailed {
-            host: args.host.clone(),
-            source: e,
-        })?;
+        .await?
+    };
 
     match response.status() {
-        StatusCode::OK => {
+        status if status.is_success() => {
             eprintln!(
                 "{} Package uploaded successfully!",
                 console::style("✔").green()
@@ -110,6 +165,12 @@ pub async fn execute(args: Args) -> miette::Result<()> {
             }
             .into());
         }
+        StatusCode::CONFLICT if args.skip_existing => {
+            eprintln!(
+                "{} Package already exists in the channel, skipped the upload",
+                console::style("✔").green()
+            );
+        }
         StatusCode::CONFLICT => {
             return Err(UploadError::Conflict {
                 host: args.host.clone(),
@@ -130,6 +191,468 @@ pub async fn execute(args: Args) -> miette::Result<()> {
     Ok(())
 }
 
+/// Sends a request until it succeeds or fails with an error that isn't
+/// transient. The attempts, which are numbered from zero, are spaced with an
+/// exponential backoff.
+async fn send_with_retries<F, Fut>(
+    retry_policy: &impl RetryPolicy,
+    mut send: F,
+) -> Result<reqwest::Response, UploadError>
+where
+    F: FnMut(u32) -> Fut,
+    Fut: Future<Output = Result<reqwest::Response, UploadError>>,
+{
+    let start_time = SystemTime::now();
+    let mut retries = 0;
+    loop {
+        let result = send(retries).await;
+        let transient = match &result {
+            Ok(response) => is_transient_status(response.status()),
+            Err(UploadError::RequestFailed { source, .. }) => is_transient_error(source),
+            Err(_) => false,
+        };
+        if transient {
+            if let RetryDecision::Retry { execute_after } =
+                retry_policy.should_retry(start_time, retries)
+            {
+                let duration = execute_after
+                    .duration_since(SystemTime::now())
+                    .unwrap_or_default();
+                eprintln!(
+                    "{}The upload failed with a transient error, retrying in {:.1}s",
+                    console::style(console::Emoji("⚠️ ", "")).yellow(),
+                    duration.as_secs_f64()
+                );
+                tokio::time::sleep(duration).await;
+                retries += 1;
+                continue;
+            }
+        }
+        return result;
+    }
+}
+
+/// Sends the package file to the host in a single streamed request.
+async fn send_package(
+    client: &reqwest_middleware::ClientWithMiddleware,
+    args: &Args,
+    filename: &str,
+    filesize: u64,
+    sha256sum: &str,
+) -> Result<reqwest::Response, UploadError> {
+    let body = package_body(&args.package_file, 0, filesize).await?;
+
+    client
+        .post(args.host.clone())
+        .header("X-File-Sha256", sha256sum)
+        .header("X-File-Name", filename)
+        .header("Content-Length", filesize)
+        .header("Content-Type", "application/octet-stream")
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| UploadError::RequestFailed {
+            host: args.host.clone(),
+            source: e,
+        })
+}
+
+/// Returns true if the host supports resumable uploads with the tus protocol,
+/// which it announces in the response to an `OPTIONS` request.
+async fn supports_resumable_uploads(
+    client: &reqwest_middleware::ClientWithMiddleware,
+    host: &str,
+) -> bool {
+    let Ok(response) = client.request(Method::OPTIONS, host).send().await else {
+        return false;
+    };
+    response
+        .headers()
+        .get("Tus-Version")
+        .and_then(|versions| versions.to_str().ok())
+        .is_some_and(supports_tus_version)
+}
+
+/// Returns true if the comma separated list of tus versions of a server
+/// contains the version that is used by pixi.
+fn supports_tus_version(versions: &str) -> bool {
+    versions
+        .split(',')
+        .any(|version| version.trim() == TUS_VERSION)
+}
+
+/// The `Upload-Metadata` of a tus upload: the base64 encoded file name and
+/// sha256 hash of the package.
+fn upload_metadata(filename: &str, sha256sum: &str) -> String {
+    format!(
+        "filename {},sha256 {}",
+        STANDARD.encode(filename),
+        STANDARD.encode(sha256sum)
+    )
+}
+
+/// Uploads the package with the tus protocol. The upload is created first,
+/// after which the package file is sent in the following requests. When a
+/// request fails with a transient error, the server is asked how much of the
+/// file it received and the upload continues from there.
+///
+/// Returns the response to the creation of the upload if it failed, or the
+/// response to the request that sent the rest of the file.
+async fn upload_resumable(
+    client: &reqwest_middleware::ClientWithMiddleware,
+    retry_policy: &impl RetryPolicy,
+    args: &Args,
+    filename: &str,
+    filesize: u64,
+    sha256sum: &str,
+) -> Result<reqwest::Response, UploadError> {
+    let host = args.host.as_str();
+    let request_failed = |source| UploadError::RequestFailed {
+        host: host.to_string(),
+        source,
+    };
+
+    let response = send_with_retries(retry_policy, |_| async move {
+        client
+            .post(host)
+            .header("Tus-Resumable", TUS_VERSION)
+            .header("Upload-Length", filesize)
+            .header("Upload-Metadata", upload_metadata(filename, sha256sum))
+            .send()
+            .await
+            .map_err(request_failed)
+    })
+    .await?;
+    if response.status() != StatusCode::CREATED {
+        return Ok(response);
+    }
+    let location = response
+        .headers()
+        .get(LOCATION)
+        .and_then(|location| location.to_str().ok())
+        .and_then(|location| Url::parse(host).ok()?.join(location).ok())
+        .ok_or_else(|| UploadError::MissingUploadLocation {
+            host: host.to_string(),
+        })?;
+    let location = &location;
+
+    send_with_retries(retry_policy, |attempt| async move {
+        // The first attempt starts at the beginning of the file, retries
+        // continue from the last byte that the server received.
+        let offset = if attempt == 0 {
+            0
+        } else {
+            upload_offset(client, host, location).await?
+        };
+        let body = package_body(&args.package_file, offset, filesize).await?;
+        client
+            .patch(location.clone())
+            .header("Tus-Resumable", TUS_VERSION)
+            .header("Upload-Offset", offset)
+            .header("Content-Length", filesize - offset)
+            .header("Content-Type", "application/offset+octet-stream")
+            .body(body)
+            .send()
+            .await
+            .map_err(request_failed)
+    })
+    .await
+}
+
+/// Returns the number of bytes of the package file that the server received
+/// for a tus upload.
+async fn upload_offset(
+    client: &reqwest_middleware::ClientWithMiddleware,
+    host: &str,
+    location: &Url,
+) -> Result<u64, UploadError> {
+    let response = client
+        .head(location.clone())
+        .header("Tus-Resumable", TUS_VERSION)
+        .send()
+        .await
+        .map_err(|e| UploadError::RequestFailed {
+            host: host.to_string(),
+            source: e,
+        })?;
+    response
+        .headers()
+        .get("Upload-Offset")
+        .and_then(|offset| offset.to_str().ok())
+        .and_then(|offset| offset.parse().ok())
+        .ok_or_else(|| UploadError::MissingUploadOffset {
+            host: host.to_string(),
+        })
+}
+
+/// Streams the package file from the given offset as the body of a request,
+/// and shows the progress of the upload.
+async fn package_body(
+    package_file: &Path,
+    offset: u64,
+    filesize: u64,
+) -> Result<reqwest::Body, UploadError> {
+    let read_error = |e| UploadError::ReadPackage {
+        path: package_file.to_path_buf(),
+        source: e,
+    };
+    let mut file = File::open(package_file).await.map_err(read_error)?;
+    file.seek(SeekFrom::Start(offset))
+        .await
+        .map_err(read_error)?;
+
+    let progress_bar = indicatif::ProgressBar::new(filesize)
+        .with_prefix("Uploading")
+        .with_style(pixi_progress::default_bytes_style());
+    progress_bar.set_position(offset);
+
+    let reader_stream = ReaderStream::with_capacity(file, UPLOAD_CHUNK_SIZE)
+        .inspect_ok(move |bytes| {
+            progress_bar.inc(bytes.len() as u64);
+        })
+        .inspect_err(|e| {
+            println!("Error while uploading: {}", e);
+        });
+
+    Ok(reqwest::Body::wrap_stream(reader_stream))
+}
+
+/// Returns the channel directory if the host is the `file://` url of a local
+/// channel, or `None` if the host is an http url.
+fn local_channel_dir(host: &str) -> Result<Option<PathBuf>, UploadError> {
+    let Ok(url) = Url::parse(host) else {
+        return Ok(None);
+    };
+    match url.scheme() {
+        "http" | "https" => Ok(None),
+        "file" => url
+            .to_file_path()
+            .map(Some)
+            .map_err(|_| UploadError::UnsupportedDestination {
+                host: host.to_string(),
+            }),
+        _ => Err(UploadError::UnsupportedDestination {
+            host: host.to_string(),
+        }),
+    }
+}
+
+/// Copies the package into the subdirectory of a local channel and adds it to
+/// the `repodata.json` of that subdirectory. The channel is locked while the
+/// repodata is updated, so concurrent uploads don't drop each other's packages.
+async fn upload_to_local_channel(
+    args: &Args,
+    channel_dir: &Path,
+    index_json: IndexJson,
+    filesize: u64,
+) -> miette::Result<()> {
+    let filename = args
+        .package_file
+        .file_name()
+        .unwrap()
+        .to_string_lossy()
+        .to_string();
+
+    let record = PackageRecord::from_index_json(
+        index_json,
+        Some(filesize),
+        Some(compute_file_digest::<Sha256>(&args.package_file).into_diagnostic()?),
+        Some(compute_file_digest::<Md5>(&args.package_file).into_diagnostic()?),
+    )
+    .into_diagnostic()?;
+
+    println!("Uploading package to: {}", channel_dir.display());
+    println!(
+        "Package file:         {} ({})\n",
+        args.package_file.display(),
+        HumanBytes(filesize)
+    );
+
+    // A dry run only checks if the package can be uploaded, it neither
+    // creates nor locks the channel.
+    let _lock = if args.dry_run {
+        None
+    } else {
+        fs_err::create_dir_all(channel_dir).into_diagnostic()?;
+        let lock_path = channel_dir.join(REPODATA_LOCK_FILE_NAME);
+        let lock_file = tokio::fs::OpenOptions::new()
+            .write(true)
+            .read(true)
+            .truncate(false)
+            .create(true)
+            .open(&lock_path)
+            .await
+            .into_diagnostic()
+            .with_context(|| format!("failed to open '{}'", lock_path.display()))?;
+        let lock = pixi_utils::lock_write_with_owner(lock_file, &lock_path)
+            .await
+            .map_err(|e| e.error)
+            .into_diagnostic()
+            .with_context(|| format!("failed to lock '{}'", lock_path.display()))?;
+        Some(lock)
+    };
+
+    let subdir_dir = channel_dir.join(&record.subdir);
+    let mut repodata = read_repodata(&subdir_dir, &record.subdir)?;
+    let exists = subdir_dir.join(&filename).exists()
+        || repodata.packages.contains_key(&filename)
+        || repodata.conda_packages.contains_key(&filename);
+    if exists {
+        if args.skip_existing {
+            eprintln!(
+                "{} Package already exists in the channel, skipped the upload",
+                console::style("✔").green()
+            );
+            return Ok(());
+        }
+        return Err(UploadError::AlreadyExists {
+            path: subdir_dir.join(&filename),
+        }
+        .into());
+    }
+
+    if args.dry_run {
+        eprintln!(
+            "{} Package {} {} {} can be uploaded, skipped the upload because of --dry-run",
+            console::style("✔").green(),
+            record.name.as_source(),
+            record.version,
+            record.build
+        );
+        return Ok(());
+    }
+
+    fs_err::create_dir_all(&subdir_dir).into_diagnostic()?;
+    fs_err::copy(&args.package_file, subdir_dir.join(&filename)).into_diagnostic()?;
+
+    // The signature of the package is served next to it.
+    let signature_file = signature_path(&args.package_file);
+    if signature_file.is_file() {
+        fs_err::copy(&signature_file, signature_path(&subdir_dir.join(&filename)))
+            .into_diagnostic()?;
+    }
+    match ArchiveType::try_from(&args.package_file) {
+        Some(ArchiveType::Conda) => repodata.conda_packages.insert(filename, record),
+        _ => repodata.packages.insert(filename, record),
+    };
+    write_repodata(&subdir_dir, &repodata)?;
+
+    // Conda clients expect a `noarch` subdirectory in every channel.
+    let noarch_dir = channel_dir.join("noarch");
+    if !noarch_dir.join("repodata.json").exists() {
+        fs_err::create_dir_all(&noarch_dir).into_diagnostic()?;
+        write_repodata(&noarch_dir, &read_repodata(&noarch_dir, "noarch")?)?;
+    }
+
+    eprintln!(
+        "{} Package uploaded successfully!",
+        console::style("✔").green()
+    );
+    Ok(())
+}
+
+/// Reads the `repodata.json` of a subdirectory of a local channel, or returns
+/// an empty repodata if the subdirectory doesn't have one yet.
+fn read_repodata(subdir_dir: &Path, subdir: &str) -> miette::Result<RepoData> {
+    let path = subdir_dir.join("repodata.json");
+    if !path.exists() {
+        return Ok(RepoData {
+            info: Some(ChannelInfo {
+                subdir: subdir.to_string(),
+                base_url: None,
+            }),
+            packages: Default::default(),
+            conda_packages: Default::default(),
+            removed: Default::default(),
+            version: Some(2),
+        });
+    }
+    let contents = fs_err::read_to_string(&path).into_diagnostic()?;
+    serde_json::from_str(&contents)
+        .into_diagnostic()
+        .with_context(|| format!("failed to parse '{}'", path.display()))
+}
+
+/// Writes the `repodata.json` of a subdirectory of a local channel. The file is
+/// replaced atomically, so clients never read a partially written file.
+fn write_repodata(subdir_dir: &Path, repodata: &RepoData) -> miette::Result<()> {
+    let contents = serde_json::to_string_pretty(repodata).into_diagnostic()?;
+    let mut file = tempfile::NamedTempFile::new_in(subdir_dir).into_diagnostic()?;
+    std::io::Write::write_all(&mut file, contents.as_bytes()).into_diagnostic()?;
+    file.persist(subdir_dir.join("repodata.json"))
+        .into_diagnostic()?;
+    Ok(())
+}
+
+/// Verifies that the file is a conda package and returns its metadata.
+fn validate_package(package_file: &std::path::Path) -> Result<IndexJson, UploadError> {
+    if ArchiveType::try_from(package_file).is_none() {
+        return Err(UploadError::NotAPackage(package_file.to_path_buf()));
+    }
+    rattler_package_streaming::seek::read_package_file(package_file).map_err(|e| {
+        UploadError::InvalidPackage {
+            path: package_file.to_path_buf(),
+            source: e,
+        }
+    })
+}
+
+/// Verifies that credentials are stored for the host.
+fn validate_credentials(storage: &AuthenticationStorage, host: &str) -> Result<(), UploadError> {
+    match storage.get_by_url(host) {
+        Ok((_, Some(_))) => Ok(()),
+        Ok((_, None)) => Err(UploadError::MissingCredentials {
+            host: host.to_string(),
+        }),
+        Err(e) => Err(UploadError::InvalidHost {
+            host: host.to_string(),
+            source: e,
+        }),
+    }
+}
+
+/// Verifies that the host can be reached and accepts the credentials, without
+/// uploading the package.
+async fn validate_upload(
+    client: &reqwest_middleware::ClientWithMiddleware,
+    host: &str,
+) -> Result<(), UploadError> {
+    let response = client
+        .request(Method::OPTIONS, host)
+        .send()
+        .await
+        .map_err(|e| UploadError::RequestFailed {
+            host: host.to_string(),
+            source: e,
+        })?;
+    match response.status() {
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Err(UploadError::Unauthorized {
+            host: host.to_string(),
+            source: response.error_for_status().unwrap_err(),
+        }),
+        status if status.is_server_error() => Err(UploadError::ServerError {
+            host: host.to_string(),
+            source: response.error_for_status().unwrap_err(),
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Returns true if a response with the given status is worth retrying.
+fn is_transient_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS
+        || (status.is_server_error() && status != StatusCode::NOT_IMPLEMENTED)
+}
+
+/// Returns true if the request failed because of an error that is worth
+/// retrying, like a dropped connection or a timeout.
+fn is_transient_error(error: &reqwest_middleware::Error) -> bool {
+    match error {
+        reqwest_middleware::Error::Reqwest(e) => e.is_connect() || e.is_timeout() || e.is_body(),
+        reqwest_middleware::Error::Middleware(_) => false,
+    }
+}
+
 #[derive(Debug, Error, Diagnostic)]
 pub enum UploadError {
     #[error("Failed to send request to {host}")]
@@ -166,10 +689,407 @@ pub enum UploadError {
     },
 
     #[error("Conflict: The package likely already exists in the channel: {host}")]
-    #[diagnostic(help("Try changing the package version or build number."))]
+    #[diagnostic(help(
+        "Try changing the package version or build number, or use `--skip-existing`."
+    ))]
     Conflict {
         host: String,
         #[source]
         source: reqwest::Error,
     },
+
+    #[error("{host} didn't return the location of the resumable upload")]
+    #[diagnostic(help("The server doesn't implement the tus protocol correctly."))]
+    MissingUploadLocation { host: String },
+
+    #[error("{host} didn't return how much of the package it received")]
+    #[diagnostic(help("The server doesn't implement the tus protocol correctly."))]
+    MissingUploadOffset { host: String },
+
+    #[error("'{}' is not a conda package", .0.display())]
+    #[diagnostic(help("Only `.conda` and `.tar.bz2` packages can be uploaded."))]
+    NotAPackage(PathBuf),
+
+    #[error("failed to read the package '{}'", path.display())]
+    ReadPackage {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("the package '{}' is invalid", path.display())]
+    InvalidPackage {
+        path: PathBuf,
+        #[source]
+        source: rattler_package_streaming::ExtractError,
+    },
+
+    #[error("no credentials are stored for {host}")]
+    #[diagnostic(help("Try logging in with `pixi auth login`."))]
+    MissingCredentials { host: String },
+
+    #[error("the package already exists in the channel at '{}'", path.display())]
+    #[diagnostic(help(
+        "Try changing the package version or build number, or use `--skip-existing`."
+    ))]
+    AlreadyExists { path: PathBuf },
+
+    #[error("uploading to {host} is not supported")]
+    #[diagnostic(help(
+        "Use the `https://` url of an upload endpoint, or the `file://` url of a local channel directory."
+    ))]
+    UnsupportedDestination { host: String },
+
+    #[error("invalid host url {host}")]
+    InvalidHost {
+        host: String,
+        #[source]
+        source: reqwest::Error,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    };
+    use std::time::Duration;
+
+    use http::Extensions;
+    use rattler_networking::retry_policies::ExponentialBackoff;
+    use reqwest::Request;
+    use reqwest_middleware::{ClientWithMiddleware, Middleware, Next};
+
+    use super::*;
+
+    const HOST: &str = "https://upload.example.com/api/v1/upload/my_channel";
+
+    /// A middleware that answers the requests instead of a server, and records
+    /// the method, path and `Upload-Offset` of every request.
+    struct MockServer<F> {
+        respond: F,
+        requests: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl<F> Middleware for MockServer<F>
+    where
+        F: Fn(&Request) -> http::Response<Vec<u8>> + Send + Sync + 'static,
+    {
+        async fn handle(
+            &self,
+            req: Request,
+            _extensions: &mut Extensions,
+            _next: Next<'_>,
+        ) -> reqwest_middleware::Result<reqwest::Response> {
+            let offset = req
+                .headers()
+                .get("Upload-Offset")
+                .map_or("-", |offset| offset.to_str().unwrap());
+            self.requests.lock().unwrap().push(format!(
+                "{} {} {}",
+                req.method(),
+                req.url().path(),
+                offset
+            ));
+            Ok(reqwest::Response::from((self.respond)(&req)))
+        }
+    }
+
+    fn mock_client<F>(respond: F) -> (ClientWithMiddleware, Arc<Mutex<Vec<String>>>)
+    where
+        F: Fn(&Request) -> http::Response<Vec<u8>> + Send + Sync + 'static,
+    {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let client = reqwest_middleware::ClientBuilder::new(reqwest::Client::new())
+            .with(MockServer {
+                respond,
+                requests: requests.clone(),
+            })
+            .build();
+        (client, requests)
+    }
+
+    fn response(status: u16) -> http::Response<Vec<u8>> {
+        http::Response::builder()
+            .status(status)
+            .body(Vec::new())
+            .unwrap()
+    }
+
+    /// A retry policy that doesn't wait between the attempts.
+    fn retry_policy(max_retries: u32) -> ExponentialBackoff {
+        ExponentialBackoff::builder()
+            .retry_bounds(Duration::from_millis(1), Duration::from_millis(1))
+            .build_with_max_retries(max_retries)
+    }
+
+    fn package_file() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join(
+            "tests/data/channels/channels/dummy_channel_1/win-64/dummy-a-0.1.0-h9490d1a_0.conda",
+        )
+    }
+
+    /// Sends requests that are answered with the given statuses in order, and
+    /// returns the final status and the number of attempts.
+    async fn send_statuses(statuses: &[u16], max_retries: u32) -> (StatusCode, usize) {
+        let attempts = AtomicUsize::new(0);
+        let result = send_with_retries(&retry_policy(max_retries), |attempt| {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            let status = statuses[attempt as usize];
+            async move { Ok(reqwest::Response::from(response(status))) }
+        })
+        .await
+        .unwrap();
+        (result.status(), attempts.load(Ordering::SeqCst))
+    }
+
+    #[tokio::test]
+    async fn test_send_with_retries() {
+        // Transient errors are retried until the request succeeds.
+        assert_eq!(
+            send_statuses(&[503, 429, 201], 3).await,
+            (StatusCode::CREATED, 3)
+        );
+
+        // Other errors are returned right away.
+        assert_eq!(
+            send_statuses(&[409, 201], 3).await,
+            (StatusCode::CONFLICT, 1)
+        );
+
+        // The last response is returned when the retries are exhausted.
+        assert_eq!(
+            send_statuses(&[503, 503, 503, 201], 2).await,
+            (StatusCode::SERVICE_UNAVAILABLE, 3)
+        );
+
+        // Errors that aren't caused by the request are not retried.
+        let attempts = AtomicUsize::new(0);
+        let result = send_with_retries(&retry_policy(3), |_| {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            async { Err(UploadError::NotAPackage(PathBuf::from("foo.txt"))) }
+        })
+        .await;
+        assert!(matches!(result, Err(UploadError::NotAPackage(_))));
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_upload_resumable() {
+        let filesize = package_file().metadata().unwrap().len();
+        let patches = AtomicUsize::new(0);
+        let (client, requests) = mock_client(move |req| match *req.method() {
+            Method::POST => {
+                assert_eq!(req.headers()["Upload-Length"], filesize.to_string());
+                http::Response::builder()
+                    .status(201)
+                    .header(LOCATION, "/uploads/1")
+                    .body(Vec::new())
+                    .unwrap()
+            }
+            // The connection drops after the server received 100 bytes.
+            Method::PATCH if patches.fetch_add(1, Ordering::SeqCst) == 0 => response(503),
+            Method::PATCH => {
+                assert_eq!(
+                    req.headers()["Content-Length"],
+                    (filesize - 100).to_string()
+                );
+                response(204)
+            }
+            Method::HEAD => http::Response::builder()
+                .status(200)
+                .header("Upload-Offset", "100")
+                .body(Vec::new())
+                .unwrap(),
+            _ => response(405),
+        });
+        let args = Args {
+            host: HOST.to_string(),
+            package_file: package_file(),
+            dry_run: false,
+            skip_existing: false,
+        };
+
+        let result = upload_resumable(
+            &client,
+            &retry_policy(3),
+            &args,
+            "dummy-a-0.1.0-h9490d1a_0.conda",
+            filesize,
+            "abc",
+        )
+        .await
+        .unwrap();
+        assert_eq!(result.status(), StatusCode::NO_CONTENT);
+
+        // The retry continues from the offset that the server received.
+        assert_eq!(
+            *requests.lock().unwrap(),
+            vec![
+                "POST /api/v1/upload/my_channel -",
+                "PATCH /uploads/1 0",
+                "HEAD /uploads/1 -",
+                "PATCH /uploads/1 100",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_upload_resumable_creation_fails() {
+        let filesize = package_file().metadata().unwrap().len();
+        let args = Args {
+            host: HOST.to_string(),
+            package_file: package_file(),
+            dry_run: false,
+            skip_existing: false,
+        };
+        let upload = |client: ClientWithMiddleware| {
+            let args = &args;
+            async move {
+                upload_resumable(
+                    &client,
+                    &retry_policy(3),
+                    args,
+                    "dummy-a-0.1.0-h9490d1a_0.conda",
+                    filesize,
+                    "abc",
+                )
+                .await
+            }
+        };
+
+        // The response is returned when the upload can't be created.
+        let (client, requests) = mock_client(|_| response(409));
+        let result = upload(client).await.unwrap();
+        assert_eq!(result.status(), StatusCode::CONFLICT);
+        assert_eq!(requests.lock().unwrap().len(), 1);
+
+        let (client, _) = mock_client(|_| response(201));
+        assert!(matches!(
+            upload(client).await,
+            Err(UploadError::MissingUploadLocation { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_validate_upload() {
+        let (client, _) = mock_client(|_| response(204));
+        validate_upload(&client, HOST).await.unwrap();
+
+        // Hosts that don't answer `OPTIONS` requests are accepted.
+        let (client, _) = mock_client(|_| response(405));
+        validate_upload(&client, HOST).await.unwrap();
+
+        let (client, _) = mock_client(|_| response(401));
+        assert!(matches!(
+            validate_upload(&client, HOST).await,
+            Err(UploadError::Unauthorized { .. })
+        ));
+
+        let (client, _) = mock_client(|_| response(500));
+        assert!(matches!(
+            validate_upload(&client, HOST).await,
+            Err(UploadError::ServerError { .. })
+        ));
+    }
+
+    #[test]
+    fn test_is_transient_status() {
+        assert!(is_transient_status(StatusCode::TOO_MANY_REQUESTS));
+        assert!(is_transient_status(StatusCode::BAD_GATEWAY));
+        assert!(is_transient_status(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(!is_transient_status(StatusCode::NOT_IMPLEMENTED));
+        assert!(!is_transient_status(StatusCode::CONFLICT));
+        assert!(!is_transient_status(StatusCode::UNAUTHORIZED));
+    }
+
+    #[test]
+    fn test_resumable_upload_headers() {
+        assert!(supports_tus_version("1.0.0"));
+        assert!(supports_tus_version("1.0.0, 0.2.2"));
+        assert!(!supports_tus_version("0.2.2"));
+        assert!(!supports_tus_version(""));
+
+        assert_eq!(
+            upload_metadata("foo-1.0-h123_0.conda", "abc"),
+            "filename Zm9vLTEuMC1oMTIzXzAuY29uZGE=,sha256 YWJj"
+        );
+    }
+
+    #[test]
+    fn test_local_channel_dir() {
+        assert!(
+            local_channel_dir("https://prefix.dev/api/v1/upload/my_channel")
+                .unwrap()
+                .is_none()
+        );
+        assert!(local_channel_dir("s3://bucket/my_channel").is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        let url = Url::from_directory_path(dir.path()).unwrap();
+        assert_eq!(
+            local_channel_dir(url.as_str()).unwrap().unwrap(),
+            dir.path()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_upload_to_local_channel() {
+        let package_file = Path::new(env!("CARGO_MANIFEST_DIR")).join(
+            "tests/data/channels/channels/dummy_channel_1/win-64/dummy-a-0.1.0-h9490d1a_0.conda",
+        );
+        let channel_dir = tempfile::tempdir().unwrap();
+        let args = |skip_existing| Args {
+            host: String::new(),
+            package_file: package_file.clone(),
+            dry_run: false,
+            skip_existing,
+        };
+        let channel_path = channel_dir.path();
+        let upload = |args: Args| async move {
+            let index_json = validate_package(&args.package_file).unwrap();
+            let filesize = args.package_file.metadata().unwrap().len();
+            upload_to_local_channel(&args, channel_path, index_json, filesize).await
+        };
+
+        upload(args(false)).await.unwrap();
+        let repodata = read_repodata(&channel_dir.path().join("win-64"), "win-64").unwrap();
+        let record = &repodata.conda_packages["dummy-a-0.1.0-h9490d1a_0.conda"];
+        assert_eq!(record.name.as_normalized(), "dummy-a");
+        assert!(record.sha256.is_some());
+        assert!(channel_dir
+            .path()
+            .join("win-64/dummy-a-0.1.0-h9490d1a_0.conda")
+            .is_file());
+        assert!(channel_dir.path().join("noarch/repodata.json").is_file());
+
+        // Uploading the same package again fails, unless it is skipped.
+        assert!(upload(args(false)).await.is_err());
+        upload(args(true)).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_upload_to_local_channel() {
+        let package_file = Path::new(env!("CARGO_MANIFEST_DIR")).join(
+            "tests/data/channels/channels/dummy_channel_1/win-64/dummy-a-0.1.0-h9490d1a_0.conda",
+        );
+        let temp_dir = tempfile::tempdir().unwrap();
+        let channel_dir = temp_dir.path().join("channel");
+        let args = Args {
+            host: String::new(),
+            package_file: package_file.clone(),
+            dry_run: true,
+            skip_existing: false,
+        };
+        let index_json = validate_package(&package_file).unwrap();
+        let filesize = package_file.metadata().unwrap().len();
+        upload_to_local_channel(&args, &channel_dir, index_json, filesize)
+            .await
+            .unwrap();
+
+        // A dry run doesn't create the channel, nor its lock file.
+        assert!(!channel_dir.exists());
+    }
 }
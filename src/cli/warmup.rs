@@ -0,0 +1,205 @@
+use clap::Parser;
+use fancy_display::FancyDisplay;
+use futures::{stream, StreamExt, TryStreamExt};
+use itertools::Itertools;
+use miette::{Context, IntoDiagnostic};
+use pixi_config::ConfigCli;
+use pixi_manifest::FeaturesExt;
+use pixi_progress::await_in_progress;
+use rattler_conda_types::{Channel, MatchSpec, Platform};
+use rattler_lock::UrlOrPath;
+use rattler_networking::retry_policies::default_retry_policy;
+
+use crate::{
+    cli::cli_config::ProjectConfig,
+    lock_file::LockFileDerivedData,
+    project::{Environment, HasProjectRef},
+    repodata::Repodata,
+    Project, UpdateLockFileOptions,
+};
+
+/// Fetch the repodata, the conda packages and the PyPI packages of the
+/// environments into the caches, without installing the environments.
+///
+/// This is useful to front-load the network work on machines that share a
+/// cache directory, like CI runners with a cache volume.
+#[derive(Parser, Debug)]
+pub struct Args {
+    #[clap(flatten)]
+    pub project_config: ProjectConfig,
+
+    #[clap(flatten)]
+    pub lock_file_usage: super::LockFileUsageArgs,
+
+    /// The environment to warm up, can be repeated. Defaults to the default
+    /// environment.
+    #[arg(long, short)]
+    pub environment: Option<Vec<String>>,
+
+    /// Warm up all the environments of the project.
+    #[arg(long, short, conflicts_with = "environment")]
+    pub all: bool,
+
+    #[clap(flatten)]
+    pub config: ConfigCli,
+}
+
+pub async fn execute(args: Args) -> miette::Result<()> {
+    let project = Project::load_or_else_discover(args.project_config.manifest_path.as_deref())?
+        .with_cli_config(args.config);
+
+    let environments = if let Some(names) = args.environment {
+        names
+            .into_iter()
+            .map(|name| project.environment_from_name_or_env_var(Some(name)))
+            .collect::<miette::Result<Vec<_>>>()?
+    } else if args.all {
+        project.environments()
+    } else {
+        vec![project.default_environment()]
+    };
+
+    let mut lock_file = project
+        .update_lock_file(UpdateLockFileOptions {
+            lock_file_usage: args.lock_file_usage.into(),
+            no_install: true,
+            max_concurrent_solves: project.config().max_concurrent_solves(),
+        })
+        .await?;
+
+    for environment in &environments {
+        fetch_repodata(environment).await?;
+        fetch_conda_packages(&lock_file, environment).await?;
+        if has_pypi_packages(&lock_file, environment) {
+            lock_file
+                .prepare_pypi_distributions(environment)
+                .await
+                .with_context(|| {
+                    format!(
+                        "failed to prepare the PyPI packages of '{}'",
+                        environment.name().fancy_display()
+                    )
+                })?;
+        }
+    }
+
+    eprintln!(
+        "{}Warmed up the caches for {}",
+        console::style(console::Emoji("✔ ", "")).green(),
+        environments
+            .iter()
+            .map(|env| env.name().fancy_display())
+            .join(", ")
+    );
+
+    Ok(())
+}
+
+/// Fetches the repodata of the dependencies of the environment for all its
+/// platforms, so re-locking the environment doesn't hit the network.
+async fn fetch_repodata(environment: &Environment<'_>) -> miette::Result<()> {
+    let project = environment.project();
+    let channel_config = project.channel_config();
+    let channels: Vec<Channel> = environment
+        .channels()
+        .into_iter()
+        .cloned()
+        .map(|channel| channel.into_channel(&channel_config))
+        .try_collect()
+        .into_diagnostic()?;
+
+    for platform in environment.platforms() {
+        let specs = environment
+            .combined_dependencies(Some(platform))
+            .names()
+            .cloned()
+            .map(MatchSpec::from)
+            .collect_vec();
+        if specs.is_empty() {
+            continue;
+        }
+
+        await_in_progress(
+            format!(
+                "fetching repodata of '{}' for {platform}",
+                environment.name().fancy_display()
+            ),
+            |_| async {
+                project
+                    .repodata_gateway()
+                    .query(channels.clone(), [platform, Platform::NoArch], specs)
+                    .recursive(true)
+                    .await
+                    .into_diagnostic()
+            },
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// Downloads the locked conda packages of the environment for the current
+/// platform into the package cache.
+async fn fetch_conda_packages(
+    lock_file: &LockFileDerivedData<'_>,
+    environment: &Environment<'_>,
+) -> miette::Result<()> {
+    let Some(locked_environment) = lock_file.lock_file.environment(environment.name().as_str())
+    else {
+        return Ok(());
+    };
+
+    // Source packages are built when the environment is installed, and local
+    // packages don't have to be downloaded.
+    let packages = locked_environment
+        .conda_packages(environment.best_platform())
+        .into_iter()
+        .flatten()
+        .filter_map(|package| package.as_binary())
+        .filter_map(|binary| match &binary.location {
+            UrlOrPath::Url(url) => Some((binary, url.clone())),
+            UrlOrPath::Path(_) => None,
+        })
+        .collect_vec();
+
+    let client = environment.project().authenticated_client();
+    await_in_progress(
+        format!(
+            "downloading the conda packages of '{}'",
+            environment.name().fancy_display()
+        ),
+        |_| {
+            stream::iter(packages)
+                .map(|(binary, url)| async move {
+                    lock_file
+                        .package_cache
+                        .get_or_fetch_from_url_with_retry(
+                            &binary.package_record,
+                            url,
+                            client.clone(),
+                            default_retry_policy(),
+                            None,
+                        )
+                        .await
+                        .into_diagnostic()
+                        .with_context(|| format!("failed to download '{}'", binary.file_name))
+                })
+                .buffer_unordered(environment.project().config().max_concurrent_downloads())
+                .try_collect::<Vec<_>>()
+        },
+    )
+    .await?;
+    Ok(())
+}
+
+/// Returns true if the environment has locked PyPI packages for the current
+/// platform.
+fn has_pypi_packages(lock_file: &LockFileDerivedData<'_>, environment: &Environment<'_>) -> bool {
+    let Some(locked_environment) = lock_file.lock_file.environment(environment.name().as_str())
+    else {
+        return false;
+    };
+    locked_environment
+        .pypi_packages(environment.best_platform())
+        .is_some_and(|mut packages| packages.next().is_some())
+}
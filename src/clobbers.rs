@@ -0,0 +1,478 @@
+//! Finds the files that are provided by multiple conda packages of a prefix.
+//! The installer links the file of one of these packages, the files of the
+//! other packages are clobbered: they are moved aside and their original path
+//! is recorded in `conda-meta`.
+
+use std::{collections::BTreeMap, fmt::Display, path::PathBuf};
+
+use ahash::{HashMap, HashSet};
+use futures::{stream, StreamExt, TryStreamExt};
+use itertools::Itertools;
+use miette::{Context, Diagnostic, IntoDiagnostic};
+use pixi_config::ClobberPolicy;
+use rattler::{
+    install::{Transaction, TransactionOperation},
+    package_cache::PackageCache,
+};
+use rattler_conda_types::{
+    package::{PackageFile, PathsJson},
+    PackageName, Platform, PrefixRecord, RepoDataRecord,
+};
+use rattler_networking::retry_policies::default_retry_policy;
+use reqwest_middleware::ClientWithMiddleware;
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::prefix::Prefix;
+
+/// The maximum number of clobbered files that are shown in a message.
+const MAX_SHOWN: usize = 5;
+
+/// The maximum number of packages that are fetched at the same time to read
+/// their files.
+const MAX_CONCURRENT_FETCHES: usize = 50;
+
+/// A file that is provided by multiple packages.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct Clobber {
+    /// The path of the file, relative to the prefix.
+    pub path: PathBuf,
+    /// The package of which the file is linked, `None` if the file was
+    /// removed with its package.
+    pub package: Option<PackageName>,
+    /// The packages of which the file is clobbered.
+    pub clobbered: Vec<PackageName>,
+}
+
+impl std::fmt::Display for Clobber {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "'{}'", self.path.display())?;
+        if let Some(package) = &self.package {
+            write!(f, " of '{}'", package.as_normalized())?;
+        }
+        write!(
+            f,
+            " clobbers {}",
+            self.clobbered
+                .iter()
+                .map(|name| format!("'{}'", name.as_normalized()))
+                .join(", ")
+        )
+    }
+}
+
+/// A file that would be provided by multiple packages once they are linked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Conflict {
+    /// The path of the file, relative to the prefix.
+    pub path: PathBuf,
+    /// The packages that provide the file, sorted by name.
+    pub packages: Vec<PackageName>,
+}
+
+impl std::fmt::Display for Conflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "'{}' is provided by {}",
+            self.path.display(),
+            self.packages
+                .iter()
+                .map(|name| format!("'{}'", name.as_normalized()))
+                .join(", ")
+        )
+    }
+}
+
+/// The environment would contain files that are provided by multiple
+/// packages while the clobber policy is `error`.
+#[derive(Debug, Error, Diagnostic)]
+#[error(
+    "the environment would contain files that are provided by multiple packages:\n\t{}",
+    format_clobbers(conflicts, "\n\t")
+)]
+#[diagnostic(help(
+    "`clobber-policy` is set to `error`. Remove one of the packages, or set it to `warn` or `allow` to accept the clobbered files."
+))]
+pub(crate) struct ClobberError {
+    conflicts: Vec<Conflict>,
+}
+
+/// Returns the files of the prefix that are provided by multiple packages,
+/// sorted by path.
+pub(crate) fn find_clobbers(records: &[PrefixRecord]) -> Vec<Clobber> {
+    let mut linked: HashMap<&PathBuf, &PackageName> = HashMap::default();
+    let mut clobbered: BTreeMap<&PathBuf, Vec<PackageName>> = BTreeMap::new();
+    for record in records {
+        let name = &record.repodata_record.package_record.name;
+        for entry in &record.paths_data.paths {
+            match &entry.original_path {
+                Some(original_path) => clobbered
+                    .entry(original_path)
+                    .or_default()
+                    .push(name.clone()),
+                None => {
+                    linked.insert(&entry.relative_path, name);
+                }
+            }
+        }
+    }
+
+    clobbered
+        .into_iter()
+        .map(|(path, clobbered)| Clobber {
+            path: path.clone(),
+            package: linked.get(path).map(|&name| name.clone()),
+            clobbered: clobbered.into_iter().sorted().collect(),
+        })
+        .collect()
+}
+
+/// Returns the files that are provided by multiple of the given packages,
+/// sorted by path.
+pub(crate) fn find_conflicts(packages: &[(PackageName, Vec<PathBuf>)]) -> Vec<Conflict> {
+    let mut providers: BTreeMap<&PathBuf, Vec<&PackageName>> = BTreeMap::new();
+    for (name, paths) in packages {
+        for path in paths {
+            providers.entry(path).or_default().push(name);
+        }
+    }
+
+    providers
+        .into_iter()
+        .filter(|(_, packages)| packages.len() > 1)
+        .map(|(path, packages)| Conflict {
+            path: path.clone(),
+            packages: packages.into_iter().cloned().sorted().dedup().collect(),
+        })
+        .collect()
+}
+
+/// Rejects a transaction of which the packages would clobber each other's
+/// files, before anything is linked. The files of the packages that stay
+/// installed are read from `conda-meta`, the files of the packages that are
+/// installed from the `paths.json` of the package in the package cache.
+///
+/// This only runs for the `error` policy, the other policies let the
+/// installer resolve the clobbers while linking.
+pub(crate) async fn check_clobbers(
+    installed_packages: &[PrefixRecord],
+    records: &[RepoDataRecord],
+    platform: Platform,
+    package_cache: &PackageCache,
+    client: &ClientWithMiddleware,
+) -> miette::Result<()> {
+    let transaction = Transaction::from_current_and_desired(
+        installed_packages.to_vec(),
+        records.to_vec(),
+        platform,
+    )
+    .into_diagnostic()?;
+
+    // The packages that are removed or replaced by the transaction.
+    let removed: HashSet<&PackageName> = transaction
+        .operations
+        .iter()
+        .filter_map(|operation| match operation {
+            TransactionOperation::Install(_) => None,
+            TransactionOperation::Change { old, .. }
+            | TransactionOperation::Reinstall(old)
+            | TransactionOperation::Remove(old) => Some(&old.repodata_record.package_record.name),
+        })
+        .collect();
+
+    let mut packages = installed_packages
+        .iter()
+        .filter(|record| !removed.contains(&record.repodata_record.package_record.name))
+        .map(|record| {
+            let paths = record
+                .paths_data
+                .paths
+                .iter()
+                .map(|entry| {
+                    entry
+                        .original_path
+                        .clone()
+                        .unwrap_or_else(|| entry.relative_path.clone())
+                })
+                .collect();
+            (record.repodata_record.package_record.name.clone(), paths)
+        })
+        .collect_vec();
+
+    let python_info = transaction.python_info.as_ref();
+    let fetched = stream::iter(transaction.installed_packages())
+        .map(|record| async move {
+            let cache_lock = package_cache
+                .get_or_fetch_from_url_with_retry(
+                    &record.package_record,
+                    record.url.clone(),
+                    client.clone(),
+                    default_retry_policy(),
+                    None,
+                )
+                .await
+                .into_diagnostic()
+                .wrap_err_with(|| format!("failed to download '{}'", record.file_name))?;
+            let paths_json =
+                PathsJson::from_package_directory(cache_lock.path()).into_diagnostic()?;
+            // The files of noarch python packages are linked into the
+            // site-packages and scripts directories of the python version.
+            let is_noarch_python = record.package_record.noarch.is_python();
+            let paths = paths_json
+                .paths
+                .into_iter()
+                .map(|entry| match python_info {
+                    Some(python_info) if is_noarch_python => python_info
+                        .get_python_noarch_target_path(&entry.relative_path)
+                        .into_owned(),
+                    _ => entry.relative_path,
+                })
+                .collect_vec();
+            Ok::<_, miette::Report>((record.package_record.name.clone(), paths))
+        })
+        .buffer_unordered(MAX_CONCURRENT_FETCHES)
+        .try_collect::<Vec<_>>()
+        .await?;
+    packages.extend(fetched);
+
+    let conflicts = find_conflicts(&packages);
+    if conflicts.is_empty() {
+        Ok(())
+    } else {
+        Err(ClobberError { conflicts }.into())
+    }
+}
+
+/// Warns about the clobbered files of the prefix after a transaction with the
+/// `warn` policy. Only the files of the packages of the transaction are
+/// reported, to not repeat them on every installation. The `error` policy is
+/// enforced before linking by [`check_clobbers`].
+pub(crate) fn report_clobbers(
+    prefix: &Prefix,
+    transaction: &Transaction<PrefixRecord, RepoDataRecord>,
+    policy: ClobberPolicy,
+) -> miette::Result<()> {
+    if policy != ClobberPolicy::Warn || transaction.operations.is_empty() {
+        return Ok(());
+    }
+
+    let clobbers = clobbers_of_transaction(
+        find_clobbers(&prefix.find_installed_packages()?),
+        transaction,
+    );
+    if !clobbers.is_empty() {
+        tracing::warn!(
+            "files are provided by multiple packages, run `pixi list --clobbers` to list them:\n\t{}",
+            format_clobbers(&clobbers, "\n\t")
+        );
+    }
+    Ok(())
+}
+
+/// Only keeps the clobbers that involve a package that is installed by the
+/// transaction.
+fn clobbers_of_transaction(
+    clobbers: Vec<Clobber>,
+    transaction: &Transaction<PrefixRecord, RepoDataRecord>,
+) -> Vec<Clobber> {
+    let installed: HashSet<&PackageName> = transaction
+        .installed_packages()
+        .map(|record| &record.package_record.name)
+        .collect();
+    clobbers
+        .into_iter()
+        .filter(|clobber| {
+            clobber
+                .package
+                .iter()
+                .chain(&clobber.clobbered)
+                .any(|name| installed.contains(name))
+        })
+        .collect()
+}
+
+/// Formats the first few clobbers.
+fn format_clobbers(clobbers: &[impl Display], separator: &str) -> String {
+    let shown = clobbers.iter().take(MAX_SHOWN).join(separator);
+    if clobbers.len() > MAX_SHOWN {
+        format!("{shown}{separator}and {} more", clobbers.len() - MAX_SHOWN)
+    } else {
+        shown
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// A prefix record of a package with the given paths and the original
+    /// paths of the clobbered files.
+    pub(crate) fn prefix_record(name: &str, paths: &[(&str, Option<&str>)]) -> PrefixRecord {
+        let file_name = format!("{name}-1.0-0.conda");
+        let paths = paths
+            .iter()
+            .map(|(path, original_path)| {
+                serde_json::json!({
+                    "_path": path,
+                    "original_path": original_path,
+                    "path_type": "hardlink",
+                })
+            })
+            .collect_vec();
+        serde_json::from_value(serde_json::json!({
+            "name": name,
+            "version": "1.0",
+            "build": "0",
+            "build_number": 0,
+            "subdir": "noarch",
+            "fn": file_name,
+            "url": format!("https://conda.anaconda.org/conda-forge/noarch/{file_name}"),
+            "channel": "https://conda.anaconda.org/conda-forge",
+            "files": [],
+            "paths_data": { "paths_version": 1, "paths": paths },
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_find_clobbers() {
+        let records = [
+            prefix_record("foo", &[("bin/tool", None), ("bin/foo", None)]),
+            prefix_record(
+                "bar",
+                &[
+                    ("bin/tool__clobber-from-bar", Some("bin/tool")),
+                    ("bin/bar", None),
+                ],
+            ),
+            prefix_record(
+                "baz",
+                &[("share/data__clobber-from-baz", Some("share/data"))],
+            ),
+        ];
+        let clobbers = find_clobbers(&records);
+        assert_eq!(
+            clobbers,
+            vec![
+                Clobber {
+                    path: PathBuf::from("bin/tool"),
+                    package: Some(PackageName::new_unchecked("foo")),
+                    clobbered: vec![PackageName::new_unchecked("bar")],
+                },
+                Clobber {
+                    path: PathBuf::from("share/data"),
+                    package: None,
+                    clobbered: vec![PackageName::new_unchecked("baz")],
+                },
+            ]
+        );
+        assert_eq!(
+            clobbers[0].to_string(),
+            "'bin/tool' of 'foo' clobbers 'bar'"
+        );
+    }
+
+    #[test]
+    fn test_find_conflicts() {
+        let package = |name: &str, paths: &[&str]| {
+            (
+                PackageName::new_unchecked(name),
+                paths.iter().map(PathBuf::from).collect_vec(),
+            )
+        };
+        let conflicts = find_conflicts(&[
+            package("foo", &["bin/tool", "bin/foo"]),
+            package("bar", &["bin/tool", "bin/bar"]),
+            package("baz", &["bin/tool"]),
+        ]);
+        assert_eq!(
+            conflicts,
+            vec![Conflict {
+                path: PathBuf::from("bin/tool"),
+                packages: ["bar", "baz", "foo"]
+                    .into_iter()
+                    .map(PackageName::new_unchecked)
+                    .collect(),
+            }]
+        );
+        assert_eq!(
+            conflicts[0].to_string(),
+            "'bin/tool' is provided by 'bar', 'baz', 'foo'"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_check_clobbers() {
+        let installed = [
+            prefix_record("foo", &[("bin/tool", None)]),
+            prefix_record("bar", &[("bin/tool__clobber-from-bar", Some("bin/tool"))]),
+        ];
+        let records = installed
+            .iter()
+            .map(|record| record.repodata_record.clone())
+            .collect_vec();
+        let cache_dir = tempfile::tempdir().unwrap();
+        let package_cache = PackageCache::new(cache_dir.path());
+        let client = ClientWithMiddleware::from(reqwest::Client::new());
+
+        // The clobbered files of the packages that stay installed are
+        // rejected, without fetching anything.
+        let err = check_clobbers(
+            &installed,
+            &records,
+            Platform::Linux64,
+            &package_cache,
+            &client,
+        )
+        .await
+        .unwrap_err();
+        let err = err.downcast_ref::<ClobberError>().unwrap();
+        assert_eq!(err.conflicts.len(), 1);
+        assert_eq!(err.conflicts[0].path, PathBuf::from("bin/tool"));
+
+        // Removing one of the packages resolves the clobber.
+        check_clobbers(
+            &installed,
+            &records[..1],
+            Platform::Linux64,
+            &package_cache,
+            &client,
+        )
+        .await
+        .unwrap();
+    }
+
+    #[test]
+    fn test_clobbers_of_transaction() {
+        let installed = vec![
+            prefix_record("foo", &[("bin/tool", None)]),
+            prefix_record("bar", &[("bin/tool__clobber-from-bar", Some("bin/tool"))]),
+        ];
+        let mut desired = installed
+            .iter()
+            .map(|record| record.repodata_record.clone())
+            .collect_vec();
+        let baz = prefix_record(
+            "baz",
+            &[("share/data__clobber-from-baz", Some("share/data"))],
+        );
+        desired.push(baz.repodata_record.clone());
+        let transaction =
+            Transaction::from_current_and_desired(installed.clone(), desired, Platform::Linux64)
+                .unwrap();
+
+        // Only the clobbers that involve the newly installed package are
+        // reported, the clobber of the packages that didn't change is not.
+        let clobbers = find_clobbers(&[installed, vec![baz]].concat());
+        assert_eq!(clobbers.len(), 2);
+        assert_eq!(
+            clobbers_of_transaction(clobbers, &transaction),
+            vec![Clobber {
+                path: PathBuf::from("share/data"),
+                package: None,
+                clobbered: vec![PackageName::new_unchecked("baz")],
+            }]
+        );
+    }
+}
@@ -1,5 +1,6 @@
 use std::{
     collections::HashSet,
+    fmt::Write as _,
     io::{stderr, Write},
 };
 
@@ -9,7 +10,7 @@ use itertools::{Either, Itertools};
 use pixi_consts::consts;
 use pixi_manifest::FeaturesExt;
 use rattler_conda_types::Platform;
-use rattler_lock::{LockFile, LockedPackage, LockedPackageRef};
+use rattler_lock::{CondaPackageData, LockFile, LockedPackage, LockedPackageRef};
 use serde::Serialize;
 use serde_json::Value;
 use tabwriter::TabWriter;
@@ -136,8 +137,10 @@ impl LockFileDiff {
                 environment_diff.insert(platform, diff);
             }
 
-            // Remove empty diffs
+            // Remove empty diffs, and order the platforms independently of the
+            // order in which they are stored in the lock-files.
             environment_diff.retain(|_, diff| !diff.is_empty());
+            environment_diff.sort_by(|a, _, b, _| a.as_str().cmp(b.as_str()));
 
             result
                 .environment
@@ -158,6 +161,7 @@ impl LockFileDiff {
                 }
                 environment_diff.insert(platform, diff);
             }
+            environment_diff.sort_by(|a, _, b, _| a.as_str().cmp(b.as_str()));
             result
                 .environment
                 .insert(environment_name.to_string(), environment_diff);
@@ -176,7 +180,12 @@ impl LockFileDiff {
 
     // Format the lock-file diff.
     pub(crate) fn print(&self) -> std::io::Result<()> {
-        let mut writer = TabWriter::new(stderr());
+        self.print_to(stderr())
+    }
+
+    /// Format the lock-file diff to the given writer.
+    pub(crate) fn print_to(&self, writer: impl Write) -> std::io::Result<()> {
+        let mut writer = TabWriter::new(writer);
         for (idx, (environment_name, environment)) in self
             .environment
             .iter()
@@ -254,15 +263,6 @@ impl LockFileDiff {
             Changed(&'i LockedPackage, &'i LockedPackage),
         }
 
-        fn format_package_identifier(package: &LockedPackage) -> String {
-            match package {
-                LockedPackage::Conda(p) => {
-                    format!("{} {}", &p.record().version.as_str(), &p.record().build)
-                }
-                LockedPackage::Pypi(p, _) => p.version.to_string(),
-            }
-        }
-
         itertools::chain!(
             packages.added.iter().map(Change::Added),
             packages.removed.iter().map(Change::Removed),
@@ -312,11 +312,14 @@ impl LockFileDiff {
                 let name = previous.name();
                 let line = match (previous, current) {
                     (LockedPackage::Conda(previous), LockedPackage::Conda(current)) => {
+                        let channels = channel_change(previous, current)
+                            .map(|(previous, current)| format!("\t({previous} -> {current})"))
+                            .unwrap_or_default();
                         let previous = previous.record();
                         let current = current.record();
 
                         format!(
-                            "{} {} {}\t{} {}\t->\t{} {}",
+                            "{} {} {}\t{} {}\t->\t{} {}{}",
                             console::style("~").yellow(),
                             consts::CondaEmoji,
                             name,
@@ -324,6 +327,7 @@ impl LockFileDiff {
                             choose_style(previous.build.as_str(), current.build.as_str()),
                             choose_style(&current.version.as_str(), &previous.version.as_str()),
                             choose_style(current.build.as_str(), previous.build.as_str()),
+                            channels,
                         )
                     }
                     (LockedPackage::Pypi(previous, _), LockedPackage::Pypi(current, _)) => {
@@ -350,6 +354,103 @@ impl LockFileDiff {
         })
         .collect()
     }
+
+    /// Format the lock-file diff as markdown, with a table of changes per
+    /// environment and platform. This is convenient to post as a comment on a
+    /// pull request.
+    pub(crate) fn to_markdown(&self) -> String {
+        let mut markdown = String::new();
+        if self.is_empty() {
+            markdown.push_str("No changes to the lock file.\n");
+            return markdown;
+        }
+
+        for (environment_name, environment) in
+            self.environment.iter().sorted_by(|(a, _), (b, _)| a.cmp(b))
+        {
+            let _ = writeln!(markdown, "### Environment: `{environment_name}`\n");
+            for (platform, packages) in environment {
+                let _ = writeln!(markdown, "#### `{platform}`\n");
+                markdown.push_str("| | Package | Type | Before | After |\n");
+                markdown.push_str("|---|---|---|---|---|\n");
+
+                let rows = itertools::chain!(
+                    packages.added.iter().map(|p| ("+", p, None, Some(p))),
+                    packages.removed.iter().map(|p| ("-", p, Some(p), None)),
+                    packages.changed.iter().map(|(previous, current)| (
+                        "~",
+                        previous,
+                        Some(previous),
+                        Some(current)
+                    ))
+                )
+                .sorted_by_key(|(_, p, _, _)| p.name());
+
+                for (change, package, previous, current) in rows {
+                    let ty = match package {
+                        LockedPackage::Conda(_) => "conda",
+                        LockedPackage::Pypi(..) => "pypi",
+                    };
+                    let channels = match (previous, current) {
+                        (
+                            Some(LockedPackage::Conda(previous)),
+                            Some(LockedPackage::Conda(current)),
+                        ) => channel_change(previous, current),
+                        _ => None,
+                    };
+                    let cell = |package: Option<&LockedPackage>, channel: Option<&str>| {
+                        let Some(package) = package else {
+                            return String::new();
+                        };
+                        match channel {
+                            Some(channel) => {
+                                format!("{} ({channel})", format_package_identifier(package))
+                            }
+                            None => format_package_identifier(package),
+                        }
+                    };
+                    let _ = writeln!(
+                        markdown,
+                        "| {change} | {} | {ty} | {} | {} |",
+                        package.name(),
+                        cell(previous, channels.map(|(previous, _)| previous)),
+                        cell(current, channels.map(|(_, current)| current)),
+                    );
+                }
+                markdown.push('\n');
+            }
+        }
+
+        markdown
+    }
+}
+
+/// Formats the version, and for conda packages the build string, of a package.
+fn format_package_identifier(package: &LockedPackage) -> String {
+    match package {
+        LockedPackage::Conda(p) => {
+            format!("{} {}", &p.record().version.as_str(), &p.record().build)
+        }
+        LockedPackage::Pypi(p, _) => p.version.to_string(),
+    }
+}
+
+/// Returns the previous and current channel of a conda package if the channel
+/// it is installed from changed.
+fn channel_change<'a>(
+    previous: &'a CondaPackageData,
+    current: &'a CondaPackageData,
+) -> Option<(&'a str, &'a str)> {
+    fn channel(package: &CondaPackageData) -> Option<&str> {
+        package
+            .as_binary()
+            .and_then(|binary| binary.channel.as_ref())
+            .map(|channel| channel.as_str().trim_end_matches('/'))
+    }
+
+    let previous = channel(previous)?;
+    let current = channel(current)?;
+    (previous != current).then_some((previous, current))
 }
 
 #[derive(Serialize, Clone)]
@@ -496,3 +597,54 @@ fn compute_json_diff(
     }
     (a, b)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{fmt::Write, str::FromStr};
+
+    use insta::assert_snapshot;
+
+    use super::*;
+
+    /// Returns a lock-file with a `foo` package of the given version for each
+    /// of the platforms, in the given order, and optionally a `bar` package on
+    /// `win-64`.
+    fn lock_file(version: &str, platforms: &[Platform], with_bar: bool) -> LockFile {
+        let mut environment = String::new();
+        let mut packages = String::new();
+        for platform in platforms {
+            let mut names = vec!["foo"];
+            if with_bar && *platform == Platform::Win64 {
+                names.push("bar");
+            }
+            let _ = writeln!(environment, "      {platform}:");
+            for name in names {
+                let url = format!(
+                    "https://conda.anaconda.org/conda-forge/{platform}/{name}-{version}-h0_0.conda"
+                );
+                let _ = writeln!(environment, "      - conda: {url}");
+                let _ = write!(
+                    packages,
+                    "- kind: conda\n  name: {name}\n  version: '{version}'\n  build: h0_0\n  subdir: {platform}\n  url: {url}\n"
+                );
+            }
+        }
+        LockFile::from_str(&format!(
+            "version: 4\nenvironments:\n  default:\n    channels:\n    - url: https://conda.anaconda.org/conda-forge/\n    packages:\n{environment}packages:\n{packages}"
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_diff_platforms_are_sorted() {
+        let previous = lock_file("1.0", &[Platform::Win64, Platform::Linux64], false);
+        let current = lock_file(
+            "2.0",
+            &[Platform::Win64, Platform::OsxArm64, Platform::Linux64],
+            true,
+        );
+
+        let diff = LockFileDiff::from_lock_files(&previous, &current);
+        assert_snapshot!(diff.to_markdown());
+    }
+}
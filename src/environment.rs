@@ -1,27 +1,32 @@
 use crate::{
     build::{BuildReporter, SourceCheckoutReporter},
-    install_pypi,
-    lock_file::{UpdateLockFileOptions, UpdateMode, UvResolutionContext},
+    clobbers, install_pypi,
+    lock_file::{JsonProgressReporter, UpdateLockFileOptions, UpdateMode, UvResolutionContext},
     prefix::Prefix,
     project::{grouped_environment::GroupedEnvironment, Environment, HasProjectRef},
     rlimit::try_increase_rlimit_to_sensible,
-    Project,
+    signing,
+    stats::StatsReporter,
+    verify_hashes, Project,
 };
 use dialoguer::theme::ColorfulTheme;
 use fancy_display::FancyDisplay;
 use fs_err as fs;
-use futures::{stream, StreamExt, TryStreamExt};
+use futures::{future, stream, StreamExt, TryStreamExt};
 use indicatif::ProgressBar;
 use itertools::{Either, Itertools};
 use miette::{IntoDiagnostic, WrapErr};
 use parking_lot::Mutex;
 use pixi_build_frontend::CondaBuildReporter;
+use pixi_build_types::BuildProgress;
+use pixi_config::{ClobberPolicy, TrustConfig, VerifyHashes};
 use pixi_consts::consts;
 use pixi_git::credentials::store_credentials_from_url;
 use pixi_manifest::{EnvironmentName, FeaturesExt, SystemRequirements};
-use pixi_progress::{await_in_progress, global_multi_progress};
+use pixi_progress::{await_in_progress, global_multi_progress, ProgressFormat};
 use pixi_record::PixiRecord;
 use pixi_spec::PixiSpec;
+use pixi_utils::to_extended_length;
 use rattler::{
     install::{DefaultProgressFormatter, IndicatifReporter, Installer, PythonInfo, Transaction},
     package_cache::PackageCache,
@@ -31,10 +36,12 @@ use rattler_conda_types::{
 };
 use rattler_lock::LockedPackageRef;
 use rattler_lock::{PypiIndexes, PypiPackageData, PypiPackageEnvironmentData};
+use rattler_networking::retry_policies::default_retry_policy;
 use reqwest_middleware::ClientWithMiddleware;
 use serde::{Deserialize, Serialize};
 use std::{
     collections::HashMap,
+    future::Future,
     hash::{Hash, Hasher},
     io::{self, ErrorKind},
     path::{Path, PathBuf},
@@ -42,6 +49,7 @@ use std::{
     time::Duration,
 };
 use tokio::sync::Semaphore;
+use url::Url;
 
 use crate::build::BuildContext;
 use uv_distribution_types::{InstalledDist, Name};
@@ -104,7 +112,7 @@ async fn prefix_location_changed(
         .map_or(None, std::convert::identity);
     if user_value == Some(true) {
         await_in_progress("removing old environment", |_| {
-            tokio::fs::remove_dir_all(environment_dir)
+            tokio::fs::remove_dir_all(to_extended_length(environment_dir))
         })
         .await
         .into_diagnostic()
@@ -209,6 +217,10 @@ impl LockedEnvironmentHash {
 
         LockedEnvironmentHash(format!("{:x}", hasher.finish()))
     }
+
+    pub(crate) fn as_str(&self) -> &str {
+        &self.0
+    }
 }
 
 /// Information about the environment that was used to create the environment.
@@ -225,7 +237,7 @@ pub(crate) struct EnvironmentFile {
 }
 
 /// The path to the environment file in the `conda-meta` directory of the environment.
-fn environment_file_path(environment_dir: &Path) -> PathBuf {
+pub(crate) fn environment_file_path(environment_dir: &Path) -> PathBuf {
     environment_dir
         .join(consts::CONDA_META_DIR)
         .join(consts::ENVIRONMENT_FILE_NAME)
@@ -501,6 +513,7 @@ pub async fn update_prefix_pypi(
     system_requirements: &SystemRequirements,
     uv_context: &UvResolutionContext,
     pypi_indexes: Option<&PypiIndexes>,
+    pinned_indexes: &[Url],
     environment_variables: &HashMap<String, String>,
     lock_file_dir: &Path,
     platform: Platform,
@@ -567,6 +580,7 @@ pub async fn update_prefix_pypi(
                 system_requirements,
                 uv_context,
                 pypi_indexes,
+                pinned_indexes,
                 environment_variables,
                 platform,
                 non_isolated_packages,
@@ -684,13 +698,14 @@ impl PythonStatus {
     }
 }
 
-struct CondaBuildProgress {
+/// Reports the progress of building source packages.
+pub(crate) struct CondaBuildProgress {
     main_progress: ProgressBar,
     build_progress: Mutex<Vec<(String, ProgressBar)>>,
 }
 
 impl CondaBuildProgress {
-    fn new(num_packages: u64) -> Self {
+    pub(crate) fn new(num_packages: u64) -> Self {
         // Create a new progress bar.
         let pb = ProgressBar::hidden();
         pb.set_length(num_packages);
@@ -779,6 +794,37 @@ impl CondaBuildReporter for CondaBuildProgress {
     fn on_build_output(&self, _operation: usize, line: String) {
         self.main_progress.suspend(|| eprintln!("{}", line));
     }
+
+    fn on_build_progress(&self, operation: usize, progress: BuildProgress) {
+        let locked = self.build_progress.lock();
+        let Some((identifier, pb)) = locked.get(operation) else {
+            return;
+        };
+        let mut message = format!("building {identifier}: {}", progress.phase);
+        if let Some(current_file) = &progress.current_file {
+            message.push(' ');
+            message.push_str(current_file);
+        }
+        match progress.position() {
+            // Only show a bar when the backend knows how far along it is.
+            Some(position) => {
+                pb.set_style(
+                    indicatif::ProgressStyle::with_template(
+                        "    {spinner:.green} {msg} [{bar:20}] {pos:>3}% {elapsed}",
+                    )
+                    .unwrap()
+                    .progress_chars("=> "),
+                );
+                pb.set_length(100);
+                pb.set_position(position);
+            }
+            None => pb.set_style(
+                indicatif::ProgressStyle::with_template("    {spinner:.green} {msg} {elapsed}")
+                    .unwrap(),
+            ),
+        }
+        pb.set_message(message);
+    }
 }
 
 impl BuildReporter for CondaBuildProgress {
@@ -806,6 +852,9 @@ pub async fn update_prefix_conda(
     progress_bar_prefix: &str,
     io_concurrency_limit: Arc<Semaphore>,
     build_context: BuildContext,
+    verify_hashes: VerifyHashes,
+    trust: TrustConfig,
+    clobber_policy: ClobberPolicy,
 ) -> miette::Result<PythonStatus> {
     // Try to increase the rlimit to a sensible value for installation.
     try_increase_rlimit_to_sensible();
@@ -823,7 +872,7 @@ pub async fn update_prefix_conda(
 
     let source_records_length = source_records.len();
     // Build conda packages out of the source records
-    let mut processed_source_packages = stream::iter(source_records)
+    let build_source_packages = stream::iter(source_records)
         .map(Ok)
         .and_then(|record| {
             // If we don't have a progress reporter, create one
@@ -861,24 +910,114 @@ pub async fn update_prefix_conda(
                     .await
             }
         })
-        .try_collect::<Vec<RepoDataRecord>>()
-        .await?;
+        .try_collect::<Vec<RepoDataRecord>>();
+
+    // Fetch the binary packages into the package cache while the source
+    // packages are built, so the installer only has to link them afterwards.
+    let fetch_binary_packages = async {
+        // Verify the packages that are downloaded, before they are linked.
+        // The packages that are built from source are not verified.
+        if verify_hashes != VerifyHashes::Off {
+            await_in_progress(
+                format!("{progress_bar_prefix}verifying the downloaded packages"),
+                |_| {
+                    verify_hashes::verify_conda_packages(
+                        verify_hashes,
+                        &installed_packages,
+                        &repodata_records,
+                        host_platform,
+                        &package_cache,
+                        &authenticated_client,
+                    )
+                },
+            )
+            .await?;
+        }
+
+        // Verify the signatures of the packages of the trusted channels,
+        // before they are linked.
+        if !trust.channels.is_empty() {
+            await_in_progress(
+                format!("{progress_bar_prefix}verifying the package signatures"),
+                |_| {
+                    signing::verify_conda_signatures(
+                        &trust,
+                        &installed_packages,
+                        &repodata_records,
+                        host_platform,
+                        &package_cache,
+                        &authenticated_client,
+                    )
+                },
+            )
+            .await?;
+        }
+
+        // The verification above already fetched the packages. Without source
+        // packages the installer fetches and links every package in a single
+        // pipeline, which is faster than fetching them first.
+        if source_records_length > 0
+            && verify_hashes == VerifyHashes::Off
+            && trust.channels.is_empty()
+        {
+            await_in_progress(
+                format!("{progress_bar_prefix}downloading the packages"),
+                |_| {
+                    fetch_conda_packages(
+                        &installed_packages,
+                        &repodata_records,
+                        host_platform,
+                        &package_cache,
+                        &authenticated_client,
+                        &io_concurrency_limit,
+                    )
+                },
+            )
+            .await?;
+        }
+        Ok::<_, miette::Report>(())
+    };
+
+    let mut processed_source_packages =
+        build_while_fetching(build_source_packages, fetch_binary_packages).await?;
 
     // Extend the repodata records with the built packages
     repodata_records.append(&mut processed_source_packages);
 
+    // Reject clobbered files before anything is linked, the installer would
+    // otherwise leave the prefix with the clobbered files.
+    if clobber_policy == ClobberPolicy::Error {
+        await_in_progress(
+            format!("{progress_bar_prefix}checking for clobbered files"),
+            |_| {
+                clobbers::check_clobbers(
+                    &installed_packages,
+                    &repodata_records,
+                    host_platform,
+                    &package_cache,
+                    &authenticated_client,
+                )
+            },
+        )
+        .await?;
+    }
+
     // Execute the operations that are returned by the solver.
     let result = await_in_progress(
         format!("{progress_bar_prefix}{progress_bar_message}",),
         |pb| async {
-            Installer::new()
+            let mut installer = Installer::new()
                 .with_download_client(authenticated_client)
                 .with_io_concurrency_semaphore(io_concurrency_limit)
                 .with_execute_link_scripts(false)
                 .with_installed_packages(installed_packages)
                 .with_target_platform(host_platform)
-                .with_package_cache(package_cache)
-                .with_reporter(
+                .with_package_cache(package_cache);
+            if pixi_progress::progress_format() == ProgressFormat::JsonLines {
+                installer
+                    .set_reporter(StatsReporter::new(JsonProgressReporter::new(prefix.root())));
+            } else {
+                installer.set_reporter(StatsReporter::new(
                     IndicatifReporter::builder()
                         .with_multi_progress(global_multi_progress())
                         .with_placement(rattler::install::Placement::After(pb))
@@ -888,7 +1027,9 @@ pub async fn update_prefix_conda(
                         )
                         .clear_when_done(true)
                         .finish(),
-                )
+                ));
+            }
+            installer
                 .install(prefix.root(), repodata_records)
                 .await
                 .into_diagnostic()
@@ -900,11 +1041,112 @@ pub async fn update_prefix_conda(
     create_prefix_location_file(prefix.root())?;
     create_history_file(prefix.root())?;
 
+    // Warn about the files that are provided by multiple packages.
+    clobbers::report_clobbers(prefix, &result.transaction, clobber_policy)?;
+
     // Determine if the python version changed.
     Ok(PythonStatus::from_transaction(&result.transaction))
 }
 
+/// Builds the source packages while the binary packages are fetched, and
+/// returns the built packages once both are done.
+async fn build_while_fetching<T>(
+    build: impl Future<Output = miette::Result<T>>,
+    fetch: impl Future<Output = miette::Result<()>>,
+) -> miette::Result<T> {
+    let (built, ()) = futures::try_join!(build, fetch)?;
+    Ok(built)
+}
+
+/// Fetches the conda packages that have to be installed in the prefix into
+/// the package cache. The packages are fetched while holding a permit of the
+/// io concurrency limit, which also bounds the installer.
+async fn fetch_conda_packages(
+    installed_packages: &[PrefixRecord],
+    records: &[RepoDataRecord],
+    platform: Platform,
+    package_cache: &PackageCache,
+    client: &ClientWithMiddleware,
+    io_concurrency_limit: &Semaphore,
+) -> miette::Result<()> {
+    let transaction = Transaction::from_current_and_desired(
+        installed_packages.to_vec(),
+        records.to_vec(),
+        platform,
+    )
+    .into_diagnostic()?;
+
+    future::try_join_all(transaction.installed_packages().map(|record| async move {
+        let _permit = io_concurrency_limit.acquire().await.into_diagnostic()?;
+        package_cache
+            .get_or_fetch_from_url_with_retry(
+                &record.package_record,
+                record.url.clone(),
+                client.clone(),
+                default_retry_policy(),
+                None,
+            )
+            .await
+            .into_diagnostic()
+            .wrap_err_with(|| format!("failed to download '{}'", record.file_name))
+    }))
+    .await?;
+    Ok(())
+}
+
 pub type PerEnvironment<'p, T> = HashMap<Environment<'p>, T>;
 pub type PerGroup<'p, T> = HashMap<GroupedEnvironment<'p>, T>;
 pub type PerEnvironmentAndPlatform<'p, T> = PerEnvironment<'p, HashMap<Platform, T>>;
 pub type PerGroupAndPlatform<'p, T> = PerGroup<'p, HashMap<Platform, T>>;
+
+#[cfg(test)]
+mod tests {
+    use rattler::package_cache::CacheKey;
+    use rattler_conda_types::{Channel, RepoData};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_fetch_overlaps_source_builds() {
+        let channel_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/data/channels/channels/dummy_channel_1");
+        let records = RepoData::from_path(channel_dir.join("linux-64/repodata.json"))
+            .unwrap()
+            .into_repo_data_records(&Channel::from_directory(&channel_dir));
+        let client = ClientWithMiddleware::from(reqwest::Client::new());
+        let cache_dir = tempfile::tempdir().unwrap();
+        let package_cache = PackageCache::new(cache_dir.path());
+        let io_concurrency_limit = Semaphore::new(1);
+
+        // A build that only finishes once a binary package has been fetched,
+        // which never happens if the fetching waits for the builds.
+        let fetched = cache_dir
+            .path()
+            .join(CacheKey::from(&records[0].package_record).to_string());
+        let build = async {
+            while !fetched.is_dir() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            Ok(Vec::<RepoDataRecord>::new())
+        };
+        let fetch = fetch_conda_packages(
+            &[],
+            &records,
+            Platform::Linux64,
+            &package_cache,
+            &client,
+            &io_concurrency_limit,
+        );
+
+        tokio::time::timeout(Duration::from_secs(60), build_while_fetching(build, fetch))
+            .await
+            .expect("the packages are not fetched while the source packages are built")
+            .unwrap();
+        for record in &records {
+            assert!(cache_dir
+                .path()
+                .join(CacheKey::from(&record.package_record).to_string())
+                .is_dir());
+        }
+    }
+}
@@ -0,0 +1,305 @@
+//! Runs the package managers of other ecosystems, as defined in the
+//! `[external-managers]` table, in an installed environment. A manager only
+//! runs again when its command, the files it is based on or the locked
+//! packages of the environment change.
+
+use std::{
+    collections::HashMap,
+    hash::{Hash, Hasher},
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use deno_task_shell::{execute_with_pipes, ShellPipeReader, ShellPipeWriter, ShellState};
+use fancy_display::FancyDisplay;
+use miette::{IntoDiagnostic, WrapErr};
+use pixi_consts::consts;
+use pixi_glob::GlobHash;
+use pixi_manifest::ExternalManager;
+use serde::{Deserialize, Serialize};
+use xxhash_rust::xxh3::Xxh3;
+
+use crate::project::{Environment, HasProjectRef};
+
+/// The hashes of the inputs of the external managers that last ran
+/// successfully in an environment, by the name of the manager.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ExternalManagersFile {
+    hashes: HashMap<String, String>,
+}
+
+/// The path to the file that tracks the external managers in the `conda-meta`
+/// directory of the environment.
+fn external_managers_file_path(environment_dir: &Path) -> PathBuf {
+    environment_dir
+        .join(consts::CONDA_META_DIR)
+        .join(consts::EXTERNAL_MANAGERS_FILE_NAME)
+}
+
+/// Reads the file that tracks the external managers, a missing or invalid
+/// file is treated as if none of the managers ran yet.
+fn read_external_managers_file(environment_dir: &Path) -> ExternalManagersFile {
+    let path = external_managers_file_path(environment_dir);
+    match fs_err::read_to_string(&path) {
+        Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+            tracing::debug!(
+                "Failed to parse external managers file at: {:?}, error: {}",
+                path,
+                e
+            );
+            ExternalManagersFile::default()
+        }),
+        Err(e) if e.kind() == ErrorKind::NotFound => ExternalManagersFile::default(),
+        Err(e) => {
+            tracing::debug!(
+                "Failed to read external managers file at: {:?}, error: {}",
+                path,
+                e
+            );
+            ExternalManagersFile::default()
+        }
+    }
+}
+
+/// Computes the hash of the command of a manager, the files it is based on
+/// and the hash of the locked packages of the environment it runs in. The
+/// tools the managers use come from the environment, so they run again when
+/// it changes.
+fn input_hash(
+    manager: &ExternalManager,
+    cwd: &Path,
+    environment_hash: &str,
+) -> miette::Result<String> {
+    let mut hasher = Xxh3::new();
+    manager.cmd.hash(&mut hasher);
+    environment_hash.hash(&mut hasher);
+    if !manager.inputs.is_empty() {
+        let glob_hash = GlobHash::from_patterns(cwd, manager.inputs.iter().map(String::as_str))
+            .into_diagnostic()?;
+        glob_hash.hash.hash(&mut hasher);
+    }
+    Ok(format!("{:x}", hasher.finish()))
+}
+
+/// A manager of which the command, the input files or the environment changed
+/// since it last ran successfully.
+#[derive(Debug)]
+struct OutdatedManager<'a> {
+    name: &'a str,
+    manager: &'a ExternalManager,
+    cwd: PathBuf,
+    hash: String,
+}
+
+/// Returns the managers that have to run, skipping the managers of which the
+/// hash of the inputs matches the hash recorded in the environment.
+fn outdated_managers<'a>(
+    managers: impl IntoIterator<Item = (&'a String, &'a ExternalManager)>,
+    state: &ExternalManagersFile,
+    root: &Path,
+    environment_hash: &str,
+) -> miette::Result<Vec<OutdatedManager<'a>>> {
+    let mut outdated = Vec::new();
+    for (name, manager) in managers {
+        let cwd = match &manager.cwd {
+            Some(cwd) => root.join(cwd),
+            None => root.to_path_buf(),
+        };
+        let hash = input_hash(manager, &cwd, environment_hash).wrap_err_with(|| {
+            format!("failed to compute the hash of the inputs of the external manager '{name}'")
+        })?;
+        if state.hashes.get(name) == Some(&hash) {
+            tracing::debug!("external manager '{}' is up-to-date", name);
+            continue;
+        }
+        outdated.push(OutdatedManager {
+            name,
+            manager,
+            cwd,
+            hash,
+        });
+    }
+    Ok(outdated)
+}
+
+/// Runs the external managers of the workspace in the environment, skipping
+/// the managers whose command, input files and environment didn't change since
+/// they last ran successfully. The environment is identified by the hash of
+/// its locked packages.
+///
+/// The output of the managers is written to stderr, so it doesn't end up in
+/// the output of the command that installed the environment.
+pub(crate) async fn sync_external_managers(
+    environment: &Environment<'_>,
+    environment_hash: &str,
+    command_env: &HashMap<String, String>,
+) -> miette::Result<()> {
+    let project = environment.project();
+    let managers = project
+        .manifest
+        .workspace
+        .external_managers
+        .iter()
+        .filter(|(_, manager)| manager.runs_in(environment.name()));
+
+    let environment_dir = environment.dir();
+    let mut state = read_external_managers_file(&environment_dir);
+    for OutdatedManager {
+        name,
+        manager,
+        cwd,
+        hash,
+    } in outdated_managers(managers, &state, project.root(), environment_hash)?
+    {
+        let script = deno_task_shell::parser::parse(manager.cmd.trim()).map_err(|err| {
+            miette::miette!(
+                "failed to parse the command '{}' of the external manager '{name}': {err}",
+                manager.cmd
+            )
+        })?;
+        let shell_state = ShellState::new(
+            command_env.clone(),
+            &cwd,
+            Default::default(),
+            Default::default(),
+        );
+        let status_code = execute_with_pipes(
+            script,
+            shell_state,
+            ShellPipeReader::stdin(),
+            ShellPipeWriter::stderr(),
+            ShellPipeWriter::stderr(),
+        )
+        .await;
+        if status_code != 0 {
+            miette::bail!(
+                help = "the command runs again on the next install",
+                "the external manager '{name}' exited with code {status_code} in environment '{}'",
+                environment.name().fancy_display()
+            );
+        }
+
+        eprintln!(
+            "{}Synced '{}' in environment '{}'",
+            console::style(console::Emoji("✔ ", "")).green(),
+            name,
+            environment.name().fancy_display()
+        );
+
+        // Record every manager directly, so a failing manager doesn't cause the
+        // previous ones to run again.
+        state.hashes.insert(name.to_string(), hash);
+        let contents = serde_json::to_string_pretty(&state).into_diagnostic()?;
+        fs_err::write(external_managers_file_path(&environment_dir), contents).into_diagnostic()?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ENVIRONMENT_HASH: &str = "8c2ee0ec4c4a8c1b";
+
+    fn manager(cmd: &str, inputs: &[&str]) -> ExternalManager {
+        ExternalManager {
+            cmd: cmd.to_string(),
+            inputs: inputs.iter().map(|input| input.to_string()).collect(),
+            cwd: None,
+            environments: None,
+        }
+    }
+
+    #[test]
+    fn test_input_hash() {
+        let dir = tempfile::tempdir().unwrap();
+        fs_err::write(dir.path().join("package-lock.json"), "{}").unwrap();
+
+        let npm = manager("npm ci", &["package-lock.json"]);
+        let hash = input_hash(&npm, dir.path(), ENVIRONMENT_HASH).unwrap();
+        assert_eq!(
+            hash,
+            input_hash(&npm, dir.path(), ENVIRONMENT_HASH).unwrap()
+        );
+
+        // The hash changes with the command and with the input files.
+        assert_ne!(
+            hash,
+            input_hash(
+                &manager("npm install", &["package-lock.json"]),
+                dir.path(),
+                ENVIRONMENT_HASH
+            )
+            .unwrap()
+        );
+        fs_err::write(
+            dir.path().join("package-lock.json"),
+            r#"{"lockfileVersion": 3}"#,
+        )
+        .unwrap();
+        assert_ne!(
+            hash,
+            input_hash(&npm, dir.path(), ENVIRONMENT_HASH).unwrap()
+        );
+
+        // Files that are not an input don't change the hash.
+        let hash = input_hash(&npm, dir.path(), ENVIRONMENT_HASH).unwrap();
+        fs_err::write(dir.path().join("README.md"), "").unwrap();
+        assert_eq!(
+            hash,
+            input_hash(&npm, dir.path(), ENVIRONMENT_HASH).unwrap()
+        );
+
+        // The hash changes with the locked packages of the environment.
+        assert_ne!(hash, input_hash(&npm, dir.path(), "0").unwrap());
+    }
+
+    #[test]
+    fn test_outdated_managers() {
+        let dir = tempfile::tempdir().unwrap();
+        fs_err::create_dir_all(dir.path().join(consts::CONDA_META_DIR)).unwrap();
+        let managers = [
+            ("npm".to_string(), manager("npm ci", &[])),
+            ("julia".to_string(), manager("julia -e 'using Pkg'", &[])),
+        ];
+        let names = |state: &ExternalManagersFile| {
+            outdated_managers(
+                managers.iter().map(|(n, m)| (n, m)),
+                state,
+                dir.path(),
+                ENVIRONMENT_HASH,
+            )
+            .unwrap()
+            .into_iter()
+            .map(|outdated| outdated.name.to_string())
+            .collect::<Vec<_>>()
+        };
+
+        // Without a file every manager runs.
+        let state = read_external_managers_file(dir.path());
+        assert_eq!(names(&state), ["npm", "julia"]);
+
+        // A manager with a recorded hash is skipped, until its command changes.
+        let mut state = ExternalManagersFile::default();
+        state.hashes.insert(
+            "npm".to_string(),
+            input_hash(&managers[0].1, dir.path(), ENVIRONMENT_HASH).unwrap(),
+        );
+        fs_err::write(
+            external_managers_file_path(dir.path()),
+            serde_json::to_string(&state).unwrap(),
+        )
+        .unwrap();
+        let mut state = read_external_managers_file(dir.path());
+        assert_eq!(names(&state), ["julia"]);
+
+        state.hashes.insert("npm".to_string(), "0".to_string());
+        assert_eq!(names(&state), ["npm", "julia"]);
+
+        // An invalid file is treated as if none of the managers ran.
+        fs_err::write(external_managers_file_path(dir.path()), "not json").unwrap();
+        let state = read_external_managers_file(dir.path());
+        assert_eq!(names(&state), ["npm", "julia"]);
+    }
+}
@@ -346,6 +346,11 @@ pub(crate) enum StateChange {
     AddedExposed(ExposedName),
     RemovedExposed(ExposedName),
     UpdatedExposed(ExposedName),
+    AddedCompletion(ExposedName),
+    AddedShortcut(ExposedName),
+    RemovedShortcut(ExposedName),
+    AddedPath(PathBuf),
+    RemovedPath(PathBuf),
     AddedPackage(PackageRecord),
     AddedEnvironment,
     RemovedEnvironment,
@@ -533,6 +538,63 @@ impl StateChanges {
                             }
                         }
                     }
+                    StateChange::AddedCompletion(exposed) => {
+                        let mut exposed_names = StateChanges::accumulate_changes(
+                            &mut iter,
+                            |next| match next {
+                                Some(StateChange::AddedCompletion(name)) => Some(name.clone()),
+                                _ => None,
+                            },
+                            Some(exposed.clone()),
+                        );
+                        exposed_names.sort();
+                        exposed_names.dedup();
+                        eprintln!(
+                            "{}Exposed shell completions for {} from environment {}.",
+                            console::style(console::Emoji("✔ ", "")).green(),
+                            exposed_names
+                                .iter()
+                                .map(|name| name.fancy_display())
+                                .join(", "),
+                            env_name.fancy_display()
+                        );
+                        eprintln!(
+                            "   Run {} once to enable them in your shell.",
+                            console::style("pixi global completions setup").bold()
+                        );
+                    }
+                    StateChange::AddedShortcut(exposed) => {
+                        eprintln!(
+                            "{}Added Start Menu shortcut for {} from environment {}.",
+                            console::style(console::Emoji("✔ ", "")).green(),
+                            exposed.fancy_display(),
+                            env_name.fancy_display()
+                        );
+                    }
+                    StateChange::RemovedShortcut(exposed) => {
+                        eprintln!(
+                            "{}Removed Start Menu shortcut for {} from environment {}.",
+                            console::style(console::Emoji("✔ ", "")).green(),
+                            exposed.fancy_display(),
+                            env_name.fancy_display()
+                        );
+                    }
+                    StateChange::AddedPath(path) => {
+                        eprintln!(
+                            "{}Exposed {} from environment {}.",
+                            console::style(console::Emoji("✔ ", "")).green(),
+                            console::style(path.display()).bold(),
+                            env_name.fancy_display()
+                        );
+                    }
+                    StateChange::RemovedPath(path) => {
+                        eprintln!(
+                            "{}Removed exposed {} from environment {}.",
+                            console::style(console::Emoji("✔ ", "")).green(),
+                            console::style(path.display()).bold(),
+                            env_name.fancy_display()
+                        );
+                    }
                     StateChange::AddedPackage(pkg) => {
                         let mut added_pkgs = StateChanges::accumulate_changes(
                             &mut iter,
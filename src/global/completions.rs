@@ -0,0 +1,310 @@
+use std::path::{Path, PathBuf};
+
+use fs_err::tokio as tokio_fs;
+use indexmap::IndexSet;
+use miette::IntoDiagnostic;
+use pixi_config::pixi_home;
+
+use super::{EnvDir, EnvironmentName, ExposedName, Mapping, StateChange, StateChanges};
+
+/// The shells for which completion scripts are exposed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum CompletionShell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl CompletionShell {
+    pub(crate) const ALL: [CompletionShell; 3] = [Self::Bash, Self::Zsh, Self::Fish];
+
+    /// The name of the subdirectory of the completions directory for this shell.
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            CompletionShell::Bash => "bash",
+            CompletionShell::Zsh => "zsh",
+            CompletionShell::Fish => "fish",
+        }
+    }
+
+    /// The file name of the completion script for `name`.
+    fn file_name(&self, name: &str) -> String {
+        match self {
+            CompletionShell::Bash => name.to_string(),
+            CompletionShell::Zsh => format!("_{name}"),
+            CompletionShell::Fish => format!("{name}.fish"),
+        }
+    }
+
+    /// The path, relative to the prefix root, where conda packages put the
+    /// completion script for `name`.
+    fn prefix_path(&self, name: &str) -> PathBuf {
+        let dir = match self {
+            CompletionShell::Bash => "share/bash-completion/completions",
+            CompletionShell::Zsh => "share/zsh/site-functions",
+            CompletionShell::Fish => "share/fish/vendor_completions.d",
+        };
+        Path::new(dir).join(self.file_name(name))
+    }
+}
+
+/// Global completions directory, default to `$HOME/.pixi/completions`
+#[derive(Debug, Clone)]
+pub struct CompletionsDir(PathBuf);
+
+impl CompletionsDir {
+    /// Create the completions directory from path
+    #[cfg(test)]
+    pub fn new(root: PathBuf) -> miette::Result<Self> {
+        let path = root.join("completions");
+        fs_err::create_dir_all(&path).into_diagnostic()?;
+        Ok(Self(path))
+    }
+
+    /// Create the completions directory from environment variables
+    pub async fn from_env() -> miette::Result<Self> {
+        let completions_dir =
+            pixi_home()
+                .map(|path| path.join("completions"))
+                .ok_or(miette::miette!(
+                    "Couldn't determine global completions directory"
+                ))?;
+        tokio_fs::create_dir_all(&completions_dir)
+            .await
+            .into_diagnostic()?;
+        Ok(Self(completions_dir))
+    }
+
+    /// Returns the path to the completions directory
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+
+    /// Returns the directory holding the completion scripts of `shell`.
+    pub(crate) fn shell_dir(&self, shell: CompletionShell) -> PathBuf {
+        self.0.join(shell.as_str())
+    }
+
+    /// Removes the completion scripts that point to files that no longer
+    /// exist, for example because the environment was removed.
+    pub(crate) async fn remove_broken_completions(&self) -> miette::Result<()> {
+        for shell in CompletionShell::ALL {
+            let dir = self.shell_dir(shell);
+            if !dir.is_dir() {
+                continue;
+            }
+            let mut entries = tokio_fs::read_dir(&dir).await.into_diagnostic()?;
+            while let Some(entry) = entries.next_entry().await.into_diagnostic()? {
+                let path = entry.path();
+                if path.is_symlink() && !path.exists() {
+                    tracing::debug!("Removing broken completion script {}", path.display());
+                    tokio_fs::remove_file(&path).await.into_diagnostic()?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A completion script shipped by a package in a global environment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Completion {
+    exposed_name: ExposedName,
+    shell: CompletionShell,
+    source: PathBuf,
+    destination: PathBuf,
+}
+
+/// Finds the completion scripts the environment ships for its exposed
+/// executables.
+///
+/// Completion scripts refer to the command by its original name, so only
+/// mappings that expose an executable under its own name are considered.
+pub(crate) fn find_completions(
+    env_dir: &EnvDir,
+    exposed: &IndexSet<Mapping>,
+    completions_dir: &CompletionsDir,
+) -> Vec<Completion> {
+    exposed
+        .iter()
+        .filter(|mapping| mapping.exposed_name().to_string() == mapping.executable_name())
+        .flat_map(|mapping| {
+            CompletionShell::ALL.into_iter().filter_map(|shell| {
+                let name = mapping.executable_name();
+                let source = env_dir.path().join(shell.prefix_path(name));
+                source.is_file().then(|| Completion {
+                    exposed_name: mapping.exposed_name().clone(),
+                    shell,
+                    source,
+                    destination: completions_dir.shell_dir(shell).join(shell.file_name(name)),
+                })
+            })
+        })
+        .collect()
+}
+
+/// Links the completion scripts of an environment into the completions
+/// directory and removes the ones of that environment that are no longer
+/// exposed.
+pub(crate) async fn sync_completions(
+    env_name: &EnvironmentName,
+    env_dir: &EnvDir,
+    completions: &[Completion],
+    completions_dir: &CompletionsDir,
+) -> miette::Result<StateChanges> {
+    let mut state_changes = StateChanges::default();
+
+    // Remove the completions that point into this environment but are not
+    // requested anymore
+    for shell in CompletionShell::ALL {
+        let dir = completions_dir.shell_dir(shell);
+        if !dir.is_dir() {
+            continue;
+        }
+        let mut entries = tokio_fs::read_dir(&dir).await.into_diagnostic()?;
+        while let Some(entry) = entries.next_entry().await.into_diagnostic()? {
+            let path = entry.path();
+            let Ok(target) = tokio_fs::read_link(&path).await else {
+                continue;
+            };
+            if target.starts_with(env_dir.path())
+                && !completions
+                    .iter()
+                    .any(|completion| completion.destination == path)
+            {
+                tokio_fs::remove_file(&path).await.into_diagnostic()?;
+            }
+        }
+    }
+
+    for completion in completions {
+        if is_linked(completion).await {
+            continue;
+        }
+        if let Some(parent) = completion.destination.parent() {
+            tokio_fs::create_dir_all(parent).await.into_diagnostic()?;
+        }
+        if completion.destination.is_symlink() || completion.destination.exists() {
+            tokio_fs::remove_file(&completion.destination)
+                .await
+                .into_diagnostic()?;
+        }
+        link_completion(&completion.source, &completion.destination).await?;
+        tracing::debug!(
+            "Exposed {} completions of {}",
+            completion.shell.as_str(),
+            completion.exposed_name
+        );
+        state_changes.insert_change(
+            env_name,
+            StateChange::AddedCompletion(completion.exposed_name.clone()),
+        );
+    }
+
+    Ok(state_changes)
+}
+
+#[cfg(unix)]
+async fn is_linked(completion: &Completion) -> bool {
+    tokio_fs::read_link(&completion.destination)
+        .await
+        .is_ok_and(|target| target == completion.source)
+}
+
+#[cfg(not(unix))]
+async fn is_linked(completion: &Completion) -> bool {
+    completion.destination.is_file()
+}
+
+#[cfg(unix)]
+async fn link_completion(source: &Path, destination: &Path) -> miette::Result<()> {
+    tokio_fs::symlink(source, destination)
+        .await
+        .into_diagnostic()
+}
+
+#[cfg(not(unix))]
+async fn link_completion(source: &Path, destination: &Path) -> miette::Result<()> {
+    tokio_fs::copy(source, destination)
+        .await
+        .map(|_| ())
+        .into_diagnostic()
+}
+
+/// Returns the snippet that has to be added to the configuration of `shell`
+/// to load the completion scripts from the completions directory.
+pub(crate) fn setup_instructions(
+    shell: CompletionShell,
+    completions_dir: &CompletionsDir,
+) -> (&'static str, String) {
+    let dir = completions_dir.shell_dir(shell);
+    let dir = dir.display();
+    match shell {
+        CompletionShell::Bash => (
+            "~/.bashrc",
+            format!("for f in \"{dir}\"/*; do [ -f \"$f\" ] && source \"$f\"; done"),
+        ),
+        CompletionShell::Zsh => (
+            "~/.zshrc (before `compinit` is called)",
+            format!("fpath=(\"{dir}\" $fpath)"),
+        ),
+        CompletionShell::Fish => (
+            "~/.config/fish/config.fish",
+            format!("set -p fish_complete_path \"{dir}\""),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_find_and_sync_completions() {
+        let tmp_home_dir = tempdir().unwrap();
+        let env_dir = EnvDir::from_path(tmp_home_dir.path().join("envs").join("ruff"));
+        let completions_dir = CompletionsDir::new(tmp_home_dir.path().to_path_buf()).unwrap();
+
+        for shell in [CompletionShell::Bash, CompletionShell::Zsh] {
+            let path = env_dir.path().join(shell.prefix_path("ruff"));
+            fs_err::create_dir_all(path.parent().unwrap()).unwrap();
+            fs_err::write(path, "").unwrap();
+        }
+
+        let exposed = IndexSet::from([
+            Mapping::new(ExposedName::from_str("ruff").unwrap(), "ruff".to_string()),
+            Mapping::new(ExposedName::from_str("ruff2").unwrap(), "ruff".to_string()),
+        ]);
+        let completions = find_completions(&env_dir, &exposed, &completions_dir);
+        assert_eq!(
+            completions
+                .iter()
+                .map(|completion| completion.shell)
+                .collect::<Vec<_>>(),
+            vec![CompletionShell::Bash, CompletionShell::Zsh]
+        );
+
+        let env_name = EnvironmentName::from_str("ruff").unwrap();
+        sync_completions(&env_name, &env_dir, &completions, &completions_dir)
+            .await
+            .unwrap();
+        assert!(completions_dir
+            .shell_dir(CompletionShell::Zsh)
+            .join("_ruff")
+            .is_file());
+
+        // Syncing without completions removes them again
+        sync_completions(&env_name, &env_dir, &[], &completions_dir)
+            .await
+            .unwrap();
+        assert!(!completions_dir
+            .shell_dir(CompletionShell::Bash)
+            .join("ruff")
+            .exists());
+    }
+}
@@ -0,0 +1,258 @@
+//! Integration of the exposed executables with the Windows desktop: the global
+//! binary directory can be registered on the user `PATH` and GUI applications
+//! can get a Start Menu shortcut. On other platforms there is nothing to do.
+
+use std::{
+    ffi::OsStr,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use fs_err as fs;
+use indexmap::IndexSet;
+use miette::IntoDiagnostic;
+
+use super::{BinDir, EnvironmentName, ExposedName, Mapping, StateChange, StateChanges};
+
+/// The registry key holding the environment variables of the user.
+const USER_ENVIRONMENT_KEY: &str = r"HKCU:\Environment";
+
+/// Returns the Start Menu folder that contains the shortcuts created by pixi.
+fn start_menu_dir() -> miette::Result<PathBuf> {
+    dirs::data_dir()
+        .map(|dir| dir.join(r"Microsoft\Windows\Start Menu\Programs\pixi"))
+        .ok_or_else(|| miette::miette!("Couldn't determine the Start Menu directory"))
+}
+
+fn shortcut_path(start_menu_dir: &Path, exposed_name: &ExposedName) -> PathBuf {
+    start_menu_dir.join(format!("{exposed_name}.lnk"))
+}
+
+/// Creates the Start Menu shortcuts of the exposed executables listed in
+/// `shortcuts` and removes the ones of the other exposed executables.
+pub(crate) fn sync_shortcuts(
+    env_name: &EnvironmentName,
+    bin_dir: &BinDir,
+    exposed: &IndexSet<Mapping>,
+    shortcuts: &IndexSet<ExposedName>,
+) -> miette::Result<StateChanges> {
+    let mut state_changes = StateChanges::default();
+    if !cfg!(windows) {
+        if !shortcuts.is_empty() {
+            tracing::debug!("Start Menu shortcuts are only created on Windows");
+        }
+        return Ok(state_changes);
+    }
+
+    let start_menu_dir = start_menu_dir()?;
+    for exposed_name in exposed.iter().map(Mapping::exposed_name) {
+        let shortcut = shortcut_path(&start_menu_dir, exposed_name);
+        if shortcuts.contains(exposed_name) {
+            if shortcut.is_file() {
+                continue;
+            }
+            fs::create_dir_all(&start_menu_dir).into_diagnostic()?;
+            create_shortcut(&shortcut, &bin_dir.executable_trampoline_path(exposed_name))?;
+            state_changes.insert_change(env_name, StateChange::AddedShortcut(exposed_name.clone()));
+        } else if shortcut.is_file() {
+            fs::remove_file(&shortcut).into_diagnostic()?;
+            state_changes
+                .insert_change(env_name, StateChange::RemovedShortcut(exposed_name.clone()));
+        }
+    }
+    Ok(state_changes)
+}
+
+/// Removes the Start Menu shortcuts whose executable is no longer exposed.
+pub(crate) fn remove_broken_shortcuts(bin_dir: &BinDir) -> miette::Result<()> {
+    if !cfg!(windows) {
+        return Ok(());
+    }
+    remove_broken_shortcuts_in(&start_menu_dir()?, bin_dir)
+}
+
+fn remove_broken_shortcuts_in(start_menu_dir: &Path, bin_dir: &BinDir) -> miette::Result<()> {
+    if !start_menu_dir.is_dir() {
+        return Ok(());
+    }
+    for entry in fs::read_dir(start_menu_dir).into_diagnostic()? {
+        let path = entry.into_diagnostic()?.path();
+        if path.extension() != Some(OsStr::new("lnk")) {
+            continue;
+        }
+        let Some(exposed_name) = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.parse().ok())
+        else {
+            continue;
+        };
+        if !bin_dir.executable_trampoline_path(&exposed_name).is_file() {
+            tracing::debug!("Removing broken shortcut {}", path.display());
+            fs::remove_file(&path).into_diagnostic()?;
+        }
+    }
+    Ok(())
+}
+
+/// Adds the global binary directory to the `PATH` of the user stored in the
+/// registry. Returns false if it was already registered.
+pub(crate) fn add_to_user_path(bin_dir: &BinDir) -> miette::Result<bool> {
+    if !cfg!(windows) {
+        return Ok(false);
+    }
+    let entries = user_path_entries()?;
+    if entries.iter().any(|entry| is_bin_dir(entry, bin_dir)) {
+        return Ok(false);
+    }
+    let bin_dir = bin_dir.path().to_string_lossy().to_string();
+    set_user_path(entries.into_iter().chain([bin_dir]))?;
+    Ok(true)
+}
+
+/// Removes the global binary directory from the `PATH` of the user stored in
+/// the registry. Returns false if it wasn't registered.
+pub(crate) fn remove_from_user_path(bin_dir: &BinDir) -> miette::Result<bool> {
+    if !cfg!(windows) {
+        return Ok(false);
+    }
+    let entries = user_path_entries()?;
+    if !entries.iter().any(|entry| is_bin_dir(entry, bin_dir)) {
+        return Ok(false);
+    }
+    set_user_path(
+        entries
+            .into_iter()
+            .filter(|entry| !is_bin_dir(entry, bin_dir)),
+    )?;
+    Ok(true)
+}
+
+/// Paths on Windows are case-insensitive and may end with a separator.
+fn is_bin_dir(entry: &str, bin_dir: &BinDir) -> bool {
+    entry
+        .trim_end_matches('\\')
+        .eq_ignore_ascii_case(&bin_dir.path().to_string_lossy())
+}
+
+/// Reads the entries of the user `PATH` without expanding the variables in it.
+fn user_path_entries() -> miette::Result<Vec<String>> {
+    let path = run_powershell(&format!(
+        "(Get-Item -Path '{USER_ENVIRONMENT_KEY}').GetValue('Path', '', 'DoNotExpandEnvironmentNames')"
+    ))?;
+    Ok(path
+        .split(';')
+        .filter(|entry| !entry.is_empty())
+        .map(ToString::to_string)
+        .collect())
+}
+
+fn set_user_path(entries: impl IntoIterator<Item = String>) -> miette::Result<()> {
+    let path = entries.into_iter().collect::<Vec<_>>().join(";");
+    // Removing a variable through .NET broadcasts the settings change, so that
+    // newly started programs pick up the new `PATH` without logging out.
+    run_powershell(&format!(
+        "New-ItemProperty -Path '{USER_ENVIRONMENT_KEY}' -Name 'Path' -PropertyType ExpandString -Value {} -Force | Out-Null; \
+         [Environment]::SetEnvironmentVariable('PIXI_PATH_UPDATE', $null, 'User')",
+        quote(&path)
+    ))?;
+    Ok(())
+}
+
+fn create_shortcut(shortcut: &Path, target: &Path) -> miette::Result<()> {
+    run_powershell(&format!(
+        "$shortcut = (New-Object -ComObject WScript.Shell).CreateShortcut({}); \
+         $shortcut.TargetPath = {}; \
+         $shortcut.WorkingDirectory = $env:USERPROFILE; \
+         $shortcut.Save()",
+        quote(&shortcut.to_string_lossy()),
+        quote(&target.to_string_lossy())
+    ))?;
+    Ok(())
+}
+
+/// Quotes a string literal for PowerShell.
+fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+fn run_powershell(script: &str) -> miette::Result<String> {
+    let output = Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", script])
+        .output()
+        .into_diagnostic()?;
+    if !output.status.success() {
+        miette::bail!(
+            "PowerShell failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn test_remove_broken_shortcuts() {
+        let tmp_home_dir = tempfile::tempdir().unwrap();
+        let bin_dir = BinDir::new(tmp_home_dir.path().to_path_buf()).unwrap();
+        let start_menu_dir = tmp_home_dir.path().join("pixi");
+        fs::create_dir_all(&start_menu_dir).unwrap();
+
+        // `python` is still exposed, `ruff` is not
+        let python = ExposedName::from_str("python").unwrap();
+        let ruff = ExposedName::from_str("ruff").unwrap();
+        fs::write(bin_dir.executable_trampoline_path(&python), "").unwrap();
+        for exposed_name in [&python, &ruff] {
+            fs::write(shortcut_path(&start_menu_dir, exposed_name), "").unwrap();
+        }
+        fs::write(start_menu_dir.join("ruff.txt"), "").unwrap();
+
+        remove_broken_shortcuts_in(&start_menu_dir, &bin_dir).unwrap();
+        assert!(shortcut_path(&start_menu_dir, &python).is_file());
+        assert!(!shortcut_path(&start_menu_dir, &ruff).exists());
+        // Files that aren't shortcuts are left alone
+        assert!(start_menu_dir.join("ruff.txt").is_file());
+    }
+
+    #[test]
+    fn test_is_bin_dir() {
+        let tmp_home_dir = tempfile::tempdir().unwrap();
+        let bin_dir = BinDir::new(tmp_home_dir.path().to_path_buf()).unwrap();
+        let path = bin_dir.path().to_string_lossy().to_string();
+
+        assert!(is_bin_dir(&path, &bin_dir));
+        assert!(is_bin_dir(&format!("{path}\\"), &bin_dir));
+        assert!(is_bin_dir(&path.to_uppercase(), &bin_dir));
+        assert!(!is_bin_dir(&format!("{path}2"), &bin_dir));
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_no_desktop_integration_outside_windows() {
+        let tmp_home_dir = tempfile::tempdir().unwrap();
+        let bin_dir = BinDir::new(tmp_home_dir.path().to_path_buf()).unwrap();
+        let env_name = EnvironmentName::from_str("test").unwrap();
+        let python = ExposedName::from_str("python").unwrap();
+
+        let state_changes = sync_shortcuts(
+            &env_name,
+            &bin_dir,
+            &IndexSet::from([Mapping::new(python.clone(), "python".to_string())]),
+            &IndexSet::from([python]),
+        )
+        .unwrap();
+        assert!(!state_changes.has_changed());
+        assert!(!add_to_user_path(&bin_dir).unwrap());
+        assert!(!remove_from_user_path(&bin_dir).unwrap());
+    }
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(quote(r"C:\Users\O'Brien"), r"'C:\Users\O''Brien'");
+    }
+}
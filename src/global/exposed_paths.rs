@@ -0,0 +1,141 @@
+use std::path::{Path, PathBuf};
+
+use fancy_display::FancyDisplay;
+use fs_err::tokio as tokio_fs;
+use indexmap::IndexMap;
+use miette::IntoDiagnostic;
+
+use super::{EnvDir, EnvironmentName, StateChange, StateChanges};
+
+/// Returns true if `target` is a link to `source`.
+async fn links_to(target: &Path, source: &Path) -> bool {
+    tokio_fs::read_link(target)
+        .await
+        .is_ok_and(|link| link == source)
+}
+
+/// Links the exposed paths of an environment to their target location.
+///
+/// Exposed paths that the environment doesn't contain anymore, for example
+/// because the package that provided them was removed, are unlinked again.
+pub(crate) async fn sync_exposed_paths(
+    env_name: &EnvironmentName,
+    env_dir: &EnvDir,
+    exposed_paths: &IndexMap<String, PathBuf>,
+) -> miette::Result<StateChanges> {
+    let mut state_changes = StateChanges::default();
+    for (path, target) in exposed_paths {
+        let source = env_dir.path().join(path);
+        if !source.exists() {
+            if remove_exposed_path(env_dir, target).await? {
+                tracing::warn!(
+                    "'{path}' doesn't exist in environment {} anymore, removed '{}'",
+                    env_name.fancy_display(),
+                    target.display()
+                );
+                state_changes.insert_change(env_name, StateChange::RemovedPath(target.clone()));
+            } else {
+                tracing::warn!(
+                    "'{path}' doesn't exist in environment {}, it can't be exposed",
+                    env_name.fancy_display()
+                );
+            }
+            continue;
+        }
+
+        if links_to(target, &source).await {
+            continue;
+        }
+        if target.exists() || target.is_symlink() {
+            miette::bail!(
+                "Can't expose '{path}' of environment {} because '{}' already exists",
+                env_name.fancy_display(),
+                target.display()
+            );
+        }
+        if let Some(parent) = target.parent() {
+            tokio_fs::create_dir_all(parent).await.into_diagnostic()?;
+        }
+        link_path(&source, target).await?;
+        state_changes.insert_change(env_name, StateChange::AddedPath(target.clone()));
+    }
+    Ok(state_changes)
+}
+
+/// Removes `target` if it is a link into the environment. Returns whether
+/// something was removed, files that pixi didn't create are left alone.
+pub(crate) async fn remove_exposed_path(env_dir: &EnvDir, target: &Path) -> miette::Result<bool> {
+    let Ok(link) = tokio_fs::read_link(target).await else {
+        return Ok(false);
+    };
+    if !link.starts_with(env_dir.path()) {
+        return Ok(false);
+    }
+    if cfg!(windows) && link.is_dir() {
+        tokio_fs::remove_dir(target).await.into_diagnostic()?;
+    } else {
+        tokio_fs::remove_file(target).await.into_diagnostic()?;
+    }
+    Ok(true)
+}
+
+#[cfg(unix)]
+async fn link_path(source: &Path, target: &Path) -> miette::Result<()> {
+    tokio_fs::symlink(source, target).await.into_diagnostic()
+}
+
+#[cfg(windows)]
+async fn link_path(source: &Path, target: &Path) -> miette::Result<()> {
+    if source.is_dir() {
+        tokio_fs::symlink_dir(source, target)
+            .await
+            .into_diagnostic()
+    } else {
+        tokio_fs::symlink_file(source, target)
+            .await
+            .into_diagnostic()
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::str::FromStr;
+
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_sync_exposed_paths() {
+        let tmp_home_dir = tempdir().unwrap();
+        let env_dir = EnvDir::from_path(tmp_home_dir.path().join("envs").join("man"));
+        fs_err::create_dir_all(env_dir.path().join("share/man/man1")).unwrap();
+        let env_name = EnvironmentName::from_str("man").unwrap();
+
+        let target = tmp_home_dir.path().join("local/share/man");
+        let exposed_paths = IndexMap::from([("share/man".to_string(), target.clone())]);
+
+        let state_changes = sync_exposed_paths(&env_name, &env_dir, &exposed_paths)
+            .await
+            .unwrap();
+        assert!(state_changes.has_changed());
+        assert!(target.join("man1").is_dir());
+
+        // Syncing again doesn't change anything
+        let state_changes = sync_exposed_paths(&env_name, &env_dir, &exposed_paths)
+            .await
+            .unwrap();
+        assert!(!state_changes.has_changed());
+
+        // Once the path is gone from the environment the link is removed
+        fs_err::remove_dir_all(env_dir.path().join("share/man")).unwrap();
+        sync_exposed_paths(&env_name, &env_dir, &exposed_paths)
+            .await
+            .unwrap();
+        assert!(!target.is_symlink());
+
+        // Files that were not created by pixi are not removed
+        fs_err::create_dir_all(&target).unwrap();
+        assert!(!remove_exposed_path(&env_dir, &target).await.unwrap());
+    }
+}
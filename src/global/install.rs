@@ -7,14 +7,14 @@ use crate::{
     prefix::Executable,
     prefix::Prefix,
 };
-use indexmap::IndexSet;
+use indexmap::{IndexMap, IndexSet};
 use itertools::Itertools;
 use miette::IntoDiagnostic;
 use pixi_utils::{executable_from_path, is_binary_folder};
 use rattler_conda_types::{
     MatchSpec, Matches, PackageName, ParseStrictness, Platform, RepoDataRecord,
 };
-use std::{path::PathBuf, str::FromStr};
+use std::{collections::HashMap, path::PathBuf, str::FromStr};
 
 use fs_err::tokio as tokio_fs;
 
@@ -85,8 +85,12 @@ pub struct ScriptExecMapping {
 /// Create the executables trampolines by running the activation scripts,
 /// recording this information in the trampoline metadata,
 /// and saving both the trampoline and the metadata.
+///
+/// The variables in `exposed_env` are set on top of the activation variables
+/// for the executable they are listed under.
 pub(crate) async fn create_executable_trampolines(
     mapped_executables: &[ScriptExecMapping],
+    exposed_env: &IndexMap<ExposedName, IndexMap<String, String>>,
     prefix: &Prefix,
     env_name: &EnvironmentName,
 ) -> miette::Result<StateChanges> {
@@ -117,7 +121,16 @@ pub(crate) async fn create_executable_trampolines(
                     original_executable.display()
                 )
             })?);
-        let metadata = Configuration::new(exe, path, Some(activation_variables.clone()));
+        let executable_name = executable_from_path(global_script_path);
+        let exposed_name = ExposedName::from_str(&executable_name)?;
+
+        let mut env = activation_variables.clone();
+        if let Some(exposed_vars) = exposed_env.get(&exposed_name) {
+            for (key, value) in exposed_vars {
+                env.insert(key.clone(), expand_variables(value, &activation_variables));
+            }
+        }
+        let metadata = Configuration::new(exe, path, Some(env));
 
         let parent_dir = global_script_path.parent().ok_or_else(|| {
             miette::miette!(
@@ -125,8 +138,8 @@ pub(crate) async fn create_executable_trampolines(
                 global_script_path.display()
             )
         })?;
-        let exposed_name = Trampoline::name(global_script_path)?;
-        let json_path = Configuration::path_from_trampoline(parent_dir, &exposed_name);
+        let trampoline_name = Trampoline::name(global_script_path)?;
+        let json_path = Configuration::path_from_trampoline(parent_dir, &trampoline_name);
 
         // Check if an old bash script is present and remove it
         let mut changed = if global_script_path.exists()
@@ -162,9 +175,6 @@ pub(crate) async fn create_executable_trampolines(
             }
         };
 
-        let executable_name = executable_from_path(global_script_path);
-        let exposed_name = ExposedName::from_str(&executable_name)?;
-
         let global_script_path_parent = global_script_path.parent().ok_or_else(|| {
             miette::miette!(
                 "Cannot find parent directory of '{}'",
@@ -192,6 +202,27 @@ pub(crate) async fn create_executable_trampolines(
     Ok(state_changes)
 }
 
+/// Replaces the `${NAME}` references in `value` with the value of the variable
+/// `NAME`. References to unknown variables are kept as they are.
+fn expand_variables(value: &str, variables: &HashMap<String, String>) -> String {
+    let mut expanded = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        let Some(end) = rest[start..].find('}') else {
+            break;
+        };
+        let name = &rest[start + 2..start + end];
+        expanded.push_str(&rest[..start]);
+        match variables.get(name) {
+            Some(variable) => expanded.push_str(variable),
+            None => expanded.push_str(&rest[start..=start + end]),
+        }
+        rest = &rest[start + end + 1..];
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
 /// Checks if the local environment matches the given specifications.
 ///
 /// This function verifies that all the given specifications are present in the
@@ -527,4 +558,18 @@ export CONDA_PREFIX="/home/user/.pixi/envs/nushell"
             Path::new("/home/user/.pixi/envs/nushell/bin/nu")
         );
     }
+
+    #[test]
+    fn test_expand_variables() {
+        let variables = HashMap::from([("CONDA_PREFIX".to_string(), "/envs/napari".to_string())]);
+        assert_eq!(
+            expand_variables("${CONDA_PREFIX}/lib/qt6/plugins", &variables),
+            "/envs/napari/lib/qt6/plugins"
+        );
+        assert_eq!(
+            expand_variables("${UNKNOWN}:${CONDA_PREFIX}", &variables),
+            "${UNKNOWN}:/envs/napari"
+        );
+        assert_eq!(expand_variables("plain ${", &variables), "plain ${");
+    }
 }
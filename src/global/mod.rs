@@ -1,10 +1,15 @@
 pub(crate) mod common;
+pub(crate) mod completions;
+pub(crate) mod desktop;
+pub(crate) mod exposed_paths;
 pub(crate) mod install;
 pub(crate) mod list;
 pub(crate) mod project;
+pub(crate) mod pypi;
 pub(crate) mod trampoline;
 
 pub(crate) use common::{BinDir, EnvChanges, EnvDir, EnvRoot, EnvState, StateChange, StateChanges};
+pub(crate) use completions::CompletionsDir;
 use pixi_utils::executable_from_path;
 pub(crate) use project::{EnvironmentName, ExposedName, Mapping, Project};
 
@@ -0,0 +1,199 @@
+use std::path::{Path, PathBuf};
+
+use indexmap::IndexMap;
+use itertools::Itertools;
+use miette::{Context, IntoDiagnostic};
+use pixi_record::PixiRecord;
+use pixi_spec::PixiSpec;
+use rattler_conda_types::{
+    ChannelConfig, ChannelUrl, Matches, PackageName, Platform, RepoDataRecord,
+};
+use rattler_lock::LockFile;
+
+use super::EnvironmentName;
+
+/// Whether the locked versions of an environment are respected when solving
+/// it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LockedVersions {
+    /// Reuse the locked records if they still satisfy the manifest, otherwise
+    /// prefer the locked versions while solving.
+    Keep,
+    /// Ignore the lock file and solve for the latest versions.
+    Update,
+}
+
+/// The file extension of the lock files of global environments.
+const LOCK_FILE_EXTENSION: &str = "lock";
+
+/// Returns the path of the lock file of an environment, which lives next to
+/// the global manifest.
+pub(crate) fn lock_file_path(manifest_dir: &Path, env_name: &EnvironmentName) -> PathBuf {
+    manifest_dir.join(format!("{}.{LOCK_FILE_EXTENSION}", env_name.as_str()))
+}
+
+/// The records that are locked for an environment.
+#[derive(Debug, Clone)]
+pub(crate) struct LockedEnvironment {
+    pub(crate) channels: Vec<String>,
+    pub(crate) records: Vec<PixiRecord>,
+}
+
+impl LockedEnvironment {
+    /// Reads the locked records of `env_name` for `platform`. Returns `None`
+    /// if there is no lock file or the platform isn't locked.
+    pub(crate) fn from_path(
+        path: &Path,
+        env_name: &EnvironmentName,
+        platform: Platform,
+    ) -> miette::Result<Option<Self>> {
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let lock_file = LockFile::from_path(path)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("failed to read lock file '{}'", path.display()))?;
+        let Some(environment) = lock_file.environment(env_name.as_str()) else {
+            return Ok(None);
+        };
+        let Some(packages) = environment.conda_packages(platform) else {
+            return Ok(None);
+        };
+        let records = packages
+            .cloned()
+            .map(PixiRecord::try_from)
+            .collect::<Result<Vec<_>, _>>()
+            .into_diagnostic()
+            .wrap_err_with(|| format!("failed to read lock file '{}'", path.display()))?;
+        Ok(Some(Self {
+            channels: environment
+                .channels()
+                .iter()
+                .map(|channel| channel.url.clone())
+                .collect(),
+            records,
+        }))
+    }
+
+    /// Writes the records of `env_name` for `platform` to a lock file.
+    pub(crate) fn write(
+        path: &Path,
+        env_name: &EnvironmentName,
+        channels: &[ChannelUrl],
+        platform: Platform,
+        records: impl IntoIterator<Item = PixiRecord>,
+    ) -> miette::Result<()> {
+        let mut builder = LockFile::builder();
+        builder.set_channels(
+            env_name.as_str(),
+            channels.iter().map(|channel| channel.to_string()),
+        );
+        for record in records {
+            builder.add_conda_package(env_name.as_str(), platform, record.into());
+        }
+        builder
+            .finish()
+            .to_path(path)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("failed to write lock file '{}'", path.display()))
+    }
+
+    /// Returns the binary records, which are preferred by the solver when the
+    /// lock file doesn't satisfy the manifest anymore.
+    pub(crate) fn binary_records(&self) -> Vec<RepoDataRecord> {
+        self.records
+            .iter()
+            .filter_map(|record| match record {
+                PixiRecord::Binary(record) => Some(record.clone()),
+                PixiRecord::Source(_) => None,
+            })
+            .collect()
+    }
+
+    /// Returns true if the locked records were solved for the given channels
+    /// and satisfy every dependency.
+    ///
+    /// Source dependencies are built locally, so only verify that a package
+    /// with the same name is locked.
+    pub(crate) fn satisfies(
+        &self,
+        channels: &[ChannelUrl],
+        dependencies: &IndexMap<PackageName, PixiSpec>,
+        channel_config: &ChannelConfig,
+    ) -> bool {
+        if self.channels != channels.iter().map(ToString::to_string).collect_vec() {
+            return false;
+        }
+        dependencies.iter().all(|(name, spec)| {
+            let Some(record) = self
+                .records
+                .iter()
+                .find(|record| &record.package_record().name == name)
+            else {
+                return false;
+            };
+            match spec.clone().try_into_nameless_match_spec(channel_config) {
+                Ok(Some(nameless_spec)) => nameless_spec.matches(record),
+                Ok(None) => true,
+                Err(_) => false,
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use pixi_config::default_channel_config;
+    use rattler_conda_types::{ParseStrictness, VersionSpec};
+    use tempfile::tempdir;
+    use url::Url;
+
+    use super::*;
+    use crate::lock_file::test_utils;
+
+    fn record(name: &str, version: &str) -> PixiRecord {
+        PixiRecord::Binary(test_utils::record(name, version))
+    }
+
+    #[test]
+    fn test_lock_file_roundtrip_and_satisfiability() {
+        let temp_dir = tempdir().unwrap();
+        let env_name = EnvironmentName::from_str("ruff").unwrap();
+        let path = lock_file_path(temp_dir.path(), &env_name);
+        let channels = vec![ChannelUrl::from(
+            Url::parse("https://conda.anaconda.org/conda-forge/").unwrap(),
+        )];
+
+        LockedEnvironment::write(
+            &path,
+            &env_name,
+            &channels,
+            Platform::Linux64,
+            [record("ruff", "0.5.0"), record("libgcc", "14.1.0")],
+        )
+        .unwrap();
+
+        let locked = LockedEnvironment::from_path(&path, &env_name, Platform::Linux64)
+            .unwrap()
+            .unwrap();
+        assert_eq!(locked.records.len(), 2);
+        assert!(
+            LockedEnvironment::from_path(&path, &env_name, Platform::OsxArm64)
+                .unwrap()
+                .is_none()
+        );
+
+        let channel_config = default_channel_config();
+        let dependencies = |spec: &str| {
+            IndexMap::from([(
+                PackageName::new_unchecked("ruff"),
+                PixiSpec::Version(VersionSpec::from_str(spec, ParseStrictness::Strict).unwrap()),
+            )])
+        };
+        assert!(locked.satisfies(&channels, &dependencies(">=0.5"), &channel_config));
+        assert!(!locked.satisfies(&channels, &dependencies(">=0.6"), &channel_config));
+        assert!(!locked.satisfies(&[], &dependencies("*"), &channel_config));
+    }
+}
@@ -10,8 +10,10 @@ use indexmap::IndexSet;
 use miette::IntoDiagnostic;
 use pixi_config::Config;
 use pixi_consts::consts;
-use pixi_manifest::{toml::TomlDocument, PrioritizedChannel};
-use pixi_spec::PixiSpec;
+use pixi_manifest::{
+    pypi::PyPiPackageName, toml::TomlDocument, PrioritizedChannel, PyPiRequirement,
+};
+use pixi_spec::{PixiSpec, SourceSpec};
 use pixi_toml::TomlIndexMap;
 use pixi_utils::{executable_from_path, strip_executable_extension};
 use rattler_conda_types::{ChannelConfig, MatchSpec, NamedChannelOrUrl, PackageName, Platform};
@@ -134,6 +136,40 @@ impl Manifest {
         Ok(())
     }
 
+    /// Copies an environment from another manifest, replacing the environment
+    /// with the same name if it already exists.
+    pub fn import_environment(
+        &mut self,
+        other: &Manifest,
+        env_name: &EnvironmentName,
+    ) -> miette::Result<()> {
+        let not_found =
+            || miette::miette!("Environment {} doesn't exist.", env_name.fancy_display());
+        let environment = other.parsed.envs.get(env_name).ok_or_else(not_found)?;
+        let item = other
+            .document
+            .get_nested_table("envs")?
+            .get(env_name.as_str())
+            .cloned()
+            .ok_or_else(not_found)?;
+
+        // Update self.parsed
+        self.parsed
+            .envs
+            .insert(env_name.clone(), environment.clone());
+
+        // Update self.document
+        self.document
+            .get_or_insert_nested_table("envs")?
+            .insert(env_name.as_str(), item);
+
+        tracing::debug!(
+            "Imported environment {} into toml document",
+            env_name.fancy_display()
+        );
+        Ok(())
+    }
+
     /// Adds a dependency to the manifest
     pub fn add_dependency(
         &mut self,
@@ -146,7 +182,58 @@ impl Manifest {
             miette::bail!("pixi doesn't support wildcard dependencies")
         };
         let spec = PixiSpec::from_nameless_matchspec(spec, channel_config);
+        self.add_pixi_spec(env_name, &name, spec)
+    }
+
+    /// Adds a source dependency, which is built locally, to the manifest.
+    pub fn add_source_dependency(
+        &mut self,
+        env_name: &EnvironmentName,
+        name: &PackageName,
+        source: SourceSpec,
+    ) -> miette::Result<()> {
+        self.add_pixi_spec(env_name, name, source.into())
+    }
+
+    /// Adds a PyPI dependency, which is installed with uv, to the manifest.
+    pub fn add_pypi_dependency(
+        &mut self,
+        env_name: &EnvironmentName,
+        name: &PyPiPackageName,
+        requirement: PyPiRequirement,
+    ) -> miette::Result<()> {
+        // Update self.parsed
+        self.parsed
+            .envs
+            .get_mut(env_name)
+            .ok_or_else(|| {
+                miette::miette!("Environment {} doesn't exist.", env_name.fancy_display())
+            })?
+            .pypi_dependencies
+            .insert(name.clone(), requirement.clone());
 
+        // Update self.document
+        self.document.insert_into_inline_table(
+            &format!("envs.{env_name}.pypi-dependencies"),
+            name.as_source(),
+            requirement.into(),
+        )?;
+
+        tracing::debug!(
+            "Added PyPI dependency {} to toml document for environment {}",
+            name.as_source(),
+            env_name.fancy_display()
+        );
+        Ok(())
+    }
+
+    /// Adds a dependency with the given spec to the manifest.
+    pub fn add_pixi_spec(
+        &mut self,
+        env_name: &EnvironmentName,
+        name: &PackageName,
+        spec: PixiSpec,
+    ) -> miette::Result<()> {
         // Update self.parsed
         self.parsed
             .envs
@@ -398,6 +485,95 @@ impl Manifest {
         Ok(())
     }
 
+    /// Adds a path of the environment that is exposed at `target`
+    pub fn add_exposed_path(
+        &mut self,
+        env_name: &EnvironmentName,
+        path: &str,
+        target: &Path,
+    ) -> miette::Result<()> {
+        let environment = self.parsed.envs.get_mut(env_name).ok_or_else(|| {
+            miette::miette!("Environment {} doesn't exist", env_name.fancy_display())
+        })?;
+
+        // Update self.parsed
+        environment
+            .exposed_paths
+            .insert(path.to_string(), target.to_path_buf());
+
+        // Update self.document
+        self.document.insert_into_inline_table(
+            &format!("envs.{env_name}.exposed-paths"),
+            path,
+            toml_edit::Value::from(target.to_string_lossy().to_string()),
+        )?;
+
+        tracing::debug!(
+            "Added exposed path {path} -> {} to toml document",
+            target.display()
+        );
+        Ok(())
+    }
+
+    /// Removes an exposed path from the manifest and returns where it was
+    /// exposed
+    pub fn remove_exposed_path(
+        &mut self,
+        env_name: &EnvironmentName,
+        path: &str,
+    ) -> miette::Result<PathBuf> {
+        let environment = self.parsed.envs.get_mut(env_name).ok_or_else(|| {
+            miette::miette!("Environment {} doesn't exist", env_name.fancy_display())
+        })?;
+
+        // Remove from the parsed environment
+        let target = environment
+            .exposed_paths
+            .shift_remove(path)
+            .ok_or_else(|| {
+                miette::miette!(
+                    "The path '{path}' isn't exposed from environment {}",
+                    env_name.fancy_display()
+                )
+            })?;
+
+        // Remove from the document
+        self.document
+            .get_or_insert_nested_table(&format!("envs.{env_name}.exposed-paths"))?
+            .remove(path);
+
+        tracing::debug!("Removed exposed path {path} from toml document");
+        Ok(target)
+    }
+
+    /// Adds a Start Menu shortcut for an exposed executable of the environment
+    pub fn add_shortcut(
+        &mut self,
+        env_name: &EnvironmentName,
+        exposed_name: &ExposedName,
+    ) -> miette::Result<()> {
+        let environment = self.parsed.envs.get_mut(env_name).ok_or_else(|| {
+            miette::miette!("Environment {} doesn't exist", env_name.fancy_display())
+        })?;
+
+        // Update self.parsed
+        if !environment.shortcuts.insert(exposed_name.clone()) {
+            return Ok(());
+        }
+
+        // Update self.document
+        self.document
+            .get_or_insert_nested_table(&format!("envs.{env_name}"))?
+            .entry("shortcuts")
+            .or_insert_with(|| toml_edit::Item::Value(toml_edit::Value::Array(Default::default())))
+            .as_array_mut()
+            .ok_or_else(|| miette::miette!("Expected an array for shortcuts"))?
+            .push(exposed_name.to_string());
+
+        tracing::debug!("Added shortcut for {exposed_name} to toml document");
+        Ok(())
+    }
+
     /// Removes all exposed mappings for a specific environment
     pub fn remove_all_exposed_mappings(
         &mut self,
@@ -622,6 +798,34 @@ mod tests {
         assert_eq!(expected_value, actual_value)
     }
 
+    #[test]
+    fn test_add_shortcut() {
+        let mut manifest = Manifest::default();
+        let exposed_name = ExposedName::from_str("napari").unwrap();
+        let env_name = EnvironmentName::from_str("napari").unwrap();
+        manifest.add_environment(&env_name, None).unwrap();
+
+        manifest.add_shortcut(&env_name, &exposed_name).unwrap();
+        // Adding it twice doesn't duplicate it
+        manifest.add_shortcut(&env_name, &exposed_name).unwrap();
+
+        // Check document
+        let shortcuts = manifest
+            .document
+            .get_or_insert_nested_table(&format!("envs.{}", env_name))
+            .unwrap()
+            .get("shortcuts")
+            .unwrap()
+            .as_array()
+            .unwrap();
+        assert_eq!(shortcuts.len(), 1);
+        assert_eq!(shortcuts.get(0).unwrap().as_str(), Some("napari"));
+
+        // Check parsed
+        let environment = manifest.parsed.envs.get(&env_name).unwrap();
+        assert!(environment.shortcuts().contains(&exposed_name));
+    }
+
     #[test]
     fn test_add_exposed_mapping_existing_env() {
         let mut manifest = Manifest::default();
@@ -837,6 +1041,63 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_import_environment() {
+        let mut manifest = Manifest::from_str(
+            Path::new("pixi-global.toml"),
+            r#"
+[envs.python]
+channels = ["conda-forge"]
+dependencies = { python = "3.11.*" }
+exposed = { python = "python" }
+
+[envs.ruff]
+channels = ["conda-forge"]
+dependencies = { ruff = "*" }
+"#,
+        )
+        .unwrap();
+        let other = Manifest::from_str(
+            Path::new("other.toml"),
+            r#"
+[envs.python]
+channels = ["conda-forge", "bioconda"]
+dependencies = { python = "3.12.*" }
+exposed = { python3 = "python" }
+"#,
+        )
+        .unwrap();
+        let env_name = EnvironmentName::from_str("python").unwrap();
+
+        // The existing environment is replaced, the others are kept
+        manifest.import_environment(&other, &env_name).unwrap();
+        let imported = manifest.parsed.envs.get(&env_name).unwrap();
+        assert_eq!(imported.channels.len(), 2);
+        assert_eq!(
+            imported
+                .exposed
+                .iter()
+                .map(|mapping| mapping.exposed_name().to_string())
+                .collect_vec(),
+            vec!["python3"]
+        );
+        assert!(manifest
+            .parsed
+            .envs
+            .contains_key(&EnvironmentName::from_str("ruff").unwrap()));
+
+        // The document is updated as well
+        let document = manifest.document.to_string();
+        assert!(document.contains(r#"python = "3.12.*""#));
+        assert!(!document.contains(r#"python = "3.11.*""#));
+        assert!(document.contains(r#"ruff = "*""#));
+
+        // Environments that don't exist in the other manifest can't be imported
+        assert!(manifest
+            .import_environment(&other, &EnvironmentName::from_str("ruff").unwrap())
+            .is_err());
+    }
+
     #[test]
     fn test_add_dependency() {
         let mut manifest = Manifest::default();
@@ -3,33 +3,38 @@ use std::{
     fmt::{Debug, Formatter},
     path::{Path, PathBuf},
     str::FromStr,
-    sync::OnceLock,
+    sync::{Arc, Mutex, OnceLock},
 };
 
-use ahash::HashSet;
+use ahash::{HashMap, HashSet};
 pub(crate) use environment::EnvironmentName;
 use fancy_display::FancyDisplay;
 use fs::tokio as tokio_fs;
 use fs_err as fs;
-use futures::stream::StreamExt;
+use futures::{stream::StreamExt, FutureExt};
 use indexmap::{IndexMap, IndexSet};
+use indicatif::ProgressBar;
 use is_executable::IsExecutable;
-use itertools::Itertools;
+use itertools::{Either, Itertools};
 pub(crate) use manifest::{ExposedType, Manifest, Mapping};
 use miette::{miette, Context, IntoDiagnostic};
 use parsed_manifest::ParsedManifest;
 pub(crate) use parsed_manifest::{ExposedName, ParsedEnvironment};
+use pixi_build_frontend::ToolContext;
 use pixi_config::{default_channel_config, pixi_home, Config};
 use pixi_consts::consts;
-use pixi_manifest::PrioritizedChannel;
-use pixi_progress::{await_in_progress, global_multi_progress, wrap_in_progress};
-use pixi_utils::{executable_from_path, reqwest::build_reqwest_clients};
+use pixi_manifest::{ChannelPriority, PrioritizedChannel};
+use pixi_progress::{await_in_progress, global_multi_progress, wrap_in_progress, ProgressFormat};
+use pixi_record::PixiRecord;
+use pixi_spec::SourceSpec;
+use pixi_utils::{executable_from_path, reqwest::build_reqwest_clients, to_extended_length};
 use rattler::{
     install::{DefaultProgressFormatter, IndicatifReporter, Installer},
     package_cache::PackageCache,
 };
 use rattler_conda_types::{
-    ChannelConfig, GenericVirtualPackage, MatchSpec, PackageName, Platform, PrefixRecord,
+    ChannelConfig, ChannelUrl, GenericVirtualPackage, MatchSpec, NamedChannelOrUrl, PackageName,
+    ParseStrictness, Platform, PrefixRecord,
 };
 use rattler_lock::Matches;
 use rattler_repodata_gateway::Gateway;
@@ -37,36 +42,60 @@ use rattler_solve::{resolvo::Solver, SolverImpl, SolverTask};
 use rattler_virtual_packages::{VirtualPackage, VirtualPackageOverrides};
 use reqwest_middleware::ClientWithMiddleware;
 use toml_edit::DocumentMut;
+use url::Url;
 
 use self::trampoline::{Configuration, ConfigurationParseError, Trampoline};
 use super::{
     common::{get_install_changes, EnvironmentUpdate},
     install::find_binary_by_name,
     trampoline::{self, GlobalExecutable},
-    BinDir, EnvRoot, StateChange, StateChanges,
+    BinDir, CompletionsDir, EnvRoot, StateChange, StateChanges,
 };
 use crate::{
+    build::{BuildContext, RemoteBuildCache, SourceCheckoutReporter, SourceMetadata},
+    environment::{CondaBuildProgress, PythonStatus},
     global::{
         common::{
             channel_url_to_prioritized_channel, find_package_records,
             get_expose_scripts_sync_status,
         },
+        completions::{find_completions, sync_completions},
+        desktop::{remove_broken_shortcuts, sync_shortcuts},
+        exposed_paths::{remove_exposed_path, sync_exposed_paths},
         find_executables, find_executables_for_many_records,
         install::{create_executable_trampolines, script_exec_mapping},
         project::environment::environment_specs_in_sync,
+        pypi::{find_pypi_executables, install_pypi_dependencies, pypi_dependencies_installed},
         EnvDir,
     },
+    lock_file::{
+        apply_requested_ignore_run_exports, resolve_conda, CondaMetadataProgress, CondaOverrides,
+        JsonProgressReporter, SolveFailure, UvResolutionContext,
+    },
     prefix::{Executable, Prefix},
     repodata::Repodata,
     rlimit::try_increase_rlimit_to_sensible,
 };
 
 mod environment;
+mod lock_file;
 mod manifest;
 mod parsed_manifest;
 
+use lock_file::LockedEnvironment;
+pub(crate) use lock_file::LockedVersions;
+
 pub(crate) const MANIFESTS_DIR: &str = "manifests";
 
+/// The solved records of a global environment, together with the information
+/// required to build its source packages and install it.
+pub(crate) struct SolvedEnvironment {
+    pub(crate) records: Vec<PixiRecord>,
+    platform: Platform,
+    channel_urls: Vec<ChannelUrl>,
+    virtual_packages: Vec<GenericVirtualPackage>,
+}
+
 /// The pixi global project, this main struct to interact with the pixi global
 /// project. This struct holds the `Manifest` and has functions to modify
 /// or request information from it. This allows in the future to have multiple
@@ -83,14 +112,27 @@ pub struct Project {
     pub(crate) env_root: EnvRoot,
     /// Binary directory
     pub(crate) bin_dir: BinDir,
+    /// Directory of the shell completions of the exposed executables
+    pub(crate) completions_dir: CompletionsDir,
     /// Reqwest client shared for this project.
     /// This is wrapped in a `OnceLock` to allow for lazy initialization.
     client: OnceLock<(reqwest::Client, ClientWithMiddleware)>,
     /// The repodata gateway to use for answering queries about repodata.
     /// This is wrapped in a `OnceLock` to allow for lazy initialization.
     repodata_gateway: OnceLock<Gateway>,
+    /// The context used to build the source dependencies, shared between the
+    /// metadata extraction and the builds so that sources are only checked
+    /// out once.
+    build_context: OnceLock<BuildContext>,
+    /// The metadata extracted from the source dependencies, so that it is
+    /// only extracted once, even when it is requested for both determining
+    /// the package names and solving the environment.
+    source_metadata: Arc<Mutex<HashMap<SourceMetadataKey, SourceMetadata>>>,
 }
 
+/// The inputs that determine the metadata extracted from a source dependency.
+type SourceMetadataKey = (SourceSpec, Platform, Vec<Url>);
+
 impl Debug for Project {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Global Project")
@@ -247,7 +289,12 @@ async fn package_from_conda_meta(
 
 impl Project {
     /// Constructs a new instance from an internal manifest representation
-    pub(crate) fn from_manifest(manifest: Manifest, env_root: EnvRoot, bin_dir: BinDir) -> Self {
+    pub(crate) fn from_manifest(
+        manifest: Manifest,
+        env_root: EnvRoot,
+        bin_dir: BinDir,
+        completions_dir: CompletionsDir,
+    ) -> Self {
         let root = manifest
             .path
             .parent()
@@ -264,8 +311,11 @@ impl Project {
             config,
             env_root,
             bin_dir,
+            completions_dir,
             client,
             repodata_gateway,
+            build_context: OnceLock::new(),
+            source_metadata: Default::default(),
         }
     }
 
@@ -275,9 +325,15 @@ impl Project {
         content: &str,
         env_root: EnvRoot,
         bin_dir: BinDir,
+        completions_dir: CompletionsDir,
     ) -> miette::Result<Self> {
         let manifest = Manifest::from_str(manifest_path, content)?;
-        Ok(Self::from_manifest(manifest, env_root, bin_dir))
+        Ok(Self::from_manifest(
+            manifest,
+            env_root,
+            bin_dir,
+            completions_dir,
+        ))
     }
 
     /// Discovers the project manifest file in path at
@@ -291,6 +347,7 @@ impl Project {
 
         let bin_dir = BinDir::from_env().await?;
         let env_root = EnvRoot::from_env().await?;
+        let completions_dir = CompletionsDir::from_env().await?;
 
         if !manifest_path.exists() {
             tracing::debug!(
@@ -305,11 +362,14 @@ impl Project {
                 tracing::debug!(
                     "Existing installation found. Creating global manifest from that information."
                 );
-                return Self::try_from_existing_installation(&manifest_path, env_root, bin_dir)
-                    .await
-                    .wrap_err_with(|| {
-                        "Failed to create global manifest from existing installation"
-                    });
+                return Self::try_from_existing_installation(
+                    &manifest_path,
+                    env_root,
+                    bin_dir,
+                    completions_dir,
+                )
+                .await
+                .wrap_err_with(|| "Failed to create global manifest from existing installation");
             } else {
                 tracing::debug!("Create an empty global manifest.");
                 tokio_fs::File::create(&manifest_path)
@@ -318,13 +378,14 @@ impl Project {
             }
         }
 
-        Self::from_path(&manifest_path, env_root, bin_dir)
+        Self::from_path(&manifest_path, env_root, bin_dir, completions_dir)
     }
 
     async fn try_from_existing_installation(
         manifest_path: &Path,
         env_root: EnvRoot,
         bin_dir: BinDir,
+        completions_dir: CompletionsDir,
     ) -> miette::Result<Self> {
         let config = Config::load(env_root.path());
 
@@ -379,7 +440,7 @@ impl Project {
         tokio_fs::write(&manifest_path, &toml)
             .await
             .into_diagnostic()?;
-        Self::from_str(manifest_path, &toml, env_root, bin_dir)
+        Self::from_str(manifest_path, &toml, env_root, bin_dir, completions_dir)
     }
 
     /// Get default dir for the pixi global manifest
@@ -418,9 +479,15 @@ impl Project {
         manifest_path: &Path,
         env_root: EnvRoot,
         bin_dir: BinDir,
+        completions_dir: CompletionsDir,
     ) -> miette::Result<Self> {
         let manifest = Manifest::from_path(manifest_path)?;
-        Ok(Project::from_manifest(manifest, env_root, bin_dir))
+        Ok(Project::from_manifest(
+            manifest,
+            env_root,
+            bin_dir,
+            completions_dir,
+        ))
     }
 
     /// Merge config with existing config project
@@ -455,12 +522,31 @@ impl Project {
         Ok(Prefix::new(self.environment_dir(env_name).await?.path()))
     }
 
+    /// Returns the packages installed in the environment with the given name.
+    /// Unlike [`Self::environment_prefix`], this doesn't create the
+    /// environment directory if the environment isn't installed.
+    pub(crate) fn installed_packages(
+        &self,
+        env_name: &EnvironmentName,
+    ) -> miette::Result<Vec<PrefixRecord>> {
+        let prefix = Prefix::new(self.env_root.path().join(env_name.as_str()));
+        if !prefix.root().join("conda-meta").is_dir() {
+            return Ok(Vec::new());
+        }
+        prefix.find_installed_packages()
+    }
+
     /// Create an authenticated reqwest client for this project
     /// use authentication from `rattler_networking`
     pub fn authenticated_client(&self) -> &ClientWithMiddleware {
         &self.client_and_authenticated_client().1
     }
 
+    /// Returns the reqwest client used for http networking
+    pub(crate) fn client(&self) -> &reqwest::Client {
+        &self.client_and_authenticated_client().0
+    }
+
     fn client_and_authenticated_client(&self) -> &(reqwest::Client, ClientWithMiddleware) {
         self.client
             .get_or_init(|| build_reqwest_clients(Some(&self.config)))
@@ -473,7 +559,22 @@ impl Project {
     pub(crate) async fn install_environment(
         &self,
         env_name: &EnvironmentName,
+        locked_versions: LockedVersions,
     ) -> miette::Result<EnvironmentUpdate> {
+        let solved_environment = self.solve_environment(env_name, locked_versions).await?;
+        self.install_solved_environment(env_name, solved_environment)
+            .await
+    }
+
+    /// Solves the requirements of an environment without installing it.
+    ///
+    /// With [`LockedVersions::Keep`] the records of the lock file are reused
+    /// as long as they satisfy the manifest.
+    pub(crate) async fn solve_environment(
+        &self,
+        env_name: &EnvironmentName,
+        locked_versions: LockedVersions,
+    ) -> miette::Result<SolvedEnvironment> {
         let environment = self
             .environment(env_name)
             .ok_or_else(|| miette::miette!("Environment {} not found", env_name.fancy_display()))?;
@@ -490,78 +591,235 @@ impl Project {
 
         let platform = environment.platform.unwrap_or_else(Platform::current);
 
-        let (match_specs, dependencies_names) = environment
-            .dependencies
-            .specs
-            .iter()
-            .map(|(name, spec)| {
-                if let Some(nameless_spec) = spec
-                    .clone()
-                    .try_into_nameless_match_spec(self.config().global_channel_config())
-                    .into_diagnostic()?
-                {
-                    Ok((
-                        MatchSpec::from_nameless(nameless_spec, Some(name.clone())),
-                        name.clone(),
-                    ))
-                } else {
-                    Err(miette!("Couldn't convert {spec:?} to nameless match spec."))
+        // Split the dependencies in binary dependencies, which are fetched from
+        // the channels, and source dependencies, which are built locally.
+        let mut match_specs = Vec::new();
+        let mut source_specs = Vec::new();
+        for (name, spec) in environment.dependencies.specs.iter() {
+            match spec.clone().into_source_or_binary() {
+                Either::Left(source) => source_specs.push((name.clone(), source)),
+                Either::Right(binary) => {
+                    let nameless_spec = binary
+                        .try_into_nameless_match_spec(self.config().global_channel_config())
+                        .into_diagnostic()?;
+                    match_specs.push(MatchSpec::from_nameless(nameless_spec, Some(name.clone())));
                 }
-            })
-            .collect::<miette::Result<(Vec<MatchSpec>, Vec<PackageName>)>>()?;
+            }
+        }
+
+        // Determine virtual packages of the current platform
+        let virtual_packages: Vec<GenericVirtualPackage> =
+            VirtualPackage::detect(&VirtualPackageOverrides::default())
+                .into_diagnostic()
+                .wrap_err_with(|| {
+                    miette::miette!(
+                        "Failed to determine virtual packages for environment {}",
+                        env_name.fancy_display()
+                    )
+                })?
+                .iter()
+                .cloned()
+                .map(GenericVirtualPackage::from)
+                .collect();
+
+        let channel_urls = self.channel_urls(environment)?;
+
+        // Reuse the locked records if they still satisfy the manifest
+        let locked = match locked_versions {
+            LockedVersions::Keep => {
+                LockedEnvironment::from_path(&self.lock_file_path(env_name), env_name, platform)?
+            }
+            LockedVersions::Update => None,
+        };
+        if let Some(locked) = &locked {
+            if locked.satisfies(
+                &channel_urls,
+                environment.dependencies(),
+                self.config.global_channel_config(),
+            ) {
+                tracing::debug!(
+                    "Using the locked records of environment {}",
+                    env_name.fancy_display()
+                );
+                return Ok(SolvedEnvironment {
+                    records: locked.records.clone(),
+                    platform,
+                    channel_urls,
+                    virtual_packages,
+                });
+            }
+        }
+        let locked_records = locked
+            .map(|locked| locked.binary_records())
+            .unwrap_or_default();
+
+        // Extract the metadata of the source dependencies, their requirements have to
+        // be part of the repodata query.
+        let source_metadata =
+            futures::future::try_join_all(source_specs.iter().map(|(name, source)| {
+                self.extract_source_metadata(source, &channel_urls, platform, &virtual_packages)
+                    .map(move |result| {
+                        result.wrap_err_with(|| {
+                            format!("failed to extract metadata for '{}'", name.as_source())
+                        })
+                    })
+            }))
+            .await?;
+        let mut query_specs = match_specs.clone();
+        for depends in source_metadata
+            .iter()
+            .flat_map(|metadata| metadata.records.iter())
+            .flat_map(|record| &record.package_record.depends)
+        {
+            if let Ok(spec) = MatchSpec::from_str(depends, ParseStrictness::Lenient) {
+                query_specs.push(spec);
+            }
+        }
 
+        // Channels whose sharded repodata fails fall back to the full repodata.
+        let platforms = [platform, Platform::NoArch];
         let repodata = await_in_progress(
             format!(
                 "Querying repodata for environment: {} ",
                 env_name.fancy_display()
             ),
-            |_| async {
-                self.repodata_gateway()
-                    .query(channels, [platform, Platform::NoArch], match_specs.clone())
-                    .recursive(true)
-                    .await
-                    .into_diagnostic()
+            |_| {
+                self.config().query_with_sharded_fallback(
+                    self.authenticated_client(),
+                    self.repodata_gateway().clone(),
+                    &channels,
+                    &platforms,
+                    &query_specs,
+                    |gateway| {
+                        let channels = channels.clone();
+                        let query_specs = query_specs.clone();
+                        async move {
+                            gateway
+                                .query(channels, platforms, query_specs)
+                                .recursive(true)
+                                .await
+                        }
+                    },
+                )
             },
         )
-        .await?;
+        .await
+        .into_diagnostic()?;
 
-        // Determine virtual packages of the current platform
-        let virtual_packages = VirtualPackage::detect(&VirtualPackageOverrides::default())
-            .into_diagnostic()
+        // Solve the environment
+        let pixi_records = if source_metadata.is_empty() {
+            let cloned_env_name = env_name.clone();
+            let virtual_packages = virtual_packages.clone();
+            tokio::task::spawn_blocking(move || {
+                wrap_in_progress(
+                    format!("Solving environment: {}", cloned_env_name.fancy_display()),
+                    move || {
+                        Solver.solve(SolverTask {
+                            specs: match_specs,
+                            locked_packages: locked_records,
+                            virtual_packages,
+                            ..SolverTask::from_iter(&repodata)
+                        })
+                    },
+                )
+                .into_diagnostic()
+                .wrap_err_with(|| {
+                    SolveFailure(format!(
+                        "failed to solve the requirements of environment {}",
+                        cloned_env_name.fancy_display()
+                    ))
+                })
+            })
+            .await
+            .into_diagnostic()??
+            .into_iter()
+            .map(PixiRecord::Binary)
+            .collect_vec()
+        } else {
+            // Also require the source packages themselves.
+            let specs = match_specs
+                .into_iter()
+                .chain(source_specs.iter().map(|(name, _)| MatchSpec {
+                    name: Some(name.clone()),
+                    ..MatchSpec::default()
+                }))
+                .collect();
+            resolve_conda(
+                specs,
+                virtual_packages.clone(),
+                locked_records,
+                repodata,
+                source_metadata,
+                ChannelPriority::default(),
+                CondaOverrides::default(),
+                None,
+            )
+            .await
             .wrap_err_with(|| {
-                miette::miette!(
-                    "Failed to determine virtual packages for environment {}",
+                SolveFailure(format!(
+                    "failed to solve the requirements of environment {}",
                     env_name.fancy_display()
-                )
+                ))
             })?
-            .iter()
-            .cloned()
-            .map(GenericVirtualPackage::from)
-            .collect();
+        };
 
-        // Solve the environment
-        let cloned_env_name = env_name.clone();
-        let solved_records = tokio::task::spawn_blocking(move || {
-            wrap_in_progress(
-                format!("Solving environment: {}", cloned_env_name.fancy_display()),
-                move || {
-                    Solver.solve(SolverTask {
-                        specs: match_specs,
-                        virtual_packages,
-                        ..SolverTask::from_iter(&repodata)
-                    })
-                },
-            )
-            .into_diagnostic()
-            .wrap_err_with(|| {
-                miette::miette!(
-                    "Failed to determine virtual packages for environment {}",
-                    cloned_env_name.fancy_display()
-                )
-            })
+        Ok(SolvedEnvironment {
+            records: pixi_records,
+            platform,
+            channel_urls,
+            virtual_packages,
         })
-        .await
-        .into_diagnostic()??;
+    }
+
+    /// Builds the source packages of a solved environment and installs it.
+    pub(crate) async fn install_solved_environment(
+        &self,
+        env_name: &EnvironmentName,
+        solved_environment: SolvedEnvironment,
+    ) -> miette::Result<EnvironmentUpdate> {
+        let SolvedEnvironment {
+            records,
+            platform,
+            channel_urls,
+            virtual_packages,
+        } = solved_environment;
+        let environment = self
+            .environment(env_name)
+            .ok_or_else(|| miette::miette!("Environment {} not found", env_name.fancy_display()))?;
+        let dependencies_names = environment.dependencies.specs.keys().cloned().collect();
+
+        // Build the source packages that are part of the solution.
+        let mut installed_records = records.clone();
+        apply_requested_ignore_run_exports(
+            &mut installed_records,
+            environment.dependencies.specs.iter(),
+        );
+        let (mut solved_records, source_records): (Vec<_>, Vec<_>) = installed_records
+            .into_iter()
+            .partition_map(|record| match record {
+                PixiRecord::Binary(record) => Either::Left(record),
+                PixiRecord::Source(record) => Either::Right(record),
+            });
+        if !source_records.is_empty() {
+            let build_context = self.build_context()?;
+            let build_reporter = Arc::new(CondaBuildProgress::new(source_records.len() as u64));
+            for record in source_records {
+                let build_id = build_reporter.associate(record.package_record.name.as_source());
+                let built = build_context
+                    .build_source_record(
+                        &record,
+                        &channel_urls,
+                        platform,
+                        virtual_packages.clone(),
+                        virtual_packages.clone(),
+                        build_reporter.clone(),
+                        None,
+                        build_id,
+                    )
+                    .await?;
+                solved_records.push(built);
+            }
+        }
 
         try_increase_rlimit_to_sensible();
 
@@ -574,30 +832,165 @@ impl Project {
                 env_name.fancy_display()
             ),
             |pb| {
-                Installer::new()
+                let mut installer = Installer::new()
                     .with_download_client(self.authenticated_client().clone())
                     .with_execute_link_scripts(false)
                     .with_package_cache(package_cache)
-                    .with_target_platform(platform)
-                    .with_reporter(
+                    .with_target_platform(platform);
+                if pixi_progress::progress_format() == ProgressFormat::JsonLines {
+                    installer.set_reporter(JsonProgressReporter::new(prefix.root()));
+                } else {
+                    installer.set_reporter(
                         IndicatifReporter::builder()
                             .with_multi_progress(global_multi_progress())
                             .with_placement(rattler::install::Placement::After(pb))
                             .with_formatter(DefaultProgressFormatter::default().with_prefix("  "))
                             .clear_when_done(true)
                             .finish(),
-                    )
-                    .install(prefix.root(), solved_records)
+                    );
+                }
+                installer.install(prefix.root(), solved_records)
             },
         )
         .await
         .into_diagnostic()?;
 
+        let python_status = PythonStatus::from_transaction(&result.transaction);
         let install_changes = get_install_changes(result.transaction);
 
+        // Install the PyPI dependencies on top of the conda packages
+        install_pypi_dependencies(
+            env_name,
+            &prefix,
+            platform,
+            &records,
+            &python_status,
+            environment.pypi_dependencies(),
+            UvResolutionContext::from_config(&self.config, self.client().clone())?,
+            self.authenticated_client().clone(),
+            self.config.cache().http_backend(),
+            &self.root,
+        )
+        .await?;
+
+        // Lock the installed records, so that later syncs install the same versions
+        LockedEnvironment::write(
+            &self.lock_file_path(env_name),
+            env_name,
+            &channel_urls,
+            platform,
+            records,
+        )?;
+
         Ok(EnvironmentUpdate::new(install_changes, dependencies_names))
     }
 
+    /// Returns the [`BuildContext`] used to build the source dependencies of
+    /// global environments. Since there is no project directory the build
+    /// artifacts are stored in the cache directory.
+    fn build_context(&self) -> miette::Result<&BuildContext> {
+        if let Some(build_context) = self.build_context.get() {
+            return Ok(build_context);
+        }
+
+        let cache_dir = pixi_config::get_cache_dir()?;
+        let build_context = BuildContext::new(
+            cache_dir.clone(),
+            cache_dir.join(consts::CACHED_GLOBAL_BUILD_DIR),
+            self.config.global_channel_config().clone(),
+            Default::default(),
+            Arc::new(ToolContext::default()),
+        )
+        .into_diagnostic()
+        .map(|build_context| {
+            build_context.with_remote_build_cache(RemoteBuildCache::from_config(
+                &self.config.build().cache,
+                self.authenticated_client().clone(),
+            ))
+        })?;
+        Ok(self.build_context.get_or_init(|| build_context))
+    }
+
+    /// Extracts the metadata of a source dependency using the build frontend.
+    /// The metadata is extracted once per source, platform and channels.
+    async fn extract_source_metadata(
+        &self,
+        source: &SourceSpec,
+        channel_urls: &[ChannelUrl],
+        platform: Platform,
+        virtual_packages: &[GenericVirtualPackage],
+    ) -> miette::Result<SourceMetadata> {
+        let key = (
+            source.clone(),
+            platform,
+            channel_urls.iter().cloned().map(Url::from).collect_vec(),
+        );
+        if let Some(metadata) = self.source_metadata.lock().unwrap().get(&key) {
+            return Ok(metadata.clone());
+        }
+
+        let pb = global_multi_progress().add(ProgressBar::hidden());
+        let metadata_reporter = Arc::new(CondaMetadataProgress::new(&pb, 1));
+        let source_reporter = Arc::new(SourceCheckoutReporter::new(
+            pb.clone(),
+            global_multi_progress(),
+        ));
+        let metadata = self
+            .build_context()?
+            .extract_source_metadata(
+                source,
+                channel_urls,
+                platform,
+                virtual_packages.to_vec(),
+                platform,
+                virtual_packages.to_vec(),
+                metadata_reporter,
+                Some(source_reporter),
+                0,
+            )
+            .await?;
+        pb.finish_and_clear();
+        self.source_metadata
+            .lock()
+            .unwrap()
+            .insert(key, metadata.clone());
+        Ok(metadata)
+    }
+
+    /// Returns the names of the packages that can be built from a source
+    /// dependency.
+    pub(crate) async fn source_package_names(
+        &self,
+        source: &SourceSpec,
+        channels: &[NamedChannelOrUrl],
+        platform: Platform,
+    ) -> miette::Result<Vec<PackageName>> {
+        let channel_urls = channels
+            .iter()
+            .map(|channel| {
+                channel
+                    .clone()
+                    .into_base_url(self.config.global_channel_config())
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .into_diagnostic()?;
+        let virtual_packages = VirtualPackage::detect(&VirtualPackageOverrides::default())
+            .into_diagnostic()?
+            .iter()
+            .cloned()
+            .map(GenericVirtualPackage::from)
+            .collect_vec();
+        let metadata = self
+            .extract_source_metadata(source, &channel_urls, platform, &virtual_packages)
+            .await?;
+        Ok(metadata
+            .records
+            .into_iter()
+            .map(|record| record.package_record.name)
+            .unique()
+            .collect())
+    }
+
     /// Remove an environment from the manifest and the global installation.
     pub(crate) async fn remove_environment(
         &mut self,
@@ -606,14 +999,31 @@ impl Project {
         let env_dir = EnvDir::from_env_root(self.env_root.clone(), env_name).await?;
         let mut state_changes = StateChanges::new_with_env(env_name.clone());
 
+        // Unlink the exposed paths of the environment
+        let exposed_paths = self
+            .environment(env_name)
+            .map(|environment| environment.exposed_paths().clone())
+            .unwrap_or_default();
+        for target in exposed_paths.values() {
+            if remove_exposed_path(&env_dir, target).await? {
+                state_changes.insert_change(env_name, StateChange::RemovedPath(target.clone()));
+            }
+        }
+
         // Remove the environment from the manifest, if it exists, otherwise ignore
         // error.
         self.manifest.remove_environment(env_name)?;
 
-        // Remove the environment
-        tokio_fs::remove_dir_all(env_dir.path())
+        // Remove the environment and its lock file
+        tokio_fs::remove_dir_all(to_extended_length(env_dir.path()))
             .await
             .into_diagnostic()?;
+        let lock_file_path = self.lock_file_path(env_name);
+        if lock_file_path.exists() {
+            tokio_fs::remove_file(lock_file_path)
+                .await
+                .into_diagnostic()?;
+        }
 
         // Get all removable binaries related to the environment
         let (to_remove, _to_add) =
@@ -628,6 +1038,12 @@ impl Project {
             );
         }
 
+        // Remove the completions that pointed into the environment
+        self.completions_dir.remove_broken_completions().await?;
+
+        // Remove the Start Menu shortcuts of the removed binaries
+        remove_broken_shortcuts(&self.bin_dir)?;
+
         state_changes.insert_change(env_name, StateChange::RemovedEnvironment);
 
         Ok(state_changes)
@@ -689,6 +1105,15 @@ impl Project {
 
             executables_for_package.insert(package_name.clone(), package_executables);
         }
+
+        // The console scripts of PyPI packages are not part of the conda metadata
+        let prefix = self.environment_prefix(env_name).await?;
+        for name in parsed_env.pypi_dependencies().keys() {
+            executables_for_package.insert(
+                PackageName::new_unchecked(name.as_normalized().as_ref()),
+                find_pypi_executables(&prefix, name)?,
+            );
+        }
         Ok(executables_for_package)
     }
 
@@ -800,16 +1225,14 @@ impl Project {
             .specs
             .iter()
             .map(|(name, spec)| {
-                let match_spec = MatchSpec::from_nameless(
-                    spec.clone()
-                        .try_into_nameless_match_spec(&default_channel_config())
-                        .into_diagnostic()?
-                        .ok_or_else(|| {
-                            miette::miette!("Couldn't convert {spec:?} to nameless match spec.")
-                        })?,
-                    Some(name.clone()),
-                );
-                Ok(match_spec)
+                // Source dependencies are built locally, so only verify that a package with
+                // the same name is installed.
+                let nameless_spec = spec
+                    .clone()
+                    .try_into_nameless_match_spec(&default_channel_config())
+                    .into_diagnostic()?
+                    .unwrap_or_default();
+                Ok(MatchSpec::from_nameless(nameless_spec, Some(name.clone())))
             })
             .collect::<Result<IndexSet<MatchSpec>, miette::Report>>()?;
 
@@ -822,6 +1245,19 @@ impl Project {
             return Ok(false);
         }
 
+        tracing::debug!("Verify that the PyPI dependencies are installed");
+        if !pypi_dependencies_installed(
+            &Prefix::new(env_dir.path()),
+            environment.pypi_dependencies(),
+        )? {
+            return Ok(false);
+        }
+
+        tracing::debug!("Verify that the installed packages match the lock file");
+        if !self.environment_matches_lock_file(env_name, &env_dir)? {
+            return Ok(false);
+        }
+
         tracing::debug!("Verify that the binaries are in sync with the environment");
         let (to_remove, to_add) =
             get_expose_scripts_sync_status(&self.bin_dir, &env_dir, &environment.exposed).await?;
@@ -838,6 +1274,107 @@ impl Project {
         Ok(true)
     }
 
+    /// Returns the base urls of the channels of an environment.
+    fn channel_urls(&self, environment: &ParsedEnvironment) -> miette::Result<Vec<ChannelUrl>> {
+        environment
+            .channels()
+            .into_iter()
+            .map(|channel| {
+                channel
+                    .clone()
+                    .into_base_url(self.config.global_channel_config())
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .into_diagnostic()
+    }
+
+    /// Returns the path of the lock file of an environment.
+    pub(crate) fn lock_file_path(&self, env_name: &EnvironmentName) -> PathBuf {
+        lock_file::lock_file_path(&self.root, env_name)
+    }
+
+    /// Check if the installed packages are the ones pinned in the lock file.
+    /// Without a lock file, any installation matches.
+    fn environment_matches_lock_file(
+        &self,
+        env_name: &EnvironmentName,
+        env_dir: &EnvDir,
+    ) -> miette::Result<bool> {
+        let environment = self.environment(env_name).ok_or(miette::miette!(
+            "Environment {} not found in manifest.",
+            env_name.fancy_display()
+        ))?;
+        let platform = environment.platform.unwrap_or_else(Platform::current);
+        let Some(locked) =
+            LockedEnvironment::from_path(&self.lock_file_path(env_name), env_name, platform)?
+        else {
+            return Ok(true);
+        };
+
+        let channel_urls = self.channel_urls(environment)?;
+        if !locked.satisfies(
+            &channel_urls,
+            environment.dependencies(),
+            self.config.global_channel_config(),
+        ) {
+            tracing::debug!(
+                "Lock file of environment {} doesn't satisfy the manifest",
+                env_name.fancy_display()
+            );
+            return Ok(false);
+        }
+
+        let installed = Prefix::new(env_dir.path()).find_installed_packages()?;
+        let matches = locked.records.iter().all(|locked_record| {
+            installed.iter().any(|installed_record| {
+                let installed_record = &installed_record.repodata_record.package_record;
+                match locked_record {
+                    PixiRecord::Binary(record) => {
+                        installed_record.name == record.package_record.name
+                            && installed_record.version == record.package_record.version
+                            && installed_record.build == record.package_record.build
+                    }
+                    // Source packages are built locally, so only the name is known
+                    PixiRecord::Source(record) => {
+                        installed_record.name == record.package_record.name
+                    }
+                }
+            })
+        });
+        Ok(matches && installed.len() == locked.records.len())
+    }
+
+    /// Writes a lock file from the installed packages of an environment that
+    /// was installed before lock files were introduced.
+    fn lock_installed_environment(&self, env_name: &EnvironmentName) -> miette::Result<()> {
+        let lock_file_path = self.lock_file_path(env_name);
+        if lock_file_path.exists() {
+            return Ok(());
+        }
+        let environment = self.environment(env_name).ok_or(miette::miette!(
+            "Environment {} not found in manifest.",
+            env_name.fancy_display()
+        ))?;
+        let channel_urls = self.channel_urls(environment)?;
+        let env_dir =
+            EnvDir::from_path(self.env_root.clone().path().join(env_name.clone().as_str()));
+        let installed = Prefix::new(env_dir.path()).find_installed_packages()?;
+
+        tracing::debug!(
+            "Creating lock file for environment {} from the installed packages",
+            env_name.fancy_display()
+        );
+        LockedEnvironment::write(
+            &lock_file_path,
+            env_name,
+            &channel_urls,
+            environment.platform.unwrap_or_else(Platform::current),
+            installed
+                .into_iter()
+                .map(|record| PixiRecord::Binary(record.repodata_record)),
+        )
+    }
+
     /// Check if all environments are in sync with the manifest
     pub async fn environments_in_sync(&self) -> miette::Result<bool> {
         let mut in_sync = true;
@@ -875,7 +1412,10 @@ impl Project {
 
         let prefix_records = &prefix.find_installed_packages()?;
 
-        let all_executables = find_executables_for_many_records(&prefix, prefix_records);
+        let mut all_executables = find_executables_for_many_records(&prefix, prefix_records);
+        for name in environment.pypi_dependencies().keys() {
+            all_executables.extend(find_pypi_executables(&prefix, name)?);
+        }
 
         let exposed: HashSet<&str> = environment
             .exposed
@@ -912,7 +1452,30 @@ impl Project {
             env_name.fancy_display()
         );
 
-        state_changes |= create_executable_trampolines(&script_mapping, &prefix, env_name).await?;
+        state_changes |= create_executable_trampolines(
+            &script_mapping,
+            environment.exposed_env(),
+            &prefix,
+            env_name,
+        )
+        .await?;
+
+        // Expose the shell completions shipped for the exposed executables
+        let completions = find_completions(&env_dir, &environment.exposed, &self.completions_dir);
+        state_changes |=
+            sync_completions(env_name, &env_dir, &completions, &self.completions_dir).await?;
+
+        // Link the exposed paths of the environment
+        state_changes |=
+            sync_exposed_paths(env_name, &env_dir, environment.exposed_paths()).await?;
+
+        // Create the Start Menu shortcuts of the environment
+        state_changes |= sync_shortcuts(
+            env_name,
+            &self.bin_dir,
+            &environment.exposed,
+            environment.shortcuts(),
+        )?;
 
         Ok(state_changes)
     }
@@ -930,12 +1493,15 @@ impl Project {
                 "Environment {} specs already up to date with global manifest",
                 env_name.fancy_display()
             );
+            self.lock_installed_environment(env_name)?;
         } else {
             tracing::debug!(
                 "Environment {} specs not up to date with global manifest",
                 env_name.fancy_display()
             );
-            let mut environment_update = self.install_environment(env_name).await?;
+            let mut environment_update = self
+                .install_environment(env_name, LockedVersions::Keep)
+                .await?;
 
             if let Some(removed_packages) = removed_packages {
                 environment_update.add_removed_packages(removed_packages.to_vec());
@@ -984,6 +1550,10 @@ impl Project {
                 }
             }
         }
+
+        // Remove the completions and shortcuts of executables that are gone
+        self.completions_dir.remove_broken_completions().await?;
+        remove_broken_shortcuts(&self.bin_dir)?;
         Ok(())
     }
 
@@ -1004,10 +1574,16 @@ impl Project {
             if !env_set.contains(&env_name) {
                 // Test if the environment directory is a conda environment
                 if let Ok(true) = env_path.join(consts::CONDA_META_DIR).try_exists() {
-                    // Remove the conda environment
-                    tokio_fs::remove_dir_all(&env_path)
+                    // Remove the conda environment and its lock file
+                    tokio_fs::remove_dir_all(to_extended_length(&env_path))
                         .await
                         .into_diagnostic()?;
+                    let lock_file_path = self.lock_file_path(&env_name);
+                    if lock_file_path.exists() {
+                        tokio_fs::remove_file(lock_file_path)
+                            .await
+                            .into_diagnostic()?;
+                    }
                     // Get all removable binaries related to the environment
                     let (to_remove, _to_add) = get_expose_scripts_sync_status(
                         &self.bin_dir,
@@ -1085,9 +1661,16 @@ mod tests {
         let manifest_path: PathBuf = FilePath().fake();
         let env_root = EnvRoot::from_env().await.unwrap();
         let bin_dir = BinDir::from_env().await.unwrap();
+        let completions_dir = CompletionsDir::from_env().await.unwrap();
 
-        let project =
-            Project::from_str(&manifest_path, SIMPLE_MANIFEST, env_root, bin_dir).unwrap();
+        let project = Project::from_str(
+            &manifest_path,
+            SIMPLE_MANIFEST,
+            env_root,
+            bin_dir,
+            completions_dir,
+        )
+        .unwrap();
         assert_eq!(project.root, manifest_path.parent().unwrap());
     }
 
@@ -1098,11 +1681,13 @@ mod tests {
 
         let env_root = EnvRoot::from_env().await.unwrap();
         let bin_dir = BinDir::from_env().await.unwrap();
+        let completions_dir = CompletionsDir::from_env().await.unwrap();
 
         // Create and write global manifest
         let mut file = fs::File::create(&manifest_path).unwrap();
         file.write_all(SIMPLE_MANIFEST.as_bytes()).unwrap();
-        let project = Project::from_path(&manifest_path, env_root, bin_dir).unwrap();
+        let project =
+            Project::from_path(&manifest_path, env_root, bin_dir, completions_dir).unwrap();
 
         // Canonicalize both paths
         let canonical_root = project.root.canonicalize().unwrap();
@@ -1117,9 +1702,10 @@ mod tests {
 
         let env_root = EnvRoot::from_env().await.unwrap();
         let bin_dir = BinDir::from_env().await.unwrap();
+        let completions_dir = CompletionsDir::from_env().await.unwrap();
 
         let manifest = Manifest::from_str(&manifest_path, SIMPLE_MANIFEST).unwrap();
-        let project = Project::from_manifest(manifest, env_root, bin_dir);
+        let project = Project::from_manifest(manifest, env_root, bin_dir, completions_dir);
         assert_eq!(project.root, manifest_path.parent().unwrap());
     }
 
@@ -1128,6 +1714,46 @@ mod tests {
         Project::manifest_dir().unwrap();
     }
 
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_remove_environment_removes_completions() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let mut project = Project::from_str(
+            &tempdir.path().join(consts::GLOBAL_MANIFEST_DEFAULT_NAME),
+            r#"
+            [envs.ruff]
+            channels = ["conda-forge"]
+            [envs.ruff.dependencies]
+            ruff = "*"
+            [envs.ruff.exposed]
+            ruff = "ruff"
+            "#,
+            EnvRoot::new(tempdir.path().to_path_buf()).unwrap(),
+            BinDir::new(tempdir.path().to_path_buf()).unwrap(),
+            CompletionsDir::new(tempdir.path().to_path_buf()).unwrap(),
+        )
+        .unwrap();
+
+        // Link a completion script of the environment
+        let script = project
+            .env_root
+            .path()
+            .join("ruff/share/bash-completion/completions/ruff");
+        fs_err::create_dir_all(script.parent().unwrap()).unwrap();
+        fs_err::write(&script, "").unwrap();
+        let bash_dir = project
+            .completions_dir
+            .shell_dir(crate::global::completions::CompletionShell::Bash);
+        fs_err::create_dir_all(&bash_dir).unwrap();
+        std::os::unix::fs::symlink(&script, bash_dir.join("ruff")).unwrap();
+
+        project
+            .remove_environment(&"ruff".parse().unwrap())
+            .await
+            .unwrap();
+        assert!(!bash_dir.join("ruff").is_symlink());
+    }
+
     #[tokio::test]
     async fn test_prune_exposed() {
         let tempdir = tempfile::tempdir().unwrap();
@@ -1143,6 +1769,7 @@ mod tests {
             "#,
             EnvRoot::new(tempdir.path().to_path_buf()).unwrap(),
             BinDir::new(tempdir.path().to_path_buf()).unwrap(),
+            CompletionsDir::new(tempdir.path().to_path_buf()).unwrap(),
         )
         .unwrap();
 
@@ -1270,6 +1897,7 @@ mod tests {
             manifest,
             env_root.clone(),
             BinDir::new(env_root.path().parent().unwrap().to_path_buf()).unwrap(),
+            CompletionsDir::new(env_root.path().parent().unwrap().to_path_buf()).unwrap(),
         );
 
         // Call the prune method with a list of environments to keep (env1 and env3) but
@@ -1,4 +1,9 @@
-use std::{cmp::Ordering, fmt, path::Path, str::FromStr};
+use std::{
+    cmp::Ordering,
+    fmt,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 
 use console::StyledObject;
 use fancy_display::FancyDisplay;
@@ -6,7 +11,10 @@ use indexmap::{IndexMap, IndexSet};
 use itertools::Itertools;
 use miette::{Context, Diagnostic, IntoDiagnostic, LabeledSpan, NamedSource, Report};
 use pixi_consts::consts;
-use pixi_manifest::{toml::TomlPlatform, utils::package_map::UniquePackageMap, PrioritizedChannel};
+use pixi_manifest::{
+    pypi::PyPiPackageName, toml::TomlPlatform, utils::package_map::UniquePackageMap,
+    PrioritizedChannel, PyPiRequirement,
+};
 use pixi_spec::PixiSpec;
 use pixi_toml::{TomlIndexMap, TomlIndexSet};
 use rattler_conda_types::{NamedChannelOrUrl, PackageName, Platform};
@@ -230,6 +238,21 @@ where
     }
 }
 
+/// Custom serializer for the PyPI dependencies, keyed by their source name.
+fn serialize_pypi_dependencies<S>(
+    dependencies: &IndexMap<PyPiPackageName, PyPiRequirement>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let mut map = serializer.serialize_map(Some(dependencies.len()))?;
+    for (name, requirement) in dependencies {
+        map.serialize_entry(name.as_source(), requirement)?;
+    }
+    map.end()
+}
+
 /// Custom serializer for a map of exposed names to executable names.
 fn serialize_expose_mappings<S>(
     mappings: &IndexSet<Mapping>,
@@ -252,8 +275,26 @@ pub(crate) struct ParsedEnvironment {
     /// Platform used by the environment.
     pub platform: Option<Platform>,
     pub(crate) dependencies: UniquePackageMap,
+    /// PyPI packages that are installed into the environment with uv.
+    #[serde(
+        default,
+        skip_serializing_if = "IndexMap::is_empty",
+        serialize_with = "serialize_pypi_dependencies"
+    )]
+    pub(crate) pypi_dependencies: IndexMap<PyPiPackageName, PyPiRequirement>,
     #[serde(default, serialize_with = "serialize_expose_mappings")]
     pub(crate) exposed: IndexSet<Mapping>,
+    /// Paths inside the environment, relative to its root, that are linked to
+    /// a location outside of it.
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub(crate) exposed_paths: IndexMap<String, PathBuf>,
+    /// Environment variables that the trampoline of an exposed executable
+    /// sets before running it, on top of the activation variables.
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub(crate) exposed_env: IndexMap<ExposedName, IndexMap<String, String>>,
+    /// Exposed executables that get a Start Menu shortcut on Windows.
+    #[serde(default, skip_serializing_if = "IndexSet::is_empty")]
+    pub(crate) shortcuts: IndexSet<ExposedName>,
 }
 
 impl<'de> toml_span::Deserialize<'de> for ParsedEnvironment {
@@ -266,10 +307,32 @@ impl<'de> toml_span::Deserialize<'de> for ParsedEnvironment {
             .unwrap_or_default();
         let platform = th.optional::<TomlPlatform>("platform").map(Platform::from);
         let dependencies = th.optional("dependencies").unwrap_or_default();
+        let pypi_dependencies = th
+            .optional::<TomlIndexMap<_, _>>("pypi-dependencies")
+            .map(TomlIndexMap::into_inner)
+            .unwrap_or_default();
         let exposed = th
             .optional::<TomlMapping>("exposed")
             .map(TomlMapping::into_inner)
             .unwrap_or_default();
+        let exposed_paths = th
+            .optional::<TomlIndexMap<String, String>>("exposed-paths")
+            .map(TomlIndexMap::into_inner)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(path, target)| (path, PathBuf::from(target)))
+            .collect();
+        let exposed_env = th
+            .optional::<TomlIndexMap<ExposedName, TomlIndexMap<String, String>>>("exposed-env")
+            .map(TomlIndexMap::into_inner)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(exposed_name, env)| (exposed_name, env.into_inner()))
+            .collect();
+        let shortcuts = th
+            .optional::<TomlIndexSet<ExposedName>>("shortcuts")
+            .map(TomlIndexSet::into_inner)
+            .unwrap_or_default();
 
         th.finalize(None)?;
 
@@ -277,7 +340,11 @@ impl<'de> toml_span::Deserialize<'de> for ParsedEnvironment {
             channels,
             platform,
             dependencies,
+            pypi_dependencies,
             exposed,
+            exposed_paths,
+            exposed_env,
+            shortcuts,
         })
     }
 }
@@ -306,10 +373,32 @@ impl ParsedEnvironment {
         &self.dependencies.specs
     }
 
+    /// Returns the PyPI dependencies associated with this environment.
+    pub(crate) fn pypi_dependencies(&self) -> &IndexMap<PyPiPackageName, PyPiRequirement> {
+        &self.pypi_dependencies
+    }
+
     /// Returns the exposed name mappings associated with this environment.
     pub(crate) fn exposed(&self) -> &IndexSet<Mapping> {
         &self.exposed
     }
+
+    /// Returns the paths of this environment that are exposed, mapped to the
+    /// location they are exposed at.
+    pub(crate) fn exposed_paths(&self) -> &IndexMap<String, PathBuf> {
+        &self.exposed_paths
+    }
+
+    /// Returns the environment variables that are set for the exposed
+    /// executables of this environment.
+    pub(crate) fn exposed_env(&self) -> &IndexMap<ExposedName, IndexMap<String, String>> {
+        &self.exposed_env
+    }
+
+    /// Returns the exposed executables that get a Start Menu shortcut.
+    pub(crate) fn shortcuts(&self) -> &IndexSet<ExposedName> {
+        &self.shortcuts
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, PartialOrd, Ord)]
@@ -490,4 +579,26 @@ mod tests {
         "#;
         let _manifest = ParsedManifest::from_toml_str(contents).unwrap();
     }
+
+    #[test]
+    fn test_exposed_env_deserialization() {
+        let contents = r#"
+        [envs.napari]
+        channels = ["conda-forge"]
+        [envs.napari.dependencies]
+        napari = "*"
+        [envs.napari.exposed]
+        napari = "napari"
+        [envs.napari.exposed-env.napari]
+        QT_PLUGIN_PATH = "${CONDA_PREFIX}/lib/qt6/plugins"
+        "#;
+        let manifest = ParsedManifest::from_toml_str(contents).unwrap();
+        let environment = manifest.envs.values().next().unwrap();
+        let (exposed_name, env) = environment.exposed_env().first().unwrap();
+        assert_eq!(exposed_name.to_string(), "napari");
+        assert_eq!(
+            env.get("QT_PLUGIN_PATH").map(String::as_str),
+            Some("${CONDA_PREFIX}/lib/qt6/plugins")
+        );
+    }
 }
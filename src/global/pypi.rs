@@ -0,0 +1,471 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Component, Path, PathBuf},
+    str::FromStr,
+};
+
+use fancy_display::FancyDisplay;
+use indexmap::{IndexMap, IndexSet};
+use indicatif::ProgressBar;
+use itertools::Itertools;
+use miette::{Context, IntoDiagnostic};
+use pep440_rs::{Version, VersionSpecifiers};
+use pep508_rs::{PackageName, Requirement};
+use pixi_config::HttpCacheBackend;
+use pixi_consts::consts;
+use pixi_manifest::{
+    pypi::{pypi_options::PypiOptions, PyPiPackageName, VersionOrStar},
+    PyPiRequirement, SystemRequirements,
+};
+use pixi_progress::global_multi_progress;
+use pixi_record::PixiRecord;
+use pixi_utils::{executable_from_path, is_binary_folder};
+use pixi_uv_conversions::{to_uv_normalize, ConversionError};
+use pypi_mapping::MappingSource;
+use rattler::install::PythonInfo;
+use rattler_conda_types::Platform;
+use reqwest_middleware::ClientWithMiddleware;
+
+use super::EnvironmentName;
+use crate::{
+    environment::{update_prefix_pypi, PythonStatus},
+    lock_file::{resolve_pypi, PypiOverrides, UvResolutionContext},
+    prefix::{Executable, Prefix},
+};
+
+/// Resolves the PyPI dependencies of a global environment against its conda
+/// packages and installs them with uv.
+///
+/// If the environment doesn't have PyPI dependencies, the PyPI packages that
+/// were installed before are removed again.
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn install_pypi_dependencies(
+    env_name: &EnvironmentName,
+    prefix: &Prefix,
+    platform: Platform,
+    pixi_records: &[PixiRecord],
+    python_status: &PythonStatus,
+    dependencies: &IndexMap<PyPiPackageName, PyPiRequirement>,
+    uv_context: UvResolutionContext,
+    client: ClientWithMiddleware,
+    http_cache: HttpCacheBackend,
+    manifest_dir: &Path,
+) -> miette::Result<()> {
+    // Without PyPI dependencies there is nothing to do, unless packages of
+    // removed dependencies have to be uninstalled.
+    if dependencies.is_empty() && !has_pypi_packages(prefix)? {
+        return Ok(());
+    }
+
+    let system_requirements = SystemRequirements::default();
+
+    let (pypi_records, env_variables) = if dependencies.is_empty() {
+        (Vec::new(), HashMap::new())
+    } else {
+        let env_variables = prefix.run_activation().await?;
+        let python_path = python_status
+            .location()
+            .map(|path| prefix.root().join(path))
+            .ok_or_else(|| {
+                miette::miette!(
+                    help = "Add `python` to the dependencies of the environment.",
+                    "environment {} has PyPI dependencies but no python interpreter",
+                    env_name.fancy_display()
+                )
+            })?;
+
+        // Find out which PyPI packages are already provided by conda packages
+        let mut pixi_records = pixi_records.to_vec();
+        pypi_mapping::amend_pypi_purls(
+            client,
+            http_cache,
+            &MappingSource::Prefix,
+            pixi_records
+                .iter_mut()
+                .filter_map(PixiRecord::as_binary_mut),
+            None,
+        )
+        .await?;
+
+        let dependencies = dependencies
+            .iter()
+            .map(|(name, requirement)| {
+                Ok((
+                    to_uv_normalize(name.as_normalized())?,
+                    IndexSet::from([requirement.clone()]),
+                ))
+            })
+            .collect::<Result<IndexMap<_, _>, ConversionError>>()
+            .into_diagnostic()?;
+
+        let pb = global_multi_progress().add(ProgressBar::hidden());
+        let pypi_records = resolve_pypi(
+            uv_context.clone(),
+            &PypiOptions::default(),
+            dependencies,
+            system_requirements.clone(),
+            &pixi_records,
+            &[],
+            platform,
+            &pb,
+            &python_path,
+            &env_variables,
+            manifest_dir,
+            &PypiOverrides::default(),
+            None,
+        )
+        .await
+        .wrap_err_with(|| {
+            format!(
+                "failed to solve the PyPI dependencies of {}",
+                env_name.fancy_display()
+            )
+        })?;
+        (pypi_records, env_variables)
+    };
+
+    update_prefix_pypi(
+        &pixi_manifest::EnvironmentName::Named(env_name.to_string()),
+        prefix,
+        platform,
+        pixi_records,
+        &pypi_records,
+        python_status,
+        &system_requirements,
+        &uv_context,
+        None,
+        &[],
+        &env_variables,
+        manifest_dir,
+        platform,
+        None,
+    )
+    .await
+    .wrap_err_with(|| {
+        format!(
+            "failed to install the PyPI dependencies of {}",
+            env_name.fancy_display()
+        )
+    })
+}
+
+/// Returns the `site-packages` directory of the python interpreter installed
+/// in the prefix.
+fn site_packages_dir(prefix: &Prefix) -> miette::Result<Option<PathBuf>> {
+    let python_record = prefix
+        .find_installed_packages()?
+        .into_iter()
+        .find(|record| record.repodata_record.package_record.name.as_normalized() == "python");
+    let Some(python_record) = python_record else {
+        return Ok(None);
+    };
+    let python_info = PythonInfo::from_python_record(
+        &python_record.repodata_record.package_record,
+        Platform::current(),
+    )
+    .into_diagnostic()?;
+    Ok(Some(prefix.root().join(python_info.site_packages_path)))
+}
+
+/// A PyPI package installed in `site-packages`.
+#[derive(Debug)]
+struct DistInfo {
+    /// The normalized name of the package.
+    name: PackageName,
+    /// The installed version, `None` if it couldn't be parsed.
+    version: Option<Version>,
+    /// The path to the `.dist-info` directory.
+    path: PathBuf,
+}
+
+impl DistInfo {
+    /// Returns true if the package was installed by pixi, as opposed to conda
+    /// packages that ship a `.dist-info` directory themselves.
+    fn installed_by_pixi(&self) -> bool {
+        fs_err::read_to_string(self.path.join("INSTALLER"))
+            .is_ok_and(|installer| installer.trim() == consts::PIXI_UV_INSTALLER)
+    }
+
+    /// Returns the names of the packages this package depends on according to
+    /// its `METADATA`. Markers are not evaluated, so dependencies that are
+    /// only required for other platforms or extras are included as well.
+    fn requires_dist(&self) -> Vec<PackageName> {
+        let Ok(metadata) = fs_err::read_to_string(self.path.join("METADATA")) else {
+            return Vec::new();
+        };
+        metadata
+            .lines()
+            .take_while(|line| !line.is_empty())
+            .filter_map(|line| line.strip_prefix("Requires-Dist:"))
+            .filter_map(|requirement| Requirement::from_str(requirement.trim()).ok())
+            .map(|requirement| requirement.name)
+            .collect()
+    }
+}
+
+/// Returns the packages of which a `.dist-info` directory is found in
+/// `site_packages`.
+fn find_dist_infos(site_packages: &Path) -> miette::Result<Vec<DistInfo>> {
+    if !site_packages.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut dist_infos = Vec::new();
+    for entry in fs_err::read_dir(site_packages).into_diagnostic()? {
+        let path = entry.into_diagnostic()?.path();
+        let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        let Some(stem) = file_name.strip_suffix(".dist-info") else {
+            continue;
+        };
+        let (dist_name, version) = match stem.split_once('-') {
+            Some((dist_name, version)) => (dist_name, Version::from_str(version).ok()),
+            None => (stem, None),
+        };
+        let Ok(name) = PackageName::from_str(dist_name) else {
+            continue;
+        };
+        dist_infos.push(DistInfo {
+            name,
+            version,
+            path,
+        });
+    }
+    Ok(dist_infos)
+}
+
+/// Returns the `.dist-info` directory of the installed PyPI package `name`.
+fn find_dist_info(site_packages: &Path, name: &PackageName) -> miette::Result<Option<PathBuf>> {
+    Ok(find_dist_infos(site_packages)?
+        .into_iter()
+        .find(|dist_info| &dist_info.name == name)
+        .map(|dist_info| dist_info.path))
+}
+
+/// Returns true if the installed PyPI packages match the PyPI dependencies:
+/// every dependency is installed with a version that satisfies its
+/// requirement, and no package that pixi installed is left over from a
+/// dependency that was removed.
+pub(crate) fn pypi_dependencies_installed(
+    prefix: &Prefix,
+    dependencies: &IndexMap<PyPiPackageName, PyPiRequirement>,
+) -> miette::Result<bool> {
+    let Some(site_packages) = site_packages_dir(prefix)? else {
+        return Ok(dependencies.is_empty());
+    };
+    Ok(dist_infos_match(
+        &find_dist_infos(&site_packages)?,
+        dependencies,
+    ))
+}
+
+/// Returns true if the dist-infos match the dependencies, see
+/// [`pypi_dependencies_installed`].
+fn dist_infos_match(
+    dist_infos: &[DistInfo],
+    dependencies: &IndexMap<PyPiPackageName, PyPiRequirement>,
+) -> bool {
+    let installed: HashMap<&PackageName, &DistInfo> = dist_infos
+        .iter()
+        .map(|dist_info| (&dist_info.name, dist_info))
+        .collect();
+
+    for (name, requirement) in dependencies {
+        let Some(dist_info) = installed.get(name.as_normalized()) else {
+            return false;
+        };
+        if let Some(specifiers) = version_specifiers(requirement) {
+            if !dist_info
+                .version
+                .as_ref()
+                .is_some_and(|version| specifiers.contains(version))
+            {
+                return false;
+            }
+        }
+    }
+
+    // Every package that pixi installed has to be required, directly or
+    // transitively, by the dependencies.
+    let mut required = HashSet::new();
+    let mut queue = dependencies
+        .keys()
+        .map(|name| name.as_normalized().clone())
+        .collect_vec();
+    while let Some(name) = queue.pop() {
+        if !required.insert(name.clone()) {
+            continue;
+        }
+        if let Some(dist_info) = installed.get(&name) {
+            queue.extend(dist_info.requires_dist());
+        }
+    }
+    dist_infos
+        .iter()
+        .filter(|dist_info| dist_info.installed_by_pixi())
+        .all(|dist_info| required.contains(&dist_info.name))
+}
+
+/// Returns the version specifiers of a requirement, `None` if it doesn't
+/// constrain the version.
+fn version_specifiers(requirement: &PyPiRequirement) -> Option<&VersionSpecifiers> {
+    match requirement {
+        PyPiRequirement::Version {
+            version: VersionOrStar::Version(specifiers),
+            ..
+        }
+        | PyPiRequirement::RawVersion(VersionOrStar::Version(specifiers)) => Some(specifiers),
+        _ => None,
+    }
+}
+
+/// Returns true if pixi installed PyPI packages in the prefix.
+fn has_pypi_packages(prefix: &Prefix) -> miette::Result<bool> {
+    let Some(site_packages) = site_packages_dir(prefix)? else {
+        return Ok(false);
+    };
+    Ok(find_dist_infos(&site_packages)?
+        .iter()
+        .any(DistInfo::installed_by_pixi))
+}
+
+/// Finds the console scripts installed by the PyPI package `name`, based on
+/// the files listed in its `RECORD`.
+pub(crate) fn find_pypi_executables(
+    prefix: &Prefix,
+    name: &PyPiPackageName,
+) -> miette::Result<Vec<Executable>> {
+    let Some(site_packages) = site_packages_dir(prefix)? else {
+        return Ok(Vec::new());
+    };
+    let Some(dist_info) = find_dist_info(&site_packages, name.as_normalized())? else {
+        return Ok(Vec::new());
+    };
+    let record = fs_err::read_to_string(dist_info.join("RECORD")).into_diagnostic()?;
+
+    let executables = record
+        .lines()
+        .filter_map(|line| line.split(',').next())
+        .filter_map(|path| {
+            let absolute = normalize_path(&site_packages.join(path));
+            let relative = absolute.strip_prefix(prefix.root()).ok()?.to_path_buf();
+            let is_script = relative.parent().is_some_and(is_binary_folder)
+                && is_executable::is_executable(&absolute);
+            is_script.then(|| Executable::new(executable_from_path(&relative), relative))
+        })
+        .collect();
+    Ok(executables)
+}
+
+/// Lexically resolves the `..` components of a path, the entries of a
+/// `RECORD` are relative to the `site-packages` directory.
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            Component::CurDir => {}
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_path() {
+        assert_eq!(
+            normalize_path(Path::new(
+                "/envs/ruff/lib/python3.12/site-packages/../../../bin/ruff"
+            )),
+            PathBuf::from("/envs/ruff/bin/ruff")
+        );
+    }
+
+    fn write_dist_info(site_packages: &Path, name: &str, installer: &str, requires: &[&str]) {
+        let dist_info = site_packages.join(format!("{name}.dist-info"));
+        fs_err::create_dir_all(&dist_info).unwrap();
+        fs_err::write(dist_info.join("INSTALLER"), format!("{installer}\n")).unwrap();
+        let metadata = requires
+            .iter()
+            .map(|requirement| format!("Requires-Dist: {requirement}\n"))
+            .collect::<String>();
+        fs_err::write(
+            dist_info.join("METADATA"),
+            format!("Metadata-Version: 2.1\n{metadata}\nDescription"),
+        )
+        .unwrap();
+    }
+
+    fn dependencies(requirements: &[(&str, &str)]) -> IndexMap<PyPiPackageName, PyPiRequirement> {
+        requirements
+            .iter()
+            .map(|(name, version)| {
+                (
+                    PyPiPackageName::from_str(name).unwrap(),
+                    PyPiRequirement::RawVersion(VersionOrStar::from_str(version).unwrap()),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_dist_infos_match() {
+        let site_packages = tempfile::tempdir().unwrap();
+        write_dist_info(
+            site_packages.path(),
+            "ruff-0.5.0",
+            consts::PIXI_UV_INSTALLER,
+            &["click>=8", "colorama; platform_system == 'Windows'"],
+        );
+        write_dist_info(
+            site_packages.path(),
+            "click-8.1.7",
+            consts::PIXI_UV_INSTALLER,
+            &[],
+        );
+        write_dist_info(site_packages.path(), "numpy-1.26.4", "conda", &[]);
+        let dist_infos = find_dist_infos(site_packages.path()).unwrap();
+
+        // The installed version satisfies the requirement
+        assert!(dist_infos_match(
+            &dist_infos,
+            &dependencies(&[("ruff", "*")])
+        ));
+        assert!(dist_infos_match(
+            &dist_infos,
+            &dependencies(&[("Ruff", ">=0.4")])
+        ));
+
+        // The installed version doesn't satisfy the requirement
+        assert!(!dist_infos_match(
+            &dist_infos,
+            &dependencies(&[("ruff", ">=0.6")])
+        ));
+
+        // A dependency is not installed
+        assert!(!dist_infos_match(
+            &dist_infos,
+            &dependencies(&[("ruff", "*"), ("black", "*")])
+        ));
+
+        // A dependency was removed, the packages that pixi installed for it are
+        // left over. Packages installed by conda are ignored.
+        assert!(!dist_infos_match(&dist_infos, &dependencies(&[])));
+        assert!(!dist_infos_match(
+            &dist_infos,
+            &dependencies(&[("click", "*")])
+        ));
+        let conda_only = find_dist_infos(site_packages.path())
+            .unwrap()
+            .into_iter()
+            .filter(|dist_info| !dist_info.installed_by_pixi())
+            .collect_vec();
+        assert!(dist_infos_match(&conda_only, &dependencies(&[])));
+    }
+}
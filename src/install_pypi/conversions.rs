@@ -17,6 +17,30 @@ use uv_pypi_types::{HashAlgorithm, HashDigest, ParsedUrl, ParsedUrlError, Verbat
 
 use super::utils::{is_direct_url, strip_direct_scheme};
 
+/// Converts the locked hashes of a package to uv hashes
+pub fn to_uv_hash_digests(hash: &PackageHashes) -> Vec<HashDigest> {
+    match hash {
+        PackageHashes::Md5(md5) => vec![HashDigest {
+            algorithm: HashAlgorithm::Md5,
+            digest: format!("{:x}", md5).into(),
+        }],
+        PackageHashes::Sha256(sha256) => vec![HashDigest {
+            algorithm: HashAlgorithm::Sha256,
+            digest: format!("{:x}", sha256).into(),
+        }],
+        PackageHashes::Md5Sha256(md5, sha256) => vec![
+            HashDigest {
+                algorithm: HashAlgorithm::Md5,
+                digest: format!("{:x}", md5).into(),
+            },
+            HashDigest {
+                algorithm: HashAlgorithm::Sha256,
+                digest: format!("{:x}", sha256).into(),
+            },
+        ],
+    }
+}
+
 /// Converts our locked data to a file
 pub fn locked_data_to_file(
     url: &Url,
@@ -26,31 +50,7 @@ pub fn locked_data_to_file(
 ) -> Result<uv_distribution_types::File, ConversionError> {
     let url = uv_distribution_types::FileLocation::AbsoluteUrl(UrlString::from(url.clone()));
 
-    // Convert PackageHashes to uv hashes
-    let hashes = if let Some(hash) = hash {
-        match hash {
-            rattler_lock::PackageHashes::Md5(md5) => vec![HashDigest {
-                algorithm: HashAlgorithm::Md5,
-                digest: format!("{:x}", md5).into(),
-            }],
-            rattler_lock::PackageHashes::Sha256(sha256) => vec![HashDigest {
-                algorithm: HashAlgorithm::Sha256,
-                digest: format!("{:x}", sha256).into(),
-            }],
-            rattler_lock::PackageHashes::Md5Sha256(md5, sha256) => vec![
-                HashDigest {
-                    algorithm: HashAlgorithm::Md5,
-                    digest: format!("{:x}", md5).into(),
-                },
-                HashDigest {
-                    algorithm: HashAlgorithm::Sha256,
-                    digest: format!("{:x}", sha256).into(),
-                },
-            ],
-        }
-    } else {
-        vec![]
-    };
+    let hashes = hash.map(to_uv_hash_digests).unwrap_or_default();
 
     let uv_requires_python = requires_python
         .map(|inside| to_uv_version_specifiers(&inside))
@@ -2,7 +2,7 @@ use std::{collections::HashMap, path::Path, sync::Arc};
 
 use conda_pypi_clobber::PypiCondaClobberRegistry;
 use itertools::Itertools;
-use miette::{IntoDiagnostic, WrapErr};
+use miette::{Diagnostic, IntoDiagnostic, WrapErr};
 use pixi_consts::consts;
 use pixi_manifest::SystemRequirements;
 use pixi_record::PixiRecord;
@@ -12,13 +12,14 @@ use pixi_uv_conversions::{
 use pypi_modifiers::pypi_tags::{get_pypi_tags, is_python_record};
 use rattler_conda_types::Platform;
 use rattler_lock::{PypiIndexes, PypiPackageData, PypiPackageEnvironmentData};
+use thiserror::Error;
+use url::Url;
 use utils::elapsed;
-use uv_auth::store_credentials_from_url;
 use uv_client::{Connectivity, FlatIndexClient, RegistryClientBuilder};
-use uv_configuration::{ConfigSettings, Constraints, IndexStrategy, LowerBound};
+use uv_configuration::{BuildOptions, ConfigSettings, Constraints, IndexStrategy, LowerBound};
 use uv_dispatch::{BuildDispatch, SharedState};
 use uv_distribution::{DistributionDatabase, RegistryWheelIndex};
-use uv_distribution_types::{DependencyMetadata, IndexLocations, Name};
+use uv_distribution_types::{DependencyMetadata, Dist, IndexLocations, Name};
 use uv_git::GitResolver;
 use uv_install_wheel::linker::LinkMode;
 use uv_installer::{Preparer, SitePackages, UninstallError};
@@ -29,7 +30,10 @@ use uv_types::HashStrategy;
 use crate::{
     lock_file::UvResolutionContext,
     prefix::Prefix,
+    pypi_auth::store_index_credentials,
+    stats::{self, Phase},
     uv_reporter::{UvReporter, UvReporterOptions},
+    verify_hashes,
 };
 
 use plan::{InstallPlanner, InstallReason, NeedReinstall, PixiInstallPlan};
@@ -42,6 +46,45 @@ pub(crate) mod utils;
 
 type CombinedPypiPackageData = (PypiPackageData, PypiPackageEnvironmentData);
 
+/// The locked packages that have to be built from source, while building
+/// source distributions is disabled in the `pypi-options`.
+#[derive(Debug, Error, Diagnostic)]
+#[error(
+    "building source distributions is disabled, but the lock file requires building {}",
+    packages.iter().map(|package| format!("'{package}'")).join(", ")
+)]
+#[diagnostic(help(
+    "Update the lock file to use the wheels of these packages, or allow building them by disabling `no-build` or removing them from `only-binary` in the `pypi-options`."
+))]
+struct NoBuildError {
+    packages: Vec<String>,
+}
+
+/// Returns a [`NoBuildError`] with the source distributions that would have
+/// to be built, while building them is disabled by the build options.
+/// Editable packages are always built.
+fn check_no_build<'a>(
+    dists: impl IntoIterator<Item = &'a Dist>,
+    build_options: &BuildOptions,
+) -> Result<(), NoBuildError> {
+    let packages = dists
+        .into_iter()
+        .filter_map(|dist| match dist {
+            Dist::Source(source)
+                if !source.is_editable() && build_options.no_build_package(source.name()) =>
+            {
+                Some(source.name().to_string())
+            }
+            _ => None,
+        })
+        .collect_vec();
+    if packages.is_empty() {
+        Ok(())
+    } else {
+        Err(NoBuildError { packages })
+    }
+}
+
 /// Installs and/or remove python distributions.
 // TODO: refactor arguments in struct
 #[allow(clippy::too_many_arguments)]
@@ -54,6 +97,7 @@ pub async fn update_python_distributions(
     system_requirements: &SystemRequirements,
     uv_context: &UvResolutionContext,
     pypi_indexes: Option<&PypiIndexes>,
+    pinned_indexes: &[Url],
     environment_variables: &HashMap<String, String>,
     platform: Platform,
     non_isolated_packages: Option<Vec<String>>,
@@ -296,6 +340,13 @@ pub async fn update_python_distributions(
         );
     }
 
+    // Fail with all the packages that would have to be built at once, instead
+    // of on the first source distribution that uv refuses to build.
+    check_no_build(
+        remote.iter().map(|(dist, _)| dist),
+        &uv_context.build_options,
+    )?;
+
     // Download, build, and unzip any missing distributions.
     let remote_dists = if remote.is_empty() {
         Vec::new()
@@ -314,16 +365,31 @@ pub async fn update_python_distributions(
         );
 
         // Before hitting the network let's make sure the credentials are available to
-        // uv
-        for url in index_locations.indexes().map(|index| index.url()) {
-            let success = store_credentials_from_url(url);
-            tracing::debug!("Stored credentials for {}: {}", url, success);
-        }
+        // uv. The credentials of an index that is pinned on a dependency are only
+        // used for the requests to that index.
+        store_index_credentials(
+            &uv_context.auth_storage,
+            index_locations
+                .indexes()
+                .map(|index| &**index.url())
+                .chain(pinned_indexes),
+        );
+
+        // Verify the downloaded distributions against the hashes in the lock-file
+        let hash_strategy = verify_hashes::pypi_hash_strategy(
+            uv_context.verify_hashes,
+            remote.iter().map(|(dist, _)| {
+                let hash = required_map
+                    .get(dist.name())
+                    .and_then(|pkg| pkg.hash.as_ref());
+                (dist, hash)
+            }),
+        )?;
 
         let preparer = Preparer::new(
             &uv_context.cache,
             &tags,
-            &uv_types::HashStrategy::None,
+            &hash_strategy,
             &uv_context.build_options,
             distribution_database,
         )
@@ -337,6 +403,7 @@ pub async fn update_python_distributions(
             .await
             .into_diagnostic()
             .context("Failed to prepare distributions")?;
+        stats::record(Phase::Download, start.elapsed());
 
         let s = if remote_dists.len() == 1 { "" } else { "s" };
         tracing::info!(
@@ -351,6 +418,11 @@ pub async fn update_python_distributions(
         remote_dists
     };
 
+    // When warming up the cache, the distributions are only prepared.
+    if uv_context.prepare_only {
+        return Ok(());
+    }
+
     // Remove any unnecessary packages.
     if !extraneous.is_empty() || !reinstalls.is_empty() {
         let start = std::time::Instant::now();
@@ -470,6 +542,7 @@ pub async fn update_python_distributions(
             .install(all_dists.clone())
             .await
             .unwrap();
+        stats::record(Phase::Link, start.elapsed());
 
         let s = if all_dists.len() == 1 { "" } else { "s" };
         tracing::info!(
@@ -484,3 +557,69 @@ pub async fn update_python_distributions(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use rattler_lock::UrlOrPath;
+
+    use super::*;
+
+    fn dist(name: &str, url: &str) -> Dist {
+        conversions::convert_to_dist(
+            &PypiPackageData {
+                name: name.parse().unwrap(),
+                version: "1.0".parse().unwrap(),
+                location: UrlOrPath::from_str(url).unwrap(),
+                hash: None,
+                requires_dist: vec![],
+                requires_python: None,
+                editable: false,
+            },
+            Path::new(""),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_check_no_build() {
+        let dists = [
+            dist(
+                "foo",
+                "https://files.pythonhosted.org/packages/foo-1.0.tar.gz",
+            ),
+            dist(
+                "bar",
+                "https://files.pythonhosted.org/packages/bar-1.0-py3-none-any.whl",
+            ),
+            dist(
+                "baz",
+                "https://files.pythonhosted.org/packages/baz-1.0.tar.gz",
+            ),
+        ];
+
+        // Source distributions may be built by default
+        check_no_build(&dists, &BuildOptions::default()).unwrap();
+
+        // All source distributions that can't be built are reported at once
+        let build_options = BuildOptions::new(
+            uv_configuration::NoBinary::None,
+            uv_configuration::NoBuild::All,
+        );
+        let err = check_no_build(&dists, &build_options).unwrap_err();
+        assert_eq!(err.packages, vec!["foo", "baz"]);
+        assert_eq!(
+            err.to_string(),
+            "building source distributions is disabled, but the lock file requires building 'foo', 'baz'"
+        );
+
+        // Only the packages of `only-binary` are reported
+        let build_options = BuildOptions::new(
+            uv_configuration::NoBinary::None,
+            uv_configuration::NoBuild::Packages(vec!["baz".parse().unwrap()]),
+        );
+        let err = check_no_build(&dists, &build_options).unwrap_err();
+        assert_eq!(err.packages, vec!["baz"]);
+    }
+}
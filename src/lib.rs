@@ -1,13 +1,17 @@
 pub mod activation;
 pub mod cli;
+mod clobbers;
 mod diff;
 pub mod environment;
+mod external_managers;
 mod global;
 mod install_pypi;
 pub mod lock_file;
 mod prefix;
 mod project;
 mod prompt;
+mod pypi_auth;
+mod repair;
 pub(crate) mod repodata;
 pub mod task;
 
@@ -15,7 +19,10 @@ mod uv_reporter;
 
 mod build;
 mod rlimit;
+mod signing;
+mod stats;
 mod utils;
+mod verify_hashes;
 
 pub use lock_file::{load_lock_file, UpdateLockFileOptions};
 pub use project::{DependencyType, Project};
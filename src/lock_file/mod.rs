@@ -1,28 +1,48 @@
 mod outdated;
 mod package_identifier;
+mod package_overrides;
+mod per_environment;
+mod ready_state;
 mod records_by_name;
 mod reporter;
 mod resolve;
 mod satisfiability;
+mod solve_group;
+#[cfg(test)]
+pub(crate) mod test_utils;
 mod update;
 mod utils;
+mod vendor;
+
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 
-use crate::Project;
+use crate::{project::Environment, Project};
 use miette::{IntoDiagnostic, WrapErr};
 pub(crate) use package_identifier::PypiPackageIdentifier;
+pub(crate) use package_overrides::{CondaOverrides, PypiOverrides};
+use per_environment::{environment_lock_file_path, environment_lock_file_paths};
+pub(crate) use per_environment::{merge_lock_files, read_environment_lock_files};
 use pixi_record::PixiRecord;
 use rattler_lock::{LockFile, ParseCondaLockError, PypiPackageData, PypiPackageEnvironmentData};
 pub(crate) use records_by_name::{PixiRecordsByName, PypiRecordsByName};
+pub(crate) use reporter::{CondaMetadataProgress, JsonProgressReporter};
 pub(crate) use resolve::{
     conda::resolve_conda, pypi::resolve_pypi, uv_resolution_context::UvResolutionContext,
 };
 pub use satisfiability::{
     verify_environment_satisfiability, verify_platform_satisfiability, EnvironmentUnsat,
-    PlatformUnsat,
+    LockFileInput, PlatformUnsat,
 };
 pub(crate) use update::{LockFileDerivedData, UpdateContext};
-pub use update::{UpdateLockFileOptions, UpdateMode};
-pub(crate) use utils::filter_lock_file;
+pub use update::{SolveFailure, UpdateLockFileOptions, UpdateMode};
+pub(crate) use utils::{
+    apply_requested_ignore_run_exports, dependency_closure, dependency_name, filter_lock_file,
+    prune_lock_file, prune_unlocked_platforms, LockedPackageName,
+};
+pub(crate) use vendor::{pypi_file_name, VendorDir};
 
 /// A list of conda packages that are locked for a specific platform.
 pub type LockedCondaPackages = Vec<PixiRecord>;
@@ -36,38 +56,177 @@ pub type PypiRecord = (PypiPackageData, PypiPackageEnvironmentData);
 
 /// Loads the lockfile for the specified project or returns a dummy one if none
 /// could be found.
+///
+/// When the project stores a lock-file per environment, these are merged into
+/// a single lock-file. The single lock-file is only used if no environment has
+/// been locked separately yet.
 pub async fn load_lock_file(project: &Project) -> miette::Result<LockFile> {
-    let lock_file_path = project.lock_file_path();
-    if lock_file_path.is_file() {
+    LockFileSources::read(project).await?.parse()
+}
+
+/// The contents of the lock-files of a project as they are on disk. Reading
+/// them separately from parsing them allows hashing the exact bytes that are
+/// parsed, without reading the files twice.
+pub(crate) struct LockFileSources {
+    /// Whether the files are the lock-files of the environments.
+    per_environment: bool,
+    /// The paths and contents of the lock-files in a stable order.
+    files: Vec<(PathBuf, String)>,
+}
+
+impl LockFileSources {
+    /// Reads the lock-files of the project. There are no files if the project
+    /// isn't locked yet.
+    pub(crate) async fn read(project: &Project) -> miette::Result<Self> {
+        let lock_file_path = project.lock_file_path();
+        let environment_lock_files_dir = project
+            .per_environment_lock_files()
+            .then(|| project.environment_lock_files_dir());
         // Spawn a background task because loading the file might be IO bound.
         tokio::task::spawn_blocking(move || {
-            LockFile::from_path(&lock_file_path)
-                .map_err(|err| match err {
-                    ParseCondaLockError::IncompatibleVersion{ lock_file_version, max_supported_version} => {
-                        miette::miette!(
-                            help="Please update pixi to the latest version and try again.",
-                            "The lock file version is {}, but only up to including version {} is supported by the current version.",
-                            lock_file_version, max_supported_version
-                        )
-                    }
-                    _ => miette::miette!(err),
-                })
-                .wrap_err_with(|| {
-                    format!(
-                        "Failed to load lock file from `{}`",
-                        lock_file_path.display()
-                    )
-                })
+            let (per_environment, paths) =
+                locked_paths(lock_file_path, environment_lock_files_dir)?;
+            Ok(Self {
+                per_environment,
+                files: read_files(paths)?,
+            })
         })
-            .await
-            .unwrap_or_else(|e| Err(e).into_diagnostic())
+        .await
+        .unwrap_or_else(|e| Err(e).into_diagnostic())
+    }
+
+    /// The paths and contents of the lock-files.
+    pub(crate) fn files(&self) -> &[(PathBuf, String)] {
+        &self.files
+    }
+
+    /// Parses the lock-files into a single lock-file.
+    pub(crate) fn parse(&self) -> miette::Result<LockFile> {
+        let mut lock_files = self
+            .files
+            .iter()
+            .map(|(path, contents)| parse_lock_file(path, contents))
+            .collect::<miette::Result<Vec<_>>>()?;
+        if self.per_environment {
+            Ok(merge_lock_files(&lock_files))
+        } else {
+            Ok(lock_files.pop().unwrap_or_default())
+        }
+    }
+}
+
+/// Returns the lock-files of the project that are on disk, and whether these
+/// are the lock-files of the environments.
+fn locked_paths(
+    lock_file_path: PathBuf,
+    environment_lock_files_dir: Option<PathBuf>,
+) -> miette::Result<(bool, Vec<PathBuf>)> {
+    if let Some(dir) = environment_lock_files_dir {
+        let paths = environment_lock_file_paths(&dir)?;
+        if !paths.is_empty() {
+            return Ok((true, paths));
+        }
+    }
+    if lock_file_path.is_file() {
+        Ok((false, vec![lock_file_path]))
     } else {
-        Ok(LockFile::default())
+        Ok((false, Vec::new()))
     }
 }
 
+/// Returns the lock-files the project is currently locked in, these are the
+/// files [`load_lock_file`] reads.
+pub(crate) fn lock_file_paths(project: &Project) -> miette::Result<Vec<PathBuf>> {
+    let environment_lock_files_dir = project
+        .per_environment_lock_files()
+        .then(|| project.environment_lock_files_dir());
+    let (_, paths) = locked_paths(project.lock_file_path(), environment_lock_files_dir)?;
+    Ok(paths)
+}
+
+/// Returns the lock-files that can lock the environment, whether they exist
+/// or not. This includes the single lock-file of the project when the
+/// environments are locked separately, because it is read until the lock-file
+/// of the environment is written.
+pub(crate) fn environment_lock_file_candidates(environment: &Environment<'_>) -> Vec<PathBuf> {
+    let project = environment.project();
+    let mut paths = vec![project.lock_file_path()];
+    if project.per_environment_lock_files() {
+        paths.push(environment_lock_file_path(
+            &project.environment_lock_files_dir(),
+            environment.name().as_str(),
+        ));
+    }
+    paths
+}
+
+fn read_files(paths: Vec<PathBuf>) -> miette::Result<Vec<(PathBuf, String)>> {
+    paths
+        .into_iter()
+        .map(|path| {
+            let contents = fs_err::read_to_string(&path).into_diagnostic()?;
+            Ok((path, contents))
+        })
+        .collect()
+}
+
+/// Reads a lock-file from disk.
+fn read_lock_file_from_path(lock_file_path: &Path) -> miette::Result<LockFile> {
+    let contents = fs_err::read_to_string(lock_file_path).into_diagnostic()?;
+    parse_lock_file(lock_file_path, &contents)
+}
+
+/// Parses the contents of the lock-file at the given path.
+fn parse_lock_file(lock_file_path: &Path, contents: &str) -> miette::Result<LockFile> {
+    LockFile::from_str(contents)
+        .map_err(|err| match err {
+            ParseCondaLockError::IncompatibleVersion{ lock_file_version, max_supported_version} => {
+                miette::miette!(
+                    help="Please update pixi to the latest version and try again.",
+                    "The lock file version is {}, but only up to including version {} is supported by the current version.",
+                    lock_file_version, max_supported_version
+                )
+            }
+            _ => miette::miette!(err),
+        })
+        .wrap_err_with(|| {
+            format!(
+                "Failed to load lock file from `{}`",
+                lock_file_path.display()
+            )
+        })
+}
+
+/// Writes the lock-file of the project to disk, either as a single lock-file
+/// or as a lock-file per environment.
+pub(crate) fn write_lock_file(project: &Project, lock_file: &LockFile) -> miette::Result<()> {
+    if !project.per_environment_lock_files() {
+        return lock_file
+            .to_path(&project.lock_file_path())
+            .into_diagnostic();
+    }
+
+    per_environment::write_environment_lock_files(
+        &project.environment_lock_files_dir(),
+        lock_file,
+    )?;
+
+    // The single lock-file is replaced by the lock-files of the environments.
+    let lock_file_path = project.lock_file_path();
+    if lock_file_path.is_file() {
+        tracing::info!(
+            "removing '{}', the environments are locked in '{}'",
+            lock_file_path.display(),
+            project.environment_lock_files_dir().display()
+        );
+        fs_err::remove_file(&lock_file_path).into_diagnostic()?;
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
+    use super::{environment_lock_file_candidates, lock_file_paths};
     use crate::{load_lock_file, Project};
 
     #[tokio::test]
@@ -105,4 +264,39 @@ mod tests {
         // Also test that we try to help user by suggesting to update pixi.
         assert!(dbg_err.contains("Please update pixi to the latest version and try again."));
     }
+
+    #[test]
+    fn test_lock_file_paths_per_environment() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let project = r#"
+        [project]
+        name = "pixi"
+        channels = []
+        platforms = []
+        per-environment-lock-files = true
+        "#;
+        let project =
+            Project::from_str(temp_dir.path().join("pixi.toml").as_path(), project).unwrap();
+        let environment = project.default_environment();
+        let environment_lock_file = project.environment_lock_files_dir().join("default.lock");
+        assert_eq!(
+            environment_lock_file_candidates(&environment),
+            vec![project.lock_file_path(), environment_lock_file.clone()]
+        );
+
+        // The single lock-file is used until the environments are locked
+        // separately.
+        assert!(lock_file_paths(&project).unwrap().is_empty());
+        fs_err::write(project.lock_file_path(), "").unwrap();
+        assert_eq!(
+            lock_file_paths(&project).unwrap(),
+            vec![project.lock_file_path()]
+        );
+        fs_err::create_dir_all(project.environment_lock_files_dir()).unwrap();
+        fs_err::write(&environment_lock_file, "").unwrap();
+        assert_eq!(
+            lock_file_paths(&project).unwrap(),
+            vec![environment_lock_file]
+        );
+    }
 }
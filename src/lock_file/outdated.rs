@@ -1,16 +1,19 @@
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+};
 
 use fancy_display::FancyDisplay;
 use itertools::Itertools;
 use pixi_consts::consts;
-use pixi_manifest::FeaturesExt;
+use pixi_manifest::{EnvironmentName, FeaturesExt};
 use rattler_conda_types::Platform;
 use rattler_lock::{LockFile, LockedPackageRef};
 
 use super::{verify_environment_satisfiability, verify_platform_satisfiability};
 use crate::{
     build::GlobHashCache,
-    lock_file::satisfiability::EnvironmentUnsat,
+    lock_file::{satisfiability::EnvironmentUnsat, LockFileInput},
     project::{Environment, SolveGroup},
     Project,
 };
@@ -34,6 +37,50 @@ pub struct OutdatedEnvironments<'p> {
     /// discarded. This is the case for instance when the order of the
     /// channels changed.
     pub disregard_locked_content: DisregardLockedContent<'p>,
+
+    /// The reasons why the environments are out of date, in the order they
+    /// were found.
+    pub drift: Vec<LockFileDrift>,
+}
+
+/// Describes a part of the project that no longer matches the lock-file.
+#[derive(Debug, Clone)]
+pub struct LockFileDrift {
+    /// The environment, or solve-group, that drifted.
+    pub scope: DriftScope,
+
+    /// The platform that drifted, or `None` if it applies to all platforms.
+    pub platform: Option<Platform>,
+
+    /// The input of the lock-file that drifted.
+    pub input: LockFileInput,
+
+    /// A description of the difference.
+    pub reason: String,
+}
+
+/// The part of the project a [`LockFileDrift`] applies to.
+#[derive(Debug, Clone)]
+pub enum DriftScope {
+    Environment(EnvironmentName),
+    SolveGroup(String),
+}
+
+impl Display for LockFileDrift {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.scope {
+            DriftScope::Environment(name) => write!(f, "environment {}", name.fancy_display())?,
+            DriftScope::SolveGroup(name) => write!(
+                f,
+                "solve-group {}",
+                consts::SOLVE_GROUP_STYLE.apply_to(name)
+            )?,
+        }
+        if let Some(platform) = self.platform {
+            write!(f, " ({})", consts::PLATFORM_STYLE.apply_to(platform))?;
+        }
+        write!(f, ", {}: {}", self.input, self.reason)
+    }
 }
 
 /// A struct that stores whether the locked content of certain environments
@@ -69,6 +116,7 @@ impl<'p> OutdatedEnvironments<'p> {
         let mut outdated_conda: HashMap<_, HashSet<_>> = HashMap::new();
         let mut outdated_pypi: HashMap<_, HashSet<_>> = HashMap::new();
         let mut disregard_locked_content = DisregardLockedContent::default();
+        let mut drift = Vec::new();
 
         // Find all targets that are not satisfied by the lock-file
         find_unsatisfiable_targets(
@@ -77,6 +125,7 @@ impl<'p> OutdatedEnvironments<'p> {
             &mut outdated_conda,
             &mut outdated_pypi,
             &mut disregard_locked_content,
+            &mut drift,
             glob_hash_cache,
         )
         .await;
@@ -93,6 +142,7 @@ impl<'p> OutdatedEnvironments<'p> {
             &outdated_conda,
             &mut conda_solve_groups_out_of_date,
             &mut pypi_solve_groups_out_of_date,
+            &mut drift,
         );
 
         // Mark the rest of the environments out of date for all solve groups
@@ -127,6 +177,7 @@ impl<'p> OutdatedEnvironments<'p> {
             conda: outdated_conda,
             pypi: outdated_pypi,
             disregard_locked_content,
+            drift,
         }
     }
 
@@ -135,6 +186,21 @@ impl<'p> OutdatedEnvironments<'p> {
     pub(crate) fn is_empty(&self) -> bool {
         self.conda.is_empty() && self.pypi.is_empty()
     }
+
+    /// Only keep the outdated targets of the given platforms.
+    pub(crate) fn retain_platforms(&mut self, platforms: &HashSet<Platform>) {
+        for outdated in [&mut self.conda, &mut self.pypi] {
+            outdated.retain(|_, outdated_platforms| {
+                outdated_platforms.retain(|platform| platforms.contains(platform));
+                !outdated_platforms.is_empty()
+            });
+        }
+        self.drift.retain(|drift| {
+            drift
+                .platform
+                .map_or(true, |platform| platforms.contains(&platform))
+        });
+    }
 }
 
 /// Find all targets (combination of environment and platform) who's
@@ -145,10 +211,11 @@ async fn find_unsatisfiable_targets<'p>(
     outdated_conda: &mut HashMap<Environment<'p>, HashSet<Platform>>,
     outdated_pypi: &mut HashMap<Environment<'p>, HashSet<Platform>>,
     disregard_locked_content: &mut DisregardLockedContent<'p>,
+    drift: &mut Vec<LockFileDrift>,
     glob_hash_cache: GlobHashCache,
 ) {
     for environment in project.environments() {
-        let platforms = environment.platforms();
+        let platforms = environment.locked_platforms();
 
         // Get the locked environment from the environment
         let Some(locked_environment) = lock_file.environment(environment.name().as_str()) else {
@@ -157,6 +224,12 @@ async fn find_unsatisfiable_targets<'p>(
                 environment.name().fancy_display()
             );
 
+            drift.push(LockFileDrift {
+                scope: DriftScope::Environment(environment.name().clone()),
+                platform: None,
+                input: LockFileInput::Environment,
+                reason: "the environment is missing from the lock-file".to_string(),
+            });
             outdated_conda
                 .entry(environment.clone())
                 .or_default()
@@ -172,6 +245,12 @@ async fn find_unsatisfiable_targets<'p>(
                 environment.name().fancy_display()
             );
 
+            drift.push(LockFileDrift {
+                scope: DriftScope::Environment(environment.name().clone()),
+                platform: None,
+                input: unsat.input(),
+                reason: unsat.to_string(),
+            });
             outdated_conda
                 .entry(environment.clone())
                 .or_default()
@@ -210,6 +289,12 @@ async fn find_unsatisfiable_targets<'p>(
                         environment.name().fancy_display()
                     );
 
+                    drift.push(LockFileDrift {
+                        scope: DriftScope::Environment(environment.name().clone()),
+                        platform: Some(platform),
+                        input: unsat.input(),
+                        reason: unsat.to_string(),
+                    });
                     outdated_pypi
                         .entry(environment.clone())
                         .or_default()
@@ -221,6 +306,12 @@ async fn find_unsatisfiable_targets<'p>(
                         environment.name().fancy_display()
                     );
 
+                    drift.push(LockFileDrift {
+                        scope: DriftScope::Environment(environment.name().clone()),
+                        platform: Some(platform),
+                        input: unsat.input(),
+                        reason: unsat.to_string(),
+                    });
                     outdated_conda
                         .entry(environment.clone())
                         .or_default()
@@ -288,12 +379,13 @@ fn find_inconsistent_solve_groups<'p>(
     outdated_conda: &HashMap<Environment<'p>, HashSet<Platform>>,
     conda_solve_groups_out_of_date: &mut HashMap<SolveGroup<'p>, HashSet<Platform>>,
     pypi_solve_groups_out_of_date: &mut HashMap<SolveGroup<'p>, HashSet<Platform>>,
+    drift: &mut Vec<LockFileDrift>,
 ) {
     let solve_groups = project.solve_groups();
     let solve_groups_and_platforms = solve_groups.iter().flat_map(|solve_group| {
         solve_group
             .environments()
-            .flat_map(|env| env.platforms())
+            .flat_map(|env| env.locked_platforms())
             .unique()
             .map(move |platform| (solve_group, platform))
     });
@@ -371,6 +463,13 @@ fn find_inconsistent_solve_groups<'p>(
             tracing::info!("the locked conda packages in solve group {} are not consistent for all environments for platform {}",
                         consts::SOLVE_GROUP_STYLE.apply_to(solve_group.name()),
                         consts::PLATFORM_STYLE.apply_to(platform));
+            drift.push(LockFileDrift {
+                scope: DriftScope::SolveGroup(solve_group.name().to_string()),
+                platform: Some(platform),
+                input: LockFileInput::SolveGroup,
+                reason: "the locked conda packages are not consistent for all environments"
+                    .to_string(),
+            });
             conda_solve_groups_out_of_date
                 .entry(solve_group.clone())
                 .or_default()
@@ -381,6 +480,13 @@ fn find_inconsistent_solve_groups<'p>(
             tracing::info!("the locked pypi packages in solve group {} are not consistent for all environments for platform {}",
                         consts::SOLVE_GROUP_STYLE.apply_to(solve_group.name()),
                         consts::PLATFORM_STYLE.apply_to(platform));
+            drift.push(LockFileDrift {
+                scope: DriftScope::SolveGroup(solve_group.name().to_string()),
+                platform: Some(platform),
+                input: LockFileInput::SolveGroup,
+                reason: "the locked pypi packages are not consistent for all environments"
+                    .to_string(),
+            });
             pypi_solve_groups_out_of_date
                 .entry(solve_group.clone())
                 .or_default()
@@ -0,0 +1,327 @@
+use std::{borrow::Cow, collections::HashMap};
+
+use indexmap::IndexMap;
+use pixi_manifest::{
+    pypi::PyPiPackageName, CondaPackageOverride, PackageOverride, PypiPackageOverride,
+};
+use pixi_uv_conversions::{to_uv_normalize, to_uv_version_specifiers, ConversionError};
+use rattler_conda_types::{
+    MatchSpec, PackageName, PackageRecord, ParseStrictness::Lenient, RepoDataRecord,
+};
+use uv_configuration::Overrides;
+use uv_pep508::MarkerTree;
+use uv_pypi_types::{Requirement, RequirementSource};
+
+/// The `[package-overrides]` of a workspace, applied to the requirements and
+/// records of a conda solve.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct CondaOverrides(IndexMap<PackageName, CondaPackageOverride>);
+
+impl From<IndexMap<PackageName, CondaPackageOverride>> for CondaOverrides {
+    fn from(overrides: IndexMap<PackageName, CondaPackageOverride>) -> Self {
+        Self(overrides)
+    }
+}
+
+impl CondaOverrides {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns the name of the package that is installed for a requirement on
+    /// `name`, or `None` if the package is excluded.
+    pub(crate) fn effective_name(&self, name: &PackageName) -> Option<PackageName> {
+        match self.0.get(name) {
+            Some(PackageOverride::Exclude) => None,
+            Some(PackageOverride::ReplaceWith(replacement)) => Some(replacement.clone()),
+            Some(PackageOverride::Version(_)) | None => Some(name.clone()),
+        }
+    }
+
+    /// Applies the override of the package to a requirement. Returns `None` if
+    /// the package is excluded.
+    pub(crate) fn apply_to_spec(&self, spec: MatchSpec) -> Option<MatchSpec> {
+        let Some(name) = &spec.name else {
+            return Some(spec);
+        };
+        match self.0.get(name) {
+            None => Some(spec),
+            Some(PackageOverride::Exclude) => None,
+            Some(PackageOverride::ReplaceWith(replacement)) => Some(MatchSpec {
+                name: Some(replacement.clone()),
+                ..MatchSpec::default()
+            }),
+            Some(PackageOverride::Version(version)) => Some(MatchSpec {
+                name: Some(name.clone()),
+                version: Some(version.clone()),
+                ..MatchSpec::default()
+            }),
+        }
+    }
+
+    /// Returns true if the record can be selected by the solver.
+    pub(crate) fn allows(&self, record: &PackageRecord) -> bool {
+        match self.0.get(&record.name) {
+            None => true,
+            Some(PackageOverride::Exclude | PackageOverride::ReplaceWith(_)) => false,
+            Some(PackageOverride::Version(version)) => version.matches(record.version.version()),
+        }
+    }
+
+    /// Applies the overrides to the dependencies of a record. Returns `None` if
+    /// the record itself cannot be selected, the record is only cloned if one
+    /// of its dependencies is overridden.
+    pub(crate) fn apply_to_record<'r>(
+        &self,
+        record: &'r RepoDataRecord,
+    ) -> Option<Cow<'r, RepoDataRecord>> {
+        if self.is_empty() {
+            return Some(Cow::Borrowed(record));
+        }
+        if !self.allows(&record.package_record) {
+            return None;
+        }
+
+        // Only the name of the requirements is needed to check if they are
+        // overridden, which avoids parsing the requirements of every record.
+        let is_overridden = |spec: &str| {
+            let name = spec
+                .split(|c: char| c.is_whitespace() || "[<>=!~".contains(c))
+                .next()
+                .unwrap_or(spec);
+            self.0.contains_key(&PackageName::new_unchecked(name))
+        };
+        let package_record = &record.package_record;
+        if !package_record
+            .depends
+            .iter()
+            .chain(&package_record.constrains)
+            .any(|spec| is_overridden(spec))
+        {
+            return Some(Cow::Borrowed(record));
+        }
+
+        let mut record = record.clone();
+        record.package_record.depends = record
+            .package_record
+            .depends
+            .into_iter()
+            .filter_map(|depends| match MatchSpec::from_str(&depends, Lenient) {
+                Ok(spec) => self.apply_to_spec(spec).map(|spec| spec.to_string()),
+                Err(_) => Some(depends),
+            })
+            .collect();
+        // The override replaces every requirement on the package, including
+        // the constraints.
+        record
+            .package_record
+            .constrains
+            .retain(|spec| !is_overridden(spec));
+        Some(Cow::Owned(record))
+    }
+
+    /// Returns requirements on the packages that replace other packages, these
+    /// have to be fetched in addition to the requirements of the environment.
+    pub(crate) fn replacements(&self) -> impl Iterator<Item = MatchSpec> + '_ {
+        self.0
+            .values()
+            .filter_map(|package_override| match package_override {
+                PackageOverride::ReplaceWith(replacement) => Some(MatchSpec {
+                    name: Some(replacement.clone()),
+                    ..MatchSpec::default()
+                }),
+                _ => None,
+            })
+    }
+}
+
+/// The override of a PyPI package, converted to uv types.
+#[derive(Debug, Clone)]
+enum UvPackageOverride {
+    Version(uv_pep440::VersionSpecifiers),
+    ReplaceWith(uv_normalize::PackageName),
+    Exclude,
+}
+
+/// The `[pypi-package-overrides]` of a workspace, applied to the requirements
+/// of a PyPI solve.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PypiOverrides(HashMap<uv_normalize::PackageName, UvPackageOverride>);
+
+impl PypiOverrides {
+    pub(crate) fn from_manifest(
+        overrides: &IndexMap<PyPiPackageName, PypiPackageOverride>,
+    ) -> Result<Self, ConversionError> {
+        overrides
+            .iter()
+            .map(|(name, package_override)| {
+                let package_override = match package_override {
+                    PackageOverride::Version(specifiers) => {
+                        UvPackageOverride::Version(to_uv_version_specifiers(specifiers)?)
+                    }
+                    PackageOverride::ReplaceWith(replacement) => UvPackageOverride::ReplaceWith(
+                        to_uv_normalize(replacement.as_normalized())?,
+                    ),
+                    PackageOverride::Exclude => UvPackageOverride::Exclude,
+                };
+                Ok((to_uv_normalize(name.as_normalized())?, package_override))
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns the name of the package that is installed for a requirement on
+    /// `name`, or `None` if the package is excluded.
+    pub(crate) fn effective_name(
+        &self,
+        name: &uv_normalize::PackageName,
+    ) -> Option<uv_normalize::PackageName> {
+        match self.0.get(name) {
+            Some(UvPackageOverride::Exclude) => None,
+            Some(UvPackageOverride::ReplaceWith(replacement)) => Some(replacement.clone()),
+            Some(UvPackageOverride::Version(_)) | None => Some(name.clone()),
+        }
+    }
+
+    /// Applies the override of the package to a requirement. Returns `None` if
+    /// the package is excluded or replaced, the replacements are required by
+    /// the environment itself, see [`Self::replacements`].
+    pub(crate) fn apply_to_requirement(&self, requirement: Requirement) -> Option<Requirement> {
+        match self.0.get(&requirement.name) {
+            None => Some(requirement),
+            Some(UvPackageOverride::Exclude | UvPackageOverride::ReplaceWith(_)) => None,
+            Some(UvPackageOverride::Version(specifier)) => Some(Requirement {
+                source: registry_source(specifier.clone()),
+                ..requirement
+            }),
+        }
+    }
+
+    /// Returns requirements on the packages that replace other packages.
+    pub(crate) fn replacements(&self) -> impl Iterator<Item = Requirement> + '_ {
+        self.0
+            .values()
+            .filter_map(|package_override| match package_override {
+                UvPackageOverride::ReplaceWith(replacement) => Some(registry_requirement(
+                    replacement.clone(),
+                    uv_pep440::VersionSpecifiers::empty(),
+                    MarkerTree::TRUE,
+                )),
+                _ => None,
+            })
+    }
+
+    /// Converts the overrides to uv overrides. Excluded and replaced packages
+    /// are overridden with a requirement that never applies, which removes
+    /// them from the resolution.
+    pub(crate) fn to_uv_overrides(&self) -> Overrides {
+        Overrides::from_requirements(
+            self.0
+                .iter()
+                .map(|(name, package_override)| match package_override {
+                    UvPackageOverride::Version(specifier) => {
+                        registry_requirement(name.clone(), specifier.clone(), MarkerTree::TRUE)
+                    }
+                    UvPackageOverride::ReplaceWith(_) | UvPackageOverride::Exclude => {
+                        registry_requirement(
+                            name.clone(),
+                            uv_pep440::VersionSpecifiers::empty(),
+                            MarkerTree::FALSE,
+                        )
+                    }
+                })
+                .collect(),
+        )
+    }
+}
+
+fn registry_source(specifier: uv_pep440::VersionSpecifiers) -> RequirementSource {
+    RequirementSource::Registry {
+        specifier,
+        index: None,
+        conflict: None,
+    }
+}
+
+fn registry_requirement(
+    name: uv_normalize::PackageName,
+    specifier: uv_pep440::VersionSpecifiers,
+    marker: MarkerTree,
+) -> Requirement {
+    Requirement {
+        name,
+        extras: vec![],
+        marker,
+        source: registry_source(specifier),
+        origin: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use rattler_conda_types::VersionSpec;
+
+    use super::*;
+    use crate::lock_file::test_utils::record_with_depends as record;
+
+    fn overrides() -> CondaOverrides {
+        CondaOverrides::from(IndexMap::from([
+            (
+                PackageName::new_unchecked("numpy"),
+                CondaPackageOverride::Version(VersionSpec::from_str("<2", Lenient).unwrap()),
+            ),
+            (
+                PackageName::new_unchecked("pillow"),
+                CondaPackageOverride::ReplaceWith(PackageName::new_unchecked("pillow-simd")),
+            ),
+            (
+                PackageName::new_unchecked("tk"),
+                CondaPackageOverride::Exclude,
+            ),
+        ]))
+    }
+
+    #[test]
+    fn test_conda_record_overrides() {
+        let overrides = overrides();
+
+        // Records of overridden packages are removed
+        assert!(overrides
+            .apply_to_record(&record("numpy", "1.26.4", &[]))
+            .is_some());
+        assert!(overrides
+            .apply_to_record(&record("numpy", "2.1.0", &[]))
+            .is_none());
+        assert!(overrides
+            .apply_to_record(&record("pillow", "10.0.0", &[]))
+            .is_none());
+        assert!(overrides
+            .apply_to_record(&record("tk", "8.6", &[]))
+            .is_none());
+
+        // Records that don't depend on an overridden package are not touched
+        let unrelated = record("python", "3.12.0", &["openssl >=3"]);
+        assert!(matches!(
+            overrides.apply_to_record(&unrelated),
+            Some(Cow::Borrowed(_))
+        ));
+
+        // The dependencies on overridden packages are replaced
+        let matplotlib = record(
+            "matplotlib",
+            "3.9.0",
+            &["numpy >=1.23", "pillow >=8", "tk", "python >=3.9"],
+        );
+        let overridden = overrides.apply_to_record(&matplotlib).unwrap();
+        assert_eq!(
+            overridden.package_record.depends,
+            vec!["numpy <2", "pillow-simd", "python >=3.9"]
+        );
+    }
+}
@@ -0,0 +1,194 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use miette::{Context, IntoDiagnostic};
+use rattler_lock::{Environment, LockFile, LockFileBuilder};
+
+use super::read_lock_file_from_path;
+
+/// The extension of the lock-file of an environment.
+const LOCK_FILE_EXTENSION: &str = "lock";
+
+/// The `.gitignore` that pixi creates in the `.pixi` directory.
+const DEFAULT_GITIGNORE: &str = "*\n";
+
+/// A `.gitignore` for the `.pixi` directory that ignores everything except
+/// the lock-files of the environments.
+const LOCKS_GITIGNORE: &str = "*\n!locks/\n!locks/*.lock\n";
+
+/// Copies a locked environment into the builder.
+fn add_environment(builder: &mut LockFileBuilder, name: &str, environment: Environment<'_>) {
+    builder.set_channels(name, environment.channels().to_vec());
+    if let Some(indexes) = environment.pypi_indexes() {
+        builder.set_pypi_indexes(name, indexes.clone());
+    }
+    for (platform, packages) in environment.packages_by_platform() {
+        for package in packages {
+            builder.add_package(name, platform, package.into());
+        }
+    }
+}
+
+/// Splits the lock-file into a lock-file per environment.
+pub(crate) fn split_lock_file(lock_file: &LockFile) -> Vec<(String, LockFile)> {
+    lock_file
+        .environments()
+        .map(|(name, environment)| {
+            let mut builder = LockFileBuilder::new();
+            add_environment(&mut builder, name, environment);
+            (name.to_string(), builder.finish())
+        })
+        .collect()
+}
+
+/// Merges the lock-files of the environments into a single lock-file.
+pub(crate) fn merge_lock_files<'l>(lock_files: impl IntoIterator<Item = &'l LockFile>) -> LockFile {
+    let mut builder = LockFileBuilder::new();
+    for lock_file in lock_files {
+        for (name, environment) in lock_file.environments() {
+            add_environment(&mut builder, name, environment);
+        }
+    }
+    builder.finish()
+}
+
+/// Returns the path of the lock-file of the environment in the directory.
+pub(crate) fn environment_lock_file_path(dir: &Path, name: &str) -> PathBuf {
+    dir.join(format!("{name}.{LOCK_FILE_EXTENSION}"))
+}
+
+/// Returns the lock-files of the environments in the directory in a stable
+/// order.
+pub(crate) fn environment_lock_file_paths(dir: &Path) -> miette::Result<Vec<PathBuf>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut paths = fs_err::read_dir(dir)
+        .into_diagnostic()?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()
+        .into_diagnostic()?;
+    paths.retain(|path| {
+        path.is_file()
+            && path
+                .extension()
+                .is_some_and(|ext| ext == LOCK_FILE_EXTENSION)
+    });
+    paths.sort();
+    Ok(paths)
+}
+
+/// Reads the lock-files of the environments from the directory and merges
+/// them. Returns `None` if the directory doesn't contain any lock-file.
+pub(crate) fn read_environment_lock_files(dir: &Path) -> miette::Result<Option<LockFile>> {
+    let paths = environment_lock_file_paths(dir)?;
+    if paths.is_empty() {
+        return Ok(None);
+    }
+
+    let lock_files = paths
+        .iter()
+        .map(|path| read_lock_file_from_path(path))
+        .collect::<miette::Result<Vec<_>>>()?;
+    Ok(Some(merge_lock_files(&lock_files)))
+}
+
+/// Writes the lock-file of every environment into the directory. Files that
+/// didn't change are not touched, and files of environments that are no
+/// longer locked are removed.
+pub(crate) fn write_environment_lock_files(dir: &Path, lock_file: &LockFile) -> miette::Result<()> {
+    fs_err::create_dir_all(dir).into_diagnostic()?;
+
+    let mut written = HashSet::new();
+    for (name, environment_lock_file) in split_lock_file(lock_file) {
+        let path = environment_lock_file_path(dir, &name);
+        let contents = environment_lock_file.render_to_string().into_diagnostic()?;
+        if fs_err::read_to_string(&path).ok().as_deref() != Some(contents.as_str()) {
+            fs_err::write(&path, contents)
+                .into_diagnostic()
+                .wrap_err_with(|| format!("failed to write '{}'", path.display()))?;
+        }
+        written.insert(path);
+    }
+
+    for entry in fs_err::read_dir(dir).into_diagnostic()? {
+        let path = entry.into_diagnostic()?.path();
+        if path
+            .extension()
+            .is_some_and(|ext| ext == LOCK_FILE_EXTENSION)
+            && !written.contains(&path)
+        {
+            fs_err::remove_file(&path).into_diagnostic()?;
+        }
+    }
+
+    // The `.pixi` directory is ignored by git, except for the lock-files which
+    // should be committed.
+    if let Some(pixi_dir) = dir.parent() {
+        let gitignore_path = pixi_dir.join(".gitignore");
+        match fs_err::read_to_string(&gitignore_path) {
+            Ok(contents) if contents != DEFAULT_GITIGNORE => {}
+            _ => fs_err::write(&gitignore_path, LOCKS_GITIGNORE).into_diagnostic()?,
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[test]
+    fn test_split_and_merge_lock_file() {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/data/satisfiability/solve-groups-pypi/pixi.lock");
+        let lock_file = LockFile::from_path(&path).unwrap();
+
+        let split = split_lock_file(&lock_file);
+        assert_eq!(split.len(), lock_file.environments().len());
+        for (name, environment_lock_file) in &split {
+            assert_eq!(environment_lock_file.environments().len(), 1);
+            assert!(environment_lock_file.environment(name).is_some());
+        }
+
+        let merged = merge_lock_files(split.iter().map(|(_, lock_file)| lock_file));
+        assert_eq!(
+            merged.render_to_string().unwrap(),
+            lock_file.render_to_string().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_write_environment_lock_files() {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/data/satisfiability/solve-groups-pypi/pixi.lock");
+        let lock_file = LockFile::from_path(&path).unwrap();
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = temp_dir.path().join("locks");
+        fs_err::create_dir_all(&dir).unwrap();
+        fs_err::write(dir.join("removed.lock"), "").unwrap();
+
+        write_environment_lock_files(&dir, &lock_file).unwrap();
+        assert!(!dir.join("removed.lock").exists());
+        for (name, _) in lock_file.environments() {
+            assert!(dir.join(format!("{name}.lock")).is_file());
+        }
+        assert_eq!(
+            fs_err::read_to_string(temp_dir.path().join(".gitignore")).unwrap(),
+            LOCKS_GITIGNORE
+        );
+
+        let read = read_environment_lock_files(&dir).unwrap().unwrap();
+        assert_eq!(
+            read.render_to_string().unwrap(),
+            lock_file.render_to_string().unwrap()
+        );
+    }
+}
@@ -0,0 +1,366 @@
+//! Remembers that the lock-file was verified to be up-to-date with the
+//! manifest. As long as neither of them, the configuration that is used to
+//! solve, nor the installed environments change, commands like `pixi run` can
+//! skip the satisfiability check, which otherwise runs on every invocation.
+
+use std::{
+    hash::{Hash, Hasher},
+    mem::size_of,
+    path::PathBuf,
+};
+
+use itertools::Itertools;
+use pixi_consts::consts;
+use rattler_lock::{CondaPackageData, LockFile, LockedPackageRef, UrlOrPath};
+use url::Url;
+use xxhash_rust::xxh3::Xxh3;
+
+use super::LockFileSources;
+use crate::{environment::environment_file_path, Project};
+
+/// Identifies the format of the state file.
+const MAGIC: &[u8; 8] = b"PIXIRDY2";
+
+/// The state file contains the magic bytes followed by three little-endian
+/// hashes.
+const STATE_FILE_LEN: usize = MAGIC.len() + 3 * size_of::<u64>();
+
+/// The hashes of the inputs of the satisfiability check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct LockFileReadyState {
+    /// The hash of the manifest, the settings that override it, the
+    /// configuration that is used to solve and the version of pixi.
+    manifest_hash: u64,
+    /// The hash of the lock-file, or of the lock-files of the environments.
+    lock_file_hash: u64,
+    /// The hash of the environment files in the prefixes of the
+    /// environments.
+    prefix_hash: u64,
+}
+
+impl LockFileReadyState {
+    /// Computes the state of the manifest of the project, including changes
+    /// that aren't saved yet, and of the given lock-files. Returns `None` if
+    /// the project isn't locked.
+    pub fn new(project: &Project, lock_files: &LockFileSources) -> Option<Self> {
+        if lock_files.files().is_empty() {
+            return None;
+        }
+
+        let mut hasher = Xxh3::new();
+        for (path, contents) in lock_files.files() {
+            path.file_name().hash(&mut hasher);
+            contents.hash(&mut hasher);
+        }
+        let lock_file_hash = hasher.finish();
+
+        Some(Self {
+            manifest_hash: manifest_hash(project),
+            lock_file_hash,
+            prefix_hash: prefix_hash(project),
+        })
+    }
+
+    /// Computes the state of the project with the lock-files as they
+    /// currently are on disk.
+    pub async fn from_disk(project: &Project) -> Option<Self> {
+        let lock_files = LockFileSources::read(project).await.ok()?;
+        Self::new(project, &lock_files)
+    }
+
+    /// Returns true if the state was stored after the lock-file was verified,
+    /// and the files didn't change since.
+    pub fn is_stored(&self, project: &Project) -> bool {
+        let Ok(bytes) = fs_err::read(state_file_path(project)) else {
+            return false;
+        };
+        Self::from_bytes(&bytes).is_some_and(|stored| stored == *self)
+    }
+
+    /// Stores the state after the lock-file was verified or updated. Nothing
+    /// is stored for lock-files with packages from the local filesystem,
+    /// because their metadata has to be checked again on every invocation.
+    pub fn store(&self, project: &Project, lock_file: &LockFile) {
+        let pixi_dir = project.pixi_dir();
+        if has_local_packages(lock_file) || !pixi_dir.is_dir() {
+            let _ = fs_err::remove_file(state_file_path(project));
+            return;
+        }
+        if let Err(err) = fs_err::write(state_file_path(project), self.to_bytes()) {
+            tracing::debug!("failed to write the lock-file state: {err}");
+        }
+    }
+
+    fn to_bytes(self) -> [u8; STATE_FILE_LEN] {
+        let mut bytes = [0; STATE_FILE_LEN];
+        bytes[..8].copy_from_slice(MAGIC);
+        bytes[8..16].copy_from_slice(&self.manifest_hash.to_le_bytes());
+        bytes[16..24].copy_from_slice(&self.lock_file_hash.to_le_bytes());
+        bytes[24..].copy_from_slice(&self.prefix_hash.to_le_bytes());
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != STATE_FILE_LEN || &bytes[..8] != MAGIC {
+            return None;
+        }
+        Some(Self {
+            manifest_hash: u64::from_le_bytes(bytes[8..16].try_into().ok()?),
+            lock_file_hash: u64::from_le_bytes(bytes[16..24].try_into().ok()?),
+            prefix_hash: u64::from_le_bytes(bytes[24..].try_into().ok()?),
+        })
+    }
+}
+
+/// The path of the state file in the `.pixi` directory.
+fn state_file_path(project: &Project) -> PathBuf {
+    project.pixi_dir().join(consts::LOCK_FILE_STATE_FILE)
+}
+
+/// Hashes the manifest and everything besides the lock-file that the
+/// satisfiability check depends on.
+fn manifest_hash(project: &Project) -> u64 {
+    let config = project.config();
+    let channel_config = project.channel_config();
+    let mut hasher = Xxh3::new();
+    project.manifest.source.to_string().hash(&mut hasher);
+    project.exclude_newer().hash(&mut hasher);
+    channel_config.channel_alias.as_str().hash(&mut hasher);
+    channel_config.root_dir.hash(&mut hasher);
+    config
+        .mirror_map()
+        .iter()
+        .map(|(url, mirrors)| (url.as_str(), mirrors.iter().map(Url::as_str).collect_vec()))
+        .sorted()
+        .collect_vec()
+        .hash(&mut hasher);
+    serde_json::to_string(config.pypi_config())
+        .unwrap_or_default()
+        .hash(&mut hasher);
+    serde_json::to_string(config.repodata_config())
+        .unwrap_or_default()
+        .hash(&mut hasher);
+    config.lock_platforms().hash(&mut hasher);
+    config.vendor_dir().hash(&mut hasher);
+    consts::PIXI_VERSION.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Hashes the environment files that pixi writes into the prefixes after
+/// installing them, so that an environment that was installed, updated or
+/// removed since the state was stored is checked again.
+fn prefix_hash(project: &Project) -> u64 {
+    let mut hasher = Xxh3::new();
+    for environment in project.environments() {
+        environment.name().as_str().hash(&mut hasher);
+        fs_err::read(environment_file_path(&environment.dir()))
+            .ok()
+            .hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// Returns true if the lock-file contains source packages or packages from
+/// a path.
+fn has_local_packages(lock_file: &LockFile) -> bool {
+    lock_file
+        .environments()
+        .flat_map(|(_, environment)| environment.packages_by_platform())
+        .flat_map(|(_, packages)| packages)
+        .any(|package| match package {
+            LockedPackageRef::Conda(CondaPackageData::Source(_)) => true,
+            LockedPackageRef::Conda(CondaPackageData::Binary(data)) => is_path(&data.location),
+            LockedPackageRef::Pypi(data, _) => is_path(&data.location),
+        })
+}
+
+fn is_path(location: &UrlOrPath) -> bool {
+    matches!(location, UrlOrPath::Path(_))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::Path, str::FromStr};
+
+    use pixi_config::Config;
+
+    use super::*;
+
+    /// A lock-file of which the packages come from a channel.
+    const LOCK_FILE: &str = r#"
+version: 6
+environments:
+  default:
+    channels:
+    - url: https://prefix.dev/conda-forge/
+    packages:
+      win-64:
+      - conda: https://prefix.dev/conda-forge/noarch/_r-mutex-1.0.1-anacondar_1.tar.bz2
+packages:
+- conda: https://prefix.dev/conda-forge/noarch/_r-mutex-1.0.1-anacondar_1.tar.bz2
+  sha256: e58f9eeb416b92b550e824bcb1b9fb1958dee69abfe3089dfd1a9173e3a0528a
+  md5: 19f9db5f4f1b7f5ef5f6d67207f25f38
+  license: BSD
+  size: 3566
+  timestamp: 1562343890778
+"#;
+
+    fn test_project(dir: &Path, dependencies: &str) -> Project {
+        let manifest = format!(
+            r#"
+        [project]
+        name = "ready-state"
+        channels = ["https://prefix.dev/conda-forge"]
+        platforms = ["win-64"]
+
+        [dependencies]
+        {dependencies}
+        "#
+        );
+        Project::from_str(&dir.join(consts::PROJECT_MANIFEST), &manifest).unwrap()
+    }
+
+    /// Writes the lock-file of the project and stores the state, as after a
+    /// successful satisfiability check.
+    async fn store(project: &Project, contents: &str) -> LockFileReadyState {
+        fs_err::create_dir_all(project.pixi_dir()).unwrap();
+        fs_err::write(project.lock_file_path(), contents).unwrap();
+        let state = LockFileReadyState::from_disk(project).await.unwrap();
+        state.store(project, &LockFile::from_str(contents).unwrap());
+        state
+    }
+
+    #[tokio::test]
+    async fn test_manifest_edit_requires_check() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = test_project(dir.path(), r#"_r-mutex = "*""#);
+        assert!(LockFileReadyState::from_disk(&project).await.is_none());
+
+        let state = store(&project, LOCK_FILE).await;
+        assert!(state.is_stored(&project));
+
+        // The manifest in memory counts, also when it isn't saved yet.
+        let edited = test_project(dir.path(), r#"_r-mutex = ">=1""#);
+        let edited_state = LockFileReadyState::from_disk(&edited).await.unwrap();
+        assert_ne!(edited_state, state);
+        assert!(!edited_state.is_stored(&edited));
+
+        // An exclude-newer override also changes the inputs of the check.
+        let excluded = test_project(dir.path(), r#"_r-mutex = "*""#)
+            .with_exclude_newer(Some("2024-01-01".parse().unwrap()));
+        assert!(!LockFileReadyState::from_disk(&excluded)
+            .await
+            .unwrap()
+            .is_stored(&excluded));
+    }
+
+    #[tokio::test]
+    async fn test_lock_file_edit_requires_check() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = test_project(dir.path(), r#"_r-mutex = "*""#);
+        let state = store(&project, LOCK_FILE).await;
+
+        fs_err::write(
+            project.lock_file_path(),
+            LOCK_FILE.replace("size: 3566", "size: 3567"),
+        )
+        .unwrap();
+        let edited_state = LockFileReadyState::from_disk(&project).await.unwrap();
+        assert_ne!(edited_state, state);
+        assert!(!edited_state.is_stored(&project));
+
+        // Restoring the lock-file restores the state.
+        fs_err::write(project.lock_file_path(), LOCK_FILE).unwrap();
+        assert!(LockFileReadyState::from_disk(&project)
+            .await
+            .unwrap()
+            .is_stored(&project));
+    }
+
+    #[tokio::test]
+    async fn test_source_packages_require_check() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = test_project(dir.path(), r#"_r-mutex = "*""#);
+        let state = store(&project, LOCK_FILE).await;
+        assert!(state.is_stored(&project));
+
+        // Once a source package is locked, the state is no longer stored, so
+        // the metadata of the package is checked on every invocation.
+        let state = store(
+            &project,
+            include_str!("../../tests/data/satisfiability/source-dependency/pixi.lock"),
+        )
+        .await;
+        assert!(!state.is_stored(&project));
+        assert!(!state_file_path(&project).exists());
+    }
+
+    #[tokio::test]
+    async fn test_config_edit_requires_check() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = test_project(dir.path(), r#"_r-mutex = "*""#);
+        let state = store(&project, LOCK_FILE).await;
+        assert!(state.is_stored(&project));
+
+        // The configuration that is used to solve is part of the state.
+        let mut config = Config::default();
+        config.pypi_config.index_url = Some("https://pypi.example.com/simple".parse().unwrap());
+        let configured = test_project(dir.path(), r#"_r-mutex = "*""#).with_cli_config(config);
+        assert!(!LockFileReadyState::from_disk(&configured)
+            .await
+            .unwrap()
+            .is_stored(&configured));
+
+        let mut config = Config::default();
+        config.mirrors.insert(
+            "https://prefix.dev/conda-forge".parse().unwrap(),
+            vec!["https://mirror.example.com/conda-forge".parse().unwrap()],
+        );
+        let configured = test_project(dir.path(), r#"_r-mutex = "*""#).with_cli_config(config);
+        assert!(!LockFileReadyState::from_disk(&configured)
+            .await
+            .unwrap()
+            .is_stored(&configured));
+    }
+
+    #[tokio::test]
+    async fn test_prefix_change_requires_check() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = test_project(dir.path(), r#"_r-mutex = "*""#);
+        let state = store(&project, LOCK_FILE).await;
+        assert!(state.is_stored(&project));
+
+        // Installing the environment writes its environment file, which
+        // changes the state.
+        let environment_file = environment_file_path(&project.default_environment().dir());
+        fs_err::create_dir_all(environment_file.parent().unwrap()).unwrap();
+        fs_err::write(&environment_file, "{}").unwrap();
+        let installed = LockFileReadyState::from_disk(&project).await.unwrap();
+        assert_ne!(installed, state);
+        assert!(!installed.is_stored(&project));
+
+        // Removing the environment changes it back.
+        fs_err::remove_file(&environment_file).unwrap();
+        assert!(LockFileReadyState::from_disk(&project)
+            .await
+            .unwrap()
+            .is_stored(&project));
+    }
+
+    #[test]
+    fn test_state_bytes_roundtrip() {
+        let state = LockFileReadyState {
+            manifest_hash: 0x0123_4567_89ab_cdef,
+            lock_file_hash: 42,
+            prefix_hash: 7,
+        };
+        let bytes = state.to_bytes();
+        assert_eq!(LockFileReadyState::from_bytes(&bytes), Some(state));
+
+        // Files in another format are ignored.
+        assert_eq!(LockFileReadyState::from_bytes(&bytes[..24]), None);
+        let mut other = bytes;
+        other[7] = b'0';
+        assert_eq!(LockFileReadyState::from_bytes(&other), None);
+    }
+}
@@ -0,0 +1,131 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Instant,
+};
+
+use parking_lot::Mutex;
+use pixi_progress::ProgressEvent;
+use rattler::install::{Reporter, Transaction};
+use rattler_conda_types::{PrefixRecord, RepoDataRecord};
+
+/// An installation reporter that emits [`ProgressEvent`]s instead of drawing
+/// progress bars. Used when the progress format is `json-lines`.
+pub(crate) struct JsonProgressReporter {
+    prefix: PathBuf,
+    started: Instant,
+    total_links: AtomicUsize,
+    linked: AtomicUsize,
+    /// The names of the packages that are being cached, indexed by cache
+    /// entry.
+    cache_entries: Mutex<Vec<String>>,
+    /// The names of the packages that are being downloaded, indexed by
+    /// download.
+    downloads: Mutex<Vec<String>>,
+    /// The names of the packages that are being linked, indexed by link.
+    links: Mutex<Vec<String>>,
+}
+
+impl JsonProgressReporter {
+    pub(crate) fn new(prefix: &Path) -> Self {
+        Self {
+            prefix: prefix.to_path_buf(),
+            started: Instant::now(),
+            total_links: AtomicUsize::new(0),
+            linked: AtomicUsize::new(0),
+            cache_entries: Mutex::default(),
+            downloads: Mutex::default(),
+            links: Mutex::default(),
+        }
+    }
+}
+
+/// Pushes a name to the list and returns its index.
+fn push(list: &Mutex<Vec<String>>, name: String) -> usize {
+    let mut list = list.lock();
+    list.push(name);
+    list.len() - 1
+}
+
+/// Returns the name at the given index.
+fn get(list: &Mutex<Vec<String>>, idx: usize) -> String {
+    list.lock().get(idx).cloned().unwrap_or_default()
+}
+
+impl Reporter for JsonProgressReporter {
+    fn on_transaction_start(&self, transaction: &Transaction<PrefixRecord, RepoDataRecord>) {
+        let link = transaction.packages_to_install();
+        self.total_links.store(link, Ordering::Relaxed);
+        pixi_progress::emit(|| ProgressEvent::InstallStarted {
+            prefix: self.prefix.display().to_string(),
+            link,
+            unlink: transaction.removed_packages().count(),
+        });
+    }
+
+    fn on_transaction_operation_start(&self, _operation: usize) {}
+
+    fn on_populate_cache_start(&self, _operation: usize, record: &RepoDataRecord) -> usize {
+        push(
+            &self.cache_entries,
+            record.package_record.name.as_normalized().to_string(),
+        )
+    }
+
+    fn on_validate_start(&self, cache_entry: usize) -> usize {
+        cache_entry
+    }
+
+    fn on_validate_complete(&self, _validate_idx: usize) {}
+
+    fn on_download_start(&self, cache_entry: usize) -> usize {
+        push(&self.downloads, get(&self.cache_entries, cache_entry))
+    }
+
+    fn on_download_progress(&self, download_idx: usize, progress: u64, total: Option<u64>) {
+        pixi_progress::emit(|| ProgressEvent::DownloadProgress {
+            package: get(&self.downloads, download_idx),
+            bytes: progress,
+            total_bytes: total,
+        });
+    }
+
+    fn on_download_completed(&self, download_idx: usize) {
+        pixi_progress::emit(|| ProgressEvent::DownloadFinished {
+            package: get(&self.downloads, download_idx),
+        });
+    }
+
+    fn on_populate_cache_complete(&self, _cache_entry: usize) {}
+
+    fn on_unlink_start(&self, operation: usize, _record: &PrefixRecord) -> usize {
+        operation
+    }
+
+    fn on_unlink_complete(&self, _index: usize) {}
+
+    fn on_link_start(&self, _operation: usize, record: &RepoDataRecord) -> usize {
+        push(
+            &self.links,
+            record.package_record.name.as_normalized().to_string(),
+        )
+    }
+
+    fn on_link_complete(&self, index: usize) {
+        let linked = self.linked.fetch_add(1, Ordering::Relaxed) + 1;
+        pixi_progress::emit(|| ProgressEvent::LinkFinished {
+            package: get(&self.links, index),
+            linked,
+            total: self.total_links.load(Ordering::Relaxed),
+        });
+    }
+
+    fn on_transaction_operation_complete(&self, _operation: usize) {}
+
+    fn on_transaction_complete(&self) {
+        pixi_progress::emit(|| ProgressEvent::InstallFinished {
+            prefix: self.prefix.display().to_string(),
+            duration_ms: self.started.elapsed().as_millis() as u64,
+        });
+    }
+}
@@ -1,8 +1,10 @@
 mod gateway_reporter;
+mod json_progress;
 mod progress_bar;
 mod purl_amend;
 
 pub(crate) use gateway_reporter::GatewayProgressReporter;
+pub(crate) use json_progress::JsonProgressReporter;
 pub(crate) use progress_bar::CondaMetadataProgress;
 pub(crate) use progress_bar::SolveProgressBar;
 pub(crate) use purl_amend::PurlAmendReporter;
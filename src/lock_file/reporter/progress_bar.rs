@@ -1,8 +1,15 @@
-use std::{borrow::Cow, fmt::Write, sync::Arc, time::Duration};
+use std::{
+    borrow::Cow,
+    fmt::Write,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use indicatif::{HumanBytes, ProgressBar, ProgressState};
+use parking_lot::Mutex;
 use pixi_build_frontend::CondaMetadataReporter;
 use pixi_consts::consts;
+use pixi_progress::{ProgressEvent, SolveKind};
 use pypi_mapping::Reporter;
 use rattler_conda_types::Platform;
 
@@ -13,11 +20,16 @@ use crate::{build::BuildMetadataReporter, project::grouped_environment::GroupedE
 #[derive(Clone)]
 pub(crate) struct SolveProgressBar {
     pub pb: ProgressBar,
+    kind: SolveKind,
+    platform: Platform,
+    environment_name: GroupedEnvironmentName,
+    started: Arc<Mutex<Option<Instant>>>,
 }
 
 impl SolveProgressBar {
     pub(crate) fn new(
         pb: ProgressBar,
+        kind: SolveKind,
         platform: Platform,
         environment_name: GroupedEnvironmentName,
     ) -> Self {
@@ -30,12 +42,24 @@ impl SolveProgressBar {
         pb.set_style(indicatif::ProgressStyle::with_template("    {prefix:20!} ..").unwrap());
         pb.enable_steady_tick(Duration::from_millis(100));
         pb.set_prefix(name_and_platform);
-        Self { pb }
+        Self {
+            pb,
+            kind,
+            platform,
+            environment_name,
+            started: Arc::default(),
+        }
     }
 
     pub(crate) fn start(&self) {
         self.pb.reset_elapsed();
-        self.reset_style()
+        self.reset_style();
+        *self.started.lock() = Some(Instant::now());
+        pixi_progress::emit(|| ProgressEvent::SolveStarted {
+            kind: self.kind,
+            environment: self.environment_name.as_str().to_string(),
+            platform: self.platform.to_string(),
+        });
     }
 
     pub(crate) fn set_message(&self, msg: impl Into<Cow<'static, str>>) {
@@ -99,6 +123,14 @@ impl SolveProgressBar {
             .unwrap(),
         );
         self.pb.finish_and_clear();
+
+        let started = self.started.lock().take();
+        pixi_progress::emit(|| ProgressEvent::SolveFinished {
+            kind: self.kind,
+            environment: self.environment_name.as_str().to_string(),
+            platform: self.platform.to_string(),
+            duration_ms: started.map_or(0, |started| started.elapsed().as_millis() as u64),
+        });
     }
 
     pub(crate) fn purl_amend_reporter(self: &Arc<Self>) -> Arc<dyn Reporter> {
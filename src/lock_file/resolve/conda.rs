@@ -1,21 +1,35 @@
+use std::borrow::Cow;
+
 use ahash::HashMap;
 use itertools::Itertools;
 use miette::IntoDiagnostic;
-use pixi_manifest::ChannelPriority;
+use pixi_manifest::{ChannelPriority, ExcludeNewer};
 use pixi_record::{PixiRecord, SourceRecord};
 use rattler_conda_types::{GenericVirtualPackage, MatchSpec, RepoDataRecord};
 use rattler_repodata_gateway::RepoData;
-use rattler_solve::{resolvo, SolverImpl};
+use rattler_solve::{resolvo, SolveError, SolverImpl};
 use url::Url;
 
+use super::conflict::UnsolvableConflict;
 use crate::{
     build::{SourceCheckout, SourceMetadata},
-    lock_file::LockedCondaPackages,
+    lock_file::{CondaOverrides, LockedCondaPackages},
 };
 
 /// Solves the conda package environment for the given input. This function is
 /// async because it spawns a background task for the solver. Since solving is a
 /// CPU intensive task we do not want to block the main task.
+///
+/// The `overrides` are applied to the requirements and the records before they
+/// are passed to the solver, the solved records keep their original
+/// dependencies.
+///
+/// Packages that were published after `exclude_newer` are not considered by
+/// the solver.
+///
+/// When the requirements conflict the error contains suggestions on how to
+/// resolve the conflict.
+#[allow(clippy::too_many_arguments)]
 pub async fn resolve_conda(
     specs: Vec<MatchSpec>,
     virtual_packages: Vec<GenericVirtualPackage>,
@@ -23,6 +37,8 @@ pub async fn resolve_conda(
     available_repodata: Vec<RepoData>,
     available_source_packages: Vec<SourceMetadata>,
     channel_priority: ChannelPriority,
+    overrides: CondaOverrides,
+    exclude_newer: Option<ExcludeNewer>,
 ) -> miette::Result<LockedCondaPackages> {
     tokio::task::spawn_blocking(move || {
         // Combine the repodata from the source packages and from registry channels.
@@ -45,32 +61,80 @@ pub async fn resolve_conda(
             }
         }
 
+        // Apply the overrides to the records. The records whose dependencies are
+        // overridden are cloned, the original is kept because the lock-file
+        // should contain the original metadata.
+        let mut original_records = HashMap::default();
+        let mut apply_overrides = |record| {
+            let overridden = overrides.apply_to_record(record)?;
+            if let Cow::Owned(overridden) = &overridden {
+                original_records.insert(overridden.url.clone(), record);
+            }
+            Some(overridden)
+        };
+        let source_records = url_to_source_package
+            .values()
+            .filter_map(|(_, record)| overrides.apply_to_record(record))
+            .collect_vec();
+        let repodata_records = available_repodata
+            .iter()
+            .map(|repo_data| {
+                repo_data
+                    .iter()
+                    .filter_map(&mut apply_overrides)
+                    .collect_vec()
+            })
+            .collect_vec();
+        let locked_packages = locked_packages
+            .iter()
+            .filter_map(&mut apply_overrides)
+            .map(Cow::into_owned)
+            .collect();
+
         let mut solvable_records = Vec::with_capacity(available_repodata.len() + 1);
-        solvable_records.push(
-            url_to_source_package
-                .values()
-                .map(|(_, record)| record)
-                .collect_vec(),
-        );
-        for repo_data in &available_repodata {
-            solvable_records.push(repo_data.iter().collect_vec());
+        solvable_records.push(source_records.iter().map(Cow::as_ref).collect_vec());
+        for records in &repodata_records {
+            solvable_records.push(records.iter().map(Cow::as_ref).collect_vec());
         }
 
+        let specs: Vec<_> = specs
+            .into_iter()
+            .filter_map(|spec| overrides.apply_to_spec(spec))
+            .collect();
+
         // Construct a solver task that we can start solving.
         let task = rattler_solve::SolverTask {
-            specs,
+            specs: specs.clone(),
             locked_packages,
-            virtual_packages,
+            virtual_packages: virtual_packages.clone(),
             channel_priority: channel_priority.into(),
+            exclude_newer: exclude_newer.map(Into::into),
             ..rattler_solve::SolverTask::from_iter(solvable_records)
         };
 
         // Solve the task
-        let solved = resolvo::Solver.solve(task).into_diagnostic()?;
+        let solved = match resolvo::Solver.solve(task) {
+            Ok(solved) => solved,
+            Err(SolveError::Unsolvable(conflicts)) => {
+                return Err(UnsolvableConflict::new(
+                    conflicts.join(", "),
+                    &specs,
+                    &virtual_packages,
+                    repodata_records.iter().flatten().map(Cow::as_ref),
+                    channel_priority,
+                    exclude_newer,
+                )
+                .into());
+            }
+            Err(err) => return Err(err).into_diagnostic(),
+        };
 
         Ok(solved
             .into_iter()
             .map(|record| {
+                let record = original_records
+                    .get(&record.url)
+                    .map_or(record, |original| (*original).clone());
                 url_to_source_package.remove(&record.url).map_or_else(
                     || PixiRecord::Binary(record),
                     |(source_record, _repodata_record)| PixiRecord::Source(source_record),
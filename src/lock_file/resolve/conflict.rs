@@ -0,0 +1,523 @@
+//! Suggestions to resolve the conflicts that the conda solver reports.
+//!
+//! The solver only explains a conflict as text. Instead of parsing that text,
+//! the requirements of the environment are checked against the records that
+//! were passed to the solver to find out which parts of the workspace the user
+//! can change to resolve the conflict. The dependencies of the candidates are
+//! only checked one level deep, so not every conflict gets a suggestion.
+
+use std::{collections::HashMap, fmt::Display, str::FromStr};
+
+use itertools::Itertools;
+use miette::Diagnostic;
+use pixi_manifest::{ChannelPriority, ExcludeNewer};
+use rattler_conda_types::{
+    GenericVirtualPackage, MatchSpec, Matches, ParseStrictness::Lenient, RepoDataRecord,
+};
+use thiserror::Error;
+
+/// A conda solve that failed because the requirements conflict, with
+/// suggestions on how to resolve the conflict.
+#[derive(Debug, Error)]
+#[error("Cannot solve the request because of: {conflict}")]
+pub(crate) struct UnsolvableConflict {
+    conflict: String,
+    suggestions: Vec<String>,
+}
+
+impl Diagnostic for UnsolvableConflict {
+    fn help<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        if self.suggestions.is_empty() {
+            return None;
+        }
+        Some(Box::new(self.suggestions.join("\n")))
+    }
+}
+
+impl UnsolvableConflict {
+    /// Analyzes the conflict reported by the solver. `specs` are the
+    /// requirements of the environment and `available` are the records that
+    /// were passed to the solver, in order of channel priority.
+    pub(crate) fn new<'r>(
+        conflict: String,
+        specs: &[MatchSpec],
+        virtual_packages: &[GenericVirtualPackage],
+        available: impl IntoIterator<Item = &'r RepoDataRecord>,
+        channel_priority: ChannelPriority,
+        exclude_newer: Option<ExcludeNewer>,
+    ) -> Self {
+        let analysis = Analysis::new(virtual_packages, available, channel_priority, exclude_newer);
+        Self {
+            conflict,
+            suggestions: analysis.suggest_fixes(specs),
+        }
+    }
+}
+
+/// Returns the key in the `[system-requirements]` table that determines the
+/// version of a virtual package.
+fn system_requirement(virtual_package: &str) -> Option<&'static str> {
+    match virtual_package {
+        "__glibc" => Some("libc"),
+        "__cuda" => Some("cuda"),
+        "__osx" => Some("macos"),
+        "__linux" => Some("linux"),
+        _ => None,
+    }
+}
+
+/// Why the solver doesn't consider a record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Exclusion {
+    /// Strict channel priority only uses the first channel that provides the
+    /// package.
+    ChannelPriority,
+
+    /// The record is published after `exclude-newer`.
+    ExcludeNewer,
+}
+
+/// The records of a package that were passed to the solver.
+#[derive(Default)]
+struct Candidates<'r> {
+    /// The channels that provide the package, in order of priority.
+    channels: Vec<&'r str>,
+    records: Vec<&'r RepoDataRecord>,
+}
+
+/// The input of the solve that the requirements are checked against.
+struct Analysis<'a> {
+    packages: HashMap<&'a str, Candidates<'a>>,
+    channels: Vec<&'a str>,
+    virtual_packages: &'a [GenericVirtualPackage],
+    channel_priority: ChannelPriority,
+    exclude_newer: Option<ExcludeNewer>,
+}
+
+impl<'a> Analysis<'a> {
+    fn new(
+        virtual_packages: &'a [GenericVirtualPackage],
+        available: impl IntoIterator<Item = &'a RepoDataRecord>,
+        channel_priority: ChannelPriority,
+        exclude_newer: Option<ExcludeNewer>,
+    ) -> Self {
+        let mut channels = Vec::new();
+        let mut packages: HashMap<&str, Candidates<'_>> = HashMap::new();
+        for record in available {
+            let package = packages
+                .entry(record.package_record.name.as_normalized())
+                .or_default();
+            package.records.push(record);
+            let Some(channel) = record.channel.as_deref().filter(|c| !c.is_empty()) else {
+                continue;
+            };
+            if !channels.contains(&channel) {
+                channels.push(channel);
+            }
+            if !package.channels.contains(&channel) {
+                package.channels.push(channel);
+            }
+        }
+        Self {
+            packages,
+            channels,
+            virtual_packages,
+            channel_priority,
+            exclude_newer,
+        }
+    }
+
+    /// Returns why the solver doesn't consider a record, if it doesn't.
+    fn exclusion(&self, candidates: &Candidates<'_>, record: &RepoDataRecord) -> Option<Exclusion> {
+        if self.channel_priority == ChannelPriority::Strict
+            && record.channel.is_some()
+            && record.channel.as_deref() != candidates.channels.first().copied()
+        {
+            return Some(Exclusion::ChannelPriority);
+        }
+        let published = record.package_record.timestamp?;
+        let exclude_newer = self.exclude_newer?;
+        (published > exclude_newer.0).then_some(Exclusion::ExcludeNewer)
+    }
+
+    /// Returns the records of a package that match the spec, together with
+    /// the reason the solver doesn't consider them.
+    fn matching(&self, spec: &MatchSpec) -> Vec<(&'a RepoDataRecord, Option<Exclusion>)> {
+        let Some(candidates) = spec
+            .name
+            .as_ref()
+            .and_then(|name| self.packages.get(name.as_normalized()))
+        else {
+            return Vec::new();
+        };
+        candidates
+            .records
+            .iter()
+            .filter(|record| spec.matches(&record.package_record))
+            .map(|record| (*record, self.exclusion(candidates, record)))
+            .collect()
+    }
+
+    /// Returns true if a virtual package of the system matches the spec.
+    fn provides_virtual_package(&self, spec: &MatchSpec) -> bool {
+        self.virtual_packages.iter().any(|package| {
+            Some(&package.name) == spec.name.as_ref()
+                && spec
+                    .version
+                    .as_ref()
+                    .map_or(true, |version| version.matches(&package.version))
+        })
+    }
+
+    /// Returns the suggestions for the requirements of the environment.
+    fn suggest_fixes(&self, specs: &[MatchSpec]) -> Vec<String> {
+        let mut suggestions = Vec::new();
+
+        // The direct dependencies that can be installed on their own, with the
+        // candidates that can be installed.
+        let mut installable = Vec::new();
+        for spec in specs {
+            match self.check_requirement(spec, None) {
+                Ok(viable) => installable.push((spec, viable)),
+                Err(causes) => {
+                    // Relaxing the dependency only helps if its versions are
+                    // considered by the solver, but their dependencies aren't
+                    // available.
+                    if self
+                        .matching(spec)
+                        .iter()
+                        .any(|(_, exclusion)| exclusion.is_none())
+                    {
+                        suggestions.push(format!(
+                            "Relax the dependency `{spec}`, none of its versions can be installed."
+                        ));
+                    }
+                    suggestions.extend(causes);
+                }
+            }
+        }
+
+        // The direct dependencies that can't be installed together
+        for ((a, a_viable), (b, b_viable)) in installable.iter().tuple_combinations() {
+            let compatible = a_viable.iter().any(|a_record| {
+                b_viable
+                    .iter()
+                    .any(|b_record| allows(a_record, b_record) && allows(b_record, a_record))
+            });
+            if !compatible && !a_viable.is_empty() && !b_viable.is_empty() {
+                suggestions.push(format!(
+                    "Relax one of the dependencies `{a}`, `{b}`, they conflict with each other."
+                ));
+            }
+        }
+
+        suggestions.into_iter().unique().collect()
+    }
+
+    /// Checks a requirement, of the environment or of the `parent` candidate.
+    /// Returns the candidates that can be installed, or the suggestions that
+    /// explain why none can.
+    fn check_requirement(
+        &self,
+        spec: &MatchSpec,
+        parent: Option<&RepoDataRecord>,
+    ) -> Result<Vec<&'a RepoDataRecord>, Vec<String>> {
+        let Some(name) = &spec.name else {
+            return Ok(Vec::new());
+        };
+        if name.as_normalized().starts_with("__") {
+            return if self.provides_virtual_package(spec) {
+                Ok(Vec::new())
+            } else {
+                Err(self
+                    .missing_system_requirement(spec, parent)
+                    .into_iter()
+                    .collect())
+            };
+        }
+
+        let matching = self.matching(spec);
+        let usable = matching
+            .iter()
+            .filter(|(_, exclusion)| exclusion.is_none())
+            .map(|(record, _)| *record)
+            .collect_vec();
+        if usable.is_empty() {
+            return Err(vec![self.unavailable(spec, parent, &matching)]);
+        }
+        if parent.is_some() {
+            return Ok(usable);
+        }
+
+        // The candidates of a direct dependency are only viable if their own
+        // dependencies are available.
+        let (viable, unviable): (Vec<_>, Vec<_>) = usable
+            .into_iter()
+            .map(|candidate| {
+                let causes = candidate
+                    .package_record
+                    .depends
+                    .iter()
+                    .filter_map(|depend| MatchSpec::from_str(depend, Lenient).ok())
+                    .filter_map(|depend| self.check_requirement(&depend, Some(candidate)).err())
+                    .flatten()
+                    .collect_vec();
+                (candidate, causes)
+            })
+            .partition(|(_, causes)| causes.is_empty());
+        if !viable.is_empty() {
+            return Ok(viable.into_iter().map(|(candidate, _)| candidate).collect());
+        }
+
+        // Explain why the highest version can't be installed, and which
+        // candidates the solver didn't consider.
+        let mut causes = unviable
+            .into_iter()
+            .max_by(|(a, _), (b, _)| a.package_record.version.cmp(&b.package_record.version))
+            .map(|(_, causes)| causes)
+            .unwrap_or_default();
+        for exclusion in [Exclusion::ChannelPriority, Exclusion::ExcludeNewer] {
+            if let Some((record, _)) = matching
+                .iter()
+                .filter(|(_, excluded)| *excluded == Some(exclusion))
+                .max_by(|(a, _), (b, _)| a.package_record.version.cmp(&b.package_record.version))
+            {
+                causes.push(self.excluded(record, exclusion));
+            }
+        }
+        Err(causes)
+    }
+
+    /// Explains why no candidate of a requirement can be used. `matching` are
+    /// the records that match the requirement but are excluded.
+    fn unavailable(
+        &self,
+        spec: &MatchSpec,
+        parent: Option<&RepoDataRecord>,
+        matching: &[(&RepoDataRecord, Option<Exclusion>)],
+    ) -> String {
+        let name = spec.name.as_ref().map_or("", |name| name.as_normalized());
+        if let Some((record, Some(exclusion))) = matching
+            .iter()
+            .max_by(|(a, _), (b, _)| a.package_record.version.cmp(&b.package_record.version))
+        {
+            return self.excluded(record, *exclusion);
+        }
+
+        let required_by = parent.map_or_else(
+            || " by the environment".to_string(),
+            |parent| format!(" by `{}`", display_record(parent)),
+        );
+        let Some(package) = self.packages.get(name) else {
+            return if self.channels.is_empty() {
+                format!("No channel provides `{name}`, which is required{required_by}.")
+            } else {
+                format!(
+                    "None of the channels {} provide `{name}`, which is required{required_by}. Check the name of the package or add a channel that provides it.",
+                    self.channels.iter().map(|c| format!("'{c}'")).join(", ")
+                )
+            };
+        };
+        let highest = package
+            .records
+            .iter()
+            .map(|record| &record.package_record.version)
+            .max()
+            .map_or_else(String::new, |version| {
+                format!(", the highest available version is `{version}`")
+            });
+        let channels = if package.channels.is_empty() {
+            String::new()
+        } else {
+            format!(
+                " in {}",
+                package.channels.iter().map(|c| format!("'{c}'")).join(", ")
+            )
+        };
+        format!(
+            "No version of `{name}`{channels} matches `{spec}`, which is required{required_by}{highest}."
+        )
+    }
+
+    /// Explains why the solver doesn't consider a record.
+    fn excluded(&self, record: &RepoDataRecord, exclusion: Exclusion) -> String {
+        let name = record.package_record.name.as_normalized();
+        match exclusion {
+            Exclusion::ChannelPriority => {
+                let channel = record.channel.as_deref().unwrap_or_default();
+                let first_channel = self
+                    .packages
+                    .get(name)
+                    .and_then(|package| package.channels.first())
+                    .map_or_else(String::new, |first| format!(" ('{first}')"));
+                format!(
+                    "`{}` from '{channel}' is not used because a channel with a higher priority{first_channel} also provides `{name}`. Require `{name}` from '{channel}' explicitly or set `channel-priority = \"disabled\"`.",
+                    display_record(record)
+                )
+            }
+            Exclusion::ExcludeNewer => format!(
+                "Some versions of `{name}` are published after `exclude-newer`, move the date forward to consider them."
+            ),
+        }
+    }
+
+    /// Explains a virtual package that the system requirements don't provide.
+    fn missing_system_requirement(
+        &self,
+        spec: &MatchSpec,
+        parent: Option<&RepoDataRecord>,
+    ) -> Option<String> {
+        let name = spec.name.as_ref()?;
+        let key = system_requirement(name.as_normalized())?;
+        let required_by = parent.map_or_else(
+            || format!("The environment requires `{spec}`"),
+            |parent| format!("`{}` requires `{spec}`", display_record(parent)),
+        );
+        let name = name.as_normalized();
+        Some(
+            match self
+                .virtual_packages
+                .iter()
+                .find(|package| package.name.as_normalized() == name)
+            {
+                Some(package) => format!(
+                    "{required_by}, but the system requirements only provide `{name} {}`. Raise `{key}` in `[system-requirements]` if the machines that use the environment support it.",
+                    package.version
+                ),
+                None => format!(
+                    "{required_by}, which is not part of the system requirements. Add `{key}` to `[system-requirements]` if the machines that use the environment provide it."
+                ),
+            },
+        )
+    }
+}
+
+/// Returns true if the dependencies and constraints of `record` allow
+/// `other` to be installed next to it.
+fn allows(record: &RepoDataRecord, other: &RepoDataRecord) -> bool {
+    let package_record = &record.package_record;
+    package_record
+        .depends
+        .iter()
+        .chain(&package_record.constrains)
+        .filter_map(|spec| MatchSpec::from_str(spec, Lenient).ok())
+        .filter(|spec| spec.name.as_ref() == Some(&other.package_record.name))
+        .all(|spec| spec.matches(&other.package_record))
+}
+
+/// Formats a record as its name and version, e.g. `numpy 2.1.0`.
+fn display_record(record: &RepoDataRecord) -> String {
+    format!(
+        "{} {}",
+        record.package_record.name.as_normalized(),
+        record.package_record.version
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use rattler_conda_types::{PackageName, Version};
+
+    use super::*;
+    use crate::lock_file::test_utils::record_in_channel;
+
+    fn record(name: &str, version: &str, channel: &str, depends: &[&str]) -> RepoDataRecord {
+        let mut record = record_in_channel(name, version, channel);
+        record.package_record.depends = depends.iter().map(ToString::to_string).collect();
+        record
+    }
+
+    fn specs(specs: &[&str]) -> Vec<MatchSpec> {
+        specs
+            .iter()
+            .map(|spec| MatchSpec::from_str(spec, Lenient).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_suggest_fixes() {
+        let conda_forge = "https://conda.anaconda.org/conda-forge/";
+        let virtual_packages = vec![GenericVirtualPackage {
+            name: PackageName::new_unchecked("__glibc"),
+            version: Version::from_str("2.17").unwrap(),
+            build_string: "0".to_string(),
+        }];
+        let available = [
+            record("numpy", "2.1.0", conda_forge, &["__glibc >=2.28"]),
+            record("scipy", "1.0", conda_forge, &["openblas >=9"]),
+            record("openblas", "0.3.28", conda_forge, &[]),
+            record("pytorch", "2.4.0", conda_forge, &["__cuda"]),
+            record(
+                "pytorch",
+                "2.5.0",
+                "https://conda.anaconda.org/pytorch/",
+                &[],
+            ),
+            record("libfoo", "1", conda_forge, &[]),
+            record("libfoo", "2", conda_forge, &[]),
+            record("bar", "1", conda_forge, &["libfoo >=2"]),
+        ];
+
+        let analysis = Analysis::new(&virtual_packages, &available, ChannelPriority::Strict, None);
+        let suggestions = analysis.suggest_fixes(&specs(&[
+            "numpy >=2",
+            "scipy ==1.0",
+            "pytorch >=2",
+            "libfoo ==1",
+            "bar",
+        ]));
+        insta::assert_snapshot!(suggestions.join("\n"), @r###"
+        Relax the dependency `numpy >=2`, none of its versions can be installed.
+        `numpy 2.1.0` requires `__glibc >=2.28`, but the system requirements only provide `__glibc 2.17`. Raise `libc` in `[system-requirements]` if the machines that use the environment support it.
+        Relax the dependency `scipy ==1.0`, none of its versions can be installed.
+        No version of `openblas` in 'https://conda.anaconda.org/conda-forge/' matches `openblas >=9`, which is required by `scipy 1.0`, the highest available version is `0.3.28`.
+        Relax the dependency `pytorch >=2`, none of its versions can be installed.
+        `pytorch 2.4.0` requires `__cuda`, which is not part of the system requirements. Add `cuda` to `[system-requirements]` if the machines that use the environment provide it.
+        `pytorch 2.5.0` from 'https://conda.anaconda.org/pytorch/' is not used because a channel with a higher priority ('https://conda.anaconda.org/conda-forge/') also provides `pytorch`. Require `pytorch` from 'https://conda.anaconda.org/pytorch/' explicitly or set `channel-priority = "disabled"`.
+        Relax one of the dependencies `libfoo ==1`, `bar`, they conflict with each other.
+        "###);
+
+        // Without strict channel priority the newer pytorch can be installed
+        let analysis = Analysis::new(
+            &virtual_packages,
+            &available,
+            ChannelPriority::Disabled,
+            None,
+        );
+        assert!(analysis.suggest_fixes(&specs(&["pytorch >=2"])).is_empty());
+    }
+
+    #[test]
+    fn test_suggest_excluded_newer() {
+        let mut numpy =
+            record_in_channel("numpy", "2.1.0", "https://conda.anaconda.org/conda-forge/");
+        numpy.package_record.timestamp = Some("2024-10-01T00:00:00Z".parse().unwrap());
+        let available = [numpy];
+        let analysis = Analysis::new(
+            &[],
+            &available,
+            ChannelPriority::Strict,
+            Some(ExcludeNewer::from_str("2024-06-01").unwrap()),
+        );
+        assert_eq!(
+            analysis.suggest_fixes(&specs(&["numpy >=2"])),
+            vec!["Some versions of `numpy` are published after `exclude-newer`, move the date forward to consider them."]
+        );
+    }
+
+    #[test]
+    fn test_suggest_missing_package() {
+        let available = [record_in_channel(
+            "numpy",
+            "2.1.0",
+            "https://conda.anaconda.org/conda-forge/",
+        )];
+        let analysis = Analysis::new(&[], &available, ChannelPriority::Strict, None);
+        assert_eq!(
+            analysis.suggest_fixes(&specs(&["nunpy"])),
+            vec!["None of the channels 'https://conda.anaconda.org/conda-forge/' provide `nunpy`, which is required by the environment. Check the name of the package or add a channel that provides it."]
+        );
+    }
+}
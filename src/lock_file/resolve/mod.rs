@@ -3,6 +3,8 @@
 //! See [`resolve_pypi`] and [`resolve_conda`] for more information.
 
 pub(crate) mod conda;
+mod conflict;
 pub(crate) mod pypi;
 mod resolver_provider;
+pub(crate) mod solve_cache;
 pub(crate) mod uv_resolution_context;
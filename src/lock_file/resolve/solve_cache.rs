@@ -0,0 +1,316 @@
+//! A cache of the results of conda solves that is shared between all
+//! workspaces on the machine. Identical solves, e.g. on CI runners or in
+//! workspaces that use the same dependencies, are then only solved once.
+//!
+//! Entries that haven't been used for [`MAX_ENTRY_AGE`] are removed, so the
+//! cache doesn't grow without bound.
+
+use std::{
+    hash::{Hash, Hasher},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Once,
+    time::{Duration, SystemTime},
+};
+
+use ahash::HashMap;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use itertools::Itertools;
+use pixi_consts::consts::{self, CACHED_SOLVES_DIR};
+use pixi_manifest::{ChannelPriority, ExcludeNewer};
+use rattler_conda_types::{ChannelUrl, GenericVirtualPackage, MatchSpec, Platform, RepoDataRecord};
+use rattler_repodata_gateway::RepoData;
+use serde::{Deserialize, Serialize};
+use url::Url;
+use xxhash_rust::xxh3::Xxh3;
+
+/// The version of `rattler_solve`, which is part of the key of a solve. It
+/// has to be updated together with the dependency, a test checks that.
+const SOLVER_VERSION: &str = "1.3.3";
+
+/// Entries that haven't been used for this long are removed from the cache.
+const MAX_ENTRY_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// The cache is pruned at most once per process.
+static PRUNE: Once = Once::new();
+
+/// The inputs of a conda solve that determine its result.
+pub(crate) struct SolveInput<'a> {
+    pub specs: &'a [MatchSpec],
+    pub channels: &'a [ChannelUrl],
+    pub platform: Platform,
+    pub virtual_packages: &'a [GenericVirtualPackage],
+    pub locked_packages: &'a [RepoDataRecord],
+    pub channel_priority: ChannelPriority,
+    pub exclude_newer: Option<ExcludeNewer>,
+    /// The records that are available to the solver.
+    pub repodata: &'a [RepoData],
+}
+
+impl SolveInput<'_> {
+    /// Computes the key of the solve in the cache.
+    ///
+    /// The records of the repodata are part of the key, so a solve is done
+    /// again when a package is added to or removed from a channel. So are the
+    /// versions of pixi and of the solver, so an upgrade never reuses a solve
+    /// of another version.
+    pub fn hash_key(&self) -> String {
+        let SolveInput {
+            specs,
+            channels,
+            platform,
+            virtual_packages,
+            locked_packages,
+            channel_priority,
+            exclude_newer,
+            repodata,
+        } = self;
+
+        let mut hasher = Xxh3::new();
+        consts::PIXI_VERSION.hash(&mut hasher);
+        SOLVER_VERSION.hash(&mut hasher);
+        for spec in specs.iter() {
+            spec.to_string().hash(&mut hasher);
+        }
+        for channel in channels.iter() {
+            channel.as_str().hash(&mut hasher);
+        }
+        platform.hash(&mut hasher);
+        virtual_packages.hash(&mut hasher);
+        for record in locked_packages.iter() {
+            record.url.hash(&mut hasher);
+        }
+        channel_priority.to_string().hash(&mut hasher);
+        exclude_newer.hash(&mut hasher);
+        for record in repodata.iter().flat_map(|repodata| repodata.iter()) {
+            record.url.hash(&mut hasher);
+            record.package_record.sha256.hash(&mut hasher);
+            record.package_record.md5.hash(&mut hasher);
+        }
+        let hash = URL_SAFE_NO_PAD.encode(hasher.finish().to_ne_bytes());
+
+        format!("{platform}-{hash}")
+    }
+}
+
+/// The contents of a cache entry.
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedSolve {
+    /// The urls of the solved records.
+    records: Vec<Url>,
+}
+
+/// A cache of solved conda environments.
+#[derive(Debug, Clone)]
+pub(crate) struct SolveCache {
+    root: PathBuf,
+}
+
+impl SolveCache {
+    /// Constructs a new instance that stores the solves in a versioned
+    /// directory in the given cache directory.
+    pub fn new(cache_dir: &Path) -> Self {
+        Self {
+            root: cache_dir.join(CACHED_SOLVES_DIR),
+        }
+    }
+
+    /// Returns the records of a previous solve with the same input, or `None`
+    /// if the input wasn't solved before.
+    ///
+    /// The cache only stores the urls of the solved records, the records are
+    /// taken from the repodata of the input. An entry that is used is marked
+    /// as recently used, so it is not pruned.
+    pub async fn get(&self, input: &SolveInput<'_>, key: &str) -> Option<Vec<RepoDataRecord>> {
+        let path = self.root.join(format!("{key}.json"));
+        let contents = fs_err::tokio::read_to_string(&path).await.ok()?;
+        if let Err(err) = touch(&path) {
+            tracing::debug!("failed to mark the solve cache entry {key} as used: {err}");
+        }
+        let cached: CachedSolve = match serde_json::from_str(&contents) {
+            Ok(cached) => cached,
+            Err(err) => {
+                tracing::debug!(
+                    "ignoring invalid solve cache entry {}: {err}",
+                    path.display()
+                );
+                return None;
+            }
+        };
+
+        let available: HashMap<&Url, &RepoDataRecord> = input
+            .repodata
+            .iter()
+            .flat_map(|repodata| repodata.iter())
+            .chain(input.locked_packages.iter())
+            .map(|record| (&record.url, record))
+            .collect();
+        cached
+            .records
+            .iter()
+            .map(|url| available.get(url).map(|record| (*record).clone()))
+            .collect()
+    }
+
+    /// Stores the solved records of the input. Failing to write the cache
+    /// is not an error, the solve is simply done again the next time.
+    ///
+    /// The first insert of a process also removes the entries that haven't
+    /// been used for [`MAX_ENTRY_AGE`].
+    pub fn insert(&self, key: &str, records: &[RepoDataRecord]) {
+        PRUNE.call_once(|| self.prune(MAX_ENTRY_AGE));
+
+        let cached = CachedSolve {
+            records: records
+                .iter()
+                .map(|record| record.url.clone())
+                .collect_vec(),
+        };
+        if let Err(err) = self.write(key, &cached) {
+            tracing::debug!("failed to write the solve cache entry {key}: {err}");
+        }
+    }
+
+    fn write(&self, key: &str, cached: &CachedSolve) -> std::io::Result<()> {
+        fs_err::create_dir_all(&self.root)?;
+        // Write to a temporary file first, so other processes never read a
+        // partially written entry.
+        let mut file = tempfile::NamedTempFile::new_in(&self.root)?;
+        serde_json::to_writer(&mut file, cached)?;
+        file.flush()?;
+        file.persist(self.root.join(format!("{key}.json")))?;
+        Ok(())
+    }
+
+    /// Removes the entries that haven't been used for the given duration.
+    fn prune(&self, max_age: Duration) {
+        let Ok(entries) = fs_err::read_dir(&self.root) else {
+            return;
+        };
+        let now = SystemTime::now();
+        for entry in entries.flatten() {
+            let expired = entry
+                .metadata()
+                .and_then(|metadata| metadata.modified())
+                .ok()
+                .and_then(|modified| now.duration_since(modified).ok())
+                .is_some_and(|age| age > max_age);
+            if expired {
+                if let Err(err) = fs_err::remove_file(entry.path()) {
+                    tracing::debug!("failed to prune the solve cache: {err}");
+                }
+            }
+        }
+    }
+}
+
+/// Sets the modification time of the file to now.
+fn touch(path: &Path) -> std::io::Result<()> {
+    std::fs::File::options()
+        .write(true)
+        .open(path)?
+        .set_modified(SystemTime::now())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use rattler_conda_types::ParseStrictness;
+
+    use super::*;
+    use crate::lock_file::test_utils::record;
+
+    #[tokio::test]
+    async fn test_solve_cache() {
+        let temp = tempfile::tempdir().unwrap();
+        let cache = SolveCache::new(temp.path());
+
+        let specs = [MatchSpec::from_str("foo", ParseStrictness::Lenient).unwrap()];
+        let channels = [ChannelUrl::from(
+            Url::parse("https://conda.anaconda.org/conda-forge/").unwrap(),
+        )];
+        let solved = vec![record("foo", "1.0")];
+        let input = SolveInput {
+            specs: &specs,
+            channels: &channels,
+            platform: Platform::Linux64,
+            virtual_packages: &[],
+            locked_packages: &solved,
+            channel_priority: ChannelPriority::Strict,
+            exclude_newer: None,
+            repodata: &[],
+        };
+        let key = input.hash_key();
+        assert!(cache.get(&input, &key).await.is_none());
+
+        cache.insert(&key, &solved);
+        let cached = cache.get(&input, &key).await.unwrap();
+        assert_eq!(cached.len(), 1);
+        assert_eq!(cached[0].url, solved[0].url);
+
+        // A different platform results in a different key.
+        let other = SolveInput {
+            platform: Platform::Win64,
+            ..input
+        };
+        assert_ne!(other.hash_key(), key);
+    }
+
+    #[test]
+    fn test_solver_version() {
+        // The solver version in the key has to match the locked dependency.
+        let lock_file =
+            fs_err::read_to_string(Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.lock"))
+                .unwrap();
+        let version = lock_file
+            .split("[[package]]")
+            .find(|package| package.contains("name = \"rattler_solve\""))
+            .and_then(|package| {
+                package
+                    .lines()
+                    .find_map(|line| line.strip_prefix("version = "))
+            })
+            .unwrap();
+        assert_eq!(version.trim_matches('"'), SOLVER_VERSION);
+    }
+
+    #[tokio::test]
+    async fn test_prune() {
+        let temp = tempfile::tempdir().unwrap();
+        let cache = SolveCache::new(temp.path());
+        let specs = [MatchSpec::from_str("foo", ParseStrictness::Lenient).unwrap()];
+        let solved = vec![record("foo", "1.0")];
+        let input = SolveInput {
+            specs: &specs,
+            channels: &[],
+            platform: Platform::Linux64,
+            virtual_packages: &[],
+            locked_packages: &solved,
+            channel_priority: ChannelPriority::Strict,
+            exclude_newer: None,
+            repodata: &[],
+        };
+        cache.insert("old", &solved);
+        cache.insert("used", &solved);
+        cache.insert("new", &solved);
+
+        let day = Duration::from_secs(24 * 60 * 60);
+        let past = SystemTime::now() - 2 * day;
+        for key in ["old", "used"] {
+            std::fs::File::options()
+                .write(true)
+                .open(cache.root.join(format!("{key}.json")))
+                .unwrap()
+                .set_modified(past)
+                .unwrap();
+        }
+
+        // Using an entry keeps it in the cache.
+        assert!(cache.get(&input, "used").await.is_some());
+        cache.prune(day);
+        assert!(cache.get(&input, "old").await.is_none());
+        assert!(cache.get(&input, "used").await.is_some());
+        assert!(cache.get(&input, "new").await.is_some());
+    }
+}
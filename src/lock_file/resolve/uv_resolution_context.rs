@@ -1,12 +1,14 @@
 use miette::{Context, IntoDiagnostic};
-use uv_cache::Cache;
+use rattler_networking::AuthenticationStorage;
+use uv_cache::{Cache, Refresh};
 use uv_configuration::{BuildOptions, Concurrency, SourceStrategy, TrustedHost};
 use uv_distribution_types::IndexCapabilities;
 use uv_types::{HashStrategy, InFlight};
 
 use crate::Project;
-use pixi_config::{self, get_cache_dir};
+use pixi_config::{self, get_cache_dir, Config, VerifyHashes};
 use pixi_consts::consts;
+use pixi_utils::reqwest::authentication_storage;
 use pixi_uv_conversions::{to_uv_trusted_host, ConversionError};
 
 /// Objects that are needed for resolutions which can be shared between different resolutions.
@@ -22,10 +24,21 @@ pub struct UvResolutionContext {
     pub source_strategy: SourceStrategy,
     pub capabilities: IndexCapabilities,
     pub allow_insecure_host: Vec<TrustedHost>,
+    pub verify_hashes: VerifyHashes,
+    pub auth_storage: AuthenticationStorage,
+    /// Only download and build the distributions into the cache, without
+    /// installing them in the environment.
+    pub prepare_only: bool,
 }
 
 impl UvResolutionContext {
     pub(crate) fn from_project(project: &Project) -> miette::Result<Self> {
+        Self::from_config(project.config(), project.client().clone())
+    }
+
+    /// Creates the context from the pixi configuration, this is used when
+    /// there is no project, e.g. for global environments.
+    pub(crate) fn from_config(config: &Config, client: reqwest::Client) -> miette::Result<Self> {
         let uv_cache = get_cache_dir()?.join(consts::PYPI_CACHE_DIR);
         if !uv_cache.exists() {
             fs_err::create_dir_all(&uv_cache)
@@ -33,9 +46,20 @@ impl UvResolutionContext {
                 .context("failed to create uv cache directory")?;
         }
 
-        let cache = Cache::from_path(uv_cache);
+        let refresh_packages = config
+            .pypi_config()
+            .cache_refresh_packages
+            .iter()
+            .map(|name| uv_normalize::PackageName::new(name.clone()))
+            .collect::<Result<Vec<_>, _>>()
+            .into_diagnostic()
+            .context("failed to parse `pypi-config.cache-refresh-packages`")?;
+        let cache = Cache::from_path(uv_cache).with_refresh(Refresh::from_args(
+            config.pypi_config().cache_refresh,
+            refresh_packages,
+        ));
 
-        let keyring_provider = match project.config().pypi_config().use_keyring() {
+        let keyring_provider = match config.pypi_config().use_keyring() {
             pixi_config::KeyringProvider::Subprocess => {
                 tracing::info!("using uv keyring (subprocess) provider");
                 uv_configuration::KeyringProviderType::Subprocess
@@ -46,8 +70,7 @@ impl UvResolutionContext {
             }
         };
 
-        let allow_insecure_host = project
-            .config()
+        let allow_insecure_host = config
             .pypi_config
             .allow_insecure_host
             .iter()
@@ -65,13 +88,16 @@ impl UvResolutionContext {
             cache,
             in_flight: InFlight::default(),
             hash_strategy: HashStrategy::None,
-            client: project.client().clone(),
+            client,
             build_options: BuildOptions::default(),
             keyring_provider,
             concurrency: Concurrency::default(),
             source_strategy: SourceStrategy::Disabled,
             capabilities: IndexCapabilities::default(),
             allow_insecure_host,
+            verify_hashes: config.verify_hashes(),
+            auth_storage: authentication_storage(config).into_diagnostic()?,
+            prepare_only: false,
         })
     }
 }
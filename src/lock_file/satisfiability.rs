@@ -7,12 +7,13 @@ use std::{
     str::FromStr,
 };
 
+use chrono::{DateTime, Utc};
 use itertools::{Either, Itertools};
 use miette::Diagnostic;
 use pep440_rs::VersionSpecifiers;
 use pixi_glob::{GlobHashCache, GlobHashError, GlobHashKey};
-use pixi_manifest::FeaturesExt;
-use pixi_record::{ParseLockFileError, PixiRecord, SourceMismatchError};
+use pixi_manifest::{pypi::pypi_options::OwnershipPolicy, ExcludeNewer, FeaturesExt};
+use pixi_record::{InputHash, ParseLockFileError, PixiRecord, SourceMismatchError};
 use pixi_spec::{PixiSpec, SourceSpec, SpecConversionError};
 use pixi_uv_conversions::{
     as_uv_req, to_normalize, to_uv_marker_tree, to_uv_version_specifiers, AsPep508Error,
@@ -35,7 +36,8 @@ use uv_pypi_types::{
 };
 
 use super::{
-    package_identifier::ConversionError, PixiRecordsByName, PypiRecord, PypiRecordsByName,
+    package_identifier::ConversionError, PixiRecordsByName, PypiOverrides, PypiRecord,
+    PypiRecordsByName,
 };
 use crate::project::{grouped_environment::GroupedEnvironment, Environment, HasProjectRef};
 
@@ -51,6 +53,19 @@ pub enum EnvironmentUnsat {
     InvalidChannel(#[from] ParseChannelError),
 }
 
+impl EnvironmentUnsat {
+    /// Returns the input of the lock-file that no longer matches the
+    /// environment.
+    pub(crate) fn input(&self) -> LockFileInput {
+        match self {
+            EnvironmentUnsat::ChannelsMismatch | EnvironmentUnsat::InvalidChannel(_) => {
+                LockFileInput::Channels
+            }
+            EnvironmentUnsat::IndexesMismatch(_) => LockFileInput::PypiOptions,
+        }
+    }
+}
+
 #[derive(Debug, Error)]
 pub struct IndexesMismatch {
     current: PypiIndexes,
@@ -158,9 +173,18 @@ pub enum PlatformUnsat {
     #[error("there are more conda packages in the lock-file than are used by the environment")]
     TooManyCondaPackages,
 
+    #[error("'{0}' was published at {1}, which is after the exclude-newer of the project ({2})")]
+    PublishedAfterExcludeNewer(String, DateTime<Utc>, ExcludeNewer),
+
     #[error("missing purls")]
     MissingPurls,
 
+    #[error("the pypi purls of '{0}' do not match the pypi-conda-mapping of the project")]
+    PypiCondaMappingMismatch(String),
+
+    #[error("'{0}' is required as a pypi dependency but is provided by a conda package, which its ownership policy rejects")]
+    OwnershipConflict(uv_normalize::PackageName),
+
     #[error("corrupted lock-file entry for '{0}'")]
     CorruptedEntry(String, ParseLockFileError),
 
@@ -221,6 +245,9 @@ pub enum PlatformUnsat {
     #[error("the input hash for '{0}' ({1}) does not match the hash in the lock-file ({2})")]
     InputHashMismatch(String, String, String),
 
+    #[error("the ignored run exports of '{0}' do not match the ones in the lock-file")]
+    IgnoreRunExportsMismatch(String),
+
     #[error("expect pypi package name '{expected}' but found '{found}'")]
     LockedPyPINamesMismatch { expected: String, found: String },
 
@@ -292,6 +319,107 @@ impl PlatformUnsat {
                 | PlatformUnsat::SourceTreeHashMismatch(..),
         )
     }
+
+    /// Returns the input of the lock-file that no longer matches the
+    /// environment.
+    pub(crate) fn input(&self) -> LockFileInput {
+        match self {
+            PlatformUnsat::UnsatisfiableMatchSpec(spec, _)
+                if spec
+                    .name
+                    .as_ref()
+                    .is_some_and(|name| name.as_normalized().starts_with("__")) =>
+            {
+                LockFileInput::SystemRequirements
+            }
+            PlatformUnsat::UnsatisfiableMatchSpec(_, _)
+            | PlatformUnsat::FailedToParseMatchSpec(_, _)
+            | PlatformUnsat::TooManyCondaPackages
+            | PlatformUnsat::PublishedAfterExcludeNewer(_, _, _) => {
+                LockFileInput::CondaDependencies
+            }
+            PlatformUnsat::SourcePackageMissing(_, _)
+            | PlatformUnsat::RequiredSourceIsBinary(_, _)
+            | PlatformUnsat::RequiredBinaryIsSource(_)
+            | PlatformUnsat::SourcePackageMismatch(_, _)
+            | PlatformUnsat::FailedToDetermineSourceTreeHash(_, _)
+            | PlatformUnsat::SourceTreeHashMismatch(_, _)
+            | PlatformUnsat::FailedToComputeInputHash(_)
+            | PlatformUnsat::InputHashMismatch(_, _, _)
+            | PlatformUnsat::IgnoreRunExportsMismatch(_) => LockFileInput::SourcePackages,
+            PlatformUnsat::DuplicateEntry(_)
+            | PlatformUnsat::MissingPurls
+            | PlatformUnsat::CorruptedEntry(_, _) => LockFileInput::LockFileContent,
+            PlatformUnsat::FailedToConvertRequirement(_, _)
+            | PlatformUnsat::UnsatisfiableRequirement(_, _)
+            | PlatformUnsat::CondaUnsatisfiableRequirement(_, _)
+            | PlatformUnsat::TooManyPypiPackages(_)
+            | PlatformUnsat::MissingPythonInterpreter
+            | PlatformUnsat::FailedToDetermineMarkerEnvironment(_)
+            | PlatformUnsat::PythonVersionMismatch(_, _, _)
+            | PlatformUnsat::AsPep508Error(_, _)
+            | PlatformUnsat::EditableDependencyOnCondaInstalledPackage(_, _)
+            | PlatformUnsat::DirectUrlDependencyOnCondaInstalledPackage(_)
+            | PlatformUnsat::GitDependencyOnCondaInstalledPackage(_)
+            | PlatformUnsat::EditablePackageMismatch(_)
+            | PlatformUnsat::EditablePackageIsUrl(_, _)
+            | PlatformUnsat::EditablePackagePathMismatch(_, _, _)
+            | PlatformUnsat::FailedToCanonicalizePath(_, _)
+            | PlatformUnsat::LockedPyPINamesMismatch { .. }
+            | PlatformUnsat::LockedPyPIVersionsMismatch { .. }
+            | PlatformUnsat::LockedPyPIMalformedUrl(_)
+            | PlatformUnsat::LockedPyPIRequiresDirectUrl(_)
+            | PlatformUnsat::LockedPyPIDirectUrlMismatch { .. }
+            | PlatformUnsat::LockedPyPIGitUrlMismatch { .. }
+            | PlatformUnsat::LockedPyPIGitRefMismatch { .. }
+            | PlatformUnsat::LockedPyPIRequiresGitUrl(_, _)
+            | PlatformUnsat::LockedPyPIRequiresPath(_)
+            | PlatformUnsat::LockedPyPIPathMismatch { .. }
+            | PlatformUnsat::UvTypesConversionError(_)
+            | PlatformUnsat::PypiCondaMappingMismatch(_)
+            | PlatformUnsat::OwnershipConflict(_) => LockFileInput::PypiDependencies,
+        }
+    }
+}
+
+/// An input of the lock-file, used to explain which part of the project drifted
+/// from the lock-file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LockFileInput {
+    /// The environment itself, e.g. it is missing from the lock-file.
+    Environment,
+    /// The channels of the environment.
+    Channels,
+    /// The PyPI options of the environment, e.g. the indexes.
+    PypiOptions,
+    /// The conda dependencies of the environment.
+    CondaDependencies,
+    /// The PyPI dependencies of the environment.
+    PypiDependencies,
+    /// The system requirements of the environment.
+    SystemRequirements,
+    /// The sources of the packages that are built from source.
+    SourcePackages,
+    /// The environments that share a solve-group.
+    SolveGroup,
+    /// The content of the lock-file itself.
+    LockFileContent,
+}
+
+impl Display for LockFileInput {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            LockFileInput::Environment => "environment",
+            LockFileInput::Channels => "channels",
+            LockFileInput::PypiOptions => "pypi options",
+            LockFileInput::CondaDependencies => "conda dependencies",
+            LockFileInput::PypiDependencies => "pypi dependencies",
+            LockFileInput::SystemRequirements => "system requirements",
+            LockFileInput::SourcePackages => "source packages",
+            LockFileInput::SolveGroup => "solve-group",
+            LockFileInput::LockFileContent => "lock-file content",
+        })
+    }
 }
 
 /// Convert something into a uv requirement.
@@ -411,7 +539,9 @@ pub fn verify_environment_satisfiability(
 
     // Check if the indexes in the lock file match our current configuration.
     if !environment.pypi_dependencies(None).is_empty() {
-        let indexes = rattler_lock::PypiIndexes::from(grouped_env.pypi_options());
+        let indexes = grouped_env
+            .pypi_options()
+            .pypi_indexes(grouped_env.platforms());
         match locked_environment.pypi_indexes() {
             None => {
                 // Mismatch when there should be an index but there is not
@@ -483,6 +613,25 @@ pub async fn verify_platform_satisfiability(
         }
     }
 
+    // Packages that were published after the `exclude-newer` of the project
+    // have to be solved again. PyPI packages don't record when they were
+    // published, so these can only be checked by solving.
+    if let Some(exclude_newer) = environment.project().exclude_newer() {
+        for record in pixi_records.iter().filter_map(PixiRecord::as_binary) {
+            if let Some(timestamp) = record
+                .package_record
+                .timestamp
+                .filter(|timestamp| *timestamp > exclude_newer.0)
+            {
+                return Err(Box::new(PlatformUnsat::PublishedAfterExcludeNewer(
+                    record.package_record.name.as_source().to_string(),
+                    timestamp,
+                    exclude_newer,
+                )));
+            }
+        }
+    }
+
     // to reflect new purls for pypi packages
     // we need to invalidate the locked environment
     // if all conda packages have empty purls
@@ -498,6 +647,30 @@ pub async fn verify_platform_satisfiability(
         }
     }
 
+    // The locked purls of the conda packages that are in the pypi-conda-mapping
+    // of the project have to match the mapping.
+    if environment.has_pypi_dependencies() {
+        let pypi_conda_mapping = environment.project().pypi_conda_mapping();
+        for record in pixi_records.iter().filter_map(PixiRecord::as_binary) {
+            let Some(expected) = pypi_conda_mapping.get(&record.package_record.name) else {
+                continue;
+            };
+            let locked = record
+                .package_record
+                .purls
+                .iter()
+                .flatten()
+                .filter(|purl| purl.package_type() == "pypi")
+                .filter_map(|purl| pep508_rs::PackageName::from_str(purl.name()).ok())
+                .collect::<HashSet<_>>();
+            if locked != expected.iter().cloned().collect::<HashSet<_>>() {
+                return Err(Box::new(PlatformUnsat::PypiCondaMappingMismatch(
+                    record.package_record.name.as_source().to_string(),
+                )));
+            }
+        }
+    }
+
     // Create a lookup table from package name to package record. Returns an error
     // if we find a duplicate entry for a record
     let pixi_records_by_name = match PixiRecordsByName::from_unique_iter(pixi_records) {
@@ -765,6 +938,18 @@ pub(crate) async fn verify_package_platform_satisfiability(
     input_hash_cache: GlobHashCache,
 ) -> Result<(), Box<PlatformUnsat>> {
     let channel_config = environment.project().channel_config();
+    let conda_overrides = environment.project().conda_overrides();
+    let pypi_overrides = PypiOverrides::from_manifest(
+        &environment
+            .project()
+            .manifest()
+            .workspace
+            .package_overrides
+            .pypi,
+    )
+    .map_err(ConversionError::NameConversion)
+    .map_err(From::from)
+    .map_err(Box::new)?;
 
     // Determine the dependencies requested by the environment
     let environment_dependencies = environment
@@ -778,23 +963,29 @@ pub(crate) async fn verify_package_platform_satisfiability(
     }
 
     // Transform from PyPiPackage name into UV Requirement type
-    let pypi_requirements = environment
+    let mut pypi_requirements = environment
         .pypi_dependencies(Some(platform))
         .iter()
         .flat_map(|(name, reqs)| {
             reqs.iter().map(move |req| {
-                Ok::<Dependency, Box<PlatformUnsat>>(Dependency::PyPi(
-                    as_uv_req(req, name.as_source(), project_root).map_err(|e| {
-                        Box::new(PlatformUnsat::AsPep508Error(
-                            name.as_normalized().clone(),
-                            e,
-                        ))
-                    })?,
-                    "<environment>".into(),
-                ))
+                as_uv_req(req, name.as_source(), project_root).map_err(|e| {
+                    Box::new(PlatformUnsat::AsPep508Error(
+                        name.as_normalized().clone(),
+                        e,
+                    ))
+                })
             })
         })
+        .filter_map_ok(|requirement| pypi_overrides.apply_to_requirement(requirement))
+        .map_ok(|requirement| Dependency::PyPi(requirement, "<environment>".into()))
         .collect::<Result<Vec<_>, _>>()?;
+    if !pypi_requirements.is_empty() {
+        pypi_requirements.extend(
+            pypi_overrides
+                .replacements()
+                .map(|requirement| Dependency::PyPi(requirement, "<package-overrides>".into())),
+        );
+    }
 
     if pypi_requirements.is_empty() && !locked_pypi_environment.is_empty() {
         return Err(Box::new(PlatformUnsat::TooManyPypiPackages(
@@ -817,8 +1008,15 @@ pub(crate) async fn verify_package_platform_satisfiability(
     let python_interpreter_record = locked_pixi_records.python_interpreter_record();
 
     // Determine the marker environment from the python interpreter package.
+    let pypi_options = environment.pypi_options();
     let marker_environment = python_interpreter_record
-        .map(|interpreter| determine_marker_environment(platform, &interpreter.package_record))
+        .map(|interpreter| {
+            determine_marker_environment(
+                platform,
+                &interpreter.package_record,
+                pypi_options.marker_environment_overrides(platform),
+            )
+        })
         .transpose()
         .map_err(|err| {
             Box::new(PlatformUnsat::FailedToDetermineMarkerEnvironment(
@@ -863,13 +1061,19 @@ pub(crate) async fn verify_package_platform_satisfiability(
     let mut conda_queue = environment_dependencies;
     let mut pypi_queue = pypi_requirements;
     let mut expected_editable_pypi_packages = HashSet::new();
-    let mut expected_conda_source_dependencies = HashSet::new();
+    let mut expected_conda_source_dependencies = HashMap::new();
     while let Some(package) = conda_queue.pop().or_else(|| pypi_queue.pop()) {
         // Determine the package that matches the requirement of matchspec.
         let found_package = match package {
             Dependency::Input(name, spec, source) => match spec.into_source_or_binary() {
                 Either::Left(source_spec) => {
-                    expected_conda_source_dependencies.insert(name.clone());
+                    expected_conda_source_dependencies.insert(
+                        name.clone(),
+                        source_spec
+                            .ignore_run_exports()
+                            .cloned()
+                            .unwrap_or_default(),
+                    );
                     find_matching_source_package(locked_pixi_records, name, source_spec, source)?
                 }
                 Either::Right(binary_spec) => {
@@ -894,10 +1098,15 @@ pub(crate) async fn verify_package_platform_satisfiability(
                         }
                         Ok(spec) => spec,
                     };
+                    let Some(spec) =
+                        conda_overrides.apply_to_spec(MatchSpec::from_nameless(spec, Some(name)))
+                    else {
+                        continue;
+                    };
                     match find_matching_package(
                         locked_pixi_records,
                         &virtual_packages,
-                        MatchSpec::from_nameless(spec, Some(name)),
+                        spec,
                         source,
                     )? {
                         Some(pkg) => pkg,
@@ -912,10 +1121,24 @@ pub(crate) async fn verify_package_platform_satisfiability(
                 }
             }
             Dependency::PyPi(requirement, source) => {
-                // Check if there is a pypi identifier that matches our requirement.
-                if let Some((identifier, repodata_idx, _)) =
-                    locked_conda_pypi_packages.get(&requirement.name)
-                {
+                // Check if there is a pypi identifier that matches our requirement,
+                // unless the ownership policy hands the package to PyPI.
+                let conda_provided = locked_conda_pypi_packages
+                    .get(&requirement.name)
+                    .map(|entry| {
+                        (
+                            entry,
+                            pypi_options.ownership_policy(entry.0.name.as_normalized()),
+                        )
+                    })
+                    .filter(|(_, policy)| *policy != OwnershipPolicy::PreferPypi);
+                if let Some(((identifier, repodata_idx, _), policy)) = conda_provided {
+                    if policy == OwnershipPolicy::Error {
+                        return Err(Box::new(PlatformUnsat::OwnershipConflict(
+                            requirement.name.clone(),
+                        )));
+                    }
+
                     if requirement.is_editable() {
                         return Err(Box::new(
                             PlatformUnsat::EditableDependencyOnCondaInstalledPackage(
@@ -996,6 +1219,9 @@ pub(crate) async fn verify_package_platform_satisfiability(
                 for depends in &record.package_record().depends {
                     let spec = MatchSpec::from_str(depends.as_str(), Lenient)
                         .map_err(|e| PlatformUnsat::FailedToParseMatchSpec(depends.clone(), e))?;
+                    let Some(spec) = conda_overrides.apply_to_spec(spec) else {
+                        continue;
+                    };
                     conda_queue.push(Dependency::Conda(
                         spec,
                         match record {
@@ -1071,6 +1297,10 @@ pub(crate) async fn verify_package_platform_satisfiability(
                         .into_uv_requirement()
                         .map_err(From::from)
                         .map_err(Box::new)?;
+                    // Skip this requirement if the package is excluded or replaced.
+                    let Some(requirement) = pypi_overrides.apply_to_requirement(requirement) else {
+                        continue;
+                    };
                     // Skip this requirement if it does not apply.
                     if !requirement.evaluate_markers(Some(marker_environment), &extras) {
                         continue;
@@ -1140,24 +1370,40 @@ pub(crate) async fn verify_package_platform_satisfiability(
         .iter()
         .filter_map(PixiRecord::as_source)
     {
-        if !expected_conda_source_dependencies.contains(&record.package_record.name) {
+        if !expected_conda_source_dependencies.contains_key(&record.package_record.name) {
             return Err(Box::new(PlatformUnsat::RequiredBinaryIsSource(
                 record.package_record.name.as_source().to_string(),
             )));
         }
     }
 
-    // Check if all source packages are still up-to-date.
+    // Check if all source packages are still up-to-date. The ignored run
+    // exports are part of the locked input hash.
     for source_record in locked_pixi_records
         .records
         .iter()
         .filter_map(PixiRecord::as_source)
     {
-        let Some(path_record) = source_record.source.as_path() else {
-            continue;
-        };
-
-        let Some(locked_input_hash) = &source_record.input_hash else {
+        let ignore_run_exports = expected_conda_source_dependencies
+            .get(&source_record.package_record.name)
+            .cloned()
+            .unwrap_or_default();
+
+        let (Some(path_record), Some(locked_input_hash)) =
+            (source_record.source.as_path(), &source_record.input_hash)
+        else {
+            // Without input files only the ignored run exports are locked.
+            let expected_input_hash = InputHash::with_ignore_run_exports(None, &ignore_run_exports);
+            if expected_input_hash.map(|input_hash| input_hash.hash)
+                != source_record
+                    .input_hash
+                    .as_ref()
+                    .map(|input_hash| input_hash.hash)
+            {
+                return Err(Box::new(PlatformUnsat::IgnoreRunExportsMismatch(
+                    source_record.package_record.name.as_source().to_string(),
+                )));
+            }
             continue;
         };
 
@@ -1169,7 +1415,7 @@ pub(crate) async fn verify_package_platform_satisfiability(
             ))
         })?;
 
-        let input_hash = input_hash_cache
+        let files_hash = input_hash_cache
             .compute_hash(GlobHashKey {
                 root: source_dir,
                 globs: locked_input_hash.globs.clone(),
@@ -1177,6 +1423,14 @@ pub(crate) async fn verify_package_platform_satisfiability(
             .await
             .map_err(PlatformUnsat::FailedToComputeInputHash)
             .map_err(Box::new)?;
+        let input_hash = InputHash::with_ignore_run_exports(
+            Some(InputHash {
+                hash: files_hash.hash,
+                globs: locked_input_hash.globs.clone(),
+            }),
+            &ignore_run_exports,
+        )
+        .expect("the hash of the input files is always present");
 
         if input_hash.hash != locked_input_hash.hash {
             return Err(Box::new(PlatformUnsat::InputHashMismatch(
@@ -1537,6 +1791,78 @@ mod tests {
         });
     }
 
+    #[tokio::test]
+    async fn test_exclude_newer_override() {
+        let manifest_path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/data/non-satisfiability/exclude-newer/pixi.toml");
+        let manifest = fs_err::read_to_string(&manifest_path)
+            .unwrap()
+            .replace("exclude-newer = \"2024-01-01\"\n", "");
+
+        // Without a cutoff the locked packages satisfy the manifest
+        let project = Project::from_str(&manifest_path, &manifest).unwrap();
+        let lock_file = LockFile::from_path(&project.lock_file_path()).unwrap();
+        verify_lockfile_satisfiability(&project, &lock_file)
+            .await
+            .unwrap();
+
+        // A cutoff passed on the command line applies to the locked packages
+        let project = project.with_exclude_newer(Some("2024-01-01".parse().unwrap()));
+        let err = verify_lockfile_satisfiability(&project, &lock_file)
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(
+                err,
+                LockfileUnsat::PlatformUnsat(_, _, PlatformUnsat::PublishedAfterExcludeNewer(..))
+            ),
+            "{err:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ownership_policy() {
+        // The conda package `ruff` provides the PyPI dependency `ruff`
+        let manifest_path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/data/satisfiability/pypi-deps-not-in-lock/pixi.toml");
+        let manifest = fs_err::read_to_string(&manifest_path).unwrap();
+        let with_policy = |policy: &str| {
+            let manifest = format!("{manifest}[pypi-options]\n{policy}\n");
+            Project::from_str(&manifest_path, &manifest).unwrap()
+        };
+
+        let project = with_policy(r#"package-ownership-policy = { ruff = "error" }"#);
+        let lock_file = LockFile::from_path(&project.lock_file_path()).unwrap();
+        let err = verify_lockfile_satisfiability(&project, &lock_file)
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(
+                err,
+                LockfileUnsat::PlatformUnsat(_, _, PlatformUnsat::OwnershipConflict(..))
+            ),
+            "{err:?}"
+        );
+
+        // The PyPI package has to be locked when PyPI owns it
+        let project = with_policy(r#"ownership-policy = "prefer-pypi""#);
+        let err = verify_lockfile_satisfiability(&project, &lock_file)
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(
+                err,
+                LockfileUnsat::PlatformUnsat(_, _, PlatformUnsat::UnsatisfiableRequirement(..))
+            ),
+            "{err:?}"
+        );
+
+        let project = with_policy(r#"ownership-policy = "prefer-conda""#);
+        verify_lockfile_satisfiability(&project, &lock_file)
+            .await
+            .unwrap();
+    }
+
     #[test]
     fn test_pypi_git_check_with_rev() {
         // Mock locked datga
@@ -0,0 +1,238 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+};
+
+use fancy_display::FancyDisplay;
+use itertools::Itertools;
+use pixi_consts::consts;
+use pixi_manifest::{EnvironmentName, FeaturesExt};
+use pixi_record::PixiRecord;
+use rattler_conda_types::{
+    ChannelConfig, Matches, NamelessMatchSpec, PackageName, Platform, RepoDataRecord, Version,
+};
+use rattler_repodata_gateway::RepoData;
+
+use crate::project::grouped_environment::GroupedEnvironment;
+
+/// The direct conda requirements of every environment in a solve-group.
+///
+/// These are used to explain why a package ends up on an older version than
+/// the environment that requests it could get on its own.
+pub(crate) struct SolveGroupConstraints {
+    group_name: String,
+    platform: Platform,
+    environments: Vec<(EnvironmentName, HashMap<PackageName, NamelessMatchSpec>)>,
+}
+
+/// A package that is held back by the requirement of another environment in
+/// the same solve-group.
+#[derive(Debug)]
+pub(crate) struct SolveGroupDowngrade {
+    pub(crate) group_name: String,
+    pub(crate) platform: Platform,
+    pub(crate) environment: EnvironmentName,
+    pub(crate) package: PackageName,
+    pub(crate) locked_version: Version,
+    pub(crate) available_version: Version,
+    pub(crate) constrained_by: EnvironmentName,
+    pub(crate) constraint: NamelessMatchSpec,
+}
+
+impl Display for SolveGroupDowngrade {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} is locked at {} in environment {} for {} because environment {} in the solve-group '{}' requires {} {}, {} would be available otherwise",
+            console::style(self.package.as_normalized()).bold(),
+            self.locked_version,
+            self.environment.fancy_display(),
+            consts::PLATFORM_STYLE.apply_to(self.platform),
+            self.constrained_by.fancy_display(),
+            self.group_name,
+            self.package.as_normalized(),
+            self.constraint,
+            self.available_version,
+        )
+    }
+}
+
+impl SolveGroupConstraints {
+    /// Collects the requirements of the environments of a solve-group. Returns
+    /// `None` if the group only consists of a single environment.
+    pub(crate) fn from_group(
+        group: &GroupedEnvironment<'_>,
+        platform: Platform,
+        channel_config: &ChannelConfig,
+    ) -> Option<Self> {
+        let GroupedEnvironment::Group(solve_group) = group else {
+            return None;
+        };
+        let environments = solve_group
+            .environments()
+            .map(|environment| {
+                let specs = environment
+                    .combined_dependencies(Some(platform))
+                    .into_specs()
+                    .filter_map(|(name, spec)| {
+                        let spec = spec.try_into_nameless_match_spec(channel_config).ok()??;
+                        Some((name, spec))
+                    })
+                    .collect();
+                (environment.name().clone(), specs)
+            })
+            .collect();
+        Some(Self {
+            group_name: solve_group.name().to_string(),
+            platform,
+            environments,
+        })
+    }
+
+    /// Returns true if another environment than `environment` has a
+    /// requirement on the package.
+    fn is_shared(&self, environment: &EnvironmentName, package: &PackageName) -> bool {
+        self.environments
+            .iter()
+            .any(|(name, specs)| name != environment && specs.contains_key(package))
+    }
+
+    /// Returns the available records of the packages that are required by more
+    /// than one environment in the group, these are the only packages that can
+    /// be held back by a sibling environment.
+    pub(crate) fn shared_candidates(&self, available: &[RepoData]) -> Vec<RepoDataRecord> {
+        let shared = self
+            .environments
+            .iter()
+            .flat_map(|(name, specs)| specs.keys().map(move |package| (name, package)))
+            .filter(|(name, package)| self.is_shared(name, package))
+            .map(|(_, package)| package)
+            .collect::<HashSet<_>>();
+        if shared.is_empty() {
+            return Vec::new();
+        }
+        available
+            .iter()
+            .flat_map(RepoData::iter)
+            .filter(|record| shared.contains(&record.package_record.name))
+            .cloned()
+            .collect()
+    }
+
+    /// Finds the direct dependencies that were solved to an older version than
+    /// the requirement of their environment allows, because a newer version is
+    /// excluded by the requirement of another environment in the group.
+    ///
+    /// This only looks at the requirements on the package itself, a newer
+    /// version is not guaranteed to be installable together with the other
+    /// packages of the environment.
+    pub(crate) fn downgrades(
+        &self,
+        candidates: &[RepoDataRecord],
+        solved: &[PixiRecord],
+    ) -> Vec<SolveGroupDowngrade> {
+        let mut downgrades = Vec::new();
+        for (environment, specs) in &self.environments {
+            for (package, spec) in specs.iter().sorted_by_key(|(name, _)| *name) {
+                let Some(locked) = solved
+                    .iter()
+                    .map(PixiRecord::package_record)
+                    .find(|record| &record.name == package)
+                else {
+                    continue;
+                };
+
+                for (sibling, sibling_specs) in &self.environments {
+                    if sibling == environment {
+                        continue;
+                    }
+                    let Some(constraint) = sibling_specs.get(package) else {
+                        continue;
+                    };
+
+                    // The newest version the environment could get if the
+                    // requirement of the sibling didn't exist.
+                    let newest = candidates
+                        .iter()
+                        .filter(|record| &record.package_record.name == package)
+                        .filter(|record| record.package_record.version > locked.version)
+                        .filter(|record| spec.matches(*record) && !constraint.matches(*record))
+                        .map(|record| &record.package_record.version)
+                        .max();
+                    if let Some(newest) = newest {
+                        downgrades.push(SolveGroupDowngrade {
+                            group_name: self.group_name.clone(),
+                            platform: self.platform,
+                            environment: environment.clone(),
+                            package: package.clone(),
+                            locked_version: locked.version.version().clone(),
+                            available_version: newest.version().clone(),
+                            constrained_by: sibling.clone(),
+                            constraint: constraint.clone(),
+                        });
+                        break;
+                    }
+                }
+            }
+        }
+        downgrades
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use rattler_conda_types::ParseStrictness;
+
+    use super::*;
+    use crate::lock_file::test_utils::record;
+
+    fn specs(specs: &[(&str, &str)]) -> HashMap<PackageName, NamelessMatchSpec> {
+        specs
+            .iter()
+            .map(|(name, spec)| {
+                (
+                    PackageName::new_unchecked(*name),
+                    NamelessMatchSpec::from_str(spec, ParseStrictness::Strict).unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_downgrades() {
+        let constraints = SolveGroupConstraints {
+            group_name: "group".to_string(),
+            platform: Platform::Linux64,
+            environments: vec![
+                (
+                    EnvironmentName::Named("test".to_string()),
+                    specs(&[("numpy", ">=1.20"), ("pytest", "*")]),
+                ),
+                (
+                    EnvironmentName::Named("lint".to_string()),
+                    specs(&[("numpy", "<2")]),
+                ),
+            ],
+        };
+
+        let candidates = vec![
+            record("numpy", "1.26.4"),
+            record("numpy", "2.0.0"),
+            record("numpy", "2.1.0"),
+        ];
+        let solved = vec![PixiRecord::Binary(record("numpy", "1.26.4"))];
+
+        let downgrades = constraints.downgrades(&candidates, &solved);
+        assert_eq!(downgrades.len(), 1);
+        let downgrade = &downgrades[0];
+        assert_eq!(downgrade.environment.as_str(), "test");
+        assert_eq!(downgrade.constrained_by.as_str(), "lint");
+        assert_eq!(downgrade.available_version.to_string(), "2.1.0");
+
+        // Nothing is held back when the newest version is locked
+        let solved = vec![PixiRecord::Binary(record("numpy", "2.1.0"))];
+        assert!(constraints.downgrades(&candidates, &solved).is_empty());
+    }
+}
@@ -0,0 +1,38 @@
+//! Fixtures that are shared by the tests of the lock-file modules.
+
+use std::str::FromStr;
+
+use rattler_conda_types::{PackageName, PackageRecord, RepoDataRecord, Version};
+use url::Url;
+
+/// The channel of the records returned by [`record`].
+const CONDA_FORGE: &str = "https://conda.anaconda.org/conda-forge/";
+
+/// Returns the record of a `noarch` package from conda-forge.
+pub(crate) fn record(name: &str, version: &str) -> RepoDataRecord {
+    record_in_channel(name, version, CONDA_FORGE)
+}
+
+/// Returns the record of a `noarch` package from conda-forge with the given
+/// dependencies.
+pub(crate) fn record_with_depends(name: &str, version: &str, depends: &[&str]) -> RepoDataRecord {
+    let mut record = record(name, version);
+    record.package_record.depends = depends.iter().map(ToString::to_string).collect();
+    record
+}
+
+/// Returns the record of a `noarch` package from the given channel, which
+/// has to end with a slash.
+pub(crate) fn record_in_channel(name: &str, version: &str, channel: &str) -> RepoDataRecord {
+    let file_name = format!("{name}-{version}-0.conda");
+    RepoDataRecord {
+        package_record: PackageRecord::new(
+            PackageName::new_unchecked(name),
+            Version::from_str(version).unwrap(),
+            "0".to_string(),
+        ),
+        url: Url::parse(&format!("{channel}noarch/{file_name}")).unwrap(),
+        file_name,
+        channel: Some(channel.to_string()),
+    }
+}
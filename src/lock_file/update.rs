@@ -18,11 +18,11 @@ use miette::{Diagnostic, IntoDiagnostic, LabeledSpan, MietteDiagnostic, Report,
 use pixi_build_frontend::ToolContext;
 use pixi_consts::consts;
 use pixi_manifest::{ChannelPriority, EnvironmentName, FeaturesExt, HasFeaturesIter};
-use pixi_progress::global_multi_progress;
+use pixi_progress::{global_multi_progress, SolveKind};
 use pixi_record::{ParseLockFileError, PixiRecord};
 use pixi_uv_conversions::{
-    to_extra_name, to_marker_environment, to_normalize, to_uv_extra_name, to_uv_normalize,
-    ConversionError,
+    pypi_options_to_build_options, to_extra_name, to_marker_environment, to_normalize,
+    to_uv_extra_name, to_uv_normalize, ConversionError,
 };
 use pypi_mapping::{self};
 use pypi_modifiers::pypi_marker_env::determine_marker_environment;
@@ -37,8 +37,13 @@ use tracing::Instrument;
 use uv_normalize::ExtraName;
 
 use super::{
-    outdated::OutdatedEnvironments, utils::IoConcurrencyLimit, PixiRecordsByName,
-    PypiRecordsByName, UvResolutionContext,
+    outdated::{LockFileDrift, OutdatedEnvironments},
+    ready_state::LockFileReadyState,
+    resolve::solve_cache::{SolveCache, SolveInput},
+    solve_group::SolveGroupConstraints,
+    utils::{apply_requested_ignore_run_exports, prune_unlocked_platforms, IoConcurrencyLimit},
+    vendor::VendorDir,
+    write_lock_file, LockFileSources, PixiRecordsByName, PypiRecordsByName, UvResolutionContext,
 };
 use crate::{
     activation::CurrentEnvVarBehavior,
@@ -48,7 +53,7 @@ use crate::{
         LockedEnvironmentHash, PerEnvironmentAndPlatform, PerGroup, PerGroupAndPlatform,
         PythonStatus,
     },
-    load_lock_file,
+    external_managers, load_lock_file,
     lock_file::{
         self,
         records_by_name::HasNameVersion,
@@ -61,6 +66,7 @@ use crate::{
         Environment, HasProjectRef,
     },
     repodata::Repodata,
+    stats::{self, Phase},
     Project,
 };
 
@@ -82,12 +88,24 @@ impl Project {
     }
 }
 
+/// The context attached to the error of a failed solve. This allows the caller
+/// to recognize solve failures, e.g. to report them with a dedicated exit code.
+#[derive(Debug, Error)]
+#[error("{0}")]
+pub struct SolveFailure(pub String);
+
 #[derive(Debug, Error, Diagnostic)]
 enum UpdateError {
     #[error("the lockfile is not up-to-date with requested environment: '{}'", .0.fancy_display())]
     LockFileMissingEnv(EnvironmentName),
     #[error("some information from the lockfile could not be parsed")]
     ParseLockFileError(#[from] ParseLockFileError),
+    #[error(
+        "lock-file not up-to-date with the project:\n{}",
+        .0.iter().map(|drift| format!("  - {drift}")).join("\n")
+    )]
+    #[diagnostic(help("run the command without `--locked` to update the lock-file"))]
+    LockFileOutOfDate(Vec<LockFileDrift>),
 }
 
 /// Options to pass to [`Project::update_lock_file`].
@@ -153,11 +171,7 @@ pub enum UpdateMode {
 impl<'p> LockFileDerivedData<'p> {
     /// Write the lock-file to disk.
     pub(crate) fn write_to_disk(&self) -> miette::Result<()> {
-        let lock_file_path = self.project.lock_file_path();
-        self.lock_file
-            .to_path(&lock_file_path)
-            .into_diagnostic()
-            .context("failed to write lock-file to disk")
+        write_lock_file(self.project, &self.lock_file).context("failed to write lock-file to disk")
     }
 
     fn locked_environment_hash(
@@ -207,6 +221,45 @@ impl<'p> LockFileDerivedData<'p> {
         Ok(prefix)
     }
 
+    /// Runs the package managers of other ecosystems in the installed
+    /// environment, if their inputs or the environment changed. Only the
+    /// commands that work in the environment, `pixi install`, `pixi run`,
+    /// `pixi shell` and `pixi shell-hook`, do this.
+    pub(crate) async fn sync_external_managers(
+        &self,
+        environment: &Environment<'p>,
+    ) -> miette::Result<()> {
+        if self.project.manifest.workspace.external_managers.is_empty() {
+            return Ok(());
+        }
+        // Environments of unsupported platforms are not installed.
+        if !environment
+            .platforms()
+            .contains(&environment.best_platform())
+        {
+            return Ok(());
+        }
+        let hash = self.locked_environment_hash(environment)?;
+        let command_env = self
+            .project
+            .get_activated_environment_variables(
+                environment,
+                CurrentEnvVarBehavior::Include,
+                Some(&self.lock_file),
+                false,
+                false,
+            )
+            .await?;
+        external_managers::sync_external_managers(environment, hash.as_str(), command_env)
+            .await
+            .with_context(|| {
+                format!(
+                    "{}: error running the external managers",
+                    environment.name()
+                )
+            })
+    }
+
     fn cached_prefix(
         &mut self,
         environment: &Environment<'p>,
@@ -241,6 +294,7 @@ impl<'p> LockFileDerivedData<'p> {
     }
 
     /// Returns the up-to-date prefix for the given environment.
+    #[tracing::instrument(name = "install_environment", skip_all, fields(environment = %environment.name().as_str()))]
     async fn update_prefix(&mut self, environment: &Environment<'p>) -> miette::Result<Prefix> {
         // If we previously updated this environment, early out.
         if let Some(prefix) = self.updated_pypi_prefixes.get(environment.name()) {
@@ -250,22 +304,53 @@ impl<'p> LockFileDerivedData<'p> {
         tracing::info!("Updating prefix");
         // Get the prefix with the conda packages installed.
         let platform = environment.best_platform();
+        if environment.platforms().contains(&platform)
+            && !environment.locked_platforms().contains(&platform)
+        {
+            miette::bail!(
+                help = "add '{platform}' to the `lock-platforms` configuration to install it",
+                "the platform '{platform}' of environment '{}' is not locked",
+                environment.name().fancy_display()
+            );
+        }
         let (prefix, python_status) = self.conda_prefix(environment).await?;
+        self.update_pypi_prefix(environment, &prefix, &python_status)
+            .await?;
+
+        // Store that we updated the environment, so we won't have to do it again.
+        self.updated_pypi_prefixes
+            .insert(environment.name().clone(), prefix.clone());
+
+        Ok(prefix)
+    }
+
+    /// Installs the locked PyPI packages of the environment in a prefix that
+    /// contains its conda packages.
+    async fn update_pypi_prefix(
+        &mut self,
+        environment: &Environment<'p>,
+        prefix: &Prefix,
+        python_status: &PythonStatus,
+    ) -> miette::Result<()> {
+        let platform = environment.best_platform();
         let pixi_records = self
             .pixi_records(environment, platform)
             .into_diagnostic()?
             .unwrap_or_default();
-        let pypi_records = self
+        let mut pypi_records = self
             .pypi_records(environment, platform)
             .into_diagnostic()?
             .unwrap_or_default();
+        if let Some(vendor_dir) = VendorDir::from_project(self.project) {
+            pypi_records = vendor_dir.apply_to_pypi_records(pypi_records);
+        }
 
         // No `uv` support for WASM right now
         if platform.arch() == Some(Arch::Wasm32) {
-            return Ok(prefix);
+            return Ok(());
         }
 
-        let uv_context = match &self.uv_context {
+        let mut uv_context = match &self.uv_context {
             None => {
                 let context = UvResolutionContext::from_project(self.project)?;
                 self.uv_context = Some(context.clone());
@@ -288,18 +373,22 @@ impl<'p> LockFileDerivedData<'p> {
             )
             .await?;
 
-        let non_isolated_packages = environment.pypi_options().no_build_isolation;
+        let pypi_options = environment.pypi_options();
+        uv_context.build_options =
+            pypi_options_to_build_options(&pypi_options).into_diagnostic()?;
+        let non_isolated_packages = pypi_options.no_build_isolation;
         // Update the prefix with Pypi records
         environment::update_prefix_pypi(
             environment.name(),
-            &prefix,
+            prefix,
             platform,
             &pixi_records,
             &pypi_records,
-            &python_status,
+            python_status,
             &environment.system_requirements(),
             &uv_context,
             self.pypi_indexes(environment).into_diagnostic()?.as_ref(),
+            &environment.pinned_pypi_indexes(Some(platform)),
             env_variables,
             self.project.root(),
             environment.best_platform(),
@@ -311,13 +400,43 @@ impl<'p> LockFileDerivedData<'p> {
                 "{}: error installing/updating PyPI dependencies",
                 environment.name()
             )
-        })?;
+        })
+    }
 
-        // Store that we updated the environment, so we won't have to do it again.
-        self.updated_pypi_prefixes
-            .insert(environment.name().clone(), prefix.clone());
+    /// Downloads and builds the PyPI packages of the environment into the uv
+    /// cache, without installing them. uv needs the python interpreter of the
+    /// environment to select and build the distributions, so the conda
+    /// packages are installed in a temporary prefix in the cache directory.
+    /// The prefix of the environment is not touched.
+    pub(crate) async fn prepare_pypi_distributions(
+        &mut self,
+        environment: &Environment<'p>,
+    ) -> miette::Result<()> {
+        let cache_dir = pixi_config::get_cache_dir()?;
+        fs_err::create_dir_all(&cache_dir).into_diagnostic()?;
+        let prefix_dir = tempfile::Builder::new()
+            .prefix("pixi-warmup-")
+            .tempdir_in(&cache_dir)
+            .into_diagnostic()
+            .context("failed to create a temporary prefix in the cache directory")?;
+        let prefix = Prefix::new(prefix_dir.path());
 
-        Ok(prefix)
+        let uv_context = match self.uv_context.take() {
+            None => UvResolutionContext::from_project(self.project)?,
+            Some(context) => context,
+        };
+        self.uv_context = Some(UvResolutionContext {
+            prepare_only: true,
+            ..uv_context.clone()
+        });
+        let result = async {
+            let python_status = self.update_conda_prefix(environment, &prefix).await?;
+            self.update_pypi_prefix(environment, &prefix, &python_status)
+                .await
+        }
+        .await;
+        self.uv_context = Some(uv_context);
+        result
     }
 
     fn pypi_records(
@@ -378,6 +497,23 @@ impl<'p> LockFileDerivedData<'p> {
         }
 
         let prefix = Prefix::new(environment.dir());
+        let python_status = self.update_conda_prefix(environment, &prefix).await?;
+
+        // Store that we updated the environment, so we won't have to do it again.
+        self.updated_conda_prefixes.insert(
+            environment.name().clone(),
+            (prefix.clone(), python_status.clone()),
+        );
+
+        Ok((prefix, python_status))
+    }
+
+    /// Installs the locked conda packages of the environment in a prefix.
+    async fn update_conda_prefix(
+        &mut self,
+        environment: &Environment<'p>,
+        prefix: &Prefix,
+    ) -> miette::Result<PythonStatus> {
         let platform = environment.best_platform();
 
         // Determine the currently installed packages.
@@ -389,10 +525,19 @@ impl<'p> LockFileDerivedData<'p> {
         })?;
 
         // Get the locked environment from the lock-file.
-        let records = self
+        let mut records = self
             .pixi_records(environment, platform)
             .into_diagnostic()?
             .unwrap_or_default();
+        if let Some(vendor_dir) = VendorDir::from_project(self.project) {
+            records = vendor_dir.apply_to_conda_records(records);
+        }
+        apply_requested_ignore_run_exports(
+            &mut records,
+            environment
+                .combined_dependencies(Some(platform))
+                .iter_specs(),
+        );
         let channel_urls = environment
             .channel_urls(&self.project.channel_config())
             .into_diagnostic()?;
@@ -400,8 +545,8 @@ impl<'p> LockFileDerivedData<'p> {
         // Update the prefix with conda packages.
         let has_existing_packages = !installed_packages.is_empty();
         let env_name = GroupedEnvironmentName::Environment(environment.name().clone());
-        let python_status = environment::update_prefix_conda(
-            &prefix,
+        environment::update_prefix_conda(
+            prefix,
             self.package_cache.clone(),
             environment.project().authenticated_client().clone(),
             installed_packages,
@@ -425,16 +570,11 @@ impl<'p> LockFileDerivedData<'p> {
             "",
             self.io_concurrency_limit.clone().into(),
             self.build_context.clone(),
+            self.project.config().verify_hashes(),
+            self.project.config().trust().clone(),
+            self.project.config().clobber_policy(),
         )
-        .await?;
-
-        // Store that we updated the environment, so we won't have to do it again.
-        self.updated_conda_prefixes.insert(
-            environment.name().clone(),
-            (prefix.clone(), python_status.clone()),
-        );
-
-        Ok((prefix, python_status))
+        .await
     }
 }
 
@@ -681,14 +821,32 @@ pub async fn update_lock_file(
     project: &Project,
     options: UpdateLockFileOptions,
 ) -> miette::Result<LockFileDerivedData<'_>> {
-    let lock_file = load_lock_file(project).await?;
+    // The lock-files are read once, the state is computed from the same bytes
+    // that are parsed.
+    let lock_file_sources = LockFileSources::read(project).await?;
     let package_cache =
         PackageCache::new(pixi_config::get_cache_dir()?.join(consts::CONDA_PACKAGE_CACHE_DIR));
     let glob_hash_cache = GlobHashCache::default();
 
+    // The lock-file doesn't have to be checked again when neither it nor the
+    // manifest changed since it was last verified.
+    let ready_state = options
+        .lock_file_usage
+        .should_check_if_out_of_date()
+        .then(|| LockFileReadyState::new(project, &lock_file_sources))
+        .flatten();
+    let is_ready = ready_state
+        .as_ref()
+        .is_some_and(|state| state.is_stored(project));
+    let mut lock_file = lock_file_sources.parse()?;
+
     // should we check the lock-file in the first place?
-    if !options.lock_file_usage.should_check_if_out_of_date() {
-        tracing::info!("skipping check if lock-file is up-to-date");
+    if !options.lock_file_usage.should_check_if_out_of_date() || is_ready {
+        if is_ready {
+            tracing::info!("the lock-file is unchanged since it was last verified");
+        } else {
+            tracing::info!("skipping check if lock-file is up-to-date");
+        }
 
         return Ok(LockFileDerivedData {
             project,
@@ -697,12 +855,25 @@ pub async fn update_lock_file(
             updated_conda_prefixes: Default::default(),
             updated_pypi_prefixes: Default::default(),
             uv_context: None,
-            io_concurrency_limit: IoConcurrencyLimit::default(),
+            io_concurrency_limit: IoConcurrencyLimit::new(
+                project.config().max_concurrent_installs(),
+            ),
             build_context: BuildContext::from_project(project)?,
             glob_hash_cache,
         });
     }
 
+    // Remove the platforms that are no longer locked, these don't have to be
+    // solved again.
+    let mut pruned_platforms = false;
+    if options.lock_file_usage.allows_lock_file_updates() {
+        if let Some(pruned_lock_file) = prune_unlocked_platforms(project, &lock_file) {
+            tracing::info!("removing the platforms that are no longer locked from the lock-file");
+            lock_file = pruned_lock_file;
+            pruned_platforms = true;
+        }
+    }
+
     // Check which environments are out of date.
     let outdated = OutdatedEnvironments::from_project_and_lock_file(
         project,
@@ -714,23 +885,35 @@ pub async fn update_lock_file(
         tracing::info!("the lock-file is up-to-date");
 
         // If no-environment is outdated we can return early.
-        return Ok(LockFileDerivedData {
+        let lock_file_derived_data = LockFileDerivedData {
             project,
             lock_file,
             package_cache,
             updated_conda_prefixes: Default::default(),
             updated_pypi_prefixes: Default::default(),
             uv_context: None,
-            io_concurrency_limit: IoConcurrencyLimit::default(),
+            io_concurrency_limit: IoConcurrencyLimit::new(
+                project.config().max_concurrent_installs(),
+            ),
             build_context: BuildContext::from_project(project)?,
             glob_hash_cache,
-        });
+        };
+        // The single lock-file is also split when the project switched to a
+        // lock-file per environment.
+        let split_lock_file = project.per_environment_lock_files()
+            && project.lock_file_path().is_file()
+            && options.lock_file_usage.allows_lock_file_updates();
+        if pruned_platforms || split_lock_file {
+            lock_file_derived_data.write_to_disk()?;
+        }
+        store_ready_state(project, &lock_file_derived_data.lock_file).await;
+        return Ok(lock_file_derived_data);
     }
 
     // If the lock-file is out of date, but we're not allowed to update it, we
     // should exit.
     if !options.lock_file_usage.allows_lock_file_updates() {
-        miette::bail!("lock-file not up-to-date with the project");
+        return Err(UpdateError::LockFileOutOfDate(outdated.drift).into());
     }
 
     // Construct an update context and perform the actual update.
@@ -747,10 +930,18 @@ pub async fn update_lock_file(
 
     // Write the lock-file to disk
     lock_file_derived_data.write_to_disk()?;
+    store_ready_state(project, &lock_file_derived_data.lock_file).await;
 
     Ok(lock_file_derived_data)
 }
 
+/// Remembers that the lock-file on disk is up-to-date with the manifest.
+async fn store_ready_state(project: &Project, lock_file: &LockFile) {
+    if let Some(state) = LockFileReadyState::from_disk(project).await {
+        state.store(project, lock_file);
+    }
+}
+
 pub struct UpdateContextBuilder<'p> {
     /// The project
     project: &'p Project,
@@ -781,6 +972,10 @@ pub struct UpdateContextBuilder<'p> {
 
     /// A cache for computing input hashes
     glob_hash_cache: Option<GlobHashCache>,
+
+    /// The platforms to update. If this value is `None` all outdated platforms
+    /// are updated.
+    platforms: Option<HashSet<Platform>>,
 }
 
 impl<'p> UpdateContextBuilder<'p> {
@@ -825,6 +1020,15 @@ impl<'p> UpdateContextBuilder<'p> {
         }
     }
 
+    /// Only update the given platforms, the other platforms keep the records
+    /// of the lock-file even if they are outdated.
+    pub(crate) fn with_platforms(self, platforms: HashSet<Platform>) -> Self {
+        Self {
+            platforms: Some(platforms),
+            ..self
+        }
+    }
+
     /// Sets the io concurrency semaphore to use when updating environments.
     #[allow(unused)]
     pub fn with_io_concurrency_semaphore(self, io_concurrency_limit: IoConcurrencyLimit) -> Self {
@@ -845,7 +1049,7 @@ impl<'p> UpdateContextBuilder<'p> {
         };
         let lock_file = self.lock_file;
         let glob_hash_cache = self.glob_hash_cache.unwrap_or_default();
-        let outdated = match self.outdated_environments {
+        let mut outdated = match self.outdated_environments {
             Some(outdated) => outdated,
             None => {
                 OutdatedEnvironments::from_project_and_lock_file(
@@ -856,6 +1060,9 @@ impl<'p> UpdateContextBuilder<'p> {
                 .await
             }
         };
+        if let Some(platforms) = &self.platforms {
+            outdated.retain_platforms(platforms);
+        }
 
         // Extract the current conda records from the lock-file
         // TODO: Should we parallelize this? Measure please.
@@ -1014,6 +1221,7 @@ impl<'p> UpdateContextBuilder<'p> {
         // tool context
         let tool_context = ToolContext::builder()
             .with_gateway(gateway)
+            .with_config(project.config().clone())
             .with_client(client)
             .build();
 
@@ -1038,7 +1246,9 @@ impl<'p> UpdateContextBuilder<'p> {
             package_cache,
             conda_solve_semaphore: Arc::new(Semaphore::new(self.max_concurrent_solves)),
             pypi_solve_semaphore: Arc::new(Semaphore::new(determine_pypi_solve_permits(project))),
-            io_concurrency_limit: self.io_concurrency_limit.unwrap_or_default(),
+            io_concurrency_limit: self.io_concurrency_limit.unwrap_or_else(|| {
+                IoConcurrencyLimit::new(project.config().max_concurrent_installs())
+            }),
             build_context,
             glob_hash_cache,
 
@@ -1059,6 +1269,7 @@ impl<'p> UpdateContext<'p> {
             max_concurrent_solves: project.config().max_concurrent_solves(),
             io_concurrency_limit: None,
             glob_hash_cache: None,
+            platforms: None,
         }
     }
 
@@ -1528,7 +1739,7 @@ impl<'p> UpdateContext<'p> {
             builder.set_channels(&environment_name, channels);
 
             let mut has_pypi_records = false;
-            for platform in environment.platforms() {
+            for platform in environment.locked_platforms() {
                 if let Some(records) = self.take_latest_repodata_records(&environment, platform) {
                     for record in records.into_inner() {
                         builder.add_conda_package(&environment_name, platform, record.into());
@@ -1550,7 +1761,12 @@ impl<'p> UpdateContext<'p> {
             // Store the indexes that were used to solve the environment. But only if there
             // are pypi packages.
             if has_pypi_records {
-                builder.set_pypi_indexes(&environment_name, grouped_env.pypi_options().into());
+                builder.set_pypi_indexes(
+                    &environment_name,
+                    grouped_env
+                        .pypi_options()
+                        .pypi_indexes(grouped_env.platforms()),
+                );
             }
         }
 
@@ -1700,6 +1916,10 @@ async fn spawn_solve_conda_environment_task(
     // Get the virtual packages for this platform
     let virtual_packages = group.virtual_packages(platform);
 
+    // Source packages are built on the current platform, which is not the
+    // platform that is solved for when cross-compiling.
+    let build_virtual_packages = group.virtual_packages(Platform::current());
+
     // The list of channels and platforms we need for this task
     let channels = group.channels().into_iter().cloned().collect_vec();
 
@@ -1709,9 +1929,39 @@ async fn spawn_solve_conda_environment_task(
     // Whether we should use custom mapping location
     let pypi_name_mapping_location = group.project().pypi_name_mapping_source()?.clone();
 
+    // The PyPI packages that conda packages provide according to the project
+    let pypi_conda_mapping = group.project().pypi_conda_mapping();
+
+    // The backend of the cache of the mapping requests
+    let http_cache = group.project().config().cache().http_backend();
+
     // Get the channel configuration
     let channel_config = group.project().channel_config();
 
+    // The overrides of the packages in the solve
+    let overrides = group.project().conda_overrides();
+
+    // Packages published after this point in time are ignored
+    let exclude_newer = group.project().exclude_newer();
+
+    // The configuration used to fall back to the full repodata
+    let config = group.project().config().clone();
+
+    // The cache of earlier solves, which is shared between workspaces
+    let solve_cache = group
+        .project()
+        .config()
+        .cache()
+        .solves()
+        .then(|| pixi_config::get_cache_dir().ok())
+        .flatten()
+        .map(|cache_dir| SolveCache::new(&cache_dir));
+
+    // The requirements of the environments in the solve-group, to explain
+    // packages that are held back by a sibling environment.
+    let solve_group_constraints =
+        SolveGroupConstraints::from_group(&group, platform, &channel_config);
+
     // A root progress bar for the task. It is used to attach sub-progress bars to,
     // that doesn't need to be split up between multiple platforms.
     let root_pb = global_multi_progress().add(ProgressBar::hidden());
@@ -1726,6 +1976,7 @@ async fn spawn_solve_conda_environment_task(
 
             let pb = Arc::new(SolveProgressBar::new(
                 global_multi_progress().add(ProgressBar::hidden()),
+                SolveKind::Conda,
                 platform,
                 group_name.clone(),
             ));
@@ -1782,8 +2033,8 @@ async fn spawn_solve_conda_environment_task(
                             &channel_urls,
                             platform,
                             virtual_packages.clone(),
-                            platform,
-                            virtual_packages.clone(),
+                            Platform::current(),
+                            build_virtual_packages.clone(),
                             metadata_reporter.clone(),
                             Some(source_reporter.clone()),
                             build_id,
@@ -1806,8 +2057,10 @@ async fn spawn_solve_conda_environment_task(
             }
             let source_repodata: Vec<_> = source_futures.try_collect().await?;
 
-            // Extract transitive requirements from the requirements of the source packages
+            // Extract transitive requirements from the requirements of the source packages,
+            // the packages that replace other packages are also fetched.
             let mut query_match_specs = match_specs.clone();
+            query_match_specs.extend(overrides.replacements());
             for source_repodata in source_repodata
                 .iter()
                 .flat_map(|r| r.records.iter())
@@ -1820,18 +2073,40 @@ async fn spawn_solve_conda_environment_task(
 
             // Extract the repo data records needed to solve the environment.
             let fetch_repodata_start = Instant::now();
-            let available_packages = repodata_gateway
-                .query(
-                    channels
-                        .into_iter()
-                        .map(|c| c.into_channel(&channel_config))
-                        .collect::<Result<Vec<_>, _>>()
-                        .into_diagnostic()?,
-                    [platform, Platform::NoArch],
-                    query_match_specs,
+            let channels = channels
+                .into_iter()
+                .map(|c| c.into_channel(&channel_config))
+                .collect::<Result<Vec<_>, _>>()
+                .into_diagnostic()?;
+            let platforms = [platform, Platform::NoArch];
+            let query_repodata = |gateway: Gateway| {
+                let channels = channels.clone();
+                let query_match_specs = query_match_specs.clone();
+                let pb = pb.clone();
+                async move {
+                    gateway
+                        .query(channels, platforms, query_match_specs)
+                        .recursive(true)
+                        .with_reporter(GatewayProgressReporter::new(pb))
+                        .await
+                }
+            };
+            // Channels whose sharded repodata fails fall back to the full
+            // repodata.
+            let available_packages = config
+                .query_with_sharded_fallback(
+                    &client,
+                    repodata_gateway,
+                    &channels,
+                    &platforms,
+                    &query_match_specs,
+                    query_repodata,
                 )
-                .recursive(true)
-                .with_reporter(GatewayProgressReporter::new(pb.clone()))
+                .instrument(tracing::info_span!(
+                    "fetch_repodata",
+                    group = %group_name.as_str(),
+                    platform = %platform
+                ))
                 .await
                 .into_diagnostic()?;
             let total_records = available_packages.iter().map(RepoData::len).sum::<usize>();
@@ -1839,6 +2114,10 @@ async fn spawn_solve_conda_environment_task(
                 "fetched {total_records} records in {:?}",
                 fetch_repodata_start.elapsed()
             );
+            let solve_group_candidates = solve_group_constraints
+                .as_ref()
+                .map(|constraints| constraints.shared_candidates(&available_packages))
+                .unwrap_or_default();
 
             // Solve conda packages
             pb.reset_style();
@@ -1854,7 +2133,7 @@ async fn spawn_solve_conda_environment_task(
                 .flat_map(|record| record.records.iter())
                 .map(|record| record.package_record.name.clone())
                 .collect();
-            let locked_records = existing_repodata_records
+            let locked_records: Vec<_> = existing_repodata_records
                 .records
                 .iter()
                 .filter_map(|record| {
@@ -1867,31 +2146,87 @@ async fn spawn_solve_conda_environment_task(
                 })
                 .collect();
 
-            let mut records = lock_file::resolve_conda(
-                all_specs,
-                virtual_packages,
-                locked_records,
-                available_packages,
-                source_repodata,
+            // Source packages and overrides are not part of the cache key, so
+            // these solves are never cached.
+            let solve_cache =
+                solve_cache.filter(|_| source_repodata.is_empty() && overrides.is_empty());
+            let solve_input = SolveInput {
+                specs: &all_specs,
+                channels: &channel_urls,
+                platform,
+                virtual_packages: &virtual_packages,
+                locked_packages: &locked_records,
                 channel_priority,
-            )
-            .await
-            .with_context(|| {
-                format!(
-                    "failed to solve the conda requirements of '{}' '{}'",
-                    group_name.fancy_display(),
-                    consts::PLATFORM_STYLE.apply_to(platform)
+                exclude_newer,
+                repodata: &available_packages,
+            };
+            let solve_key = solve_cache.as_ref().map(|_| solve_input.hash_key());
+            let cached_records = match (&solve_cache, &solve_key) {
+                (Some(solve_cache), Some(solve_key)) => {
+                    solve_cache.get(&solve_input, solve_key).await
+                }
+                _ => None,
+            };
+
+            let mut records = if let Some(cached_records) = cached_records {
+                tracing::info!(
+                    "reusing the cached solve of '{}' '{}'",
+                    group_name.as_str(),
+                    platform
+                );
+                cached_records
+                    .into_iter()
+                    .map(PixiRecord::Binary)
+                    .collect_vec()
+            } else {
+                let records = lock_file::resolve_conda(
+                    all_specs,
+                    virtual_packages,
+                    locked_records,
+                    available_packages,
+                    source_repodata,
+                    channel_priority,
+                    overrides,
+                    exclude_newer,
                 )
-            })?;
+                .await
+                .with_context(|| {
+                    SolveFailure(format!(
+                        "failed to solve the conda requirements of '{}' '{}'",
+                        group_name.fancy_display(),
+                        consts::PLATFORM_STYLE.apply_to(platform)
+                    ))
+                })?;
+                if let (Some(solve_cache), Some(solve_key)) = (&solve_cache, &solve_key) {
+                    let binary_records = records
+                        .iter()
+                        .filter_map(PixiRecord::as_binary)
+                        .cloned()
+                        .collect_vec();
+                    solve_cache.insert(solve_key, &binary_records);
+                }
+                records
+            };
+
+            if let Some(constraints) = &solve_group_constraints {
+                for downgrade in constraints.downgrades(&solve_group_candidates, &records) {
+                    tracing::warn!("{downgrade}");
+                }
+            }
 
             // Add purl's for the conda packages that are also available as pypi packages if
             // we need them.
             if has_pypi_dependencies {
                 pb.set_message("extracting pypi packages");
+                let not_overridden = pypi_mapping::apply_mapping_overrides(
+                    &pypi_conda_mapping,
+                    records.iter_mut().filter_map(PixiRecord::as_binary_mut),
+                );
                 pypi_mapping::amend_pypi_purls(
                     client,
+                    http_cache,
                     &pypi_name_mapping_location,
-                    records.iter_mut().filter_map(PixiRecord::as_binary_mut),
+                    not_overridden,
                     Some(pb.purl_amend_reporter()),
                 )
                 .await?;
@@ -1901,6 +2236,7 @@ async fn spawn_solve_conda_environment_task(
             let records_by_name = PixiRecordsByName::from(records);
 
             let end = Instant::now();
+            stats::record(Phase::Solve, end - start);
 
             // Finish the progress bar
             pb.finish();
@@ -1964,11 +2300,16 @@ async fn spawn_extract_environment_task(
         Pypi((&'a PypiRecord, Option<ExtraName>)),
     }
 
+    // The overridden packages are not part of the environment, their
+    // replacements are.
+    let conda_overrides = environment.project().conda_overrides();
+    let pypi_overrides = environment.project().pypi_overrides()?;
+
     // Determine the conda packages we need.
     let conda_package_names = environment
         .combined_dependencies(Some(platform))
         .names()
-        .cloned()
+        .filter_map(|name| conda_overrides.effective_name(name))
         .map(PackageName::Conda)
         .collect::<Vec<_>>();
 
@@ -1978,7 +2319,11 @@ async fn spawn_extract_environment_task(
     let mut pypi_package_names = HashSet::new();
     for (name, reqs) in pypi_dependencies {
         let name = name.as_normalized().clone();
-        let uv_name = to_uv_normalize(&name).into_diagnostic()?;
+        let Some(uv_name) =
+            pypi_overrides.effective_name(&to_uv_normalize(&name).into_diagnostic()?)
+        else {
+            continue;
+        };
         for req in reqs {
             for extra in req.extras().iter() {
                 pypi_package_names.insert(PackageName::Pypi((
@@ -1989,13 +2334,28 @@ async fn spawn_extract_environment_task(
         }
         pypi_package_names.insert(PackageName::Pypi((uv_name, None)));
     }
+    if has_pypi_dependencies {
+        pypi_package_names.extend(
+            pypi_overrides
+                .replacements()
+                .map(|requirement| PackageName::Pypi((requirement.name, None))),
+        );
+    }
 
     // Compute the Pypi marker environment. Only do this if we have pypi
     // dependencies.
     let marker_environment = if has_pypi_dependencies {
+        let pypi_options = environment.pypi_options();
         grouped_repodata_records
             .python_interpreter_record()
-            .and_then(|record| determine_marker_environment(platform, &record.package_record).ok())
+            .and_then(|record| {
+                determine_marker_environment(
+                    platform,
+                    &record.package_record,
+                    pypi_options.marker_environment_overrides(platform),
+                )
+                .ok()
+            })
     } else {
         None
     };
@@ -2033,10 +2393,14 @@ async fn spawn_extract_environment_task(
             PackageRecord::Conda(record) => {
                 // Find all dependencies in the record and add them to the queue.
                 for dependency in record.package_record().depends.iter() {
-                    let dependency_name =
-                        PackageName::Conda(rattler_conda_types::PackageName::new_unchecked(
+                    let Some(dependency_name) = conda_overrides.effective_name(
+                        &rattler_conda_types::PackageName::new_unchecked(
                             dependency.split_once(' ').unwrap_or((dependency, "")).0,
-                        ));
+                        ),
+                    ) else {
+                        continue;
+                    };
+                    let dependency_name = PackageName::Conda(dependency_name);
                     if queued_names.insert(dependency_name.clone()) {
                         queue.push(dependency_name);
                     }
@@ -2063,7 +2427,11 @@ async fn spawn_extract_environment_task(
                             continue;
                         }
                     }
-                    let uv_name = to_uv_normalize(&req.name).into_diagnostic()?;
+                    let Some(uv_name) = pypi_overrides
+                        .effective_name(&to_uv_normalize(&req.name).into_diagnostic()?)
+                    else {
+                        continue;
+                    };
 
                     // Add the package to the queue
                     for extra in req.extras.iter() {
@@ -2139,21 +2507,29 @@ async fn spawn_solve_pypi_task(
     let mut pixi_records = repodata_records.records.clone();
     let locked_pypi_records = locked_pypi_packages.records.clone();
 
-    pypi_mapping::amend_pypi_purls(
-        environment.project().client().clone().into(),
-        pypi_name_mapping_location,
+    let not_overridden = pypi_mapping::apply_mapping_overrides(
+        &environment.project().pypi_conda_mapping(),
         pixi_records
             .iter_mut()
             .filter_map(PixiRecord::as_binary_mut),
+    );
+    pypi_mapping::amend_pypi_purls(
+        environment.project().client().clone().into(),
+        environment.project().config().cache().http_backend(),
+        pypi_name_mapping_location,
+        not_overridden,
         None,
     )
     .await?;
 
     let pypi_options = environment.pypi_options();
+    let overrides = environment.project().pypi_overrides()?;
+    let exclude_newer = environment.project().exclude_newer();
     // let (pypi_packages, duration) = tokio::spawn(
     let (pypi_packages, duration) = async move {
         let pb = SolveProgressBar::new(
             global_multi_progress().add(ProgressBar::hidden()),
+            SolveKind::Pypi,
             platform,
             environment_name.clone(),
         );
@@ -2191,16 +2567,19 @@ async fn spawn_solve_pypi_task(
             &python_path,
             env_variables,
             &project_root,
+            &overrides,
+            exclude_newer,
         )
         .await
         .with_context(|| {
-            format!(
+            SolveFailure(format!(
                 "failed to solve the pypi requirements of '{}' '{}'",
                 environment_name.fancy_display(),
                 consts::PLATFORM_STYLE.apply_to(platform)
-            )
+            ))
         })?;
         let end = Instant::now();
+        stats::record(Phase::Solve, end - start);
 
         pb.finish();
 
@@ -2235,6 +2614,9 @@ async fn spawn_create_prefix_task(
     let group_name = group.name().clone();
     let prefix = group.prefix();
     let client = group.project().authenticated_client().clone();
+    let verify_hashes = group.project().config().verify_hashes();
+    let trust = group.project().config().trust().clone();
+    let clobber_policy = group.project().config().clobber_policy();
     let channels = group
         .channel_urls(&group.project().channel_config())
         .into_diagnostic()?;
@@ -2253,6 +2635,13 @@ async fn spawn_create_prefix_task(
     // for this prefix are available.
     let (pixi_records, installed_packages) =
         tokio::try_join!(pixi_records.map(Ok), installed_packages_future)?;
+    let mut pixi_records = pixi_records.records.clone();
+    apply_requested_ignore_run_exports(
+        &mut pixi_records,
+        group
+            .combined_dependencies(Some(Platform::current()))
+            .iter_specs(),
+    );
 
     let build_virtual_packages = group.virtual_packages(Platform::current());
 
@@ -2268,7 +2657,7 @@ async fn spawn_create_prefix_task(
                 package_cache,
                 client,
                 installed_packages,
-                pixi_records.records.clone(),
+                pixi_records,
                 build_virtual_packages,
                 channels,
                 Platform::current(),
@@ -2284,6 +2673,9 @@ async fn spawn_create_prefix_task(
                 "  ",
                 io_concurrency_limit.into(),
                 build_context,
+                verify_hashes,
+                trust,
+                clobber_policy,
             )
             .await?;
             let end = Instant::now();
@@ -1,7 +1,13 @@
-use std::sync::Arc;
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+    sync::Arc,
+};
 
 use pixi_manifest::FeaturesExt;
-use rattler_conda_types::Platform;
+use pixi_record::PixiRecord;
+use pixi_spec::PixiSpec;
+use rattler_conda_types::{MatchSpec, PackageName, ParseStrictness, Platform};
 use rattler_lock::{LockFile, LockFileBuilder, LockedPackageRef};
 use tokio::sync::Semaphore;
 
@@ -15,6 +21,14 @@ use crate::{
 #[derive(Clone)]
 pub struct IoConcurrencyLimit(Arc<Semaphore>);
 
+impl IoConcurrencyLimit {
+    /// Constructs a new limit that allows the given number of concurrent IO
+    /// operations.
+    pub fn new(permits: usize) -> Self {
+        Self(Arc::new(Semaphore::new(permits)))
+    }
+}
+
 impl Default for IoConcurrencyLimit {
     fn default() -> Self {
         Self(Arc::new(Semaphore::new(10)))
@@ -68,3 +82,201 @@ pub fn filter_lock_file<
 
     builder.finish()
 }
+
+/// Identifies a package in a locked environment.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub enum LockedPackageName {
+    Conda(PackageName),
+    Pypi(pep508_rs::PackageName),
+}
+
+impl From<LockedPackageRef<'_>> for LockedPackageName {
+    fn from(value: LockedPackageRef<'_>) -> Self {
+        match value {
+            LockedPackageRef::Conda(data) => Self::Conda(data.record().name.clone()),
+            LockedPackageRef::Pypi(data, _) => Self::Pypi(data.name.clone()),
+        }
+    }
+}
+
+/// Returns the packages of a locked environment that are reachable from the
+/// direct dependencies of the environment.
+///
+/// Packages that are not reachable are no longer required by the environment.
+pub fn reachable_packages<'lock>(
+    environment: &Environment<'_>,
+    platform: Platform,
+    packages: impl IntoIterator<Item = LockedPackageRef<'lock>>,
+) -> HashSet<LockedPackageName> {
+    let pypi_dependencies = environment.pypi_dependencies(Some(platform));
+    let mut queue: Vec<LockedPackageName> = environment
+        .combined_dependencies(Some(platform))
+        .names()
+        .cloned()
+        .map(LockedPackageName::Conda)
+        .chain(
+            pypi_dependencies
+                .names()
+                .map(|name| LockedPackageName::Pypi(name.as_normalized().clone())),
+        )
+        .collect();
+
+    // PyPI packages are installed by the python interpreter of the environment.
+    if !pypi_dependencies.is_empty() {
+        queue.push(LockedPackageName::Conda(PackageName::new_unchecked(
+            "python",
+        )));
+    }
+
+    dependency_closure(packages, queue)
+}
+
+/// Returns the names of the `roots` and of all the locked packages they
+/// (transitively) depend on.
+pub fn dependency_closure<'lock>(
+    packages: impl IntoIterator<Item = LockedPackageRef<'lock>>,
+    roots: impl IntoIterator<Item = LockedPackageName>,
+) -> HashSet<LockedPackageName> {
+    let packages: HashMap<LockedPackageName, LockedPackageRef<'lock>> = packages
+        .into_iter()
+        .map(|package| (LockedPackageName::from(package), package))
+        .collect();
+
+    let mut queue: Vec<_> = roots.into_iter().collect();
+    let mut reachable = HashSet::new();
+    while let Some(name) = queue.pop() {
+        if !reachable.insert(name.clone()) {
+            continue;
+        }
+        match packages.get(&name) {
+            Some(LockedPackageRef::Conda(data)) => {
+                queue.extend(
+                    data.record()
+                        .depends
+                        .iter()
+                        .filter_map(|depends| dependency_name(depends))
+                        .map(LockedPackageName::Conda),
+                );
+            }
+            Some(LockedPackageRef::Pypi(data, _)) => {
+                queue.extend(
+                    data.requires_dist
+                        .iter()
+                        .map(|req| LockedPackageName::Pypi(req.name.clone())),
+                );
+            }
+            None => {}
+        }
+    }
+
+    reachable
+}
+
+/// Returns the name of the package a `depends` entry of a conda package
+/// refers to, e.g. `numpy` for `numpy>=1.20` or `numpy 1.20.*`.
+pub fn dependency_name(depends: &str) -> Option<PackageName> {
+    MatchSpec::from_str(depends, ParseStrictness::Lenient)
+        .ok()?
+        .name
+}
+
+/// Sets the ignored run exports of the source records to the ones of the
+/// requested source dependencies. The lock-file has no field for them, so the
+/// records that are read from the lock-file don't know them.
+pub fn apply_requested_ignore_run_exports<'a>(
+    records: &mut [PixiRecord],
+    requested: impl IntoIterator<Item = (&'a PackageName, &'a PixiSpec)>,
+) {
+    let requested = requested
+        .into_iter()
+        .filter_map(|(name, spec)| {
+            let source_spec = spec.clone().into_source_or_binary().left()?;
+            Some((name, source_spec.ignore_run_exports().cloned()))
+        })
+        .collect::<HashMap<_, _>>();
+    for record in records {
+        let PixiRecord::Source(record) = record else {
+            continue;
+        };
+        if let Some(ignore_run_exports) = requested.get(&record.package_record.name) {
+            record.ignore_run_exports = ignore_run_exports.clone().unwrap_or_default();
+        }
+    }
+}
+
+/// Constructs a new lock-file where all the packages that are no longer
+/// reachable from the direct dependencies of an environment have been removed.
+pub fn prune_lock_file(project: &Project, lock_file: &LockFile) -> LockFile {
+    let mut reachable = HashMap::new();
+    for (environment_name, environment) in lock_file.environments() {
+        let Some(project_env) = project.environment(environment_name) else {
+            continue;
+        };
+        for (platform, packages) in environment.packages_by_platform() {
+            reachable.insert(
+                (environment_name.to_string(), platform),
+                reachable_packages(&project_env, platform, packages),
+            );
+        }
+    }
+
+    filter_lock_file(project, lock_file, |env, platform, package| {
+        reachable
+            .get(&(env.name().to_string(), platform))
+            .map_or(true, |reachable| {
+                reachable.contains(&LockedPackageName::from(package))
+            })
+    })
+}
+
+/// Constructs a new lock-file without the platforms that are no longer locked
+/// for the environments of the project, e.g. because they were removed from
+/// the project or are excluded by the `lock-platforms` configuration. Returns
+/// `None` if the lock-file doesn't contain any of these platforms.
+pub fn prune_unlocked_platforms(project: &Project, lock_file: &LockFile) -> Option<LockFile> {
+    let locked_platforms: HashMap<String, HashSet<Platform>> = project
+        .environments()
+        .into_iter()
+        .map(|env| (env.name().to_string(), env.locked_platforms()))
+        .collect();
+
+    let has_unlocked_platforms = lock_file.environments().any(|(name, environment)| {
+        locked_platforms.get(name).is_some_and(|platforms| {
+            environment
+                .packages_by_platform()
+                .any(|(platform, _)| !platforms.contains(&platform))
+        })
+    });
+    if !has_unlocked_platforms {
+        return None;
+    }
+
+    Some(filter_lock_file(project, lock_file, |env, platform, _| {
+        locked_platforms
+            .get(env.name().as_str())
+            .is_some_and(|platforms| platforms.contains(&platform))
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dependency_name() {
+        for (depends, expected) in [
+            ("numpy", "numpy"),
+            ("numpy 1.20.*", "numpy"),
+            ("numpy>=1.20", "numpy"),
+            ("python >=3.8,<3.13.0a0", "python"),
+            ("libblas=*=*mkl", "libblas"),
+            ("openssl[version='>=3']", "openssl"),
+        ] {
+            assert_eq!(
+                dependency_name(depends),
+                Some(PackageName::new_unchecked(expected)),
+                "{depends}"
+            );
+        }
+    }
+}
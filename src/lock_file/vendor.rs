@@ -0,0 +1,214 @@
+use std::path::PathBuf;
+
+use pixi_record::PixiRecord;
+use rattler_lock::{PypiPackageData, PypiPackageEnvironmentData, UrlOrPath};
+use url::Url;
+
+use crate::Project;
+
+/// The directory in the vendor directory that contains the conda packages,
+/// stored per subdir like a channel.
+pub(crate) const CONDA_DIR: &str = "conda";
+
+/// The directory in the vendor directory that contains the wheels and source
+/// distributions of the PyPI packages.
+pub(crate) const PYPI_DIR: &str = "pypi";
+
+/// A directory with the packages of a lock-file, as created by `pixi lock
+/// vendor`.
+#[derive(Debug, Clone)]
+pub(crate) struct VendorDir(PathBuf);
+
+impl VendorDir {
+    pub(crate) fn new(path: impl Into<PathBuf>) -> Self {
+        Self(path.into())
+    }
+
+    /// Returns the vendor directory that is configured for the project.
+    pub(crate) fn from_project(project: &Project) -> Option<Self> {
+        project
+            .config()
+            .vendor_dir()
+            .map(|dir| Self(project.root().join(dir)))
+    }
+
+    /// The directory that contains the conda packages of the given subdir.
+    pub(crate) fn conda_subdir(&self, subdir: &str) -> PathBuf {
+        self.0.join(CONDA_DIR).join(subdir)
+    }
+
+    /// The path a conda package is vendored at.
+    pub(crate) fn conda_package_path(&self, subdir: &str, file_name: &str) -> PathBuf {
+        self.conda_subdir(subdir).join(file_name)
+    }
+
+    /// The path a PyPI package is vendored at.
+    pub(crate) fn pypi_package_path(&self, file_name: &str) -> PathBuf {
+        self.0.join(PYPI_DIR).join(file_name)
+    }
+
+    /// Points the binary conda records to the vendored packages, records that
+    /// are not vendored are left untouched.
+    pub(crate) fn apply_to_conda_records(&self, records: Vec<PixiRecord>) -> Vec<PixiRecord> {
+        records
+            .into_iter()
+            .map(|record| match record {
+                PixiRecord::Binary(mut record) => {
+                    let path =
+                        self.conda_package_path(&record.package_record.subdir, &record.file_name);
+                    if let Some(url) = vendored_url(path) {
+                        record.url = url;
+                    }
+                    PixiRecord::Binary(record)
+                }
+                source => source,
+            })
+            .collect()
+    }
+
+    /// Points the PyPI packages to the vendored files, packages that are not
+    /// vendored are left untouched.
+    pub(crate) fn apply_to_pypi_records(
+        &self,
+        records: Vec<(PypiPackageData, PypiPackageEnvironmentData)>,
+    ) -> Vec<(PypiPackageData, PypiPackageEnvironmentData)> {
+        records
+            .into_iter()
+            .map(|(mut data, env_data)| {
+                if let Some(url) = pypi_file_name(&data.location)
+                    .and_then(|file_name| vendored_url(self.pypi_package_path(&file_name)))
+                {
+                    data.location = UrlOrPath::Url(url);
+                }
+                (data, env_data)
+            })
+            .collect()
+    }
+}
+
+/// Returns the `file://` url of a vendored package if it exists.
+fn vendored_url(path: PathBuf) -> Option<Url> {
+    if !path.is_file() {
+        return None;
+    }
+    Url::from_file_path(path).ok()
+}
+
+/// Returns the file name of a PyPI package that is downloaded from an index.
+/// Packages from git, a direct url or a local path return `None`.
+pub(crate) fn pypi_file_name(location: &UrlOrPath) -> Option<String> {
+    let UrlOrPath::Url(url) = location else {
+        return None;
+    };
+    if !matches!(url.scheme(), "http" | "https") {
+        return None;
+    }
+    let file_name = url.path_segments()?.last()?;
+    Some(
+        percent_encoding::percent_decode_str(file_name)
+            .decode_utf8_lossy()
+            .into_owned(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use itertools::Itertools;
+    use rattler_lock::PypiPackageEnvironmentData;
+
+    use super::*;
+    use crate::lock_file::test_utils::record;
+
+    #[test]
+    fn test_apply_vendored_conda_records() {
+        let dir = tempfile::tempdir().unwrap();
+        let vendor_dir = VendorDir::new(dir.path());
+
+        // Only `foo` is vendored
+        let foo = record("foo", "1.0");
+        let bar = record("bar", "1.0");
+        let vendored = vendor_dir.conda_package_path(&foo.package_record.subdir, &foo.file_name);
+        fs_err::create_dir_all(vendored.parent().unwrap()).unwrap();
+        fs_err::write(&vendored, "").unwrap();
+
+        let records = vendor_dir.apply_to_conda_records(vec![
+            PixiRecord::Binary(foo),
+            PixiRecord::Binary(bar.clone()),
+        ]);
+        assert_eq!(
+            records
+                .iter()
+                .map(|record| record.as_binary().unwrap().url.clone())
+                .collect_vec(),
+            vec![Url::from_file_path(&vendored).unwrap(), bar.url]
+        );
+    }
+
+    #[test]
+    fn test_apply_vendored_pypi_records() {
+        let dir = tempfile::tempdir().unwrap();
+        let vendor_dir = VendorDir::new(dir.path());
+        let package = |name: &str, location: &str| {
+            (
+                PypiPackageData {
+                    name: name.parse().unwrap(),
+                    version: "1.0".parse().unwrap(),
+                    location: UrlOrPath::from_str(location).unwrap(),
+                    hash: None,
+                    requires_dist: vec![],
+                    requires_python: None,
+                    editable: false,
+                },
+                PypiPackageEnvironmentData::default(),
+            )
+        };
+
+        // Only the wheel of `foo` is vendored, `bar` is not and `baz` is not
+        // from an index.
+        let vendored = vendor_dir.pypi_package_path("foo-1.0-py3-none-any.whl");
+        fs_err::create_dir_all(vendored.parent().unwrap()).unwrap();
+        fs_err::write(&vendored, "").unwrap();
+
+        let records = vendor_dir.apply_to_pypi_records(vec![
+            package(
+                "foo",
+                "https://files.pythonhosted.org/packages/foo-1.0-py3-none-any.whl",
+            ),
+            package(
+                "bar",
+                "https://files.pythonhosted.org/packages/bar-1.0-py3-none-any.whl",
+            ),
+            package("baz", "git+https://github.com/foo/baz.git"),
+        ]);
+        assert_eq!(
+            records
+                .iter()
+                .map(|(data, _)| data.location.to_string())
+                .collect_vec(),
+            vec![
+                Url::from_file_path(&vendored).unwrap().to_string(),
+                "https://files.pythonhosted.org/packages/bar-1.0-py3-none-any.whl".to_string(),
+                "git+https://github.com/foo/baz.git".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pypi_file_name() {
+        let location = |url: &str| UrlOrPath::from_str(url).unwrap();
+        assert_eq!(
+            pypi_file_name(&location(
+                "https://files.pythonhosted.org/packages/ab/cd/foo-1.0%2Blocal-py3-none-any.whl"
+            ))
+            .as_deref(),
+            Some("foo-1.0+local-py3-none-any.whl")
+        );
+        assert_eq!(
+            pypi_file_name(&location("git+https://github.com/foo/bar.git")),
+            None
+        );
+        assert_eq!(pypi_file_name(&location("./foo")), None);
+    }
+}
@@ -3,7 +3,7 @@
 #[cfg(feature = "pixi_allocator")]
 extern crate pixi_allocator;
 
-pub fn main() -> miette::Result<()> {
+pub fn main() {
     // We often run out of stack space when running the CLI. This is especially an
     // issue for debug builds.
     //
@@ -45,11 +45,16 @@ pub fn main() -> miette::Result<()> {
         result
     };
 
-    std::thread::Builder::new()
+    let result = std::thread::Builder::new()
         .name("main2".to_string())
         .stack_size(main_stack_size)
         .spawn(main2)
         .expect("Tokio executor failed, was there a panic?")
         .join()
-        .expect("Tokio executor failed, was there a panic?")
+        .expect("Tokio executor failed, was there a panic?");
+
+    // Report the error and exit with the exit code of its category.
+    if let Err(err) = result {
+        pixi::cli::error_format::exit_with_error(err);
+    }
 }
@@ -6,13 +6,14 @@ use std::{
 };
 
 use indexmap::IndexMap;
-use itertools::Either;
+use itertools::{Either, Itertools};
 use pixi_consts::consts;
 use pixi_manifest::{
     self as manifest, EnvironmentName, Feature, FeatureName, FeaturesExt, HasFeaturesIter,
-    HasManifestRef, Manifest, SystemRequirements, Task, TaskName,
+    HasManifestRef, Manifest, PyPiRequirement, SystemRequirements, Task, TaskName,
 };
 use rattler_conda_types::{Arch, Platform};
+use url::Url;
 
 use super::{
     errors::{UnknownTask, UnsupportedPlatformError},
@@ -110,6 +111,17 @@ impl<'p> Environment<'p> {
             .join(self.activation_cache_name())
     }
 
+    /// Returns the platforms of the environment that are locked. These are the
+    /// platforms of the environment, restricted to the `lock-platforms` of
+    /// the configuration.
+    pub(crate) fn locked_platforms(&self) -> HashSet<Platform> {
+        let mut platforms = self.platforms();
+        if let Some(lock_platforms) = self.project.config().lock_platforms() {
+            platforms.retain(|platform| lock_platforms.contains(platform));
+        }
+        platforms
+    }
+
     /// Returns the best platform for the current platform & environment.
     pub fn best_platform(&self) -> Platform {
         let current = Platform::current();
@@ -171,6 +183,26 @@ impl<'p> Environment<'p> {
         current
     }
 
+    /// Returns the indexes that are pinned on the PyPI dependencies of this
+    /// environment with `index`, and the index of the PyTorch packages of the
+    /// platform.
+    pub fn pinned_pypi_indexes(&self, platform: Option<Platform>) -> Vec<Url> {
+        let torch_index = self
+            .pypi_options()
+            .torch
+            .zip(platform)
+            .and_then(|(torch, platform)| torch.index_url(platform));
+        self.pypi_dependencies(platform)
+            .into_specs()
+            .filter_map(|(_, requirement)| match requirement {
+                PyPiRequirement::Version { index, .. } => index,
+                _ => None,
+            })
+            .chain(torch_index)
+            .unique()
+            .collect()
+    }
+
     /// Returns the tasks defined for this environment.
     ///
     /// Tasks are defined on a per-target per-feature per-environment basis.
@@ -887,4 +919,75 @@ mod tests {
             .validate_platform_support(Some(Platform::EmscriptenWasm32))
             .is_ok());
     }
+
+    #[test]
+    fn test_pinned_pypi_indexes() {
+        let project = Project::from_str(
+            Path::new("pixi.toml"),
+            r#"
+        [project]
+        name = "foobar"
+        channels = []
+        platforms = ["linux-64", "osx-arm64"]
+
+        [pypi-dependencies]
+        rich = "*"
+        private = { version = "*", index = "https://pypi.example.com/simple" }
+        shared = { version = "*", index = "https://pypi.example.com/simple" }
+
+        [target.linux-64.pypi-dependencies]
+        linux-only = { version = "*", index = "https://linux.example.com/simple" }
+        "#,
+        )
+        .unwrap();
+        let env = project.default_environment();
+
+        // Every index is listed once.
+        let mut linux_indexes = env.pinned_pypi_indexes(Some(Platform::Linux64));
+        linux_indexes.sort();
+        assert_eq!(
+            linux_indexes,
+            vec![
+                Url::parse("https://linux.example.com/simple").unwrap(),
+                Url::parse("https://pypi.example.com/simple").unwrap(),
+            ]
+        );
+        assert_eq!(
+            env.pinned_pypi_indexes(Some(Platform::OsxArm64)),
+            vec![Url::parse("https://pypi.example.com/simple").unwrap()]
+        );
+    }
+
+    #[test]
+    fn test_pinned_pypi_indexes_torch() {
+        let project = Project::from_str(
+            Path::new("pixi.toml"),
+            r#"
+        [project]
+        name = "foobar"
+        channels = []
+        platforms = ["linux-64", "osx-arm64"]
+
+        [pypi-options.torch]
+        backend = "cu124"
+
+        [pypi-dependencies]
+        private = { version = "*", index = "https://pypi.example.com/simple" }
+        "#,
+        )
+        .unwrap();
+        let env = project.default_environment();
+        assert_eq!(
+            env.pinned_pypi_indexes(Some(Platform::Linux64)),
+            vec![
+                Url::parse("https://pypi.example.com/simple").unwrap(),
+                Url::parse("https://download.pytorch.org/whl/cu124").unwrap(),
+            ]
+        );
+        // The PyTorch wheels for macOS are published on PyPI
+        assert_eq!(
+            env.pinned_pypi_indexes(Some(Platform::OsxArm64)),
+            vec![Url::parse("https://pypi.example.com/simple").unwrap()]
+        );
+    }
 }
@@ -31,15 +31,16 @@ use pep508_rs::{Requirement, VersionOrUrl::VersionSpecifier};
 use pixi_config::{Config, PinningStrategy};
 use pixi_consts::consts;
 use pixi_manifest::{
-    pypi::PyPiPackageName, DependencyOverwriteBehavior, EnvironmentName, Environments, FeatureName,
-    FeaturesExt, HasFeaturesIter, HasManifestRef, Manifest, PypiDependencyLocation, SpecType,
-    WorkspaceManifest,
+    pypi::PyPiPackageName, DependencyOverwriteBehavior, EnvironmentName, Environments,
+    ExcludeNewer, FeatureName, FeaturesExt, HasFeaturesIter, HasManifestRef, Manifest,
+    PypiCondaMapping, PypiDependencyLocation, SpecType, WorkspaceManifest,
 };
 use pixi_spec::{PixiSpec, SourceSpec};
 use pixi_utils::reqwest::build_reqwest_clients;
-use pypi_mapping::{ChannelName, CustomMapping, MappingLocation, MappingSource};
+use pypi_mapping::{ChannelName, CustomMapping, MappingLocation, MappingOverrides, MappingSource};
 use rattler_conda_types::{
-    Channel, ChannelConfig, MatchSpec, NamelessMatchSpec, PackageName, Platform, Version,
+    Channel, ChannelConfig, MatchSpec, Matches, NamelessMatchSpec, PackageName, Platform, Version,
+    VersionSpec,
 };
 use rattler_lock::{LockFile, LockedPackageRef};
 use rattler_repodata_gateway::Gateway;
@@ -54,7 +55,10 @@ use crate::{
     diff::LockFileDiff,
     environment::LockFileUsage,
     load_lock_file,
-    lock_file::{filter_lock_file, LockFileDerivedData, UpdateContext, UpdateMode},
+    lock_file::{
+        filter_lock_file, CondaOverrides, LockFileDerivedData, PypiOverrides, UpdateContext,
+        UpdateMode,
+    },
 };
 
 static CUSTOM_TARGET_DIR_WARN: OnceCell<()> = OnceCell::new();
@@ -64,6 +68,7 @@ static CUSTOM_TARGET_DIR_WARN: OnceCell<()> = OnceCell::new();
 pub enum DependencyType {
     CondaDependency(SpecType),
     PypiDependency,
+    RDependency,
 }
 
 impl DependencyType {
@@ -72,6 +77,7 @@ impl DependencyType {
         match self {
             DependencyType::CondaDependency(dep) => dep.name(),
             DependencyType::PypiDependency => consts::PYPI_DEPENDENCIES,
+            DependencyType::RDependency => consts::R_DEPENDENCIES,
         }
     }
 }
@@ -164,6 +170,15 @@ pub type PypiDeps = indexmap::IndexMap<
 
 pub type MatchSpecs = indexmap::IndexMap<PackageName, (MatchSpec, SpecType)>;
 pub type SourceSpecs = indexmap::IndexMap<PackageName, (SourceSpec, SpecType)>;
+pub type RSpecs = indexmap::IndexMap<String, NamelessMatchSpec>;
+
+/// The table of the manifest that a conda spec is added to.
+enum CondaSpecTable {
+    Dependencies(SpecType),
+    /// The `[r-dependencies]` table, the spec is added for the R package with
+    /// the given name.
+    RDependencies(String),
+}
 
 #[derive(thiserror::Error, Debug, miette::Diagnostic)]
 pub enum ProjectError {
@@ -203,7 +218,8 @@ impl Project {
             .expect("manifest path should always have a parent")
             .to_owned();
 
-        let config = Config::load(&root);
+        let config = tracing::info_span!("load_config").in_scope(|| Config::load(&root));
+        crate::stats::enable_from_config(&config);
 
         Self {
             root,
@@ -262,7 +278,8 @@ impl Project {
 
     /// Loads a project from manifest file.
     pub fn from_path(manifest_path: &Path) -> Result<Self, ProjectError> {
-        let manifest = Manifest::from_path(manifest_path)
+        let manifest = tracing::info_span!("parse_manifest")
+            .in_scope(|| Manifest::from_path(manifest_path))
             .map_err(|e| ProjectError::ParseErrorWithPathBuf(e, manifest_path.into()))?;
         Ok(Project::from_manifest(manifest))
     }
@@ -317,6 +334,22 @@ impl Project {
         self
     }
 
+    /// Only locks the given platforms of the project, overrides the
+    /// `lock-platforms` of the configuration.
+    pub(crate) fn with_lock_platforms(mut self, platforms: Vec<Platform>) -> Self {
+        self.config.lock_platforms = Some(platforms);
+        self
+    }
+
+    /// Overrides the `exclude-newer` of the manifest, e.g. with the value
+    /// passed on the command line. The manifest on disk is not modified.
+    pub(crate) fn with_exclude_newer(mut self, exclude_newer: Option<ExcludeNewer>) -> Self {
+        if let Some(exclude_newer) = exclude_newer {
+            self.manifest.workspace.workspace.exclude_newer = Some(exclude_newer);
+        }
+        self
+    }
+
     /// Returns the name of the project
     pub fn name(&self) -> &str {
         &self.manifest.workspace.workspace.name
@@ -432,6 +465,18 @@ impl Project {
         self.root.join(consts::PROJECT_LOCK_FILE)
     }
 
+    /// Returns true if the lock file of every environment is stored in a
+    /// separate file in [`Self::environment_lock_files_dir`].
+    pub(crate) fn per_environment_lock_files(&self) -> bool {
+        self.manifest.workspace.workspace.per_environment_lock_files
+    }
+
+    /// Returns the directory that contains the lock files of the environments
+    /// when `per-environment-lock-files` is enabled.
+    pub(crate) fn environment_lock_files_dir(&self) -> PathBuf {
+        self.pixi_dir().join(consts::LOCKS_DIR)
+    }
+
     /// Save back changes
     pub(crate) fn save(&mut self) -> miette::Result<()> {
         self.manifest.save()
@@ -696,11 +741,47 @@ impl Project {
         &self.manifest
     }
 
+    /// Returns the overrides of the conda packages of the project.
+    pub(crate) fn conda_overrides(&self) -> CondaOverrides {
+        CondaOverrides::from(self.manifest.workspace.package_overrides.conda.clone())
+    }
+
+    /// Returns the overrides of the PyPI packages of the project.
+    pub(crate) fn pypi_overrides(&self) -> miette::Result<PypiOverrides> {
+        PypiOverrides::from_manifest(&self.manifest.workspace.package_overrides.pypi)
+            .into_diagnostic()
+    }
+
+    /// Returns the PyPI packages that conda packages provide, as defined in
+    /// the `[pypi-conda-mapping]` table of the project.
+    pub(crate) fn pypi_conda_mapping(&self) -> MappingOverrides {
+        self.manifest
+            .workspace
+            .package_overrides
+            .pypi_conda_mapping
+            .iter()
+            .map(|(conda_name, mapping)| {
+                let pypi_name = match mapping {
+                    PypiCondaMapping::Provides(name) => Some(name.as_normalized().clone()),
+                    PypiCondaMapping::NotAPypiPackage => None,
+                };
+                (conda_name.clone(), pypi_name)
+            })
+            .collect()
+    }
+
+    /// Returns the point in time after which published packages are ignored
+    /// when solving the project.
+    pub(crate) fn exclude_newer(&self) -> Option<ExcludeNewer> {
+        self.manifest.workspace.workspace.exclude_newer
+    }
+
     /// Update the manifest with the given package specs, and upgrade the
     /// packages if possible
     ///
     /// 1. Modify the manifest with the given package specs, if no version is
-    ///    given, use `no-pin` strategy
+    ///    given, use `no-pin` strategy. R packages are added as the conda
+    ///    packages that provide them.
     /// 2. Update the lock file
     /// 3. Given packages without version restrictions will get a semver
     ///    restriction
@@ -710,6 +791,7 @@ impl Project {
         match_specs: MatchSpecs,
         pypi_deps: PypiDeps,
         source_specs: SourceSpecs,
+        r_specs: RSpecs,
         prefix_update_config: &PrefixUpdateConfig,
         feature_name: &FeatureName,
         platforms: &[Platform],
@@ -718,8 +800,8 @@ impl Project {
     ) -> Result<Option<UpdateDeps>, miette::Error> {
         let mut conda_specs_to_add_constraints_for = IndexMap::new();
         let mut pypi_specs_to_add_constraints_for = IndexMap::new();
-        let mut conda_packages = HashSet::new();
-        let mut pypi_packages = HashSet::new();
+        let mut conda_packages = HashMap::new();
+        let mut pypi_packages = HashMap::new();
         let channel_config = self.channel_config();
         for (name, (spec, spec_type)) in match_specs {
             let (_, nameless_spec) = spec.into_nameless();
@@ -736,10 +818,43 @@ impl Project {
             )?;
             if added {
                 if nameless_spec.version.is_none() {
-                    conda_specs_to_add_constraints_for
-                        .insert(name.clone(), (spec_type, nameless_spec));
+                    conda_specs_to_add_constraints_for.insert(
+                        name.clone(),
+                        (
+                            CondaSpecTable::Dependencies(spec_type),
+                            nameless_spec.clone(),
+                        ),
+                    );
+                }
+                conda_packages.insert(name, nameless_spec);
+            }
+        }
+
+        for (r_name, nameless_spec) in r_specs {
+            let name = self
+                .manifest
+                .workspace
+                .r_mapping
+                .conda_name(&r_name)
+                .into_diagnostic()?;
+            let pixi_spec =
+                PixiSpec::from_nameless_matchspec(nameless_spec.clone(), &channel_config);
+
+            let added = self.manifest.add_r_dependency(
+                &r_name,
+                &pixi_spec,
+                platforms,
+                feature_name,
+                DependencyOverwriteBehavior::Overwrite,
+            )?;
+            if added {
+                if nameless_spec.version.is_none() {
+                    conda_specs_to_add_constraints_for.insert(
+                        name.clone(),
+                        (CondaSpecTable::RDependencies(r_name), nameless_spec.clone()),
+                    );
                 }
-                conda_packages.insert(name);
+                conda_packages.insert(name, nameless_spec);
             }
         }
 
@@ -766,10 +881,10 @@ impl Project {
                 &location,
             )?;
             if added {
+                pypi_packages.insert(name.as_normalized().clone(), spec.clone());
                 if spec.version_or_url.is_none() {
                     pypi_specs_to_add_constraints_for.insert(name.clone(), (spec, location));
                 }
-                pypi_packages.insert(name.as_normalized().clone());
             }
         }
 
@@ -904,20 +1019,31 @@ impl Project {
 
     /// Constructs a new lock-file where some of the constraints have been
     /// removed.
+    ///
+    /// Packages that are already locked at a version that satisfies the added
+    /// requirement (e.g. because they were a transitive dependency) are kept.
+    /// If nothing else changed, the lock-file is then still up-to-date and the
+    /// environment doesn't have to be solved again.
     fn unlock_packages(
         &self,
         lock_file: &LockFile,
-        conda_packages: HashSet<PackageName>,
-        pypi_packages: HashSet<pep508_rs::PackageName>,
+        conda_packages: HashMap<PackageName, NamelessMatchSpec>,
+        pypi_packages: HashMap<pep508_rs::PackageName, Requirement>,
         affected_environments: HashSet<(&str, Platform)>,
     ) -> LockFile {
         filter_lock_file(self, lock_file, |env, platform, package| {
             if affected_environments.contains(&(env.name().as_str(), platform)) {
                 match package {
-                    LockedPackageRef::Conda(package) => {
-                        !conda_packages.contains(&package.record().name)
-                    }
-                    LockedPackageRef::Pypi(package, _env) => !pypi_packages.contains(&package.name),
+                    // A spec with a channel could select a package from another channel,
+                    // so the package is always unlocked.
+                    LockedPackageRef::Conda(package) => conda_packages
+                        .get(&package.record().name)
+                        .map_or(true, |spec| {
+                            spec.channel.is_none() && spec.matches(package.record())
+                        }),
+                    LockedPackageRef::Pypi(package, _env) => pypi_packages
+                        .get(&package.name)
+                        .map_or(true, |requirement| package.satisfies(requirement)),
                 }
             } else {
                 true
@@ -930,7 +1056,10 @@ impl Project {
     fn update_conda_specs_from_lock_file(
         &mut self,
         updated_lock_file: &LockFile,
-        conda_specs_to_add_constraints_for: IndexMap<PackageName, (SpecType, NamelessMatchSpec)>,
+        conda_specs_to_add_constraints_for: IndexMap<
+            PackageName,
+            (CondaSpecTable, NamelessMatchSpec),
+        >,
         affect_environment_and_platforms: Vec<(String, Platform)>,
         feature_name: &FeatureName,
         platforms: &[Platform],
@@ -950,33 +1079,20 @@ impl Project {
             .collect_vec();
 
         let channel_config = self.channel_config();
-        for (name, (spec_type, spec)) in conda_specs_to_add_constraints_for {
-            let mut pinning_strategy = self.config().pinning_strategy;
-
-            // Edge case: some packages are a special case where we want to pin the minor
-            // version by default. This is done to avoid early user confusion
-            // when the minor version changes and environments magically start breaking.
-            // This move a `>=3.13, <4` to a `>=3.13, <3.14` constraint.
-            if NON_SEMVER_PACKAGES.contains(&name.as_normalized()) && pinning_strategy.is_none() {
-                tracing::info!(
-                    "Pinning {} to minor version by default",
-                    name.as_normalized()
-                );
-                pinning_strategy = Some(PinningStrategy::Minor);
-            }
-            let version_constraint = pinning_strategy
-                .unwrap_or_default()
-                .determine_version_constraint(conda_records.iter().filter_map(|record| {
+        for (name, (table, spec)) in conda_specs_to_add_constraints_for {
+            let version_constraint = self.conda_version_constraint(
+                &name,
+                conda_records.iter().filter_map(|record| {
                     if record.package_record.name == name {
                         Some(record.package_record.version.version())
                     } else {
                         None
                     }
-                }));
+                }),
+            );
 
             if let Some(version_constraint) = version_constraint {
-                implicit_constraints
-                    .insert(name.as_source().to_string(), version_constraint.to_string());
+                let constraint = version_constraint.to_string();
                 let spec = NamelessMatchSpec {
                     version: Some(version_constraint),
                     ..spec
@@ -984,20 +1100,60 @@ impl Project {
 
                 let pixi_spec = PixiSpec::from_nameless_matchspec(spec.clone(), &channel_config);
 
-                self.manifest.add_dependency(
-                    &name,
-                    &pixi_spec,
-                    spec_type,
-                    platforms,
-                    feature_name,
-                    DependencyOverwriteBehavior::Overwrite,
-                )?;
+                match table {
+                    CondaSpecTable::Dependencies(spec_type) => {
+                        implicit_constraints.insert(name.as_source().to_string(), constraint);
+                        self.manifest.add_dependency(
+                            &name,
+                            &pixi_spec,
+                            spec_type,
+                            platforms,
+                            feature_name,
+                            DependencyOverwriteBehavior::Overwrite,
+                        )?;
+                    }
+                    CondaSpecTable::RDependencies(r_name) => {
+                        self.manifest.add_r_dependency(
+                            &r_name,
+                            &pixi_spec,
+                            platforms,
+                            feature_name,
+                            DependencyOverwriteBehavior::Overwrite,
+                        )?;
+                        implicit_constraints.insert(r_name, constraint);
+                    }
+                }
             }
         }
 
         Ok(implicit_constraints)
     }
 
+    /// Determines the version constraint to add to the manifest for a conda
+    /// package based on the configured pinning strategy.
+    pub(crate) fn conda_version_constraint<'a>(
+        &self,
+        name: &PackageName,
+        versions: impl IntoIterator<Item = &'a Version> + Clone,
+    ) -> Option<VersionSpec> {
+        let mut pinning_strategy = self.config().pinning_strategy;
+
+        // Edge case: some packages are a special case where we want to pin the minor
+        // version by default. This is done to avoid early user confusion
+        // when the minor version changes and environments magically start breaking.
+        // This move a `>=3.13, <4` to a `>=3.13, <3.14` constraint.
+        if NON_SEMVER_PACKAGES.contains(&name.as_normalized()) && pinning_strategy.is_none() {
+            tracing::info!(
+                "Pinning {} to minor version by default",
+                name.as_normalized()
+            );
+            pinning_strategy = Some(PinningStrategy::Minor);
+        }
+        pinning_strategy
+            .unwrap_or_default()
+            .determine_version_constraint(versions)
+    }
+
     /// Update the pypi specs of newly added packages based on the contents of
     /// the updated lock-file.
     fn update_pypi_specs_from_lock_file(
@@ -1192,7 +1348,7 @@ mod tests {
     use insta::{assert_debug_snapshot, assert_snapshot};
     use itertools::Itertools;
     use pixi_manifest::FeatureName;
-    use rattler_conda_types::Platform;
+    use rattler_conda_types::{PackageUrl, Platform, RepoDataRecord};
     use rattler_virtual_packages::{LibC, VirtualPackage};
     use tempfile::tempdir;
 
@@ -1515,6 +1671,54 @@ mod tests {
         insta::assert_snapshot!(project.pypi_name_mapping_source().unwrap_err());
     }
 
+    #[test]
+    fn test_pypi_conda_mapping() {
+        let file_contents = r#"
+            [project]
+            name = "foo"
+            channels = ["conda-forge"]
+            platforms = []
+
+            [pypi-conda-mapping]
+            pytorch = "torch"
+            python-graphviz = false
+            "#;
+        let manifest = Manifest::from_str(Path::new("pixi.toml"), file_contents).unwrap();
+        let project = Project::from_manifest(manifest);
+
+        let mut pytorch = crate::lock_file::test_utils::record("pytorch", "2.5.1");
+        let mut graphviz = crate::lock_file::test_utils::record("python-graphviz", "0.20.3");
+        graphviz.package_record.purls =
+            Some([PackageUrl::from_str("pkg:pypi/graphviz").unwrap()].into());
+        let mut numpy = crate::lock_file::test_utils::record("numpy", "2.1.0");
+
+        let not_overridden = pypi_mapping::apply_mapping_overrides(
+            &project.pypi_conda_mapping(),
+            [&mut pytorch, &mut graphviz, &mut numpy],
+        );
+        assert_eq!(
+            not_overridden
+                .iter()
+                .map(|record| record.package_record.name.as_normalized())
+                .collect_vec(),
+            ["numpy"]
+        );
+
+        // The overridden packages only provide the PyPI package of the mapping
+        let pypi_names = |record: &RepoDataRecord| {
+            record
+                .package_record
+                .purls
+                .iter()
+                .flatten()
+                .filter(|purl| purl.package_type() == "pypi")
+                .map(|purl| purl.name().to_string())
+                .collect_vec()
+        };
+        assert_eq!(pypi_names(&pytorch), ["torch"]);
+        assert!(pypi_names(&graphviz).is_empty());
+    }
+
     #[test]
     fn test_find_project_manifest_in_current_dir() {
         for manifest in &[consts::PROJECT_MANIFEST, consts::PYPROJECT_MANIFEST] {
@@ -0,0 +1,420 @@
+//! Verifies the files of the conda packages that are installed in a prefix
+//! against their `conda-meta` records, so an environment that was damaged on
+//! disk can be repaired without removing it entirely.
+
+use std::{
+    collections::HashMap,
+    fmt::{Display, Formatter},
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use futures::{stream, StreamExt, TryStreamExt};
+use itertools::Itertools;
+use miette::{Context, IntoDiagnostic};
+use rattler_conda_types::{PackageName, PackageRecord, PrefixRecord};
+use rattler_digest::{Sha256, Sha256Hash};
+use rattler_lock::LockFile;
+
+use crate::{prefix::Prefix, project::Environment};
+
+/// The extension that is added to the `conda-meta` records of damaged packages
+/// while they are reinstalled.
+const SET_ASIDE_EXTENSION: &str = "repair";
+
+/// A problem with a file of an installed package.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Damage {
+    /// The file doesn't exist.
+    Missing(PathBuf),
+    /// The contents of the file differ from the contents that were linked.
+    Modified(PathBuf),
+}
+
+impl Display for Damage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Damage::Missing(path) => write!(f, "missing '{}'", path.display()),
+            Damage::Modified(path) => write!(f, "modified '{}'", path.display()),
+        }
+    }
+}
+
+/// An installed package with files that are missing or modified.
+#[derive(Debug)]
+pub(crate) struct DamagedPackage {
+    pub name: PackageName,
+    pub damage: Vec<Damage>,
+}
+
+/// The damaged packages of a prefix, of which the `conda-meta` records are set
+/// aside so the next installation of the environment installs them again.
+#[derive(Debug)]
+pub(crate) struct PrefixRepair {
+    pub damaged: Vec<DamagedPackage>,
+    /// The paths of the `conda-meta` records that were set aside.
+    set_aside: Vec<PathBuf>,
+}
+
+impl PrefixRepair {
+    /// Completes the repair after the environment was installed, also if the
+    /// installation failed. The records of the packages that were installed
+    /// again are discarded, the other records are restored so the files of
+    /// their packages stay tracked.
+    pub(crate) fn finish(self) -> miette::Result<()> {
+        for path in self.set_aside {
+            let set_aside = set_aside_path(&path);
+            if path.exists() {
+                fs_err::remove_file(&set_aside).into_diagnostic()?;
+            } else {
+                fs_err::rename(&set_aside, &path).into_diagnostic()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Scans the conda packages in the prefix of the environment and sets the
+/// `conda-meta` records of the damaged packages aside, so the next
+/// installation of the environment installs these packages again. Call
+/// [`PrefixRepair::finish`] after that installation.
+///
+/// Only the packages that match the lock-file are scanned, the other packages
+/// are replaced by the installation anyway. The lock-file should therefore be
+/// up-to-date.
+pub(crate) async fn repair_prefix(
+    environment: &Environment<'_>,
+    prefix: &Prefix,
+    lock_file: &LockFile,
+) -> miette::Result<PrefixRepair> {
+    let locked_environment = lock_file.environment(environment.name().as_str());
+    let locked: HashMap<&PackageName, &PackageRecord> = locked_environment
+        .as_ref()
+        .and_then(|env| env.conda_packages(environment.best_platform()))
+        .into_iter()
+        .flatten()
+        .filter_map(|package| package.as_binary())
+        .map(|binary| (&binary.package_record.name, &binary.package_record))
+        .collect();
+
+    repair_locked_packages(
+        prefix,
+        &locked,
+        environment.project().config().max_concurrent_installs(),
+    )
+    .await
+}
+
+/// Sets the `conda-meta` records of the damaged packages aside, of the
+/// installed packages that match the locked packages.
+async fn repair_locked_packages(
+    prefix: &Prefix,
+    locked: &HashMap<&PackageName, &PackageRecord>,
+    concurrency: usize,
+) -> miette::Result<PrefixRepair> {
+    if !prefix.root().join("conda-meta").is_dir() {
+        return Ok(PrefixRepair {
+            damaged: Vec::new(),
+            set_aside: Vec::new(),
+        });
+    }
+
+    let installed = prefix.find_installed_packages()?;
+    let damaged = find_damaged_packages(
+        prefix.root(),
+        installed,
+        |record| {
+            let installed = &record.repodata_record.package_record;
+            locked.get(&installed.name).is_some_and(|locked| {
+                locked.sha256 == installed.sha256 && locked.md5 == installed.md5
+            })
+        },
+        concurrency,
+    )
+    .await?;
+
+    let mut set_aside = Vec::new();
+    for (record, _) in &damaged {
+        let path = prefix
+            .root()
+            .join("conda-meta")
+            .join(conda_meta_file_name(record));
+        fs_err::rename(&path, set_aside_path(&path)).into_diagnostic()?;
+        set_aside.push(path);
+    }
+
+    Ok(PrefixRepair {
+        damaged: damaged
+            .into_iter()
+            .map(|(record, damage)| DamagedPackage {
+                name: record.repodata_record.package_record.name,
+                damage,
+            })
+            .collect(),
+        set_aside,
+    })
+}
+
+/// The path a `conda-meta` record is moved to while its package is
+/// reinstalled.
+fn set_aside_path(path: &Path) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".");
+    file_name.push(SET_ASIDE_EXTENSION);
+    path.with_file_name(file_name)
+}
+
+/// Returns the installed packages, for which `filter` returns true, that have
+/// files that are missing or modified, together with the problems.
+///
+/// A file that is part of multiple packages is only modified if it matches
+/// none of these packages, one of them has clobbered the others.
+async fn find_damaged_packages(
+    prefix: &Path,
+    installed: Vec<PrefixRecord>,
+    filter: impl Fn(&PrefixRecord) -> bool,
+    concurrency: usize,
+) -> miette::Result<Vec<(PrefixRecord, Vec<Damage>)>> {
+    // The hashes that each file may have.
+    let mut expected_hashes: HashMap<PathBuf, Vec<Sha256Hash>> = HashMap::new();
+    for entry in installed.iter().flat_map(|record| &record.paths_data.paths) {
+        if let Some(sha256) = entry.sha256_in_prefix.or(entry.sha256) {
+            expected_hashes
+                .entry(entry.relative_path.clone())
+                .or_default()
+                .push(sha256);
+        }
+    }
+    let expected_hashes = Arc::new(expected_hashes);
+
+    let damaged = stream::iter(installed.into_iter().filter(|record| filter(record)))
+        .map(|record| {
+            let prefix = prefix.to_path_buf();
+            let expected_hashes = expected_hashes.clone();
+            tokio::task::spawn_blocking(move || {
+                let damage = find_damage(&prefix, &record, &expected_hashes);
+                (record, damage)
+            })
+        })
+        .buffer_unordered(concurrency)
+        .try_collect::<Vec<_>>()
+        .await
+        .into_diagnostic()
+        .context("failed to scan the prefix")?;
+
+    Ok(damaged
+        .into_iter()
+        .filter(|(_, damage)| !damage.is_empty())
+        .sorted_by(|(a, _), (b, _)| {
+            a.repodata_record
+                .package_record
+                .name
+                .cmp(&b.repodata_record.package_record.name)
+        })
+        .collect())
+}
+
+/// Checks the files of a single package. Links are only checked for
+/// existence, the files they point to are checked by their own package.
+fn find_damage(
+    prefix: &Path,
+    record: &PrefixRecord,
+    expected_hashes: &HashMap<PathBuf, Vec<Sha256Hash>>,
+) -> Vec<Damage> {
+    let mut damage = Vec::new();
+    for entry in &record.paths_data.paths {
+        let path = prefix.join(&entry.relative_path);
+        let metadata = match fs_err::symlink_metadata(&path) {
+            Ok(metadata) => metadata,
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                damage.push(Damage::Missing(entry.relative_path.clone()));
+                continue;
+            }
+            Err(err) => {
+                tracing::debug!("failed to read the metadata of {}: {err}", path.display());
+                continue;
+            }
+        };
+        if !metadata.is_file() {
+            continue;
+        }
+        let Some(expected) = expected_hashes.get(&entry.relative_path) else {
+            continue;
+        };
+        match rattler_digest::compute_file_digest::<Sha256>(&path) {
+            Ok(actual) if expected.contains(&actual) => {}
+            Ok(_) => damage.push(Damage::Modified(entry.relative_path.clone())),
+            Err(err) => {
+                tracing::debug!("failed to compute the sha256 of {}: {err}", path.display());
+                damage.push(Damage::Modified(entry.relative_path.clone()));
+            }
+        }
+    }
+    damage
+}
+
+/// The name of the file of the record in the `conda-meta` directory.
+fn conda_meta_file_name(record: &PrefixRecord) -> String {
+    let package_record = &record.repodata_record.package_record;
+    format!(
+        "{}-{}-{}.json",
+        package_record.name.as_normalized(),
+        package_record.version,
+        package_record.build
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prefix_record(name: &str, files: &[(&str, &[u8])]) -> PrefixRecord {
+        let file_name = format!("{name}-1.0-0.conda");
+        let paths = files
+            .iter()
+            .map(|(path, contents)| {
+                serde_json::json!({
+                    "_path": path,
+                    "path_type": "hardlink",
+                    "sha256": format!("{:x}", rattler_digest::compute_bytes_digest::<Sha256>(contents)),
+                    "size_in_bytes": contents.len(),
+                })
+            })
+            .collect_vec();
+        serde_json::from_value(serde_json::json!({
+            "name": name,
+            "version": "1.0",
+            "build": "0",
+            "build_number": 0,
+            "subdir": "noarch",
+            "fn": file_name,
+            "url": format!("https://conda.anaconda.org/conda-forge/noarch/{file_name}"),
+            "channel": "https://conda.anaconda.org/conda-forge",
+            "files": files.iter().map(|(path, _)| path).collect_vec(),
+            "paths_data": { "paths_version": 1, "paths": paths },
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_find_damaged_packages() {
+        let prefix = tempfile::tempdir().unwrap();
+        fs_err::create_dir_all(prefix.path().join("bin")).unwrap();
+        fs_err::write(prefix.path().join("bin/foo"), b"foo").unwrap();
+        fs_err::write(prefix.path().join("bin/bar"), b"changed").unwrap();
+        // Both packages provide `share-data`, `foo` clobbered `bar`.
+        fs_err::write(prefix.path().join("share-data"), b"foo").unwrap();
+
+        let installed = vec![
+            prefix_record("foo", &[("bin/foo", b"foo"), ("share-data", b"foo")]),
+            prefix_record(
+                "bar",
+                &[
+                    ("bin/bar", b"bar"),
+                    ("bin/baz", b"baz"),
+                    ("share-data", b"bar"),
+                ],
+            ),
+        ];
+        let damaged = find_damaged_packages(prefix.path(), installed.clone(), |_| true, 2)
+            .await
+            .unwrap();
+        assert_eq!(damaged.len(), 1);
+        assert_eq!(
+            damaged[0]
+                .0
+                .repodata_record
+                .package_record
+                .name
+                .as_normalized(),
+            "bar"
+        );
+        assert_eq!(
+            damaged[0].1,
+            vec![
+                Damage::Modified(PathBuf::from("bin/bar")),
+                Damage::Missing(PathBuf::from("bin/baz")),
+            ]
+        );
+
+        // Packages that are filtered out are not scanned.
+        let damaged = find_damaged_packages(
+            prefix.path(),
+            installed,
+            |record| record.repodata_record.package_record.name.as_normalized() == "foo",
+            2,
+        )
+        .await
+        .unwrap();
+        assert!(damaged.is_empty());
+    }
+
+    fn write_record(prefix: &Path, record: &PrefixRecord) {
+        fs_err::write(
+            prefix.join("conda-meta").join(conda_meta_file_name(record)),
+            serde_json::to_string(record).unwrap(),
+        )
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_repair_locked_packages() {
+        let dir = tempfile::tempdir().unwrap();
+        fs_err::create_dir_all(dir.path().join("conda-meta")).unwrap();
+        let prefix = Prefix::new(dir.path());
+        let foo = prefix_record("foo", &[("bin/foo", b"foo")]);
+        let bar = prefix_record("bar", &[("bin/bar", b"bar")]);
+        let baz = prefix_record("baz", &[("bin/baz", b"baz")]);
+        for record in [&foo, &bar, &baz] {
+            write_record(dir.path(), record);
+        }
+
+        // `foo` is locked, `bar` is locked with another build and `baz` isn't
+        // locked, all of them are missing their files.
+        let mut other_bar = bar.repodata_record.package_record.clone();
+        other_bar.sha256 = Some(rattler_digest::compute_bytes_digest::<Sha256>(b"other"));
+        let locked = HashMap::from([
+            (
+                &foo.repodata_record.package_record.name,
+                &foo.repodata_record.package_record,
+            ),
+            (&bar.repodata_record.package_record.name, &other_bar),
+        ]);
+
+        let repair = repair_locked_packages(&prefix, &locked, 2).await.unwrap();
+        let damaged = repair
+            .damaged
+            .iter()
+            .map(|package| package.name.as_normalized())
+            .collect_vec();
+        assert_eq!(damaged, ["foo"]);
+        let installed = |prefix: &Prefix| {
+            prefix
+                .find_installed_packages()
+                .unwrap()
+                .into_iter()
+                .map(|record| record.repodata_record.package_record.name)
+                .map(|name| name.as_normalized().to_string())
+                .sorted()
+                .collect_vec()
+        };
+        assert_eq!(installed(&prefix), ["bar", "baz"]);
+
+        // The installation failed, the record of `foo` is restored.
+        repair.finish().unwrap();
+        assert_eq!(installed(&prefix), ["bar", "baz", "foo"]);
+
+        // The installation installed `foo` again, its old record is discarded.
+        let repair = repair_locked_packages(&prefix, &locked, 2).await.unwrap();
+        write_record(dir.path(), &foo);
+        repair.finish().unwrap();
+        assert_eq!(installed(&prefix), ["bar", "baz", "foo"]);
+        assert!(!set_aside_path(
+            &dir.path()
+                .join("conda-meta")
+                .join(conda_meta_file_name(&foo))
+        )
+        .exists());
+    }
+}
@@ -0,0 +1,638 @@
+//! Signs built conda packages with minisign keys, and verifies the signatures
+//! of the packages of trusted channels before they are installed.
+//!
+//! The signatures use the format of [minisign](https://jedisct1.github.io/minisign/),
+//! so they can also be created and verified with the `minisign` tool. A
+//! signature is stored next to its package, with the `.minisig` extension.
+
+use std::{
+    fmt::{self, Display, Formatter},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::SystemTime,
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use blake2::{Blake2b512, Digest};
+use futures::{stream, StreamExt, TryStreamExt};
+use itertools::Itertools;
+use miette::{Context, Diagnostic, IntoDiagnostic};
+use pixi_config::TrustConfig;
+use rattler::{install::Transaction, package_cache::PackageCache};
+use rattler_conda_types::{Platform, PrefixRecord, RepoDataRecord};
+use rattler_digest::Blake2b256;
+use rattler_package_streaming::ExtractError;
+use reqwest_middleware::ClientWithMiddleware;
+use ring::signature::{Ed25519KeyPair, KeyPair, UnparsedPublicKey, ED25519};
+use thiserror::Error;
+use tokio::io::AsyncWriteExt;
+use url::Url;
+
+use crate::verify_hashes::{package_dirs_match, CachedPackageMismatchError};
+
+/// The extension of the signature file of a package.
+pub(crate) const SIGNATURE_EXTENSION: &str = "minisig";
+
+/// The algorithm of the keys, and of signatures over the complete message.
+const ED25519_ALGORITHM: &[u8; 2] = b"Ed";
+
+/// The algorithm of signatures over the blake2b-512 hash of the message.
+const PREHASHED_ALGORITHM: &[u8; 2] = b"ED";
+
+/// The prefix of the comment lines that are not covered by the signature.
+const UNTRUSTED_COMMENT: &str = "untrusted comment: ";
+
+/// The prefix of the comment line that is covered by the signature.
+const TRUSTED_COMMENT: &str = "trusted comment: ";
+
+/// The maximum number of conda packages that are downloaded at the same time
+/// to verify them.
+const MAX_CONCURRENT_DOWNLOADS: usize = 50;
+
+#[derive(Debug, Error, Diagnostic)]
+pub(crate) enum SigningError {
+    #[error("failed to read the secret key '{}'", .0.display())]
+    ReadKey(PathBuf, #[source] std::io::Error),
+
+    #[error("invalid minisign key, {0}")]
+    InvalidKey(String),
+
+    #[error("encrypted minisign secret keys are not supported")]
+    #[diagnostic(help("create a key without a password with `minisign -G -W`"))]
+    EncryptedKey,
+
+    #[error("invalid minisign signature, {0}")]
+    InvalidSignature(String),
+
+    #[error("the package is signed with the key {0}, which is not a trusted key")]
+    #[diagnostic(help("add the public key to the `trust.public-keys` configuration"))]
+    UntrustedKey(KeyId),
+
+    #[error("the signature doesn't match the package")]
+    Mismatch,
+
+    #[error("failed to read '{}'", .0.display())]
+    Io(PathBuf, #[source] std::io::Error),
+}
+
+/// The id of a minisign key, which identifies the key that made a signature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct KeyId([u8; 8]);
+
+impl Display for KeyId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016X}", u64::from_le_bytes(self.0))
+    }
+}
+
+/// A minisign public key, which verifies the signatures of packages.
+#[derive(Debug, Clone)]
+pub(crate) struct PublicKey {
+    key_id: KeyId,
+    key: [u8; 32],
+}
+
+impl FromStr for PublicKey {
+    type Err = SigningError;
+
+    /// Parses the base64 encoded key, which is the last line of a minisign
+    /// public key file.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = decode_base64(s.trim()).map_err(SigningError::InvalidKey)?;
+        if bytes.len() != 42 || &bytes[..2] != ED25519_ALGORITHM {
+            return Err(SigningError::InvalidKey(
+                "expected an Ed25519 public key".to_string(),
+            ));
+        }
+        Ok(Self {
+            key_id: KeyId(bytes[2..10].try_into().unwrap()),
+            key: bytes[10..42].try_into().unwrap(),
+        })
+    }
+}
+
+/// A minisign secret key, which signs built packages.
+pub(crate) struct SecretKey {
+    key_id: KeyId,
+    key_pair: Ed25519KeyPair,
+}
+
+impl SecretKey {
+    /// Reads an unencrypted minisign secret key file.
+    pub fn from_path(path: &Path) -> Result<Self, SigningError> {
+        let contents =
+            fs_err::read_to_string(path).map_err(|e| SigningError::ReadKey(path.into(), e))?;
+        let line = contents
+            .lines()
+            .find(|line| !line.is_empty() && !line.starts_with(UNTRUSTED_COMMENT))
+            .ok_or_else(|| SigningError::InvalidKey("the key file is empty".to_string()))?;
+        let bytes = decode_base64(line).map_err(SigningError::InvalidKey)?;
+
+        // The key consists of the algorithms, the parameters of the key
+        // derivation function, the key id, the key and its checksum.
+        if bytes.len() != 158 || &bytes[..2] != ED25519_ALGORITHM || &bytes[4..6] != b"B2" {
+            return Err(SigningError::InvalidKey(
+                "expected an Ed25519 secret key".to_string(),
+            ));
+        }
+        if bytes[2..4] != [0, 0] {
+            return Err(SigningError::EncryptedKey);
+        }
+        let key_id = &bytes[54..62];
+        let key = &bytes[62..126];
+        let checksum = Blake2b256::new()
+            .chain_update(ED25519_ALGORITHM)
+            .chain_update(key_id)
+            .chain_update(key)
+            .finalize();
+        if checksum.as_slice() != &bytes[126..158] {
+            return Err(SigningError::InvalidKey(
+                "the checksum of the key doesn't match".to_string(),
+            ));
+        }
+
+        let key_pair = Ed25519KeyPair::from_seed_and_public_key(&key[..32], &key[32..])
+            .map_err(|e| SigningError::InvalidKey(e.to_string()))?;
+        Ok(Self {
+            key_id: KeyId(key_id.try_into().unwrap()),
+            key_pair,
+        })
+    }
+
+    /// Returns the public key that verifies the signatures of this key.
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey {
+            key_id: self.key_id,
+            key: self.key_pair.public_key().as_ref().try_into().unwrap(),
+        }
+    }
+}
+
+/// Returns the path of the signature of a package.
+pub(crate) fn signature_path(package_file: &Path) -> PathBuf {
+    let mut path = package_file.as_os_str().to_owned();
+    path.push(".");
+    path.push(SIGNATURE_EXTENSION);
+    PathBuf::from(path)
+}
+
+/// Signs the package and writes the signature next to it. Returns the path of
+/// the signature.
+pub(crate) fn sign_package(
+    secret_key: &SecretKey,
+    package_file: &Path,
+) -> Result<PathBuf, SigningError> {
+    let hash = hash_file(package_file)?;
+    let signature = secret_key.key_pair.sign(&hash);
+
+    let timestamp = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let file_name = package_file
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+    let trusted_comment = format!("timestamp:{timestamp}\tfile:{file_name}\thashed");
+    let global_signature = secret_key
+        .key_pair
+        .sign(&[signature.as_ref(), trusted_comment.as_bytes()].concat());
+
+    let contents = format!(
+        "{UNTRUSTED_COMMENT}signature from minisign secret key {}\n{}\n{TRUSTED_COMMENT}{trusted_comment}\n{}\n",
+        secret_key.key_id,
+        STANDARD.encode(
+            [
+                PREHASHED_ALGORITHM.as_slice(),
+                &secret_key.key_id.0,
+                signature.as_ref()
+            ]
+            .concat()
+        ),
+        STANDARD.encode(global_signature.as_ref()),
+    );
+
+    let path = signature_path(package_file);
+    fs_err::write(&path, contents).map_err(|e| SigningError::Io(path.clone(), e))?;
+    Ok(path)
+}
+
+/// Verifies that the signature of the package is made by one of the public
+/// keys.
+pub(crate) fn verify_package(
+    public_keys: &[PublicKey],
+    package_file: &Path,
+    signature: &str,
+) -> Result<(), SigningError> {
+    let lines = signature.lines().collect_vec();
+    let [untrusted_comment, signature, trusted_comment, global_signature, ..] = lines.as_slice()
+    else {
+        return Err(SigningError::InvalidSignature(
+            "expected four lines".to_string(),
+        ));
+    };
+    let Some(trusted_comment) = trusted_comment.strip_prefix(TRUSTED_COMMENT) else {
+        return Err(SigningError::InvalidSignature(
+            "missing the trusted comment".to_string(),
+        ));
+    };
+    if !untrusted_comment.starts_with(UNTRUSTED_COMMENT) {
+        return Err(SigningError::InvalidSignature(
+            "missing the untrusted comment".to_string(),
+        ));
+    }
+
+    let signature = decode_base64(signature).map_err(SigningError::InvalidSignature)?;
+    if signature.len() != 74 {
+        return Err(SigningError::InvalidSignature(
+            "expected an Ed25519 signature".to_string(),
+        ));
+    }
+    if &signature[..2] != PREHASHED_ALGORITHM {
+        return Err(SigningError::InvalidSignature(
+            "only signatures of the hash of the package are supported".to_string(),
+        ));
+    }
+    let key_id = KeyId(signature[2..10].try_into().unwrap());
+    let signature = &signature[10..];
+    let global_signature =
+        decode_base64(global_signature).map_err(SigningError::InvalidSignature)?;
+
+    let public_key = public_keys
+        .iter()
+        .find(|key| key.key_id == key_id)
+        .ok_or(SigningError::UntrustedKey(key_id))?;
+    let public_key = UnparsedPublicKey::new(&ED25519, &public_key.key);
+
+    let hash = hash_file(package_file)?;
+    public_key
+        .verify(&hash, signature)
+        .map_err(|_| SigningError::Mismatch)?;
+    public_key
+        .verify(
+            &[signature, trusted_comment.as_bytes()].concat(),
+            &global_signature,
+        )
+        .map_err(|_| SigningError::Mismatch)?;
+    Ok(())
+}
+
+fn hash_file(path: &Path) -> Result<Vec<u8>, SigningError> {
+    rattler_digest::compute_file_digest::<Blake2b512>(path)
+        .map(|hash| hash.to_vec())
+        .map_err(|e| SigningError::Io(path.to_path_buf(), e))
+}
+
+fn decode_base64(value: &str) -> Result<Vec<u8>, String> {
+    STANDARD.decode(value.trim()).map_err(|e| e.to_string())
+}
+
+#[derive(Debug, Error)]
+enum FetchError {
+    #[error(transparent)]
+    Request(#[from] reqwest_middleware::Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Extract(#[from] ExtractError),
+    #[error(transparent)]
+    Signature(#[from] SigningError),
+}
+
+/// Downloads the conda packages of the trusted channels that have to be
+/// installed in the prefix to the package cache and verifies their
+/// signatures, so the installer links the verified packages. Packages that are
+/// already in the cache are not downloaded again.
+pub(crate) async fn verify_conda_signatures(
+    trust: &TrustConfig,
+    installed_packages: &[PrefixRecord],
+    records: &[RepoDataRecord],
+    platform: Platform,
+    package_cache: &PackageCache,
+    client: &ClientWithMiddleware,
+) -> miette::Result<()> {
+    if trust.channels.is_empty() {
+        return Ok(());
+    }
+    let public_keys: Arc<[PublicKey]> = trust
+        .public_keys
+        .iter()
+        .map(|key| PublicKey::from_str(key))
+        .collect::<Result<Vec<_>, _>>()
+        .wrap_err("failed to parse the `trust.public-keys` configuration")?
+        .into();
+
+    let transaction = Transaction::from_current_and_desired(
+        installed_packages.to_vec(),
+        records.to_vec(),
+        platform,
+    )
+    .into_diagnostic()?;
+    let to_verify = transaction
+        .installed_packages()
+        .filter(|record| {
+            record
+                .channel
+                .as_deref()
+                .and_then(|channel| Url::parse(channel).ok())
+                .is_some_and(|channel| trust.is_trusted_channel(&channel))
+        })
+        .collect_vec();
+
+    stream::iter(to_verify)
+        .map(|record| fetch_and_verify(package_cache, client, record, public_keys.clone()))
+        .buffer_unordered(MAX_CONCURRENT_DOWNLOADS)
+        .try_collect::<Vec<_>>()
+        .await?;
+    Ok(())
+}
+
+/// Fetches a conda package and its signature, and extracts the package into
+/// the package cache if the signature is valid.
+///
+/// A package that is already in the cache may have been put there without
+/// verifying its signature, so its signed archive is downloaded and verified
+/// as well, and the files in the cache have to match the files of the
+/// verified archive.
+async fn fetch_and_verify(
+    package_cache: &PackageCache,
+    client: &ClientWithMiddleware,
+    record: &RepoDataRecord,
+    public_keys: Arc<[PublicKey]>,
+) -> miette::Result<()> {
+    let fetched = Arc::new(AtomicBool::new(false));
+    let cache_lock = package_cache
+        .get_or_fetch(
+            &record.package_record,
+            {
+                let url = record.url.clone();
+                let client = client.clone();
+                let public_keys = public_keys.clone();
+                let fetched = fetched.clone();
+                move |destination| {
+                    let url = url.clone();
+                    let client = client.clone();
+                    let public_keys = public_keys.clone();
+                    fetched.store(true, Ordering::Relaxed);
+                    async move { extract_signed(&client, &url, public_keys, destination).await }
+                }
+            },
+            None,
+        )
+        .await
+        .into_diagnostic()
+        .wrap_err_with(|| format!("failed to verify the signature of '{}'", record.file_name))?;
+
+    if !fetched.load(Ordering::Relaxed) {
+        let verified = tempfile::tempdir().into_diagnostic()?;
+        extract_signed(
+            client,
+            &record.url,
+            public_keys,
+            verified.path().to_path_buf(),
+        )
+        .await
+        .into_diagnostic()
+        .wrap_err_with(|| format!("failed to verify the signature of '{}'", record.file_name))?;
+        let cached = cache_lock.path().to_path_buf();
+        let verified = verified.path().to_path_buf();
+        let matches = tokio::task::spawn_blocking(move || package_dirs_match(&verified, &cached))
+            .await
+            .into_diagnostic()??;
+        if !matches {
+            return Err(CachedPackageMismatchError {
+                file_name: record.file_name.clone(),
+                path: cache_lock.path().to_path_buf(),
+            }
+            .into());
+        }
+    }
+    Ok(())
+}
+
+/// Downloads a conda package and its signature, and extracts the package into
+/// the destination if the signature is valid.
+async fn extract_signed(
+    client: &ClientWithMiddleware,
+    url: &Url,
+    public_keys: Arc<[PublicKey]>,
+    destination: PathBuf,
+) -> Result<(), FetchError> {
+    let signature_url = Url::parse(&format!("{url}.{SIGNATURE_EXTENSION}"))
+        .expect("appending an extension keeps the url valid");
+
+    // The archive keeps its file name, so its format can be derived from the
+    // extension when it is extracted.
+    let download_dir = tempfile::tempdir()?;
+    let archive = download_dir
+        .path()
+        .join(url.path().rsplit('/').next().unwrap_or_default());
+    let signature_file = signature_path(&archive);
+    download(client, &signature_url, &signature_file).await?;
+    download(client, url, &archive).await?;
+
+    tokio::task::spawn_blocking(move || {
+        let signature = String::from_utf8_lossy(&fs_err::read(&signature_file)?).into_owned();
+        verify_package(&public_keys, &archive, &signature)?;
+        rattler_package_streaming::fs::extract(&archive, &destination)?;
+        drop(download_dir);
+        Ok::<_, FetchError>(())
+    })
+    .await
+    .map_err(std::io::Error::other)??;
+    Ok(())
+}
+
+/// Downloads the contents of a url, which is either a local file or a remote
+/// file, to the destination. Remote files are streamed to disk, so packages are
+/// never kept in memory as a whole.
+async fn download(
+    client: &ClientWithMiddleware,
+    url: &Url,
+    destination: &Path,
+) -> Result<(), FetchError> {
+    if let Ok(path) = url.to_file_path() {
+        fs_err::tokio::copy(path, destination).await?;
+        return Ok(());
+    }
+    let mut response = client
+        .get(url.clone())
+        .send()
+        .await?
+        .error_for_status()
+        .map_err(reqwest_middleware::Error::Reqwest)?;
+    let mut file = fs_err::tokio::File::create(destination).await?;
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(reqwest_middleware::Error::Reqwest)?
+    {
+        file.write_all(&chunk).await?;
+    }
+    file.flush().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use rattler_conda_types::RepoData;
+
+    use super::*;
+
+    /// Writes an unencrypted secret key in the format of `minisign -G -W` and
+    /// reads it back.
+    fn secret_key(dir: &Path) -> SecretKey {
+        let seed = [7u8; 32];
+        let key_pair = Ed25519KeyPair::from_seed_unchecked(&seed).unwrap();
+        let key_id = [1, 2, 3, 4, 5, 6, 7, 8];
+        let key = [seed.as_slice(), key_pair.public_key().as_ref()].concat();
+        let checksum = Blake2b256::new()
+            .chain_update(ED25519_ALGORITHM)
+            .chain_update(key_id)
+            .chain_update(&key)
+            .finalize();
+        let bytes = [
+            ED25519_ALGORITHM.as_slice(),
+            &[0, 0],
+            b"B2",
+            &[0; 48],
+            &key_id,
+            &key,
+            checksum.as_slice(),
+        ]
+        .concat();
+
+        let path = dir.join("pixi.key");
+        fs_err::write(
+            &path,
+            format!(
+                "{UNTRUSTED_COMMENT}minisign secret key\n{}\n",
+                STANDARD.encode(bytes)
+            ),
+        )
+        .unwrap();
+        SecretKey::from_path(&path).unwrap()
+    }
+
+    #[test]
+    fn test_sign_and_verify_package() {
+        let dir = tempfile::tempdir().unwrap();
+        let secret_key = secret_key(dir.path());
+        let package_file = dir.path().join("foo-1.0-0.conda");
+        fs_err::write(&package_file, "package contents").unwrap();
+
+        let signature_file = sign_package(&secret_key, &package_file).unwrap();
+        assert_eq!(signature_file, dir.path().join("foo-1.0-0.conda.minisig"));
+        let signature = fs_err::read_to_string(&signature_file).unwrap();
+        verify_package(&[secret_key.public_key()], &package_file, &signature).unwrap();
+
+        // A public key round-trips through its base64 representation.
+        let public_key = PublicKey::from_str(
+            &STANDARD.encode(
+                [
+                    ED25519_ALGORITHM.as_slice(),
+                    &secret_key.key_id.0,
+                    &secret_key.public_key().key,
+                ]
+                .concat(),
+            ),
+        )
+        .unwrap();
+        verify_package(&[public_key], &package_file, &signature).unwrap();
+
+        // Packages that are modified after signing are rejected.
+        fs_err::write(&package_file, "modified contents").unwrap();
+        assert!(matches!(
+            verify_package(&[secret_key.public_key()], &package_file, &signature),
+            Err(SigningError::Mismatch)
+        ));
+
+        // Signatures of other keys are rejected.
+        let other_key = PublicKey {
+            key_id: KeyId([8; 8]),
+            key: [0; 32],
+        };
+        assert!(matches!(
+            verify_package(&[other_key], &package_file, &signature),
+            Err(SigningError::UntrustedKey(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_verify_conda_signatures() {
+        let dir = tempfile::tempdir().unwrap();
+        let secret_key = secret_key(dir.path());
+        let source_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/data/channels/channels/dummy_channel_1/win-64");
+        let file_name = "dummy-a-0.1.0-h9490d1a_0.conda";
+
+        // A local channel with a signed package.
+        let channel_dir = dir.path().join("channel");
+        fs_err::create_dir_all(channel_dir.join("win-64")).unwrap();
+        let package_file = channel_dir.join("win-64").join(file_name);
+        fs_err::copy(source_dir.join(file_name), &package_file).unwrap();
+        sign_package(&secret_key, &package_file).unwrap();
+
+        let repodata = RepoData::from_path(source_dir.join("repodata.json")).unwrap();
+        let channel = Url::from_directory_path(&channel_dir).unwrap();
+        let records = [RepoDataRecord {
+            package_record: repodata.conda_packages[file_name].clone(),
+            file_name: file_name.to_string(),
+            url: Url::from_file_path(&package_file).unwrap(),
+            channel: Some(channel.to_string()),
+        }];
+        let trust = TrustConfig {
+            public_keys: vec![STANDARD.encode(
+                [
+                    ED25519_ALGORITHM.as_slice(),
+                    &secret_key.key_id.0,
+                    &secret_key.public_key().key,
+                ]
+                .concat(),
+            )],
+            channels: vec![channel],
+        };
+        let client = ClientWithMiddleware::from(reqwest::Client::new());
+
+        // A package with a valid signature is extracted into the package cache.
+        let package_cache = PackageCache::new(dir.path().join("pkgs"));
+        verify_conda_signatures(
+            &trust,
+            &[],
+            &records,
+            Platform::Win64,
+            &package_cache,
+            &client,
+        )
+        .await
+        .unwrap();
+
+        // A package with the signature of another file is rejected.
+        let other_file = dir.path().join("other.conda");
+        fs_err::write(&other_file, "other contents").unwrap();
+        fs_err::copy(
+            sign_package(&secret_key, &other_file).unwrap(),
+            signature_path(&package_file),
+        )
+        .unwrap();
+        let package_cache = PackageCache::new(dir.path().join("other-pkgs"));
+        let err = verify_conda_signatures(
+            &trust,
+            &[],
+            &records,
+            Platform::Win64,
+            &package_cache,
+            &client,
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!("failed to verify the signature of '{file_name}'")
+        );
+    }
+}
@@ -0,0 +1,327 @@
+//! Records the duration of commands and the phases they spend their time in,
+//! solving, downloading and linking, in a newline-delimited JSON file in the
+//! pixi home. Recording is opt-in through `experimental.local-stats` and the
+//! statistics never leave the machine.
+//!
+//! The statistics of every invocation are collected in memory, and only
+//! written if the configuration of the project the command ran in, or else the
+//! global configuration, enables them.
+
+use std::{
+    collections::BTreeMap,
+    io::Write,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        OnceLock,
+    },
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Utc};
+use miette::IntoDiagnostic;
+use parking_lot::Mutex;
+use pixi_config::Config;
+use pixi_consts::consts;
+use rattler::install::{Reporter, Transaction};
+use rattler_conda_types::{PrefixRecord, RepoDataRecord};
+use serde::{Deserialize, Serialize};
+
+/// The phases of a command that are recorded separately.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Phase {
+    /// Solving the conda or PyPI dependencies of an environment.
+    Solve,
+    /// Downloading conda packages and preparing PyPI distributions into the
+    /// package caches.
+    Download,
+    /// Linking conda packages and installing PyPI distributions into an
+    /// environment.
+    Link,
+}
+
+impl Phase {
+    pub const ALL: [Phase; 3] = [Phase::Solve, Phase::Download, Phase::Link];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Phase::Solve => "solve",
+            Phase::Download => "download",
+            Phase::Link => "link",
+        }
+    }
+}
+
+/// A single line of the statistics file, describing one invocation of pixi.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatsEntry {
+    pub timestamp: DateTime<Utc>,
+    pub pixi_version: String,
+    pub command: String,
+    pub success: bool,
+    pub duration_ms: u64,
+    /// The number of solves that were done.
+    #[serde(default)]
+    pub solves: usize,
+    /// The time spent in every phase. Phases can run concurrently, so their
+    /// sum can exceed the duration of the command.
+    #[serde(default)]
+    pub phases_ms: BTreeMap<Phase, u64>,
+}
+
+/// Collects the statistics of the current invocation.
+struct StatsRecorder {
+    started: Instant,
+    solves: AtomicUsize,
+    phases: Mutex<BTreeMap<Phase, Duration>>,
+}
+
+static RECORDER: OnceLock<StatsRecorder> = OnceLock::new();
+
+/// Whether the statistics of this invocation are written, decided by the
+/// configuration that is loaded first.
+static ENABLED: OnceLock<bool> = OnceLock::new();
+
+/// Returns the path of the statistics file in the pixi home.
+pub fn stats_file_path() -> Option<PathBuf> {
+    pixi_config::pixi_home().map(|home| home.join(consts::STATS_FILE_NAME))
+}
+
+/// Starts recording the statistics of this invocation.
+pub(crate) fn start() {
+    RECORDER.get_or_init(|| StatsRecorder {
+        started: Instant::now(),
+        solves: AtomicUsize::new(0),
+        phases: Mutex::default(),
+    });
+}
+
+/// Decides from the configuration of a project whether the statistics of this
+/// invocation are written, the first project that is loaded decides.
+pub(crate) fn enable_from_config(config: &Config) {
+    ENABLED.get_or_init(|| config.local_stats());
+}
+
+/// Adds the duration to the time spent in the given phase, does nothing if
+/// recording hasn't started.
+pub(crate) fn record(phase: Phase, duration: Duration) {
+    if let Some(recorder) = RECORDER.get() {
+        if phase == Phase::Solve {
+            recorder.solves.fetch_add(1, Ordering::Relaxed);
+        }
+        *recorder.phases.lock().entry(phase).or_default() += duration;
+    }
+}
+
+/// Appends the statistics of this invocation to the statistics file, does
+/// nothing if recording is disabled. Commands that didn't load a project read
+/// the setting from the global configuration.
+pub(crate) fn finish(command: &str, success: bool) {
+    let Some(recorder) = RECORDER.get() else {
+        return;
+    };
+    if !*ENABLED.get_or_init(|| Config::load_global().local_stats()) {
+        return;
+    }
+    let entry = StatsEntry {
+        timestamp: Utc::now(),
+        pixi_version: consts::PIXI_VERSION.to_string(),
+        command: command.to_string(),
+        success,
+        duration_ms: recorder.started.elapsed().as_millis() as u64,
+        solves: recorder.solves.load(Ordering::Relaxed),
+        phases_ms: recorder
+            .phases
+            .lock()
+            .iter()
+            .map(|(phase, duration)| (*phase, duration.as_millis() as u64))
+            .collect(),
+    };
+
+    // Failing to write the statistics should never fail the command itself.
+    if let Err(e) = append_entry(&entry) {
+        tracing::debug!("failed to write the local statistics: {e}");
+    }
+}
+
+fn append_entry(entry: &StatsEntry) -> miette::Result<()> {
+    let path = stats_file_path()
+        .ok_or_else(|| miette::miette!("could not determine the pixi home directory"))?;
+    if let Some(parent) = path.parent() {
+        fs_err::create_dir_all(parent).into_diagnostic()?;
+    }
+    let mut line = serde_json::to_string(entry).into_diagnostic()?;
+    line.push('\n');
+    let mut file = fs_err::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .into_diagnostic()?;
+    file.write_all(line.as_bytes()).into_diagnostic()
+}
+
+/// Reads all entries of the statistics file, skipping lines that can't be
+/// parsed.
+pub fn read_entries() -> miette::Result<Vec<StatsEntry>> {
+    let Some(path) = stats_file_path() else {
+        return Ok(Vec::new());
+    };
+    let contents = match fs_err::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).into_diagnostic(),
+    };
+    Ok(contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| match serde_json::from_str(line) {
+            Ok(entry) => Some(entry),
+            Err(e) => {
+                tracing::debug!("skipping invalid line in {}: {e}", path.display());
+                None
+            }
+        })
+        .collect())
+}
+
+/// Tracks the wall-clock time between the first and the last event of a
+/// phase of an installation.
+#[derive(Default)]
+struct PhaseSpan {
+    started: Option<Instant>,
+    finished: Option<Instant>,
+}
+
+impl PhaseSpan {
+    fn start(&mut self) {
+        self.started.get_or_insert_with(Instant::now);
+    }
+
+    fn finish(&mut self) {
+        self.finished = Some(Instant::now());
+    }
+
+    fn duration(&self) -> Option<Duration> {
+        Some(self.finished?.saturating_duration_since(self.started?))
+    }
+}
+
+/// An installation reporter that records the time spent downloading and
+/// linking packages, and forwards every event to the wrapped reporter.
+pub(crate) struct StatsReporter<R> {
+    inner: R,
+    download: Mutex<PhaseSpan>,
+    link: Mutex<PhaseSpan>,
+}
+
+impl<R> StatsReporter<R> {
+    pub(crate) fn new(inner: R) -> Self {
+        Self {
+            inner,
+            download: Mutex::default(),
+            link: Mutex::default(),
+        }
+    }
+}
+
+impl<R: Reporter> Reporter for StatsReporter<R> {
+    fn on_transaction_start(&self, transaction: &Transaction<PrefixRecord, RepoDataRecord>) {
+        self.inner.on_transaction_start(transaction)
+    }
+
+    fn on_transaction_operation_start(&self, operation: usize) {
+        self.inner.on_transaction_operation_start(operation)
+    }
+
+    fn on_populate_cache_start(&self, operation: usize, record: &RepoDataRecord) -> usize {
+        self.inner.on_populate_cache_start(operation, record)
+    }
+
+    fn on_validate_start(&self, cache_entry: usize) -> usize {
+        self.inner.on_validate_start(cache_entry)
+    }
+
+    fn on_validate_complete(&self, validate_idx: usize) {
+        self.inner.on_validate_complete(validate_idx)
+    }
+
+    fn on_download_start(&self, cache_entry: usize) -> usize {
+        self.download.lock().start();
+        self.inner.on_download_start(cache_entry)
+    }
+
+    fn on_download_progress(&self, download_idx: usize, progress: u64, total: Option<u64>) {
+        self.inner
+            .on_download_progress(download_idx, progress, total)
+    }
+
+    fn on_download_completed(&self, download_idx: usize) {
+        self.download.lock().finish();
+        self.inner.on_download_completed(download_idx)
+    }
+
+    fn on_populate_cache_complete(&self, cache_entry: usize) {
+        self.inner.on_populate_cache_complete(cache_entry)
+    }
+
+    fn on_unlink_start(&self, operation: usize, record: &PrefixRecord) -> usize {
+        self.link.lock().start();
+        self.inner.on_unlink_start(operation, record)
+    }
+
+    fn on_unlink_complete(&self, index: usize) {
+        self.link.lock().finish();
+        self.inner.on_unlink_complete(index)
+    }
+
+    fn on_link_start(&self, operation: usize, record: &RepoDataRecord) -> usize {
+        self.link.lock().start();
+        self.inner.on_link_start(operation, record)
+    }
+
+    fn on_link_complete(&self, index: usize) {
+        self.link.lock().finish();
+        self.inner.on_link_complete(index)
+    }
+
+    fn on_transaction_operation_complete(&self, operation: usize) {
+        self.inner.on_transaction_operation_complete(operation)
+    }
+
+    fn on_transaction_complete(&self) {
+        if let Some(duration) = self.download.lock().duration() {
+            record(Phase::Download, duration);
+        }
+        if let Some(duration) = self.link.lock().duration() {
+            record(Phase::Link, duration);
+        }
+        self.inner.on_transaction_complete()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats_entry_roundtrip() {
+        let line = r#"{"timestamp":"2025-01-01T12:00:00Z","pixi_version":"0.40.0","command":"install","success":true,"duration_ms":1500,"solves":2,"phases_ms":{"solve":700,"download":500,"link":200}}"#;
+        let entry: StatsEntry = serde_json::from_str(line).unwrap();
+        assert_eq!(entry.command, "install");
+        assert_eq!(entry.solves, 2);
+        assert_eq!(entry.phases_ms[&Phase::Solve], 700);
+        assert_eq!(entry.phases_ms[&Phase::Link], 200);
+        assert_eq!(serde_json::to_string(&entry).unwrap(), line);
+    }
+
+    #[test]
+    fn test_phase_span() {
+        let mut span = PhaseSpan::default();
+        assert_eq!(span.duration(), None);
+        span.start();
+        assert_eq!(span.duration(), None);
+        span.finish();
+        assert!(span.duration().is_some());
+    }
+}
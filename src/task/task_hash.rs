@@ -1,6 +1,7 @@
 use crate::project;
 use crate::task::{ExecutableTask, FileHashes, FileHashesError, InvalidWorkingDirectory};
 use miette::Diagnostic;
+use pixi_consts::consts;
 use rattler_lock::LockFile;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -38,6 +39,12 @@ pub struct TaskCache {
 pub struct EnvironmentHash(String);
 
 impl EnvironmentHash {
+    /// Hashes the environment and its activation.
+    ///
+    /// Of the environment variables of the shell only the given ones are
+    /// hashed. The activation passes the variables it sets and the ones its
+    /// scripts refer to, variables that a task only reads are not known up
+    /// front.
     pub(crate) fn from_environment(
         run_environment: &project::Environment<'_>,
         input_environment_variables: &HashMap<String, Option<String>>,
@@ -54,11 +61,14 @@ impl EnvironmentHash {
             value.hash(&mut hasher);
         }
 
-        // Hash the activation scripts
+        // Hash the activation scripts and their contents, so editing a script
+        // invalidates the cache.
         let activation_scripts =
             run_environment.activation_scripts(Some(run_environment.best_platform()));
         for script in activation_scripts {
+            let contents = fs_err::read(run_environment.project().root().join(&script)).ok();
             script.hash(&mut hasher);
+            contents.hash(&mut hasher);
         }
 
         // Hash the environment variables
@@ -84,6 +94,9 @@ impl EnvironmentHash {
         urls.sort();
         urls.hash(&mut hasher);
 
+        // The activation itself can change between pixi versions
+        consts::PIXI_VERSION.hash(&mut hasher);
+
         EnvironmentHash(format!("{:x}", hasher.finish()))
     }
 }
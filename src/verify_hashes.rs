@@ -0,0 +1,401 @@
+//! Verifies the sha256 of the packages that are downloaded against the lock
+//! file, before they are installed.
+
+use std::{
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use futures::{stream, StreamExt, TryStreamExt};
+use itertools::Itertools;
+use miette::{Context, Diagnostic, IntoDiagnostic};
+use pixi_config::VerifyHashes;
+use rattler::{install::Transaction, package_cache::PackageCache};
+use rattler_conda_types::{
+    package::{PackageFile, PathsJson},
+    Platform, PrefixRecord, RepoDataRecord,
+};
+use rattler_digest::{Sha256, Sha256Hash};
+use rattler_lock::PackageHashes;
+use rattler_package_streaming::ExtractError;
+use reqwest_middleware::ClientWithMiddleware;
+use thiserror::Error;
+use url::Url;
+use uv_distribution_types::{BuiltDist, Dist, DistributionMetadata, Name, SourceDist};
+use uv_types::HashStrategy;
+
+use crate::install_pypi::conversions::to_uv_hash_digests;
+
+/// The maximum number of conda packages that are downloaded at the same time
+/// to verify them.
+const MAX_CONCURRENT_DOWNLOADS: usize = 50;
+
+/// The packages that cannot be verified because the lock file doesn't contain
+/// their sha256.
+#[derive(Debug, Error, Diagnostic)]
+#[error(
+    "the lock file doesn't contain the sha256 of {}",
+    packages.iter().map(|package| format!("'{package}'")).join(", ")
+)]
+#[diagnostic(help(
+    "`verify-hashes` is set to `always`, which requires the sha256 of every package. Set it to `lock-only` to only verify the packages that have a sha256."
+))]
+pub(crate) struct MissingHashesError {
+    packages: Vec<String>,
+}
+
+/// A downloaded package doesn't match the sha256 in the lock file.
+#[derive(Debug, Error)]
+#[error("the sha256 of '{url}' is {actual:x}, but the lock file expects {expected:x}")]
+struct HashMismatchError {
+    url: Url,
+    expected: Sha256Hash,
+    actual: Sha256Hash,
+}
+
+/// A package in the cache doesn't match the verified archive of the package.
+#[derive(Debug, Error, Diagnostic)]
+#[error("the cached package '{}' differs from the verified archive in '{}'", file_name, path.display())]
+#[diagnostic(help("Run `pixi clean cache --conda` to remove the cached packages."))]
+pub(crate) struct CachedPackageMismatchError {
+    pub file_name: String,
+    pub path: std::path::PathBuf,
+}
+
+#[derive(Debug, Error)]
+enum FetchError {
+    #[error(transparent)]
+    Extract(#[from] ExtractError),
+    #[error(transparent)]
+    HashMismatch(#[from] HashMismatchError),
+}
+
+/// Downloads the conda packages that have to be installed in the prefix to the
+/// package cache and verifies their sha256, so the installer links the
+/// verified packages. Packages that are already in the cache are not
+/// downloaded again.
+pub(crate) async fn verify_conda_packages(
+    policy: VerifyHashes,
+    installed_packages: &[PrefixRecord],
+    records: &[RepoDataRecord],
+    platform: Platform,
+    package_cache: &PackageCache,
+    client: &ClientWithMiddleware,
+) -> miette::Result<()> {
+    if policy == VerifyHashes::Off {
+        return Ok(());
+    }
+
+    let transaction = Transaction::from_current_and_desired(
+        installed_packages.to_vec(),
+        records.to_vec(),
+        platform,
+    )
+    .into_diagnostic()?;
+    let to_install = transaction.installed_packages().collect_vec();
+
+    if policy == VerifyHashes::Always {
+        let packages = to_install
+            .iter()
+            .filter(|record| record.package_record.sha256.is_none())
+            .map(|record| record.file_name.clone())
+            .collect_vec();
+        if !packages.is_empty() {
+            return Err(MissingHashesError { packages }.into());
+        }
+    }
+
+    let to_verify = to_install
+        .into_iter()
+        .filter_map(|record| Some((record, record.package_record.sha256?)));
+    stream::iter(to_verify)
+        .map(|(record, expected)| fetch_and_verify(policy, package_cache, client, record, expected))
+        .buffer_unordered(MAX_CONCURRENT_DOWNLOADS)
+        .try_collect::<Vec<_>>()
+        .await?;
+    Ok(())
+}
+
+/// Fetches a conda package into the package cache, unless it is already
+/// there, and fails if the downloaded archive doesn't have the expected
+/// sha256.
+///
+/// With [`VerifyHashes::Always`] a package that is already in the cache is
+/// verified as well: its archive is downloaded again and verified, and the
+/// files in the cache have to match the files of the verified archive.
+async fn fetch_and_verify(
+    policy: VerifyHashes,
+    package_cache: &PackageCache,
+    client: &ClientWithMiddleware,
+    record: &RepoDataRecord,
+    expected: Sha256Hash,
+) -> miette::Result<()> {
+    let fetched = Arc::new(AtomicBool::new(false));
+    let cache_lock = package_cache
+        .get_or_fetch(
+            &record.package_record,
+            {
+                let url = record.url.clone();
+                let client = client.clone();
+                let fetched = fetched.clone();
+                move |destination| {
+                    let url = url.clone();
+                    let client = client.clone();
+                    fetched.store(true, Ordering::Relaxed);
+                    async move { extract_verified(client, url, &destination, expected).await }
+                }
+            },
+            None,
+        )
+        .await
+        .into_diagnostic()
+        .wrap_err_with(|| format!("failed to verify '{}'", record.file_name))?;
+
+    if policy == VerifyHashes::Always && !fetched.load(Ordering::Relaxed) {
+        let verified = tempfile::tempdir().into_diagnostic()?;
+        extract_verified(
+            client.clone(),
+            record.url.clone(),
+            verified.path(),
+            expected,
+        )
+        .await
+        .into_diagnostic()
+        .wrap_err_with(|| format!("failed to verify '{}'", record.file_name))?;
+        let cached = cache_lock.path().to_path_buf();
+        let verified = verified.path().to_path_buf();
+        let matches = tokio::task::spawn_blocking(move || package_dirs_match(&verified, &cached))
+            .await
+            .into_diagnostic()??;
+        if !matches {
+            return Err(CachedPackageMismatchError {
+                file_name: record.file_name.clone(),
+                path: cache_lock.path().to_path_buf(),
+            }
+            .into());
+        }
+    }
+    Ok(())
+}
+
+/// Downloads and extracts a conda package, and fails if the archive doesn't
+/// have the expected sha256.
+async fn extract_verified(
+    client: ClientWithMiddleware,
+    url: Url,
+    destination: &Path,
+    expected: Sha256Hash,
+) -> Result<(), FetchError> {
+    let result = rattler_package_streaming::reqwest::tokio::extract(
+        client,
+        url.clone(),
+        destination,
+        Some(expected),
+        None,
+    )
+    .await?;
+    if result.sha256 != expected {
+        // Never leave the package behind in the cache, it would be
+        // linked by the next installation.
+        let _ = fs_err::tokio::remove_dir_all(destination).await;
+        return Err(FetchError::HashMismatch(HashMismatchError {
+            url,
+            expected,
+            actual: result.sha256,
+        }));
+    }
+    Ok(())
+}
+
+/// Returns true if the package directory `cached` contains the same files
+/// as the package directory `verified`, according to the `paths.json` of the
+/// verified package.
+pub(crate) fn package_dirs_match(verified: &Path, cached: &Path) -> miette::Result<bool> {
+    let paths = PathsJson::from_package_directory(verified).into_diagnostic()?;
+    let file_sha256 = |path: &Path| rattler_digest::compute_file_digest::<Sha256>(path).ok();
+    Ok(paths
+        .paths
+        .iter()
+        .map(|entry| entry.relative_path.as_path())
+        .chain([PathsJson::package_path()])
+        .all(|path| {
+            let verified = file_sha256(&verified.join(path));
+            verified.is_some() && verified == file_sha256(&cached.join(path))
+        }))
+}
+
+/// Returns the hash strategy with which uv verifies the PyPI packages that
+/// it downloads against the hashes in the lock file.
+pub(crate) fn pypi_hash_strategy<'a>(
+    policy: VerifyHashes,
+    packages: impl IntoIterator<Item = (&'a Dist, Option<&'a PackageHashes>)>,
+) -> Result<HashStrategy, MissingHashesError> {
+    if policy == VerifyHashes::Off {
+        return Ok(HashStrategy::None);
+    }
+
+    let mut hashes = Vec::new();
+    let mut missing = Vec::new();
+    for (dist, hash) in packages {
+        match hash {
+            Some(hash) => hashes.push((dist.version_id(), to_uv_hash_digests(hash))),
+            None if policy == VerifyHashes::Always && is_archive(dist) => {
+                missing.push(dist.name().to_string())
+            }
+            None => {}
+        }
+    }
+    if !missing.is_empty() {
+        return Err(MissingHashesError { packages: missing });
+    }
+
+    Ok(HashStrategy::Verify(Arc::new(hashes.into_iter().collect())))
+}
+
+/// Returns true if the distribution is downloaded as an archive. Git and local
+/// distributions never have a hash.
+fn is_archive(dist: &Dist) -> bool {
+    matches!(
+        dist,
+        Dist::Built(BuiltDist::Registry(_) | BuiltDist::DirectUrl(_))
+            | Dist::Source(SourceDist::Registry(_) | SourceDist::DirectUrl(_))
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use rattler::package_cache::CacheKey;
+    use rattler_conda_types::{Channel, RepoData};
+
+    use super::*;
+
+    /// Returns the records of the dummy channel for linux-64.
+    fn dummy_records() -> Vec<RepoDataRecord> {
+        let channel_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/data/channels/channels/dummy_channel_1");
+        let channel = Channel::from_directory(&channel_dir);
+        RepoData::from_path(channel_dir.join("linux-64/repodata.json"))
+            .unwrap()
+            .into_repo_data_records(&channel)
+    }
+
+    #[tokio::test]
+    async fn test_verify_conda_packages() {
+        let records = dummy_records();
+        let client = ClientWithMiddleware::from(reqwest::Client::new());
+
+        let cache_dir = tempfile::tempdir().unwrap();
+        let package_cache = PackageCache::new(cache_dir.path());
+        verify_conda_packages(
+            VerifyHashes::LockOnly,
+            &[],
+            &records,
+            Platform::Linux64,
+            &package_cache,
+            &client,
+        )
+        .await
+        .unwrap();
+
+        // A package that doesn't match the lock file is an error, and is not
+        // stored in the cache.
+        let mut record = records[0].clone();
+        record.package_record.sha256 = Some(Sha256Hash::default());
+        let cache_dir = tempfile::tempdir().unwrap();
+        let package_cache = PackageCache::new(cache_dir.path());
+        let err = verify_conda_packages(
+            VerifyHashes::LockOnly,
+            &[],
+            &[record.clone()],
+            Platform::Linux64,
+            &package_cache,
+            &client,
+        )
+        .await
+        .unwrap_err();
+        assert!(err
+            .chain()
+            .any(|err| err.to_string().contains("but the lock file expects")));
+        assert!(!cache_dir
+            .path()
+            .join(CacheKey::from(&record.package_record).to_string())
+            .exists());
+
+        // A package without a sha256 is only an error when every package has
+        // to be verified.
+        record.package_record.sha256 = None;
+        verify_conda_packages(
+            VerifyHashes::LockOnly,
+            &[],
+            &[record.clone()],
+            Platform::Linux64,
+            &package_cache,
+            &client,
+        )
+        .await
+        .unwrap();
+        let err = verify_conda_packages(
+            VerifyHashes::Always,
+            &[],
+            &[record],
+            Platform::Linux64,
+            &package_cache,
+            &client,
+        )
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("doesn't contain the sha256"));
+    }
+
+    #[tokio::test]
+    async fn test_verify_cached_conda_packages() {
+        let records = dummy_records();
+        let client = ClientWithMiddleware::from(reqwest::Client::new());
+        let cache_dir = tempfile::tempdir().unwrap();
+        let package_cache = PackageCache::new(cache_dir.path());
+
+        // The packages are fetched into the cache, and verified again when
+        // every package has to be verified.
+        for policy in [VerifyHashes::LockOnly, VerifyHashes::Always] {
+            verify_conda_packages(
+                policy,
+                &[],
+                &records,
+                Platform::Linux64,
+                &package_cache,
+                &client,
+            )
+            .await
+            .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_package_dirs_match() {
+        let write_package = |content: &str| {
+            let dir = tempfile::tempdir().unwrap();
+            fs_err::create_dir_all(dir.path().join("info")).unwrap();
+            fs_err::create_dir_all(dir.path().join("bin")).unwrap();
+            fs_err::write(
+                dir.path().join(PathsJson::package_path()),
+                r#"{"paths": [{"_path": "bin/tool", "path_type": "hardlink"}], "paths_version": 1}"#,
+            )
+            .unwrap();
+            fs_err::write(dir.path().join("bin/tool"), content).unwrap();
+            dir
+        };
+
+        let verified = write_package("#!/bin/sh");
+        assert!(package_dirs_match(verified.path(), write_package("#!/bin/sh").path()).unwrap());
+        assert!(!package_dirs_match(verified.path(), write_package("#!/bin/bash").path()).unwrap());
+
+        let incomplete = write_package("#!/bin/sh");
+        fs_err::remove_file(incomplete.path().join("bin/tool")).unwrap();
+        assert!(!package_dirs_match(verified.path(), incomplete.path()).unwrap());
+    }
+}
@@ -0,0 +1,64 @@
+from pathlib import Path
+import shutil
+import tomllib
+
+import pytest
+
+from ..common import verify_cli_command, exec_extension, ExitCode
+
+
+def copy_rich_example(build_data: Path, target: Path) -> Path:
+    shutil.copytree(build_data / "rich_example", target)
+    shutil.rmtree(target.joinpath(".pixi"), ignore_errors=True)
+    return target
+
+
+@pytest.mark.extra_slow
+def test_global_install_from_path(pixi: Path, tmp_pixi_workspace: Path, build_data: Path) -> None:
+    env = {"PIXI_HOME": str(tmp_pixi_workspace)}
+    project = copy_rich_example(build_data, tmp_pixi_workspace / "rich_example")
+
+    verify_cli_command([pixi, "global", "install", "--path", project], env=env)
+
+    # The absolute path of the source is stored in the manifest
+    manifest = tomllib.loads(
+        tmp_pixi_workspace.joinpath("manifests", "pixi-global.toml").read_text()
+    )
+    assert manifest["envs"]["rich_example"]["dependencies"]["rich_example"] == {
+        "path": str(project.resolve())
+    }
+    assert tmp_pixi_workspace.joinpath("bin", exec_extension("rich-example-main")).is_file()
+
+
+@pytest.mark.extra_slow
+def test_global_install_from_git(pixi: Path, tmp_pixi_workspace: Path, build_data: Path) -> None:
+    env = {"PIXI_HOME": str(tmp_pixi_workspace)}
+    git_dir = copy_rich_example(build_data, tmp_pixi_workspace / "git_project")
+
+    # Commit the project to a branch of a new git repository
+    verify_cli_command(["git", "init"], cwd=git_dir)
+    verify_cli_command(["git", "config", "user.email", "some@email.com"], cwd=git_dir)
+    verify_cli_command(["git", "config", "user.name", "some-name"], cwd=git_dir)
+    verify_cli_command(["git", "checkout", "-b", "test-branch"], cwd=git_dir)
+    verify_cli_command(["git", "add", "."], cwd=git_dir)
+    verify_cli_command(["git", "commit", "-m", "initial commit"], cwd=git_dir)
+
+    verify_cli_command(
+        [pixi, "global", "install", "--git", git_dir.as_uri(), "--branch", "test-branch"],
+        env=env,
+    )
+
+    manifest = tomllib.loads(
+        tmp_pixi_workspace.joinpath("manifests", "pixi-global.toml").read_text()
+    )
+    spec = manifest["envs"]["rich_example"]["dependencies"]["rich_example"]
+    assert spec["git"] == git_dir.as_uri()
+    assert spec["branch"] == "test-branch"
+    assert tmp_pixi_workspace.joinpath("bin", exec_extension("rich-example-main")).is_file()
+
+    # Options of a git source require `--git`
+    verify_cli_command(
+        [pixi, "global", "install", "--branch", "test-branch", "rich_example"],
+        env=env,
+        expected_exit_code=ExitCode.INCORRECT_USAGE,
+    )
@@ -1632,6 +1632,106 @@ def test_global_update_multiple_packages_in_one_env(
     assert "0.2.0" in bin_file_package2.read_text()
 
 
+def test_global_upgrade(
+    pixi: Path, tmp_pixi_workspace: Path, multiple_versions_channel_1: str
+) -> None:
+    env = {"PIXI_HOME": str(tmp_pixi_workspace)}
+    verify_cli_command(
+        [pixi, "global", "install", "--channel", multiple_versions_channel_1, "package==0.1.0"],
+        env=env,
+    )
+    manifest = tmp_pixi_workspace.joinpath("manifests", "pixi-global.toml")
+    package0_1_0 = tmp_pixi_workspace / "bin" / exec_extension("package0.1.0")
+    package0_2_0 = tmp_pixi_workspace / "bin" / exec_extension("package0.2.0")
+
+    # A dry run only shows the upgrade
+    original_manifest = manifest.read_text()
+    verify_cli_command(
+        [pixi, "global", "upgrade", "--dry-run", "package"],
+        env=env,
+        stdout_contains=["package", "0.1.0", "0.2.0"],
+    )
+    assert manifest.read_text() == original_manifest
+    assert package0_1_0.is_file()
+    assert not package0_2_0.is_file()
+
+    # The upgrade ignores the version constraint and pins the new version
+    verify_cli_command(
+        [pixi, "global", "upgrade", "package"],
+        env=env,
+        stdout_contains=["package", "0.1.0", "0.2.0"],
+    )
+    parsed_toml = tomllib.loads(manifest.read_text())
+    assert parsed_toml["envs"]["package"]["dependencies"]["package"] == ">=0.2.0"
+    assert not package0_1_0.is_file()
+    assert package0_2_0.is_file()
+
+    verify_cli_command(
+        [pixi, "global", "upgrade", "package"],
+        env=env,
+        stderr_contains="All global environments are already up-to-date",
+    )
+
+
+def test_global_upgrade_dry_run_does_not_create_environment(
+    pixi: Path, tmp_pixi_workspace: Path, multiple_versions_channel_1: str
+) -> None:
+    env = {"PIXI_HOME": str(tmp_pixi_workspace)}
+    manifests = tmp_pixi_workspace.joinpath("manifests")
+    manifests.mkdir()
+    manifest = manifests.joinpath("pixi-global.toml")
+    toml = f"""
+    [envs.package]
+    channels = ["{multiple_versions_channel_1}"]
+    dependencies = {{ package = "==0.1.0" }}
+    exposed = {{ package = "package" }}
+    """
+    manifest.write_text(toml)
+
+    verify_cli_command(
+        [pixi, "global", "upgrade", "--dry-run", "package"],
+        env=env,
+        stdout_contains=["package", "0.2.0"],
+    )
+    assert manifest.read_text() == toml
+    assert not tmp_pixi_workspace.joinpath("envs", "package").exists()
+
+
+def test_global_upgrade_all_with_exclude(
+    pixi: Path, tmp_pixi_workspace: Path, multiple_versions_channel_1: str
+) -> None:
+    env = {"PIXI_HOME": str(tmp_pixi_workspace)}
+    verify_cli_command(
+        [
+            pixi,
+            "global",
+            "install",
+            "--channel",
+            multiple_versions_channel_1,
+            "package==0.1.0",
+            "package2==0.1.0",
+        ],
+        env=env,
+    )
+    manifest = tmp_pixi_workspace.joinpath("manifests", "pixi-global.toml")
+
+    verify_cli_command(
+        [pixi, "global", "upgrade", "--all", "--exclude", "package2"],
+        env=env,
+    )
+    parsed_toml = tomllib.loads(manifest.read_text())
+    assert parsed_toml["envs"]["package"]["dependencies"]["package"] == ">=0.2.0"
+    assert parsed_toml["envs"]["package2"]["dependencies"]["package2"] == "==0.1.0"
+
+    # Excluding an environment that doesn't exist fails
+    verify_cli_command(
+        [pixi, "global", "upgrade", "--all", "--exclude", "unknown"],
+        env=env,
+        expected_exit_code=ExitCode.FAILURE,
+        stderr_contains="Environment unknown doesn't exist",
+    )
+
+
 def test_pixi_update_cleanup(
     pixi: Path, tmp_pixi_workspace: Path, multiple_versions_channel_1: str
 ) -> None:
@@ -1849,3 +1949,40 @@ def test_remove_dependency(pixi: Path, tmp_pixi_workspace: Path, dummy_channel_1
         env=env,
         stderr_contains="Environment dummy-a doesn't exist",
     )
+
+
+@pytest.mark.skipif(
+    platform.system() == "Windows",
+    reason="For some reason .bat files are not correctly executed on windows",
+)
+def test_global_run(pixi: Path, tmp_pixi_workspace: Path, dummy_channel_1: str) -> None:
+    env = {"PIXI_HOME": str(tmp_pixi_workspace)}
+
+    # The executable with the name of the package is run
+    verify_cli_command(
+        [pixi, "global", "run", "--channel", dummy_channel_1, "dummy-f"],
+        env=env,
+        stdout_contains="dummy-f on",
+    )
+
+    # Another executable of the environment can be selected
+    verify_cli_command(
+        [
+            pixi,
+            "global",
+            "run",
+            "--channel",
+            dummy_channel_1,
+            "--with",
+            "dummy-f",
+            "--executable",
+            "dummy-f",
+            "dummy-b",
+        ],
+        env=env,
+        stdout_contains="dummy-f on",
+    )
+
+    # Nothing is installed globally
+    assert not tmp_pixi_workspace.joinpath("envs", "dummy-f").exists()
+    assert not tmp_pixi_workspace.joinpath("bin", exec_extension("dummy-f")).exists()
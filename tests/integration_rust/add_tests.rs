@@ -76,6 +76,77 @@ async fn add_functionality() {
     ));
 }
 
+/// Test that removing a dependency with `--prune` also drops its orphaned
+/// transitive dependencies from the lock-file.
+#[tokio::test]
+async fn remove_with_prune() {
+    let mut package_database = PackageDatabase::default();
+    package_database.add_package(Package::build("foo", "1").with_dependency("bar").finish());
+    package_database.add_package(Package::build("bar", "1").finish());
+    package_database.add_package(Package::build("baz", "1").finish());
+
+    let channel_dir = TempDir::new().unwrap();
+    package_database
+        .write_repodata(channel_dir.path())
+        .await
+        .unwrap();
+
+    let pixi = PixiControl::new().unwrap();
+    pixi.init()
+        .with_local_channel(channel_dir.path())
+        .await
+        .unwrap();
+
+    pixi.add("foo").await.unwrap();
+    pixi.add("baz").with_install(true).await.unwrap();
+
+    // Whether the package is installed in the default environment
+    let is_installed = |name: &str| {
+        fs_err::read_dir(pixi.default_env_path().unwrap().join("conda-meta"))
+            .unwrap()
+            .any(|entry| {
+                entry
+                    .unwrap()
+                    .file_name()
+                    .to_string_lossy()
+                    .starts_with(&format!("{name}-1-"))
+            })
+    };
+    assert!(is_installed("bar"));
+
+    let lock = pixi.lock_file().await.unwrap();
+    assert!(lock.contains_conda_package(
+        consts::DEFAULT_ENVIRONMENT_NAME,
+        Platform::current(),
+        "bar"
+    ));
+
+    pixi.remove("foo")
+        .with_prune(true)
+        .with_install(true)
+        .await
+        .unwrap();
+
+    let lock = pixi.lock_file().await.unwrap();
+    for removed in ["foo", "bar"] {
+        assert!(!lock.contains_conda_package(
+            consts::DEFAULT_ENVIRONMENT_NAME,
+            Platform::current(),
+            removed
+        ));
+    }
+    assert!(lock.contains_conda_package(
+        consts::DEFAULT_ENVIRONMENT_NAME,
+        Platform::current(),
+        "baz"
+    ));
+
+    // The pruned packages are removed from the environment as well
+    assert!(!is_installed("foo"));
+    assert!(!is_installed("bar"));
+    assert!(is_installed("baz"));
+}
+
 /// Test adding a package with a specific channel
 #[tokio::test]
 async fn add_with_channel() {
@@ -520,6 +591,58 @@ async fn add_unconstrained_dependency() {
     "###);
 }
 
+/// Test that adding a dependency that is already locked as a transitive
+/// dependency keeps the locked version.
+#[tokio::test]
+async fn add_locked_transitive_dependency() {
+    let mut package_database = PackageDatabase::default();
+    package_database.add_package(Package::build("foo", "1").with_dependency("bar").finish());
+    package_database.add_package(Package::build("bar", "1").finish());
+
+    let channel_dir = TempDir::new().unwrap();
+    package_database
+        .write_repodata(channel_dir.path())
+        .await
+        .unwrap();
+
+    let pixi = PixiControl::new().unwrap();
+    pixi.init()
+        .with_local_channel(channel_dir.path())
+        .await
+        .unwrap();
+    pixi.add("foo").await.unwrap();
+
+    // Publish a newer version of `bar`, which is not used because `bar` is
+    // already locked.
+    package_database.add_package(Package::build("bar", "2").finish());
+    package_database
+        .write_repodata(channel_dir.path())
+        .await
+        .unwrap();
+    pixi.add("bar").await.unwrap();
+
+    let lock = pixi.lock_file().await.unwrap();
+    assert!(lock.contains_match_spec(
+        consts::DEFAULT_ENVIRONMENT_NAME,
+        Platform::current(),
+        "bar==1"
+    ));
+
+    let bar_spec = pixi
+        .project()
+        .unwrap()
+        .manifest()
+        .default_feature()
+        .combined_dependencies(None)
+        .unwrap_or_default()
+        .get("bar")
+        .cloned()
+        .unwrap()
+        .to_toml_value()
+        .to_string();
+    assert_eq!(bar_spec, "\">=1,<2\"");
+}
+
 #[tokio::test]
 async fn pinning_dependency() {
     // Create a channel with a single package
@@ -881,3 +1004,100 @@ preview = ['pixi-build']
         insta::assert_snapshot!(project.manifest().source.to_string());
     });
 }
+
+/// Test adding and removing R packages by their name on CRAN, and that a conda
+/// dependency on the package that provides them takes precedence.
+#[tokio::test]
+async fn add_remove_r_dependency() {
+    let mut package_database = PackageDatabase::default();
+    package_database.add_package(Package::build("r-ggplot2", "3.4.0").finish());
+    package_database.add_package(Package::build("r-ggplot2", "3.5.1").finish());
+    package_database.add_package(Package::build("bioconductor-deseq2", "1.42.0").finish());
+    let channel_dir = TempDir::new().unwrap();
+    package_database
+        .write_repodata(channel_dir.path())
+        .await
+        .unwrap();
+
+    let pixi = PixiControl::new().unwrap();
+    pixi.init()
+        .with_local_channel(channel_dir.path())
+        .await
+        .unwrap();
+    let manifest = pixi.manifest_contents().unwrap();
+    pixi.update_manifest(&format!(
+        "{manifest}\n[r-mapping]\nDESeq2 = \"bioconductor-deseq2\"\n"
+    ))
+    .unwrap();
+
+    // The R packages are mapped to the conda packages that provide them.
+    pixi.add("ggplot2")
+        .set_type(DependencyType::RDependency)
+        .await
+        .unwrap();
+    pixi.add("DESeq2")
+        .set_type(DependencyType::RDependency)
+        .await
+        .unwrap();
+    let lock = pixi.lock_file().await.unwrap();
+    assert!(lock.contains_match_spec(
+        consts::DEFAULT_ENVIRONMENT_NAME,
+        Platform::current(),
+        "r-ggplot2==3.5.1"
+    ));
+    assert!(lock.contains_conda_package(
+        consts::DEFAULT_ENVIRONMENT_NAME,
+        Platform::current(),
+        "bioconductor-deseq2"
+    ));
+
+    // A conda dependency on the same package takes precedence, also when the
+    // R package is added again.
+    pixi.add("r-ggplot2==3.4.0").await.unwrap();
+    pixi.add("ggplot2>=3.5")
+        .set_type(DependencyType::RDependency)
+        .await
+        .unwrap();
+    let lock = pixi.lock_file().await.unwrap();
+    assert!(lock.contains_match_spec(
+        consts::DEFAULT_ENVIRONMENT_NAME,
+        Platform::current(),
+        "r-ggplot2==3.4.0"
+    ));
+
+    // Removing the R package keeps the conda dependency, in the manifest on
+    // disk as well.
+    pixi.remove("ggplot2")
+        .set_type(DependencyType::RDependency)
+        .await
+        .unwrap();
+    let project = pixi.project().unwrap();
+    let target = project.manifest().default_feature().targets.default();
+    assert!(target
+        .r_dependencies
+        .as_ref()
+        .is_some_and(|deps| !deps.contains_key("ggplot2")));
+    assert!(target
+        .run_dependencies()
+        .unwrap()
+        .contains_key(&PackageName::from_str("r-ggplot2").unwrap()));
+
+    // Without a conda dependency, removing the R package removes the conda
+    // package that provides it.
+    pixi.remove("DESeq2")
+        .set_type(DependencyType::RDependency)
+        .await
+        .unwrap();
+    let project = pixi.project().unwrap();
+    let target = project.manifest().default_feature().targets.default();
+    assert!(!target
+        .run_dependencies()
+        .unwrap()
+        .contains_key(&PackageName::from_str("bioconductor-deseq2").unwrap()));
+    let lock = pixi.lock_file().await.unwrap();
+    assert!(!lock.contains_conda_package(
+        consts::DEFAULT_ENVIRONMENT_NAME,
+        Platform::current(),
+        "bioconductor-deseq2"
+    ));
+}
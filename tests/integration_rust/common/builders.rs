@@ -141,6 +141,7 @@ pub trait HasDependencyConfig: Sized {
             host: false,
             build: false,
             pypi: false,
+            r: false,
             platforms: Default::default(),
             feature: Default::default(),
             git: Default::default(),
@@ -176,6 +177,11 @@ pub trait HasDependencyConfig: Sized {
                 self.dependency_config().build = false;
                 self.dependency_config().pypi = true;
             }
+            DependencyType::RDependency => {
+                self.dependency_config().host = false;
+                self.dependency_config().build = false;
+                self.dependency_config().r = true;
+            }
         }
         self
     }
@@ -274,6 +280,14 @@ pub struct RemoveBuilder {
     pub args: remove::Args,
 }
 
+impl RemoveBuilder {
+    /// Drop packages that are no longer required from the lock-file.
+    pub fn with_prune(mut self, prune: bool) -> Self {
+        self.args.prune = prune;
+        self
+    }
+}
+
 impl HasDependencyConfig for RemoveBuilder {
     fn dependency_config(&mut self) -> &mut DependencyConfig {
         &mut self.args.dependency_config
@@ -529,6 +543,11 @@ impl UpdateBuilder {
         self
     }
 
+    pub fn recursive(mut self, recursive: bool) -> Self {
+        self.args.specs.recursive = recursive;
+        self
+    }
+
     pub fn dry_run(mut self, dry_run: bool) -> Self {
         self.args.dry_run = dry_run;
         self
@@ -342,6 +342,7 @@ impl PixiControl {
                     revalidate: false,
                 },
                 editable: false,
+                interactive: false,
             },
         }
     }
@@ -358,6 +359,9 @@ impl PixiControl {
                 platform: Platform::current(),
                 limit: None,
                 channels: ChannelsConfig::default(),
+                depends_on: None,
+                license: None,
+                json: false,
             },
         }
     }
@@ -377,6 +381,7 @@ impl PixiControl {
                     config: Default::default(),
                     revalidate: false,
                 },
+                prune: false,
             },
         }
     }
@@ -528,6 +533,8 @@ impl PixiControl {
                 },
                 config: Default::default(),
                 all: false,
+                repair: false,
+                exclude_newer: None,
             },
         }
     }
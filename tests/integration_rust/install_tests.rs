@@ -13,6 +13,7 @@ use pixi_config::{Config, DetachedEnvironments};
 use pixi_consts::consts;
 use pixi_manifest::{FeatureName, FeaturesExt};
 use rattler_conda_types::Platform;
+use rattler_lock::LockFile;
 use std::{
     fs::File,
     io::Write,
@@ -20,6 +21,7 @@ use std::{
     str::FromStr,
 };
 use tempfile::TempDir;
+use url::Url;
 use uv_python::PythonEnvironment;
 
 /// Should add a python version to the environment and lock file that matches
@@ -289,6 +291,184 @@ async fn install_frozen() {
     assert!(result.stderr.is_empty());
 }
 
+/// Test that `pixi install --locked` explains what drifted from the lock-file
+#[tokio::test]
+async fn install_locked_explains_drift() {
+    let mut package_database = PackageDatabase::default();
+    package_database.add_package(Package::build("foo", "1").finish());
+    package_database.add_package(Package::build("bar", "1").finish());
+
+    // Write the repodata to disk
+    let channel_dir = TempDir::new().unwrap();
+    package_database
+        .write_repodata(channel_dir.path())
+        .await
+        .unwrap();
+
+    let pixi = PixiControl::new().unwrap();
+    pixi.init()
+        .with_local_channel(channel_dir.path())
+        .await
+        .unwrap();
+    pixi.add("foo").await.unwrap();
+
+    // Add a dependency only to the manifest
+    pixi.add("bar").without_lockfile_update().await.unwrap();
+
+    let err = pixi
+        .install()
+        .with_locked()
+        .await
+        .expect_err("the lock-file should be out of date");
+    let message = format!("{err}");
+    assert!(message.contains("lock-file not up-to-date with the project"));
+    assert!(
+        message.contains("conda dependencies: the requirement 'bar"),
+        "expected the missing requirement in the explanation, got: {message}"
+    );
+}
+
+/// Test that a lock-file that is edited after it was verified is checked
+/// again, instead of being trusted because of the stored ready state.
+#[tokio::test]
+async fn install_locked_checks_edited_lock_file() {
+    let mut package_database = PackageDatabase::default();
+    package_database.add_package(Package::build("foo", "1").finish());
+
+    // Write the repodata to disk
+    let channel_dir = TempDir::new().unwrap();
+    package_database
+        .write_repodata(channel_dir.path())
+        .await
+        .unwrap();
+
+    let pixi = PixiControl::new().unwrap();
+    pixi.init()
+        .with_local_channel(channel_dir.path())
+        .await
+        .unwrap();
+    pixi.add("foo").await.unwrap();
+    pixi.install().with_locked().await.unwrap();
+
+    // Replace the lock-file with one that doesn't lock the environment.
+    let lock_file_path = pixi.project().unwrap().lock_file_path();
+    let original = fs_err::read_to_string(&lock_file_path).unwrap();
+    LockFile::default().to_path(&lock_file_path).unwrap();
+    assert!(
+        pixi.install().with_locked().await.is_err(),
+        "the edited lock-file should be checked again"
+    );
+
+    // The original lock-file is up-to-date again.
+    fs_err::write(&lock_file_path, original).unwrap();
+    pixi.install().with_locked().await.unwrap();
+}
+
+#[tokio::test]
+async fn lock_platforms_are_pruned_from_lock_file() {
+    let mut package_database = PackageDatabase::default();
+    for platform in [Platform::Linux64, Platform::Osx64] {
+        package_database.add_package(Package::build("foo", "1").with_subdir(platform).finish());
+    }
+
+    // Write the repodata to disk
+    let channel_dir = TempDir::new().unwrap();
+    package_database
+        .write_repodata(channel_dir.path())
+        .await
+        .unwrap();
+
+    let pixi = PixiControl::new().unwrap();
+    pixi.init_with_platforms(vec!["linux-64".to_string(), "osx-64".to_string()])
+        .with_local_channel(channel_dir.path())
+        .await
+        .unwrap();
+    pixi.add("foo").await.unwrap();
+
+    let lock_file = pixi.lock_file().await.unwrap();
+    assert!(lock_file.contains_match_spec(
+        consts::DEFAULT_ENVIRONMENT_NAME,
+        Platform::Osx64,
+        "foo"
+    ));
+
+    // Only lock linux-64 from now on
+    let pixi_dir = pixi.project_path().join(consts::PIXI_DIR);
+    fs_err::create_dir_all(&pixi_dir).unwrap();
+    fs_err::write(
+        pixi_dir.join(consts::CONFIG_FILE),
+        r#"lock-platforms = ["linux-64"]"#,
+    )
+    .unwrap();
+
+    pixi.update_lock_file().await.unwrap();
+    let lock_file = pixi.lock_file().await.unwrap();
+    assert!(lock_file.contains_match_spec(
+        consts::DEFAULT_ENVIRONMENT_NAME,
+        Platform::Linux64,
+        "foo"
+    ));
+    assert!(!lock_file.contains_match_spec(
+        consts::DEFAULT_ENVIRONMENT_NAME,
+        Platform::Osx64,
+        "foo"
+    ));
+}
+
+/// The lock-file of every environment is stored in a separate file when
+/// `per-environment-lock-files` is enabled.
+#[tokio::test]
+async fn per_environment_lock_files() {
+    let mut package_database = PackageDatabase::default();
+    package_database.add_package(Package::build("foo", "1").finish());
+    package_database.add_package(Package::build("bar", "1").finish());
+
+    // Write the repodata to disk
+    let channel_dir = TempDir::new().unwrap();
+    package_database
+        .write_repodata(channel_dir.path())
+        .await
+        .unwrap();
+
+    let channel = Url::from_file_path(channel_dir.path()).unwrap();
+    let platform = Platform::current();
+    let pixi = PixiControl::from_manifest(&format!(
+        r#"
+    [project]
+    name = "per-environment-lock-files"
+    channels = ["{channel}"]
+    platforms = ["{platform}"]
+    per-environment-lock-files = true
+
+    [dependencies]
+    foo = "*"
+
+    [feature.test.dependencies]
+    bar = "*"
+
+    [environments]
+    test = ["test"]
+    "#
+    ))
+    .unwrap();
+
+    pixi.update_lock_file().await.unwrap();
+
+    let locks_dir = pixi
+        .project_path()
+        .join(consts::PIXI_DIR)
+        .join(consts::LOCKS_DIR);
+    assert!(locks_dir.join("default.lock").is_file());
+    assert!(locks_dir.join("test.lock").is_file());
+    assert!(!pixi.project_path().join(consts::PROJECT_LOCK_FILE).exists());
+
+    // The environments are read back from the separate lock-files
+    let lock_file = pixi.lock_file().await.unwrap();
+    assert!(lock_file.contains_match_spec(consts::DEFAULT_ENVIRONMENT_NAME, platform, "foo"));
+    assert!(!lock_file.contains_match_spec(consts::DEFAULT_ENVIRONMENT_NAME, platform, "bar"));
+    assert!(lock_file.contains_match_spec("test", platform, "bar"));
+}
+
 fn create_uv_environment(prefix: &Path, cache: &uv_cache::Cache) -> PythonEnvironment {
     let python = if cfg!(target_os = "windows") {
         prefix.join("python.exe")
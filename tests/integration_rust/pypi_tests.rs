@@ -287,3 +287,47 @@ async fn test_allow_insecure_host() {
     .unwrap();
     pixi.update_lock_file().await.unwrap();
 }
+
+#[tokio::test]
+#[cfg_attr(not(feature = "slow_integration_tests"), ignore)]
+async fn test_warmup_does_not_install_the_environment() {
+    let pypi_indexes = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/pypi-indexes");
+    let pixi = PixiControl::from_manifest(&format!(
+        r#"
+        [project]
+        name = "pypi-warmup"
+        platforms = ["{platform}"]
+        channels = ["https://prefix.dev/conda-forge"]
+
+        [dependencies]
+        python = "~=3.12.0"
+
+        [pypi-dependencies]
+        foo = "*"
+
+        [pypi-options]
+        find-links = [{{ path = "{pypi_indexes}/multiple-indexes-a/flat"}}]"#,
+        platform = Platform::current(),
+        pypi_indexes = pypi_indexes.display().to_string().replace("\\", "/"),
+    ))
+    .unwrap();
+
+    pixi::cli::warmup::execute(pixi::cli::warmup::Args {
+        project_config: pixi::cli::cli_config::ProjectConfig {
+            manifest_path: Some(pixi.manifest_path()),
+        },
+        lock_file_usage: pixi::cli::LockFileUsageArgs {
+            frozen: false,
+            locked: false,
+        },
+        environment: None,
+        all: false,
+        config: Default::default(),
+    })
+    .await
+    .unwrap();
+
+    // The caches are filled without creating the environment
+    assert!(pixi.lock_file().await.is_ok());
+    assert!(!pixi.default_env_path().unwrap().exists());
+}
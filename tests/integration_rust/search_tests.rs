@@ -1,4 +1,5 @@
 use insta::assert_snapshot;
+use itertools::Itertools;
 use pixi::cli::search;
 use rattler_conda_types::Platform;
 use tempfile::TempDir;
@@ -137,3 +138,120 @@ async fn test_search_multiple_versions() {
         .join("\n");
     assert_snapshot!(output);
 }
+
+#[tokio::test]
+async fn test_search_depends_on_json() {
+    let mut package_database = PackageDatabase::default();
+    package_database.add_package(
+        Package::build("foo", "1")
+            .with_dependency("bar >=1")
+            .with_subdir(Platform::NoArch)
+            .finish(),
+    );
+    package_database.add_package(
+        Package::build("foo", "2")
+            .with_subdir(Platform::NoArch)
+            .finish(),
+    );
+    package_database.add_package(
+        Package::build("foo-utils", "1")
+            .with_dependency("bar")
+            .with_subdir(Platform::NoArch)
+            .finish(),
+    );
+    let temp_dir = TempDir::new().unwrap();
+    let channel_dir = temp_dir.path().join("channel");
+    package_database.write_repodata(&channel_dir).await.unwrap();
+    let channel = Url::from_file_path(channel_dir).unwrap();
+    let platform = Platform::current();
+    let pixi = PixiControl::from_manifest(&format!(
+        r#"
+    [project]
+    name = "test-depends-on"
+    channels = ["{channel}"]
+    platforms = ["{platform}"]
+
+    "#
+    ))
+    .unwrap();
+
+    // Only the older version of `foo` depends on `bar`
+    let mut builder = pixi.search("foo".to_string());
+    builder.args.depends_on = Some("bar".parse().unwrap());
+    builder.args.json = true;
+    let mut out = Vec::new();
+    let result = search::execute_impl(builder.args, &mut out)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(result[0].package_record.version.as_str(), "1");
+
+    let records: Vec<serde_json::Value> = serde_json::from_slice(&out).unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0]["name"], "foo");
+    assert_eq!(records[0]["version"], "1");
+    assert_eq!(records[0]["depends"][0], "bar >=1");
+
+    // The wildcard search applies the same filters
+    let mut builder = pixi.search("foo*".to_string());
+    builder.args.depends_on = Some("bar".parse().unwrap());
+    let result = builder.await.unwrap().unwrap();
+    assert_eq!(
+        result
+            .iter()
+            .map(|record| record.package_record.name.as_normalized())
+            .sorted()
+            .collect::<Vec<_>>(),
+        vec!["foo", "foo-utils"]
+    );
+}
+
+#[tokio::test]
+async fn test_search_filters_exclude_all() {
+    let mut package_database = PackageDatabase::default();
+    package_database.add_package(
+        Package::build("foo", "1")
+            .with_subdir(Platform::NoArch)
+            .finish(),
+    );
+    package_database.add_package(
+        Package::build("fooo", "1")
+            .with_dependency("bar")
+            .with_subdir(Platform::NoArch)
+            .finish(),
+    );
+    let temp_dir = TempDir::new().unwrap();
+    let channel_dir = temp_dir.path().join("channel");
+    package_database.write_repodata(&channel_dir).await.unwrap();
+    let channel = Url::from_file_path(channel_dir).unwrap();
+    let platform = Platform::current();
+    let pixi = PixiControl::from_manifest(&format!(
+        r#"
+    [project]
+    name = "test-filters-exclude-all"
+    channels = ["{channel}"]
+    platforms = ["{platform}"]
+
+    "#
+    ))
+    .unwrap();
+
+    // The filters exclude every version of `foo`
+    let mut builder = pixi.search("foo".to_string());
+    builder.args.depends_on = Some("bar".parse().unwrap());
+    let err = builder.await.unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Package foo has no versions that match --depends-on 'bar'"
+    );
+
+    // The wildcard search doesn't fall back to the similar `fooo`
+    let mut builder = pixi.search("fo*".to_string());
+    builder.args.depends_on = Some("baz".parse().unwrap());
+    builder.args.license = Some("MIT".to_string());
+    let err = builder.await.unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "No packages matching 'fo*' match --depends-on 'baz' and --license 'MIT'"
+    );
+}
@@ -727,6 +727,7 @@ async fn test_disabled_mapping() {
 
     pypi_mapping::amend_pypi_purls(
         blocked_client,
+        project.config().cache().http_backend(),
         project.pypi_name_mapping_source().unwrap(),
         &mut packages,
         None,
@@ -151,6 +151,138 @@ async fn test_update_single_package() {
     );
 }
 
+#[tokio::test]
+async fn test_update_recursive() {
+    let mut package_database = PackageDatabase::default();
+
+    // `foo` depends on `bar`, `baz` is unrelated
+    package_database.add_package(Package::build("bar", "1").finish());
+    package_database.add_package(Package::build("baz", "1").finish());
+    package_database.add_package(Package::build("foo", "1").with_dependency("bar").finish());
+
+    // Write the repodata to disk
+    let channel_dir = TempDir::new().unwrap();
+    package_database
+        .write_repodata(channel_dir.path())
+        .await
+        .unwrap();
+
+    let pixi = PixiControl::new().unwrap();
+
+    // Create a new project using our package database.
+    pixi.init()
+        .with_local_channel(channel_dir.path())
+        .await
+        .unwrap();
+
+    pixi.add("foo").await.unwrap();
+    pixi.add("baz").await.unwrap();
+
+    // Release new versions of all packages
+    package_database.add_package(Package::build("bar", "2").finish());
+    package_database.add_package(Package::build("baz", "2").finish());
+    package_database.add_package(Package::build("foo", "2").with_dependency("bar").finish());
+    package_database
+        .write_repodata(channel_dir.path())
+        .await
+        .unwrap();
+
+    // Update `foo` together with its dependencies
+    pixi.update()
+        .with_package("foo")
+        .recursive(true)
+        .await
+        .unwrap();
+
+    let lock = pixi.lock_file().await.unwrap();
+    assert!(
+        lock.contains_match_spec(
+            consts::DEFAULT_ENVIRONMENT_NAME,
+            Platform::current(),
+            "foo ==2"
+        ),
+        "expected `foo` to be on version 2 because we updated it"
+    );
+    assert!(
+        lock.contains_match_spec(
+            consts::DEFAULT_ENVIRONMENT_NAME,
+            Platform::current(),
+            "bar ==2"
+        ),
+        "expected `bar` to be on version 2 because it is a dependency of `foo`"
+    );
+    assert!(
+        lock.contains_match_spec(
+            consts::DEFAULT_ENVIRONMENT_NAME,
+            Platform::current(),
+            "baz ==1"
+        ),
+        "expected `baz` to be on version 1 because it is unrelated to `foo`"
+    );
+}
+
+#[tokio::test]
+async fn test_package_overrides() {
+    let mut package_database = PackageDatabase::default();
+
+    // `foo` depends on `bar`, a newer `baz` and `tk`, which is not available
+    package_database.add_package(Package::build("bar", "1").finish());
+    package_database.add_package(Package::build("bar-fast", "1").finish());
+    package_database.add_package(Package::build("baz", "1").finish());
+    package_database.add_package(Package::build("baz", "2").finish());
+    package_database.add_package(
+        Package::build("foo", "1")
+            .with_dependency("bar")
+            .with_dependency("baz >=2")
+            .with_dependency("tk")
+            .finish(),
+    );
+
+    // Write the repodata to disk
+    let channel_dir = TempDir::new().unwrap();
+    package_database
+        .write_repodata(channel_dir.path())
+        .await
+        .unwrap();
+
+    let pixi = PixiControl::new().unwrap();
+
+    // Create a new project using our package database.
+    pixi.init()
+        .with_local_channel(channel_dir.path())
+        .await
+        .unwrap();
+
+    // Override the dependencies of `foo`, without them `foo` can't be solved
+    let manifest = pixi.manifest_contents().unwrap();
+    pixi.update_manifest(&format!(
+        r#"{manifest}
+[package-overrides]
+bar = {{ replace-with = "bar-fast" }}
+baz = "<2"
+tk = {{ exclude = true }}
+"#
+    ))
+    .unwrap();
+    pixi.add("foo").await.unwrap();
+
+    let lock = pixi.lock_file().await.unwrap();
+    let env = consts::DEFAULT_ENVIRONMENT_NAME;
+    assert!(lock.contains_conda_package(env, Platform::current(), "bar-fast"));
+    assert!(
+        !lock.contains_conda_package(env, Platform::current(), "bar"),
+        "expected `bar` to be replaced by `bar-fast`"
+    );
+    assert!(
+        lock.contains_match_spec(env, Platform::current(), "baz ==1"),
+        "expected `baz` to be on version 1 because of the override"
+    );
+    assert!(
+        !lock.contains_conda_package(env, Platform::current(), "tk"),
+        "expected `tk` to be excluded"
+    );
+}
+
 // #[tokio::test]
 // async fn test_update_single_environment() {
 //     let mut package_database = PackageDatabase::default();